@@ -0,0 +1,290 @@
+//! Python bindings for embedding the object store's service layer
+//!
+//! [`ObjectStore`] builds the application with [`AppBuilder`] and exposes the
+//! operations of its [`ObjectService`] as blocking methods, so test suites and
+//! notebooks can run the store in-process, typically on the in-memory or local
+//! filesystem backend, without starting the HTTP server.
+//!
+//! ```python
+//! from object_store_server import ObjectStore
+//!
+//! store = ObjectStore.in_memory()
+//! store.put("reports/2024.csv", b"a,b\n1,2\n", content_type="text/csv")
+//! assert store.get("reports/2024.csv") == b"a,b\n1,2\n"
+//! print([entry.key for entry in store.list(prefix="reports/")])
+//! ```
+
+use chrono::{DateTime, Utc};
+use object_store_server::{
+    AppBuilder, AppError, AppServices, ObjectInfo, ObjectKey, ObjectMetadata, ObjectService,
+    RepositoryBackend, StorageBackend, StorageError, VersionId, create_app_from_env,
+    domain::models::{CreateObjectRequest, GetObjectRequest},
+};
+use pyo3::{
+    create_exception,
+    exceptions::{PyException, PyValueError},
+    prelude::*,
+    types::PyBytes,
+};
+use std::{collections::HashMap, future::Future, path::PathBuf};
+use tokio::runtime::Runtime;
+
+create_exception!(
+    object_store_server,
+    StoreError,
+    PyException,
+    "An operation on the store failed."
+);
+create_exception!(
+    object_store_server,
+    ObjectNotFoundError,
+    StoreError,
+    "The object or version does not exist."
+);
+
+fn store_error(error: StorageError) -> PyErr {
+    match error {
+        StorageError::ObjectNotFound { .. }
+        | StorageError::VersionNotFound { .. }
+        | StorageError::ObjectExpired { .. } => ObjectNotFoundError::new_err(error.to_string()),
+        StorageError::ValidationError { .. } => PyValueError::new_err(error.to_string()),
+        _ => StoreError::new_err(error.to_string()),
+    }
+}
+
+fn object_key(key: String) -> PyResult<ObjectKey> {
+    ObjectKey::new(key).map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+/// Metadata of a stored object
+#[pyclass(frozen, get_all, module = "object_store_server")]
+#[derive(Clone)]
+struct ObjectHead {
+    key: String,
+    size: u64,
+    content_type: Option<String>,
+    etag: Option<String>,
+    last_modified: DateTime<Utc>,
+    metadata: HashMap<String, String>,
+}
+
+impl ObjectHead {
+    fn new(key: &ObjectKey, metadata: ObjectMetadata) -> Self {
+        ObjectHead {
+            key: key.to_string(),
+            size: metadata.content_length,
+            content_type: metadata.content_type,
+            etag: metadata.etag,
+            last_modified: metadata.last_modified.into(),
+            metadata: metadata.custom_metadata,
+        }
+    }
+}
+
+#[pymethods]
+impl ObjectHead {
+    fn __repr__(&self) -> String {
+        format!("ObjectHead(key={:?}, size={})", self.key, self.size)
+    }
+}
+
+/// An object returned by a listing
+#[pyclass(frozen, get_all, module = "object_store_server")]
+#[derive(Clone)]
+struct ObjectEntry {
+    key: String,
+    size: u64,
+    etag: Option<String>,
+    version_id: Option<String>,
+    last_modified: DateTime<Utc>,
+}
+
+impl From<ObjectInfo> for ObjectEntry {
+    fn from(info: ObjectInfo) -> Self {
+        ObjectEntry {
+            key: info.key.to_string(),
+            size: info.size,
+            etag: info.etag,
+            version_id: info.version_id,
+            last_modified: info.last_modified,
+        }
+    }
+}
+
+#[pymethods]
+impl ObjectEntry {
+    fn __repr__(&self) -> String {
+        format!("ObjectEntry(key={:?}, size={})", self.key, self.size)
+    }
+}
+
+/// An object store running in this process
+///
+/// Every method blocks until the operation finished and releases the GIL while
+/// it waits, so a store can be shared between Python threads.
+#[pyclass(frozen, module = "object_store_server")]
+struct ObjectStore {
+    services: AppServices,
+    /// Runs the operations, and the background workers started by the build
+    runtime: Runtime,
+}
+
+impl ObjectStore {
+    fn start<F>(py: Python<'_>, build: F) -> PyResult<Self>
+    where
+        F: Future<Output = Result<AppServices, AppError>> + Send,
+    {
+        let runtime = Runtime::new().map_err(|e| StoreError::new_err(e.to_string()))?;
+        let services = py
+            .allow_threads(|| runtime.block_on(build))
+            .map_err(|e| StoreError::new_err(e.to_string()))?;
+        Ok(ObjectStore { services, runtime })
+    }
+
+    fn block_on<F>(&self, py: Python<'_>, operation: F) -> F::Output
+    where
+        F: Future + Send,
+        F::Output: Send,
+    {
+        py.allow_threads(|| self.runtime.block_on(operation))
+    }
+}
+
+#[pymethods]
+impl ObjectStore {
+    /// A store keeping objects and their metadata in memory
+    #[staticmethod]
+    fn in_memory(py: Python<'_>) -> PyResult<Self> {
+        let builder = AppBuilder::new()
+            .with_storage_backend(StorageBackend::InMemory)
+            .with_repository_backend(RepositoryBackend::InMemory);
+        Self::start(py, builder.build())
+    }
+
+    /// A store keeping objects in files under `root` and their metadata in
+    /// memory
+    #[staticmethod]
+    fn local(py: Python<'_>, root: PathBuf) -> PyResult<Self> {
+        let builder = AppBuilder::new()
+            .with_storage_backend(StorageBackend::LocalFs { root })
+            .with_repository_backend(RepositoryBackend::InMemory);
+        Self::start(py, builder.build())
+    }
+
+    /// A store configured from the same environment variables as the server
+    #[staticmethod]
+    fn from_env(py: Python<'_>) -> PyResult<Self> {
+        Self::start(py, create_app_from_env())
+    }
+
+    /// Store `data` under `key`, replacing any object already there
+    #[pyo3(signature = (key, data, content_type=None, metadata=None))]
+    fn put(
+        &self,
+        py: Python<'_>,
+        key: String,
+        data: &[u8],
+        content_type: Option<String>,
+        metadata: Option<HashMap<String, String>>,
+    ) -> PyResult<ObjectHead> {
+        let request = CreateObjectRequest {
+            key: object_key(key)?,
+            data: data.to_vec(),
+            content_type,
+            custom_metadata: metadata.unwrap_or_default(),
+        };
+        let object = self
+            .block_on(py, self.services.object_service.create_object(request))
+            .map_err(store_error)?;
+        Ok(ObjectHead::new(&object.key, object.metadata))
+    }
+
+    /// The data of an object, or of one of its versions
+    #[pyo3(signature = (key, version_id=None))]
+    fn get<'py>(
+        &self,
+        py: Python<'py>,
+        key: String,
+        version_id: Option<String>,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        let request = GetObjectRequest {
+            key: object_key(key)?,
+            version_id: version_id
+                .map(VersionId::new)
+                .transpose()
+                .map_err(|e| PyValueError::new_err(e.to_string()))?,
+        };
+        let object = self
+            .block_on(py, self.services.object_service.get_object(request))
+            .map_err(store_error)?;
+        Ok(PyBytes::new_bound(py, &object.data))
+    }
+
+    /// The metadata of an object without its data
+    fn head(&self, py: Python<'_>, key: String) -> PyResult<ObjectHead> {
+        let key = object_key(key)?;
+        let metadata = self
+            .block_on(py, self.services.object_service.head_object(&key))
+            .map_err(store_error)?;
+        Ok(ObjectHead::new(&key, metadata))
+    }
+
+    fn exists(&self, py: Python<'_>, key: String) -> PyResult<bool> {
+        let key = object_key(key)?;
+        self.block_on(py, self.services.object_service.object_exists(&key))
+            .map_err(store_error)
+    }
+
+    fn delete(&self, py: Python<'_>, key: String) -> PyResult<()> {
+        let key = object_key(key)?;
+        self.block_on(py, self.services.object_service.delete_object(&key))
+            .map_err(store_error)
+    }
+
+    /// Copy an object to `destination`, returning the copy's metadata
+    fn copy(&self, py: Python<'_>, source: String, destination: String) -> PyResult<ObjectHead> {
+        let source = object_key(source)?;
+        let destination = object_key(destination)?;
+        let object = self
+            .block_on(
+                py,
+                self.services
+                    .object_service
+                    .copy_object(&source, &destination),
+            )
+            .map_err(store_error)?;
+        Ok(ObjectHead::new(&object.key, object.metadata))
+    }
+
+    /// The objects whose keys start with `prefix`, at most `max_results`
+    #[pyo3(signature = (prefix=None, max_results=None))]
+    fn list(
+        &self,
+        py: Python<'_>,
+        prefix: Option<String>,
+        max_results: Option<usize>,
+    ) -> PyResult<Vec<ObjectEntry>> {
+        let objects = self
+            .block_on(
+                py,
+                self.services
+                    .object_service
+                    .list_objects(prefix.as_deref(), max_results),
+            )
+            .map_err(store_error)?;
+        Ok(objects.into_iter().map(ObjectEntry::from).collect())
+    }
+}
+
+#[pymodule]
+fn object_store_server(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<ObjectStore>()?;
+    m.add_class::<ObjectHead>()?;
+    m.add_class::<ObjectEntry>()?;
+    m.add("StoreError", m.py().get_type_bound::<StoreError>())?;
+    m.add(
+        "ObjectNotFoundError",
+        m.py().get_type_bound::<ObjectNotFoundError>(),
+    )?;
+    Ok(())
+}
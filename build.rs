@@ -0,0 +1,14 @@
+fn main() {
+    println!("cargo:rerun-if-changed=proto/object_store.proto");
+
+    // Only the server has a gRPC adapter
+    if std::env::var("CARGO_CFG_TARGET_ARCH").as_deref() == Ok("wasm32") {
+        return;
+    }
+
+    let mut config = tonic_prost_build::Config::new();
+    config.protoc_executable(protoc_bin_vendored::protoc_bin_path().expect("vendored protoc"));
+    tonic_prost_build::configure()
+        .compile_with_config(config, &["proto/object_store.proto"], &["proto"])
+        .expect("failed to compile proto/object_store.proto");
+}
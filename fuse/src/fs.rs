@@ -0,0 +1,876 @@
+//! The filesystem the kernel sees: inodes for object keys and the prefixes
+//! between their slashes, and write handles spooling a file's contents to a
+//! temporary file until it is closed and uploaded
+
+use fuser::{
+    FUSE_ROOT_ID, FileAttr, FileType, Filesystem, KernelConfig, ReplyAttr, ReplyCreate, ReplyData,
+    ReplyDirectory, ReplyEmpty, ReplyEntry, ReplyOpen, ReplyWrite, Request, TimeOrNow,
+};
+use libc::{EBADF, EINVAL, EIO, ENOENT, ENOTDIR, ENOTEMPTY, EROFS, EXDEV};
+use object_store_server::{
+    adapters::inbound::http::dto::ObjectInfoDto,
+    client::{Client, ClientError, PutObjectOptions},
+};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    ffi::OsStr,
+    fs::File,
+    future::Future,
+    io::{Seek, SeekFrom},
+    os::{raw::c_int, unix::fs::FileExt},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::runtime::Runtime;
+use tokio_util::io::ReaderStream;
+
+const BLOCK_SIZE: u32 = 4096;
+
+/// A file or directory the kernel knows by its inode
+#[derive(Debug, Clone)]
+struct Node {
+    /// Path relative to the bucket, empty for its root
+    path: String,
+    kind: FileType,
+    size: u64,
+    modified: SystemTime,
+}
+
+/// A file opened for writing
+struct WriteHandle {
+    ino: u64,
+    /// The file's contents, uploaded when the file is closed
+    spool: File,
+    /// Written to since the last upload
+    dirty: bool,
+}
+
+/// A file or subdirectory directly inside a directory
+#[derive(Debug, Clone, PartialEq)]
+struct DirEntry {
+    name: String,
+    kind: FileType,
+    size: u64,
+    modified: SystemTime,
+}
+
+/// A bucket mounted through the server's HTTP API
+pub struct BucketFs {
+    client: Client,
+    runtime: Runtime,
+    /// Prefix of the keys of the bucket's objects, `bucket/`
+    prefix: String,
+    read_only: bool,
+    /// How long the kernel may cache attributes and entries
+    ttl: Duration,
+    uid: u32,
+    gid: u32,
+    nodes: HashMap<u64, Node>,
+    inodes: HashMap<String, u64>,
+    next_ino: u64,
+    /// Directories made with mkdir that hold no objects yet
+    empty_dirs: HashSet<String>,
+    handles: HashMap<u64, WriteHandle>,
+    next_fh: u64,
+}
+
+impl BucketFs {
+    pub fn new(
+        client: Client,
+        runtime: Runtime,
+        bucket: &str,
+        read_only: bool,
+        ttl: Duration,
+    ) -> Self {
+        let root = Node {
+            path: String::new(),
+            kind: FileType::Directory,
+            size: 0,
+            modified: SystemTime::now(),
+        };
+        BucketFs {
+            client,
+            runtime,
+            prefix: format!("{}/", bucket.trim_end_matches('/')),
+            read_only,
+            ttl,
+            uid: 0,
+            gid: 0,
+            nodes: HashMap::from([(FUSE_ROOT_ID, root)]),
+            inodes: HashMap::from([(String::new(), FUSE_ROOT_ID)]),
+            next_ino: FUSE_ROOT_ID + 1,
+            empty_dirs: HashSet::new(),
+            handles: HashMap::new(),
+            // 0 is the handle of files opened for reading
+            next_fh: 1,
+        }
+    }
+
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    fn key(&self, path: &str) -> String {
+        format!("{}{}", self.prefix, path)
+    }
+
+    /// Prefix of the keys of the objects inside a directory
+    fn dir_prefix(&self, path: &str) -> String {
+        if path.is_empty() {
+            self.prefix.clone()
+        } else {
+            format!("{}{}/", self.prefix, path)
+        }
+    }
+
+    fn node(&self, ino: u64) -> Result<&Node, c_int> {
+        self.nodes.get(&ino).ok_or(ENOENT)
+    }
+
+    fn attr(&self, ino: u64) -> Result<FileAttr, c_int> {
+        let node = self.node(ino)?;
+        let (perm, nlink) = match node.kind {
+            FileType::Directory => (0o755, 2),
+            _ => (0o644, 1),
+        };
+        Ok(FileAttr {
+            ino,
+            size: node.size,
+            blocks: node.size.div_ceil(512),
+            atime: node.modified,
+            mtime: node.modified,
+            ctime: node.modified,
+            crtime: node.modified,
+            kind: node.kind,
+            perm: if self.read_only { perm & 0o555 } else { perm },
+            nlink,
+            uid: self.uid,
+            gid: self.gid,
+            rdev: 0,
+            blksize: BLOCK_SIZE,
+            flags: 0,
+        })
+    }
+
+    fn child_path(&self, parent: u64, name: &OsStr) -> Result<String, c_int> {
+        let parent = self.node(parent)?;
+        if parent.kind != FileType::Directory {
+            return Err(ENOTDIR);
+        }
+        let name = name.to_str().ok_or(EINVAL)?;
+        Ok(join(&parent.path, name))
+    }
+
+    fn is_open_for_write(&self, ino: u64) -> bool {
+        self.handles.values().any(|handle| handle.ino == ino)
+    }
+
+    /// Inode of the node at `path`, updating the node if it is known already
+    ///
+    /// Files being written keep the size of what was written so far.
+    fn insert(&mut self, path: String, kind: FileType, size: u64, modified: SystemTime) -> u64 {
+        if let Some(&ino) = self.inodes.get(&path) {
+            let open = self.is_open_for_write(ino);
+            if let Some(node) = self.nodes.get_mut(&ino).filter(|_| !open) {
+                node.kind = kind;
+                node.size = size;
+                node.modified = modified;
+            }
+            return ino;
+        }
+
+        let ino = self.next_ino;
+        self.next_ino += 1;
+        self.inodes.insert(path.clone(), ino);
+        self.nodes.insert(
+            ino,
+            Node {
+                path,
+                kind,
+                size,
+                modified,
+            },
+        );
+        ino
+    }
+
+    fn forget(&mut self, path: &str) {
+        if let Some(ino) = self.inodes.remove(path) {
+            self.nodes.remove(&ino);
+        }
+    }
+
+    /// Look up the object or prefix at `path` on the server
+    fn resolve(&mut self, path: String) -> Result<u64, c_int> {
+        if let Some(&ino) = self.inodes.get(&path) {
+            if self.is_open_for_write(ino) || self.empty_dirs.contains(&path) {
+                return Ok(ino);
+            }
+        }
+
+        match self.block_on(self.client.head_object(&self.key(&path))) {
+            Ok(head) => {
+                let modified = head.last_modified.map_or(UNIX_EPOCH, SystemTime::from);
+                let size = head.content_length.unwrap_or(0);
+                return Ok(self.insert(path, FileType::RegularFile, size, modified));
+            }
+            Err(e) if e.is_not_found() => {}
+            Err(e) => return Err(errno(&e)),
+        }
+
+        let prefix = self.dir_prefix(&path);
+        let listing = self
+            .block_on(self.client.list_objects(Some(&prefix), Some(1)))
+            .map_err(|e| errno(&e))?;
+        if listing.objects.is_empty() {
+            self.forget(&path);
+            return Err(ENOENT);
+        }
+        Ok(self.insert(path, FileType::Directory, 0, SystemTime::now()))
+    }
+
+    /// The entries of a directory as inodes, kinds and names
+    fn list_dir(&mut self, ino: u64) -> Result<Vec<(u64, FileType, String)>, c_int> {
+        let node = self.node(ino)?;
+        if node.kind != FileType::Directory {
+            return Err(ENOTDIR);
+        }
+        let path = node.path.clone();
+        let prefix = self.dir_prefix(&path);
+        let listing = self
+            .block_on(self.client.list_objects(Some(&prefix), None))
+            .map_err(|e| errno(&e))?;
+
+        let mut entries = Vec::new();
+        for entry in dir_entries(&prefix, &listing.objects) {
+            let child = join(&path, &entry.name);
+            let ino = self.insert(child, entry.kind, entry.size, entry.modified);
+            entries.push((ino, entry.kind, entry.name));
+        }
+
+        // Directories and files made here that the server doesn't have yet
+        let pending: Vec<_> = self
+            .nodes
+            .iter()
+            .filter(|(ino, node)| {
+                !node.path.is_empty()
+                    && parent(&node.path) == path
+                    && (self.empty_dirs.contains(&node.path) || self.is_open_for_write(**ino))
+                    && !entries.iter().any(|(listed, ..)| listed == *ino)
+            })
+            .map(|(ino, node)| (*ino, node.kind, name(&node.path).to_string()))
+            .collect();
+        entries.extend(pending);
+        Ok(entries)
+    }
+
+    fn add_handle(&mut self, handle: WriteHandle) -> u64 {
+        let fh = self.next_fh;
+        self.next_fh += 1;
+        self.handles.insert(fh, handle);
+        fh
+    }
+
+    /// A write handle for a file, holding its current contents when `load`
+    fn open_for_write(&self, ino: u64, load: bool) -> Result<WriteHandle, c_int> {
+        let spool = tempfile::tempfile().map_err(io_errno)?;
+        if load {
+            let key = self.key(&self.node(ino)?.path);
+            let file = spool.try_clone().map_err(io_errno)?;
+            self.block_on(async {
+                let mut file = tokio::fs::File::from_std(file);
+                self.client
+                    .get_object(&key)
+                    .await?
+                    .write_to(&mut file)
+                    .await
+            })
+            .map_err(|e| errno(&e))?;
+        }
+        Ok(WriteHandle {
+            ino,
+            spool,
+            dirty: false,
+        })
+    }
+
+    fn upload(&self, handle: &WriteHandle) -> Result<(), c_int> {
+        let key = self.key(&self.node(handle.ino)?.path);
+        let mut spool = handle.spool.try_clone().map_err(io_errno)?;
+        spool.seek(SeekFrom::Start(0)).map_err(io_errno)?;
+
+        let data = ReaderStream::new(tokio::fs::File::from_std(spool));
+        self.block_on(
+            self.client
+                .put_object_stream(&key, data, PutObjectOptions::default()),
+        )
+        .map_err(|e| {
+            tracing::warn!("Failed to upload {}: {}", key, e);
+            errno(&e)
+        })
+    }
+
+    /// Upload what was written through a handle since its last upload
+    fn write_back(&mut self, fh: u64) -> Result<(), c_int> {
+        let Some(handle) = self.handles.get(&fh).filter(|handle| handle.dirty) else {
+            return Ok(());
+        };
+        self.upload(handle)?;
+        if let Some(handle) = self.handles.get_mut(&fh) {
+            handle.dirty = false;
+        }
+        Ok(())
+    }
+
+    fn truncate(&mut self, ino: u64, fh: Option<u64>, size: u64) -> Result<(), c_int> {
+        let fh = fh.filter(|fh| self.handles.contains_key(fh)).or_else(|| {
+            self.handles
+                .iter()
+                .find(|(_, handle)| handle.ino == ino)
+                .map(|(fh, _)| *fh)
+        });
+        match fh.and_then(|fh| self.handles.get_mut(&fh)) {
+            Some(handle) => {
+                handle.spool.set_len(size).map_err(io_errno)?;
+                handle.dirty = true;
+            }
+            // Truncated without being open, e.g. by truncate(1)
+            None => {
+                let handle = self.open_for_write(ino, size > 0)?;
+                handle.spool.set_len(size).map_err(io_errno)?;
+                self.upload(&handle)?;
+            }
+        }
+
+        if let Some(node) = self.nodes.get_mut(&ino) {
+            node.size = size;
+            node.modified = SystemTime::now();
+        }
+        Ok(())
+    }
+
+    fn remove_dir(&mut self, path: String) -> Result<(), c_int> {
+        let ino = self.resolve(path.clone())?;
+        if !self.list_dir(ino)?.is_empty() {
+            return Err(ENOTEMPTY);
+        }
+        self.empty_dirs.remove(&path);
+        self.forget(&path);
+        Ok(())
+    }
+
+    fn remove_file(&mut self, path: String) -> Result<(), c_int> {
+        let ino = self.inodes.get(&path).copied();
+        let pending = ino.is_some_and(|ino| self.is_open_for_write(ino));
+        match self.block_on(self.client.delete_object(&self.key(&path))) {
+            Ok(()) => {}
+            // A new file that was never uploaded
+            Err(e) if e.is_not_found() && pending => {}
+            Err(e) => return Err(errno(&e)),
+        }
+
+        // Handles still open on the file no longer upload it
+        for handle in self.handles.values_mut() {
+            if Some(handle.ino) == ino {
+                handle.dirty = false;
+            }
+        }
+        self.forget(&path);
+        Ok(())
+    }
+
+    /// Move a file by copying it to the new key and deleting the old one
+    fn rename_file(&mut self, from: String, to: String) -> Result<(), c_int> {
+        let ino = self.resolve(from.clone())?;
+        if self.node(ino)?.kind == FileType::Directory {
+            // Tools like mv fall back to moving the directory's files one by one
+            return Err(EXDEV);
+        }
+
+        let pending: Vec<u64> = self
+            .handles
+            .iter()
+            .filter(|(_, handle)| handle.ino == ino)
+            .map(|(fh, _)| *fh)
+            .collect();
+        for fh in pending {
+            self.write_back(fh)?;
+        }
+
+        let (from_key, to_key) = (self.key(&from), self.key(&to));
+        self.block_on(async {
+            let download = self.client.get_object(&from_key).await?;
+            let options = PutObjectOptions {
+                content_type: download.head.content_type.clone(),
+                cache_control: download.head.cache_control.clone(),
+                ..Default::default()
+            };
+            self.client
+                .put_object_stream(&to_key, download.into_stream(), options)
+                .await?;
+            self.client.delete_object(&from_key).await
+        })
+        .map_err(|e| errno(&e))?;
+
+        self.forget(&to);
+        self.inodes.remove(&from);
+        self.inodes.insert(to.clone(), ino);
+        if let Some(node) = self.nodes.get_mut(&ino) {
+            node.path = to;
+        }
+        Ok(())
+    }
+}
+
+impl Filesystem for BucketFs {
+    fn init(&mut self, req: &Request<'_>, _config: &mut KernelConfig) -> Result<(), c_int> {
+        self.uid = req.uid();
+        self.gid = req.gid();
+        Ok(())
+    }
+
+    fn lookup(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        let result = self
+            .child_path(parent, name)
+            .and_then(|path| self.resolve(path))
+            .and_then(|ino| self.attr(ino));
+        match result {
+            Ok(attr) => reply.entry(&self.ttl, &attr, 0),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn getattr(&mut self, _req: &Request<'_>, ino: u64, reply: ReplyAttr) {
+        match self.attr(ino) {
+            Ok(attr) => reply.attr(&self.ttl, &attr),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn setattr(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        _mode: Option<u32>,
+        _uid: Option<u32>,
+        _gid: Option<u32>,
+        size: Option<u64>,
+        _atime: Option<TimeOrNow>,
+        _mtime: Option<TimeOrNow>,
+        _ctime: Option<SystemTime>,
+        fh: Option<u64>,
+        _crtime: Option<SystemTime>,
+        _chgtime: Option<SystemTime>,
+        _bkuptime: Option<SystemTime>,
+        _flags: Option<u32>,
+        reply: ReplyAttr,
+    ) {
+        // Only the size can be changed; objects have no owner, mode or times
+        // of their own
+        let result = match size {
+            Some(_) if self.read_only => Err(EROFS),
+            Some(size) => self.truncate(ino, fh, size),
+            None => Ok(()),
+        };
+        match result.and_then(|()| self.attr(ino)) {
+            Ok(attr) => reply.attr(&self.ttl, &attr),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn mkdir(
+        &mut self,
+        _req: &Request<'_>,
+        parent: u64,
+        name: &OsStr,
+        _mode: u32,
+        _umask: u32,
+        reply: ReplyEntry,
+    ) {
+        if self.read_only {
+            return reply.error(EROFS);
+        }
+        let result = self.child_path(parent, name).and_then(|path| {
+            let ino = self.insert(path.clone(), FileType::Directory, 0, SystemTime::now());
+            self.empty_dirs.insert(path);
+            self.attr(ino)
+        });
+        match result {
+            Ok(attr) => reply.entry(&self.ttl, &attr, 0),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn unlink(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEmpty) {
+        if self.read_only {
+            return reply.error(EROFS);
+        }
+        match self
+            .child_path(parent, name)
+            .and_then(|path| self.remove_file(path))
+        {
+            Ok(()) => reply.ok(),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn rmdir(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEmpty) {
+        match self
+            .child_path(parent, name)
+            .and_then(|path| self.remove_dir(path))
+        {
+            Ok(()) => reply.ok(),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn rename(
+        &mut self,
+        _req: &Request<'_>,
+        parent: u64,
+        name: &OsStr,
+        newparent: u64,
+        newname: &OsStr,
+        flags: u32,
+        reply: ReplyEmpty,
+    ) {
+        if self.read_only {
+            return reply.error(EROFS);
+        }
+        // RENAME_NOREPLACE and RENAME_EXCHANGE cannot be done atomically
+        if flags != 0 {
+            return reply.error(EINVAL);
+        }
+        let result = self.child_path(parent, name).and_then(|from| {
+            let to = self.child_path(newparent, newname)?;
+            self.rename_file(from, to)
+        });
+        match result {
+            Ok(()) => reply.ok(),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn open(&mut self, _req: &Request<'_>, ino: u64, flags: i32, reply: ReplyOpen) {
+        if flags & libc::O_ACCMODE == libc::O_RDONLY {
+            return reply.opened(0, 0);
+        }
+        if self.read_only {
+            return reply.error(EROFS);
+        }
+
+        let truncate = flags & libc::O_TRUNC != 0;
+        let load = !truncate && self.node(ino).is_ok_and(|node| node.size > 0);
+        match self.open_for_write(ino, load) {
+            Ok(mut handle) => {
+                if truncate {
+                    handle.dirty = true;
+                    if let Some(node) = self.nodes.get_mut(&ino) {
+                        node.size = 0;
+                    }
+                }
+                let fh = self.add_handle(handle);
+                reply.opened(fh, 0);
+            }
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn read(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyData,
+    ) {
+        let offset = offset.max(0) as u64;
+        if let Some(handle) = self.handles.get(&fh) {
+            let mut data = vec![0; size as usize];
+            return match read_at(&handle.spool, &mut data, offset) {
+                Ok(read) => reply.data(&data[..read]),
+                Err(e) => reply.error(io_errno(e)),
+            };
+        }
+
+        let node = match self.node(ino) {
+            Ok(node) => node,
+            Err(e) => return reply.error(e),
+        };
+        let end = offset.saturating_add(size as u64).min(node.size);
+        if offset >= end {
+            return reply.data(&[]);
+        }
+        let key = self.key(&node.path);
+        match self.block_on(self.client.get_object_range(&key, offset..end)) {
+            Ok(data) => reply.data(&data),
+            Err(e) => reply.error(errno(&e)),
+        }
+    }
+
+    fn write(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        fh: u64,
+        offset: i64,
+        data: &[u8],
+        _write_flags: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyWrite,
+    ) {
+        let Some(handle) = self.handles.get_mut(&fh) else {
+            return reply.error(EBADF);
+        };
+        let offset = offset.max(0) as u64;
+        if let Err(e) = handle.spool.write_all_at(data, offset) {
+            return reply.error(io_errno(e));
+        }
+        handle.dirty = true;
+
+        if let Some(node) = self.nodes.get_mut(&ino) {
+            node.size = node.size.max(offset + data.len() as u64);
+            node.modified = SystemTime::now();
+        }
+        reply.written(data.len() as u32);
+    }
+
+    /// Upload on every close of a descriptor, so errors reach close(2)
+    fn flush(
+        &mut self,
+        _req: &Request<'_>,
+        _ino: u64,
+        fh: u64,
+        _lock_owner: u64,
+        reply: ReplyEmpty,
+    ) {
+        match self.write_back(fh) {
+            Ok(()) => reply.ok(),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn release(
+        &mut self,
+        _req: &Request<'_>,
+        _ino: u64,
+        fh: u64,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        _flush: bool,
+        reply: ReplyEmpty,
+    ) {
+        let result = self.write_back(fh);
+        self.handles.remove(&fh);
+        match result {
+            Ok(()) => reply.ok(),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn readdir(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        mut reply: ReplyDirectory,
+    ) {
+        let entries = match self.list_dir(ino) {
+            Ok(entries) => entries,
+            Err(e) => return reply.error(e),
+        };
+        let parent_ino = self
+            .node(ino)
+            .ok()
+            .and_then(|node| self.inodes.get(parent(&node.path)))
+            .copied()
+            .unwrap_or(FUSE_ROOT_ID);
+
+        let dots = [
+            (ino, FileType::Directory, ".".to_string()),
+            (parent_ino, FileType::Directory, "..".to_string()),
+        ];
+        for (i, (ino, kind, name)) in dots
+            .into_iter()
+            .chain(entries)
+            .enumerate()
+            .skip(offset.max(0) as usize)
+        {
+            if reply.add(ino, i as i64 + 1, kind, name) {
+                break;
+            }
+        }
+        reply.ok();
+    }
+
+    fn create(
+        &mut self,
+        _req: &Request<'_>,
+        parent: u64,
+        name: &OsStr,
+        _mode: u32,
+        _umask: u32,
+        _flags: i32,
+        reply: ReplyCreate,
+    ) {
+        if self.read_only {
+            return reply.error(EROFS);
+        }
+        let result = self.child_path(parent, name).and_then(|path| {
+            let ino = self.insert(path, FileType::RegularFile, 0, SystemTime::now());
+            let spool = tempfile::tempfile().map_err(io_errno)?;
+            // Uploaded on close even if nothing is written
+            let fh = self.add_handle(WriteHandle {
+                ino,
+                spool,
+                dirty: true,
+            });
+            Ok((fh, self.attr(ino)?))
+        });
+        match result {
+            Ok((fh, attr)) => reply.created(&self.ttl, &attr, 0, fh, 0),
+            Err(e) => reply.error(e),
+        }
+    }
+}
+
+/// The files and subdirectories directly inside the directory holding the
+/// objects whose keys start with `prefix`
+///
+/// A subdirectory is as recent as the newest object in it. Objects named like
+/// a subdirectory are hidden by it.
+fn dir_entries(prefix: &str, objects: &[ObjectInfoDto]) -> Vec<DirEntry> {
+    let mut dirs: BTreeMap<&str, SystemTime> = BTreeMap::new();
+    let mut files = Vec::new();
+    for object in objects {
+        let Some(rest) = object.key.strip_prefix(prefix) else {
+            continue;
+        };
+        let modified = SystemTime::from(object.last_modified);
+        match rest.split_once('/') {
+            // Markers like `logs/` of empty directories have nothing after the slash
+            Some((dir, _)) if !dir.is_empty() => {
+                let newest = dirs.entry(dir).or_insert(modified);
+                *newest = (*newest).max(modified);
+            }
+            None if !rest.is_empty() => files.push(DirEntry {
+                name: rest.to_string(),
+                kind: FileType::RegularFile,
+                size: object.size,
+                modified,
+            }),
+            _ => {}
+        }
+    }
+
+    files.retain(|file| !dirs.contains_key(file.name.as_str()));
+    dirs.into_iter()
+        .map(|(name, modified)| DirEntry {
+            name: name.to_string(),
+            kind: FileType::Directory,
+            size: 0,
+            modified,
+        })
+        .chain(files)
+        .collect()
+}
+
+fn join(dir: &str, name: &str) -> String {
+    if dir.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+fn parent(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(parent, _)| parent)
+}
+
+fn name(path: &str) -> &str {
+    path.rsplit_once('/').map_or(path, |(_, name)| name)
+}
+
+/// Read into `data` from `offset` until it is full or the file ends
+fn read_at(file: &File, data: &mut [u8], offset: u64) -> std::io::Result<usize> {
+    let mut read = 0;
+    while read < data.len() {
+        match file.read_at(&mut data[read..], offset + read as u64)? {
+            0 => break,
+            n => read += n,
+        }
+    }
+    Ok(read)
+}
+
+fn io_errno(error: std::io::Error) -> c_int {
+    error.raw_os_error().unwrap_or(EIO)
+}
+
+fn errno(error: &ClientError) -> c_int {
+    match error.status() {
+        Some(404) => ENOENT,
+        Some(401) | Some(403) => libc::EACCES,
+        Some(413) => libc::EFBIG,
+        Some(507) => libc::ENOSPC,
+        Some(501) => libc::ENOSYS,
+        _ => EIO,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn object(key: &str, size: u64, secs: i64) -> ObjectInfoDto {
+        ObjectInfoDto {
+            key: key.to_string(),
+            size,
+            last_modified: Utc.timestamp_opt(secs, 0).unwrap(),
+            etag: None,
+            storage_class: None,
+            version_id: None,
+        }
+    }
+
+    #[test]
+    fn test_dir_entries_from_prefixes() {
+        let objects = [
+            object("photos/cat.jpg", 10, 100),
+            object("photos/2024/a.jpg", 20, 200),
+            object("photos/2024/b/c.jpg", 30, 300),
+            object("photos/logs/", 0, 50),
+            object("photos/2024", 5, 400),
+        ];
+        let entries = dir_entries("photos/", &objects);
+
+        let names: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.name.as_str(), entry.kind))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("2024", FileType::Directory),
+                ("logs", FileType::Directory),
+                ("cat.jpg", FileType::RegularFile),
+            ]
+        );
+        assert_eq!(entries[0].modified, UNIX_EPOCH + Duration::from_secs(300));
+        assert_eq!(entries[2].size, 10);
+    }
+
+    #[test]
+    fn test_paths() {
+        assert_eq!(join("", "a"), "a");
+        assert_eq!(join("a/b", "c"), "a/b/c");
+        assert_eq!(parent("a/b/c"), "a/b");
+        assert_eq!(parent("a"), "");
+        assert_eq!(name("a/b/c"), "c");
+    }
+}
@@ -0,0 +1,97 @@
+//! Mount a bucket of the object store server as a filesystem
+//!
+//! Directories are the prefixes of the bucket's object keys, reads fetch byte
+//! ranges as they are asked for, and files written through the mount are
+//! uploaded when they are closed.
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use fuser::MountOption;
+use object_store_server::client::Client;
+use std::{path::PathBuf, time::Duration};
+use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
+
+mod fs;
+
+use fs::BucketFs;
+
+#[derive(Parser, Debug)]
+#[command(name = "object-store-fuse")]
+#[command(about = "Mount a bucket of the object store server as a filesystem", long_about = None)]
+struct Cli {
+    /// Bucket to mount
+    bucket: String,
+
+    /// Directory to mount the bucket on
+    mountpoint: PathBuf,
+
+    /// Server URL
+    #[arg(
+        short,
+        long,
+        env = "OBJECT_STORE_URL",
+        default_value = "http://localhost:3000"
+    )]
+    url: String,
+
+    /// API key for authentication
+    #[arg(long, env = "OBJECT_STORE_API_KEY")]
+    api_key: Option<String>,
+
+    /// Refuse writes, renames and deletes
+    #[arg(long)]
+    read_only: bool,
+
+    /// Let users other than the one mounting access the filesystem
+    #[arg(long)]
+    allow_other: bool,
+
+    /// Seconds the kernel may cache file attributes and directory entries
+    #[arg(long, default_value_t = 1)]
+    attr_ttl: u64,
+}
+
+fn main() -> Result<()> {
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer())
+        .with(EnvFilter::from_default_env())
+        .init();
+
+    let cli = Cli::parse();
+
+    let mut builder = Client::builder(&cli.url);
+    if let Some(api_key) = &cli.api_key {
+        builder = builder.api_key(api_key);
+    }
+    let client = builder.build()?;
+    let runtime = tokio::runtime::Runtime::new().context("Failed to start the async runtime")?;
+
+    let mut options = vec![
+        MountOption::FSName(format!("object-store:{}", cli.bucket)),
+        MountOption::Subtype("object-store".to_string()),
+        MountOption::DefaultPermissions,
+        MountOption::AutoUnmount,
+    ];
+    if cli.read_only {
+        options.push(MountOption::RO);
+    }
+    if cli.allow_other {
+        options.push(MountOption::AllowOther);
+    }
+
+    let fs = BucketFs::new(
+        client,
+        runtime,
+        &cli.bucket,
+        cli.read_only,
+        Duration::from_secs(cli.attr_ttl),
+    );
+    tracing::info!(
+        "Mounting bucket {} of {} on {}",
+        cli.bucket,
+        cli.url,
+        cli.mountpoint.display()
+    );
+    fuser::mount2(fs, &cli.mountpoint, &options)
+        .with_context(|| format!("Failed to mount {}", cli.mountpoint.display()))
+}
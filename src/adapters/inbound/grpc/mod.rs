@@ -0,0 +1,23 @@
+//! gRPC adapter streaming object uploads and downloads
+//!
+//! The `Objects` service of `proto/object_store.proto` moves object data in
+//! chunks, each way ending with a trailer carrying the length and CRC32 of
+//! the data, so neither side has to hold a whole object in memory:
+//!
+//! - `PutObject` is client-streaming: a header naming the object, its data
+//!   chunks, then the trailer. The data is streamed to the storage backend as
+//!   it arrives, and the upload fails without storing anything when the
+//!   trailer doesn't match the data received.
+//! - `GetObject` is server-streaming: a header with the object's metadata,
+//!   its data chunks read from the backend, then the trailer.
+
+pub mod objects;
+pub mod status;
+
+/// Messages and service generated from `proto/object_store.proto`
+pub mod proto {
+    tonic::include_proto!("objectstore.v1");
+}
+
+pub use objects::{GrpcObjectService, DEFAULT_CHUNK_SIZE};
+pub use proto::objects_server::ObjectsServer;
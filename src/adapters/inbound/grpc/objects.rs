@@ -0,0 +1,354 @@
+use bytes::Bytes;
+use futures::stream::{self, BoxStream, Stream, StreamExt};
+use std::sync::{Arc, Mutex};
+use tokio_util::io::{ReaderStream, StreamReader};
+use tonic::{Request, Response, Status, Streaming};
+
+use super::proto::{
+    get_object_response, objects_server::Objects, put_object_request, ChecksumTrailer,
+    GetObjectRequest, GetObjectResponse, ObjectInfo, PutObjectHeader, PutObjectRequest,
+    PutObjectResponse,
+};
+use super::status::storage_status;
+use crate::{
+    domain::{models::ObjectMetadata, value_objects::ObjectKey},
+    ports::services::ObjectService,
+};
+
+/// Size of the data chunks `GetObject` sends
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// The `Objects` gRPC service over an [`ObjectService`]
+#[derive(Clone)]
+pub struct GrpcObjectService {
+    object_service: Arc<dyn ObjectService>,
+    chunk_size: usize,
+}
+
+impl GrpcObjectService {
+    pub fn new(object_service: Arc<dyn ObjectService>) -> Self {
+        Self {
+            object_service,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        }
+    }
+
+    /// Send downloads in chunks of up to `chunk_size` bytes
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Store the object sent as a header, data chunks and a checksum trailer
+    pub async fn put<S>(&self, mut parts: S) -> Result<PutObjectResponse, Status>
+    where
+        S: Stream<Item = Result<PutObjectRequest, Status>> + Send + Unpin + 'static,
+    {
+        let header = match parts.next().await.transpose()?.and_then(|p| p.part) {
+            Some(put_object_request::Part::Header(header)) => header,
+            _ => {
+                return Err(Status::invalid_argument(
+                    "PutObject must start with a header",
+                ))
+            }
+        };
+        let PutObjectHeader {
+            key,
+            content_type,
+            custom_metadata,
+        } = header;
+        let key = ObjectKey::new(key).map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+        // Why the data stopped, when it was the stream rather than the store
+        let failure = Arc::new(Mutex::new(None));
+        let body = StreamReader::new(verified_chunks(parts, failure.clone()));
+        let result = self
+            .object_service
+            .create_object_stream(key.clone(), Box::new(body), content_type, custom_metadata)
+            .await;
+        if let Some(status) = failure.lock().unwrap().take() {
+            return Err(status);
+        }
+        let (metadata, quota_warnings) = result.map_err(storage_status)?;
+
+        Ok(PutObjectResponse {
+            object: Some(object_info(&key, metadata)),
+            quota_warnings: quota_warnings.iter().map(ToString::to_string).collect(),
+        })
+    }
+
+    /// The object as a header, data chunks and a checksum trailer
+    pub async fn get(
+        &self,
+        request: GetObjectRequest,
+    ) -> Result<BoxStream<'static, Result<GetObjectResponse, Status>>, Status> {
+        let key =
+            ObjectKey::new(request.key).map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let (metadata, reader) = self
+            .object_service
+            .get_object_stream(&key, None)
+            .await
+            .map_err(storage_status)?;
+
+        let header = GetObjectResponse {
+            part: Some(get_object_response::Part::Header(object_info(
+                &key, metadata,
+            ))),
+        };
+        let chunks = ReaderStream::with_capacity(reader, self.chunk_size);
+        let data = stream::unfold(
+            Some((chunks, crc32fast::Hasher::new(), 0u64)),
+            |state| async move {
+                let (mut chunks, mut hasher, mut length) = state?;
+                let part = match chunks.next().await {
+                    Some(Ok(chunk)) => {
+                        hasher.update(&chunk);
+                        length += chunk.len() as u64;
+                        let part = get_object_response::Part::Chunk(chunk.to_vec());
+                        return Some((Ok(part), Some((chunks, hasher, length))));
+                    }
+                    Some(Err(e)) => Err(Status::internal(format!("Failed to read object: {}", e))),
+                    None => Ok(get_object_response::Part::Trailer(ChecksumTrailer {
+                        content_length: length,
+                        crc32: hasher.finalize(),
+                    })),
+                };
+                // The trailer, or the error, ends the stream
+                Some((part, None))
+            },
+        )
+        .map(|part| part.map(|part| GetObjectResponse { part: Some(part) }));
+
+        Ok(stream::once(async { Ok(header) }).chain(data).boxed())
+    }
+}
+
+#[tonic::async_trait]
+impl Objects for GrpcObjectService {
+    async fn put_object(
+        &self,
+        request: Request<Streaming<PutObjectRequest>>,
+    ) -> Result<Response<PutObjectResponse>, Status> {
+        self.put(request.into_inner()).await.map(Response::new)
+    }
+
+    type GetObjectStream = BoxStream<'static, Result<GetObjectResponse, Status>>;
+
+    async fn get_object(
+        &self,
+        request: Request<GetObjectRequest>,
+    ) -> Result<Response<Self::GetObjectStream>, Status> {
+        self.get(request.into_inner()).await.map(Response::new)
+    }
+}
+
+fn object_info(key: &ObjectKey, metadata: ObjectMetadata) -> ObjectInfo {
+    ObjectInfo {
+        key: key.as_str().to_string(),
+        content_type: metadata.content_type,
+        content_length: metadata.content_length,
+        etag: metadata.etag,
+        custom_metadata: metadata.custom_metadata,
+    }
+}
+
+/// The data chunks of an upload, ending once a trailer matching them arrives
+///
+/// Anything else fails the stream, recording the status to answer with in
+/// `failure`, so the store never sees the end of data that didn't arrive
+/// intact.
+fn verified_chunks<S>(
+    parts: S,
+    failure: Arc<Mutex<Option<Status>>>,
+) -> BoxStream<'static, std::io::Result<Bytes>>
+where
+    S: Stream<Item = Result<PutObjectRequest, Status>> + Send + Unpin + 'static,
+{
+    stream::unfold(
+        Some((parts, crc32fast::Hasher::new(), 0u64)),
+        move |state| {
+            let failure = failure.clone();
+            async move {
+                let (mut parts, mut hasher, mut length) = state?;
+                let status = match parts.next().await {
+                    Some(Ok(PutObjectRequest {
+                        part: Some(put_object_request::Part::Chunk(chunk)),
+                    })) => {
+                        hasher.update(&chunk);
+                        length += chunk.len() as u64;
+                        return Some((Ok(Bytes::from(chunk)), Some((parts, hasher, length))));
+                    }
+                    Some(Ok(PutObjectRequest {
+                        part: Some(put_object_request::Part::Trailer(trailer)),
+                    })) => {
+                        let crc32 = hasher.finalize();
+                        if trailer.content_length != length || trailer.crc32 != crc32 {
+                            Status::data_loss(format!(
+                                "Received {} bytes with CRC32 {:08x}, trailer says {} with {:08x}",
+                                length, crc32, trailer.content_length, trailer.crc32
+                            ))
+                        } else if parts.next().await.is_some() {
+                            Status::invalid_argument("PutObject must end with the trailer")
+                        } else {
+                            return None;
+                        }
+                    }
+                    Some(Ok(_)) => {
+                        Status::invalid_argument("PutObject expects data chunks after the header")
+                    }
+                    Some(Err(status)) => status,
+                    None => Status::invalid_argument("PutObject ended without a checksum trailer"),
+                };
+                let error = std::io::Error::other(status.message().to_string());
+                *failure.lock().unwrap() = Some(status);
+                Some((Err(error), None))
+            }
+        },
+    )
+    .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::inbound::grpc::proto::objects_client::ObjectsClient;
+    use crate::adapters::inbound::grpc::ObjectsServer;
+    use crate::adapters::outbound::{
+        persistence::InMemoryObjectRepository, storage::S3ObjectStoreAdapter,
+    };
+    use crate::domain::value_objects::BucketName;
+    use crate::services::ObjectServiceImpl;
+    use object_store::memory::InMemory;
+    use std::collections::HashMap;
+    use tokio::net::TcpListener;
+    use tonic::Code;
+
+    fn object_service() -> Arc<dyn ObjectService> {
+        let bucket = BucketName::new("test-bucket".to_string()).unwrap();
+        Arc::new(ObjectServiceImpl::new(
+            Arc::new(InMemoryObjectRepository::new()),
+            Arc::new(S3ObjectStoreAdapter::new(Arc::new(InMemory::new()), bucket)),
+        ))
+    }
+
+    fn put_parts(key: &str, chunks: &[&[u8]], trailer: ChecksumTrailer) -> Vec<PutObjectRequest> {
+        let mut parts = vec![PutObjectRequest {
+            part: Some(put_object_request::Part::Header(PutObjectHeader {
+                key: key.to_string(),
+                content_type: Some("text/plain".to_string()),
+                custom_metadata: HashMap::from([("team".to_string(), "data".to_string())]),
+            })),
+        }];
+        for chunk in chunks {
+            parts.push(PutObjectRequest {
+                part: Some(put_object_request::Part::Chunk(chunk.to_vec())),
+            });
+        }
+        parts.push(PutObjectRequest {
+            part: Some(put_object_request::Part::Trailer(trailer)),
+        });
+        parts
+    }
+
+    fn trailer_for(data: &[u8]) -> ChecksumTrailer {
+        ChecksumTrailer {
+            content_length: data.len() as u64,
+            crc32: crc32fast::hash(data),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_objects_stream_both_ways_with_checksum_trailers() {
+        let service = GrpcObjectService::new(object_service()).with_chunk_size(4);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let incoming = stream::unfold(listener, |listener| async move {
+            Some((listener.accept().await.map(|(socket, _)| socket), listener))
+        });
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(ObjectsServer::new(service))
+                .serve_with_incoming(incoming),
+        );
+        let mut client = ObjectsClient::connect(format!("http://{}", addr))
+            .await
+            .unwrap();
+
+        let response = client
+            .put_object(stream::iter(put_parts(
+                "docs/streamed.txt",
+                &[b"hello ", b"streaming ", b"world"],
+                trailer_for(b"hello streaming world"),
+            )))
+            .await
+            .unwrap()
+            .into_inner();
+        let object = response.object.unwrap();
+        assert_eq!(object.content_length, 21);
+        assert_eq!(object.custom_metadata["team"], "data");
+
+        let mut parts = client
+            .get_object(GetObjectRequest {
+                key: "docs/streamed.txt".to_string(),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        let mut data = Vec::new();
+        let mut chunk_count = 0;
+        let mut trailer = None;
+        while let Some(part) = parts.message().await.unwrap() {
+            match part.part.unwrap() {
+                get_object_response::Part::Header(header) => {
+                    assert_eq!(header.content_type.as_deref(), Some("text/plain"));
+                    assert!(data.is_empty());
+                }
+                get_object_response::Part::Chunk(chunk) => {
+                    assert!(chunk.len() <= 4);
+                    data.extend_from_slice(&chunk);
+                    chunk_count += 1;
+                }
+                get_object_response::Part::Trailer(t) => trailer = Some(t),
+            }
+        }
+        assert_eq!(data, b"hello streaming world");
+        assert!(chunk_count > 1);
+        assert_eq!(trailer, Some(trailer_for(b"hello streaming world")));
+    }
+
+    #[tokio::test]
+    async fn test_uploads_not_matching_their_trailer_are_not_stored() {
+        let objects = object_service();
+        let service = GrpcObjectService::new(objects.clone());
+        let key = ObjectKey::new("docs/corrupt.txt".to_string()).unwrap();
+
+        let parts = put_parts("docs/corrupt.txt", &[b"hello"], trailer_for(b"hellO"));
+        let status = service
+            .put(stream::iter(parts.into_iter().map(Ok)))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::DataLoss);
+        assert!(!objects.object_exists(&key).await.unwrap());
+
+        // A stream cut off before its trailer stores nothing either
+        let mut parts = put_parts("docs/corrupt.txt", &[b"hello"], trailer_for(b"hello"));
+        parts.pop();
+        let status = service
+            .put(stream::iter(parts.into_iter().map(Ok)))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert!(!objects.object_exists(&key).await.unwrap());
+
+        let status = match service
+            .get(GetObjectRequest {
+                key: "docs/corrupt.txt".to_string(),
+            })
+            .await
+        {
+            Err(status) => status,
+            Ok(_) => panic!("Expected the object not to exist"),
+        };
+        assert_eq!(status.code(), Code::NotFound);
+    }
+}
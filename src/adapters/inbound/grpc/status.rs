@@ -0,0 +1,37 @@
+use tonic::{Code, Status};
+
+use crate::domain::errors::StorageError;
+
+/// The gRPC status a domain error is reported with, mirroring the HTTP
+/// adapter's status codes
+pub fn storage_status(error: StorageError) -> Status {
+    let code = match &error {
+        StorageError::ObjectNotFound { .. }
+        | StorageError::ObjectExpired { .. }
+        | StorageError::VersionNotFound { .. }
+        | StorageError::UploadNotFound { .. }
+        | StorageError::TransactionNotFound { .. }
+        | StorageError::DatasetNotFound { .. }
+        | StorageError::BackupNotFound { .. } => Code::NotFound,
+        StorageError::ObjectAlreadyExists { .. } => Code::AlreadyExists,
+        StorageError::VersionConflict { .. } => Code::Aborted,
+        StorageError::InvalidObjectSize { .. }
+        | StorageError::InvalidStorageClass { .. }
+        | StorageError::InvalidPart { .. }
+        | StorageError::InvalidPartOrder { .. }
+        | StorageError::ValidationError { .. } => Code::InvalidArgument,
+        StorageError::QuotaExceeded { .. } | StorageError::PrefixQuotaExceeded { .. } => {
+            Code::ResourceExhausted
+        }
+        StorageError::AccessDenied { .. } => Code::PermissionDenied,
+        StorageError::OperationNotSupported { .. } | StorageError::UnsupportedOperation { .. } => {
+            Code::Unimplemented
+        }
+        StorageError::StorageBackendError { .. } => Code::Unavailable,
+        StorageError::Timeout { .. } => Code::DeadlineExceeded,
+        StorageError::InfrastructureError { .. } | StorageError::InternalError { .. } => {
+            Code::Internal
+        }
+    };
+    Status::new(code, error.to_string())
+}
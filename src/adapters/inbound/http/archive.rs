@@ -0,0 +1,1093 @@
+//! Streaming tar and zip support for bundle downloads and archive uploads
+//!
+//! Entries are written and read as their data flows through, so an archive of
+//! many large objects never has to be held in memory or staged on disk.
+
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::io;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+const TAR_BLOCK: usize = 512;
+const COPY_BUFFER: usize = 64 * 1024;
+
+const ZIP_LOCAL_HEADER_SIG: u32 = 0x0403_4b50;
+const ZIP_DATA_DESCRIPTOR_SIG: u32 = 0x0807_4b50;
+const ZIP_CENTRAL_HEADER_SIG: u32 = 0x0201_4b50;
+const ZIP_END_OF_CENTRAL_DIR_SIG: u32 = 0x0605_4b50;
+/// The CRC follows the data in a descriptor, and names are UTF-8
+const ZIP_FLAGS: u16 = (1 << 3) | (1 << 11);
+const ZIP_VERSION: u16 = 20;
+
+/// Default limit on the size of a single file extracted from an archive (5 GiB)
+pub const DEFAULT_MAX_ENTRY_SIZE: u64 = 5 * 1024 * 1024 * 1024;
+
+/// Default limit on the total size of the files extracted from an archive (50 GiB)
+pub const DEFAULT_MAX_EXTRACTED_SIZE: u64 = 50 * 1024 * 1024 * 1024;
+
+/// Archive format of a bundle download
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArchiveFormat {
+    #[default]
+    Tar,
+    Zip,
+}
+
+impl ArchiveFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ArchiveFormat::Tar => "application/x-tar",
+            ArchiveFormat::Zip => "application/zip",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ArchiveFormat::Tar => "tar",
+            ArchiveFormat::Zip => "zip",
+        }
+    }
+}
+
+/// Central directory record kept for each zip entry until the archive is finished
+struct ZipEntry {
+    name: String,
+    crc: u32,
+    size: u32,
+    offset: u32,
+    time: u16,
+    date: u16,
+}
+
+/// Writes entries of a tar or zip archive to an async writer
+///
+/// Zip entries are stored uncompressed and without zip64 extensions, so zip
+/// archives are limited to 4 GiB and 65535 entries; tar has no such limits.
+pub struct ArchiveWriter<W> {
+    inner: W,
+    format: ArchiveFormat,
+    written: u64,
+    zip_entries: Vec<ZipEntry>,
+}
+
+impl<W: AsyncWrite + Unpin> ArchiveWriter<W> {
+    pub fn new(inner: W, format: ArchiveFormat) -> Self {
+        Self {
+            inner,
+            format,
+            written: 0,
+            zip_entries: Vec::new(),
+        }
+    }
+
+    /// Append a file of `size` bytes read from `reader`
+    pub async fn append<R: AsyncRead + Unpin>(
+        &mut self,
+        name: &str,
+        size: u64,
+        modified: DateTime<Utc>,
+        reader: R,
+    ) -> io::Result<()> {
+        match self.format {
+            ArchiveFormat::Tar => self.append_tar(name, size, modified, reader).await,
+            ArchiveFormat::Zip => self.append_zip(name, size, modified, reader).await,
+        }
+    }
+
+    /// Write the archive trailer and return the underlying writer
+    pub async fn finish(mut self) -> io::Result<W> {
+        match self.format {
+            ArchiveFormat::Tar => self.write(&[0u8; TAR_BLOCK * 2]).await?,
+            ArchiveFormat::Zip => self.finish_zip().await?,
+        }
+        self.inner.flush().await?;
+        Ok(self.inner)
+    }
+
+    async fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        self.inner.write_all(data).await?;
+        self.written += data.len() as u64;
+        Ok(())
+    }
+
+    /// Copy an entry's data, returning its length and CRC-32
+    async fn copy_data<R: AsyncRead + Unpin>(&mut self, mut reader: R) -> io::Result<(u64, u32)> {
+        let mut hasher = crc32fast::Hasher::new();
+        let mut buffer = vec![0u8; COPY_BUFFER];
+        let mut copied = 0u64;
+        loop {
+            let n = reader.read(&mut buffer).await?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buffer[..n]);
+            self.write(&buffer[..n]).await?;
+            copied += n as u64;
+        }
+        Ok((copied, hasher.finalize()))
+    }
+
+    async fn append_tar<R: AsyncRead + Unpin>(
+        &mut self,
+        name: &str,
+        size: u64,
+        modified: DateTime<Utc>,
+        reader: R,
+    ) -> io::Result<()> {
+        let mtime = modified.timestamp().max(0) as u64;
+        let (name_field, prefix_field) = match split_tar_name(name) {
+            Some(split) => split,
+            None => {
+                // GNU long name extension: the real name is the data of a preceding entry
+                let long_name = name.as_bytes();
+                let header = tar_header(b"././@LongLink", b"", long_name.len() as u64 + 1, 0, b'L');
+                self.write(&header).await?;
+                self.write(long_name).await?;
+                self.write(&[0u8]).await?;
+                self.write(&[0u8; TAR_BLOCK][..tar_padding(long_name.len() as u64 + 1)])
+                    .await?;
+                (&name.as_bytes()[..100], &b""[..])
+            }
+        };
+
+        self.write(&tar_header(name_field, prefix_field, size, mtime, b'0'))
+            .await?;
+        let (copied, _) = self.copy_data(reader.take(size)).await?;
+        if copied != size {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("'{}' ended after {} of {} bytes", name, copied, size),
+            ));
+        }
+        self.write(&[0u8; TAR_BLOCK][..tar_padding(size)]).await
+    }
+
+    async fn append_zip<R: AsyncRead + Unpin>(
+        &mut self,
+        name: &str,
+        size: u64,
+        modified: DateTime<Utc>,
+        reader: R,
+    ) -> io::Result<()> {
+        let too_large = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Zip archives are limited to 4 GiB and 65535 entries; use tar instead",
+            )
+        };
+        if self.zip_entries.len() >= u16::MAX as usize || size > u32::MAX as u64 {
+            return Err(too_large());
+        }
+        let offset = u32::try_from(self.written).map_err(|_| too_large())?;
+        let name_len = u16::try_from(name.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Entry name is too long"))?;
+        let (time, date) = dos_date_time(modified);
+
+        let mut header = Vec::with_capacity(30 + name.len());
+        header.extend_from_slice(&ZIP_LOCAL_HEADER_SIG.to_le_bytes());
+        header.extend_from_slice(&ZIP_VERSION.to_le_bytes());
+        header.extend_from_slice(&ZIP_FLAGS.to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes()); // stored
+        header.extend_from_slice(&time.to_le_bytes());
+        header.extend_from_slice(&date.to_le_bytes());
+        // The CRC is only known once the data is written, so it goes in the data
+        // descriptor; the sizes are known up front and let streaming readers find
+        // the end of the entry
+        header.extend_from_slice(&0u32.to_le_bytes());
+        header.extend_from_slice(&(size as u32).to_le_bytes());
+        header.extend_from_slice(&(size as u32).to_le_bytes());
+        header.extend_from_slice(&name_len.to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(name.as_bytes());
+        self.write(&header).await?;
+
+        let (copied, crc) = self.copy_data(reader.take(size)).await?;
+        if copied != size {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("'{}' ended after {} of {} bytes", name, copied, size),
+            ));
+        }
+        let size = size as u32;
+
+        let mut descriptor = Vec::with_capacity(16);
+        descriptor.extend_from_slice(&ZIP_DATA_DESCRIPTOR_SIG.to_le_bytes());
+        descriptor.extend_from_slice(&crc.to_le_bytes());
+        descriptor.extend_from_slice(&size.to_le_bytes());
+        descriptor.extend_from_slice(&size.to_le_bytes());
+        self.write(&descriptor).await?;
+
+        self.zip_entries.push(ZipEntry {
+            name: name.to_string(),
+            crc,
+            size,
+            offset,
+            time,
+            date,
+        });
+        Ok(())
+    }
+
+    async fn finish_zip(&mut self) -> io::Result<()> {
+        let too_large = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Zip archives are limited to 4 GiB; use tar instead",
+            )
+        };
+        let directory_offset = u32::try_from(self.written).map_err(|_| too_large())?;
+
+        let mut directory = Vec::new();
+        for entry in &self.zip_entries {
+            directory.extend_from_slice(&ZIP_CENTRAL_HEADER_SIG.to_le_bytes());
+            directory.extend_from_slice(&ZIP_VERSION.to_le_bytes()); // made by
+            directory.extend_from_slice(&ZIP_VERSION.to_le_bytes()); // needed
+            directory.extend_from_slice(&ZIP_FLAGS.to_le_bytes());
+            directory.extend_from_slice(&0u16.to_le_bytes());
+            directory.extend_from_slice(&entry.time.to_le_bytes());
+            directory.extend_from_slice(&entry.date.to_le_bytes());
+            directory.extend_from_slice(&entry.crc.to_le_bytes());
+            directory.extend_from_slice(&entry.size.to_le_bytes());
+            directory.extend_from_slice(&entry.size.to_le_bytes());
+            directory.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+            // Extra field, comment, disk number, internal and external attributes
+            directory.extend_from_slice(&[0u8; 12]);
+            directory.extend_from_slice(&entry.offset.to_le_bytes());
+            directory.extend_from_slice(entry.name.as_bytes());
+        }
+        let directory_size = u32::try_from(directory.len()).map_err(|_| too_large())?;
+        u32::try_from(self.written + directory.len() as u64).map_err(|_| too_large())?;
+
+        let entries = self.zip_entries.len() as u16;
+        directory.extend_from_slice(&ZIP_END_OF_CENTRAL_DIR_SIG.to_le_bytes());
+        directory.extend_from_slice(&[0u8; 4]); // disk numbers
+        directory.extend_from_slice(&entries.to_le_bytes());
+        directory.extend_from_slice(&entries.to_le_bytes());
+        directory.extend_from_slice(&directory_size.to_le_bytes());
+        directory.extend_from_slice(&directory_offset.to_le_bytes());
+        directory.extend_from_slice(&0u16.to_le_bytes());
+        self.write(&directory).await
+    }
+}
+
+/// Split a name into the ustar name and prefix fields, if it fits in them
+fn split_tar_name(name: &str) -> Option<(&[u8], &[u8])> {
+    let bytes = name.as_bytes();
+    if bytes.len() <= 100 {
+        return Some((bytes, b""));
+    }
+    bytes
+        .iter()
+        .enumerate()
+        .filter(|(i, b)| **b == b'/' && *i <= 155 && bytes.len() - i - 1 <= 100)
+        .map(|(i, _)| (&bytes[i + 1..], &bytes[..i]))
+        .next()
+}
+
+fn tar_padding(size: u64) -> usize {
+    (TAR_BLOCK - (size % TAR_BLOCK as u64) as usize) % TAR_BLOCK
+}
+
+/// Write `value` as a NUL-terminated octal number filling `field`
+fn write_octal(field: &mut [u8], value: u64) {
+    let digits = field.len() - 1;
+    let text = format!("{:0width$o}", value, width = digits);
+    field[..digits].copy_from_slice(text.as_bytes());
+    field[digits] = 0;
+}
+
+fn tar_header(name: &[u8], prefix: &[u8], size: u64, mtime: u64, kind: u8) -> [u8; TAR_BLOCK] {
+    let mut header = [0u8; TAR_BLOCK];
+    header[..name.len()].copy_from_slice(name);
+    write_octal(&mut header[100..108], 0o644);
+    write_octal(&mut header[108..116], 0);
+    write_octal(&mut header[116..124], 0);
+    if size < 0o77777777777 {
+        write_octal(&mut header[124..136], size);
+    } else {
+        // Base-256 encoding for sizes beyond 8 GiB
+        header[124] = 0x80;
+        header[128..136].copy_from_slice(&size.to_be_bytes());
+    }
+    write_octal(&mut header[136..148], mtime);
+    header[156] = kind;
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    header[345..345 + prefix.len()].copy_from_slice(prefix);
+
+    // The checksum is computed with its own field set to spaces
+    header[148..156].copy_from_slice(b"        ");
+    let checksum: u32 = header.iter().map(|b| *b as u32).sum();
+    write_octal(&mut header[148..155], checksum as u64);
+    header[155] = b' ';
+    header
+}
+
+/// MS-DOS time and date fields, clamped to the earliest representable date
+fn dos_date_time(timestamp: DateTime<Utc>) -> (u16, u16) {
+    if timestamp.year() < 1980 {
+        return (0, (1 << 5) | 1);
+    }
+    let time = (timestamp.hour() << 11) | (timestamp.minute() << 5) | (timestamp.second() / 2);
+    let date = ((timestamp.year() as u32 - 1980).min(127) << 9)
+        | (timestamp.month() << 5)
+        | timestamp.day();
+    (time as u16, date as u16)
+}
+
+/// A regular file found while reading an archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    /// Path of the file inside the archive
+    pub name: String,
+
+    /// Uncompressed size, when the archive records it up front
+    pub size: Option<u64>,
+}
+
+/// Integrity check of a zip entry's uncompressed data
+struct ZipCheck {
+    /// CRC from the local header, or None when it follows in a data descriptor
+    crc: Option<u32>,
+    zip64: bool,
+    hasher: crc32fast::Hasher,
+}
+
+/// How the data of the current entry is encoded
+enum EntryData {
+    /// Exactly `remaining` raw bytes follow, padded to a tar block when done
+    Tar { remaining: u64, padding: usize },
+
+    /// Exactly `remaining` uncompressed bytes follow
+    ZipStored { remaining: u64, check: ZipCheck },
+
+    /// A raw deflate stream follows, terminated by its own end marker
+    ZipDeflated {
+        inflater: flate2::Decompress,
+        check: ZipCheck,
+        /// Uncompressed size from the local header, unless left to a data descriptor
+        declared: Option<u64>,
+    },
+}
+
+/// Reads regular files out of a tar or zip archive as it streams in
+///
+/// Entries are visited in the order they appear; their data must be read with
+/// [`ArchiveReader::read_chunk`] before moving to the next one, or it is skipped.
+/// Directories, links and other special entries are skipped. Zip archives are read
+/// through their local headers, so stored entries must record their size up front;
+/// deflated entries may use data descriptors.
+///
+/// The size of each file and the total size of all files read are limited, so a
+/// small archive cannot expand into unbounded data. Deflated entries may also not
+/// inflate past the size their header declares.
+pub struct ArchiveReader<R> {
+    inner: R,
+    format: ArchiveFormat,
+    buffer: Vec<u8>,
+    eof: bool,
+    current: Option<EntryData>,
+    finished: bool,
+    max_entry_size: u64,
+    max_extracted_size: u64,
+    /// Bytes of the current entry read so far
+    entry_read: u64,
+    /// Bytes of all entries read so far
+    extracted: u64,
+}
+
+impl<R: AsyncRead + Unpin> ArchiveReader<R> {
+    pub fn new(inner: R, format: ArchiveFormat) -> Self {
+        Self {
+            inner,
+            format,
+            buffer: Vec::new(),
+            eof: false,
+            current: None,
+            finished: false,
+            max_entry_size: DEFAULT_MAX_ENTRY_SIZE,
+            max_extracted_size: DEFAULT_MAX_EXTRACTED_SIZE,
+            entry_read: 0,
+            extracted: 0,
+        }
+    }
+
+    /// Set the largest file that may be read out of the archive
+    pub fn with_max_entry_size(mut self, max_entry_size: u64) -> Self {
+        self.max_entry_size = max_entry_size;
+        self
+    }
+
+    /// Set how much data may be read out of the archive in total
+    pub fn with_max_extracted_size(mut self, max_extracted_size: u64) -> Self {
+        self.max_extracted_size = max_extracted_size;
+        self
+    }
+
+    /// Advance to the next regular file, returning None at the end of the archive
+    pub async fn next_entry(&mut self) -> io::Result<Option<ArchiveEntry>> {
+        // Skip whatever the caller left unread of the previous entry
+        while self.read_chunk().await?.is_some() {}
+        if let Some(EntryData::Tar { padding, .. }) = self.current.take() {
+            self.consume(padding).await?;
+        }
+
+        if self.finished {
+            return Ok(None);
+        }
+        let entry = match self.format {
+            ArchiveFormat::Tar => self.next_tar_entry().await?,
+            ArchiveFormat::Zip => self.next_zip_entry().await?,
+        };
+        self.entry_read = 0;
+        match &entry {
+            // Entries of a known size are refused before any of their data is read
+            Some(ArchiveEntry {
+                size: Some(size), ..
+            }) => self.check_limits(*size, *size)?,
+            Some(_) => {}
+            None => self.finished = true,
+        }
+        Ok(entry)
+    }
+
+    /// Check that `entry_size` bytes of the current entry, of which `unread` are
+    /// not yet counted, stay within the limits
+    fn check_limits(&self, entry_size: u64, unread: u64) -> io::Result<()> {
+        if entry_size > self.max_entry_size {
+            return Err(invalid(&format!(
+                "Archive entry is larger than the {} byte limit",
+                self.max_entry_size
+            )));
+        }
+        if self.extracted.saturating_add(unread) > self.max_extracted_size {
+            return Err(invalid(&format!(
+                "Archive expands past the {} byte limit",
+                self.max_extracted_size
+            )));
+        }
+        Ok(())
+    }
+
+    /// Read the next chunk of the current entry's data, or None once it is exhausted
+    pub async fn read_chunk(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let Some(mut data) = self.current.take() else {
+            return Ok(None);
+        };
+
+        let chunk = match &mut data {
+            EntryData::Tar { remaining, .. } => {
+                if *remaining == 0 {
+                    // Keep the entry so its padding is skipped by next_entry
+                    self.current = Some(data);
+                    return Ok(None);
+                }
+                let chunk = self.take_bytes(*remaining).await?;
+                *remaining -= chunk.len() as u64;
+                Some(chunk)
+            }
+            EntryData::ZipStored { remaining, check } => {
+                if *remaining == 0 {
+                    self.verify_zip_entry(check).await?;
+                    return Ok(None);
+                }
+                let chunk = self.take_bytes(*remaining).await?;
+                *remaining -= chunk.len() as u64;
+                check.hasher.update(&chunk);
+                Some(chunk)
+            }
+            EntryData::ZipDeflated { .. } => self.inflate_chunk(&mut data).await?,
+        };
+
+        if let Some(chunk) = &chunk {
+            let len = chunk.len() as u64;
+            self.check_limits(self.entry_read + len, len)?;
+            self.entry_read += len;
+            self.extracted += len;
+            self.current = Some(data);
+        }
+        Ok(chunk)
+    }
+
+    /// Make sure at least `len` bytes are buffered, returning false at end of input
+    async fn fill(&mut self, len: usize) -> io::Result<bool> {
+        while self.buffer.len() < len && !self.eof {
+            let mut chunk = vec![0u8; COPY_BUFFER];
+            let n = self.inner.read(&mut chunk).await?;
+            if n == 0 {
+                self.eof = true;
+            }
+            self.buffer.extend_from_slice(&chunk[..n]);
+        }
+        Ok(self.buffer.len() >= len)
+    }
+
+    /// Take exactly `len` bytes from the input
+    async fn take_exact(&mut self, len: usize) -> io::Result<Vec<u8>> {
+        if !self.fill(len).await? {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Archive ended unexpectedly",
+            ));
+        }
+        Ok(self.buffer.drain(..len).collect())
+    }
+
+    /// Take up to `limit` bytes from the input, at least one unless it has ended
+    async fn take_bytes(&mut self, limit: u64) -> io::Result<Vec<u8>> {
+        if !self.fill(1).await? {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Archive ended unexpectedly",
+            ));
+        }
+        let len = (self.buffer.len() as u64).min(limit) as usize;
+        Ok(self.buffer.drain(..len).collect())
+    }
+
+    async fn consume(&mut self, mut len: usize) -> io::Result<()> {
+        while len > 0 {
+            len -= self.take_bytes(len as u64).await?.len();
+        }
+        Ok(())
+    }
+
+    async fn next_tar_entry(&mut self) -> io::Result<Option<ArchiveEntry>> {
+        let mut long_name: Option<String> = None;
+        let mut pax_path: Option<String> = None;
+        let mut pax_size: Option<u64> = None;
+
+        loop {
+            if !self.fill(TAR_BLOCK).await? {
+                // Tolerate archives that end without the trailer blocks
+                return Ok(None);
+            }
+            let header = self.take_exact(TAR_BLOCK).await?;
+            if header.iter().all(|b| *b == 0) {
+                return Ok(None);
+            }
+            verify_tar_checksum(&header)?;
+
+            let size = pax_size
+                .take()
+                .map_or_else(|| parse_tar_size(&header), Ok)?;
+            let padding = tar_padding(size);
+            let kind = header[156];
+
+            match kind {
+                b'L' | b'x' | b'g' => {
+                    let data = self.take_exact(size as usize).await?;
+                    self.consume(padding).await?;
+                    match kind {
+                        b'L' => {
+                            let end = data.iter().position(|b| *b == 0).unwrap_or(data.len());
+                            long_name = Some(String::from_utf8_lossy(&data[..end]).into_owned());
+                        }
+                        b'x' => {
+                            for (key, value) in parse_pax_records(&data) {
+                                match key.as_str() {
+                                    "path" => pax_path = Some(value),
+                                    "size" => pax_size = value.parse().ok(),
+                                    _ => {}
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                b'0' | 0 | b'7' => {
+                    let name = pax_path
+                        .take()
+                        .or_else(|| long_name.take())
+                        .unwrap_or_else(|| tar_header_name(&header));
+                    self.current = Some(EntryData::Tar {
+                        remaining: size,
+                        padding,
+                    });
+                    return Ok(Some(ArchiveEntry {
+                        name,
+                        size: Some(size),
+                    }));
+                }
+                _ => {
+                    // Directories, links and devices carry no file data worth storing
+                    pax_path = None;
+                    long_name = None;
+                    let skip = size as usize + padding;
+                    self.consume(skip).await?;
+                }
+            }
+        }
+    }
+
+    async fn next_zip_entry(&mut self) -> io::Result<Option<ArchiveEntry>> {
+        loop {
+            if !self.fill(4).await? {
+                return Ok(None);
+            }
+            let signature = read_u32(&self.buffer, 0);
+            if signature != ZIP_LOCAL_HEADER_SIG {
+                // The central directory follows the last entry
+                if signature == ZIP_CENTRAL_HEADER_SIG || signature == ZIP_END_OF_CENTRAL_DIR_SIG {
+                    return Ok(None);
+                }
+                return Err(invalid("Not a zip archive"));
+            }
+
+            let header = self.take_exact(30).await?;
+            let flags = read_u16(&header, 6);
+            let method = read_u16(&header, 8);
+            let crc = read_u32(&header, 14);
+            let mut compressed = read_u32(&header, 18) as u64;
+            let mut size = read_u32(&header, 22) as u64;
+            let name_len = read_u16(&header, 26) as usize;
+            let extra_len = read_u16(&header, 28) as usize;
+            let name = String::from_utf8_lossy(&self.take_exact(name_len).await?).into_owned();
+            let extra = self.take_exact(extra_len).await?;
+
+            let zip64 = zip64_sizes(&extra);
+            if let Some((zip64_size, zip64_compressed)) = zip64 {
+                size = zip64_size;
+                compressed = zip64_compressed;
+            }
+
+            if flags & 1 != 0 {
+                return Err(invalid("Encrypted zip entries are not supported"));
+            }
+            let descriptor = flags & (1 << 3) != 0;
+            let check = ZipCheck {
+                crc: (!descriptor).then_some(crc),
+                zip64: zip64.is_some(),
+                hasher: crc32fast::Hasher::new(),
+            };
+
+            let data = match method {
+                0 => {
+                    // Stored entries have no end marker, so their size must be known.
+                    // An empty entry is recognised by its descriptor following directly.
+                    if descriptor
+                        && compressed == 0
+                        && (!self.fill(4).await?
+                            || read_u32(&self.buffer, 0) != ZIP_DATA_DESCRIPTOR_SIG)
+                    {
+                        return Err(invalid(
+                            "Stored zip entries without sizes cannot be streamed; deflate them instead",
+                        ));
+                    }
+                    EntryData::ZipStored {
+                        remaining: compressed,
+                        check,
+                    }
+                }
+                8 => EntryData::ZipDeflated {
+                    inflater: flate2::Decompress::new(false),
+                    check,
+                    declared: (!descriptor || size != 0).then_some(size),
+                },
+                other => {
+                    return Err(invalid(&format!(
+                        "Unsupported zip compression method {}",
+                        other
+                    )));
+                }
+            };
+            self.current = Some(data);
+
+            if name.ends_with('/') {
+                // Directory entries have no data worth storing
+                while self.read_chunk().await?.is_some() {}
+                continue;
+            }
+
+            return Ok(Some(ArchiveEntry {
+                name,
+                // Deflated entries with a data descriptor may leave the size unset
+                size: (method == 0 || !descriptor || size != 0).then_some(size),
+            }));
+        }
+    }
+
+    /// Check a finished zip entry against its CRC, reading the data descriptor if any
+    async fn verify_zip_entry(&mut self, check: &ZipCheck) -> io::Result<()> {
+        let expected = match check.crc {
+            Some(crc) => crc,
+            None => {
+                if self.fill(4).await? && read_u32(&self.buffer, 0) == ZIP_DATA_DESCRIPTOR_SIG {
+                    self.consume(4).await?;
+                }
+                let fields = self.take_exact(if check.zip64 { 20 } else { 12 }).await?;
+                read_u32(&fields, 0)
+            }
+        };
+        if check.hasher.clone().finalize() != expected {
+            return Err(invalid("Zip entry failed its CRC check"));
+        }
+        Ok(())
+    }
+
+    /// Inflate the next chunk of a deflated zip entry
+    async fn inflate_chunk(&mut self, data: &mut EntryData) -> io::Result<Option<Vec<u8>>> {
+        let EntryData::ZipDeflated {
+            inflater,
+            check,
+            declared,
+        } = data
+        else {
+            return Ok(None);
+        };
+
+        let mut out = vec![0u8; COPY_BUFFER];
+        loop {
+            if self.buffer.is_empty() && !self.fill(1).await? {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "Archive ended inside a deflated entry",
+                ));
+            }
+
+            let in_before = inflater.total_in();
+            let out_before = inflater.total_out();
+            let status = inflater
+                .decompress(&self.buffer, &mut out, flate2::FlushDecompress::None)
+                .map_err(|e| invalid(&format!("Corrupt deflate data: {}", e)))?;
+            let consumed = (inflater.total_in() - in_before) as usize;
+            let produced = (inflater.total_out() - out_before) as usize;
+            self.buffer.drain(..consumed);
+            check.hasher.update(&out[..produced]);
+
+            if declared.is_some_and(|declared| inflater.total_out() > declared) {
+                return Err(invalid("Zip entry inflates past its declared size"));
+            }
+            if produced > 0 {
+                // At the end of the stream the next call finds nothing more to inflate
+                out.truncate(produced);
+                return Ok(Some(out));
+            }
+            if status == flate2::Status::StreamEnd {
+                self.verify_zip_entry(check).await?;
+                return Ok(None);
+            }
+            if consumed == 0 && !self.fill(self.buffer.len() + 1).await? {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "Archive ended inside a deflated entry",
+                ));
+            }
+        }
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+/// Uncompressed and compressed sizes from a zip64 extended information field
+fn zip64_sizes(extra: &[u8]) -> Option<(u64, u64)> {
+    let mut offset = 0;
+    while offset + 4 <= extra.len() {
+        let id = read_u16(extra, offset);
+        let len = read_u16(extra, offset + 2) as usize;
+        let field = extra.get(offset + 4..offset + 4 + len)?;
+        if id == 0x0001 && field.len() >= 16 {
+            let size = u64::from_le_bytes(field[..8].try_into().unwrap());
+            let compressed = u64::from_le_bytes(field[8..16].try_into().unwrap());
+            return Some((size, compressed));
+        }
+        offset += 4 + len;
+    }
+    None
+}
+
+/// Read a NUL- or space-terminated octal number, or a base-256 number
+fn parse_tar_number(field: &[u8]) -> io::Result<u64> {
+    if field[0] & 0x80 != 0 {
+        return Ok(field[field.len() - 8..]
+            .iter()
+            .fold(0u64, |acc, b| (acc << 8) | *b as u64));
+    }
+    let text = std::str::from_utf8(field).map_err(|_| invalid("Corrupt tar header"))?;
+    let text = text.trim_matches(|c: char| c == '\0' || c == ' ');
+    if text.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(text, 8).map_err(|_| invalid("Corrupt tar header"))
+}
+
+fn parse_tar_size(header: &[u8]) -> io::Result<u64> {
+    parse_tar_number(&header[124..136])
+}
+
+fn verify_tar_checksum(header: &[u8]) -> io::Result<()> {
+    let stored = parse_tar_number(&header[148..156])?;
+    let actual: u64 = header
+        .iter()
+        .enumerate()
+        .map(|(i, b)| {
+            if (148..156).contains(&i) {
+                b' ' as u64
+            } else {
+                *b as u64
+            }
+        })
+        .sum();
+    if stored != actual {
+        return Err(invalid("Tar header checksum mismatch"));
+    }
+    Ok(())
+}
+
+/// Full path of a ustar header, joining the prefix and name fields
+fn tar_header_name(header: &[u8]) -> String {
+    let field = |range: std::ops::Range<usize>| {
+        let bytes = &header[range];
+        let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+        String::from_utf8_lossy(&bytes[..end]).into_owned()
+    };
+    let name = field(0..100);
+    let prefix = if &header[257..262] == b"ustar" {
+        field(345..500)
+    } else {
+        String::new()
+    };
+    if prefix.is_empty() {
+        name
+    } else {
+        format!("{}/{}", prefix, name)
+    }
+}
+
+/// Parse pax extended header records of the form "<len> <key>=<value>\n"
+fn parse_pax_records(data: &[u8]) -> Vec<(String, String)> {
+    let mut records = Vec::new();
+    let mut rest = data;
+    while let Some(space) = rest.iter().position(|b| *b == b' ') {
+        let Some(len) = std::str::from_utf8(&rest[..space])
+            .ok()
+            .and_then(|len| len.parse::<usize>().ok())
+        else {
+            break;
+        };
+        if len <= space || len > rest.len() {
+            break;
+        }
+        let record = String::from_utf8_lossy(&rest[space + 1..len]);
+        if let Some((key, value)) = record.trim_end_matches('\n').split_once('=') {
+            records.push((key.to_string(), value.to_string()));
+        }
+        rest = &rest[len..];
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    async fn build(format: ArchiveFormat, entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let modified = Utc.with_ymd_and_hms(2024, 5, 17, 12, 30, 10).unwrap();
+        let mut writer = ArchiveWriter::new(Vec::new(), format);
+        for (name, data) in entries {
+            writer
+                .append(name, data.len() as u64, modified, *data)
+                .await
+                .unwrap();
+        }
+        writer.finish().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_tar_layout() {
+        let long_name = format!("{}/file.txt", "d".repeat(120));
+        let archive = build(
+            ArchiveFormat::Tar,
+            &[("a.txt", b"hello"), (&long_name, b"world!")],
+        )
+        .await;
+
+        // Header, one data block, header, one data block, two trailer blocks
+        assert_eq!(archive.len(), 6 * TAR_BLOCK);
+        assert_eq!(&archive[..5], b"a.txt");
+        assert_eq!(&archive[257..262], b"ustar");
+        assert_eq!(&archive[124..135], b"00000000005");
+        assert_eq!(&archive[TAR_BLOCK..TAR_BLOCK + 5], b"hello");
+
+        // The long name is split between the prefix and name fields
+        let second = &archive[2 * TAR_BLOCK..3 * TAR_BLOCK];
+        assert_eq!(&second[..8], b"file.txt");
+        assert_eq!(&second[345..465], "d".repeat(120).as_bytes());
+
+        // Stored checksum matches the header contents
+        let mut copy = [0u8; TAR_BLOCK];
+        copy.copy_from_slice(&archive[..TAR_BLOCK]);
+        copy[148..156].copy_from_slice(b"        ");
+        let expected: u32 = copy.iter().map(|b| *b as u32).sum();
+        let stored = std::str::from_utf8(&archive[148..154]).unwrap();
+        assert_eq!(u32::from_str_radix(stored, 8).unwrap(), expected);
+        assert!(archive[4 * TAR_BLOCK..].iter().all(|b| *b == 0));
+    }
+
+    #[tokio::test]
+    async fn test_zip_layout() {
+        let archive = build(ArchiveFormat::Zip, &[("a.txt", b"hello"), ("b/c.txt", b"")]).await;
+
+        assert_eq!(&archive[..4], &ZIP_LOCAL_HEADER_SIG.to_le_bytes());
+        let end = archive.len() - 22;
+        assert_eq!(
+            &archive[end..end + 4],
+            &ZIP_END_OF_CENTRAL_DIR_SIG.to_le_bytes()
+        );
+        assert_eq!(
+            u16::from_le_bytes([archive[end + 10], archive[end + 11]]),
+            2
+        );
+
+        let directory_offset =
+            u32::from_le_bytes(archive[end + 16..end + 20].try_into().unwrap()) as usize;
+        assert_eq!(
+            &archive[directory_offset..directory_offset + 4],
+            &ZIP_CENTRAL_HEADER_SIG.to_le_bytes()
+        );
+
+        // The central directory records the CRC of the data
+        let crc = u32::from_le_bytes(
+            archive[directory_offset + 16..directory_offset + 20]
+                .try_into()
+                .unwrap(),
+        );
+        assert_eq!(crc, crc32fast::hash(b"hello"));
+    }
+
+    #[tokio::test]
+    async fn test_short_entry_is_an_error() {
+        let mut writer = ArchiveWriter::new(Vec::new(), ArchiveFormat::Tar);
+        let result = writer.append("a", 10, Utc::now(), &b"short"[..]).await;
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    async fn read_all(format: ArchiveFormat, archive: &[u8]) -> Vec<(String, Vec<u8>)> {
+        let mut reader = ArchiveReader::new(archive, format);
+        let mut entries = Vec::new();
+        while let Some(entry) = reader.next_entry().await.unwrap() {
+            let mut data = Vec::new();
+            while let Some(chunk) = reader.read_chunk().await.unwrap() {
+                data.extend_from_slice(&chunk);
+            }
+            entries.push((entry.name, data));
+        }
+        entries
+    }
+
+    #[tokio::test]
+    async fn test_read_back_written_archives() {
+        let long_name = format!("{}/{}", "d".repeat(200), "f".repeat(120));
+        let big: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        let entries: Vec<(&str, &[u8])> = vec![
+            ("a.txt", b"hello"),
+            ("empty", b""),
+            (&long_name, b"long"),
+            ("nested/big.bin", &big),
+        ];
+
+        for format in [ArchiveFormat::Tar, ArchiveFormat::Zip] {
+            let archive = build(format, &entries).await;
+            let read = read_all(format, &archive).await;
+            assert_eq!(read.len(), entries.len());
+            for ((name, data), (expected_name, expected_data)) in read.iter().zip(&entries) {
+                assert_eq!(name, expected_name);
+                assert_eq!(data.as_slice(), *expected_data);
+            }
+        }
+    }
+
+    /// A zip archive of one deflated entry, with its sizes and CRC in a data descriptor
+    fn deflated_zip(data: &[u8]) -> Vec<u8> {
+        let mut compressor = flate2::Compress::new(flate2::Compression::default(), false);
+        let mut compressed = Vec::with_capacity(data.len());
+        compressor
+            .compress_vec(data, &mut compressed, flate2::FlushCompress::Finish)
+            .unwrap();
+
+        // Local header with sizes and CRC deferred to a data descriptor
+        let mut archive = Vec::new();
+        archive.extend_from_slice(&ZIP_LOCAL_HEADER_SIG.to_le_bytes());
+        archive.extend_from_slice(&ZIP_VERSION.to_le_bytes());
+        archive.extend_from_slice(&ZIP_FLAGS.to_le_bytes());
+        archive.extend_from_slice(&8u16.to_le_bytes());
+        archive.extend_from_slice(&[0u8; 16]);
+        archive.extend_from_slice(&5u16.to_le_bytes());
+        archive.extend_from_slice(&0u16.to_le_bytes());
+        archive.extend_from_slice(b"a.txt");
+        archive.extend_from_slice(&compressed);
+        archive.extend_from_slice(&ZIP_DATA_DESCRIPTOR_SIG.to_le_bytes());
+        archive.extend_from_slice(&crc32fast::hash(data).to_le_bytes());
+        archive.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+        archive.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        archive.extend_from_slice(&ZIP_END_OF_CENTRAL_DIR_SIG.to_le_bytes());
+        archive
+    }
+
+    /// Read the first entry of an archive to its end or first error
+    async fn read_first(mut reader: ArchiveReader<&[u8]>) -> io::Result<()> {
+        reader.next_entry().await?;
+        while reader.read_chunk().await?.is_some() {}
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_deflated_zip_entry() {
+        let data = b"compressible ".repeat(1000);
+        let mut archive = deflated_zip(&data);
+
+        let read = read_all(ArchiveFormat::Zip, &archive).await;
+        assert_eq!(read, vec![("a.txt".to_string(), data.clone())]);
+
+        // A corrupted CRC is detected
+        let crc_offset = archive.len() - 4 - 12;
+        archive[crc_offset] ^= 0xff;
+        let reader = ArchiveReader::new(archive.as_slice(), ArchiveFormat::Zip);
+        let result = read_first(reader).await;
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn test_extracted_size_is_limited() {
+        let data = vec![0u8; 1_000_000];
+        let archive = deflated_zip(&data);
+        assert!(archive.len() < 10_000);
+
+        // Deflated entries are stopped as soon as they inflate past a limit
+        let reader =
+            ArchiveReader::new(archive.as_slice(), ArchiveFormat::Zip).with_max_entry_size(100_000);
+        assert!(read_first(reader).await.is_err());
+        let reader = ArchiveReader::new(archive.as_slice(), ArchiveFormat::Zip)
+            .with_max_extracted_size(100_000);
+        assert!(read_first(reader).await.is_err());
+
+        // ...or past the size their header declares
+        let mut declared = archive.clone();
+        declared[22..26].copy_from_slice(&1000u32.to_le_bytes());
+        let reader = ArchiveReader::new(declared.as_slice(), ArchiveFormat::Zip);
+        let result = read_first(reader).await;
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        // Entries of a known size are refused up front, counting earlier entries
+        let tar = build(ArchiveFormat::Tar, &[("a", b"12345"), ("b", b"12345")]).await;
+        let mut reader =
+            ArchiveReader::new(tar.as_slice(), ArchiveFormat::Tar).with_max_extracted_size(8);
+        reader.next_entry().await.unwrap().unwrap();
+        assert!(reader.next_entry().await.is_err());
+        let mut reader =
+            ArchiveReader::new(tar.as_slice(), ArchiveFormat::Tar).with_max_entry_size(4);
+        assert!(reader.next_entry().await.is_err());
+    }
+}
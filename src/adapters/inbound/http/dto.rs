@@ -1,15 +1,30 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
+use crate::domain::{
+    errors::{LifecycleError, StorageError, ValidationError},
+    models::{
+        ApplicableAction, BackupJob, BackupMode, BackupRestore, BackupRun, BackupRunState,
+        BlockSignature, BulkDeleteVersionsResult, DatasetEntry, DatasetManifest, DeltaInstruction,
+        Filter, IntegrityManifest, LifecycleAction, LifecycleConfiguration,
+        LifecycleConfigurationDiff, LifecycleRule, LifecycleStorageClass, ObjectDelta,
+        ObjectSignature, ObjectTransaction, PrefixRewrite, PrefixUsage, PresignedMultipartUpload,
+        RuleChange, RuleStatus, StagedObject, UploadedPart, VersionSelection,
+        VersioningConfiguration,
+    },
+    value_objects::{BucketName, ObjectKey, VersionId},
+};
+#[cfg(not(target_arch = "wasm32"))]
 use crate::{
-    adapters::outbound::storage,
-    domain::{
-        errors::{LifecycleError, StorageError, ValidationError},
-        models::{
-            Filter, LifecycleConfiguration, LifecycleRule, LifecycleStorageClass, RuleStatus,
+    adapters::inbound::http::archive::ArchiveFormat,
+    ports::{
+        services::{
+            BucketLifecycleResults, LifecycleSimulationResults, ProcessingStatus, ValidationResult,
         },
-        value_objects::{BucketName, ObjectKey},
+        storage::CompletedPart,
     },
 };
 
@@ -42,7 +57,7 @@ pub struct ListObjectsDto {
 }
 
 /// DTO for object list response
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ListObjectsResponseDto {
     pub objects: Vec<ObjectInfoDto>,
     pub is_truncated: bool,
@@ -50,6 +65,29 @@ pub struct ListObjectsResponseDto {
     pub total_count: usize,
 }
 
+/// DTO for searching objects by text, prefix, tags and size
+///
+/// `q` is a free-text query whose terms must all occur in the key or a
+/// metadata value. `tags` holds comma-separated `name:value` pairs, all of
+/// which must match.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SearchObjectsDto {
+    pub q: Option<String>,
+    pub prefix: Option<String>,
+    pub tags: Option<String>,
+    pub size_greater_than: Option<u64>,
+    pub size_less_than: Option<u64>,
+    pub max_results: Option<usize>,
+}
+
+/// DTO for object search response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchObjectsResponseDto {
+    pub keys: Vec<String>,
+    pub is_truncated: bool,
+    pub total_count: usize,
+}
+
 /// DTO for lifecycle rule
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LifecycleRuleDto {
@@ -66,101 +104,1067 @@ pub struct LifecycleRuleDto {
     // Delete marker expiration
     pub del_marker_expiration_days: Option<u32>,
 
-    // All versions expiration
-    pub all_versions_expiration_days: Option<u32>,
-    pub all_versions_expiration_delete_marker: Option<bool>,
+    // All versions expiration
+    pub all_versions_expiration_days: Option<u32>,
+    pub all_versions_expiration_delete_marker: Option<bool>,
+
+    // Transition settings
+    pub transition_days: Option<u32>,
+    pub transition_date: Option<DateTime<Utc>>,
+    pub transition_storage_class: Option<String>,
+
+    // Non-current version expiration
+    pub noncurrent_version_expiration_noncurrent_days: Option<u32>,
+    pub noncurrent_version_expiration_newer_versions: Option<u32>,
+
+    // Non-current version transition
+    pub noncurrent_version_transition_noncurrent_days: Option<u32>,
+    pub noncurrent_version_transition_storage_class: Option<String>,
+    pub noncurrent_version_transition_newer_versions: Option<u32>,
+
+    // Abort incomplete multipart uploads
+    pub abort_incomplete_multipart_upload_days_after_initiation: Option<u32>,
+}
+
+/// DTO for lifecycle rule filter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilterDto {
+    pub prefix: Option<String>,
+    pub tags: Option<HashMap<String, String>>,
+    pub object_size_greater_than: Option<u64>,
+    pub object_size_less_than: Option<u64>,
+}
+
+/// DTO for lifecycle configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LifecycleConfigurationDto {
+    pub bucket: String,
+    pub rules: Vec<LifecycleRuleDto>,
+}
+
+/// DTO for applying a lifecycle rule template to a bucket
+///
+/// Both fields are optional: the prefix defaults to the template's own prefix
+/// and the rule ID to the template name.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApplyLifecycleTemplateDto {
+    pub prefix: Option<String>,
+    pub rule_id: Option<String>,
+}
+
+/// DTO describing an available lifecycle rule template
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LifecycleTemplateDto {
+    pub name: String,
+    pub description: String,
+    pub rule: LifecycleRuleDto,
+}
+
+/// DTO for scheduling (or clearing) the deletion of a single object
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleDeletionDto {
+    pub delete_at: Option<DateTime<Utc>>,
+}
+
+/// DTO for requesting a presigned URL of an object
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresignRequestDto {
+    /// `GET`, `PUT` or `DELETE`
+    pub method: String,
+    /// Seconds the URL stays valid, an hour if not given
+    pub expires_in_secs: Option<u64>,
+}
+
+/// Response DTO for a presigned URL
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresignedUrlResponseDto {
+    pub url: String,
+    pub method: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Query parameters of a usage report
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UsageQueryDto {
+    /// Report this prefix instead of every prefix with a quota
+    pub prefix: Option<String>,
+}
+
+/// Objects stored under a prefix, with the limits configured for it
+///
+/// `soft_limit_reached` is set once writes are warned about and
+/// `hard_limit_reached` once they would be refused.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrefixUsageDto {
+    pub prefix: String,
+    pub object_count: u64,
+    pub total_bytes: u64,
+    pub max_objects: Option<u64>,
+    pub max_bytes: Option<u64>,
+    pub soft_limit_percent: Option<u8>,
+    pub soft_limit_reached: bool,
+    pub hard_limit_reached: bool,
+}
+
+impl From<PrefixUsage> for PrefixUsageDto {
+    fn from(usage: PrefixUsage) -> Self {
+        let quota = usage.quota.clone().unwrap_or_default();
+        Self {
+            soft_limit_reached: quota.soft_limit_reached_by(&usage).is_some(),
+            hard_limit_reached: quota.limit_reached_by(&usage),
+            prefix: usage.prefix,
+            object_count: usage.object_count,
+            total_bytes: usage.total_bytes,
+            max_objects: quota.max_objects,
+            max_bytes: quota.max_bytes,
+            soft_limit_percent: quota.soft_limit_percent,
+        }
+    }
+}
+
+/// Response DTO for a usage report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageResponseDto {
+    pub prefixes: Vec<PrefixUsageDto>,
+}
+
+/// An object staged in a transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StagedObjectDto {
+    pub key: String,
+    pub size: u64,
+    pub etag: String,
+    pub content_type: Option<String>,
+}
+
+impl From<StagedObject> for StagedObjectDto {
+    fn from(object: StagedObject) -> Self {
+        Self {
+            key: object.key.to_string(),
+            size: object.size,
+            etag: object.etag,
+            content_type: object.content_type,
+        }
+    }
+}
+
+/// Response DTO for a multi-object transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionDto {
+    pub transaction_id: String,
+    pub started: DateTime<Utc>,
+    pub committed: bool,
+    pub objects: Vec<StagedObjectDto>,
+}
+
+impl From<ObjectTransaction> for TransactionDto {
+    fn from(transaction: ObjectTransaction) -> Self {
+        Self {
+            transaction_id: transaction.transaction_id,
+            started: transaction.started,
+            committed: transaction.committed_at.is_some(),
+            objects: transaction.objects.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// DTO for publishing a dataset manifest
+#[derive(Debug, Clone, Deserialize)]
+pub struct PublishDatasetDto {
+    pub objects: Vec<DatasetObjectRefDto>,
+}
+
+/// An object of a dataset to publish, at its latest version unless one is given
+#[derive(Debug, Clone, Deserialize)]
+pub struct DatasetObjectRefDto {
+    pub key: String,
+    pub version_id: Option<String>,
+}
+
+/// Query parameters for reading a dataset
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DatasetQueryDto {
+    /// Read the manifest published as this version instead of the latest
+    pub version: Option<String>,
+}
+
+/// Response DTO for a dataset manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatasetDto {
+    pub name: String,
+    pub manifest_version: String,
+    pub published: DateTime<Utc>,
+    pub objects: Vec<DatasetObjectDto>,
+}
+
+/// An object version listed by a dataset manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatasetObjectDto {
+    pub key: String,
+    pub version_id: String,
+    pub size: Option<u64>,
+    pub etag: Option<String>,
+}
+
+impl From<DatasetManifest> for DatasetDto {
+    fn from(manifest: DatasetManifest) -> Self {
+        Self {
+            name: manifest.name,
+            manifest_version: manifest.manifest_version.to_string(),
+            published: manifest.published,
+            objects: manifest.entries.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<DatasetEntry> for DatasetObjectDto {
+    fn from(entry: DatasetEntry) -> Self {
+        Self {
+            key: entry.key.to_string(),
+            version_id: entry.version_id.to_string(),
+            size: entry.size,
+            etag: entry.etag,
+        }
+    }
+}
+
+/// Query parameters of an object upload
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UploadObjectQueryDto {
+    /// Expand the uploaded archive into one object per entry under the key
+    pub extract: Option<ArchiveFormat>,
+}
+
+/// DTO for requesting a bundle download of several objects
+///
+/// Either `keys` or `prefix` selects the objects; with neither, the whole bucket
+/// is archived.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateArchiveDto {
+    pub keys: Option<Vec<String>>,
+    pub prefix: Option<String>,
+    #[serde(default)]
+    pub format: ArchiveFormat,
+}
+
+/// DTO for starting a master key rotation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StartKeyRotationDto {
+    pub key_id: String,
+}
+
+/// DTO for exporting the current objects of a bucket to an external
+/// S3-compatible bucket
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExportBucketDto {
+    pub target: ExportTargetDto,
+    /// Export every object again instead of resuming from the target's checkpoint
+    #[serde(default)]
+    pub restart: bool,
+}
+
+/// External bucket an export writes to
+///
+/// The credentials are required: the server's own AWS environment is never
+/// used for exports.
+#[derive(Clone, Deserialize)]
+pub struct ExportTargetDto {
+    pub bucket: String,
+    pub region: Option<String>,
+    pub endpoint: Option<String>,
+    pub access_key: String,
+    pub secret_key: String,
+    /// Prefix put in front of the exported keys
+    #[serde(default)]
+    pub prefix: String,
+}
+
+impl std::fmt::Debug for ExportTargetDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExportTargetDto")
+            .field("bucket", &self.bucket)
+            .field("region", &self.region)
+            .field("endpoint", &self.endpoint)
+            .field("access_key", &self.access_key)
+            .field("secret_key", &"<redacted>")
+            .field("prefix", &self.prefix)
+            .finish()
+    }
+}
+
+/// Progress of a bucket export, streamed while it runs and kept in the target
+/// as the checkpoint it resumes from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportCheckpointDto {
+    pub bucket: String,
+    /// Last key exported; keys are exported in order, so a resumed export
+    /// continues after it
+    pub last_key: Option<String>,
+    pub objects_exported: u64,
+    pub bytes_exported: u64,
+    pub completed: bool,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub error: Option<String>,
+}
+
+/// DTO for creating a recurring backup job
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateBackupJobDto {
+    pub bucket: String,
+    /// Cron expression with a leading seconds field, e.g. `0 0 3 * * *`
+    pub schedule: String,
+    #[serde(default)]
+    pub mode: BackupModeDto,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupModeDto {
+    Full,
+    #[default]
+    Incremental,
+}
+
+impl From<BackupModeDto> for BackupMode {
+    fn from(mode: BackupModeDto) -> Self {
+        match mode {
+            BackupModeDto::Full => BackupMode::Full,
+            BackupModeDto::Incremental => BackupMode::Incremental,
+        }
+    }
+}
+
+impl From<BackupMode> for BackupModeDto {
+    fn from(mode: BackupMode) -> Self {
+        match mode {
+            BackupMode::Full => BackupModeDto::Full,
+            BackupMode::Incremental => BackupModeDto::Incremental,
+        }
+    }
+}
+
+/// Response DTO for a backup job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupJobDto {
+    pub job_id: String,
+    pub bucket: String,
+    pub schedule: String,
+    pub mode: BackupModeDto,
+    pub created: DateTime<Utc>,
+    pub last_run: Option<DateTime<Utc>>,
+}
+
+impl From<BackupJob> for BackupJobDto {
+    fn from(job: BackupJob) -> Self {
+        Self {
+            job_id: job.job_id,
+            bucket: job.bucket.to_string(),
+            schedule: job.schedule,
+            mode: job.mode.into(),
+            created: job.created,
+            last_run: job.last_run,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupRunStateDto {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+/// Response DTO for a run of a backup job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupRunDto {
+    pub run_id: String,
+    pub job_id: String,
+    pub mode: BackupModeDto,
+    pub state: BackupRunStateDto,
+    pub started: DateTime<Utc>,
+    pub finished: Option<DateTime<Utc>>,
+    pub objects_total: u64,
+    pub objects_copied: u64,
+    pub bytes_copied: u64,
+    pub error: Option<String>,
+}
+
+impl From<BackupRun> for BackupRunDto {
+    fn from(run: BackupRun) -> Self {
+        Self {
+            run_id: run.run_id,
+            job_id: run.job_id,
+            mode: run.mode.into(),
+            state: match run.state {
+                BackupRunState::Running => BackupRunStateDto::Running,
+                BackupRunState::Succeeded => BackupRunStateDto::Succeeded,
+                BackupRunState::Failed => BackupRunStateDto::Failed,
+            },
+            started: run.started,
+            finished: run.finished,
+            objects_total: run.objects_total,
+            objects_copied: run.objects_copied,
+            bytes_copied: run.bytes_copied,
+            error: run.error,
+        }
+    }
+}
+
+/// DTO for restoring a backup run, into its own bucket unless one is given
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RestoreBackupDto {
+    pub bucket: Option<String>,
+    /// Replace objects that exist, instead of leaving them in place
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+/// Response DTO for a restore from a backup run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupRestoreDto {
+    pub objects_restored: u64,
+    pub objects_skipped: u64,
+    pub bytes_restored: u64,
+}
+
+impl From<BackupRestore> for BackupRestoreDto {
+    fn from(restore: BackupRestore) -> Self {
+        Self {
+            objects_restored: restore.objects_restored,
+            objects_skipped: restore.objects_skipped,
+            bytes_restored: restore.bytes_restored,
+        }
+    }
+}
+
+/// DTO for lifecycle evaluation request
+#[derive(Debug, Clone, Deserialize)]
+pub struct EvaluateLifecycleDto {
+    pub key: String,
+    pub object_created_at: Option<DateTime<Utc>>,
+    pub object_tags: Option<HashMap<String, String>>,
+    pub is_delete_marker: Option<bool>,
+    pub is_current_version: Option<bool>,
+    pub delete_at: Option<DateTime<Utc>>,
+}
+
+/// DTO for lifecycle evaluation response
+#[derive(Debug, Clone, Serialize)]
+pub struct LifecycleEvaluationResponseDto {
+    pub actions_to_apply: Vec<ApplicableActionDto>,
+}
+
+/// DTO for copying a bucket's lifecycle configuration to other buckets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CopyLifecycleDto {
+    pub targets: Vec<String>,
+    pub rewrite_prefix: Option<PrefixRewriteDto>,
+}
+
+/// DTO for rewriting rule prefixes when copying lifecycle rules
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrefixRewriteDto {
+    pub from: String,
+    pub to: String,
+}
+
+/// DTO for the result of validating a proposed lifecycle configuration
+#[derive(Debug, Clone, Serialize)]
+pub struct LifecycleValidationResponseDto {
+    pub is_valid: bool,
+    pub errors: Vec<ValidationIssueDto>,
+    pub warnings: Vec<ValidationWarningDto>,
+    pub diff: LifecycleDiffDto,
+}
+
+/// DTO for a lifecycle validation error
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationIssueDto {
+    pub rule_id: Option<String>,
+    pub field: String,
+    pub message: String,
+}
+
+/// DTO for a lifecycle validation warning
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationWarningDto {
+    pub rule_id: Option<String>,
+    pub message: String,
+}
+
+/// DTO for the differences between the active and a proposed lifecycle configuration
+#[derive(Debug, Clone, Serialize)]
+pub struct LifecycleDiffDto {
+    pub has_active_configuration: bool,
+    pub added: Vec<LifecycleRuleDto>,
+    pub removed: Vec<LifecycleRuleDto>,
+    pub modified: Vec<RuleChangeDto>,
+    pub unchanged: Vec<String>,
+}
+
+/// DTO for a rule whose settings would change
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleChangeDto {
+    pub rule_id: String,
+    pub changes: Vec<FieldChangeDto>,
+}
+
+/// DTO for a single changed rule setting
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldChangeDto {
+    pub field: String,
+    pub before: serde_json::Value,
+    pub after: serde_json::Value,
+}
+
+/// DTO for a lifecycle dry run over a bucket
+///
+/// Without rules, the bucket's active configuration is simulated; without
+/// `as_of`, rules are evaluated as of now.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SimulateLifecycleDto {
+    pub rules: Option<Vec<LifecycleRuleDto>>,
+    pub as_of: Option<DateTime<Utc>>,
+}
+
+/// DTO for lifecycle dry run response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LifecycleSimulationResponseDto {
+    pub bucket: String,
+    pub as_of: DateTime<Utc>,
+    pub objects_evaluated: usize,
+    pub affected_objects: Vec<SimulatedObjectDto>,
+}
+
+/// DTO for an object a lifecycle dry run would act on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulatedObjectDto {
+    pub key: String,
+    pub actions: Vec<ApplicableActionDto>,
+}
+
+/// DTO for the lifecycle processing status of a bucket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LifecycleProcessingStatusDto {
+    pub bucket: String,
+    pub is_running: bool,
+    pub cancelled: bool,
+    pub last_run: Option<DateTime<Utc>>,
+    pub next_scheduled_run: Option<DateTime<Utc>>,
+    pub last_run_results: Option<LifecycleRunResultsDto>,
+}
+
+/// DTO for the results of a lifecycle processing run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LifecycleRunResultsDto {
+    pub objects_processed: usize,
+    pub objects_affected: usize,
+    pub actions_applied: usize,
+    pub errors: Vec<LifecycleProcessingErrorDto>,
+    pub duration_ms: u64,
+}
+
+/// DTO for an object lifecycle processing failed to act on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LifecycleProcessingErrorDto {
+    pub object_key: String,
+    pub rule_id: String,
+    pub error: String,
+}
+
+/// DTO for applicable lifecycle action
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApplicableActionDto {
+    pub rule_id: String,
+    pub action_type: String,
+    pub reason: String,
+    pub details: HashMap<String, serde_json::Value>,
+}
+
+/// DTO for versioned object
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionedObjectDto {
+    pub key: String,
+    pub version_id: String,
+    pub size: u64,
+    pub last_modified: DateTime<Utc>,
+    pub etag: Option<String>,
+    pub is_latest: bool,
+    #[serde(default)]
+    pub labels: BTreeSet<String>,
+    #[serde(default)]
+    pub annotations: HashMap<String, String>,
+}
+
+/// Query parameters for the block signature of a version
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SignatureQueryDto {
+    pub block_size: Option<u64>,
+}
+
+/// Response DTO for the block signature of a version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectSignatureDto {
+    pub key: String,
+    pub version_id: String,
+    pub block_size: u64,
+    pub size: u64,
+    pub blocks: Vec<BlockSignatureDto>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockSignatureDto {
+    pub index: u64,
+    pub weak: u32,
+    pub strong: String,
+}
+
+impl From<BlockSignature> for BlockSignatureDto {
+    fn from(block: BlockSignature) -> Self {
+        Self {
+            index: block.index,
+            weak: block.weak,
+            strong: block.strong,
+        }
+    }
+}
+
+impl From<BlockSignatureDto> for BlockSignature {
+    fn from(block: BlockSignatureDto) -> Self {
+        Self {
+            index: block.index,
+            weak: block.weak,
+            strong: block.strong,
+        }
+    }
+}
+
+impl From<ObjectSignatureDto> for ObjectSignature {
+    fn from(signature: ObjectSignatureDto) -> Self {
+        Self {
+            block_size: signature.block_size,
+            size: signature.size,
+            blocks: signature.blocks.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// DTO for a delta upload: blocks copied from the base version and the
+/// base64-encoded bytes in between
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectDeltaDto {
+    pub block_size: u64,
+    pub instructions: Vec<DeltaInstructionDto>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeltaInstructionDto {
+    Copy { block: u64, checksum: String },
+    Literal(String),
+}
+
+impl ObjectDeltaDto {
+    /// The delta, with its literals decoded
+    pub fn into_delta(self) -> Result<ObjectDelta, base64::DecodeError> {
+        let instructions = self
+            .instructions
+            .into_iter()
+            .map(|instruction| {
+                Ok(match instruction {
+                    DeltaInstructionDto::Copy { block, checksum } => {
+                        DeltaInstruction::Copy { block, checksum }
+                    }
+                    DeltaInstructionDto::Literal(bytes) => {
+                        DeltaInstruction::Literal(BASE64.decode(bytes)?)
+                    }
+                })
+            })
+            .collect::<Result<_, base64::DecodeError>>()?;
+
+        Ok(ObjectDelta {
+            block_size: self.block_size,
+            instructions,
+        })
+    }
+}
+
+impl From<ObjectDelta> for ObjectDeltaDto {
+    fn from(delta: ObjectDelta) -> Self {
+        Self {
+            block_size: delta.block_size,
+            instructions: delta
+                .instructions
+                .into_iter()
+                .map(|instruction| match instruction {
+                    DeltaInstruction::Copy { block, checksum } => {
+                        DeltaInstructionDto::Copy { block, checksum }
+                    }
+                    DeltaInstruction::Literal(bytes) => {
+                        DeltaInstructionDto::Literal(BASE64.encode(bytes))
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// DTO replacing the labels and annotations of a version
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VersionLabelsDto {
+    #[serde(default)]
+    pub labels: BTreeSet<String>,
+    #[serde(default)]
+    pub annotations: HashMap<String, String>,
+}
+
+/// DTO for the versioning state of a bucket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BucketVersioningDto {
+    pub status: VersioningStatusDto,
+}
+
+/// Versioning state of a bucket, named as in S3
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VersioningStatusDto {
+    Enabled,
+    Suspended,
+}
+
+/// S3 `VersioningConfiguration` document of PutBucketVersioning and
+/// GetBucketVersioning
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename = "VersioningConfiguration")]
+pub struct S3VersioningConfigurationDto {
+    #[serde(rename = "Status")]
+    pub status: Option<VersioningStatusDto>,
+    /// `Enabled` or `Disabled`; MFA delete is not supported
+    #[serde(rename = "MfaDelete", skip_serializing_if = "Option::is_none")]
+    pub mfa_delete: Option<String>,
+}
+
+/// Subresource a request on a bucket names in its query, e.g. `?versioning`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BucketSubresourceDto {
+    pub versioning: Option<String>,
+}
+
+/// Query parameters of the marker-based ListObjects (V1) on a bucket
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ListObjectsV1QueryDto {
+    pub prefix: Option<String>,
+    pub delimiter: Option<String>,
+    /// List the keys after this one
+    pub marker: Option<String>,
+    #[serde(rename = "max-keys")]
+    pub max_keys: Option<usize>,
+    /// `url` to percent-encode the keys and prefixes in the response
+    #[serde(rename = "encoding-type")]
+    pub encoding_type: Option<String>,
+    /// `2` for ListObjectsV2
+    #[serde(rename = "list-type")]
+    pub list_type: Option<String>,
+}
+
+/// S3 `ListBucketResult` document of ListObjects (V1)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename = "ListBucketResult")]
+pub struct S3ListBucketResultDto {
+    #[serde(rename = "@xmlns")]
+    pub xmlns: String,
+    #[serde(rename = "Name")]
+    pub name: String,
+    #[serde(rename = "Prefix")]
+    pub prefix: String,
+    #[serde(rename = "Marker")]
+    pub marker: String,
+    #[serde(rename = "MaxKeys")]
+    pub max_keys: usize,
+    #[serde(rename = "Delimiter", skip_serializing_if = "Option::is_none")]
+    pub delimiter: Option<String>,
+    #[serde(rename = "EncodingType", skip_serializing_if = "Option::is_none")]
+    pub encoding_type: Option<String>,
+    #[serde(rename = "IsTruncated")]
+    pub is_truncated: bool,
+    /// Marker of the next page, only given when listing with a delimiter
+    #[serde(rename = "NextMarker", skip_serializing_if = "Option::is_none")]
+    pub next_marker: Option<String>,
+    #[serde(rename = "Contents")]
+    pub contents: Vec<S3ObjectDto>,
+    #[serde(rename = "CommonPrefixes")]
+    pub common_prefixes: Vec<S3CommonPrefixDto>,
+}
+
+/// An object in an S3 listing
+#[derive(Debug, Clone, Serialize)]
+pub struct S3ObjectDto {
+    #[serde(rename = "Key")]
+    pub key: String,
+    /// ISO 8601 with milliseconds, as S3 formats it
+    #[serde(rename = "LastModified")]
+    pub last_modified: String,
+    #[serde(rename = "ETag", skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(rename = "Size")]
+    pub size: u64,
+    #[serde(rename = "StorageClass")]
+    pub storage_class: String,
+}
+
+/// Keys rolled up at a delimiter in an S3 listing
+#[derive(Debug, Clone, Serialize)]
+pub struct S3CommonPrefixDto {
+    #[serde(rename = "Prefix")]
+    pub prefix: String,
+}
+
+/// DTO for version list response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListVersionsResponseDto {
+    pub versions: Vec<VersionedObjectDto>,
+    pub delete_markers: Vec<DeleteMarkerDto>,
+    /// Number of versions across all pages
+    pub total_count: usize,
+    pub is_truncated: bool,
+    pub next_key_marker: Option<String>,
+    pub next_version_id_marker: Option<String>,
+}
+
+/// DTO for delete marker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteMarkerDto {
+    pub key: String,
+    pub version_id: String,
+    pub last_modified: DateTime<Utc>,
+    pub is_latest: bool,
+}
+
+/// DTO for deleting many versions of an object at once
+///
+/// Exactly one of the fields selects the versions: noncurrent versions older
+/// than `older_than`, all noncurrent versions, or the listed `version_ids`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BulkDeleteVersionsDto {
+    pub older_than: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub noncurrent: bool,
+    pub version_ids: Option<Vec<String>>,
+}
+
+/// DTO for the result of a bulk version deletion
+#[derive(Debug, Clone, Serialize)]
+pub struct BulkDeleteVersionsResponseDto {
+    pub key: String,
+    pub deleted: usize,
+    pub failed: usize,
+    pub results: Vec<VersionDeletionDto>,
+}
+
+/// DTO for the outcome of deleting one version
+#[derive(Debug, Clone, Serialize)]
+pub struct VersionDeletionDto {
+    pub version_id: String,
+    pub deleted: bool,
+    pub error: Option<String>,
+}
+
+/// Query parameters of the S3-style multipart operations on an object
+///
+/// `?uploads` starts an upload; every other operation names the upload with
+/// `uploadId`, and part uploads also carry a `partNumber`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MultipartQueryDto {
+    pub uploads: Option<String>,
+    #[serde(rename = "uploadId")]
+    pub upload_id: Option<String>,
+    #[serde(rename = "partNumber")]
+    pub part_number: Option<u32>,
+}
+
+/// Query parameters of listing a bucket's in-progress multipart uploads
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ListMultipartUploadsDto {
+    pub uploads: Option<String>,
+    pub prefix: Option<String>,
+    #[serde(rename = "max-uploads")]
+    pub max_uploads: Option<usize>,
+}
+
+/// DTO for a started multipart upload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InitiateMultipartUploadResponseDto {
+    pub bucket: String,
+    pub key: String,
+    pub upload_id: String,
+}
+
+/// DTO for a part uploaded to a multipart upload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartDto {
+    pub part_number: u32,
+    pub etag: String,
+    pub size: u64,
+    pub last_modified: DateTime<Utc>,
+    /// Base64 SHA-256 of the part, absent for copied parts
+    pub checksum_sha256: Option<String>,
+}
+
+/// DTO listing the parts to assemble a multipart upload from, in ascending order
+#[derive(Debug, Clone, Deserialize)]
+pub struct CompleteMultipartUploadDto {
+    pub parts: Vec<CompletedPartDto>,
+}
+
+/// DTO naming one part of a multipart upload by number and ETag
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletedPartDto {
+    pub part_number: u32,
+    pub etag: String,
+}
+
+/// S3 `CompleteMultipartUpload` document listing the parts to assemble an
+/// upload from
+///
+/// Part checksums sent by SDKs are ignored; each part is checked by its ETag.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename = "CompleteMultipartUpload")]
+pub struct S3CompleteMultipartUploadDto {
+    #[serde(rename = "Part", default)]
+    pub parts: Vec<S3CompletedPartDto>,
+}
 
-    // Transition settings
-    pub transition_days: Option<u32>,
-    pub transition_date: Option<DateTime<Utc>>,
-    pub transition_storage_class: Option<String>,
+/// One `Part` of an S3 `CompleteMultipartUpload` document
+#[derive(Debug, Clone, Deserialize)]
+pub struct S3CompletedPartDto {
+    #[serde(rename = "PartNumber")]
+    pub part_number: u32,
+    #[serde(rename = "ETag")]
+    pub etag: String,
+}
 
-    // Non-current version expiration
-    pub noncurrent_version_expiration_noncurrent_days: Option<u32>,
-    pub noncurrent_version_expiration_newer_versions: Option<u32>,
+/// S3 `CompleteMultipartUploadResult` document of a completed upload
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename = "CompleteMultipartUploadResult")]
+pub struct S3CompleteMultipartUploadResultDto {
+    #[serde(rename = "@xmlns")]
+    pub xmlns: String,
+    #[serde(rename = "Location")]
+    pub location: String,
+    #[serde(rename = "Bucket")]
+    pub bucket: String,
+    #[serde(rename = "Key")]
+    pub key: String,
+    #[serde(rename = "ETag")]
+    pub etag: String,
+    #[serde(rename = "ChecksumSHA256", skip_serializing_if = "Option::is_none")]
+    pub checksum_sha256: Option<String>,
+}
 
-    // Non-current version transition
-    pub noncurrent_version_transition_noncurrent_days: Option<u32>,
-    pub noncurrent_version_transition_storage_class: Option<String>,
-    pub noncurrent_version_transition_newer_versions: Option<u32>,
+/// S3 `Error` document
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename = "Error")]
+pub struct S3ErrorDto {
+    #[serde(rename = "Code")]
+    pub code: String,
+    #[serde(rename = "Message")]
+    pub message: String,
+}
 
-    // Abort incomplete multipart uploads
-    pub abort_incomplete_multipart_upload_days_after_initiation: Option<u32>,
+/// DTO for a completed multipart upload
+#[derive(Debug, Clone, Serialize)]
+pub struct CompleteMultipartUploadResponseDto {
+    pub bucket: String,
+    pub key: String,
+    pub etag: Option<String>,
+    pub size: u64,
+    /// Composite SHA-256 of the parts, if every part has a checksum
+    pub checksum_sha256: Option<String>,
+    /// Checksums of the parts in object order, to verify the object part by part
+    pub parts: Vec<PartChecksumDto>,
 }
 
-/// DTO for lifecycle rule filter
+/// DTO for the checksum of one part of a completed multipart object
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct FilterDto {
-    pub prefix: Option<String>,
-    pub tags: Option<HashMap<String, String>>,
-    pub object_size_greater_than: Option<u64>,
-    pub object_size_less_than: Option<u64>,
+pub struct PartChecksumDto {
+    pub part_number: u32,
+    /// Offset of the part within the object
+    pub offset: u64,
+    pub size: u64,
+    pub checksum_sha256: String,
 }
 
-/// DTO for lifecycle configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LifecycleConfigurationDto {
-    pub bucket: String,
-    pub rules: Vec<LifecycleRuleDto>,
+impl PartChecksumDto {
+    /// Checksums of the parts of a manifest, with their offsets
+    pub fn from_manifest(manifest: &IntegrityManifest) -> Vec<Self> {
+        manifest
+            .part_ranges()
+            .map(|(part, range)| PartChecksumDto {
+                part_number: part.part_number,
+                offset: range.start,
+                size: part.size,
+                checksum_sha256: part.checksum.clone(),
+            })
+            .collect()
+    }
 }
 
-/// DTO for lifecycle evaluation request
-#[derive(Debug, Clone, Deserialize)]
-pub struct EvaluateLifecycleDto {
-    pub key: String,
-    pub object_created_at: Option<DateTime<Utc>>,
-    pub object_tags: Option<HashMap<String, String>>,
-    pub is_delete_marker: Option<bool>,
-    pub is_current_version: Option<bool>,
+/// DTO for presigning the parts and completion of a multipart upload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresignMultipartUploadDto {
+    /// Parts to create upload URLs for
+    pub part_numbers: Vec<u32>,
+    /// Seconds the URLs stay valid, an hour if not given
+    pub expires_in_secs: Option<u64>,
 }
 
-/// DTO for lifecycle evaluation response
+/// Response DTO for a presigned multipart upload
 #[derive(Debug, Clone, Serialize)]
-pub struct LifecycleEvaluationResponseDto {
-    pub actions_to_apply: Vec<ApplicableActionDto>,
+pub struct PresignedMultipartUploadDto {
+    pub upload_id: String,
+    pub parts: Vec<PresignedPartDto>,
+    pub complete_url: String,
+    pub expires_at: DateTime<Utc>,
 }
 
-/// DTO for applicable lifecycle action
+/// DTO for the upload URL of one part
 #[derive(Debug, Clone, Serialize)]
-pub struct ApplicableActionDto {
-    pub rule_id: String,
-    pub action_type: String,
-    pub reason: String,
-    pub details: HashMap<String, serde_json::Value>,
+pub struct PresignedPartDto {
+    pub part_number: u32,
+    pub url: String,
 }
 
-/// DTO for versioned object
+/// DTO for an in-progress multipart upload
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct VersionedObjectDto {
+pub struct MultipartUploadDto {
     pub key: String,
-    pub version_id: String,
-    pub size: u64,
-    pub last_modified: DateTime<Utc>,
-    pub etag: Option<String>,
-    pub is_latest: bool,
+    pub upload_id: String,
+    pub initiated: DateTime<Utc>,
 }
 
-/// DTO for version list response
+/// DTO for the in-progress multipart uploads of a bucket
 #[derive(Debug, Clone, Serialize)]
-pub struct ListVersionsResponseDto {
-    pub versions: Vec<VersionedObjectDto>,
-    pub delete_markers: Vec<DeleteMarkerDto>,
+pub struct ListMultipartUploadsResponseDto {
+    pub bucket: String,
+    pub prefix: Option<String>,
+    pub uploads: Vec<MultipartUploadDto>,
     pub is_truncated: bool,
-    pub next_key_marker: Option<String>,
-    pub next_version_id_marker: Option<String>,
 }
 
-/// DTO for delete marker
+/// DTO for the parts uploaded so far to a multipart upload
 #[derive(Debug, Clone, Serialize)]
-pub struct DeleteMarkerDto {
+pub struct ListPartsResponseDto {
+    pub bucket: String,
     pub key: String,
-    pub version_id: String,
-    pub last_modified: DateTime<Utc>,
-    pub is_latest: bool,
+    pub upload_id: String,
+    pub parts: Vec<PartDto>,
 }
 
 /// DTO for error responses
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ErrorResponseDto {
     pub error: String,
     pub message: String,
@@ -169,7 +1173,7 @@ pub struct ErrorResponseDto {
 }
 
 /// DTO for success responses
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SuccessResponseDto {
     pub message: String,
     pub data: Option<serde_json::Value>,
@@ -203,9 +1207,6 @@ impl From<FilterDto> for Filter {
 
         if let Some(tags) = dto.tags {
             filter = filter.with_tags(tags);
-        } else {
-            let mut filter = Filter::new();
-            filter = Filter::new();
         }
 
         if let Some(size) = dto.object_size_greater_than {
@@ -220,6 +1221,36 @@ impl From<FilterDto> for Filter {
     }
 }
 
+impl TryFrom<&SearchObjectsDto> for Filter {
+    type Error = ValidationError;
+
+    fn try_from(dto: &SearchObjectsDto) -> Result<Self, Self::Error> {
+        let tags = dto
+            .tags
+            .iter()
+            .flat_map(|tags| tags.split(','))
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| match pair.split_once(':') {
+                Some((name, value)) if !name.is_empty() => {
+                    Ok((name.to_string(), value.to_string()))
+                }
+                _ => Err(ValidationError::InvalidField {
+                    field: "tags".to_string(),
+                    value: pair.to_string(),
+                    expected: "comma-separated name:value pairs".to_string(),
+                }),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Filter {
+            prefix: dto.prefix.clone(),
+            tags,
+            object_size_greater_than: dto.size_greater_than,
+            object_size_less_than: dto.size_less_than,
+        })
+    }
+}
+
 impl From<Filter> for FilterDto {
     fn from(filter: Filter) -> Self {
         FilterDto {
@@ -249,11 +1280,11 @@ impl TryFrom<LifecycleRuleDto> for LifecycleRule {
 
         let transition_storage_class = dto
             .transition_storage_class
-            .map(|s| LifecycleStorageClass::from_str(&s));
+            .map(|s| LifecycleStorageClass::from(s.as_str()));
 
         let noncurrent_version_transition_storage_class = dto
             .noncurrent_version_transition_storage_class
-            .map(|s| LifecycleStorageClass::from_str(&s));
+            .map(|s| LifecycleStorageClass::from(s.as_str()));
 
         Ok(LifecycleRule {
             id: dto.id,
@@ -268,15 +1299,14 @@ impl TryFrom<LifecycleRuleDto> for LifecycleRule {
             all_versions_expiration_delete_marker: dto.all_versions_expiration_delete_marker,
             transition_days: dto.transition_days,
             transition_date: dto.transition_date,
-            transition_storage_class: transition_storage_class,
+            transition_storage_class,
             noncurrent_version_expiration_noncurrent_days: dto
                 .noncurrent_version_expiration_noncurrent_days,
             noncurrent_version_expiration_newer_versions: dto
                 .noncurrent_version_expiration_newer_versions,
             noncurrent_version_transition_noncurrent_days: dto
                 .noncurrent_version_transition_noncurrent_days,
-            noncurrent_version_transition_storage_class:
-                noncurrent_version_transition_storage_class,
+            noncurrent_version_transition_storage_class,
             noncurrent_version_transition_newer_versions: dto
                 .noncurrent_version_transition_newer_versions,
             abort_incomplete_multipart_upload_days_after_initiation: dto
@@ -323,6 +1353,304 @@ impl From<LifecycleRule> for LifecycleRuleDto {
     }
 }
 
+impl From<ApplicableAction> for ApplicableActionDto {
+    fn from(action: ApplicableAction) -> Self {
+        let mut details = HashMap::new();
+
+        // Add action-specific details
+        match &action.action {
+            LifecycleAction::Expiration { days, date } => {
+                if let Some(days) = days {
+                    details.insert(
+                        "days".to_string(),
+                        serde_json::Value::Number((*days).into()),
+                    );
+                }
+                if let Some(date) = date {
+                    details.insert(
+                        "date".to_string(),
+                        serde_json::Value::String(date.to_rfc3339()),
+                    );
+                }
+            }
+            LifecycleAction::Transition {
+                days,
+                date,
+                storage_class,
+            } => {
+                if let Some(days) = days {
+                    details.insert(
+                        "days".to_string(),
+                        serde_json::Value::Number((*days).into()),
+                    );
+                }
+                if let Some(date) = date {
+                    details.insert(
+                        "date".to_string(),
+                        serde_json::Value::String(date.to_rfc3339()),
+                    );
+                }
+                details.insert(
+                    "storage_class".to_string(),
+                    serde_json::Value::String(storage_class.as_str().to_string()),
+                );
+            }
+            LifecycleAction::ScheduledDeletion { delete_at } => {
+                details.insert(
+                    "delete_at".to_string(),
+                    serde_json::Value::String(delete_at.to_rfc3339()),
+                );
+            }
+            _ => {}
+        }
+
+        ApplicableActionDto {
+            rule_id: action.rule_id,
+            action_type: format!("{:?}", action.action),
+            reason: action.reason,
+            details,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<LifecycleSimulationResults> for LifecycleSimulationResponseDto {
+    fn from(results: LifecycleSimulationResults) -> Self {
+        LifecycleSimulationResponseDto {
+            bucket: results.bucket.as_str().to_string(),
+            as_of: results.as_of,
+            objects_evaluated: results.objects_evaluated,
+            affected_objects: results
+                .affected_objects
+                .into_iter()
+                .map(|object| SimulatedObjectDto {
+                    key: object.object_key.as_str().to_string(),
+                    actions: object
+                        .actions
+                        .into_iter()
+                        .map(ApplicableActionDto::from)
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl LifecycleProcessingStatusDto {
+    pub fn new(bucket: &BucketName, status: ProcessingStatus) -> Self {
+        LifecycleProcessingStatusDto {
+            bucket: bucket.as_str().to_string(),
+            is_running: status.is_running,
+            cancelled: status.cancelled,
+            last_run: status.last_run.map(DateTime::<Utc>::from),
+            next_scheduled_run: status.next_scheduled_run.map(DateTime::<Utc>::from),
+            last_run_results: status.last_run_results.map(LifecycleRunResultsDto::from),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<BucketLifecycleResults> for LifecycleRunResultsDto {
+    fn from(results: BucketLifecycleResults) -> Self {
+        LifecycleRunResultsDto {
+            objects_processed: results.objects_processed,
+            objects_affected: results.objects_affected,
+            actions_applied: results.actions_applied,
+            errors: results
+                .errors
+                .into_iter()
+                .map(|error| LifecycleProcessingErrorDto {
+                    object_key: error.object_key.as_str().to_string(),
+                    rule_id: error.rule_id,
+                    error: error.error,
+                })
+                .collect(),
+            duration_ms: results.duration.as_millis() as u64,
+        }
+    }
+}
+
+impl TryFrom<BulkDeleteVersionsDto> for VersionSelection {
+    type Error = ValidationError;
+
+    fn try_from(dto: BulkDeleteVersionsDto) -> Result<Self, Self::Error> {
+        match (dto.older_than, dto.noncurrent, dto.version_ids) {
+            (Some(cutoff), false, None) => Ok(VersionSelection::OlderThan(cutoff)),
+            (None, true, None) => Ok(VersionSelection::Noncurrent),
+            (None, false, Some(version_ids)) => version_ids
+                .into_iter()
+                .map(VersionId::new)
+                .collect::<Result<_, _>>()
+                .map(VersionSelection::Explicit),
+            _ => Err(ValidationError::InvalidField {
+                field: "selection".to_string(),
+                value: "none or several".to_string(),
+                expected: "exactly one of older_than, noncurrent or version_ids".to_string(),
+            }),
+        }
+    }
+}
+
+impl From<BulkDeleteVersionsResult> for BulkDeleteVersionsResponseDto {
+    fn from(result: BulkDeleteVersionsResult) -> Self {
+        let deleted = result.deleted_count();
+        BulkDeleteVersionsResponseDto {
+            key: result.key.as_str().to_string(),
+            deleted,
+            failed: result.outcomes.len() - deleted,
+            results: result
+                .outcomes
+                .into_iter()
+                .map(|outcome| VersionDeletionDto {
+                    version_id: outcome.version_id.as_str().to_string(),
+                    deleted: outcome.error.is_none(),
+                    error: outcome.error,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<&VersioningConfiguration> for VersioningStatusDto {
+    fn from(config: &VersioningConfiguration) -> Self {
+        match config.enabled {
+            true => VersioningStatusDto::Enabled,
+            false => VersioningStatusDto::Suspended,
+        }
+    }
+}
+
+impl PresignedMultipartUploadDto {
+    pub fn new(presigned: PresignedMultipartUpload, expires_at: DateTime<Utc>) -> Self {
+        Self {
+            upload_id: presigned.upload_id,
+            parts: presigned
+                .parts
+                .into_iter()
+                .map(|part| PresignedPartDto {
+                    part_number: part.part_number,
+                    url: part.url,
+                })
+                .collect(),
+            complete_url: presigned.complete_url,
+            expires_at,
+        }
+    }
+}
+
+impl From<UploadedPart> for PartDto {
+    fn from(part: UploadedPart) -> Self {
+        PartDto {
+            part_number: part.part_number,
+            etag: part.etag,
+            size: part.size,
+            last_modified: part.last_modified,
+            checksum_sha256: part.checksum_sha256,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<CompletedPartDto> for CompletedPart {
+    fn from(dto: CompletedPartDto) -> Self {
+        CompletedPart {
+            part_number: dto.part_number,
+            etag: dto.etag,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<S3CompletedPartDto> for CompletedPart {
+    fn from(dto: S3CompletedPartDto) -> Self {
+        CompletedPart {
+            part_number: dto.part_number,
+            etag: dto.etag,
+        }
+    }
+}
+
+impl From<PrefixRewriteDto> for PrefixRewrite {
+    fn from(dto: PrefixRewriteDto) -> Self {
+        PrefixRewrite {
+            from: dto.from,
+            to: dto.to,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl LifecycleValidationResponseDto {
+    pub fn new(result: ValidationResult, diff: LifecycleDiffDto) -> Self {
+        LifecycleValidationResponseDto {
+            is_valid: result.is_valid,
+            errors: result
+                .errors
+                .into_iter()
+                .map(|error| ValidationIssueDto {
+                    rule_id: error.rule_id,
+                    field: error.field,
+                    message: error.message,
+                })
+                .collect(),
+            warnings: result
+                .warnings
+                .into_iter()
+                .map(|warning| ValidationWarningDto {
+                    rule_id: warning.rule_id,
+                    message: warning.message,
+                })
+                .collect(),
+            diff,
+        }
+    }
+}
+
+impl LifecycleDiffDto {
+    pub fn new(diff: LifecycleConfigurationDiff, has_active_configuration: bool) -> Self {
+        LifecycleDiffDto {
+            has_active_configuration,
+            added: diff.added.into_iter().map(LifecycleRuleDto::from).collect(),
+            removed: diff
+                .removed
+                .into_iter()
+                .map(LifecycleRuleDto::from)
+                .collect(),
+            modified: diff.modified.into_iter().map(RuleChangeDto::from).collect(),
+            unchanged: diff.unchanged,
+        }
+    }
+}
+
+impl From<RuleChange> for RuleChangeDto {
+    fn from(change: RuleChange) -> Self {
+        let rule_id = change.after.id.clone();
+        let before =
+            serde_json::to_value(LifecycleRuleDto::from(change.before)).unwrap_or_default();
+        let after = serde_json::to_value(LifecycleRuleDto::from(change.after)).unwrap_or_default();
+
+        // Compare the rules setting by setting, as named in the rule DTO
+        let mut changes = Vec::new();
+        if let (serde_json::Value::Object(before), serde_json::Value::Object(after)) =
+            (before, after)
+        {
+            for (field, after_value) in after {
+                let before_value = before.get(&field).cloned().unwrap_or_default();
+                if before_value != after_value {
+                    changes.push(FieldChangeDto {
+                        field,
+                        before: before_value,
+                        after: after_value,
+                    });
+                }
+            }
+        }
+
+        RuleChangeDto { rule_id, changes }
+    }
+}
+
 impl TryFrom<LifecycleConfigurationDto> for LifecycleConfiguration {
     type Error = ValidationError;
 
@@ -370,6 +1698,63 @@ impl ErrorResponseDto {
                     serde_json::Value::String(version_id.as_str().to_string()),
                 );
             }
+            StorageError::ObjectExpired {
+                key,
+                rule_id,
+                expired_at,
+            } => {
+                details.insert(
+                    "key".to_string(),
+                    serde_json::Value::String(key.as_str().to_string()),
+                );
+                details.insert(
+                    "rule_id".to_string(),
+                    serde_json::Value::String(rule_id.clone()),
+                );
+                details.insert(
+                    "expired_at".to_string(),
+                    serde_json::Value::String(expired_at.to_rfc3339()),
+                );
+            }
+            StorageError::TransactionNotFound { transaction_id } => {
+                details.insert(
+                    "transaction_id".to_string(),
+                    serde_json::Value::String(transaction_id.clone()),
+                );
+            }
+            StorageError::DatasetNotFound { name } => {
+                details.insert(
+                    "dataset".to_string(),
+                    serde_json::Value::String(name.clone()),
+                );
+            }
+            StorageError::BackupNotFound { id } => {
+                details.insert("backup".to_string(), serde_json::Value::String(id.clone()));
+            }
+            StorageError::UploadNotFound { key, upload_id } => {
+                details.insert(
+                    "key".to_string(),
+                    serde_json::Value::String(key.as_str().to_string()),
+                );
+                details.insert(
+                    "upload_id".to_string(),
+                    serde_json::Value::String(upload_id.clone()),
+                );
+            }
+            StorageError::InvalidPart {
+                upload_id,
+                part_number,
+                ..
+            } => {
+                details.insert(
+                    "upload_id".to_string(),
+                    serde_json::Value::String(upload_id.clone()),
+                );
+                details.insert(
+                    "part_number".to_string(),
+                    serde_json::Value::Number((*part_number).into()),
+                );
+            }
             StorageError::QuotaExceeded { used, limit } => {
                 details.insert(
                     "used".to_string(),
@@ -380,6 +1765,29 @@ impl ErrorResponseDto {
                     serde_json::Value::Number((*limit).into()),
                 );
             }
+            StorageError::PrefixQuotaExceeded {
+                prefix,
+                kind,
+                used,
+                limit,
+            } => {
+                details.insert(
+                    "prefix".to_string(),
+                    serde_json::Value::String(prefix.clone()),
+                );
+                details.insert(
+                    "kind".to_string(),
+                    serde_json::Value::String(kind.as_str().to_string()),
+                );
+                details.insert(
+                    "used".to_string(),
+                    serde_json::Value::Number((*used).into()),
+                );
+                details.insert(
+                    "limit".to_string(),
+                    serde_json::Value::Number((*limit).into()),
+                );
+            }
             _ => {}
         }
 
@@ -431,6 +1839,15 @@ impl ErrorResponseDto {
         }
     }
 
+    pub fn from_validation_error(error: ValidationError) -> Self {
+        ErrorResponseDto {
+            error: "ValidationError".to_string(),
+            message: error.to_string(),
+            details: None,
+            timestamp: Utc::now(),
+        }
+    }
+
     pub fn bad_request(message: &str) -> Self {
         ErrorResponseDto {
             error: "BadRequest".to_string(),
@@ -440,6 +1857,15 @@ impl ErrorResponseDto {
         }
     }
 
+    pub fn not_found(message: &str) -> Self {
+        ErrorResponseDto {
+            error: "NotFound".to_string(),
+            message: message.to_string(),
+            details: None,
+            timestamp: Utc::now(),
+        }
+    }
+
     pub fn internal_error(message: &str) -> Self {
         ErrorResponseDto {
             error: "InternalServerError".to_string(),
@@ -448,6 +1874,51 @@ impl ErrorResponseDto {
             timestamp: Utc::now(),
         }
     }
+
+    pub fn service_unavailable(message: &str) -> Self {
+        ErrorResponseDto {
+            error: "ServiceUnavailable".to_string(),
+            message: message.to_string(),
+            details: None,
+            timestamp: Utc::now(),
+        }
+    }
+
+    /// Error for a request signed for a region other than the server's
+    ///
+    /// The server's region is included in the details so clients can retry there.
+    pub fn region_mismatch(signed_region: &str, region: &str) -> Self {
+        ErrorResponseDto {
+            error: "AuthorizationHeaderMalformed".to_string(),
+            message: format!(
+                "The request was signed for region '{}', but this server is in '{}'",
+                signed_region, region
+            ),
+            details: Some(HashMap::from([(
+                "region".to_string(),
+                serde_json::Value::String(region.to_string()),
+            )])),
+            timestamp: Utc::now(),
+        }
+    }
+
+    pub fn gateway_timeout(message: &str) -> Self {
+        ErrorResponseDto {
+            error: "DeadlineExceeded".to_string(),
+            message: message.to_string(),
+            details: None,
+            timestamp: Utc::now(),
+        }
+    }
+
+    pub fn access_denied(message: &str) -> Self {
+        ErrorResponseDto {
+            error: "AccessDenied".to_string(),
+            message: message.to_string(),
+            details: None,
+            timestamp: Utc::now(),
+        }
+    }
 }
 
 impl SuccessResponseDto {
@@ -0,0 +1,203 @@
+use axum::{
+    Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use std::collections::HashMap;
+
+use crate::{
+    adapters::inbound::http::dto::ErrorResponseDto,
+    domain::errors::{LifecycleError, StorageError, ValidationError},
+};
+
+/// Error returned by the HTTP handlers
+///
+/// Domain errors convert into it with `?`, and each kind of failure maps to
+/// one status code whichever handler reports it:
+///
+/// - 404 when an object, version, upload, transaction, dataset, bucket
+///   configuration or rule does not exist, or the object has expired
+/// - 409 when the request conflicts with the current state of a resource
+/// - 422 when a well-formed request carries values the domain rejects
+/// - 400 when the request itself cannot be interpreted
+/// - 504 when the storage backend does not answer in time
+#[derive(Debug, Clone)]
+pub enum ApiError {
+    Storage(StorageError),
+    Lifecycle(LifecycleError),
+    Validation(ValidationError),
+    /// A malformed request, such as an unparsable header or query parameter
+    BadRequest(String),
+    /// A resource looked up by the handler itself does not exist
+    NotFound(String),
+    /// A response whose status and body the handler chose
+    Custom(StatusCode, ErrorResponseDto),
+}
+
+impl ApiError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::Storage(error) => storage_status(error),
+            ApiError::Lifecycle(error) => lifecycle_status(error),
+            ApiError::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Custom(status, _) => *status,
+        }
+    }
+
+    /// Add `value` to the details of the error body under `name`
+    pub fn with_detail(self, name: &str, value: serde_json::Value) -> Self {
+        let status = self.status_code();
+        let mut body = ErrorResponseDto::from(self);
+        body.details
+            .get_or_insert_with(HashMap::new)
+            .insert(name.to_string(), value);
+        ApiError::Custom(status, body)
+    }
+}
+
+fn storage_status(error: &StorageError) -> StatusCode {
+    match error {
+        StorageError::ObjectNotFound { .. }
+        | StorageError::ObjectExpired { .. }
+        | StorageError::VersionNotFound { .. }
+        | StorageError::UploadNotFound { .. }
+        | StorageError::TransactionNotFound { .. }
+        | StorageError::DatasetNotFound { .. }
+        | StorageError::BackupNotFound { .. } => StatusCode::NOT_FOUND,
+        StorageError::VersionConflict { .. } | StorageError::ObjectAlreadyExists { .. } => {
+            StatusCode::CONFLICT
+        }
+        StorageError::InvalidObjectSize { .. }
+        | StorageError::InvalidStorageClass { .. }
+        | StorageError::InvalidPart { .. }
+        | StorageError::InvalidPartOrder { .. }
+        | StorageError::ValidationError { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+        StorageError::QuotaExceeded { .. } | StorageError::PrefixQuotaExceeded { .. } => {
+            StatusCode::INSUFFICIENT_STORAGE
+        }
+        StorageError::AccessDenied { .. } => StatusCode::FORBIDDEN,
+        StorageError::OperationNotSupported { .. } | StorageError::UnsupportedOperation { .. } => {
+            StatusCode::NOT_IMPLEMENTED
+        }
+        StorageError::StorageBackendError { .. } => StatusCode::BAD_GATEWAY,
+        StorageError::Timeout { .. } => StatusCode::GATEWAY_TIMEOUT,
+        StorageError::InfrastructureError { .. } | StorageError::InternalError { .. } => {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+fn lifecycle_status(error: &LifecycleError) -> StatusCode {
+    match error {
+        LifecycleError::ConfigurationNotFound { .. } | LifecycleError::RuleNotFound { .. } => {
+            StatusCode::NOT_FOUND
+        }
+        LifecycleError::ValidationFailed { .. }
+        | LifecycleError::InvalidRule { .. }
+        | LifecycleError::ConflictingRules { .. }
+        | LifecycleError::InvalidExpiration { .. }
+        | LifecycleError::InvalidTransition { .. }
+        | LifecycleError::TooManyRules { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+        LifecycleError::ActionFailed { .. }
+        | LifecycleError::ActionExecutionFailed { .. }
+        | LifecycleError::ProcessingError { .. }
+        | LifecycleError::RepositoryError { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+        LifecycleError::ProcessingInProgress { .. }
+        | LifecycleError::ProcessingNotRunning { .. } => StatusCode::CONFLICT,
+    }
+}
+
+impl From<StorageError> for ApiError {
+    fn from(error: StorageError) -> Self {
+        ApiError::Storage(error)
+    }
+}
+
+impl From<LifecycleError> for ApiError {
+    fn from(error: LifecycleError) -> Self {
+        ApiError::Lifecycle(error)
+    }
+}
+
+impl From<ValidationError> for ApiError {
+    fn from(error: ValidationError) -> Self {
+        ApiError::Validation(error)
+    }
+}
+
+impl From<ApiError> for ErrorResponseDto {
+    fn from(error: ApiError) -> Self {
+        match error {
+            ApiError::Storage(error) => ErrorResponseDto::from_storage_error(error),
+            ApiError::Lifecycle(error) => ErrorResponseDto::from_lifecycle_error(error),
+            ApiError::Validation(error) => ErrorResponseDto::from_validation_error(error),
+            ApiError::BadRequest(message) => ErrorResponseDto::bad_request(&message),
+            ApiError::NotFound(message) => ErrorResponseDto::not_found(&message),
+            ApiError::Custom(_, body) => body,
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = self.status_code();
+        (status, Json(ErrorResponseDto::from(self))).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::value_objects::ObjectKey;
+
+    #[test]
+    fn test_domain_errors_map_to_consistent_statuses() {
+        let key = ObjectKey::new("a/b".to_string()).unwrap();
+
+        let not_found = [
+            ApiError::from(StorageError::ObjectNotFound { key: key.clone() }),
+            ApiError::from(LifecycleError::RuleNotFound {
+                rule_id: "rule".to_string(),
+            }),
+            ApiError::NotFound("missing".to_string()),
+        ];
+        for error in not_found {
+            assert_eq!(error.status_code(), StatusCode::NOT_FOUND);
+        }
+
+        let conflict = ApiError::from(StorageError::ObjectAlreadyExists { key });
+        assert_eq!(conflict.status_code(), StatusCode::CONFLICT);
+
+        let unprocessable = [
+            ApiError::from(ValidationError::EmptyObjectKey),
+            ApiError::from(StorageError::ValidationError {
+                message: "bad part".to_string(),
+            }),
+            ApiError::from(LifecycleError::TooManyRules { count: 2, max: 1 }),
+        ];
+        for error in unprocessable {
+            assert_eq!(error.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        }
+
+        assert_eq!(
+            ApiError::BadRequest("bad header".to_string()).status_code(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[test]
+    fn test_details_keep_the_status_and_body() {
+        let error = ApiError::from(ValidationError::EmptyObjectKey)
+            .with_detail("stored_keys", serde_json::json!(["a"]));
+
+        assert_eq!(error.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = ErrorResponseDto::from(error);
+        assert_eq!(body.error, "ValidationError");
+        assert_eq!(
+            body.details.unwrap()["stored_keys"],
+            serde_json::json!(["a"])
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use axum::{
+    extract::{FromRequestParts, Path},
+    http::{StatusCode, request::Parts},
+};
+use std::collections::HashMap;
+
+use crate::{
+    adapters::inbound::http::{dto::ErrorResponseDto, error::ApiError},
+    domain::value_objects::{BucketName, ObjectKey, VersionId},
+};
+
+/// Bucket named by the `{bucket}` path parameter
+///
+/// A name S3 would reject is refused with 422 before the handler runs.
+#[derive(Debug, Clone)]
+pub struct BucketNamePath(pub BucketName);
+
+/// Object key given by the `{key}` path parameter
+///
+/// The parameter is percent-decoded, and may also be a `{*key}` wildcard
+/// tail. Invalid keys are refused with 422 before the handler runs.
+#[derive(Debug, Clone)]
+pub struct ObjectKeyPath(pub ObjectKey);
+
+/// Object within a bucket, given by the `{bucket}` and `{key}` path parameters
+#[derive(Debug, Clone)]
+pub struct BucketObjectKeyPath {
+    pub bucket: BucketName,
+    /// Key of the object relative to the bucket, as given in the path
+    pub key: String,
+    /// Key of the object in the store, `<bucket>/<key>`
+    pub object_key: ObjectKey,
+}
+
+/// Version given by the `{version_id}` path parameter
+#[derive(Debug, Clone)]
+pub struct VersionIdPath(pub VersionId);
+
+/// Percent-decoded value of the path parameter `name`
+async fn path_param<S: Send + Sync>(
+    parts: &mut Parts,
+    state: &S,
+    name: &str,
+) -> Result<String, ApiError> {
+    let Path(mut params) = Path::<HashMap<String, String>>::from_request_parts(parts, state)
+        .await
+        .map_err(|e| ApiError::BadRequest(e.body_text()))?;
+
+    params.remove(name).ok_or_else(|| {
+        // Only a route registered without the parameter gets here
+        ApiError::Custom(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorResponseDto::internal_error(&format!("Route has no {{{}}} parameter", name)),
+        )
+    })
+}
+
+/// Key path parameter without the leading slash a wildcard tail may keep
+async fn key_param<S: Send + Sync>(parts: &mut Parts, state: &S) -> Result<String, ApiError> {
+    let key = path_param(parts, state, "key").await?;
+    Ok(key.strip_prefix('/').map(str::to_string).unwrap_or(key))
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for BucketNamePath {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let bucket = path_param(parts, state, "bucket").await?;
+        Ok(BucketNamePath(BucketName::new(bucket)?))
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ObjectKeyPath {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let key = key_param(parts, state).await?;
+        Ok(ObjectKeyPath(ObjectKey::new(key)?))
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for BucketObjectKeyPath {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let BucketNamePath(bucket) = BucketNamePath::from_request_parts(parts, state).await?;
+        let key = key_param(parts, state).await?;
+        let object_key = ObjectKey::new(format!("{}/{}", bucket.as_str(), key))?;
+
+        Ok(BucketObjectKeyPath {
+            bucket,
+            key,
+            object_key,
+        })
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for VersionIdPath {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let version_id = path_param(parts, state, "version_id").await?;
+        Ok(VersionIdPath(VersionId::new(version_id)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, routing::get};
+    use axum_test::TestServer;
+
+    async fn echo_key(
+        BucketObjectKeyPath {
+            bucket,
+            key,
+            object_key,
+        }: BucketObjectKeyPath,
+    ) -> String {
+        format!("{} {} {}", bucket.as_str(), key, object_key.as_str())
+    }
+
+    async fn echo_tail(ObjectKeyPath(key): ObjectKeyPath) -> String {
+        key.as_str().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_path_extractors_decode_and_validate() {
+        let router = Router::new()
+            .route("/buckets/{bucket}/objects/{key}", get(echo_key))
+            .route("/files/{*key}", get(echo_tail));
+        let server = TestServer::new(router).unwrap();
+
+        let response = server
+            .get("/buckets/photos/objects/2024%2Fcat%20one.jpg")
+            .await;
+        response.assert_status_ok();
+        assert_eq!(
+            response.text(),
+            "photos 2024/cat one.jpg photos/2024/cat one.jpg"
+        );
+
+        let response = server.get("/files/nested/dir/file.txt").await;
+        assert_eq!(response.text(), "nested/dir/file.txt");
+
+        // Bucket names S3 would reject never reach the handler
+        let response = server.get("/buckets/Not_A_Bucket/objects/a.txt").await;
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+}
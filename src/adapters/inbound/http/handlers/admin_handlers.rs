@@ -0,0 +1,103 @@
+use axum::{Json, extract::State, http::StatusCode};
+use std::sync::Arc;
+
+use crate::adapters::{
+    inbound::http::{
+        dto::{ErrorResponseDto, StartKeyRotationDto, SuccessResponseDto},
+        error::ApiError,
+    },
+    outbound::storage::{
+        encryption::{KeyRotationControl, KeyRotationError},
+        migration::MigrationControl,
+    },
+};
+
+/// Handle starting a rotation to a new master key
+pub async fn start_key_rotation(
+    State(rotation): State<Arc<dyn KeyRotationControl>>,
+    Json(request): Json<StartKeyRotationDto>,
+) -> Result<(StatusCode, Json<SuccessResponseDto>), ApiError> {
+    rotation
+        .start_rotation(&request.key_id)
+        .await
+        .map_err(|e| match e {
+            KeyRotationError::AlreadyRunning => ApiError::Custom(
+                StatusCode::CONFLICT,
+                ErrorResponseDto::bad_request(&e.to_string()),
+            ),
+            KeyRotationError::UnknownKey(_) => ApiError::Custom(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ErrorResponseDto::bad_request(&e.to_string()),
+            ),
+            KeyRotationError::Backend(_) => ApiError::Custom(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ErrorResponseDto::internal_error(&e.to_string()),
+            ),
+        })?;
+
+    let status = rotation.rotation_status().await;
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(SuccessResponseDto::with_data(
+            &format!("Key rotation to '{}' started", request.key_id),
+            serde_json::to_value(status).unwrap_or_default(),
+        )),
+    ))
+}
+
+/// Handle getting the progress of the current or most recent key rotation
+pub async fn get_key_rotation_status(
+    State(rotation): State<Arc<dyn KeyRotationControl>>,
+) -> Json<SuccessResponseDto> {
+    let status = rotation.rotation_status().await;
+    Json(SuccessResponseDto::with_data(
+        "Key rotation status",
+        serde_json::to_value(status).unwrap_or_default(),
+    ))
+}
+
+/// Handle starting the copy of every object to the new backend
+pub async fn start_migration(
+    State(migration): State<Arc<dyn MigrationControl>>,
+) -> Result<(StatusCode, Json<SuccessResponseDto>), ApiError> {
+    migration
+        .start_migration()
+        .await
+        .map_err(|e| ApiError::Custom(StatusCode::CONFLICT, ErrorResponseDto::bad_request(&e)))?;
+
+    let status = migration.migration_status().await;
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(SuccessResponseDto::with_data(
+            "Backend migration started",
+            serde_json::to_value(status).unwrap_or_default(),
+        )),
+    ))
+}
+
+/// Handle getting the progress of the backend migration
+pub async fn get_migration_status(
+    State(migration): State<Arc<dyn MigrationControl>>,
+) -> Json<SuccessResponseDto> {
+    let status = migration.migration_status().await;
+    Json(SuccessResponseDto::with_data(
+        "Backend migration status",
+        serde_json::to_value(status).unwrap_or_default(),
+    ))
+}
+
+/// Handle flipping all traffic to the new backend
+pub async fn cut_over_migration(
+    State(migration): State<Arc<dyn MigrationControl>>,
+) -> Result<Json<SuccessResponseDto>, ApiError> {
+    migration
+        .cut_over()
+        .await
+        .map_err(|e| ApiError::Custom(StatusCode::CONFLICT, ErrorResponseDto::bad_request(&e)))?;
+
+    let status = migration.migration_status().await;
+    Ok(Json(SuccessResponseDto::with_data(
+        "Traffic cut over to the new backend",
+        serde_json::to_value(status).unwrap_or_default(),
+    )))
+}
@@ -0,0 +1,236 @@
+use axum::{
+    Json,
+    body::{Body, Bytes},
+    extract::State,
+    http::StatusCode,
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::io::AsyncRead;
+use tokio::sync::mpsc;
+use tokio_util::io::{ReaderStream, StreamReader};
+
+use crate::{
+    adapters::inbound::http::{
+        archive::{ArchiveFormat, ArchiveReader, ArchiveWriter},
+        dto::{CreateArchiveDto, SuccessResponseDto},
+        error::ApiError,
+        extract::BucketNamePath,
+        router::AppState,
+    },
+    domain::{errors::StorageError, models::QuotaWarning, value_objects::ObjectKey},
+    ports::services::ObjectService,
+};
+
+/// Size of the pipe between the archive writer task and the response body
+const ARCHIVE_PIPE_CAPACITY: usize = 256 * 1024;
+
+/// Number of chunks buffered between the archive reader and the store
+const EXTRACT_CHANNEL_DEPTH: usize = 4;
+
+/// Handle a bundle download of several objects as a tar or zip archive
+///
+/// The archive is assembled on the fly: a background task streams each object
+/// into the archive writer while the response body is being sent, so neither the
+/// objects nor the archive are buffered. Objects are namespaced by bucket as
+/// `bucket/key`, and entries are named by their key within the bucket.
+pub async fn create_bucket_archive(
+    State(app_state): State<AppState>,
+    BucketNamePath(bucket): BucketNamePath,
+    Json(request): Json<CreateArchiveDto>,
+) -> Result<Response<Body>, ApiError> {
+    let object_service = app_state.object_service.clone();
+    let bucket_prefix = format!("{}/", bucket);
+
+    let keys = match (request.keys, request.prefix) {
+        (Some(_), Some(_)) => {
+            return Err(ApiError::BadRequest(
+                "Specify either keys or a prefix, not both".to_string(),
+            ));
+        }
+        (Some(keys), None) => {
+            let mut object_keys = Vec::with_capacity(keys.len());
+            for key in keys {
+                let object_key = ObjectKey::new(format!("{}{}", bucket_prefix, key))?;
+
+                // Missing objects are reported before any of the archive is sent
+                let exists = object_service.object_exists(&object_key).await?;
+                if !exists {
+                    return Err(StorageError::ObjectNotFound { key: object_key }.into());
+                }
+                object_keys.push(object_key);
+            }
+            object_keys
+        }
+        (None, prefix) => {
+            let prefix = format!("{}{}", bucket_prefix, prefix.unwrap_or_default());
+            object_service
+                .list_objects(Some(&prefix), None)
+                .await?
+                .into_iter()
+                .map(|info| info.key)
+                .collect()
+        }
+    };
+
+    let format = request.format;
+    let (writer, reader) = tokio::io::duplex(ARCHIVE_PIPE_CAPACITY);
+    tokio::spawn(async move {
+        // An error here truncates the archive, which the client sees as a broken download
+        if let Err(e) = write_archive(object_service, keys, bucket_prefix, writer, format).await {
+            tracing::error!("Failed to stream archive: {}", e);
+        }
+    });
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("content-type", format.content_type())
+        .header(
+            "content-disposition",
+            format!("attachment; filename=\"{}.{}\"", bucket, format.extension()),
+        )
+        .body(Body::from_stream(ReaderStream::new(reader)))
+        .unwrap())
+}
+
+async fn write_archive(
+    object_service: Arc<dyn ObjectService>,
+    keys: Vec<ObjectKey>,
+    bucket_prefix: String,
+    writer: tokio::io::DuplexStream,
+    format: ArchiveFormat,
+) -> std::io::Result<()> {
+    let mut archive = ArchiveWriter::new(writer, format);
+    for key in keys {
+        let (metadata, reader) = object_service
+            .get_object_stream(&key, None)
+            .await
+            .map_err(|e| std::io::Error::other(e.to_string()))?;
+        let name = key
+            .as_str()
+            .strip_prefix(&bucket_prefix)
+            .unwrap_or(key.as_str());
+        let modified: DateTime<Utc> = metadata.last_modified.into();
+        archive
+            .append(name, metadata.content_length, modified, reader)
+            .await?;
+    }
+    archive.finish().await?;
+    Ok(())
+}
+
+/// Store every file of an uploaded archive as an object under `prefix`
+///
+/// Entries are streamed into the store one at a time as the archive arrives.
+/// Entry names are made relative and may not escape the prefix; directories and
+/// links are skipped. Objects stored before an error are left in place and listed
+/// in the error details.
+///
+/// Along with the response, returns the soft limits reached, each as of the
+/// last entry its quota covers.
+pub(crate) async fn extract_archive_upload(
+    object_service: Arc<dyn ObjectService>,
+    prefix: ObjectKey,
+    format: ArchiveFormat,
+    body: impl AsyncRead + Unpin,
+    custom_metadata: HashMap<String, String>,
+) -> Result<(StatusCode, Json<SuccessResponseDto>, Vec<QuotaWarning>), ApiError> {
+    let mut archive = ArchiveReader::new(body, format);
+    let mut stored: Vec<String> = Vec::new();
+    let mut quota_warnings: Vec<QuotaWarning> = Vec::new();
+
+    let failed = |error: ApiError, stored: &[String]| {
+        error.with_detail("stored_keys", serde_json::json!(stored))
+    };
+
+    loop {
+        let entry = match archive.next_entry().await {
+            Ok(Some(entry)) => entry,
+            Ok(None) => break,
+            Err(e) => {
+                return Err(failed(
+                    ApiError::BadRequest(format!("Invalid archive: {}", e)),
+                    &stored,
+                ));
+            }
+        };
+
+        let object_key = entry_key(&prefix, &entry.name).ok_or_else(|| {
+            failed(
+                ApiError::BadRequest(format!("Invalid archive entry name: {}", entry.name)),
+                &stored,
+            )
+        })?;
+
+        // Archive errors are passed through to the store so a truncated entry fails
+        // its upload instead of being stored short
+        let (sender, mut receiver) = mpsc::channel::<std::io::Result<Bytes>>(EXTRACT_CHANNEL_DEPTH);
+        let reader = StreamReader::new(futures::stream::poll_fn(move |cx| receiver.poll_recv(cx)));
+        let entries = &mut archive;
+        let feed = async move {
+            loop {
+                match entries.read_chunk().await {
+                    Ok(Some(chunk)) => {
+                        if sender.send(Ok(Bytes::from(chunk))).await.is_err() {
+                            // The store stopped reading and reports its own error
+                            return Ok(());
+                        }
+                    }
+                    Ok(None) => return Ok(()),
+                    Err(e) => {
+                        let message = e.to_string();
+                        let _ = sender.send(Err(e)).await;
+                        return Err(message);
+                    }
+                }
+            }
+        };
+        let store = object_service.create_object_stream(
+            object_key.clone(),
+            Box::new(reader),
+            None,
+            custom_metadata.clone(),
+        );
+
+        let (fed, result) = tokio::join!(feed, store);
+        if let Err(e) = fed {
+            return Err(failed(
+                ApiError::BadRequest(format!("Invalid archive entry {}: {}", entry.name, e)),
+                &stored,
+            ));
+        }
+        let (_, warnings) = result.map_err(|e| failed(e.into(), &stored))?;
+        for warning in warnings {
+            quota_warnings.retain(|earlier| earlier.prefix != warning.prefix);
+            quota_warnings.push(warning);
+        }
+        stored.push(object_key.as_str().to_string());
+    }
+
+    Ok((
+        StatusCode::CREATED,
+        Json(SuccessResponseDto::with_data(
+            &format!("Extracted {} objects from archive", stored.len()),
+            serde_json::json!({ "keys": stored }),
+        )),
+        quota_warnings,
+    ))
+}
+
+/// Key of an archive entry under the upload prefix, or None if the name is unsafe
+fn entry_key(prefix: &ObjectKey, name: &str) -> Option<ObjectKey> {
+    let mut segments = Vec::new();
+    for segment in name.split('/') {
+        match segment {
+            "" | "." => continue,
+            ".." => return None,
+            segment => segments.push(segment),
+        }
+    }
+    if segments.is_empty() {
+        return None;
+    }
+    ObjectKey::new(format!("{}/{}", prefix.as_str(), segments.join("/"))).ok()
+}
@@ -0,0 +1,77 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use std::sync::Arc;
+
+use crate::{
+    adapters::inbound::http::{
+        dto::{BackupJobDto, BackupRestoreDto, BackupRunDto, CreateBackupJobDto, RestoreBackupDto},
+        error::ApiError,
+    },
+    domain::value_objects::BucketName,
+    ports::services::BackupService,
+};
+
+/// Handle creating a recurring backup job
+pub async fn create_backup_job(
+    State(backup_service): State<Arc<dyn BackupService>>,
+    Json(dto): Json<CreateBackupJobDto>,
+) -> Result<(StatusCode, Json<BackupJobDto>), ApiError> {
+    let job = backup_service
+        .create_backup_job(BucketName::new(dto.bucket)?, &dto.schedule, dto.mode.into())
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(job.into())))
+}
+
+/// Handle listing the backup jobs
+pub async fn list_backup_jobs(
+    State(backup_service): State<Arc<dyn BackupService>>,
+) -> Result<Json<Vec<BackupJobDto>>, ApiError> {
+    let jobs = backup_service.list_backup_jobs().await?;
+    Ok(Json(jobs.into_iter().map(Into::into).collect()))
+}
+
+/// Handle deleting a backup job; backups already taken stay in the target
+pub async fn delete_backup_job(
+    State(backup_service): State<Arc<dyn BackupService>>,
+    Path(job_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    backup_service.delete_backup_job(&job_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Handle running a backup job now, outside its schedule
+pub async fn run_backup_job(
+    State(backup_service): State<Arc<dyn BackupService>>,
+    Path(job_id): Path<String>,
+) -> Result<Json<BackupRunDto>, ApiError> {
+    let run = backup_service.run_backup(&job_id).await?;
+    Ok(Json(run.into()))
+}
+
+/// Handle listing the run history of a backup job, newest first
+pub async fn list_backup_runs(
+    State(backup_service): State<Arc<dyn BackupService>>,
+    Path(job_id): Path<String>,
+) -> Result<Json<Vec<BackupRunDto>>, ApiError> {
+    let runs = backup_service.list_backup_runs(&job_id).await?;
+    Ok(Json(runs.into_iter().map(Into::into).collect()))
+}
+
+/// Handle restoring the objects of a backup run
+pub async fn restore_backup_run(
+    State(backup_service): State<Arc<dyn BackupService>>,
+    Path((job_id, run_id)): Path<(String, String)>,
+    dto: Option<Json<RestoreBackupDto>>,
+) -> Result<Json<BackupRestoreDto>, ApiError> {
+    let Json(dto) = dto.unwrap_or_default();
+    let bucket = dto.bucket.map(BucketName::new).transpose()?;
+
+    let restore = backup_service
+        .restore_backup(&job_id, &run_id, bucket, dto.overwrite)
+        .await?;
+    Ok(Json(restore.into()))
+}
@@ -0,0 +1,415 @@
+use axum::{
+    Json,
+    body::{Body, Bytes},
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use chrono::SecondsFormat;
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+use serde::Serialize;
+
+use crate::{
+    adapters::inbound::http::{
+        AppState,
+        dto::{
+            BucketSubresourceDto, BucketVersioningDto, ErrorResponseDto, ListMultipartUploadsDto,
+            ListObjectsV1QueryDto, S3CommonPrefixDto, S3ListBucketResultDto, S3ObjectDto,
+            S3VersioningConfigurationDto, VersioningStatusDto,
+        },
+        error::ApiError,
+        extract::BucketNamePath,
+        handlers::{multipart_handlers::list_multipart_uploads, object_handlers::quoted_etag},
+        middleware::region::ServerRegion,
+    },
+    domain::value_objects::BucketName,
+};
+
+/// Namespace of S3 XML documents
+pub(crate) const S3_XMLNS: &str = "http://s3.amazonaws.com/doc/2006-03-01/";
+
+/// Most keys a bucket listing returns at once, as in S3
+const MAX_LIST_KEYS: usize = 1000;
+
+/// Characters escaped in a listing with `encoding-type=url`
+const LISTING_ESCAPES: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~')
+    .remove(b'/');
+
+/// Handle GET on a bucket: GetBucketVersioning with `?versioning`,
+/// ListMultipartUploads with `?uploads`, otherwise the marker-based
+/// ListObjects (V1)
+pub async fn get_bucket(
+    State(app_state): State<AppState>,
+    BucketNamePath(bucket): BucketNamePath,
+    Query(subresource): Query<BucketSubresourceDto>,
+    Query(list): Query<ListObjectsV1QueryDto>,
+    Query(uploads): Query<ListMultipartUploadsDto>,
+) -> Result<Response, ApiError> {
+    if subresource.versioning.is_some() {
+        let config = app_state
+            .versioning_service
+            .get_versioning_configuration(&bucket)
+            .await?;
+        return Ok(versioning_response(
+            VersioningStatusDto::from(&config),
+            true,
+        ));
+    }
+    if uploads.uploads.is_some() {
+        return list_multipart_uploads(State(app_state), BucketNamePath(bucket), Query(uploads))
+            .await
+            .map(IntoResponse::into_response);
+    }
+    if list.list_type.is_some() {
+        return Err(ApiError::BadRequest(
+            "ListObjectsV2 is not supported, list without list-type".to_string(),
+        ));
+    }
+
+    list_objects_v1(&app_state, &bucket, list).await
+}
+
+/// Handle PUT on a bucket, of which only PutBucketVersioning (`?versioning`)
+/// is supported
+///
+/// The body is read as the S3 `VersioningConfiguration` document whatever its
+/// content type, as SDKs don't always label it.
+pub async fn put_bucket(
+    State(app_state): State<AppState>,
+    BucketNamePath(bucket): BucketNamePath,
+    Query(subresource): Query<BucketSubresourceDto>,
+    body: Bytes,
+) -> Result<StatusCode, ApiError> {
+    if subresource.versioning.is_none() {
+        return Err(ApiError::BadRequest(
+            "Unsupported bucket operation, expected ?versioning".to_string(),
+        ));
+    }
+
+    let status = parse_versioning_configuration(&body)?;
+    set_versioning(&app_state, &bucket, status).await?;
+    Ok(StatusCode::OK)
+}
+
+/// Handle HeadBucket, which SDKs send to check a bucket before using it
+///
+/// Buckets are key prefixes, so a bucket exists once it holds an object or an
+/// in-progress multipart upload, or has a lifecycle or versioning
+/// configuration; otherwise the answer is 404. A name S3 would reject is
+/// answered with 400, as S3 does, and 403 when the storage backend refuses to
+/// list the bucket.
+pub async fn head_bucket(
+    State(app_state): State<AppState>,
+    Path(bucket): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let bucket = BucketName::new(bucket).map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    let prefix = format!("{}/", bucket);
+
+    let objects = app_state
+        .object_service
+        .list_objects(Some(&prefix), Some(1))
+        .await?;
+    if !objects.is_empty() {
+        return Ok(StatusCode::OK);
+    }
+
+    let uploads = app_state
+        .object_service
+        .list_multipart_uploads(Some(&prefix), Some(1))
+        .await?;
+    if !uploads.is_empty() {
+        return Ok(StatusCode::OK);
+    }
+
+    if app_state
+        .lifecycle_service
+        .get_lifecycle_configuration(&bucket)
+        .await?
+        .is_some()
+        || app_state
+            .versioning_service
+            .has_versioning_configuration(&bucket)
+            .await?
+    {
+        return Ok(StatusCode::OK);
+    }
+
+    Err(ApiError::NotFound(format!(
+        "Bucket {} does not exist",
+        bucket
+    )))
+}
+
+/// Handle ListObjects (V1): up to `max-keys` keys after the `marker`, with the
+/// keys sharing a prefix up to the `delimiter` rolled up into one common prefix
+///
+/// Keys are relative to the bucket and listed in ascending order. `NextMarker`
+/// is only reported when listing with a delimiter; otherwise clients continue
+/// after the last key, as S3 documents.
+async fn list_objects_v1(
+    app_state: &AppState,
+    bucket: &BucketName,
+    params: ListObjectsV1QueryDto,
+) -> Result<Response, ApiError> {
+    let url_encoded = match params.encoding_type.as_deref() {
+        None => false,
+        Some("url") => true,
+        Some(other) => {
+            return Err(ApiError::BadRequest(format!(
+                "Invalid encoding-type '{}', expected url",
+                other
+            )));
+        }
+    };
+    let encode = |value: &str| match url_encoded {
+        true => utf8_percent_encode(value, LISTING_ESCAPES).to_string(),
+        false => value.to_string(),
+    };
+
+    let prefix = params.prefix.unwrap_or_default();
+    let marker = params.marker.unwrap_or_default();
+    let delimiter = params.delimiter.filter(|delimiter| !delimiter.is_empty());
+    let max_keys = params.max_keys.unwrap_or(MAX_LIST_KEYS).min(MAX_LIST_KEYS);
+
+    let bucket_prefix = format!("{}/", bucket);
+    let start_after = (!marker.is_empty()).then(|| format!("{}{}", bucket_prefix, marker));
+    // Without a delimiter every key is an entry of the page, so one key more
+    // than the page tells whether the listing is truncated
+    let limit = delimiter.is_none().then_some(max_keys + 1);
+    let objects = app_state
+        .object_service
+        .list_objects_after(
+            Some(&format!("{}{}", bucket_prefix, prefix)),
+            start_after.as_deref(),
+            limit,
+        )
+        .await?;
+
+    let mut contents = Vec::new();
+    let mut common_prefixes: Vec<String> = Vec::new();
+    // Last key or common prefix of the page
+    let mut last = None;
+    let mut is_truncated = false;
+    for object in &objects {
+        let Some(key) = object.key.as_str().strip_prefix(&bucket_prefix) else {
+            continue;
+        };
+        let rolled_up = delimiter.as_deref().and_then(|delimiter| {
+            let rest = key.strip_prefix(prefix.as_str())?;
+            let end = rest.find(delimiter)? + delimiter.len();
+            Some(&key[..prefix.len() + end])
+        });
+        // Keys are sorted, so the keys under a common prefix follow each other,
+        // and a marker naming the prefix skips all of them
+        if rolled_up.is_some_and(|common| {
+            common == marker || common_prefixes.last().map(String::as_str) == Some(common)
+        }) {
+            continue;
+        }
+        if contents.len() + common_prefixes.len() == max_keys {
+            is_truncated = true;
+            break;
+        }
+
+        match rolled_up {
+            Some(common) => {
+                common_prefixes.push(common.to_string());
+                last = Some(common);
+            }
+            None => {
+                contents.push(S3ObjectDto {
+                    key: encode(key),
+                    last_modified: object
+                        .last_modified
+                        .to_rfc3339_opts(SecondsFormat::Millis, true),
+                    etag: object.etag.as_deref().map(quoted_etag),
+                    size: object.size,
+                    storage_class: "STANDARD".to_string(),
+                });
+                last = Some(key);
+            }
+        }
+    }
+
+    xml_response(&S3ListBucketResultDto {
+        xmlns: S3_XMLNS.to_string(),
+        name: bucket.to_string(),
+        prefix: encode(&prefix),
+        marker: encode(&marker),
+        max_keys,
+        next_marker: last
+            .filter(|_| is_truncated && delimiter.is_some())
+            .map(encode),
+        delimiter: delimiter.as_deref().map(encode),
+        encoding_type: url_encoded.then(|| "url".to_string()),
+        is_truncated,
+        contents,
+        common_prefixes: common_prefixes
+            .iter()
+            .map(|prefix| S3CommonPrefixDto {
+                prefix: encode(prefix),
+            })
+            .collect(),
+    })
+}
+
+/// Handle GetBucketLocation, reporting the region the bucket lives in
+///
+/// The body is the S3 `LocationConstraint` document, which is empty for
+/// us-east-1, so SDKs that probe a bucket's location before signing can parse it.
+pub async fn get_bucket_location(
+    State(server_region): State<ServerRegion>,
+    BucketNamePath(_): BucketNamePath,
+) -> Result<Response<Body>, ApiError> {
+    let body = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <LocationConstraint xmlns=\"http://s3.amazonaws.com/doc/2006-03-01/\">{}</LocationConstraint>",
+        server_region.location_constraint()
+    );
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("content-type", "application/xml")
+        .body(Body::from(body))
+        .unwrap())
+}
+
+/// Handle reading the versioning state of a bucket
+///
+/// Clients that accept XML get the S3 `VersioningConfiguration` document,
+/// others the JSON form.
+pub async fn get_bucket_versioning(
+    State(app_state): State<AppState>,
+    BucketNamePath(bucket): BucketNamePath,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let config = app_state
+        .versioning_service
+        .get_versioning_configuration(&bucket)
+        .await?;
+
+    Ok(versioning_response(
+        VersioningStatusDto::from(&config),
+        wants_xml(&headers, header::ACCEPT),
+    ))
+}
+
+/// Handle enabling or suspending versioning of a bucket
+///
+/// The body is either the JSON form, `{"status": "Enabled"}`, or the S3
+/// `VersioningConfiguration` document when sent as XML. The response reports
+/// the resulting state in the same form.
+pub async fn put_bucket_versioning(
+    State(app_state): State<AppState>,
+    BucketNamePath(bucket): BucketNamePath,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, ApiError> {
+    let xml = wants_xml(&headers, header::CONTENT_TYPE);
+
+    let status = match xml {
+        true => parse_versioning_configuration(&body)?,
+        false => {
+            let dto: BucketVersioningDto = serde_json::from_slice(&body).map_err(|e| {
+                ApiError::BadRequest(format!("Invalid versioning configuration: {}", e))
+            })?;
+            dto.status
+        }
+    };
+
+    set_versioning(&app_state, &bucket, status).await?;
+    Ok(versioning_response(status, xml))
+}
+
+/// The versioning state requested by an S3 `VersioningConfiguration` document
+fn parse_versioning_configuration(body: &[u8]) -> Result<VersioningStatusDto, ApiError> {
+    let body = std::str::from_utf8(body)
+        .map_err(|_| ApiError::BadRequest("Body is not valid UTF-8".to_string()))?;
+    let config: S3VersioningConfigurationDto = quick_xml::de::from_str(body)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid versioning configuration: {}", e)))?;
+
+    if config.mfa_delete.as_deref() == Some("Enabled") {
+        return Err(ApiError::BadRequest(
+            "MFA delete is not supported".to_string(),
+        ));
+    }
+    config
+        .status
+        .ok_or_else(|| ApiError::BadRequest("Versioning configuration has no Status".to_string()))
+}
+
+async fn set_versioning(
+    app_state: &AppState,
+    bucket: &BucketName,
+    status: VersioningStatusDto,
+) -> Result<(), ApiError> {
+    match status {
+        VersioningStatusDto::Enabled => {
+            app_state
+                .versioning_service
+                .enable_versioning(bucket)
+                .await?
+        }
+        VersioningStatusDto::Suspended => {
+            app_state
+                .versioning_service
+                .disable_versioning(bucket)
+                .await?
+        }
+    }
+    Ok(())
+}
+
+/// Whether the request header `name` names an XML media type
+fn wants_xml(headers: &HeaderMap, name: header::HeaderName) -> bool {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.contains("xml"))
+}
+
+fn versioning_response(status: VersioningStatusDto, xml: bool) -> Response {
+    if !xml {
+        return Json(BucketVersioningDto { status }).into_response();
+    }
+
+    let status = match status {
+        VersioningStatusDto::Enabled => "Enabled",
+        VersioningStatusDto::Suspended => "Suspended",
+    };
+    let body = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <VersioningConfiguration xmlns=\"http://s3.amazonaws.com/doc/2006-03-01/\">\
+         <Status>{}</Status></VersioningConfiguration>",
+        status
+    );
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("content-type", "application/xml")
+        .body(Body::from(body))
+        .unwrap()
+}
+
+/// An S3 XML document as the response body
+pub(crate) fn xml_response<T: Serialize>(document: &T) -> Result<Response, ApiError> {
+    let body = quick_xml::se::to_string(document).map_err(|e| {
+        ApiError::Custom(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorResponseDto::internal_error(&format!("Failed to write XML response: {}", e)),
+        )
+    })?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("content-type", "application/xml")
+        .body(Body::from(format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n{}",
+            body
+        )))
+        .unwrap())
+}
@@ -0,0 +1,57 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+};
+
+use crate::{
+    adapters::inbound::http::{
+        dto::{DatasetDto, DatasetQueryDto, PublishDatasetDto},
+        error::ApiError,
+        router::AppState,
+    },
+    domain::value_objects::{ObjectKey, VersionId},
+};
+
+/// Handle publishing a new manifest for a dataset
+///
+/// Consumers reading the dataset see the new set of object versions as soon
+/// as this returns, and never a mix of the old and new sets.
+pub async fn publish_dataset(
+    State(app_state): State<AppState>,
+    Path(name): Path<String>,
+    Json(dto): Json<PublishDatasetDto>,
+) -> Result<Json<DatasetDto>, ApiError> {
+    let objects = dto
+        .objects
+        .into_iter()
+        .map(|object| {
+            Ok((
+                ObjectKey::new(object.key)?,
+                object.version_id.map(VersionId::new).transpose()?,
+            ))
+        })
+        .collect::<Result<Vec<_>, ApiError>>()?;
+
+    let manifest = app_state
+        .versioning_service
+        .publish_dataset(&name, objects)
+        .await?;
+
+    Ok(Json(manifest.into()))
+}
+
+/// Handle resolving the manifest of a dataset, the latest unless `?version` is given
+pub async fn get_dataset(
+    State(app_state): State<AppState>,
+    Path(name): Path<String>,
+    Query(params): Query<DatasetQueryDto>,
+) -> Result<Json<DatasetDto>, ApiError> {
+    let manifest_version = params.version.map(VersionId::new).transpose()?;
+
+    let manifest = app_state
+        .versioning_service
+        .get_dataset(&name, manifest_version.as_ref())
+        .await?;
+
+    Ok(Json(manifest.into()))
+}
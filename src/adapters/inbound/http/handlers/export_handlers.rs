@@ -0,0 +1,253 @@
+use axum::{
+    Json,
+    body::{Body, Bytes},
+    extract::State,
+    http::StatusCode,
+    response::Response,
+};
+use chrono::Utc;
+use futures::stream;
+use object_store::{
+    Attribute, Attributes, ObjectStore, aws::AmazonS3Builder, buffered::BufWriter,
+    path::Path as StorePath,
+};
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+
+use crate::{
+    adapters::inbound::http::{
+        dto::{ErrorResponseDto, ExportBucketDto, ExportCheckpointDto, ExportTargetDto},
+        error::ApiError,
+        extract::BucketNamePath,
+        router::AppState,
+    },
+    domain::value_objects::ObjectKey,
+    ports::services::ObjectService,
+};
+
+/// Prefix in the target under which the checkpoints of exports are kept
+const EXPORT_CHECKPOINT_PREFIX: &str = ".export-checkpoints";
+
+/// Number of objects exported between checkpoints
+const EXPORT_CHECKPOINT_INTERVAL: u64 = 100;
+
+/// Number of progress lines buffered for a slow client
+const PROGRESS_CHANNEL_DEPTH: usize = 16;
+
+/// Handle exporting every current object of a bucket to an external
+/// S3-compatible bucket
+///
+/// The export runs in a background task, in key order, and the response
+/// streams its progress as one JSON checkpoint per line. Every
+/// `EXPORT_CHECKPOINT_INTERVAL` objects the checkpoint is also written to the
+/// target, so repeating the request after a dropped connection, a failure or a
+/// restart resumes after the last recorded key. A completed export starts over.
+pub async fn export_bucket(
+    State(app_state): State<AppState>,
+    BucketNamePath(bucket): BucketNamePath,
+    Json(request): Json<ExportBucketDto>,
+) -> Result<Response<Body>, ApiError> {
+    let target = target_store(&request.target)?;
+    let checkpoint_path = StorePath::from(format!(
+        "{}{}/{}.json",
+        request.target.prefix, EXPORT_CHECKPOINT_PREFIX, bucket
+    ));
+
+    let previous = match request.restart {
+        true => None,
+        false => read_checkpoint(target.as_ref(), &checkpoint_path).await?,
+    };
+    let now = Utc::now();
+    let checkpoint = match previous {
+        Some(checkpoint) if !checkpoint.completed && checkpoint.bucket == bucket.as_str() => {
+            ExportCheckpointDto {
+                error: None,
+                updated_at: now,
+                ..checkpoint
+            }
+        }
+        _ => ExportCheckpointDto {
+            bucket: bucket.to_string(),
+            last_key: None,
+            objects_exported: 0,
+            bytes_exported: 0,
+            completed: false,
+            started_at: now,
+            updated_at: now,
+            error: None,
+        },
+    };
+
+    let bucket_prefix = format!("{}/", bucket);
+    let mut keys: Vec<ObjectKey> = app_state
+        .object_service
+        .list_objects(Some(&bucket_prefix), None)
+        .await?
+        .into_iter()
+        .map(|info| info.key)
+        .filter(|key| {
+            let name = key
+                .as_str()
+                .strip_prefix(&bucket_prefix)
+                .unwrap_or_default();
+            checkpoint
+                .last_key
+                .as_deref()
+                .is_none_or(|last| name > last)
+        })
+        .collect();
+    keys.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+
+    let (progress, lines) = mpsc::channel(PROGRESS_CHANNEL_DEPTH);
+    let export = Export {
+        object_service: app_state.object_service.clone(),
+        target,
+        target_prefix: request.target.prefix,
+        bucket_prefix,
+        checkpoint_path,
+        progress,
+    };
+    // The export carries on if the client goes away, to be resumed later
+    tokio::spawn(export.run(keys, checkpoint));
+
+    let body = stream::unfold(lines, |mut lines| async move {
+        let line = lines.recv().await?;
+        Some((Ok::<_, Infallible>(line), lines))
+    });
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("content-type", "application/x-ndjson")
+        .body(Body::from_stream(body))
+        .unwrap())
+}
+
+/// Object store of the external bucket, reached with the request's own
+/// credentials
+///
+/// Nothing is taken from the server's AWS environment, so an export can't
+/// sign requests to a host of the caller's choosing with the server's
+/// credentials.
+fn target_store(target: &ExportTargetDto) -> Result<Arc<dyn ObjectStore>, ApiError> {
+    let mut builder = AmazonS3Builder::new()
+        .with_bucket_name(&target.bucket)
+        .with_access_key_id(&target.access_key)
+        .with_secret_access_key(&target.secret_key);
+    if let Some(region) = &target.region {
+        builder = builder.with_region(region);
+    }
+    if let Some(endpoint) = &target.endpoint {
+        builder = builder
+            .with_endpoint(endpoint)
+            .with_allow_http(endpoint.starts_with("http://"));
+    }
+    let store = builder
+        .build()
+        .map_err(|e| ApiError::BadRequest(format!("Invalid export target: {}", e)))?;
+    Ok(Arc::new(store))
+}
+
+async fn read_checkpoint(
+    target: &dyn ObjectStore,
+    path: &StorePath,
+) -> Result<Option<ExportCheckpointDto>, ApiError> {
+    let unreachable = |e: object_store::Error| {
+        ApiError::Custom(
+            StatusCode::BAD_GATEWAY,
+            ErrorResponseDto::internal_error(&format!("Failed to read export checkpoint: {}", e)),
+        )
+    };
+
+    let data = match target.get(path).await {
+        Ok(result) => result.bytes().await.map_err(unreachable)?,
+        Err(object_store::Error::NotFound { .. }) => return Ok(None),
+        Err(e) => return Err(unreachable(e)),
+    };
+    // An unreadable checkpoint only costs exporting everything again
+    Ok(serde_json::from_slice(&data).ok())
+}
+
+/// A running export and where it reports its progress
+struct Export {
+    object_service: Arc<dyn ObjectService>,
+    target: Arc<dyn ObjectStore>,
+    target_prefix: String,
+    bucket_prefix: String,
+    checkpoint_path: StorePath,
+    progress: mpsc::Sender<Bytes>,
+}
+
+impl Export {
+    async fn run(self, keys: Vec<ObjectKey>, mut checkpoint: ExportCheckpointDto) {
+        for key in keys {
+            let name = key
+                .as_str()
+                .strip_prefix(&self.bucket_prefix)
+                .unwrap_or(key.as_str())
+                .to_string();
+
+            match self.export_object(&key, &name).await {
+                Ok(bytes) => {
+                    checkpoint.last_key = Some(name);
+                    checkpoint.objects_exported += 1;
+                    checkpoint.bytes_exported += bytes;
+                    checkpoint.updated_at = Utc::now();
+                }
+                Err(e) => {
+                    checkpoint.error = Some(format!("{}: {}", key, e));
+                    checkpoint.updated_at = Utc::now();
+                    self.save(&checkpoint).await;
+                    return;
+                }
+            }
+
+            if checkpoint
+                .objects_exported
+                .is_multiple_of(EXPORT_CHECKPOINT_INTERVAL)
+            {
+                self.save(&checkpoint).await;
+            }
+        }
+
+        checkpoint.completed = true;
+        checkpoint.updated_at = Utc::now();
+        self.save(&checkpoint).await;
+    }
+
+    /// Stream one object to the target, returning its size
+    async fn export_object(&self, key: &ObjectKey, name: &str) -> std::io::Result<u64> {
+        let (metadata, mut reader) = self
+            .object_service
+            .get_object_stream(key, None)
+            .await
+            .map_err(|e| std::io::Error::other(e.to_string()))?;
+
+        let mut attributes = Attributes::new();
+        if let Some(content_type) = metadata.content_type {
+            attributes.insert(Attribute::ContentType, content_type.into());
+        }
+        for (name, value) in metadata.custom_metadata {
+            attributes.insert(Attribute::Metadata(name.into()), value.into());
+        }
+
+        let path = StorePath::from(format!("{}{}", self.target_prefix, name));
+        let mut writer = BufWriter::new(self.target.clone(), path).with_attributes(attributes);
+        let bytes = tokio::io::copy(&mut reader, &mut writer).await?;
+        writer.shutdown().await?;
+        Ok(bytes)
+    }
+
+    /// Record the checkpoint in the target and report it to the client
+    async fn save(&self, checkpoint: &ExportCheckpointDto) {
+        let mut checkpoint = checkpoint.clone();
+        let data = serde_json::to_vec(&checkpoint).unwrap_or_default();
+        if let Err(e) = self.target.put(&self.checkpoint_path, data.into()).await {
+            checkpoint.error = Some(format!("Failed to write export checkpoint: {}", e));
+        }
+
+        let mut line = serde_json::to_vec(&checkpoint).unwrap_or_default();
+        line.push(b'\n');
+        let _ = self.progress.send(Bytes::from(line)).await;
+    }
+}
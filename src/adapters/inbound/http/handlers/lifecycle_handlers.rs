@@ -3,62 +3,59 @@ use axum::{
     extract::{Path, State},
     http::StatusCode,
 };
-use std::collections::HashMap;
+use chrono::Utc;
+use serde::Deserialize;
 
 use crate::{
     adapters::inbound::http::{
         dto::{
-            ApplicableActionDto, ErrorResponseDto, EvaluateLifecycleDto, LifecycleConfigurationDto,
-            LifecycleEvaluationResponseDto, LifecycleRuleDto, SuccessResponseDto,
+            ApplicableActionDto, ApplyLifecycleTemplateDto, CopyLifecycleDto, EvaluateLifecycleDto,
+            LifecycleConfigurationDto, LifecycleDiffDto, LifecycleEvaluationResponseDto,
+            LifecycleProcessingStatusDto, LifecycleRuleDto, LifecycleSimulationResponseDto,
+            LifecycleTemplateDto, LifecycleValidationResponseDto, SimulateLifecycleDto,
+            SuccessResponseDto,
         },
+        error::ApiError,
+        extract::BucketNamePath,
         router::AppState,
     },
     domain::{
-        models::EvaluateLifecycleRequest,
+        errors::LifecycleError,
+        models::{
+            EvaluateLifecycleRequest, LifecycleConfiguration, LifecycleConfigurationDiff,
+            LifecycleRule, LifecycleTemplate, PrefixRewrite,
+        },
         value_objects::{BucketName, ObjectKey},
     },
 };
 
+/// Path parameter naming a rule of a bucket's lifecycle configuration
+#[derive(Debug, Deserialize)]
+pub struct RuleIdPath {
+    pub rule_id: String,
+}
+
+/// Path parameter naming a lifecycle template
+#[derive(Debug, Deserialize)]
+pub struct TemplatePath {
+    pub template: String,
+}
+
 /// Handle setting lifecycle configuration for a bucket
 pub async fn set_lifecycle_configuration(
     State(app_state): State<AppState>,
-    Path(bucket_name): Path<String>,
+    BucketNamePath(bucket): BucketNamePath,
     Json(config_dto): Json<LifecycleConfigurationDto>,
-) -> Result<(StatusCode, Json<SuccessResponseDto>), (StatusCode, Json<ErrorResponseDto>)> {
+) -> Result<(StatusCode, Json<SuccessResponseDto>), ApiError> {
     let lifecycle_service = &app_state.lifecycle_service;
 
-    // Validate bucket name
-    let bucket = BucketName::new(bucket_name).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponseDto::bad_request(&format!(
-                "Invalid bucket name: {}",
-                e
-            ))),
-        )
-    })?;
-
     // Convert DTO to domain model
-    let config = config_dto
-        .try_into()
-        .map_err(|e: crate::domain::errors::ValidationError| {
-            (
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponseDto::bad_request(&format!(
-                    "Invalid configuration: {}",
-                    e
-                ))),
-            )
-        })?;
+    let config = LifecycleConfiguration::try_from(config_dto)?;
 
     // Set the configuration
     lifecycle_service
         .set_lifecycle_configuration(&bucket, config)
-        .await
-        .map_err(|e| {
-            let status_code = StatusCode::from(e.clone());
-            (status_code, Json(ErrorResponseDto::from_lifecycle_error(e)))
-        })?;
+        .await?;
 
     Ok((
         StatusCode::OK,
@@ -71,67 +68,94 @@ pub async fn set_lifecycle_configuration(
 /// Handle getting lifecycle configuration for a bucket
 pub async fn get_lifecycle_configuration(
     State(app_state): State<AppState>,
-    Path(bucket_name): Path<String>,
-) -> Result<Json<LifecycleConfigurationDto>, (StatusCode, Json<ErrorResponseDto>)> {
+    BucketNamePath(bucket): BucketNamePath,
+) -> Result<Json<LifecycleConfigurationDto>, ApiError> {
     let lifecycle_service = &app_state.lifecycle_service;
 
-    // Validate bucket name
-    let bucket = BucketName::new(bucket_name).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponseDto::bad_request(&format!(
-                "Invalid bucket name: {}",
-                e
-            ))),
-        )
-    })?;
-
     // Get the configuration
     let config = lifecycle_service
         .get_lifecycle_configuration(&bucket)
-        .await
-        .map_err(|e| {
-            let status_code = StatusCode::from(e.clone());
-            (status_code, Json(ErrorResponseDto::from_lifecycle_error(e)))
-        })?;
+        .await?;
 
     match config {
         Some(config) => Ok(Json(config.into())),
-        None => Err((
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponseDto::bad_request(
-                "Lifecycle configuration not found",
-            )),
-        )),
+        None => Err(LifecycleError::ConfigurationNotFound { bucket }.into()),
+    }
+}
+
+/// Handle copying a bucket's lifecycle configuration to other buckets
+///
+/// Targets are updated in order, replacing their configuration. If one fails,
+/// the targets already updated are listed in the error details.
+pub async fn copy_lifecycle_configuration(
+    State(app_state): State<AppState>,
+    BucketNamePath(source): BucketNamePath,
+    Json(request): Json<CopyLifecycleDto>,
+) -> Result<Json<SuccessResponseDto>, ApiError> {
+    let lifecycle_service = &app_state.lifecycle_service;
+
+    // Validate every target bucket name before changing anything
+    let targets = request
+        .targets
+        .into_iter()
+        .map(BucketName::new)
+        .collect::<Result<Vec<_>, _>>()?;
+    let prefix_rewrite = request.rewrite_prefix.map(PrefixRewrite::from);
+
+    let mut copied: Vec<String> = Vec::new();
+    for target in &targets {
+        if let Err(e) = lifecycle_service
+            .copy_lifecycle_configuration(&source, target, prefix_rewrite.as_ref())
+            .await
+        {
+            return Err(ApiError::from(e).with_detail("copied_to", serde_json::json!(copied)));
+        }
+        copied.push(target.as_str().to_string());
     }
+
+    Ok(Json(SuccessResponseDto::with_data(
+        &format!("Lifecycle configuration copied to {} buckets", copied.len()),
+        serde_json::json!({ "targets": copied }),
+    )))
+}
+
+/// Handle validating a proposed lifecycle configuration without applying it
+///
+/// Returns the validation errors and warnings along with the rule changes the
+/// configuration would make to the bucket's active configuration.
+pub async fn validate_lifecycle_configuration(
+    State(app_state): State<AppState>,
+    BucketNamePath(bucket): BucketNamePath,
+    Json(config_dto): Json<LifecycleConfigurationDto>,
+) -> Result<Json<LifecycleValidationResponseDto>, ApiError> {
+    let lifecycle_service = &app_state.lifecycle_service;
+
+    // Convert DTO to domain model
+    let config = LifecycleConfiguration::try_from(config_dto)?;
+
+    let validation = lifecycle_service.validate_configuration(&config).await?;
+    let active = lifecycle_service
+        .get_lifecycle_configuration(&bucket)
+        .await?;
+
+    let diff = LifecycleConfigurationDiff::between(active.as_ref(), &config);
+    Ok(Json(LifecycleValidationResponseDto::new(
+        validation,
+        LifecycleDiffDto::new(diff, active.is_some()),
+    )))
 }
 
 /// Handle deleting lifecycle configuration for a bucket
 pub async fn delete_lifecycle_configuration(
     State(app_state): State<AppState>,
-    Path(bucket_name): Path<String>,
-) -> Result<(StatusCode, Json<SuccessResponseDto>), (StatusCode, Json<ErrorResponseDto>)> {
+    BucketNamePath(bucket): BucketNamePath,
+) -> Result<(StatusCode, Json<SuccessResponseDto>), ApiError> {
     let lifecycle_service = &app_state.lifecycle_service;
 
-    // Validate bucket name
-    let bucket = BucketName::new(bucket_name).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponseDto::bad_request(&format!(
-                "Invalid bucket name: {}",
-                e
-            ))),
-        )
-    })?;
-
     // Delete the configuration
     lifecycle_service
         .delete_lifecycle_configuration(&bucket)
-        .await
-        .map_err(|e| {
-            let status_code = StatusCode::from(e.clone());
-            (status_code, Json(ErrorResponseDto::from_lifecycle_error(e)))
-        })?;
+        .await?;
 
     Ok((
         StatusCode::OK,
@@ -145,19 +169,11 @@ pub async fn delete_lifecycle_configuration(
 pub async fn evaluate_object_lifecycle(
     State(app_state): State<AppState>,
     Json(request_dto): Json<EvaluateLifecycleDto>,
-) -> Result<Json<LifecycleEvaluationResponseDto>, (StatusCode, Json<ErrorResponseDto>)> {
+) -> Result<Json<LifecycleEvaluationResponseDto>, ApiError> {
     let lifecycle_service = &app_state.lifecycle_service;
 
     // Create object key
-    let object_key = ObjectKey::new(request_dto.key).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponseDto::bad_request(&format!(
-                "Invalid object key: {}",
-                e
-            ))),
-        )
-    })?;
+    let object_key = ObjectKey::new(request_dto.key)?;
 
     // Create evaluation request
     let request = EvaluateLifecycleRequest {
@@ -169,72 +185,17 @@ pub async fn evaluate_object_lifecycle(
         object_tags: request_dto.object_tags.unwrap_or_default(),
         is_delete_marker: request_dto.is_delete_marker.unwrap_or(false),
         is_current_version: request_dto.is_current_version.unwrap_or(true),
+        delete_at: request_dto.delete_at,
     };
 
     // Evaluate lifecycle
-    let result = lifecycle_service
-        .evaluate_object_lifecycle(request)
-        .await
-        .map_err(|e| {
-            let status_code = StatusCode::from(e.clone());
-            (status_code, Json(ErrorResponseDto::from_lifecycle_error(e)))
-        })?;
+    let result = lifecycle_service.evaluate_object_lifecycle(request).await?;
 
     // Convert to DTO
     let actions_dto: Vec<ApplicableActionDto> = result
         .actions_to_apply
         .into_iter()
-        .map(|action| {
-            let mut details = HashMap::new();
-
-            // Add action-specific details
-            match &action.action {
-                crate::domain::models::LifecycleAction::Expiration { days, date } => {
-                    if let Some(days) = days {
-                        details.insert(
-                            "days".to_string(),
-                            serde_json::Value::Number((*days).into()),
-                        );
-                    }
-                    if let Some(date) = date {
-                        details.insert(
-                            "date".to_string(),
-                            serde_json::Value::String(date.to_rfc3339()),
-                        );
-                    }
-                }
-                crate::domain::models::LifecycleAction::Transition {
-                    days,
-                    date,
-                    storage_class,
-                } => {
-                    if let Some(days) = days {
-                        details.insert(
-                            "days".to_string(),
-                            serde_json::Value::Number((*days).into()),
-                        );
-                    }
-                    if let Some(date) = date {
-                        details.insert(
-                            "date".to_string(),
-                            serde_json::Value::String(date.to_rfc3339()),
-                        );
-                    }
-                    details.insert(
-                        "storage_class".to_string(),
-                        serde_json::Value::String(storage_class.as_str().to_string()),
-                    );
-                }
-                _ => {}
-            }
-
-            ApplicableActionDto {
-                rule_id: action.rule_id,
-                action_type: format!("{:?}", action.action),
-                reason: action.reason,
-                details,
-            }
-        })
+        .map(ApplicableActionDto::from)
         .collect();
 
     Ok(Json(LifecycleEvaluationResponseDto {
@@ -242,46 +203,48 @@ pub async fn evaluate_object_lifecycle(
     }))
 }
 
+/// Handle a lifecycle dry run, reporting what the rules would do to existing objects
+pub async fn simulate_bucket_lifecycle(
+    State(app_state): State<AppState>,
+    BucketNamePath(bucket): BucketNamePath,
+    Json(request): Json<SimulateLifecycleDto>,
+) -> Result<Json<LifecycleSimulationResponseDto>, ApiError> {
+    let lifecycle_service = &app_state.lifecycle_service;
+
+    let config = match request.rules {
+        Some(rules) => {
+            let rules = rules
+                .into_iter()
+                .map(LifecycleRule::try_from)
+                .collect::<Result<Vec<_>, _>>()?;
+            Some(LifecycleConfiguration {
+                bucket: bucket.clone(),
+                rules,
+            })
+        }
+        None => None,
+    };
+
+    let results = lifecycle_service
+        .simulate_bucket_lifecycle(&bucket, config, request.as_of.unwrap_or_else(Utc::now))
+        .await?;
+
+    Ok(Json(results.into()))
+}
+
 /// Handle adding a new lifecycle rule
 pub async fn add_lifecycle_rule(
     State(app_state): State<AppState>,
-    Path(bucket_name): Path<String>,
+    BucketNamePath(bucket): BucketNamePath,
     Json(rule_dto): Json<LifecycleRuleDto>,
-) -> Result<(StatusCode, Json<SuccessResponseDto>), (StatusCode, Json<ErrorResponseDto>)> {
+) -> Result<(StatusCode, Json<SuccessResponseDto>), ApiError> {
     let lifecycle_service = &app_state.lifecycle_service;
 
-    // Validate bucket name
-    let bucket = BucketName::new(bucket_name).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponseDto::bad_request(&format!(
-                "Invalid bucket name: {}",
-                e
-            ))),
-        )
-    })?;
-
     // Convert DTO to domain model
-    let rule = rule_dto
-        .try_into()
-        .map_err(|e: crate::domain::errors::ValidationError| {
-            (
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponseDto::bad_request(&format!(
-                    "Invalid rule: {}",
-                    e
-                ))),
-            )
-        })?;
+    let rule = LifecycleRule::try_from(rule_dto)?;
 
     // Add the rule
-    lifecycle_service
-        .add_rule(&bucket, rule)
-        .await
-        .map_err(|e| {
-            let status_code = StatusCode::from(e.clone());
-            (status_code, Json(ErrorResponseDto::from_lifecycle_error(e)))
-        })?;
+    lifecycle_service.add_rule(&bucket, rule).await?;
 
     Ok((
         StatusCode::CREATED,
@@ -289,32 +252,57 @@ pub async fn add_lifecycle_rule(
     ))
 }
 
+/// Handle listing the available lifecycle rule templates
+pub async fn list_lifecycle_templates() -> Json<Vec<LifecycleTemplateDto>> {
+    let templates = LifecycleTemplate::ALL
+        .into_iter()
+        .map(|template| LifecycleTemplateDto {
+            name: template.name().to_string(),
+            description: template.description().to_string(),
+            rule: template.rule(None, None).into(),
+        })
+        .collect();
+
+    Json(templates)
+}
+
+/// Handle adding a lifecycle rule built from a named template
+pub async fn apply_lifecycle_template(
+    State(app_state): State<AppState>,
+    BucketNamePath(bucket): BucketNamePath,
+    Path(TemplatePath { template }): Path<TemplatePath>,
+    body: Option<Json<ApplyLifecycleTemplateDto>>,
+) -> Result<(StatusCode, Json<SuccessResponseDto>), ApiError> {
+    let lifecycle_service = &app_state.lifecycle_service;
+
+    let template = LifecycleTemplate::from_name(&template)
+        .ok_or_else(|| ApiError::NotFound(format!("Unknown lifecycle template: {}", template)))?;
+
+    let Json(options) = body.unwrap_or_default();
+    let rule = template.rule(options.prefix, options.rule_id);
+    let rule_dto = LifecycleRuleDto::from(rule.clone());
+
+    lifecycle_service.add_rule(&bucket, rule).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(SuccessResponseDto::with_data(
+            &format!("Lifecycle template {} applied", template.name()),
+            serde_json::json!(rule_dto),
+        )),
+    ))
+}
+
 /// Handle removing a lifecycle rule
 pub async fn remove_lifecycle_rule(
     State(app_state): State<AppState>,
-    Path((bucket_name, rule_id)): Path<(String, String)>,
-) -> Result<(StatusCode, Json<SuccessResponseDto>), (StatusCode, Json<ErrorResponseDto>)> {
+    BucketNamePath(bucket): BucketNamePath,
+    Path(RuleIdPath { rule_id }): Path<RuleIdPath>,
+) -> Result<(StatusCode, Json<SuccessResponseDto>), ApiError> {
     let lifecycle_service = &app_state.lifecycle_service;
 
-    // Validate bucket name
-    let bucket = BucketName::new(bucket_name).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponseDto::bad_request(&format!(
-                "Invalid bucket name: {}",
-                e
-            ))),
-        )
-    })?;
-
     // Remove the rule
-    lifecycle_service
-        .remove_rule(&bucket, &rule_id)
-        .await
-        .map_err(|e| {
-            let status_code = StatusCode::from(e.clone());
-            (status_code, Json(ErrorResponseDto::from_lifecycle_error(e)))
-        })?;
+    lifecycle_service.remove_rule(&bucket, &rule_id).await?;
 
     Ok((
         StatusCode::OK,
@@ -327,29 +315,13 @@ pub async fn remove_lifecycle_rule(
 /// Handle enabling a lifecycle rule
 pub async fn enable_lifecycle_rule(
     State(app_state): State<AppState>,
-    Path((bucket_name, rule_id)): Path<(String, String)>,
-) -> Result<(StatusCode, Json<SuccessResponseDto>), (StatusCode, Json<ErrorResponseDto>)> {
+    BucketNamePath(bucket): BucketNamePath,
+    Path(RuleIdPath { rule_id }): Path<RuleIdPath>,
+) -> Result<(StatusCode, Json<SuccessResponseDto>), ApiError> {
     let lifecycle_service = &app_state.lifecycle_service;
 
-    // Validate bucket name
-    let bucket = BucketName::new(bucket_name).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponseDto::bad_request(&format!(
-                "Invalid bucket name: {}",
-                e
-            ))),
-        )
-    })?;
-
     // Enable the rule
-    lifecycle_service
-        .enable_rule(&bucket, &rule_id)
-        .await
-        .map_err(|e| {
-            let status_code = StatusCode::from(e.clone());
-            (status_code, Json(ErrorResponseDto::from_lifecycle_error(e)))
-        })?;
+    lifecycle_service.enable_rule(&bucket, &rule_id).await?;
 
     Ok((
         StatusCode::OK,
@@ -362,29 +334,13 @@ pub async fn enable_lifecycle_rule(
 /// Handle disabling a lifecycle rule
 pub async fn disable_lifecycle_rule(
     State(app_state): State<AppState>,
-    Path((bucket_name, rule_id)): Path<(String, String)>,
-) -> Result<(StatusCode, Json<SuccessResponseDto>), (StatusCode, Json<ErrorResponseDto>)> {
+    BucketNamePath(bucket): BucketNamePath,
+    Path(RuleIdPath { rule_id }): Path<RuleIdPath>,
+) -> Result<(StatusCode, Json<SuccessResponseDto>), ApiError> {
     let lifecycle_service = &app_state.lifecycle_service;
 
-    // Validate bucket name
-    let bucket = BucketName::new(bucket_name).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponseDto::bad_request(&format!(
-                "Invalid bucket name: {}",
-                e
-            ))),
-        )
-    })?;
-
     // Disable the rule
-    lifecycle_service
-        .disable_rule(&bucket, &rule_id)
-        .await
-        .map_err(|e| {
-            let status_code = StatusCode::from(e.clone());
-            (status_code, Json(ErrorResponseDto::from_lifecycle_error(e)))
-        })?;
+    lifecycle_service.disable_rule(&bucket, &rule_id).await?;
 
     Ok((
         StatusCode::OK,
@@ -394,32 +350,65 @@ pub async fn disable_lifecycle_rule(
     ))
 }
 
+/// Handle starting lifecycle processing of a bucket in the background
+///
+/// Answers 409 while a run of the bucket is underway.
+pub async fn run_bucket_lifecycle(
+    State(app_state): State<AppState>,
+    BucketNamePath(bucket): BucketNamePath,
+) -> Result<(StatusCode, Json<LifecycleProcessingStatusDto>), ApiError> {
+    let status = app_state
+        .lifecycle_service
+        .start_bucket_lifecycle(&bucket)
+        .await?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(LifecycleProcessingStatusDto::new(&bucket, status)),
+    ))
+}
+
+/// Handle stopping the running lifecycle processing of a bucket
+///
+/// The run stops once the object it is working on is done. Answers 409 when
+/// no run of the bucket is underway.
+pub async fn cancel_bucket_lifecycle(
+    State(app_state): State<AppState>,
+    BucketNamePath(bucket): BucketNamePath,
+) -> Result<(StatusCode, Json<LifecycleProcessingStatusDto>), ApiError> {
+    let status = app_state
+        .lifecycle_service
+        .cancel_bucket_lifecycle(&bucket)
+        .await?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(LifecycleProcessingStatusDto::new(&bucket, status)),
+    ))
+}
+
+/// Handle getting the lifecycle processing status of a bucket
+pub async fn get_bucket_lifecycle_status(
+    State(app_state): State<AppState>,
+    BucketNamePath(bucket): BucketNamePath,
+) -> Result<Json<LifecycleProcessingStatusDto>, ApiError> {
+    let status = app_state
+        .lifecycle_service
+        .get_processing_status(&bucket)
+        .await?;
+
+    Ok(Json(LifecycleProcessingStatusDto::new(&bucket, status)))
+}
+
 /// Handle processing bucket lifecycle
 pub async fn process_bucket_lifecycle(
     State(app_state): State<AppState>,
-    Path(bucket_name): Path<String>,
-) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponseDto>)> {
+    BucketNamePath(bucket): BucketNamePath,
+) -> Result<Json<serde_json::Value>, ApiError> {
     let lifecycle_service = &app_state.lifecycle_service;
 
-    // Validate bucket name
-    let bucket = BucketName::new(bucket_name).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponseDto::bad_request(&format!(
-                "Invalid bucket name: {}",
-                e
-            ))),
-        )
-    })?;
-
     // Process lifecycle
-    let results = lifecycle_service
-        .process_bucket_lifecycle(&bucket)
-        .await
-        .map_err(|e| {
-            let status_code = StatusCode::from(e.clone());
-            (status_code, Json(ErrorResponseDto::from_lifecycle_error(e)))
-        })?;
+    let results = lifecycle_service.process_bucket_lifecycle(&bucket).await?;
 
     // Convert results to JSON
     let response = serde_json::json!({
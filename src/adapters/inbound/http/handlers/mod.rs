@@ -1,7 +1,23 @@
+pub mod admin_handlers;
+pub mod archive_handlers;
+pub mod backup_handlers;
+pub mod bucket_handlers;
+pub mod dataset_handlers;
+pub mod export_handlers;
 pub mod lifecycle_handlers;
+pub mod multipart_handlers;
 pub mod object_handlers;
+pub mod transaction_handlers;
 pub mod versioning_handlers;
 
+pub use admin_handlers::*;
+pub use archive_handlers::*;
+pub use backup_handlers::*;
+pub use bucket_handlers::*;
+pub use dataset_handlers::*;
+pub use export_handlers::*;
 pub use lifecycle_handlers::*;
+pub use multipart_handlers::*;
 pub use object_handlers::*;
+pub use transaction_handlers::*;
 pub use versioning_handlers::*;
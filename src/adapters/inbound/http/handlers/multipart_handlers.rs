@@ -0,0 +1,443 @@
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use percent_encoding::{NON_ALPHANUMERIC, percent_decode_str, utf8_percent_encode};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::{
+    adapters::inbound::http::{
+        dto::{
+            CompleteMultipartUploadDto, CompleteMultipartUploadResponseDto,
+            InitiateMultipartUploadResponseDto, ListMultipartUploadsDto,
+            ListMultipartUploadsResponseDto, ListPartsResponseDto, MultipartQueryDto,
+            MultipartUploadDto, PartChecksumDto, PartDto, PresignMultipartUploadDto,
+            PresignedMultipartUploadDto, S3CompleteMultipartUploadDto,
+            S3CompleteMultipartUploadResultDto, S3ErrorDto,
+        },
+        error::ApiError,
+        extract::{BucketNamePath, BucketObjectKeyPath},
+        handlers::{
+            bucket_handlers::{S3_XMLNS, xml_response},
+            object_handlers::{MAX_PRESIGN_EXPIRY_SECS, quoted_etag},
+        },
+        router::AppState,
+    },
+    domain::{
+        errors::StorageError,
+        models::{CHECKSUM_SHA256_METADATA_KEY, IntegrityManifest},
+        value_objects::{BucketName, ObjectKey},
+    },
+};
+
+/// Path parameter naming a multipart upload
+#[derive(Debug, Deserialize)]
+pub struct UploadIdPath {
+    pub upload_id: String,
+}
+
+/// Handle the S3 multipart POSTs on an object: `?uploads` initiates an upload,
+/// `?uploadId` completes it from the parts listed in the body
+///
+/// The parts are listed either in JSON or, as S3 clients send them, in a
+/// `CompleteMultipartUpload` XML document, which is answered in S3 XML (see
+/// [`complete_s3_multipart_upload`]). A completed upload reports the checksum
+/// of each part with its offset, so a very large object can later be verified
+/// one range at a time, and the composite checksum, also returned in the
+/// `x-amz-checksum-sha256` header.
+pub async fn create_or_complete_multipart_upload(
+    State(app_state): State<AppState>,
+    BucketObjectKeyPath {
+        bucket,
+        key,
+        object_key,
+    }: BucketObjectKeyPath,
+    Query(params): Query<MultipartQueryDto>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, ApiError> {
+    if params.uploads.is_some() {
+        let content_type = headers
+            .get("content-type")
+            .and_then(|ct| ct.to_str().ok())
+            .map(|s| s.to_string());
+
+        let upload = app_state
+            .object_service
+            .initiate_multipart_upload(object_key, content_type, HashMap::new())
+            .await?;
+
+        return Ok(Json(InitiateMultipartUploadResponseDto {
+            bucket: bucket.to_string(),
+            key,
+            upload_id: upload.upload_id,
+        })
+        .into_response());
+    }
+
+    let upload_id = required_upload_id(params.upload_id)?;
+    if body.trim_ascii_start().starts_with(b"<") {
+        return Ok(complete_s3_multipart_upload(
+            &app_state,
+            &bucket,
+            &key,
+            &object_key,
+            &upload_id,
+            &body,
+        )
+        .await);
+    }
+    let dto: CompleteMultipartUploadDto = serde_json::from_slice(&body)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid part list: {}", e)))?;
+
+    let metadata = app_state
+        .object_service
+        .complete_multipart_upload(
+            &object_key,
+            &upload_id,
+            dto.parts.into_iter().map(Into::into).collect(),
+        )
+        .await?;
+
+    let manifest = IntegrityManifest::from_metadata(&metadata.custom_metadata);
+    let mut response_headers = HeaderMap::new();
+    if let Some(composite) = manifest
+        .as_ref()
+        .and_then(|manifest| HeaderValue::from_str(&manifest.composite).ok())
+    {
+        response_headers.insert(CHECKSUM_SHA256_METADATA_KEY, composite);
+    }
+
+    Ok((
+        response_headers,
+        Json(CompleteMultipartUploadResponseDto {
+            bucket: bucket.to_string(),
+            key,
+            etag: metadata.etag,
+            size: metadata.content_length,
+            checksum_sha256: manifest.as_ref().map(|manifest| manifest.composite.clone()),
+            parts: manifest
+                .as_ref()
+                .map(PartChecksumDto::from_manifest)
+                .unwrap_or_default(),
+        }),
+    )
+        .into_response())
+}
+
+/// Complete an upload from an S3 `CompleteMultipartUpload` document
+///
+/// Requests S3 rejects up front are answered with an S3 `Error` document and a
+/// client error status, e.g. `InvalidPart`, `InvalidPartOrder`, `NoSuchUpload`
+/// or `MalformedXML`. A failure while assembling the object is reported the
+/// way S3 does once it has started answering: a 200 whose body is an `Error`
+/// document, which SDKs check for and retry.
+async fn complete_s3_multipart_upload(
+    app_state: &AppState,
+    bucket: &BucketName,
+    key: &str,
+    object_key: &ObjectKey,
+    upload_id: &str,
+    body: &[u8],
+) -> Response {
+    let document = std::str::from_utf8(body)
+        .map_err(|e| e.to_string())
+        .and_then(|body| {
+            quick_xml::de::from_str::<S3CompleteMultipartUploadDto>(body).map_err(|e| e.to_string())
+        });
+    let parts = match document {
+        Ok(document) => document.parts.into_iter().map(Into::into).collect(),
+        Err(e) => {
+            return s3_error_response(
+                StatusCode::BAD_REQUEST,
+                "MalformedXML",
+                &format!("Invalid part list: {}", e),
+            );
+        }
+    };
+
+    let metadata = match app_state
+        .object_service
+        .complete_multipart_upload(object_key, upload_id, parts)
+        .await
+    {
+        Ok(metadata) => metadata,
+        Err(error) => {
+            let (status, code) = s3_error_code(&error);
+            return s3_error_response(status, code, &error.to_string());
+        }
+    };
+
+    let manifest = IntegrityManifest::from_metadata(&metadata.custom_metadata);
+    xml_response(&S3CompleteMultipartUploadResultDto {
+        xmlns: S3_XMLNS.to_string(),
+        location: format!(
+            "/buckets/{}/objects/{}",
+            bucket,
+            utf8_percent_encode(key, NON_ALPHANUMERIC)
+        ),
+        bucket: bucket.to_string(),
+        key: key.to_string(),
+        etag: quoted_etag(metadata.etag.as_deref().unwrap_or_default()),
+        checksum_sha256: manifest.map(|manifest| manifest.composite),
+    })
+    .unwrap_or_else(IntoResponse::into_response)
+}
+
+/// Status and S3 error code of a failed CompleteMultipartUpload
+///
+/// Server-side failures keep the 200 status S3 has already sent by the time
+/// they happen.
+fn s3_error_code(error: &StorageError) -> (StatusCode, &'static str) {
+    match error {
+        StorageError::InvalidPart { .. } => (StatusCode::BAD_REQUEST, "InvalidPart"),
+        StorageError::InvalidPartOrder { .. } => (StatusCode::BAD_REQUEST, "InvalidPartOrder"),
+        StorageError::UploadNotFound { .. } => (StatusCode::NOT_FOUND, "NoSuchUpload"),
+        StorageError::AccessDenied { .. } => (StatusCode::FORBIDDEN, "AccessDenied"),
+        StorageError::ObjectAlreadyExists { .. } => (StatusCode::CONFLICT, "OperationAborted"),
+        StorageError::QuotaExceeded { .. } | StorageError::PrefixQuotaExceeded { .. } => {
+            (StatusCode::INSUFFICIENT_STORAGE, "QuotaExceeded")
+        }
+        StorageError::OperationNotSupported { .. } | StorageError::UnsupportedOperation { .. } => {
+            (StatusCode::NOT_IMPLEMENTED, "NotImplemented")
+        }
+        StorageError::StorageBackendError { .. }
+        | StorageError::InfrastructureError { .. }
+        | StorageError::InternalError { .. }
+        | StorageError::Timeout { .. } => (StatusCode::OK, "InternalError"),
+        _ => (StatusCode::BAD_REQUEST, "InvalidRequest"),
+    }
+}
+
+fn s3_error_response(status: StatusCode, code: &str, message: &str) -> Response {
+    let mut response = xml_response(&S3ErrorDto {
+        code: code.to_string(),
+        message: message.to_string(),
+    })
+    .unwrap_or_else(IntoResponse::into_response);
+    *response.status_mut() = status;
+    response
+}
+
+/// Handle UploadPart (`?partNumber=N&uploadId=ID`)
+///
+/// With an `x-amz-copy-source` header this is UploadPartCopy: the part is
+/// copied from the named `bucket/key`, limited to the inclusive
+/// `x-amz-copy-source-range` (`bytes=first-last`) if given, and the body is
+/// ignored. The part's ETag is returned in the `ETag` header, as S3 clients
+/// expect.
+pub async fn upload_part(
+    State(app_state): State<AppState>,
+    BucketObjectKeyPath { object_key, .. }: BucketObjectKeyPath,
+    Query(params): Query<MultipartQueryDto>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, ApiError> {
+    let upload_id = required_upload_id(params.upload_id)?;
+    let part_number = params
+        .part_number
+        .ok_or_else(|| ApiError::BadRequest("Missing partNumber".to_string()))?;
+
+    let part = match headers.get("x-amz-copy-source") {
+        Some(source) => {
+            let source_key = parse_copy_source(source.to_str().unwrap_or_default())?;
+            let range = headers
+                .get("x-amz-copy-source-range")
+                .map(|range| parse_copy_source_range(range.to_str().unwrap_or_default()))
+                .transpose()?;
+
+            app_state
+                .object_service
+                .upload_part_copy(&object_key, &upload_id, part_number, &source_key, range)
+                .await
+        }
+        None => {
+            app_state
+                .object_service
+                .upload_part(&object_key, &upload_id, part_number, body)
+                .await
+        }
+    }?;
+
+    Ok((
+        [("etag", format!("\"{}\"", part.etag))],
+        Json(PartDto::from(part)),
+    )
+        .into_response())
+}
+
+/// Handle ListParts (`?uploadId=ID`)
+pub async fn list_parts(
+    State(app_state): State<AppState>,
+    BucketObjectKeyPath {
+        bucket,
+        key,
+        object_key,
+    }: BucketObjectKeyPath,
+    Query(params): Query<MultipartQueryDto>,
+) -> Result<Json<ListPartsResponseDto>, ApiError> {
+    let upload_id = required_upload_id(params.upload_id)?;
+
+    let parts = app_state
+        .object_service
+        .list_parts(&object_key, &upload_id)
+        .await?;
+
+    Ok(Json(ListPartsResponseDto {
+        bucket: bucket.to_string(),
+        key,
+        upload_id,
+        parts: parts.into_iter().map(PartDto::from).collect(),
+    }))
+}
+
+/// Handle AbortMultipartUpload (`?uploadId=ID`)
+pub async fn abort_multipart_upload(
+    State(app_state): State<AppState>,
+    BucketObjectKeyPath { object_key, .. }: BucketObjectKeyPath,
+    Query(params): Query<MultipartQueryDto>,
+) -> Result<StatusCode, ApiError> {
+    let upload_id = required_upload_id(params.upload_id)?;
+
+    app_state
+        .object_service
+        .abort_multipart_upload(&object_key, &upload_id)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Handle ListMultipartUploads (`?uploads`) on a bucket
+///
+/// Keys are reported relative to the bucket and an optional `prefix` narrows
+/// the listing within it.
+pub async fn list_multipart_uploads(
+    State(app_state): State<AppState>,
+    BucketNamePath(bucket): BucketNamePath,
+    Query(params): Query<ListMultipartUploadsDto>,
+) -> Result<Json<ListMultipartUploadsResponseDto>, ApiError> {
+    if params.uploads.is_none() {
+        return Err(ApiError::BadRequest(
+            "Unsupported bucket operation, expected ?uploads".to_string(),
+        ));
+    }
+    let bucket_prefix = format!("{}/", bucket);
+    let prefix = format!(
+        "{}{}",
+        bucket_prefix,
+        params.prefix.as_deref().unwrap_or_default()
+    );
+    // Ask for one more upload than requested to tell whether there are more
+    let max_uploads = params.max_uploads.unwrap_or(1000);
+    let mut uploads = app_state
+        .object_service
+        .list_multipart_uploads(Some(&prefix), Some(max_uploads.saturating_add(1)))
+        .await?;
+    let is_truncated = uploads.len() > max_uploads;
+    uploads.truncate(max_uploads);
+
+    Ok(Json(ListMultipartUploadsResponseDto {
+        prefix: params.prefix,
+        uploads: uploads
+            .into_iter()
+            .map(|upload| MultipartUploadDto {
+                key: upload
+                    .key
+                    .as_str()
+                    .strip_prefix(&bucket_prefix)
+                    .unwrap_or(upload.key.as_str())
+                    .to_string(),
+                upload_id: upload.upload_id,
+                initiated: upload.initiated,
+            })
+            .collect(),
+        is_truncated,
+        bucket: bucket.to_string(),
+    }))
+}
+
+/// Handle presigning a multipart upload, returning a URL for each requested
+/// part and one completing the upload
+///
+/// Clients such as browsers can then send the parts straight to the storage
+/// backend. As with other presigned writes, the data does not pass through
+/// the server, so the parts are not listed by it while the upload is open.
+/// The URLs stay valid for `expires_in_secs` seconds, an hour by default and
+/// a week at most.
+pub async fn presign_multipart_upload(
+    State(app_state): State<AppState>,
+    BucketObjectKeyPath { object_key, .. }: BucketObjectKeyPath,
+    Path(UploadIdPath { upload_id }): Path<UploadIdPath>,
+    Json(dto): Json<PresignMultipartUploadDto>,
+) -> Result<Json<PresignedMultipartUploadDto>, ApiError> {
+    let expires_in_secs = dto.expires_in_secs.unwrap_or(3600);
+    if expires_in_secs == 0 || expires_in_secs > MAX_PRESIGN_EXPIRY_SECS {
+        return Err(ApiError::BadRequest(format!(
+            "expires_in_secs must be between 1 and {}",
+            MAX_PRESIGN_EXPIRY_SECS
+        )));
+    }
+
+    let presigned = app_state
+        .object_service
+        .presign_multipart_upload(&object_key, &upload_id, &dto.part_numbers, expires_in_secs)
+        .await?;
+
+    Ok(Json(PresignedMultipartUploadDto::new(
+        presigned,
+        Utc::now() + chrono::Duration::seconds(expires_in_secs as i64),
+    )))
+}
+
+/// Key of the object named by `x-amz-copy-source`, a percent-encoded
+/// `bucket/key` with an optional leading slash
+fn parse_copy_source(header: &str) -> Result<ObjectKey, ApiError> {
+    let invalid = ApiError::BadRequest;
+
+    if header.contains("?versionId=") {
+        return Err(invalid(
+            "Copying a specific version of an object is not supported".to_string(),
+        ));
+    }
+    let source = percent_decode_str(header)
+        .decode_utf8()
+        .map_err(|e| invalid(format!("Invalid copy source '{}': {}", header, e)))?;
+    let source = source.strip_prefix('/').unwrap_or(&source);
+    if !source.contains('/') {
+        return Err(invalid(format!(
+            "Copy source '{}' must name a bucket and a key",
+            header
+        )));
+    }
+
+    ObjectKey::new(source.to_string())
+        .map_err(|e| invalid(format!("Invalid copy source '{}': {}", header, e)))
+}
+
+/// Parse an `x-amz-copy-source-range` of the form `bytes=first-last` into a
+/// half-open byte range
+fn parse_copy_source_range(header: &str) -> Result<Range<u64>, ApiError> {
+    header
+        .trim()
+        .strip_prefix("bytes=")
+        .and_then(|spec| spec.split_once('-'))
+        .and_then(|(first, last)| Some((first.parse::<u64>().ok()?, last.parse::<u64>().ok()?)))
+        .filter(|(first, last)| first <= last)
+        .map(|(first, last)| first..last + 1)
+        .ok_or_else(|| {
+            ApiError::BadRequest(format!(
+                "Invalid copy source range '{}', expected bytes=first-last",
+                header
+            ))
+        })
+}
+
+fn required_upload_id(upload_id: Option<String>) -> Result<String, ApiError> {
+    upload_id.ok_or_else(|| ApiError::BadRequest("Missing uploadId".to_string()))
+}
@@ -1,34 +1,62 @@
 use axum::{
     Json,
-    body::Body,
+    body::{Body, Bytes},
     extract::{Path, Query, State},
-    http::{HeaderMap, StatusCode},
+    http::{
+        HeaderMap, HeaderValue, StatusCode,
+        header::{
+            CACHE_CONTROL, CONTENT_RANGE, ETAG, IF_MATCH, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+            LAST_MODIFIED,
+        },
+    },
     response::Response,
 };
-use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use std::collections::HashMap;
+use std::ops::Range;
+use tokio_util::io::{ReaderStream, StreamReader};
 
 use crate::{
     adapters::inbound::http::{
         dto::{
             ErrorResponseDto, ListObjectsDto, ListObjectsResponseDto, ObjectInfoDto,
-            SuccessResponseDto,
+            PresignRequestDto, PresignedUrlResponseDto, ScheduleDeletionDto, SearchObjectsDto,
+            SearchObjectsResponseDto, SuccessResponseDto, UploadObjectQueryDto, UsageQueryDto,
+            UsageResponseDto,
         },
+        error::ApiError,
+        extract::ObjectKeyPath,
+        handlers::archive_handlers::extract_archive_upload,
         router::AppState,
     },
     domain::{
-        models::{CreateObjectRequest, GetObjectRequest},
+        errors::StorageError,
+        models::{
+            DELETE_AT_METADATA_KEY, EXPIRE_AFTER_SECONDS_HEADER, Filter, ObjectMetadata,
+            QuotaWarning, TextQuery, parse_delete_at,
+        },
         value_objects::ObjectKey,
     },
-    ports::storage::ObjectInfo,
+    ports::storage::{ObjectInfo, PresignedUrlMethod},
 };
 
+/// Response header warning about a quota past its soft limit, sent with every
+/// write past it, one header per quota
+pub const QUOTA_WARNING_HEADER: &str = "x-quota-warning";
+
 /// Handle object creation
+///
+/// The request body is streamed straight into the store rather than being
+/// collected in memory first. With `?extract=tar` or `?extract=zip` the body is
+/// an archive whose files are stored as separate objects under the key.
 pub async fn create_object(
     State(app_state): State<AppState>,
-    Path(key): Path<String>,
+    ObjectKeyPath(object_key): ObjectKeyPath,
+    Query(params): Query<UploadObjectQueryDto>,
     headers: HeaderMap,
-    body: Bytes,
-) -> Result<(StatusCode, Json<SuccessResponseDto>), (StatusCode, Json<ErrorResponseDto>)> {
+    body: Body,
+) -> Result<(StatusCode, HeaderMap, Json<SuccessResponseDto>), ApiError> {
     let object_service = &app_state.object_service;
 
     // Extract content type from headers
@@ -37,107 +65,221 @@ pub async fn create_object(
         .and_then(|ct| ct.to_str().ok())
         .map(|s| s.to_string());
 
-    // Create object key
-    let object_key = ObjectKey::new(key).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponseDto::bad_request(&format!(
-                "Invalid object key: {}",
-                e
-            ))),
-        )
-    })?;
+    let custom_metadata = upload_metadata(&headers)?;
 
-    // Create request
-    let request = CreateObjectRequest {
-        key: object_key,
-        data: body.to_vec(),
-        content_type,
-        custom_metadata: Default::default(),
-    };
+    let reader = StreamReader::new(body.into_data_stream().map_err(std::io::Error::other));
+
+    if let Some(format) = params.extract {
+        let (status, response, warnings) = extract_archive_upload(
+            object_service.clone(),
+            object_key,
+            format,
+            reader,
+            custom_metadata,
+        )
+        .await?;
+        return Ok((status, quota_warning_headers(warnings), response));
+    }
 
     // Store the object
-    object_service.create_object(request).await.map_err(|e| {
-        let status_code = StatusCode::from(e.clone());
-        (status_code, Json(ErrorResponseDto::from_storage_error(e)))
-    })?;
+    let (_, warnings) = object_service
+        .create_object_stream(object_key, Box::new(reader), content_type, custom_metadata)
+        .await?;
 
     Ok((
         StatusCode::CREATED,
+        quota_warning_headers(warnings),
         Json(SuccessResponseDto::new("Object created successfully")),
     ))
 }
 
+/// Headers warning about the quotas a write took past their soft limit
+fn quota_warning_headers(warnings: Vec<QuotaWarning>) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for warning in warnings {
+        if let Ok(value) = HeaderValue::from_str(&warning.to_string()) {
+            headers.append(QUOTA_WARNING_HEADER, value);
+        }
+    }
+    headers
+}
+
 /// Handle object retrieval
+///
+/// The object data is streamed from the store into the response body, with
+/// the `ETag`, `Last-Modified` and any `Cache-Control` of the object. A
+/// request whose cached copy is still current is answered with 304, and one
+/// whose `If-Match` names an ETag the object no longer has with 412.
 pub async fn get_object(
     State(app_state): State<AppState>,
-    Path(key): Path<String>,
-) -> Result<Response<Body>, (StatusCode, Json<ErrorResponseDto>)> {
+    ObjectKeyPath(object_key): ObjectKeyPath,
+    headers: HeaderMap,
+) -> Result<Response<Body>, ApiError> {
     let object_service = &app_state.object_service;
 
-    // Create object key
-    let object_key = ObjectKey::new(key).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponseDto::bad_request(&format!(
-                "Invalid object key: {}",
-                e
-            ))),
-        )
-    })?;
+    // A cached copy that is still current needs no stream opened
+    let metadata = object_service.head_object(&object_key).await?;
+    if !matches_if_match(&headers, &metadata) {
+        return Err(ApiError::Custom(
+            StatusCode::PRECONDITION_FAILED,
+            ErrorResponseDto::bad_request(&format!(
+                "{} no longer has the ETag in If-Match",
+                object_key
+            )),
+        ));
+    }
+    if is_not_modified(&headers, &metadata) {
+        return Ok(not_modified(&metadata));
+    }
 
-    // Create request
-    let request = GetObjectRequest {
-        key: object_key,
-        version_id: None,
-    };
+    let range = requested_range(&headers, metadata.content_length)?;
 
-    // Get the object
-    let storage_object = object_service.get_object(request).await.map_err(|e| {
-        let status_code = StatusCode::from(e.clone());
-        (status_code, Json(ErrorResponseDto::from_storage_error(e)))
-    })?;
+    // Only the requested range is read from the store
+    let (_, reader) = object_service
+        .get_object_stream(&object_key, range.clone())
+        .await?;
 
-    // Return the object data
-    let content_type = storage_object
-        .metadata
+    Ok(object_response(&metadata, range, reader))
+}
+
+/// The byte range a request's `Range` header asks for, if any
+///
+/// A range the object can't satisfy is answered with 416.
+pub(crate) fn requested_range(
+    headers: &HeaderMap,
+    total_size: u64,
+) -> Result<Option<Range<u64>>, ApiError> {
+    let Some(range_header) = headers.get("range").and_then(|r| r.to_str().ok()) else {
+        return Ok(None);
+    };
+    parse_byte_range(range_header, total_size)
+        .map(Some)
+        .ok_or_else(|| {
+            ApiError::Custom(
+                StatusCode::RANGE_NOT_SATISFIABLE,
+                ErrorResponseDto::bad_request(&format!(
+                    "Invalid range '{}' for object of {} bytes",
+                    range_header, total_size
+                )),
+            )
+        })
+}
+
+/// Response streaming an object's data, or the byte range `range` of it,
+/// from `reader`
+pub(crate) fn object_response(
+    metadata: &ObjectMetadata,
+    range: Option<Range<u64>>,
+    reader: Box<dyn tokio::io::AsyncRead + Send + Unpin>,
+) -> Response<Body> {
+    let content_type = metadata
         .content_type
         .as_deref()
         .unwrap_or("application/octet-stream");
 
-    Ok(Response::builder()
-        .status(StatusCode::OK)
+    let mut response = Response::builder()
         .header("content-type", content_type)
-        .body(Body::from(storage_object.data))
-        .unwrap())
+        .header("accept-ranges", "bytes");
+    if let Some(response_headers) = response.headers_mut() {
+        response_headers.extend(cache_headers(metadata));
+    }
+
+    let total_size = metadata.content_length;
+    let response = match range {
+        Some(range) => response
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header("content-length", range.end - range.start)
+            .header(
+                "content-range",
+                format!("bytes {}-{}/{}", range.start, range.end - 1, total_size),
+            ),
+        None => response
+            .status(StatusCode::OK)
+            .header("content-length", total_size),
+    };
+    response
+        .body(Body::from_stream(ReaderStream::new(reader)))
+        .unwrap()
+}
+
+/// Handle overwriting a region of an object
+///
+/// The `Content-Range` header, `bytes first-last/size` with `*` for a size left
+/// unchecked, places the body within the object. The region may run past the
+/// end of the object to extend it, but may not start beyond it. `If-Match`
+/// must carry the object's current ETag, so a patch never applies to data
+/// the client hasn't seen: without it the answer is 428, and 412 once the
+/// object has changed.
+pub async fn patch_object(
+    State(app_state): State<AppState>,
+    ObjectKeyPath(object_key): ObjectKeyPath,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<SuccessResponseDto>, ApiError> {
+    let content_range = headers
+        .get(CONTENT_RANGE)
+        .ok_or_else(|| ApiError::BadRequest("Missing Content-Range header".to_string()))?
+        .to_str()
+        .unwrap_or_default();
+    let (range, expected_size) = parse_content_range(content_range).ok_or_else(|| {
+        ApiError::BadRequest(format!(
+            "Invalid Content-Range '{}', expected bytes first-last/size",
+            content_range
+        ))
+    })?;
+    if range.end - range.start != body.len() as u64 {
+        return Err(ApiError::BadRequest(format!(
+            "Content-Range '{}' does not match a body of {} bytes",
+            content_range,
+            body.len()
+        )));
+    }
+
+    let if_match = headers
+        .get(IF_MATCH)
+        .ok_or_else(|| {
+            ApiError::Custom(
+                StatusCode::PRECONDITION_REQUIRED,
+                ErrorResponseDto::bad_request("Patches require an If-Match header"),
+            )
+        })?
+        .to_str()
+        .map_err(|_| ApiError::BadRequest("Invalid If-Match header".to_string()))?;
+
+    let metadata = app_state
+        .object_service
+        .patch_object(&object_key, range.start, body, if_match, expected_size)
+        .await
+        .map_err(|e| match e {
+            StorageError::VersionConflict { .. } => ApiError::Custom(
+                StatusCode::PRECONDITION_FAILED,
+                ErrorResponseDto::bad_request(&format!(
+                    "{} has changed since ETag {}",
+                    object_key, if_match
+                )),
+            ),
+            e => ApiError::from(e),
+        })?;
+
+    Ok(Json(SuccessResponseDto::with_data(
+        "Object patched successfully",
+        serde_json::json!({
+            "key": object_key.as_str(),
+            "size": metadata.content_length,
+            "etag": metadata.etag,
+        }),
+    )))
 }
 
 /// Handle object deletion
 pub async fn delete_object(
     State(app_state): State<AppState>,
-    Path(key): Path<String>,
-) -> Result<(StatusCode, Json<SuccessResponseDto>), (StatusCode, Json<ErrorResponseDto>)> {
+    ObjectKeyPath(object_key): ObjectKeyPath,
+) -> Result<(StatusCode, Json<SuccessResponseDto>), ApiError> {
     let object_service = &app_state.object_service;
 
-    // Create object key
-    let object_key = ObjectKey::new(key).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponseDto::bad_request(&format!(
-                "Invalid object key: {}",
-                e
-            ))),
-        )
-    })?;
-
     // Delete the object
-    object_service
-        .delete_object(&object_key)
-        .await
-        .map_err(|e| {
-            let status_code = StatusCode::from(e.clone());
-            (status_code, Json(ErrorResponseDto::from_storage_error(e)))
-        })?;
+    object_service.delete_object(&object_key).await?;
 
     Ok((
         StatusCode::OK,
@@ -145,70 +287,149 @@ pub async fn delete_object(
     ))
 }
 
-/// Handle object existence check
-pub async fn head_object(
+/// Handle scheduling (or clearing) the deletion of an object
+pub async fn set_object_delete_at(
     State(app_state): State<AppState>,
-    Path(key): Path<String>,
-) -> Result<(StatusCode, HeaderMap), (StatusCode, Json<ErrorResponseDto>)> {
+    ObjectKeyPath(object_key): ObjectKeyPath,
+    Json(schedule_dto): Json<ScheduleDeletionDto>,
+) -> Result<(StatusCode, Json<SuccessResponseDto>), ApiError> {
     let object_service = &app_state.object_service;
 
-    // Create object key
-    let object_key = ObjectKey::new(key).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponseDto::bad_request(&format!(
-                "Invalid object key: {}",
-                e
-            ))),
-        )
-    })?;
+    // Load the current metadata so only the schedule changes
+    let mut metadata = object_service.head_object(&object_key).await?;
+    metadata.set_delete_at(schedule_dto.delete_at);
 
-    // Check if object exists and get its size
-    let exists = object_service
-        .object_exists(&object_key)
-        .await
-        .map_err(|e| {
-            let status_code = StatusCode::from(e.clone());
-            (status_code, Json(ErrorResponseDto::from_storage_error(e)))
-        })?;
+    object_service
+        .update_metadata(&object_key, metadata)
+        .await?;
 
-    if !exists {
-        return Err((
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponseDto::bad_request("Object not found")),
-        ));
+    let message = match schedule_dto.delete_at {
+        Some(_) => "Object deletion scheduled",
+        None => "Object deletion schedule cleared",
+    };
+
+    Ok((
+        StatusCode::OK,
+        Json(SuccessResponseDto::with_data(
+            message,
+            serde_json::json!({
+                "key": object_key.as_str(),
+                "delete_at": schedule_dto.delete_at,
+            }),
+        )),
+    ))
+}
+
+/// Handle reporting how much is stored under prefixes
+///
+/// Without a `prefix` every prefix with a quota is reported, along with its
+/// limits.
+pub async fn get_usage(
+    State(app_state): State<AppState>,
+    Query(params): Query<UsageQueryDto>,
+) -> Result<Json<UsageResponseDto>, ApiError> {
+    let usage = match params.prefix {
+        Some(prefix) => vec![app_state.object_service.prefix_usage(&prefix).await?],
+        None => app_state.object_service.quota_usage().await?,
+    };
+
+    Ok(Json(UsageResponseDto {
+        prefixes: usage.into_iter().map(Into::into).collect(),
+    }))
+}
+
+/// Longest a presigned URL can stay valid, as in S3
+pub const MAX_PRESIGN_EXPIRY_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// Handle creating a presigned URL for an object
+///
+/// The URL stays valid for `expires_in_secs` seconds, an hour by default and
+/// a week at most. GET and DELETE URLs are only issued for existing objects.
+pub async fn presign_object(
+    State(app_state): State<AppState>,
+    ObjectKeyPath(object_key): ObjectKeyPath,
+    Json(presign_dto): Json<PresignRequestDto>,
+) -> Result<Json<PresignedUrlResponseDto>, ApiError> {
+    let method = match presign_dto.method.to_ascii_uppercase().as_str() {
+        "GET" => PresignedUrlMethod::Get,
+        "PUT" => PresignedUrlMethod::Put,
+        "DELETE" => PresignedUrlMethod::Delete,
+        other => {
+            return Err(ApiError::BadRequest(format!(
+                "Cannot presign method '{}', expected GET, PUT or DELETE",
+                other
+            )));
+        }
+    };
+    let expires_in_secs = presign_dto.expires_in_secs.unwrap_or(3600);
+    if expires_in_secs == 0 || expires_in_secs > MAX_PRESIGN_EXPIRY_SECS {
+        return Err(ApiError::BadRequest(format!(
+            "expires_in_secs must be between 1 and {}",
+            MAX_PRESIGN_EXPIRY_SECS
+        )));
     }
 
-    let size = object_service
-        .get_object_size(&object_key)
-        .await
-        .map_err(|e| {
-            let status_code = StatusCode::from(e.clone());
-            (status_code, Json(ErrorResponseDto::from_storage_error(e)))
-        })?;
+    let url = app_state
+        .object_service
+        .presign_url(&object_key, method, expires_in_secs)
+        .await?;
 
-    let mut headers = HeaderMap::new();
-    headers.insert("content-length", size.to_string().parse().unwrap());
-    headers.insert("content-type", "application/octet-stream".parse().unwrap());
+    Ok(Json(PresignedUrlResponseDto {
+        url,
+        method: method.to_string(),
+        expires_at: Utc::now() + chrono::Duration::seconds(expires_in_secs as i64),
+    }))
+}
+
+/// Handle object existence check
+///
+/// Only the object's metadata is looked up, its data is not read.
+pub async fn head_object(
+    State(app_state): State<AppState>,
+    ObjectKeyPath(object_key): ObjectKeyPath,
+) -> Result<(StatusCode, HeaderMap), ApiError> {
+    let object_service = &app_state.object_service;
+
+    let metadata = object_service.head_object(&object_key).await?;
+
+    let content_type = metadata
+        .content_type
+        .as_deref()
+        .and_then(|ct| ct.parse().ok())
+        .unwrap_or_else(|| "application/octet-stream".parse().unwrap());
+
+    let mut headers = cache_headers(&metadata);
+    headers.insert("content-length", metadata.content_length.into());
+    headers.insert("content-type", content_type);
+    headers.insert("accept-ranges", "bytes".parse().unwrap());
 
     Ok((StatusCode::OK, headers))
 }
 
 /// Handle object listing
+///
+/// Pages continue after the last key of the previous page, which is returned
+/// as the continuation token of truncated pages.
 pub async fn list_objects(
     State(app_state): State<AppState>,
     Query(params): Query<ListObjectsDto>,
-) -> Result<Json<ListObjectsResponseDto>, (StatusCode, Json<ErrorResponseDto>)> {
+) -> Result<Json<ListObjectsResponseDto>, ApiError> {
     let object_service = &app_state.object_service;
 
-    // List objects with optional prefix and max results
-    let objects = object_service
-        .list_objects(params.prefix.as_deref(), params.max_results)
-        .await
-        .map_err(|e| {
-            let status_code = StatusCode::from(e.clone());
-            (status_code, Json(ErrorResponseDto::from_storage_error(e)))
-        })?;
+    // Ask for one object more than the page to tell whether it is truncated
+    let mut objects = object_service
+        .list_objects_after(
+            params.prefix.as_deref(),
+            params.continuation_token.as_deref(),
+            params.max_results.map(|max| max.saturating_add(1)),
+        )
+        .await?;
+    let is_truncated = params.max_results.is_some_and(|max| objects.len() > max);
+    objects.truncate(params.max_results.unwrap_or(usize::MAX));
+    let next_continuation_token = objects
+        .last()
+        .filter(|_| is_truncated)
+        .map(|obj| obj.key.as_str().to_string());
 
     // Convert to DTOs
     let object_dtos: Vec<ObjectInfoDto> = objects
@@ -216,7 +437,7 @@ pub async fn list_objects(
         .map(|obj| ObjectInfoDto {
             key: obj.key.as_str().to_string(),
             size: obj.size,
-            last_modified: obj.last_modified.into(),
+            last_modified: obj.last_modified,
             etag: obj.etag,
             storage_class: None, // Would need to be provided by the storage layer
             version_id: None,    // Would be set for versioned objects
@@ -224,12 +445,39 @@ pub async fn list_objects(
         .collect();
 
     let total_count = object_dtos.len();
-    let is_truncated = params.max_results.map_or(false, |max| total_count >= max);
 
     Ok(Json(ListObjectsResponseDto {
         objects: object_dtos,
         is_truncated,
-        next_continuation_token: None, // Would be implemented for pagination
+        next_continuation_token,
+        total_count,
+    }))
+}
+
+/// Handle object search by text, prefix, tags and size
+pub async fn search_objects(
+    State(app_state): State<AppState>,
+    Query(params): Query<SearchObjectsDto>,
+) -> Result<Json<SearchObjectsResponseDto>, ApiError> {
+    let filter = Filter::try_from(&params)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid search: {}", e)))?;
+
+    let query = TextQuery::new(params.q.as_deref().unwrap_or(""));
+
+    let keys = app_state
+        .object_service
+        .search_objects(&query, &filter, params.max_results)
+        .await?;
+
+    let total_count = keys.len();
+    let is_truncated = params.max_results.is_some_and(|max| total_count >= max);
+
+    Ok(Json(SearchObjectsResponseDto {
+        keys: keys
+            .into_iter()
+            .map(|key| key.as_str().to_string())
+            .collect(),
+        is_truncated,
         total_count,
     }))
 }
@@ -238,55 +486,316 @@ pub async fn list_objects(
 pub async fn copy_object(
     State(app_state): State<AppState>,
     Path((source_key, dest_key)): Path<(String, String)>,
-) -> Result<(StatusCode, Json<SuccessResponseDto>), (StatusCode, Json<ErrorResponseDto>)> {
+) -> Result<(StatusCode, HeaderMap, Json<SuccessResponseDto>), ApiError> {
     let object_service = &app_state.object_service;
 
     // Create object keys
-    let source_object_key = ObjectKey::new(source_key).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponseDto::bad_request(&format!(
-                "Invalid source key: {}",
-                e
-            ))),
-        )
-    })?;
+    let source_object_key = ObjectKey::new(source_key)?;
 
-    let dest_object_key = ObjectKey::new(dest_key).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponseDto::bad_request(&format!(
-                "Invalid destination key: {}",
-                e
-            ))),
-        )
-    })?;
+    let dest_object_key = ObjectKey::new(dest_key)?;
 
     // Copy the object
-    object_service
+    let (_, warnings) = object_service
         .copy_object(&source_object_key, &dest_object_key)
-        .await
-        .map_err(|e| {
-            let status_code = StatusCode::from(e.clone());
-            (status_code, Json(ErrorResponseDto::from_storage_error(e)))
-        })?;
+        .await?;
 
     Ok((
         StatusCode::OK,
+        quota_warning_headers(warnings),
         Json(SuccessResponseDto::new("Object copied successfully")),
     ))
 }
 
+/// Parse a single `bytes=` range header into a half-open byte range
+pub(crate) fn parse_byte_range(header: &str, total_size: u64) -> Option<Range<u64>> {
+    let spec = header.trim().strip_prefix("bytes=")?;
+    // Multiple ranges are not supported
+    if spec.contains(',') {
+        return None;
+    }
+
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().ok()?;
+            (total_size.saturating_sub(suffix), total_size)
+        }
+        (start, "") => (start.parse().ok()?, total_size),
+        (start, end) => {
+            let end: u64 = end.parse().ok()?;
+            (start.parse().ok()?, end.saturating_add(1).min(total_size))
+        }
+    };
+
+    if start >= end || start >= total_size {
+        return None;
+    }
+
+    Some(start..end)
+}
+
+/// Parse a `Content-Range` of the form `bytes first-last/size` into a half-open
+/// byte range and the size, which is None when given as `*`
+fn parse_content_range(header: &str) -> Option<(Range<u64>, Option<u64>)> {
+    let (range, size) = header.trim().strip_prefix("bytes ")?.split_once('/')?;
+    let (first, last) = range.split_once('-')?;
+    let (first, last): (u64, u64) = (first.trim().parse().ok()?, last.trim().parse().ok()?);
+    if first > last {
+        return None;
+    }
+    let size = match size.trim() {
+        "*" => None,
+        size => {
+            let size: u64 = size.parse().ok()?;
+            // The range has to lie within the size
+            if size <= last {
+                return None;
+            }
+            Some(size)
+        }
+    };
+
+    Some((first..last + 1, size))
+}
+
+/// Validators and caching directives of an object as response headers
+///
+/// The Cache-Control stored with the object, or given to it by the default
+/// metadata of its prefix, is passed on unchanged.
+pub(crate) fn cache_headers(metadata: &ObjectMetadata) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Some(etag) = metadata
+        .etag
+        .as_deref()
+        .and_then(|etag| quoted_etag(etag).parse().ok())
+    {
+        headers.insert(ETAG, etag);
+    }
+    let last_modified = DateTime::<Utc>::from(metadata.last_modified)
+        .format("%a, %d %b %Y %H:%M:%S GMT")
+        .to_string();
+    headers.insert(LAST_MODIFIED, last_modified.parse().unwrap());
+    if let Some(cache_control) = metadata
+        .custom_metadata
+        .get(CACHE_CONTROL.as_str())
+        .and_then(|value| value.parse().ok())
+    {
+        headers.insert(CACHE_CONTROL, cache_control);
+    }
+    headers
+}
+
+/// Whether the copy a client has cached, identified by `If-None-Match` or
+/// else by `If-Modified-Since`, is still current
+pub(crate) fn is_not_modified(request: &HeaderMap, metadata: &ObjectMetadata) -> bool {
+    if let Some(if_none_match) = request.get(IF_NONE_MATCH) {
+        let etag = metadata.etag.as_deref().map(quoted_etag);
+        return if_none_match
+            .to_str()
+            .unwrap_or_default()
+            .split(',')
+            .map(|tag| tag.trim().trim_start_matches("W/"))
+            .any(|tag| tag == "*" || Some(tag) == etag.as_deref());
+    }
+
+    request
+        .get(IF_MODIFIED_SINCE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| DateTime::parse_from_rfc2822(value).ok())
+        .is_some_and(|since| {
+            // HTTP dates have no fractional seconds
+            DateTime::<Utc>::from(metadata.last_modified).timestamp() <= since.timestamp()
+        })
+}
+
+/// Whether an object has one of the ETags in a request's `If-Match`, if it
+/// sends one
+///
+/// Weak ETags never match, as `If-Match` compares them strongly.
+pub(crate) fn matches_if_match(request: &HeaderMap, metadata: &ObjectMetadata) -> bool {
+    let Some(if_match) = request.get(IF_MATCH) else {
+        return true;
+    };
+    let etag = metadata.etag.as_deref().map(quoted_etag);
+    if_match
+        .to_str()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || Some(tag) == etag.as_deref())
+}
+
+/// A 304 response for an object, carrying its validators
+pub(crate) fn not_modified(metadata: &ObjectMetadata) -> Response<Body> {
+    let mut response = Response::builder().status(StatusCode::NOT_MODIFIED);
+    if let Some(headers) = response.headers_mut() {
+        headers.extend(cache_headers(metadata));
+    }
+    response.body(Body::empty()).unwrap()
+}
+
+pub(crate) fn quoted_etag(etag: &str) -> String {
+    if etag.starts_with('"') {
+        etag.to_string()
+    } else {
+        format!("\"{}\"", etag)
+    }
+}
+
+/// Custom metadata requested through the headers of an upload
+pub(crate) fn upload_metadata(headers: &HeaderMap) -> Result<HashMap<String, String>, ApiError> {
+    // Extract an optional deletion schedule from headers
+    let mut custom_metadata = HashMap::new();
+    if let Some(delete_at) = parse_expiration_headers(headers)? {
+        custom_metadata.insert(DELETE_AT_METADATA_KEY.to_string(), delete_at.to_rfc3339());
+    }
+    // Stored with the object, where it overrides any default for its prefix
+    if let Some(cache_control) = headers.get(CACHE_CONTROL).and_then(|v| v.to_str().ok()) {
+        custom_metadata.insert(CACHE_CONTROL.to_string(), cache_control.to_string());
+    }
+    Ok(custom_metadata)
+}
+
+/// Resolve the deletion schedule requested through the `x-delete-at` or
+/// `x-expire-after-seconds` headers
+fn parse_expiration_headers(headers: &HeaderMap) -> Result<Option<DateTime<Utc>>, ApiError> {
+    let delete_at = headers.get(DELETE_AT_METADATA_KEY);
+    let expire_after = headers.get(EXPIRE_AFTER_SECONDS_HEADER);
+
+    match (delete_at, expire_after) {
+        (None, None) => Ok(None),
+        (Some(_), Some(_)) => Err(ApiError::BadRequest(format!(
+            "Only one of {} and {} may be specified",
+            DELETE_AT_METADATA_KEY, EXPIRE_AFTER_SECONDS_HEADER
+        ))),
+        (Some(value), None) => value
+            .to_str()
+            .ok()
+            .and_then(parse_delete_at)
+            .map(Some)
+            .ok_or_else(|| {
+                ApiError::BadRequest(format!(
+                    "Invalid {} header: expected RFC 3339 timestamp or Unix seconds",
+                    DELETE_AT_METADATA_KEY
+                ))
+            }),
+        (None, Some(value)) => value
+            .to_str()
+            .ok()
+            .and_then(|v| v.trim().parse::<u32>().ok())
+            .map(|seconds| Some(Utc::now() + chrono::Duration::seconds(seconds as i64)))
+            .ok_or_else(|| {
+                ApiError::BadRequest(format!(
+                    "Invalid {} header: expected a non-negative number of seconds",
+                    EXPIRE_AFTER_SECONDS_HEADER
+                ))
+            }),
+    }
+}
+
 /// Convert ObjectInfo to ObjectInfoDto helper
 impl From<ObjectInfo> for ObjectInfoDto {
     fn from(info: ObjectInfo) -> Self {
         ObjectInfoDto {
             key: info.key.as_str().to_string(),
             size: info.size,
-            last_modified: info.last_modified.into(),
+            last_modified: info.last_modified,
             etag: info.etag,
             storage_class: None,
             version_id: None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_expiration_headers() {
+        let headers = HeaderMap::new();
+        assert!(parse_expiration_headers(&headers).unwrap().is_none());
+
+        let mut headers = HeaderMap::new();
+        headers.insert(EXPIRE_AFTER_SECONDS_HEADER, "3600".parse().unwrap());
+        let delete_at = parse_expiration_headers(&headers).unwrap().unwrap();
+        assert!(delete_at > Utc::now() + chrono::Duration::minutes(59));
+
+        headers.insert(DELETE_AT_METADATA_KEY, "1700000000".parse().unwrap());
+        assert!(parse_expiration_headers(&headers).is_err());
+
+        let mut headers = HeaderMap::new();
+        headers.insert(EXPIRE_AFTER_SECONDS_HEADER, "-5".parse().unwrap());
+        assert!(parse_expiration_headers(&headers).is_err());
+    }
+
+    #[test]
+    fn test_parse_byte_range() {
+        assert_eq!(parse_byte_range("bytes=0-9", 100), Some(0..10));
+        assert_eq!(parse_byte_range("bytes=90-", 100), Some(90..100));
+        assert_eq!(parse_byte_range("bytes=-10", 100), Some(90..100));
+        assert_eq!(parse_byte_range("bytes=50-500", 100), Some(50..100));
+        assert_eq!(parse_byte_range("bytes=100-", 100), None);
+        assert_eq!(parse_byte_range("bytes=0-1,5-6", 100), None);
+        assert_eq!(parse_byte_range("items=0-1", 100), None);
+    }
+
+    #[test]
+    fn test_conditional_requests() {
+        let metadata = ObjectMetadata {
+            content_type: None,
+            content_length: 4,
+            etag: Some("abc".to_string()),
+            last_modified: DateTime::parse_from_rfc3339("2025-01-01T12:00:00Z")
+                .unwrap()
+                .into(),
+            custom_metadata: HashMap::from([(
+                "cache-control".to_string(),
+                "max-age=60".to_string(),
+            )]),
+        };
+        let headers = cache_headers(&metadata);
+        assert_eq!(headers[ETAG], "\"abc\"");
+        assert_eq!(headers[LAST_MODIFIED], "Wed, 01 Jan 2025 12:00:00 GMT");
+        assert_eq!(headers[CACHE_CONTROL], "max-age=60");
+
+        let request = |name, value: &str| HeaderMap::from_iter([(name, value.parse().unwrap())]);
+        assert!(is_not_modified(
+            &request(IF_NONE_MATCH, "W/\"abc\""),
+            &metadata
+        ));
+        assert!(!is_not_modified(
+            &request(IF_NONE_MATCH, "\"old\""),
+            &metadata
+        ));
+        assert!(is_not_modified(
+            &request(IF_MODIFIED_SINCE, "Wed, 01 Jan 2025 12:00:00 GMT"),
+            &metadata
+        ));
+        assert!(!is_not_modified(
+            &request(IF_MODIFIED_SINCE, "Tue, 31 Dec 2024 12:00:00 GMT"),
+            &metadata
+        ));
+        assert!(!is_not_modified(&HeaderMap::new(), &metadata));
+
+        assert!(matches_if_match(&request(IF_MATCH, "\"abc\""), &metadata));
+        assert!(!matches_if_match(&request(IF_MATCH, "\"old\""), &metadata));
+        assert!(!matches_if_match(
+            &request(IF_MATCH, "W/\"abc\""),
+            &metadata
+        ));
+        assert!(matches_if_match(&HeaderMap::new(), &metadata));
+    }
+
+    #[test]
+    fn test_parse_content_range() {
+        assert_eq!(
+            parse_content_range("bytes 0-9/100"),
+            Some((0..10, Some(100)))
+        );
+        assert_eq!(parse_content_range("bytes 4-7/*"), Some((4..8, None)));
+        assert_eq!(parse_content_range("bytes 0-9/5"), None);
+        assert_eq!(parse_content_range("bytes 9-0/*"), None);
+        assert_eq!(parse_content_range("bytes=0-9/100"), None);
+    }
+}
@@ -0,0 +1,104 @@
+use axum::{
+    Json,
+    body::Body,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+};
+use futures::TryStreamExt;
+use serde::Deserialize;
+use tokio_util::io::StreamReader;
+
+use crate::adapters::inbound::http::{
+    dto::{StagedObjectDto, TransactionDto},
+    error::ApiError,
+    extract::ObjectKeyPath,
+    handlers::object_handlers::upload_metadata,
+    router::AppState,
+};
+
+/// Path parameter naming a transaction
+#[derive(Debug, Deserialize)]
+pub struct TransactionIdPath {
+    pub transaction_id: String,
+}
+
+/// Handle starting a multi-object transaction
+pub async fn begin_transaction(
+    State(app_state): State<AppState>,
+) -> Result<(StatusCode, Json<TransactionDto>), ApiError> {
+    let transaction = app_state.object_service.begin_transaction().await?;
+
+    Ok((StatusCode::CREATED, Json(transaction.into())))
+}
+
+/// Handle getting a transaction and the objects staged in it so far
+pub async fn get_transaction(
+    State(app_state): State<AppState>,
+    Path(transaction_id): Path<String>,
+) -> Result<Json<TransactionDto>, ApiError> {
+    let transaction = app_state
+        .object_service
+        .get_transaction(&transaction_id)
+        .await?;
+
+    Ok(Json(transaction.into()))
+}
+
+/// Handle staging an object in a transaction
+///
+/// The body is streamed into the store as for a plain upload, and the same
+/// headers apply, but the object stays invisible until the transaction
+/// commits. Staging a key again replaces the earlier upload.
+pub async fn stage_transaction_object(
+    State(app_state): State<AppState>,
+    Path(TransactionIdPath { transaction_id }): Path<TransactionIdPath>,
+    ObjectKeyPath(object_key): ObjectKeyPath,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<(StatusCode, Json<StagedObjectDto>), ApiError> {
+    let content_type = headers
+        .get("content-type")
+        .and_then(|ct| ct.to_str().ok())
+        .map(|s| s.to_string());
+    let custom_metadata = upload_metadata(&headers)?;
+
+    let reader = StreamReader::new(body.into_data_stream().map_err(std::io::Error::other));
+    let staged = app_state
+        .object_service
+        .stage_object(
+            &transaction_id,
+            object_key,
+            Box::new(reader),
+            content_type,
+            custom_metadata,
+        )
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(staged.into())))
+}
+
+/// Handle committing a transaction, making all of its objects visible at once
+pub async fn commit_transaction(
+    State(app_state): State<AppState>,
+    Path(transaction_id): Path<String>,
+) -> Result<Json<TransactionDto>, ApiError> {
+    let transaction = app_state
+        .object_service
+        .commit_transaction(&transaction_id)
+        .await?;
+
+    Ok(Json(transaction.into()))
+}
+
+/// Handle aborting a transaction, discarding its staged objects
+pub async fn abort_transaction(
+    State(app_state): State<AppState>,
+    Path(transaction_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    app_state
+        .object_service
+        .abort_transaction(&transaction_id)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
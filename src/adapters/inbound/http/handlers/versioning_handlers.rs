@@ -2,53 +2,67 @@ use axum::{
     Json,
     body::Body,
     extract::{Path, Query, State},
-    http::{HeaderMap, StatusCode},
+    http::{HeaderMap, HeaderValue, StatusCode},
     response::Response,
 };
 use bytes::Bytes;
 use serde::Deserialize;
-use std::sync::Arc;
 
 use crate::{
     adapters::inbound::http::{
         AppState,
-        dto::{ErrorResponseDto, ListVersionsResponseDto, SuccessResponseDto, VersionedObjectDto},
+        dto::{
+            BulkDeleteVersionsDto, BulkDeleteVersionsResponseDto, ListVersionsResponseDto,
+            ObjectDeltaDto, ObjectSignatureDto, SignatureQueryDto, SuccessResponseDto,
+            VersionLabelsDto, VersionedObjectDto,
+        },
+        error::ApiError,
+        extract::{BucketObjectKeyPath, ObjectKeyPath, VersionIdPath},
+        handlers::object_handlers::{
+            is_not_modified, not_modified, object_response, requested_range,
+        },
     },
     domain::{
-        models::{CreateObjectRequest, DeleteVersionRequest, GetObjectRequest},
+        errors::StorageError,
+        models::{
+            BulkDeleteVersionsRequest, CreateObjectRequest, DEFAULT_DELTA_BLOCK_SIZE,
+            DeleteVersionRequest, GetObjectRequest, LabelVersionRequest, VersionSelection,
+        },
         value_objects::{ObjectKey, VersionId},
     },
-    ports::services::VersioningService,
 };
 
+/// Request header asking to delete versions despite governance retention
+pub const BYPASS_GOVERNANCE_RETENTION_HEADER: &str = "x-amz-bypass-governance-retention";
+
+/// Versions returned per page when the request does not ask for fewer
+pub const MAX_VERSIONS_PER_PAGE: usize = 1000;
+
 #[derive(Debug, Deserialize)]
 pub struct ListVersionsQuery {
     pub max_keys: Option<usize>,
     pub key_marker: Option<String>,
     pub version_id_marker: Option<String>,
+    /// Only list versions carrying this label
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RollbackQuery {
+    /// How many versions to go back, one by default
+    pub steps: Option<usize>,
 }
 
 /// Handle creating a versioned object
 pub async fn put_versioned_object(
     State(app_state): State<AppState>,
-    Path(key): Path<String>,
+    ObjectKeyPath(object_key): ObjectKeyPath,
     headers: HeaderMap,
     body: Bytes,
-) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponseDto>)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     // Extract content type from headers
     let content_type = headers.get("content-type").and_then(|ct| ct.to_str().ok());
 
-    // Create object key
-    let object_key = ObjectKey::new(key).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponseDto::bad_request(&format!(
-                "Invalid object key: {}",
-                e
-            ))),
-        )
-    })?;
-
     // Create request
     let request = CreateObjectRequest {
         key: object_key.clone(),
@@ -61,11 +75,7 @@ pub async fn put_versioned_object(
     let versioned_object = app_state
         .versioning_service
         .create_versioned_object(request)
-        .await
-        .map_err(|e| {
-            let status_code = StatusCode::from(e.clone());
-            (status_code, Json(ErrorResponseDto::from_storage_error(e)))
-        })?;
+        .await?;
 
     let response = serde_json::json!({
         "message": "Versioned object created successfully",
@@ -77,149 +87,85 @@ pub async fn put_versioned_object(
 }
 
 /// Handle getting a specific version of an object
+///
+/// The version's data is streamed from the store, and a `Range` header
+/// reads only that part of it.
 pub async fn get_versioned_object(
     State(app_state): State<AppState>,
-    Path((key, version_id)): Path<(String, String)>,
-) -> Result<Response<Body>, (StatusCode, Json<ErrorResponseDto>)> {
-    // Create object key and version ID
-    let object_key = ObjectKey::new(key).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponseDto::bad_request(&format!(
-                "Invalid object key: {}",
-                e
-            ))),
-        )
-    })?;
-
-    let version = VersionId::new(version_id).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponseDto::bad_request(&format!(
-                "Invalid version ID: {}",
-                e
-            ))),
-        )
-    })?;
-
-    // Create request for getting versioned object
+    ObjectKeyPath(object_key): ObjectKeyPath,
+    VersionIdPath(version): VersionIdPath,
+    headers: HeaderMap,
+) -> Result<Response<Body>, ApiError> {
     let request = GetObjectRequest {
         key: object_key,
         version_id: Some(version),
     };
 
-    // Get the versioned object
-    let versioned_object = app_state
-        .versioning_service
-        .get_object(request)
-        .await
-        .map_err(|e| {
-            let status_code = StatusCode::from(e.clone());
-            (status_code, Json(ErrorResponseDto::from_storage_error(e)))
-        })?;
-
-    // Return the object data with version headers
-    let content_type = versioned_object
-        .metadata
-        .content_type
-        .as_deref()
-        .unwrap_or("application/octet-stream");
-
-    Ok(Response::builder()
-        .status(StatusCode::OK)
-        .header("content-type", content_type)
-        .header("x-amz-version-id", versioned_object.version_id.as_str())
-        .body(Body::from(versioned_object.data))
-        .unwrap())
+    get_object_version(&app_state, request, &headers).await
 }
 
 /// Handle getting the latest version of an object
 pub async fn get_latest_object(
     State(app_state): State<AppState>,
-    Path(key): Path<String>,
-) -> Result<Response<Body>, (StatusCode, Json<ErrorResponseDto>)> {
-    // Create object key
-    let object_key = ObjectKey::new(key).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponseDto::bad_request(&format!(
-                "Invalid object key: {}",
-                e
-            ))),
-        )
-    })?;
-
+    ObjectKeyPath(object_key): ObjectKeyPath,
+    headers: HeaderMap,
+) -> Result<Response<Body>, ApiError> {
     // Get the latest version (no specific version requested)
     let request = GetObjectRequest {
         key: object_key,
         version_id: None,
     };
 
-    let versioned_object = app_state
-        .versioning_service
-        .get_object(request)
-        .await
-        .map_err(|e| {
-            let status_code = StatusCode::from(e.clone());
-            (status_code, Json(ErrorResponseDto::from_storage_error(e)))
-        })?;
-
-    // Return the object data with version headers
-    let content_type = versioned_object
-        .metadata
-        .content_type
-        .as_deref()
-        .unwrap_or("application/octet-stream");
-
-    Ok(Response::builder()
-        .status(StatusCode::OK)
-        .header("content-type", content_type)
-        .header("x-amz-version-id", versioned_object.version_id.as_str())
-        .body(Body::from(versioned_object.data))
-        .unwrap())
+    get_object_version(&app_state, request, &headers).await
+}
+
+/// Stream the version a request asks for, with version headers
+async fn get_object_version(
+    app_state: &AppState,
+    request: GetObjectRequest,
+    headers: &HeaderMap,
+) -> Result<Response<Body>, ApiError> {
+    let versioning_service = &app_state.versioning_service;
+
+    // A cached copy that is still current needs no stream opened
+    let (version_id, metadata) = versioning_service.head_object(request.clone()).await?;
+    if is_not_modified(headers, &metadata) {
+        return Ok(not_modified(&metadata));
+    }
+    let range = requested_range(headers, metadata.content_length)?;
+
+    // The version resolved above is read, even if a newer one has been written since
+    let request = GetObjectRequest {
+        key: request.key,
+        version_id: Some(version_id.clone()),
+    };
+    let (_, _, reader) = versioning_service
+        .get_object_stream(request, range.clone())
+        .await?;
+
+    let mut response = object_response(&metadata, range, reader);
+    if let Ok(value) = HeaderValue::from_str(version_id.as_str()) {
+        response.headers_mut().insert("x-amz-version-id", value);
+    }
+    Ok(response)
 }
 
 /// Handle deleting a specific version
 pub async fn delete_versioned_object(
     State(app_state): State<AppState>,
-    Path((key, version_id)): Path<(String, String)>,
-) -> Result<(StatusCode, Json<SuccessResponseDto>), (StatusCode, Json<ErrorResponseDto>)> {
-    // Create object key and version ID
-    let object_key = ObjectKey::new(key).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponseDto::bad_request(&format!(
-                "Invalid object key: {}",
-                e
-            ))),
-        )
-    })?;
-
-    let version = VersionId::new(version_id).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponseDto::bad_request(&format!(
-                "Invalid version ID: {}",
-                e
-            ))),
-        )
-    })?;
-
+    ObjectKeyPath(object_key): ObjectKeyPath,
+    VersionIdPath(version): VersionIdPath,
+    headers: HeaderMap,
+) -> Result<(StatusCode, Json<SuccessResponseDto>), ApiError> {
     // Create delete request
     let request = DeleteVersionRequest {
         key: object_key,
         version_id: version,
+        bypass_governance_retention: bypasses_governance_retention(&headers),
     };
 
     // Delete the version
-    app_state
-        .versioning_service
-        .delete_version(request)
-        .await
-        .map_err(|e| {
-            let status_code = StatusCode::from(e.clone());
-            (status_code, Json(ErrorResponseDto::from_storage_error(e)))
-        })?;
+    app_state.versioning_service.delete_version(request).await?;
 
     Ok((
         StatusCode::OK,
@@ -227,35 +173,149 @@ pub async fn delete_versioned_object(
     ))
 }
 
+/// Handle promoting an earlier version of a bucket's object as the latest
+///
+/// Keys are namespaced by bucket, so the object rolled back is `bucket/key`.
+pub async fn rollback_object(
+    State(app_state): State<AppState>,
+    BucketObjectKeyPath { object_key, .. }: BucketObjectKeyPath,
+    Query(params): Query<RollbackQuery>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let result = app_state
+        .versioning_service
+        .rollback_version(&object_key, params.steps.unwrap_or(1))
+        .await?;
+
+    let response = serde_json::json!({
+        "message": "Object rolled back successfully",
+        "key": result.key.as_str(),
+        "restored_version_id": result.restored_version_id.as_str(),
+        "new_version_id": result.new_version_id.as_str()
+    });
+
+    Ok(Json(response))
+}
+
+/// Handle replacing the labels and annotations of a version
+pub async fn label_version(
+    State(app_state): State<AppState>,
+    ObjectKeyPath(object_key): ObjectKeyPath,
+    VersionIdPath(version): VersionIdPath,
+    Json(dto): Json<VersionLabelsDto>,
+) -> Result<Json<SuccessResponseDto>, ApiError> {
+    let request = LabelVersionRequest {
+        key: object_key,
+        version_id: version,
+        labels: dto.labels,
+        annotations: dto.annotations,
+    };
+
+    app_state.versioning_service.label_version(request).await?;
+
+    Ok(Json(SuccessResponseDto::new(
+        "Version labels updated successfully",
+    )))
+}
+
+/// Handle pinning a version so it cannot be deleted
+pub async fn pin_version(
+    State(app_state): State<AppState>,
+    ObjectKeyPath(object_key): ObjectKeyPath,
+    VersionIdPath(version): VersionIdPath,
+) -> Result<Json<SuccessResponseDto>, ApiError> {
+    app_state
+        .versioning_service
+        .set_version_pinned(&object_key, &version, true)
+        .await?;
+    Ok(Json(SuccessResponseDto::new("Version pinned successfully")))
+}
+
+/// Handle unpinning a version so it can be deleted again
+pub async fn unpin_version(
+    State(app_state): State<AppState>,
+    ObjectKeyPath(object_key): ObjectKeyPath,
+    VersionIdPath(version): VersionIdPath,
+) -> Result<Json<SuccessResponseDto>, ApiError> {
+    app_state
+        .versioning_service
+        .set_version_pinned(&object_key, &version, false)
+        .await?;
+    Ok(Json(SuccessResponseDto::new(
+        "Version unpinned successfully",
+    )))
+}
+
+/// Handle deleting many versions of an object in one call
+///
+/// Versions that fail to delete are reported in the response without failing
+/// the request.
+pub async fn delete_object_versions(
+    State(app_state): State<AppState>,
+    ObjectKeyPath(object_key): ObjectKeyPath,
+    headers: HeaderMap,
+    Json(dto): Json<BulkDeleteVersionsDto>,
+) -> Result<Json<BulkDeleteVersionsResponseDto>, ApiError> {
+    let selection = VersionSelection::try_from(dto)?;
+
+    let result = app_state
+        .versioning_service
+        .delete_versions(BulkDeleteVersionsRequest {
+            key: object_key,
+            selection,
+            bypass_governance_retention: bypasses_governance_retention(&headers),
+        })
+        .await?;
+
+    Ok(Json(BulkDeleteVersionsResponseDto::from(result)))
+}
+
+/// Whether the request asks to bypass governance retention
+fn bypasses_governance_retention(headers: &HeaderMap) -> bool {
+    headers
+        .get(BYPASS_GOVERNANCE_RETENTION_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.eq_ignore_ascii_case("true"))
+}
+
 /// Handle listing all versions of an object
 pub async fn list_object_versions(
     State(app_state): State<AppState>,
-    Path(key): Path<String>,
+    ObjectKeyPath(object_key): ObjectKeyPath,
     Query(params): Query<ListVersionsQuery>,
-) -> Result<Json<ListVersionsResponseDto>, (StatusCode, Json<ErrorResponseDto>)> {
-    // Create object key
-    let object_key = ObjectKey::new(key).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponseDto::bad_request(&format!(
-                "Invalid object key: {}",
-                e
-            ))),
-        )
-    })?;
-
-    // Get all versions
-    let versions = app_state
-        .versioning_service
-        .list_versions(&object_key)
-        .await
-        .map_err(|e| {
-            let status_code = StatusCode::from(e.clone());
-            (status_code, Json(ErrorResponseDto::from_storage_error(e)))
-        })?;
+) -> Result<Json<ListVersionsResponseDto>, ApiError> {
+    let marker = params
+        .version_id_marker
+        .map(VersionId::new)
+        .transpose()
+        .map_err(|e| ApiError::BadRequest(format!("Invalid version ID marker: {}", e)))?;
+    let limit = params
+        .max_keys
+        .unwrap_or(MAX_VERSIONS_PER_PAGE)
+        .clamp(1, MAX_VERSIONS_PER_PAGE);
+
+    // Get one page of versions
+    let page = match params.label.as_deref() {
+        Some(label) => app_state
+            .versioning_service
+            .list_labeled_versions(&object_key, label)
+            .await?
+            .paginate(marker.as_ref(), limit)
+            .ok_or_else(|| {
+                ApiError::BadRequest(format!(
+                    "Version ID marker is not a version labeled {}",
+                    label
+                ))
+            })?,
+        None => {
+            app_state
+                .versioning_service
+                .list_versions_paginated(&object_key, marker.as_ref(), limit)
+                .await?
+        }
+    };
 
     // Convert to DTOs
-    let version_dtos: Vec<VersionedObjectDto> = versions
+    let version_dtos: Vec<VersionedObjectDto> = page
         .versions
         .into_iter()
         .map(|version_info| VersionedObjectDto {
@@ -265,24 +325,20 @@ pub async fn list_object_versions(
             last_modified: chrono::DateTime::from(version_info.last_modified),
             etag: version_info.etag,
             is_latest: version_info.is_latest,
+            labels: version_info.labels,
+            annotations: version_info.annotations,
         })
         .collect();
 
-    // Apply pagination if specified
-    let (truncated_versions, is_truncated) = if let Some(max_keys) = params.max_keys {
-        let truncated: Vec<_> = version_dtos.into_iter().take(max_keys).collect();
-        let is_truncated = truncated.len() >= max_keys;
-        (truncated, is_truncated)
-    } else {
-        (version_dtos, false)
-    };
-
     Ok(Json(ListVersionsResponseDto {
-        versions: truncated_versions,
+        versions: version_dtos,
         delete_markers: Vec::new(), // Would need to track delete markers
-        is_truncated,
-        next_key_marker: None,
-        next_version_id_marker: None,
+        total_count: page.total_count,
+        is_truncated: page.is_truncated,
+        next_key_marker: page.is_truncated.then(|| object_key.as_str().to_string()),
+        next_version_id_marker: page
+            .next_version_id_marker
+            .map(|marker| marker.as_str().to_string()),
     }))
 }
 
@@ -290,47 +346,19 @@ pub async fn list_object_versions(
 pub async fn copy_versioned_object(
     State(app_state): State<AppState>,
     Path((source_key, source_version_id, dest_key)): Path<(String, String, String)>,
-) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponseDto>)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     // Create object keys and version ID
-    let source_object_key = ObjectKey::new(source_key).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponseDto::bad_request(&format!(
-                "Invalid source key: {}",
-                e
-            ))),
-        )
-    })?;
-
-    let source_version = VersionId::new(source_version_id).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponseDto::bad_request(&format!(
-                "Invalid source version ID: {}",
-                e
-            ))),
-        )
-    })?;
-
-    let dest_object_key = ObjectKey::new(dest_key).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponseDto::bad_request(&format!(
-                "Invalid destination key: {}",
-                e
-            ))),
-        )
-    })?;
+    let source_object_key = ObjectKey::new(source_key)?;
+
+    let source_version = VersionId::new(source_version_id)?;
+
+    let dest_object_key = ObjectKey::new(dest_key)?;
 
     // Copy the version
     let new_version_id = app_state
         .versioning_service
         .copy_version(&source_object_key, &source_version, &dest_object_key)
-        .await
-        .map_err(|e| {
-            let status_code = StatusCode::from(e.clone());
-            (status_code, Json(ErrorResponseDto::from_storage_error(e)))
-        })?;
+        .await?;
 
     let response = serde_json::json!({
         "message": "Version copied successfully",
@@ -346,44 +374,21 @@ pub async fn copy_versioned_object(
 /// Handle checking if a specific version exists
 pub async fn head_versioned_object(
     State(app_state): State<AppState>,
-    Path((key, version_id)): Path<(String, String)>,
-) -> Result<(StatusCode, HeaderMap), (StatusCode, Json<ErrorResponseDto>)> {
-    // Create object key and version ID
-    let object_key = ObjectKey::new(key).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponseDto::bad_request(&format!(
-                "Invalid object key: {}",
-                e
-            ))),
-        )
-    })?;
-
-    let version = VersionId::new(version_id).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponseDto::bad_request(&format!(
-                "Invalid version ID: {}",
-                e
-            ))),
-        )
-    })?;
-
+    ObjectKeyPath(object_key): ObjectKeyPath,
+    VersionIdPath(version): VersionIdPath,
+) -> Result<(StatusCode, HeaderMap), ApiError> {
     // Check if version exists
     let exists = app_state
         .versioning_service
         .version_exists(&object_key, &version)
-        .await
-        .map_err(|e| {
-            let status_code = StatusCode::from(e.clone());
-            (status_code, Json(ErrorResponseDto::from_storage_error(e)))
-        })?;
+        .await?;
 
     if !exists {
-        return Err((
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponseDto::bad_request("Version not found")),
-        ));
+        return Err(StorageError::VersionNotFound {
+            key: object_key,
+            version_id: version,
+        }
+        .into());
     }
 
     let mut headers = HeaderMap::new();
@@ -396,45 +401,79 @@ pub async fn head_versioned_object(
 /// Handle restoring a previous version as the latest
 pub async fn restore_version(
     State(app_state): State<AppState>,
-    Path((key, version_id)): Path<(String, String)>,
-) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponseDto>)> {
-    // Create object key and version ID
-    let object_key = ObjectKey::new(key.clone()).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponseDto::bad_request(&format!(
-                "Invalid object key: {}",
-                e
-            ))),
-        )
-    })?;
-
-    let version = VersionId::new(version_id).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponseDto::bad_request(&format!(
-                "Invalid version ID: {}",
-                e
-            ))),
-        )
-    })?;
-
+    ObjectKeyPath(object_key): ObjectKeyPath,
+    VersionIdPath(version): VersionIdPath,
+) -> Result<Json<serde_json::Value>, ApiError> {
     // Restore the version by copying it as a new version
     let new_version_id = app_state
         .versioning_service
         .copy_version(&object_key, &version, &object_key)
-        .await
-        .map_err(|e| {
-            let status_code = StatusCode::from(e.clone());
-            (status_code, Json(ErrorResponseDto::from_storage_error(e)))
-        })?;
+        .await?;
 
     let response = serde_json::json!({
         "message": "Version restored successfully",
-        "key": key,
+        "key": object_key.as_str(),
         "restored_version_id": version.as_str(),
         "new_version_id": new_version_id.as_str()
     });
 
     Ok(Json(response))
 }
+
+/// Handle getting the block signature of a version, in blocks of `?block_size`
+/// bytes or [`DEFAULT_DELTA_BLOCK_SIZE`]
+pub async fn get_version_signature(
+    State(app_state): State<AppState>,
+    ObjectKeyPath(object_key): ObjectKeyPath,
+    VersionIdPath(version): VersionIdPath,
+    Query(params): Query<SignatureQueryDto>,
+) -> Result<Json<ObjectSignatureDto>, ApiError> {
+    let signature = app_state
+        .versioning_service
+        .version_signature(
+            &object_key,
+            &version,
+            params.block_size.unwrap_or(DEFAULT_DELTA_BLOCK_SIZE),
+        )
+        .await?;
+
+    Ok(Json(ObjectSignatureDto {
+        key: object_key.to_string(),
+        version_id: version.to_string(),
+        block_size: signature.block_size,
+        size: signature.size,
+        blocks: signature.blocks.into_iter().map(Into::into).collect(),
+    }))
+}
+
+/// Handle uploading a new version as a delta against an existing version
+///
+/// The client computes the delta from the signature of the base version, so
+/// only the changed blocks are sent.
+pub async fn upload_version_delta(
+    State(app_state): State<AppState>,
+    ObjectKeyPath(object_key): ObjectKeyPath,
+    VersionIdPath(base_version): VersionIdPath,
+    Json(dto): Json<ObjectDeltaDto>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let delta = dto
+        .into_delta()
+        .map_err(|e| ApiError::BadRequest(format!("Invalid literal in delta: {}", e)))?;
+    let literal_bytes = delta.literal_bytes();
+
+    let versioned_object = app_state
+        .versioning_service
+        .create_version_from_delta(&object_key, &base_version, delta)
+        .await?;
+
+    let response = serde_json::json!({
+        "message": "Versioned object created from delta",
+        "key": object_key.as_str(),
+        "base_version_id": base_version.as_str(),
+        "version_id": versioned_object.version_id.as_str(),
+        "size": versioned_object.metadata.content_length,
+        "literal_bytes": literal_bytes
+    });
+
+    Ok(Json(response))
+}
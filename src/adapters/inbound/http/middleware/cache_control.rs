@@ -0,0 +1,78 @@
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use http::{
+    HeaderValue, Method, StatusCode,
+    header::{CACHE_CONTROL, ETAG},
+};
+
+/// Cache-Control sent with object reads when neither the object nor the
+/// defaults for its prefix set one
+///
+/// Object responses are recognised by their `ETag`, so listings and other
+/// API responses are left alone.
+#[derive(Debug, Clone)]
+pub struct DefaultCacheControl(pub HeaderValue);
+
+impl DefaultCacheControl {
+    pub fn new(value: &str) -> Result<Self, http::header::InvalidHeaderValue> {
+        Ok(Self(HeaderValue::from_str(value)?))
+    }
+}
+
+/// Add the default Cache-Control to successful GET and HEAD responses for
+/// objects that carry none
+pub async fn apply_default_cache_control(
+    State(default): State<DefaultCacheControl>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let is_read = matches!(*request.method(), Method::GET | Method::HEAD);
+    let mut response = next.run(request).await;
+
+    let cacheable = response.status().is_success() || response.status() == StatusCode::NOT_MODIFIED;
+    let headers = response.headers_mut();
+    if is_read && cacheable && headers.contains_key(ETAG) && !headers.contains_key(CACHE_CONTROL) {
+        headers.insert(CACHE_CONTROL, default.0);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, routing::get};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_default_applies_only_to_objects_without_their_own() {
+        let app = Router::new()
+            .route("/object", get(|| async { ([(ETAG, "\"abc\"")], "data") }))
+            .route(
+                "/private",
+                get(|| async { ([(ETAG, "\"abc\""), (CACHE_CONTROL, "private")], "data") }),
+            )
+            .route("/listing", get(|| async { "[]" }))
+            .layer(axum::middleware::from_fn_with_state(
+                DefaultCacheControl::new("public, max-age=60").unwrap(),
+                apply_default_cache_control,
+            ));
+        let get = |uri: &str| {
+            http::Request::builder()
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(get("/object")).await.unwrap();
+        assert_eq!(response.headers()[CACHE_CONTROL], "public, max-age=60");
+
+        let response = app.clone().oneshot(get("/private")).await.unwrap();
+        assert_eq!(response.headers()[CACHE_CONTROL], "private");
+
+        let response = app.oneshot(get("/listing")).await.unwrap();
+        assert!(!response.headers().contains_key(CACHE_CONTROL));
+    }
+}
@@ -0,0 +1,201 @@
+use axum::{
+    Json,
+    extract::Request,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use http::{HeaderMap, StatusCode};
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::adapters::inbound::http::dto::ErrorResponseDto;
+
+/// Header carrying the absolute time by which the client needs a response
+///
+/// Accepts Unix epoch milliseconds or an RFC 3339 timestamp.
+pub const REQUEST_DEADLINE_HEADER: &str = "x-request-deadline";
+
+/// Header carrying how long the client is willing to wait, in milliseconds
+pub const REQUEST_TIMEOUT_HEADER: &str = "x-request-timeout";
+
+/// Deadline of the current request, available to handlers as a request extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestDeadline(pub Instant);
+
+impl RequestDeadline {
+    /// Time left before the deadline passes
+    pub fn remaining(&self) -> Duration {
+        self.0.saturating_duration_since(Instant::now())
+    }
+
+    /// Whether the deadline has already passed
+    pub fn is_expired(&self) -> bool {
+        self.remaining().is_zero()
+    }
+}
+
+/// Parse the deadline headers into the time left for the request
+///
+/// When both headers are present the earlier deadline wins. Returns `Ok(None)`
+/// if the client did not ask for a deadline.
+pub fn parse_request_deadline(
+    headers: &HeaderMap,
+    now: DateTime<Utc>,
+) -> Result<Option<Duration>, String> {
+    let header = |name: &str| -> Result<Option<&str>, String> {
+        headers
+            .get(name)
+            .map(|v| v.to_str().map_err(|_| format!("Invalid {} header", name)))
+            .transpose()
+    };
+
+    let from_deadline = match header(REQUEST_DEADLINE_HEADER)? {
+        Some(value) => {
+            let deadline = parse_deadline_value(value.trim()).ok_or_else(|| {
+                format!(
+                    "Invalid {} header '{}': expected Unix milliseconds or RFC 3339",
+                    REQUEST_DEADLINE_HEADER, value
+                )
+            })?;
+            Some((deadline - now).to_std().unwrap_or(Duration::ZERO))
+        }
+        None => None,
+    };
+
+    let from_timeout = match header(REQUEST_TIMEOUT_HEADER)? {
+        Some(value) => {
+            let millis = value.trim().parse::<u64>().map_err(|_| {
+                format!(
+                    "Invalid {} header '{}': expected milliseconds",
+                    REQUEST_TIMEOUT_HEADER, value
+                )
+            })?;
+            Some(Duration::from_millis(millis))
+        }
+        None => None,
+    };
+
+    Ok(match (from_deadline, from_timeout) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    })
+}
+
+fn parse_deadline_value(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(millis) = value.parse::<i64>() {
+        return DateTime::from_timestamp_millis(millis);
+    }
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+fn deadline_exceeded() -> Response {
+    (
+        StatusCode::GATEWAY_TIMEOUT,
+        Json(ErrorResponseDto::gateway_timeout(
+            "Request deadline exceeded before a response was ready",
+        )),
+    )
+        .into_response()
+}
+
+/// Middleware turning the client's deadline into a cancellation deadline
+///
+/// The handler future is dropped once the deadline passes, which cancels any
+/// storage or repository call it is awaiting, so work for clients that have given
+/// up stops consuming backend resources. Requests whose deadline has already
+/// passed are rejected without reaching the handler.
+pub async fn enforce_request_deadline(mut request: Request, next: Next) -> Response {
+    let remaining = match parse_request_deadline(request.headers(), Utc::now()) {
+        Ok(Some(remaining)) => remaining,
+        Ok(None) => return next.run(request).await,
+        Err(message) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponseDto::bad_request(&message)),
+            )
+                .into_response();
+        }
+    };
+
+    if remaining.is_zero() {
+        return deadline_exceeded();
+    }
+
+    let deadline = Instant::now() + remaining;
+    request.extensions_mut().insert(RequestDeadline(deadline));
+
+    match tokio::time::timeout_at(deadline, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => deadline_exceeded(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn test_parse_request_deadline() {
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let millis = now.timestamp_millis();
+
+        assert_eq!(parse_request_deadline(&HeaderMap::new(), now), Ok(None));
+        assert_eq!(
+            parse_request_deadline(&headers(&[(REQUEST_TIMEOUT_HEADER, "1500")]), now),
+            Ok(Some(Duration::from_millis(1500)))
+        );
+        assert_eq!(
+            parse_request_deadline(
+                &headers(&[(REQUEST_DEADLINE_HEADER, &(millis + 2000).to_string())]),
+                now
+            ),
+            Ok(Some(Duration::from_secs(2)))
+        );
+        assert_eq!(
+            parse_request_deadline(
+                &headers(&[(REQUEST_DEADLINE_HEADER, "2024-01-01T00:00:03Z")]),
+                now
+            ),
+            Ok(Some(Duration::from_secs(3)))
+        );
+
+        // The earlier of the two wins, and past deadlines leave no time
+        assert_eq!(
+            parse_request_deadline(
+                &headers(&[
+                    (REQUEST_DEADLINE_HEADER, "2024-01-01T00:00:03Z"),
+                    (REQUEST_TIMEOUT_HEADER, "500"),
+                ]),
+                now
+            ),
+            Ok(Some(Duration::from_millis(500)))
+        );
+        assert_eq!(
+            parse_request_deadline(
+                &headers(&[(REQUEST_DEADLINE_HEADER, "2023-12-31T23:59:00Z")]),
+                now
+            ),
+            Ok(Some(Duration::ZERO))
+        );
+
+        assert!(
+            parse_request_deadline(&headers(&[(REQUEST_TIMEOUT_HEADER, "soon")]), now).is_err()
+        );
+        assert!(
+            parse_request_deadline(&headers(&[(REQUEST_DEADLINE_HEADER, "tomorrow")]), now)
+                .is_err()
+        );
+    }
+}
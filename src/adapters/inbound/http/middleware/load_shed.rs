@@ -0,0 +1,281 @@
+use axum::{
+    Json,
+    body::Body,
+    response::{IntoResponse, Response},
+};
+use futures::future::BoxFuture;
+use http::{Request, StatusCode, header::RETRY_AFTER};
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::sync::Semaphore;
+use tower::{Layer, Service};
+
+use crate::adapters::inbound::http::dto::ErrorResponseDto;
+
+/// Default number of requests admitted at once, executing or queued
+pub const DEFAULT_MAX_IN_FLIGHT: usize = 1024;
+
+/// Default number of requests executing at once
+pub const DEFAULT_MAX_CONCURRENCY: usize = 256;
+
+/// Default time a request may wait for an execution slot
+pub const DEFAULT_MAX_QUEUE_AGE: Duration = Duration::from_secs(1);
+
+/// Default delay suggested to shed clients through `Retry-After`
+pub const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+/// Thresholds at which the server starts rejecting requests
+#[derive(Debug, Clone)]
+pub struct LoadShedConfig {
+    /// Requests admitted at once, including those waiting for a slot
+    pub max_in_flight: usize,
+
+    /// Requests executing at once
+    pub max_concurrency: usize,
+
+    /// How long an admitted request may wait for a slot before it is shed
+    pub max_queue_age: Duration,
+
+    /// Delay returned to clients in the `Retry-After` header
+    pub retry_after: Duration,
+}
+
+impl Default for LoadShedConfig {
+    fn default() -> Self {
+        Self {
+            max_in_flight: DEFAULT_MAX_IN_FLIGHT,
+            max_concurrency: DEFAULT_MAX_CONCURRENCY,
+            max_queue_age: DEFAULT_MAX_QUEUE_AGE,
+            retry_after: DEFAULT_RETRY_AFTER,
+        }
+    }
+}
+
+/// Counters shared by every clone of the layer
+#[derive(Debug)]
+struct LoadShedState {
+    in_flight: AtomicUsize,
+    shed: AtomicU64,
+    slots: Arc<Semaphore>,
+}
+
+/// Decrements the in-flight counter when a request finishes or is dropped
+struct InFlightGuard(Arc<LoadShedState>);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Tower layer that sheds load once the server is overloaded
+///
+/// A request is rejected with `503 Service Unavailable` and a `Retry-After` header
+/// when too many requests are already in flight, or when it has waited longer than
+/// the maximum queue age for an execution slot. Failing fast keeps the latency of
+/// admitted requests predictable instead of letting every request slow down.
+#[derive(Debug, Clone)]
+pub struct LoadShedLayer {
+    config: Arc<LoadShedConfig>,
+    state: Arc<LoadShedState>,
+}
+
+impl LoadShedLayer {
+    pub fn new(config: LoadShedConfig) -> Self {
+        let state = LoadShedState {
+            in_flight: AtomicUsize::new(0),
+            shed: AtomicU64::new(0),
+            slots: Arc::new(Semaphore::new(
+                config.max_concurrency.clamp(1, Semaphore::MAX_PERMITS),
+            )),
+        };
+
+        Self {
+            config: Arc::new(config),
+            state: Arc::new(state),
+        }
+    }
+
+    /// Number of requests currently executing or queued
+    pub fn in_flight(&self) -> usize {
+        self.state.in_flight.load(Ordering::Acquire)
+    }
+
+    /// Number of requests rejected so far
+    pub fn shed_count(&self) -> u64 {
+        self.state.shed.load(Ordering::Relaxed)
+    }
+}
+
+impl Default for LoadShedLayer {
+    fn default() -> Self {
+        Self::new(LoadShedConfig::default())
+    }
+}
+
+impl<S> Layer<S> for LoadShedLayer {
+    type Service = LoadShed<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        LoadShed {
+            inner,
+            config: self.config.clone(),
+            state: self.state.clone(),
+        }
+    }
+}
+
+/// Service produced by [`LoadShedLayer`]
+#[derive(Debug, Clone)]
+pub struct LoadShed<S> {
+    inner: S,
+    config: Arc<LoadShedConfig>,
+    state: Arc<LoadShedState>,
+}
+
+impl<S> Service<Request<Body>> for LoadShed<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Send + Clone + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let mut inner = self.inner.clone();
+        let config = self.config.clone();
+        let state = self.state.clone();
+
+        let in_flight = state.in_flight.fetch_add(1, Ordering::AcqRel) + 1;
+        let guard = InFlightGuard(state.clone());
+
+        if in_flight > config.max_in_flight {
+            drop(guard);
+            state.shed.fetch_add(1, Ordering::Relaxed);
+            let response = overloaded(&config, "Too many requests in flight");
+            return Box::pin(async move { Ok(response) });
+        }
+
+        Box::pin(async move {
+            let _guard = guard;
+
+            let slot =
+                tokio::time::timeout(config.max_queue_age, state.slots.clone().acquire_owned())
+                    .await;
+
+            let _slot = match slot {
+                Ok(Ok(slot)) => slot,
+                _ => {
+                    state.shed.fetch_add(1, Ordering::Relaxed);
+                    return Ok(overloaded(&config, "Request queued for too long"));
+                }
+            };
+
+            inner.call(req).await
+        })
+    }
+}
+
+/// Build the `503 Service Unavailable` response returned to shed requests
+fn overloaded(config: &LoadShedConfig, reason: &str) -> Response<Body> {
+    let retry_after = config.retry_after.as_secs().max(1).to_string();
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        [(RETRY_AFTER, retry_after)],
+        Json(ErrorResponseDto::service_unavailable(&format!(
+            "Server is overloaded: {}",
+            reason
+        ))),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+    use tokio::sync::Notify;
+    use tower::{ServiceExt, service_fn};
+
+    /// Service that blocks every request until `release` is notified
+    fn blocking_service(
+        release: Arc<Notify>,
+    ) -> impl Service<
+        Request<Body>,
+        Response = Response<Body>,
+        Error = Infallible,
+        Future = BoxFuture<'static, Result<Response<Body>, Infallible>>,
+    > + Clone {
+        service_fn(move |_req: Request<Body>| {
+            let release = release.clone();
+            Box::pin(async move {
+                release.notified().await;
+                Ok::<_, Infallible>(Response::new(Body::empty()))
+            }) as BoxFuture<'static, _>
+        })
+    }
+
+    fn request() -> Request<Body> {
+        Request::builder().uri("/").body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_sheds_when_too_many_in_flight() {
+        let layer = LoadShedLayer::new(LoadShedConfig {
+            max_in_flight: 1,
+            max_queue_age: Duration::from_secs(5),
+            ..Default::default()
+        });
+        let release = Arc::new(Notify::new());
+        let service = layer.layer(blocking_service(release.clone()));
+
+        let first = tokio::spawn(service.clone().oneshot(request()));
+        while layer.in_flight() == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        let response = service.clone().oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers().get(RETRY_AFTER).unwrap(), "1");
+        assert_eq!(layer.shed_count(), 1);
+
+        release.notify_one();
+        let response = first.await.unwrap().unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(layer.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_sheds_requests_queued_too_long() {
+        let layer = LoadShedLayer::new(LoadShedConfig {
+            max_concurrency: 1,
+            max_queue_age: Duration::from_millis(20),
+            ..Default::default()
+        });
+        let release = Arc::new(Notify::new());
+        let service = layer.layer(blocking_service(release.clone()));
+
+        let first = tokio::spawn(service.clone().oneshot(request()));
+        while layer.state.slots.available_permits() > 0 {
+            tokio::task::yield_now().await;
+        }
+
+        // The only slot is taken, so this request times out in the queue
+        let response = service.clone().oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        release.notify_one();
+        assert_eq!(first.await.unwrap().unwrap().status(), StatusCode::OK);
+    }
+}
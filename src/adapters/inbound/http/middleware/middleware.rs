@@ -1,30 +1,41 @@
 use axum::{
     Router,
     body::Body,
-    extract::{Path, Query, State},
+    extract::{Query, State},
     http::{Request, Response, StatusCode},
     response::{IntoResponse, Json},
-    routing::get,
+    routing::any,
 };
 use bytes::Bytes;
-use futures::future::BoxFuture;
+use futures::{StreamExt, future::BoxFuture};
 use http::HeaderMap;
-use object_store::{ObjectStore, path::Path as ObjectPath};
+use object_store::{
+    GetOptions, GetResult, ObjectStore, buffered::BufWriter, path::Path as ObjectPath,
+};
+use percent_encoding::{AsciiSet, CONTROLS, percent_decode_str, utf8_percent_encode};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
+    ops::Range,
     sync::Arc,
     task::{Context, Poll},
 };
+use tokio::io::AsyncWriteExt;
 use tower::{Layer, Service};
 use uuid::Uuid;
 
-use crate::adapters::outbound::storage::{
-    error::StoreError,
-    lifecycle::{LifecycleConfiguration, LifecycleManager},
-    lifecycle_adapter,
-    minio::MinioClient,
-    versioning::VersionedStore,
+use crate::adapters::{
+    inbound::http::handlers::{
+        object_handlers::{parse_byte_range, quoted_etag},
+        versioning_handlers::MAX_VERSIONS_PER_PAGE,
+    },
+    outbound::storage::{
+        error::StoreError,
+        lifecycle::{LifecycleConfiguration, LifecycleManager},
+        lifecycle_adapter,
+        minio::MinioClient,
+        versioning::{VersionMetadata, VersionedStore},
+    },
 };
 
 // Responses
@@ -47,6 +58,12 @@ pub struct ListVersionsResponse {
 
     /// List of versions
     pub versions: Vec<VersionInfo>,
+
+    /// Whether more versions follow this page
+    pub is_truncated: bool,
+
+    /// Version ID to pass as `version_id_marker` for the next page
+    pub next_version_id_marker: Option<String>,
 }
 
 /// Information about a specific version
@@ -111,6 +128,20 @@ impl<T: ObjectStore + Send + Sync + 'static> ObjectStoreService<T> {
         Ok(result.bytes().await?)
     }
 
+    /// Get an object as a stream, without buffering its data
+    pub async fn get_object_stream(
+        &self,
+        bucket: &str,
+        key: &str,
+    ) -> Result<GetResult, StoreError> {
+        let path = self.make_path(bucket, key);
+
+        self.store.get(&path).await.map_err(|e| match e {
+            object_store::Error::NotFound { .. } => StoreError::ObjectNotFound(key.to_string()),
+            e => StoreError::ObjectStore(e),
+        })
+    }
+
     /// Get a specific version of an object
     pub async fn get_object_version(
         &self,
@@ -148,6 +179,37 @@ impl<T: ObjectStore + Send + Sync + 'static> ObjectStoreService<T> {
         Ok(PutObjectResponse { version_id, etag })
     }
 
+    /// Put an object by streaming a request body into the store
+    ///
+    /// Small bodies are written with a single put, larger ones are uploaded
+    /// in parts as they arrive, so memory use stays bounded.
+    pub async fn put_object_stream(
+        &self,
+        bucket: &str,
+        key: &str,
+        body: Body,
+        metadata: Option<HashMap<String, String>>,
+    ) -> Result<PutObjectResponse, StoreError> {
+        let path = self.make_path(bucket, key);
+
+        // Generate version ID
+        let version_id = Uuid::new_v4().to_string();
+
+        let store: Arc<dyn ObjectStore> = self.store.clone();
+        let mut writer = BufWriter::new(store, path);
+        let mut stream = body.into_data_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| StoreError::Other(e.to_string()))?;
+            writer.write_all(&chunk).await?;
+        }
+        writer.shutdown().await?;
+
+        // In a real implementation, we'd store the metadata and etag
+        let etag = metadata.as_ref().and_then(|m| m.get("etag").cloned());
+
+        Ok(PutObjectResponse { version_id, etag })
+    }
+
     /// Delete an object
     pub async fn delete_object(&self, bucket: &str, key: &str) -> Result<(), StoreError> {
         let path = self.make_path(bucket, key);
@@ -158,6 +220,38 @@ impl<T: ObjectStore + Send + Sync + 'static> ObjectStoreService<T> {
             .map_err(StoreError::ObjectStore)
     }
 
+    /// Get a specific version of an object, or a byte range of it, as a
+    /// stream along with the version's metadata
+    pub async fn get_object_version_stream(
+        &self,
+        bucket: &str,
+        key: &str,
+        version_id: &str,
+        range: Option<Range<u64>>,
+    ) -> Result<(VersionMetadata, GetResult), StoreError> {
+        let path = self.make_path(bucket, key);
+        let options = GetOptions {
+            range: range.map(Into::into),
+            ..Default::default()
+        };
+
+        self.store
+            .get_version_opts(&path, version_id, options)
+            .await
+    }
+
+    /// Get the metadata of a specific version of an object
+    pub async fn head_object_version(
+        &self,
+        bucket: &str,
+        key: &str,
+        version_id: &str,
+    ) -> Result<VersionMetadata, StoreError> {
+        let path = self.make_path(bucket, key);
+
+        self.store.head_version(&path, version_id).await
+    }
+
     /// Delete a specific version of an object
     pub async fn delete_object_version(
         &self,
@@ -195,17 +289,21 @@ impl<T: ObjectStore + Send + Sync + 'static> ObjectStoreService<T> {
         result
     }
 
-    /// List versions of an object
+    /// List up to `limit` versions of an object, oldest first, after the
+    /// version `marker`
     pub async fn list_object_versions(
         &self,
         bucket: &str,
         key: &str,
+        marker: Option<&str>,
+        limit: usize,
     ) -> Result<ListVersionsResponse, StoreError> {
         let path = self.make_path(bucket, key);
 
-        let versions = self.store.list_versions(&path)?;
+        let page = self.store.list_versions_page(&path, marker, limit)?;
 
-        let version_infos = versions
+        let version_infos = page
+            .versions
             .into_iter()
             .map(|v| VersionInfo {
                 version_id: v.version_id,
@@ -218,6 +316,8 @@ impl<T: ObjectStore + Send + Sync + 'static> ObjectStoreService<T> {
         Ok(ListVersionsResponse {
             key: key.to_string(),
             versions: version_infos,
+            is_truncated: page.next_marker.is_some(),
+            next_version_id_marker: page.next_marker,
         })
     }
 
@@ -355,263 +455,16 @@ where
         let mut inner = self.inner.clone();
         let service = self.service.clone();
 
-        // Extract important information before moving req
-        let path = req.uri().path().to_string();
-        let method = req.method().clone();
-        let headers = req.headers().clone();
-
-        println!(
-            "Middleware received request: method={}, path={}",
-            method, path
-        );
-
         Box::pin(async move {
-            // If this is a request to the object store API, handle it
-            if path.starts_with("/storage/") {
-                // Determine what kind of request this is
-                if path.contains("/versions") && method == http::Method::GET {
-                    // This is a request to list versions
-                    if let Some(parts) = path.strip_prefix("/storage/").and_then(|p| {
-                        let parts: Vec<&str> = p.split('/').collect();
-                        if parts.len() >= 3 && parts[1] == "versions" {
-                            Some((parts[0], parts[2]))
-                        } else {
-                            None
-                        }
-                    }) {
-                        let (bucket, key) = parts;
-                        match service.list_object_versions(bucket, key).await {
-                            Ok(versions) => {
-                                return Ok(Json(versions).into_response());
-                            }
-                            Err(e) => {
-                                let status: StatusCode = e.into();
-                                return Ok(status.into_response());
-                            }
-                        }
-                    }
-                } else if path.contains("/version/") && method == http::Method::DELETE {
-                    // Delete a specific version
-                    println!(
-                        "Middleware: Processing DELETE for path containing /version/: {}",
-                        path
-                    );
-
-                    if let Some((bucket, key, version)) = path.strip_prefix("/storage/").and_then(|p| {
-                        let parts: Vec<&str> = p.split('/').collect();
-                        println!("Middleware: Extracted path parts: {:?}", parts);
-
-                        if parts.len() >= 4 && parts[1] == "version" {
-                            println!("Middleware: Found version deletion pattern with bucket={}, key={}, version={}", 
-                                    parts[0], parts[2], parts[3]);
-                            Some((parts[0], parts[2], parts[3]))
-                        } else {
-                            println!("Middleware: Path parts don't match expected pattern for version deletion");
-                            None
-                        }
-                    }) {
-                        println!("Middleware: Calling delete_object_version with bucket={}, key={}, version={}", bucket, key, version);
-                        match service.delete_object_version(bucket, key, version).await {
-                            Ok(_) => {
-                                println!("Middleware: Successfully processed version deletion");
-                                return Ok(StatusCode::NO_CONTENT.into_response());
-                            }
-                            Err(e) => {
-                                println!("Middleware: Error in version deletion: {:?}", e);
-                                let status: StatusCode = e.into();
-                                return Ok(status.into_response());
-                            }
-                        }
-                    } else {
-                        println!("Middleware: Could not extract version deletion components from path: {}", path);
-                    }
-                } else if path.contains("/version/") && method == http::Method::GET {
-                    // Get a specific version
-                    if let Some((bucket, key, version)) =
-                        path.strip_prefix("/storage/").and_then(|p| {
-                            let parts: Vec<&str> = p.split('/').collect();
-                            if parts.len() >= 4 && parts[1] == "version" {
-                                Some((parts[0], parts[2], parts[3]))
-                            } else {
-                                None
-                            }
-                        })
-                    {
-                        match service.get_object_version(bucket, key, version).await {
-                            Ok(data) => {
-                                return Ok(Response::builder()
-                                    .status(StatusCode::OK)
-                                    .body(Body::from(data))
-                                    .unwrap());
-                            }
-                            Err(e) => {
-                                let status: StatusCode = e.into();
-                                return Ok(status.into_response());
-                            }
-                        }
-                    }
-                } else if path.contains("/version/") && method == http::Method::DELETE {
-                    // Delete a specific version
-                    println!(
-                        "Middleware: Processing DELETE for path containing /version/: {}",
-                        path
-                    );
-
-                    if let Some((bucket, key, version)) =
-                        path.strip_prefix("/storage/").and_then(|p| {
-                            let parts: Vec<&str> = p.split('/').collect();
-                            println!("Middleware: Extracted path parts: {:?}", parts);
-                            if parts.len() >= 4 && parts[1] == "version" {
-                                Some((parts[0], parts[2], parts[3]))
-                            } else {
-                                None
-                            }
-                        })
-                    {
-                        println!(
-                            "Middleware: Will delete version: bucket={}, key={}, version={}",
-                            bucket, key, version
-                        );
-                        match service.delete_object_version(bucket, key, version).await {
-                            Ok(_) => {
-                                println!("Middleware: Successfully deleted version");
-                                return Ok(StatusCode::NO_CONTENT.into_response());
-                            }
-                            Err(e) => {
-                                println!("Middleware: Failed to delete version: {}", e);
-                                let status: StatusCode = e.into();
-                                return Ok(status.into_response());
-                            }
-                        }
-                    } else {
-                        println!(
-                            "Middleware: Failed to parse DELETE version request path: {}",
-                            path
-                        );
-                    }
-                } else if path.contains("/lifecycle") {
-                    // Handle lifecycle configuration requests
-                    if let Some(bucket) = path.strip_prefix("/storage/").and_then(|p| {
-                        let parts: Vec<&str> = p.split('/').collect();
-                        if parts.len() >= 2 && parts[1] == "lifecycle" {
-                            Some(parts[0])
-                        } else {
-                            None
-                        }
-                    }) {
-                        if method == http::Method::GET {
-                            // Get lifecycle configuration
-                            match service.get_lifecycle_config(bucket) {
-                                Ok(Some(config)) => {
-                                    return Ok(Json(config).into_response());
-                                }
-                                Ok(None) => {
-                                    // Create an empty config instead of returning 404
-                                    let empty_config = LifecycleConfiguration::new();
-                                    return Ok(Json(empty_config).into_response());
-                                }
-                                Err(e) => {
-                                    let status: StatusCode = e.into();
-                                    return Ok(status.into_response());
-                                }
-                            }
-                        } else if method == http::Method::PUT {
-                            // Read the request body and convert it to a LifecycleConfiguration
-                            let body_bytes = axum::body::to_bytes(req.into_body(), usize::MAX)
-                                .await
-                                .unwrap_or_default();
-
-                            // Parse the JSON to a LifecycleConfiguration
-                            match serde_json::from_slice::<LifecycleConfiguration>(&body_bytes) {
-                                Ok(config) => {
-                                    // Set the configuration
-                                    match service.set_lifecycle_config(bucket, config) {
-                                        Ok(_) => return Ok(StatusCode::OK.into_response()),
-                                        Err(e) => {
-                                            let status: StatusCode = e.into();
-                                            return Ok(status.into_response());
-                                        }
-                                    }
-                                }
-                                Err(_) => return Ok(StatusCode::BAD_REQUEST.into_response()),
-                            }
-                        }
-                    }
-                } else if method == http::Method::GET {
-                    // Regular GET request
-                    if let Some((bucket, key)) = path.strip_prefix("/storage/").and_then(|p| {
-                        let parts: Vec<&str> = p.split('/').collect();
-                        if parts.len() >= 2 {
-                            Some((parts[0], parts[1]))
-                        } else {
-                            None
-                        }
-                    }) {
-                        match service.get_object(bucket, key).await {
-                            Ok(data) => {
-                                return Ok(Response::builder()
-                                    .status(StatusCode::OK)
-                                    .body(Body::from(data))
-                                    .unwrap());
-                            }
-                            Err(e) => {
-                                let status: StatusCode = e.into();
-                                return Ok(status.into_response());
-                            }
-                        }
-                    }
-                } else if method == http::Method::PUT || method == http::Method::POST {
-                    // Upload request
-                    if let Some((bucket, key)) = path.strip_prefix("/storage/").and_then(|p| {
-                        let parts: Vec<&str> = p.split('/').collect();
-                        if parts.len() >= 2 {
-                            Some((parts[0], parts[1]))
-                        } else {
-                            None
-                        }
-                    }) {
-                        // Extract metadata from headers
-                        let metadata = extract_metadata_from_headers(&headers);
-
-                        // Read the request body (we can safely move the body now)
-                        let body_bytes = axum::body::to_bytes(req.into_body(), usize::MAX)
-                            .await
-                            .unwrap_or_default();
-
-                        match service.put_object(bucket, key, body_bytes, metadata).await {
-                            Ok(response) => {
-                                return Ok((StatusCode::CREATED, Json(response)).into_response());
-                            }
-                            Err(e) => {
-                                let status: StatusCode = e.into();
-                                return Ok(status.into_response());
-                            }
-                        }
-                    }
-                } else if method == http::Method::DELETE {
-                    // Delete request
-                    if let Some((bucket, key)) = path.strip_prefix("/storage/").and_then(|p| {
-                        let parts: Vec<&str> = p.split('/').collect();
-                        if parts.len() >= 2 {
-                            Some((parts[0], parts[1]))
-                        } else {
-                            None
-                        }
-                    }) {
-                        match service.delete_object(bucket, key).await {
-                            Ok(_) => {
-                                return Ok(StatusCode::NO_CONTENT.into_response());
-                            }
-                            Err(e) => {
-                                let status: StatusCode = e.into();
-                                return Ok(status.into_response());
-                            }
-                        }
-                    }
-                }
-            }
+            // Requests the route table has no handler for are passed on
+            let req = match StorageRoute::parse(req.uri().path()) {
+                Some(route) => match dispatch(&service, route, req).await {
+                    Ok(response) => return Ok(response),
+                    Err(req) => req,
+                },
+                None => req,
+            };
 
-            // If we get here, it wasn't handled by our middleware, so pass it on
             inner.call(req).await
         })
     }
@@ -630,6 +483,104 @@ where
     }
 }
 
+/// Build a response that streams an object's data straight from the store
+fn stream_response(result: GetResult) -> Response<Body> {
+    let size = result.meta.size;
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(http::header::CONTENT_LENGTH, size)
+        .body(Body::from_stream(result.into_stream()))
+        .unwrap()
+}
+
+/// Serve a version of an object, or the byte range of it that the `Range`
+/// header asks for, with the version's metadata as headers
+async fn version_response<T: ObjectStore + Send + Sync + 'static>(
+    service: &ObjectStoreService<T>,
+    bucket: &str,
+    key: &str,
+    version_id: &str,
+    headers: &HeaderMap,
+) -> Response<Body> {
+    let range = match headers
+        .get(http::header::RANGE)
+        .and_then(|r| r.to_str().ok())
+    {
+        Some(range_header) => {
+            // The range is checked against the size of the version
+            let size = match service.head_object_version(bucket, key, version_id).await {
+                Ok(metadata) => metadata.size as u64,
+                Err(e) => return error_response(e),
+            };
+            match parse_byte_range(range_header, size) {
+                Some(range) => Some(range),
+                None => {
+                    return Response::builder()
+                        .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                        .header(http::header::CONTENT_RANGE, format!("bytes */{}", size))
+                        .body(Body::empty())
+                        .unwrap();
+                }
+            }
+        }
+        None => None,
+    };
+
+    let (metadata, result) = match service
+        .get_object_version_stream(bucket, key, version_id, range.clone())
+        .await
+    {
+        Ok(version) => version,
+        Err(e) => return error_response(e),
+    };
+
+    let served = result.range.clone();
+    let mut response = Response::builder()
+        .status(StatusCode::OK)
+        .header(http::header::CONTENT_LENGTH, served.end - served.start);
+    if range.is_some() {
+        response = response.status(StatusCode::PARTIAL_CONTENT).header(
+            http::header::CONTENT_RANGE,
+            format!(
+                "bytes {}-{}/{}",
+                served.start,
+                served.end - 1,
+                result.meta.size
+            ),
+        );
+    }
+    if let Some(headers) = response.headers_mut() {
+        headers.extend(version_headers(&metadata));
+    }
+    response
+        .body(Body::from_stream(result.into_stream()))
+        .unwrap()
+}
+
+/// The version ID, ETag, creation time and range support of a version of an
+/// object as response headers
+fn version_headers(metadata: &VersionMetadata) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Ok(version_id) = metadata.version_id.parse() {
+        headers.insert("x-amz-version-id", version_id);
+    }
+    if let Some(etag) = metadata
+        .etag
+        .as_deref()
+        .and_then(|etag| quoted_etag(etag).parse().ok())
+    {
+        headers.insert(http::header::ETAG, etag);
+    }
+    let last_modified = metadata
+        .created_at
+        .format("%a, %d %b %Y %H:%M:%S GMT")
+        .to_string();
+    headers.insert(http::header::LAST_MODIFIED, last_modified.parse().unwrap());
+    headers.insert(http::header::ACCEPT_RANGES, "bytes".parse().unwrap());
+    headers
+}
+
 /// Extract metadata from HTTP headers
 fn extract_metadata_from_headers(headers: &HeaderMap) -> Option<HashMap<String, String>> {
     let mut metadata = HashMap::new();
@@ -665,180 +616,336 @@ fn extract_metadata_from_headers(headers: &HeaderMap) -> Option<HashMap<String,
     }
 }
 
-// Router builder
-
-/// Create an Axum router with object store routes
-pub fn create_object_store_router<T: ObjectStore + Send + Sync + 'static>(
-    service: Arc<ObjectStoreService<T>>,
-) -> Router {
-    Router::new()
-        // Object routes
-        .route(
-            "/storage/:bucket/:key",
-            get(get_object).put(put_object).delete(delete_object),
-        )
-        // Version management
-        .route("/storage/:bucket/versions/:key", get(list_object_versions))
-        .route(
-            "/storage/:bucket/version/:key/:version_id",
-            get(get_object_version).delete(delete_object_version),
-        )
-        // Lifecycle management
-        .route(
-            "/storage/:bucket/lifecycle",
-            get(get_lifecycle_config).put(set_lifecycle_config),
-        )
-        // MinIO-specific lifecycle management
-        .route(
-            "/storage/:bucket/minio-lifecycle",
-            get(get_minio_lifecycle_config)
-                .put(set_minio_lifecycle_config)
-                .delete(delete_minio_lifecycle_config),
-        )
-        .with_state(service)
+// Routes
+
+/// Characters percent-encoded in a path segment, including `/` itself
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// Operation addressed by a request path under `/storage/`
+///
+/// Keys may span several path segments and are percent-decoded, so
+/// `/storage/b/docs/a.txt` and `/storage/b/docs%2Fa.txt` name the same object.
+/// Keys starting with `versions/` or `version/`, and the keys `lifecycle` and
+/// `minio-lifecycle`, are taken by the other routes and can't name an object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StorageRoute {
+    /// `/storage/{bucket}/{key}`
+    Object { bucket: String, key: String },
+    /// `/storage/{bucket}/versions/{key}`
+    Versions { bucket: String, key: String },
+    /// `/storage/{bucket}/version/{key}/{version_id}`
+    Version {
+        bucket: String,
+        key: String,
+        version_id: String,
+    },
+    /// `/storage/{bucket}/lifecycle`
+    Lifecycle { bucket: String },
+    /// `/storage/{bucket}/minio-lifecycle`
+    MinioLifecycle { bucket: String },
 }
 
-// Handler functions
-
-async fn get_object(
-    State(service): State<Arc<ObjectStoreService<impl ObjectStore + Send + Sync + 'static>>>,
-    Path((bucket, key)): Path<(String, String)>,
-) -> impl IntoResponse {
-    match service.get_object(&bucket, &key).await {
-        Ok(data) => Response::builder()
-            .status(StatusCode::OK)
-            .body(Body::from(data))
-            .unwrap(),
-        Err(e) => {
-            let status: StatusCode = e.into();
-            status.into_response()
-        }
+impl StorageRoute {
+    /// Match a request path against the route table
+    ///
+    /// Returns None for paths outside `/storage/`, and for paths with an empty
+    /// bucket, key or version ID, or with percent-encoding that is not UTF-8.
+    pub fn parse(path: &str) -> Option<Self> {
+        let (bucket, rest) = path.strip_prefix("/storage/")?.split_once('/')?;
+        let bucket = decode_segment(bucket)?;
+
+        let route = match rest {
+            "lifecycle" => StorageRoute::Lifecycle { bucket },
+            "minio-lifecycle" => StorageRoute::MinioLifecycle { bucket },
+            _ => {
+                if let Some(key) = rest.strip_prefix("versions/") {
+                    StorageRoute::Versions {
+                        bucket,
+                        key: decode_segment(key)?,
+                    }
+                } else if let Some(rest) = rest.strip_prefix("version/") {
+                    let (key, version_id) = rest.rsplit_once('/')?;
+                    StorageRoute::Version {
+                        bucket,
+                        key: decode_segment(key)?,
+                        version_id: decode_segment(version_id)?,
+                    }
+                } else {
+                    StorageRoute::Object {
+                        bucket,
+                        key: decode_segment(rest)?,
+                    }
+                }
+            }
+        };
+        Some(route)
     }
-}
 
-async fn get_object_version(
-    State(service): State<Arc<ObjectStoreService<impl ObjectStore + Send + Sync + 'static>>>,
-    Path((bucket, key, version_id)): Path<(String, String, String)>,
-) -> impl IntoResponse {
-    match service.get_object_version(&bucket, &key, &version_id).await {
-        Ok(data) => Response::builder()
-            .status(StatusCode::OK)
-            .body(Body::from(data))
-            .unwrap(),
-        Err(e) => {
-            let status: StatusCode = e.into();
-            status.into_response()
+    /// Request path of this route, the inverse of [`Self::parse`]
+    ///
+    /// Each `/`-separated segment of the key is percent-encoded on its own, so
+    /// nested keys keep their structure in the path.
+    pub fn path(&self) -> String {
+        let encode_key = |key: &str| {
+            key.split('/')
+                .map(encode_segment)
+                .collect::<Vec<_>>()
+                .join("/")
+        };
+
+        match self {
+            StorageRoute::Object { bucket, key } => {
+                format!("/storage/{}/{}", encode_segment(bucket), encode_key(key))
+            }
+            StorageRoute::Versions { bucket, key } => {
+                format!(
+                    "/storage/{}/versions/{}",
+                    encode_segment(bucket),
+                    encode_key(key)
+                )
+            }
+            StorageRoute::Version {
+                bucket,
+                key,
+                version_id,
+            } => format!(
+                "/storage/{}/version/{}/{}",
+                encode_segment(bucket),
+                encode_key(key),
+                encode_segment(version_id)
+            ),
+            StorageRoute::Lifecycle { bucket } => {
+                format!("/storage/{}/lifecycle", encode_segment(bucket))
+            }
+            StorageRoute::MinioLifecycle { bucket } => {
+                format!("/storage/{}/minio-lifecycle", encode_segment(bucket))
+            }
         }
     }
 }
 
-async fn put_object(
-    State(service): State<Arc<ObjectStoreService<impl ObjectStore + Send + Sync + 'static>>>,
-    Path((bucket, key)): Path<(String, String)>,
-    body: Bytes,
-) -> impl IntoResponse {
-    match service.put_object(&bucket, &key, body, None).await {
-        Ok(response) => (StatusCode::CREATED, Json(response)).into_response(),
-        Err(e) => {
-            let status: StatusCode = e.into();
-            status.into_response()
-        }
-    }
+/// Percent-decode a non-empty part of a request path
+fn decode_segment(segment: &str) -> Option<String> {
+    let decoded = percent_decode_str(segment).decode_utf8().ok()?;
+    (!decoded.is_empty()).then(|| decoded.into_owned())
 }
 
-async fn delete_object(
-    State(service): State<Arc<ObjectStoreService<impl ObjectStore + Send + Sync + 'static>>>,
-    Path((bucket, key)): Path<(String, String)>,
-) -> impl IntoResponse {
-    match service.delete_object(&bucket, &key).await {
-        Ok(_) => StatusCode::NO_CONTENT,
-        Err(e) => e.into(),
-    }
+fn encode_segment(segment: &str) -> String {
+    utf8_percent_encode(segment, PATH_SEGMENT).to_string()
 }
 
-async fn delete_object_version(
-    State(service): State<Arc<ObjectStoreService<impl ObjectStore + Send + Sync + 'static>>>,
-    Path((bucket, key, version_id)): Path<(String, String, String)>,
-) -> impl IntoResponse {
-    println!(
-        "Router handler: delete_object_version called with bucket={}, key={}, version_id={}",
-        bucket, key, version_id
-    );
-    println!(
-        "Router handler: Path components extracted from router path: bucket={}, key={}, version_id={}",
-        bucket, key, version_id
-    );
-
-    // Logging the constructed path for comparison
-    let object_path = ObjectPath::from(key.as_str());
-    println!("Router handler: Constructed object path: {}", object_path);
-    // Don't try to print object_path directly as it returns an iterator that doesn't implement Debug
-    println!(
-        "Router handler: Object path string: {}",
-        object_path.to_string()
-    );
-
-    match service
-        .delete_object_version(&bucket, &key, &version_id)
-        .await
-    {
-        Ok(_) => {
-            println!(
-                "Router handler: Successfully deleted version: bucket={}, key={}, version_id={}",
-                bucket, key, version_id
-            );
-            StatusCode::NO_CONTENT
+/// Serve `req` for `route`, handing the request back if the route has no
+/// handler for its method
+async fn dispatch<T: ObjectStore + Send + Sync + 'static>(
+    service: &ObjectStoreService<T>,
+    route: StorageRoute,
+    req: Request<Body>,
+) -> Result<Response<Body>, Request<Body>> {
+    let method = req.method().clone();
+
+    let response = match (route, method.as_str()) {
+        (StorageRoute::Object { bucket, key }, "GET") => {
+            match service.get_object_stream(&bucket, &key).await {
+                Ok(result) => stream_response(result),
+                Err(e) => error_response(e),
+            }
         }
-        Err(e) => {
-            println!(
-                "Router handler: Error deleting version: bucket={}, key={}, version_id={}, error={}",
-                bucket, key, version_id, e
-            );
-            println!("Router handler: Error details: {:?}", e);
-            e.into()
+        (StorageRoute::Object { bucket, key }, "PUT" | "POST") => {
+            let metadata = extract_metadata_from_headers(req.headers());
+            match service
+                .put_object_stream(&bucket, &key, req.into_body(), metadata)
+                .await
+            {
+                Ok(response) => (StatusCode::CREATED, Json(response)).into_response(),
+                Err(e) => error_response(e),
+            }
         }
-    }
+        (StorageRoute::Object { bucket, key }, "DELETE") => {
+            match service.delete_object(&bucket, &key).await {
+                Ok(()) => StatusCode::NO_CONTENT.into_response(),
+                Err(e) => error_response(e),
+            }
+        }
+        (StorageRoute::Versions { bucket, key }, "GET") => {
+            let Ok(Query(params)) = Query::<ListVersionsParams>::try_from_uri(req.uri()) else {
+                return Ok(StatusCode::BAD_REQUEST.into_response());
+            };
+            let limit = params
+                .max_keys
+                .unwrap_or(MAX_VERSIONS_PER_PAGE)
+                .clamp(1, MAX_VERSIONS_PER_PAGE);
+            match service
+                .list_object_versions(&bucket, &key, params.version_id_marker.as_deref(), limit)
+                .await
+            {
+                Ok(versions) => Json(versions).into_response(),
+                Err(e) => error_response(e),
+            }
+        }
+        (
+            StorageRoute::Version {
+                bucket,
+                key,
+                version_id,
+            },
+            "GET",
+        ) => version_response(service, &bucket, &key, &version_id, req.headers()).await,
+        (
+            StorageRoute::Version {
+                bucket,
+                key,
+                version_id,
+            },
+            "HEAD",
+        ) => match service
+            .head_object_version(&bucket, &key, &version_id)
+            .await
+        {
+            Ok(metadata) => {
+                let mut response = Response::builder()
+                    .status(StatusCode::OK)
+                    .header(http::header::CONTENT_LENGTH, metadata.size);
+                if let Some(headers) = response.headers_mut() {
+                    headers.extend(version_headers(&metadata));
+                }
+                response.body(Body::empty()).unwrap()
+            }
+            Err(e) => error_response(e),
+        },
+        (
+            StorageRoute::Version {
+                bucket,
+                key,
+                version_id,
+            },
+            "DELETE",
+        ) => match service
+            .delete_object_version(&bucket, &key, &version_id)
+            .await
+        {
+            Ok(()) => StatusCode::NO_CONTENT.into_response(),
+            Err(e) => error_response(e),
+        },
+        (StorageRoute::Lifecycle { bucket }, "GET") => {
+            // A bucket without a configuration has an empty one rather than none
+            match service.get_lifecycle_config(&bucket) {
+                Ok(config) => {
+                    Json(config.unwrap_or_else(LifecycleConfiguration::new)).into_response()
+                }
+                Err(e) => error_response(e),
+            }
+        }
+        (StorageRoute::Lifecycle { bucket }, "PUT") => {
+            let config = match read_json::<LifecycleConfiguration>(req).await {
+                Ok(config) => config,
+                Err(response) => return Ok(response),
+            };
+            match service.set_lifecycle_config(&bucket, config) {
+                Ok(()) => StatusCode::OK.into_response(),
+                Err(e) => error_response(e),
+            }
+        }
+        (StorageRoute::MinioLifecycle { bucket }, "GET" | "PUT" | "DELETE") => {
+            let Ok(Query(creds)) = Query::<MinioCredentials>::try_from_uri(req.uri()) else {
+                return Ok(StatusCode::BAD_REQUEST.into_response());
+            };
+            let (endpoint, access_key, secret_key) =
+                (&creds.endpoint, &creds.access_key, &creds.secret_key);
+
+            match method.as_str() {
+                "GET" => match service
+                    .get_minio_lifecycle_config(&bucket, endpoint, access_key, secret_key)
+                    .await
+                {
+                    Ok(config) => Json(config).into_response(),
+                    Err(e) => error_response(e),
+                },
+                "PUT" => {
+                    let config = match read_json::<LifecycleConfiguration>(req).await {
+                        Ok(config) => config,
+                        Err(response) => return Ok(response),
+                    };
+                    match service
+                        .set_minio_lifecycle_config(
+                            &bucket, &config, endpoint, access_key, secret_key,
+                        )
+                        .await
+                    {
+                        Ok(()) => StatusCode::OK.into_response(),
+                        Err(e) => error_response(e),
+                    }
+                }
+                _ => match service
+                    .delete_minio_lifecycle_config(&bucket, endpoint, access_key, secret_key)
+                    .await
+                {
+                    Ok(()) => StatusCode::NO_CONTENT.into_response(),
+                    Err(e) => error_response(e),
+                },
+            }
+        }
+        _ => return Err(req),
+    };
+
+    Ok(response)
 }
 
-async fn list_object_versions(
-    State(service): State<Arc<ObjectStoreService<impl ObjectStore + Send + Sync + 'static>>>,
-    Path((bucket, key)): Path<(String, String)>,
-) -> impl IntoResponse {
-    match service.list_object_versions(&bucket, &key).await {
-        Ok(versions) => Json(versions).into_response(),
-        Err(e) => {
-            let status: StatusCode = e.into();
-            status.into_response()
-        }
-    }
+/// Read a JSON request body, answering 400 if it isn't one
+async fn read_json<D: serde::de::DeserializeOwned>(
+    req: Request<Body>,
+) -> Result<D, Response<Body>> {
+    let body = axum::body::to_bytes(req.into_body(), usize::MAX)
+        .await
+        .map_err(|_| StatusCode::BAD_REQUEST.into_response())?;
+    serde_json::from_slice(&body).map_err(|_| StatusCode::BAD_REQUEST.into_response())
 }
 
-async fn get_lifecycle_config(
-    State(service): State<Arc<ObjectStoreService<impl ObjectStore + Send + Sync + 'static>>>,
-    Path(bucket): Path<String>,
-) -> impl IntoResponse {
-    match service.get_lifecycle_config(&bucket) {
-        Ok(Some(config)) => Json(config).into_response(),
-        Ok(None) => StatusCode::NOT_FOUND.into_response(),
-        Err(e) => {
-            let status: StatusCode = e.into();
-            status.into_response()
-        }
-    }
+fn error_response(e: StoreError) -> Response<Body> {
+    StatusCode::from(e).into_response()
+}
+
+// Router builder
+
+/// Create an Axum router with object store routes
+///
+/// Requests are matched with [`StorageRoute`], the same table
+/// [`ObjectStoreMiddleware`] serves from.
+pub fn create_object_store_router<T: ObjectStore + Send + Sync + 'static>(
+    service: Arc<ObjectStoreService<T>>,
+) -> Router {
+    Router::new()
+        .route("/storage/{bucket}/{*rest}", any(route_storage_request::<T>))
+        .with_state(service)
 }
 
-async fn set_lifecycle_config(
-    State(service): State<Arc<ObjectStoreService<impl ObjectStore + Send + Sync + 'static>>>,
-    Path(bucket): Path<String>,
-    Json(config): Json<LifecycleConfiguration>,
-) -> impl IntoResponse {
-    match service.set_lifecycle_config(&bucket, config) {
-        Ok(_) => StatusCode::OK,
-        Err(e) => e.into(),
+async fn route_storage_request<T: ObjectStore + Send + Sync + 'static>(
+    State(service): State<Arc<ObjectStoreService<T>>>,
+    req: Request<Body>,
+) -> Response<Body> {
+    match StorageRoute::parse(req.uri().path()) {
+        Some(route) => dispatch(&service, route, req)
+            .await
+            .unwrap_or_else(|_| StatusCode::METHOD_NOT_ALLOWED.into_response()),
+        None => StatusCode::NOT_FOUND.into_response(),
     }
 }
 
-// MinIO-specific handlers
+#[derive(Deserialize)]
+struct ListVersionsParams {
+    max_keys: Option<usize>,
+    version_id_marker: Option<String>,
+}
 
 #[derive(Deserialize)]
 struct MinioCredentials {
@@ -847,64 +954,152 @@ struct MinioCredentials {
     secret_key: String,
 }
 
-async fn get_minio_lifecycle_config(
-    State(service): State<Arc<ObjectStoreService<impl ObjectStore + Send + Sync + 'static>>>,
-    Path(bucket): Path<String>,
-    Query(creds): Query<MinioCredentials>,
-) -> impl IntoResponse {
-    match service
-        .get_minio_lifecycle_config(
-            &bucket,
-            &creds.endpoint,
-            &creds.access_key,
-            &creds.secret_key,
-        )
-        .await
-    {
-        Ok(config) => Json(config).into_response(),
-        Err(e) => {
-            let status: StatusCode = e.into();
-            status.into_response()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum_test::TestServer;
+    use object_store::memory::InMemory;
+
+    #[test]
+    fn test_routes_handle_nested_and_encoded_keys() {
+        assert_eq!(
+            StorageRoute::parse("/storage/media/videos/2024/intro.mp4"),
+            Some(StorageRoute::Object {
+                bucket: "media".to_string(),
+                key: "videos/2024/intro.mp4".to_string(),
+            })
+        );
+        assert_eq!(
+            StorageRoute::parse("/storage/media/videos%2F2024/intro%20final.mp4"),
+            Some(StorageRoute::Object {
+                bucket: "media".to_string(),
+                key: "videos/2024/intro final.mp4".to_string(),
+            })
+        );
+        assert_eq!(
+            StorageRoute::parse("/storage/media/version/videos/intro.mp4/v1"),
+            Some(StorageRoute::Version {
+                bucket: "media".to_string(),
+                key: "videos/intro.mp4".to_string(),
+                version_id: "v1".to_string(),
+            })
+        );
+        assert_eq!(
+            StorageRoute::parse("/storage/media/lifecycle"),
+            Some(StorageRoute::Lifecycle {
+                bucket: "media".to_string(),
+            })
+        );
+
+        for path in [
+            "/objects/a.txt",
+            "/storage/media",
+            "/storage/media/",
+            "/storage//a",
+        ] {
+            assert_eq!(StorageRoute::parse(path), None, "{}", path);
         }
     }
-}
 
-async fn set_minio_lifecycle_config(
-    State(service): State<Arc<ObjectStoreService<impl ObjectStore + Send + Sync + 'static>>>,
-    Path(bucket): Path<String>,
-    Query(creds): Query<MinioCredentials>,
-    Json(config): Json<LifecycleConfiguration>,
-) -> impl IntoResponse {
-    match service
-        .set_minio_lifecycle_config(
-            &bucket,
-            &config,
-            &creds.endpoint,
-            &creds.access_key,
-            &creds.secret_key,
-        )
-        .await
-    {
-        Ok(_) => StatusCode::OK,
-        Err(e) => e.into(),
+    #[test]
+    fn test_route_paths_round_trip() {
+        let routes = [
+            StorageRoute::Object {
+                bucket: "media".to_string(),
+                key: "a dir/50% off?.txt".to_string(),
+            },
+            StorageRoute::Versions {
+                bucket: "media".to_string(),
+                key: "docs/#1.txt".to_string(),
+            },
+            StorageRoute::Version {
+                bucket: "media".to_string(),
+                key: "docs/report.pdf".to_string(),
+                version_id: "3f2a".to_string(),
+            },
+            StorageRoute::MinioLifecycle {
+                bucket: "media".to_string(),
+            },
+        ];
+
+        for route in routes {
+            assert_eq!(StorageRoute::parse(&route.path()), Some(route));
+        }
     }
-}
 
-async fn delete_minio_lifecycle_config(
-    State(service): State<Arc<ObjectStoreService<impl ObjectStore + Send + Sync + 'static>>>,
-    Path(bucket): Path<String>,
-    Query(creds): Query<MinioCredentials>,
-) -> impl IntoResponse {
-    match service
-        .delete_minio_lifecycle_config(
-            &bucket,
-            &creds.endpoint,
-            &creds.access_key,
-            &creds.secret_key,
-        )
-        .await
-    {
-        Ok(_) => StatusCode::NO_CONTENT,
-        Err(e) => e.into(),
+    #[tokio::test]
+    async fn test_router_serves_deep_keys() {
+        let service = Arc::new(ObjectStoreService::new(InMemory::new()));
+        let server = TestServer::new(create_object_store_router(service)).unwrap();
+
+        let put = server
+            .put("/storage/photos/2024/07/img.jpg")
+            .bytes(Bytes::from_static(b"jpeg"))
+            .await;
+        assert_eq!(put.status_code(), StatusCode::CREATED);
+
+        // Literal and encoded slashes name the same object
+        for path in [
+            "/storage/photos/2024/07/img.jpg",
+            "/storage/photos/2024%2F07%2Fimg.jpg",
+        ] {
+            let get = server.get(path).await;
+            assert_eq!(get.status_code(), StatusCode::OK, "{}", path);
+            assert_eq!(get.as_bytes().as_ref(), b"jpeg");
+        }
+
+        let versions = server.get("/storage/photos/versions/2024/07/img.jpg").await;
+        assert_eq!(versions.status_code(), StatusCode::OK);
+        let versions: serde_json::Value = versions.json();
+        assert_eq!(versions["key"], "2024/07/img.jpg");
+        assert_eq!(versions["versions"].as_array().unwrap().len(), 1);
+
+        let page = server
+            .get("/storage/photos/versions/2024/07/img.jpg")
+            .add_query_param("max_keys", 1)
+            .await;
+        let page: serde_json::Value = page.json();
+        assert_eq!(page["is_truncated"], false);
+
+        let delete = server.delete("/storage/photos/2024/07/img.jpg").await;
+        assert_eq!(delete.status_code(), StatusCode::NO_CONTENT);
+        let get = server.get("/storage/photos/2024/07/img.jpg").await;
+        assert_eq!(get.status_code(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_router_serves_version_ranges_with_metadata() {
+        let service = Arc::new(ObjectStoreService::new(InMemory::new()));
+        let server = TestServer::new(create_object_store_router(service)).unwrap();
+
+        for body in ["0123456789", "latest"] {
+            let put = server
+                .put("/storage/docs/a.txt")
+                .bytes(Bytes::from_static(body.as_bytes()))
+                .await;
+            assert_eq!(put.status_code(), StatusCode::CREATED);
+        }
+        let versions: serde_json::Value = server.get("/storage/docs/versions/a.txt").await.json();
+        let version_id = versions["versions"][0]["version_id"].as_str().unwrap();
+        let path = format!("/storage/docs/version/a.txt/{}", version_id);
+
+        let get = server.get(&path).add_header("range", "bytes=2-4").await;
+        assert_eq!(get.status_code(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(get.as_bytes().as_ref(), b"234");
+        assert_eq!(get.header("content-range"), "bytes 2-4/10");
+        assert_eq!(get.header("x-amz-version-id"), version_id);
+        assert!(get.maybe_header("etag").is_some());
+        assert!(get.maybe_header("last-modified").is_some());
+
+        let get = server.get(&path).await;
+        assert_eq!(get.status_code(), StatusCode::OK);
+        assert_eq!(get.as_bytes().as_ref(), b"0123456789");
+
+        let get = server.get(&path).add_header("range", "bytes=10-").await;
+        assert_eq!(get.status_code(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(get.header("content-range"), "bytes */10");
+
+        let missing = server.get("/storage/docs/version/a.txt/missing").await;
+        assert_eq!(missing.status_code(), StatusCode::NOT_FOUND);
     }
 }
@@ -0,0 +1,197 @@
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use http::{
+    HeaderMap, HeaderName, HeaderValue, Method,
+    header::{CONNECTION, CONTENT_LENGTH, HOST},
+};
+use reqwest::Client;
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+use tokio::sync::Semaphore;
+use tracing::debug;
+
+/// Default number of mirrored requests that may be outstanding at once
+pub const DEFAULT_MAX_MIRRORS_IN_FLIGHT: usize = 64;
+
+/// Default time a mirrored request may take before it is abandoned
+pub const DEFAULT_MIRROR_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Headers carrying a client's credentials, only mirrored when asked to with
+/// [`RequestMirror::with_credentials`]
+const CREDENTIAL_HEADERS: [&str; 5] = [
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "x-api-key",
+    "x-amz-security-token",
+];
+
+/// Shadow traffic sent to a secondary server
+///
+/// A share of GET and HEAD requests is replayed against the secondary server in
+/// the background and its responses are discarded, so a migration target or a
+/// new backend can be exercised with real load without affecting clients.
+/// Sampling is deterministic: with 10 percent, every tenth read is mirrored.
+/// When too many mirrored requests are outstanding, further ones are skipped
+/// instead of queued.
+///
+/// Clients' credentials, such as the `Authorization` header and cookies, are
+/// left out of mirrored requests unless forwarding them is enabled.
+#[derive(Debug, Clone)]
+pub struct RequestMirror {
+    client: Client,
+    target: String,
+    percent: f64,
+    forward_credentials: bool,
+    reads_seen: Arc<AtomicU64>,
+    in_flight: Arc<Semaphore>,
+}
+
+impl RequestMirror {
+    /// Mirror `percent` (0 to 100) of reads to the server at `target`
+    pub fn new(target: &str, percent: f64) -> Self {
+        let client = Client::builder()
+            .timeout(DEFAULT_MIRROR_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            client,
+            target: target.trim_end_matches('/').to_string(),
+            percent: percent.clamp(0.0, 100.0),
+            forward_credentials: false,
+            reads_seen: Arc::new(AtomicU64::new(0)),
+            in_flight: Arc::new(Semaphore::new(DEFAULT_MAX_MIRRORS_IN_FLIGHT)),
+        }
+    }
+
+    /// Set how many mirrored requests may be outstanding at once
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.in_flight = Arc::new(Semaphore::new(max_in_flight.max(1)));
+        self
+    }
+
+    /// Forward clients' credentials to the secondary server too, for a
+    /// secondary that authenticates requests itself
+    pub fn with_credentials(mut self) -> Self {
+        self.forward_credentials = true;
+        self
+    }
+
+    /// Headers of a read that are replayed against the secondary server
+    fn forwarded_headers<'a>(
+        &'a self,
+        headers: &'a HeaderMap,
+    ) -> impl Iterator<Item = (&'a HeaderName, &'a HeaderValue)> {
+        headers.iter().filter(|(name, _)| {
+            *name != HOST
+                && *name != CONNECTION
+                && *name != CONTENT_LENGTH
+                && (self.forward_credentials || !CREDENTIAL_HEADERS.contains(&name.as_str()))
+        })
+    }
+
+    /// Whether the next read is one of those mirrored
+    fn sample(&self) -> bool {
+        let seen = self.reads_seen.fetch_add(1, Ordering::Relaxed) + 1;
+        let mirrored = |reads: u64| (reads as f64 * self.percent / 100.0).floor();
+        mirrored(seen) > mirrored(seen - 1)
+    }
+
+    /// Replay a read against the secondary server in the background
+    fn mirror(&self, method: &Method, path_and_query: &str, headers: &HeaderMap) {
+        let Ok(permit) = self.in_flight.clone().try_acquire_owned() else {
+            debug!("Skipping mirrored request, too many outstanding");
+            return;
+        };
+
+        let url = format!("{}{}", self.target, path_and_query);
+        let method = match *method {
+            Method::HEAD => reqwest::Method::HEAD,
+            _ => reqwest::Method::GET,
+        };
+        let mut request = self.client.request(method, &url);
+        for (name, value) in self.forwarded_headers(headers) {
+            request = request.header(name.as_str(), value.as_bytes());
+        }
+
+        tokio::spawn(async move {
+            match request.send().await {
+                Ok(response) => {
+                    // Read the body so the connection can be reused
+                    let status = response.status();
+                    let _ = response.bytes().await;
+                    debug!("Mirrored request to {} answered {}", url, status);
+                }
+                Err(e) => debug!("Mirrored request to {} failed: {}", url, e),
+            }
+            drop(permit);
+        });
+    }
+}
+
+/// Middleware mirroring a share of reads to a secondary server
+pub async fn mirror_reads(
+    State(mirror): State<RequestMirror>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if matches!(*request.method(), Method::GET | Method::HEAD) && mirror.sample() {
+        let path_and_query = request
+            .uri()
+            .path_and_query()
+            .map(|path| path.as_str())
+            .unwrap_or("/");
+        mirror.mirror(request.method(), path_and_query, request.headers());
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::header::{AUTHORIZATION, COOKIE};
+
+    #[test]
+    fn test_sampling_mirrors_the_configured_share() {
+        let mirrored = |percent: f64| {
+            let mirror = RequestMirror::new("http://localhost:3001/", percent);
+            (0..200).filter(|_| mirror.sample()).count()
+        };
+
+        assert_eq!(mirrored(0.0), 0);
+        assert_eq!(mirrored(12.5), 25);
+        assert_eq!(mirrored(100.0), 200);
+        assert_eq!(mirrored(250.0), 200);
+    }
+
+    #[test]
+    fn test_credentials_are_only_forwarded_when_enabled() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_static("AWS4-HMAC-SHA256 ..."),
+        );
+        headers.insert(COOKIE, HeaderValue::from_static("session=secret"));
+        headers.insert("x-api-key", HeaderValue::from_static("secret"));
+        headers.insert("range", HeaderValue::from_static("bytes=0-9"));
+        let forwarded = |mirror: &RequestMirror| {
+            mirror
+                .forwarded_headers(&headers)
+                .map(|(name, _)| name.as_str().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let mirror = RequestMirror::new("http://localhost:3001", 100.0);
+        assert_eq!(forwarded(&mirror), vec!["range"]);
+        assert_eq!(forwarded(&mirror.with_credentials()).len(), 4);
+    }
+}
@@ -1,3 +1,20 @@
+pub mod cache_control;
+pub mod cdn_origin;
+pub mod deadline;
+pub mod load_shed;
+#[allow(clippy::module_inception)]
 pub mod middleware;
+pub mod mirror;
+pub mod region;
+pub mod upload_budget;
 
-pub use middleware::{ObjectStoreLayer, ObjectStoreService};
+pub use cache_control::{DefaultCacheControl, apply_default_cache_control};
+pub use cdn_origin::{CdnOrigin, require_cdn_signature};
+pub use deadline::{RequestDeadline, enforce_request_deadline};
+pub use load_shed::{LoadShed, LoadShedConfig, LoadShedLayer};
+pub use middleware::{
+    ObjectStoreLayer, ObjectStoreService, StorageRoute, create_object_store_router,
+};
+pub use mirror::{RequestMirror, mirror_reads};
+pub use region::{RegionMismatch, ServerRegion, enforce_region};
+pub use upload_budget::{UploadBudget, limit_upload_memory};
@@ -0,0 +1,194 @@
+use axum::{
+    extract::{DefaultBodyLimit, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use http::{Method, header::CONTENT_LENGTH};
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tower::{Layer, ServiceExt};
+
+/// Default memory budget shared by all in-flight uploads (256 MiB)
+pub const DEFAULT_UPLOAD_MEMORY_BUDGET: usize = 256 * 1024 * 1024;
+
+/// Default amount of data a single upload may buffer before it is written out (10 MiB)
+///
+/// This matches the point at which streamed uploads switch to multipart.
+pub const DEFAULT_UPLOAD_BUFFER_SIZE: usize = 10 * 1024 * 1024;
+
+/// Global budget for data buffered by in-flight uploads
+///
+/// Every upload reserves the memory it may buffer before its body is read. When
+/// the budget is exhausted new uploads wait for a reservation, and because their
+/// bodies are not being read the clients are slowed down by TCP flow control
+/// instead of the process running out of memory.
+#[derive(Debug, Clone)]
+pub struct UploadBudget {
+    /// One permit per byte of budget
+    semaphore: Arc<Semaphore>,
+
+    /// Total budget in bytes
+    total_bytes: usize,
+
+    /// Most an individual upload may buffer
+    per_upload_bytes: usize,
+}
+
+impl UploadBudget {
+    /// Create a budget of `total_bytes` shared by all uploads
+    pub fn new(total_bytes: usize) -> Self {
+        let total_bytes = total_bytes.clamp(1, Semaphore::MAX_PERMITS);
+        Self {
+            semaphore: Arc::new(Semaphore::new(total_bytes)),
+            total_bytes,
+            per_upload_bytes: DEFAULT_UPLOAD_BUFFER_SIZE,
+        }
+    }
+
+    /// Set how much a single upload may buffer
+    pub fn with_per_upload_bytes(mut self, per_upload_bytes: usize) -> Self {
+        self.per_upload_bytes = per_upload_bytes.max(1);
+        self
+    }
+
+    /// Total budget in bytes
+    pub fn total_bytes(&self) -> usize {
+        self.total_bytes
+    }
+
+    /// Bytes not currently reserved by an upload
+    pub fn available_bytes(&self) -> usize {
+        self.semaphore.available_permits()
+    }
+
+    /// Number of bytes an upload with the given content length must reserve
+    fn reservation_for(&self, content_length: Option<u64>) -> u32 {
+        let wanted = content_length
+            .map(|len| usize::try_from(len).unwrap_or(usize::MAX))
+            .unwrap_or(self.per_upload_bytes);
+
+        wanted
+            .min(self.per_upload_bytes)
+            .min(self.total_bytes)
+            .min(u32::MAX as usize) as u32
+    }
+
+    /// Wait until the memory for an upload is available and reserve it
+    ///
+    /// The reservation is released when the returned permit is dropped.
+    pub async fn reserve(&self, content_length: Option<u64>) -> Option<OwnedSemaphorePermit> {
+        let bytes = self.reservation_for(content_length);
+        if bytes == 0 {
+            return None;
+        }
+
+        // The semaphore is never closed, so acquiring can only fail if that changes
+        self.semaphore.clone().acquire_many_owned(bytes).await.ok()
+    }
+}
+
+impl Default for UploadBudget {
+    fn default() -> Self {
+        Self::new(DEFAULT_UPLOAD_MEMORY_BUDGET)
+    }
+}
+
+/// Middleware holding a reservation from the upload budget for the duration of each upload
+///
+/// Handlers that buffer the whole body may read no more than was reserved, so
+/// chunked uploads and uploads larger than a single reservation are rejected
+/// with `413 Payload Too Large` by them. Handlers streaming the body are not
+/// limited, as they only buffer up to the reservation at a time.
+pub async fn limit_upload_memory(
+    State(budget): State<UploadBudget>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !matches!(
+        *request.method(),
+        Method::PUT | Method::POST | Method::PATCH
+    ) {
+        return next.run(request).await;
+    }
+
+    let content_length = request
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+
+    let limit = budget.reservation_for(content_length) as usize;
+    let _reservation = budget.reserve(content_length).await;
+    DefaultBodyLimit::max(limit)
+        .layer(next)
+        .oneshot(request)
+        .await
+        .unwrap_or_else(|never| match never {})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, routing::put};
+    use axum_test::TestServer;
+    use bytes::Bytes;
+    use std::time::Duration;
+
+    #[test]
+    fn test_reservation_size() {
+        let budget = UploadBudget::new(100).with_per_upload_bytes(40);
+
+        assert_eq!(budget.reservation_for(Some(10)), 10);
+        assert_eq!(budget.reservation_for(Some(1_000)), 40);
+        assert_eq!(budget.reservation_for(None), 40);
+        assert_eq!(budget.reservation_for(Some(0)), 0);
+
+        // A single upload can never need more than the whole budget
+        let small = UploadBudget::new(20).with_per_upload_bytes(40);
+        assert_eq!(small.reservation_for(None), 20);
+    }
+
+    #[tokio::test]
+    async fn test_uploads_wait_for_budget() {
+        let budget = UploadBudget::new(100).with_per_upload_bytes(60);
+
+        let first = budget.reserve(None).await;
+        assert_eq!(budget.available_bytes(), 40);
+
+        // A second full-size upload has to wait for the first to finish
+        let waiting = tokio::time::timeout(Duration::from_millis(50), budget.reserve(None)).await;
+        assert!(waiting.is_err());
+
+        drop(first);
+        let second = tokio::time::timeout(Duration::from_millis(50), budget.reserve(None)).await;
+        assert!(second.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_buffered_bodies_are_limited_to_the_reservation() {
+        async fn buffered(body: Bytes) -> String {
+            body.len().to_string()
+        }
+        async fn streamed(body: Body) -> String {
+            let body = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+            body.len().to_string()
+        }
+        let budget = UploadBudget::new(100).with_per_upload_bytes(8);
+        let router = Router::new()
+            .route("/buffered", put(buffered))
+            .route("/streamed", put(streamed))
+            .layer(axum::middleware::from_fn_with_state(
+                budget,
+                limit_upload_memory,
+            ));
+        let server = TestServer::new(router).unwrap();
+
+        let small = server.put("/buffered").bytes(vec![0; 8].into()).await;
+        assert_eq!(small.text(), "8");
+        let large = server.put("/buffered").bytes(vec![0; 16].into()).await;
+        assert_eq!(large.status_code(), 413);
+
+        let streamed = server.put("/streamed").bytes(vec![0; 16].into()).await;
+        assert_eq!(streamed.text(), "16");
+    }
+}
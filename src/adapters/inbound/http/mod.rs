@@ -1,9 +1,27 @@
+#[cfg(not(target_arch = "wasm32"))]
+pub mod archive;
 pub mod dto;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod error;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod extract;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod handlers;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod middleware;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod nested_keys;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod router;
 
 pub use dto::*;
+#[cfg(not(target_arch = "wasm32"))]
+pub use error::*;
+#[cfg(not(target_arch = "wasm32"))]
+pub use extract::*;
+#[cfg(not(target_arch = "wasm32"))]
 pub use handlers::*;
+#[cfg(not(target_arch = "wasm32"))]
 pub use middleware::*;
+#[cfg(not(target_arch = "wasm32"))]
 pub use router::*;
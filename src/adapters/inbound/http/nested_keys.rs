@@ -0,0 +1,192 @@
+//! Object keys spanning several path segments
+//!
+//! Routes capture an object key as a single path segment, so on its own a key
+//! with slashes would have to be sent percent-encoded
+//! (`/objects/photos%2F2024%2Fimg.jpg`). [`nest_keys`] lets clients send the
+//! slashes as they are: before routing, the slashes inside a key are encoded,
+//! and the route's key parameter decodes them again.
+//!
+//! Where a route has segments after the key, the key ends before them, so a
+//! key can't itself end in those segments: `/objects/a/presign` presigns `a`
+//! rather than naming the object `a/presign`, and
+//! `/versioned-objects/a/versions/b` is version `b` of `a`.
+
+use axum::extract::Request;
+use axum::http::Uri;
+
+/// Operations addressed by the two segments after `versions/` of a
+/// versioned object's path
+const VERSION_OPERATIONS: [&str; 5] = ["restore", "pin", "labels", "signature", "delta"];
+
+/// Encode the slashes of any nested object key in the request path, so the
+/// key reaches its route as one segment
+pub async fn nest_keys(mut request: Request) -> Request {
+    if let Some(path) = encode_key_slashes(request.uri().path()) {
+        let path_and_query = match request.uri().query() {
+            Some(query) => format!("{}?{}", path, query),
+            None => path,
+        };
+        let mut parts = request.uri().clone().into_parts();
+        if let Ok(path_and_query) = path_and_query.parse() {
+            parts.path_and_query = Some(path_and_query);
+            if let Ok(uri) = Uri::from_parts(parts) {
+                *request.uri_mut() = uri;
+            }
+        }
+    }
+    request
+}
+
+/// The path with the slashes inside its object keys encoded, or None when no
+/// key in it has any
+pub fn encode_key_slashes(path: &str) -> Option<String> {
+    let encoded = if let Some(rest) = path.strip_prefix("/objects/") {
+        format!("/objects/{}", encode_object_route(rest))
+    } else if let Some(rest) = path.strip_prefix("/versioned-objects/") {
+        format!("/versioned-objects/{}", encode_versioned_route(rest))
+    } else if let Some((prefix, key)) = split_after_objects(path, "/transactions/") {
+        format!("{}{}", prefix, encode(key))
+    } else if let Some((prefix, rest)) = split_after_objects(path, "/buckets/") {
+        format!("{}{}", prefix, encode_bucket_object_route(rest))
+    } else {
+        return None;
+    };
+    (encoded != path).then_some(encoded)
+}
+
+/// Split `<prefix><segment>/objects/<rest>` after `/objects/`
+fn split_after_objects<'a>(path: &'a str, prefix: &str) -> Option<(&'a str, &'a str)> {
+    let segment_end = prefix.len() + path.strip_prefix(prefix)?.find('/')?;
+    let rest = path[segment_end..].strip_prefix("/objects/")?;
+    Some(path.split_at(path.len() - rest.len()))
+}
+
+fn encode(key: &str) -> String {
+    key.replace('/', "%2F")
+}
+
+/// `<key>`, `<key>/delete-at`, `<key>/presign` or `<source>/copy/<dest>`
+fn encode_object_route(rest: &str) -> String {
+    if let Some((source, dest)) = rest.split_once("/copy/") {
+        return format!("{}/copy/{}", encode(source), encode(dest));
+    }
+    for suffix in ["/delete-at", "/presign"] {
+        if let Some(key) = rest.strip_suffix(suffix) {
+            return format!("{}{}", encode(key), suffix);
+        }
+    }
+    encode(rest)
+}
+
+/// `<key>`, `<key>/latest`, `<key>/versions`, `<key>/versions/delete`,
+/// `<key>/versions/<version>[/<operation>]` or
+/// `<source>/versions/<version>/copy/<dest>`
+fn encode_versioned_route(rest: &str) -> String {
+    for suffix in ["/latest", "/versions"] {
+        if let Some(key) = rest.strip_suffix(suffix) {
+            return format!("{}{}", encode(key), suffix);
+        }
+    }
+
+    // The first `versions/<version>/copy/` splits a copy between two keys
+    for (index, _) in rest.match_indices("/versions/") {
+        let (source, tail) = rest.split_at(index);
+        let tail = &tail["/versions/".len()..];
+        if let Some((version, dest)) = tail.split_once("/copy/") {
+            if !version.is_empty() && !version.contains('/') {
+                return format!(
+                    "{}/versions/{}/copy/{}",
+                    encode(source),
+                    version,
+                    encode(dest)
+                );
+            }
+        }
+    }
+
+    // Otherwise the last `versions/` followed by a version, and maybe an
+    // operation on it, ends the key
+    if let Some(index) = rest.rfind("/versions/") {
+        let (key, tail) = rest.split_at(index);
+        let segments: Vec<&str> = tail["/versions/".len()..].split('/').collect();
+        let addresses_version = match segments[..] {
+            [version] => !version.is_empty(),
+            [version, operation] => !version.is_empty() && VERSION_OPERATIONS.contains(&operation),
+            _ => false,
+        };
+        if addresses_version {
+            return format!("{}{}", encode(key), tail);
+        }
+    }
+    encode(rest)
+}
+
+/// `<key>`, `<key>/rollback` or `<key>/uploads/<upload>/presign`
+fn encode_bucket_object_route(rest: &str) -> String {
+    if let Some(key) = rest.strip_suffix("/rollback") {
+        return format!("{}/rollback", encode(key));
+    }
+    if let Some(index) = rest.rfind("/uploads/") {
+        let (key, tail) = rest.split_at(index);
+        if tail["/uploads/".len()..]
+            .split_once('/')
+            .is_some_and(|(upload, presign)| !upload.is_empty() && presign == "presign")
+        {
+            return format!("{}{}", encode(key), tail);
+        }
+    }
+    encode(rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slashes_are_encoded_inside_keys_only() {
+        for (path, encoded) in [
+            ("/objects/a/b/c.txt", "/objects/a%2Fb%2Fc.txt"),
+            ("/objects/a/b/presign", "/objects/a%2Fb/presign"),
+            ("/objects/a/b/copy/c/d", "/objects/a%2Fb/copy/c%2Fd"),
+            (
+                "/versioned-objects/a/b/latest",
+                "/versioned-objects/a%2Fb/latest",
+            ),
+            (
+                "/versioned-objects/a/b/versions/v1/pin",
+                "/versioned-objects/a%2Fb/versions/v1/pin",
+            ),
+            (
+                "/versioned-objects/a/versions/x/y/z",
+                "/versioned-objects/a%2Fversions%2Fx%2Fy%2Fz",
+            ),
+            (
+                "/versioned-objects/a/b/versions/v1/copy/c/d",
+                "/versioned-objects/a%2Fb/versions/v1/copy/c%2Fd",
+            ),
+            (
+                "/transactions/t1/objects/a/b",
+                "/transactions/t1/objects/a%2Fb",
+            ),
+            (
+                "/buckets/photos/objects/2024/img.jpg/uploads/u1/presign",
+                "/buckets/photos/objects/2024%2Fimg.jpg/uploads/u1/presign",
+            ),
+            (
+                "/buckets/photos/objects/2024/img.jpg/rollback",
+                "/buckets/photos/objects/2024%2Fimg.jpg/rollback",
+            ),
+        ] {
+            assert_eq!(
+                encode_key_slashes(path).as_deref(),
+                Some(encoded),
+                "{}",
+                path
+            );
+        }
+
+        for path in ["/objects/a.txt", "/buckets/photos/lifecycle", "/usage"] {
+            assert_eq!(encode_key_slashes(path), None, "{}", path);
+        }
+    }
+}
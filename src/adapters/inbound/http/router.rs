@@ -1,41 +1,98 @@
 use axum::{
     Router,
-    routing::{delete, get, head, post, put},
+    middleware::map_request,
+    routing::{delete, get, head, patch, post, put},
 };
+use tower::Layer;
 
+use super::nested_keys::nest_keys;
 use super::handlers::{
+    abort_multipart_upload,
+    abort_transaction,
     add_lifecycle_rule,
+    apply_lifecycle_template,
+    begin_transaction,
+    cancel_bucket_lifecycle,
+    commit_transaction,
+    copy_lifecycle_configuration,
     copy_object,
     copy_versioned_object,
+    create_backup_job,
+    create_bucket_archive,
+    cut_over_migration,
     // Object handlers
     create_object,
+    create_or_complete_multipart_upload,
+    delete_backup_job,
     delete_lifecycle_configuration,
     delete_object,
+    delete_object_versions,
     delete_versioned_object,
     disable_lifecycle_rule,
     enable_lifecycle_rule,
     evaluate_object_lifecycle,
+    export_bucket,
+    get_bucket,
+    get_bucket_lifecycle_status,
+    get_bucket_location,
+    get_bucket_versioning,
+    get_dataset,
+    get_key_rotation_status,
     get_latest_object,
     get_lifecycle_configuration,
+    get_migration_status,
     get_object,
+    get_transaction,
+    get_usage,
+    get_version_signature,
     get_versioned_object,
+    head_bucket,
     head_object,
     head_versioned_object,
+    label_version,
+    list_backup_jobs,
+    list_backup_runs,
     list_object_versions,
+    list_lifecycle_templates,
     list_objects,
+    list_parts,
+    patch_object,
+    pin_version,
+    presign_multipart_upload,
+    presign_object,
     process_bucket_lifecycle,
+    publish_dataset,
+    put_bucket,
+    put_bucket_versioning,
     // Versioning handlers
     put_versioned_object,
     remove_lifecycle_rule,
+    restore_backup_run,
     restore_version,
+    rollback_object,
+    run_bucket_lifecycle,
+    run_backup_job,
+    search_objects,
+    set_object_delete_at,
+    simulate_bucket_lifecycle,
+    stage_transaction_object,
+    start_key_rotation,
+    start_migration,
+    unpin_version,
+    upload_part,
+    upload_version_delta,
+    validate_lifecycle_configuration,
     // Lifecycle handlers
     set_lifecycle_configuration,
 };
 use std::sync::Arc;
 
 use crate::{
-    ports::services::{LifecycleService, ObjectService, VersioningService},
-    services::{LifecycleServiceImpl, ObjectServiceImpl, VersioningServiceImpl},
+    adapters::{
+        inbound::http::middleware::region::ServerRegion,
+        outbound::storage::{encryption::KeyRotationControl, migration::MigrationControl},
+    },
+    ports::services::{BackupService, LifecycleService, ObjectService, VersioningService},
 };
 
 /// Application state containing all services
@@ -47,15 +104,37 @@ pub struct AppState {
 }
 
 /// Create the main application router with all endpoints
+///
+/// Object keys may contain slashes (`PUT /objects/photos/2024/img.jpg`); see
+/// [`nest_keys`] for how they're told apart from the segments after a key.
 pub fn create_router(state: AppState) -> Router {
-    Router::new()
+    let routes = Router::new()
         // Object operations
         .route("/objects", get(list_objects))
+        .route("/objects/search", get(search_objects))
         .route("/objects/{key}", put(create_object))
         .route("/objects/{key}", get(get_object))
         .route("/objects/{key}", delete(delete_object))
         .route("/objects/{key}", head(head_object))
+        .route("/objects/{key}", patch(patch_object))
+        .route("/objects/{key}/delete-at", put(set_object_delete_at))
+        .route("/objects/{key}/presign", post(presign_object))
         .route("/objects/{source_key}/copy/{dest_key}", post(copy_object))
+        .route("/usage", get(get_usage))
+        // Multi-object transactions
+        .route("/transactions", post(begin_transaction))
+        .route("/transactions/{transaction_id}", get(get_transaction))
+        .route("/transactions/{transaction_id}", delete(abort_transaction))
+        .route(
+            "/transactions/{transaction_id}/objects/{key}",
+            put(stage_transaction_object),
+        )
+        .route(
+            "/transactions/{transaction_id}/commit",
+            post(commit_transaction),
+        )
+        // Dataset manifests
+        .route("/datasets/{name}", put(publish_dataset).get(get_dataset))
         // Versioned object operations
         .route("/versioned-objects/{key}", put(put_versioned_object))
         .route("/versioned-objects/{key}/latest", get(get_latest_object))
@@ -63,6 +142,10 @@ pub fn create_router(state: AppState) -> Router {
             "/versioned-objects/{key}/versions",
             get(list_object_versions),
         )
+        .route(
+            "/versioned-objects/{key}/versions/delete",
+            post(delete_object_versions),
+        )
         .route(
             "/versioned-objects/{key}/versions/{version_id}",
             get(get_versioned_object),
@@ -83,6 +166,46 @@ pub fn create_router(state: AppState) -> Router {
             "/versioned-objects/{key}/versions/{version_id}/restore",
             post(restore_version),
         )
+        .route(
+            "/versioned-objects/{key}/versions/{version_id}/pin",
+            put(pin_version).delete(unpin_version),
+        )
+        .route(
+            "/versioned-objects/{key}/versions/{version_id}/labels",
+            put(label_version),
+        )
+        .route(
+            "/versioned-objects/{key}/versions/{version_id}/signature",
+            get(get_version_signature),
+        )
+        .route(
+            "/versioned-objects/{key}/versions/{version_id}/delta",
+            post(upload_version_delta),
+        )
+        .route(
+            "/buckets/{bucket}/versioning",
+            put(put_bucket_versioning).get(get_bucket_versioning),
+        )
+        .route(
+            "/buckets/{bucket}/objects/{key}/rollback",
+            post(rollback_object),
+        )
+        // S3 bucket listings and multipart uploads
+        .route(
+            "/buckets/{bucket}",
+            get(get_bucket).put(put_bucket).head(head_bucket),
+        )
+        .route(
+            "/buckets/{bucket}/objects/{key}",
+            post(create_or_complete_multipart_upload)
+                .put(upload_part)
+                .get(list_parts)
+                .delete(abort_multipart_upload),
+        )
+        .route(
+            "/buckets/{bucket}/objects/{key}/uploads/{upload_id}/presign",
+            post(presign_multipart_upload),
+        )
         // Lifecycle management
         .route(
             "/buckets/{bucket}/lifecycle",
@@ -97,6 +220,18 @@ pub fn create_router(state: AppState) -> Router {
             delete(delete_lifecycle_configuration),
         )
         .route("/buckets/{bucket}/lifecycle/rules", post(add_lifecycle_rule))
+        .route(
+            "/buckets/{bucket}/lifecycle/copy",
+            post(copy_lifecycle_configuration),
+        )
+        .route(
+            "/buckets/{bucket}/lifecycle/validate",
+            post(validate_lifecycle_configuration),
+        )
+        .route(
+            "/buckets/{bucket}/lifecycle/templates/{template}",
+            post(apply_lifecycle_template),
+        )
         .route(
             "/buckets/{bucket}/lifecycle/rules/{rule_id}",
             delete(remove_lifecycle_rule),
@@ -113,85 +248,189 @@ pub fn create_router(state: AppState) -> Router {
             "/buckets/{bucket}/lifecycle/process",
             post(process_bucket_lifecycle),
         )
+        .route(
+            "/buckets/{bucket}/lifecycle/simulate",
+            post(simulate_bucket_lifecycle),
+        )
+        .route("/buckets/{bucket}/lifecycle/run", post(run_bucket_lifecycle))
+        .route(
+            "/buckets/{bucket}/lifecycle/cancel",
+            post(cancel_bucket_lifecycle),
+        )
+        .route(
+            "/buckets/{bucket}/lifecycle/status",
+            get(get_bucket_lifecycle_status),
+        )
         .route("/lifecycle/evaluate", post(evaluate_object_lifecycle))
+        .route("/lifecycle/templates", get(list_lifecycle_templates))
+        // Bundle downloads
+        .route("/buckets/{bucket}/archive", post(create_bucket_archive))
+        // Bucket exports to external S3-compatible storage
+        .route("/admin/buckets/{bucket}/export", post(export_bucket))
         // Add state for dependency injection
-        .with_state(state)
+        .with_state(state);
+
+    // Keys are encoded into a single segment before the request is routed
+    Router::new().fallback_service(map_request(nest_keys).layer(routes))
+}
+
+/// Create the admin router for master key rotation of an encrypted store
+pub fn create_key_rotation_router(rotation: Arc<dyn KeyRotationControl>) -> Router {
+    Router::new()
+        .route("/admin/encryption/rotation", post(start_key_rotation))
+        .route("/admin/encryption/rotation", get(get_key_rotation_status))
+        .with_state(rotation)
+}
+
+/// Create the admin router for a blue/green migration between backends
+pub fn create_migration_router(migration: Arc<dyn MigrationControl>) -> Router {
+    Router::new()
+        .route("/admin/migration", post(start_migration))
+        .route("/admin/migration", get(get_migration_status))
+        .route("/admin/migration/cutover", post(cut_over_migration))
+        .with_state(migration)
+}
+
+/// Create the admin router for recurring bucket backups
+pub fn create_backup_router(backup_service: Arc<dyn BackupService>) -> Router {
+    Router::new()
+        .route("/admin/backups", post(create_backup_job))
+        .route("/admin/backups", get(list_backup_jobs))
+        .route("/admin/backups/{job_id}", delete(delete_backup_job))
+        .route("/admin/backups/{job_id}/runs", post(run_backup_job))
+        .route("/admin/backups/{job_id}/runs", get(list_backup_runs))
+        .route(
+            "/admin/backups/{job_id}/runs/{run_id}/restore",
+            post(restore_backup_run),
+        )
+        .with_state(backup_service)
+}
+
+/// Create the router answering GetBucketLocation for the server's region
+pub fn create_bucket_location_router(region: ServerRegion) -> Router {
+    Router::new()
+        .route("/buckets/{bucket}/location", get(get_bucket_location))
+        .with_state(region)
 }
 
 /// Create a router with just object operations
+///
+/// Keys with slashes are sent percent-encoded, unless requests pass
+/// through [`nest_keys`] first.
 pub fn create_object_router() -> Router<AppState> {
     Router::new()
         .route("/", get(list_objects))
-        .route("/:key", put(create_object))
-        .route("/:key", get(get_object))
-        .route("/:key", delete(delete_object))
-        .route("/:key", head(head_object))
-        .route("/:source_key/copy/:dest_key", post(copy_object))
+        .route("/search", get(search_objects))
+        .route("/{key}", put(create_object))
+        .route("/{key}", get(get_object))
+        .route("/{key}", delete(delete_object))
+        .route("/{key}", head(head_object))
+        .route("/{key}/delete-at", put(set_object_delete_at))
+        .route("/{source_key}/copy/{dest_key}", post(copy_object))
 }
 
 /// Create a router with just lifecycle operations
 pub fn create_lifecycle_router() -> Router<AppState> {
     Router::new()
         .route(
-            "/buckets/:bucket/lifecycle",
+            "/buckets/{bucket}/lifecycle",
             put(set_lifecycle_configuration),
         )
         .route(
-            "/buckets/:bucket/lifecycle",
+            "/buckets/{bucket}/lifecycle",
             get(get_lifecycle_configuration),
         )
         .route(
-            "/buckets/:bucket/lifecycle",
+            "/buckets/{bucket}/lifecycle",
             delete(delete_lifecycle_configuration),
         )
-        .route("/buckets/:bucket/lifecycle/rules", post(add_lifecycle_rule))
+        .route("/buckets/{bucket}/lifecycle/rules", post(add_lifecycle_rule))
+        .route(
+            "/buckets/{bucket}/lifecycle/copy",
+            post(copy_lifecycle_configuration),
+        )
+        .route(
+            "/buckets/{bucket}/lifecycle/validate",
+            post(validate_lifecycle_configuration),
+        )
+        .route(
+            "/buckets/{bucket}/lifecycle/templates/{template}",
+            post(apply_lifecycle_template),
+        )
         .route(
-            "/buckets/:bucket/lifecycle/rules/:rule_id",
+            "/buckets/{bucket}/lifecycle/rules/{rule_id}",
             delete(remove_lifecycle_rule),
         )
         .route(
-            "/buckets/:bucket/lifecycle/rules/:rule_id/enable",
+            "/buckets/{bucket}/lifecycle/rules/{rule_id}/enable",
             post(enable_lifecycle_rule),
         )
         .route(
-            "/buckets/:bucket/lifecycle/rules/:rule_id/disable",
+            "/buckets/{bucket}/lifecycle/rules/{rule_id}/disable",
             post(disable_lifecycle_rule),
         )
         .route(
-            "/buckets/:bucket/lifecycle/process",
+            "/buckets/{bucket}/lifecycle/process",
             post(process_bucket_lifecycle),
         )
+        .route(
+            "/buckets/{bucket}/lifecycle/simulate",
+            post(simulate_bucket_lifecycle),
+        )
+        .route("/buckets/{bucket}/lifecycle/run", post(run_bucket_lifecycle))
+        .route(
+            "/buckets/{bucket}/lifecycle/cancel",
+            post(cancel_bucket_lifecycle),
+        )
+        .route(
+            "/buckets/{bucket}/lifecycle/status",
+            get(get_bucket_lifecycle_status),
+        )
         .route("/lifecycle/evaluate", post(evaluate_object_lifecycle))
+        .route("/lifecycle/templates", get(list_lifecycle_templates))
 }
 
 /// Create a router with just versioning operations
+///
+/// Keys with slashes are sent percent-encoded, unless requests pass
+/// through [`nest_keys`] first.
 pub fn create_versioning_router() -> Router<AppState> {
     Router::new()
-        .route("/:key", put(put_versioned_object))
-        .route("/:key/latest", get(get_latest_object))
-        .route("/:key/versions", get(list_object_versions))
-        .route("/:key/versions/:version_id", get(get_versioned_object))
+        .route("/{key}", put(put_versioned_object))
+        .route("/{key}/latest", get(get_latest_object))
+        .route("/{key}/versions", get(list_object_versions))
+        .route("/{key}/versions/delete", post(delete_object_versions))
+        .route("/{key}/versions/{version_id}", get(get_versioned_object))
         .route(
-            "/:key/versions/:version_id",
+            "/{key}/versions/{version_id}",
             delete(delete_versioned_object),
         )
-        .route("/:key/versions/:version_id", head(head_versioned_object))
+        .route("/{key}/versions/{version_id}", head(head_versioned_object))
         .route(
-            "/:source_key/versions/:source_version_id/copy/:dest_key",
+            "/{source_key}/versions/{source_version_id}/copy/{dest_key}",
             post(copy_versioned_object),
         )
-        .route("/:key/versions/:version_id/restore", post(restore_version))
+        .route("/{key}/versions/{version_id}/restore", post(restore_version))
+        .route(
+            "/{key}/versions/{version_id}/pin",
+            put(pin_version).delete(unpin_version),
+        )
+        .route("/{key}/versions/{version_id}/labels", put(label_version))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
-        VersionedApacheObjectStoreAdapter,
         adapters::outbound::{
-            persistence::{InMemoryLifecycleRepository, InMemoryObjectRepository},
-            storage::ApacheObjectStoreAdapter,
+            persistence::{
+                InMemoryLifecycleRepository, InMemoryMultipartUploadRepository,
+                InMemoryObjectRepository,
+            },
+            storage::{S3ObjectStoreAdapter, VersionedS3ObjectStoreAdapter},
         },
+        domain::value_objects::BucketName,
+        services::{LifecycleServiceImpl, ObjectServiceImpl, VersioningServiceImpl},
     };
     use axum_test::TestServer;
     use object_store::memory::InMemory;
@@ -199,15 +438,19 @@ mod tests {
 
     async fn create_test_app_state() -> AppState {
         let memory_store = Arc::new(InMemory::new());
-        let object_store = Arc::new(ApacheObjectStoreAdapter::new(memory_store.clone()));
-        let versioned_store = Arc::new(VersionedApacheObjectStoreAdapter::new(memory_store));
+        let bucket = BucketName::new("test-bucket".to_string()).unwrap();
+        let object_store = Arc::new(S3ObjectStoreAdapter::new(memory_store.clone(), bucket));
+        let versioned_store = Arc::new(VersionedS3ObjectStoreAdapter::new(
+            object_store.clone(),
+            memory_store,
+        ));
         let object_repo = Arc::new(InMemoryObjectRepository::new());
         let lifecycle_repo = Arc::new(InMemoryLifecycleRepository::new());
 
-        let object_service = Arc::new(ObjectServiceImpl::new(
-            object_repo.clone(),
-            object_store.clone(),
-        ));
+        let object_service = Arc::new(
+            ObjectServiceImpl::new(object_repo.clone(), object_store.clone())
+                .with_multipart_uploads(Arc::new(InMemoryMultipartUploadRepository::new())),
+        );
 
         let lifecycle_service = Arc::new(LifecycleServiceImpl::new(
             lifecycle_repo,
@@ -216,7 +459,7 @@ mod tests {
             versioned_store.clone(),
         ));
 
-        let versioning_service = Arc::new(crate::services::VersioningServiceImpl::new(
+        let versioning_service = Arc::new(VersioningServiceImpl::new(
             object_repo.clone(),
             versioned_store,
         ));
@@ -231,10 +474,9 @@ mod tests {
     #[tokio::test]
     async fn test_router_creation() {
         let state = create_test_app_state().await;
-        let app = create_router(state);
 
         // This test just ensures the router can be created without panicking
-        assert!(true);
+        let _app = create_router(state);
     }
 
     #[tokio::test]
@@ -244,6 +486,204 @@ mod tests {
 
         // Test that we can create a test server with the router
         let _server = TestServer::new(object_router).unwrap();
-        assert!(true);
+    }
+    #[tokio::test]
+    async fn test_nested_keys_route_with_their_slashes() {
+        let state = create_test_app_state().await;
+        let server = TestServer::new(create_router(state)).unwrap();
+
+        let put = server.put("/objects/a/b/c.txt").text("nested").await;
+        assert_eq!(put.status_code(), 201);
+
+        let get = server.get("/objects/a/b/c.txt").await;
+        assert_eq!(get.status_code(), 200);
+        assert_eq!(get.text(), "nested");
+        let head = server.method(axum::http::Method::HEAD, "/objects/a/b/c.txt").await;
+        assert_eq!(head.status_code(), 200);
+
+        let copy = server.post("/objects/a/b/c.txt/copy/d/e.txt").await;
+        assert!(copy.status_code().is_success());
+        assert_eq!(server.get("/objects/d/e.txt").await.text(), "nested");
+
+        let delete = server.delete("/objects/a/b/c.txt").await;
+        assert!(delete.status_code().is_success());
+        assert_eq!(server.get("/objects/a/b/c.txt").await.status_code(), 404);
+
+        server.put("/versioned-objects/a/b/v.txt").text("one").await;
+        let versions = server.get("/versioned-objects/a/b/v.txt/versions").await;
+        assert_eq!(versions.status_code(), 200);
+        assert_eq!(versions.json::<serde_json::Value>()["total_count"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_bucket_versioning_in_json_and_xml() {
+        let state = create_test_app_state().await;
+        let server = TestServer::new(create_router(state)).unwrap();
+
+        let suspend = server
+            .put("/buckets/test-bucket/versioning")
+            .json(&serde_json::json!({ "status": "Suspended" }))
+            .await;
+        assert_eq!(suspend.status_code(), 200);
+        let status: serde_json::Value = server.get("/buckets/test-bucket/versioning").await.json();
+        assert_eq!(status["status"], "Suspended");
+
+        let enable = server
+            .put("/buckets/test-bucket/versioning")
+            .text(
+                "<VersioningConfiguration xmlns=\"http://s3.amazonaws.com/doc/2006-03-01/\">\
+                 <Status>Enabled</Status></VersioningConfiguration>",
+            )
+            .content_type("application/xml")
+            .await;
+        assert_eq!(enable.status_code(), 200);
+        let status = server
+            .get("/buckets/test-bucket/versioning")
+            .add_header("accept", "application/xml")
+            .await;
+        assert!(status.text().contains("<Status>Enabled</Status>"));
+
+        let invalid = server
+            .put("/buckets/test-bucket/versioning")
+            .json(&serde_json::json!({ "status": "Paused" }))
+            .await;
+        assert_eq!(invalid.status_code(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_bucket_versioning_subresource() {
+        let state = create_test_app_state().await;
+        let server = TestServer::new(create_router(state)).unwrap();
+
+        // As sent by `aws s3api put-bucket-versioning`, without a content type
+        let suspend = server
+            .put("/buckets/test-bucket")
+            .add_query_param("versioning", "")
+            .bytes(
+                "<VersioningConfiguration xmlns=\"http://s3.amazonaws.com/doc/2006-03-01/\">\
+                 <Status>Suspended</Status></VersioningConfiguration>"
+                    .into(),
+            )
+            .await;
+        assert_eq!(suspend.status_code(), 200);
+
+        let status = server
+            .get("/buckets/test-bucket")
+            .add_query_param("versioning", "")
+            .await;
+        assert_eq!(status.header("content-type"), "application/xml");
+        assert!(status.text().contains("<Status>Suspended</Status>"));
+
+        let mfa = server
+            .put("/buckets/test-bucket")
+            .add_query_param("versioning", "")
+            .bytes(
+                "<VersioningConfiguration><Status>Enabled</Status>\
+                 <MfaDelete>Enabled</MfaDelete></VersioningConfiguration>"
+                    .into(),
+            )
+            .await;
+        assert_eq!(mfa.status_code(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_list_objects_v1_with_delimiter_and_marker() {
+        use crate::domain::{models::CreateObjectRequest, value_objects::ObjectKey};
+
+        let state = create_test_app_state().await;
+        for key in [
+            "test-bucket/a.txt",
+            "test-bucket/photos/1.jpg",
+            "test-bucket/photos/2.jpg",
+            "test-bucket/z b.txt",
+            "other-bucket/c.txt",
+        ] {
+            state
+                .object_service
+                .create_object(CreateObjectRequest {
+                    key: ObjectKey::new(key.to_string()).unwrap(),
+                    data: b"data".to_vec(),
+                    content_type: None,
+                    custom_metadata: Default::default(),
+                })
+                .await
+                .unwrap();
+        }
+        let server = TestServer::new(create_router(state)).unwrap();
+
+        let first = server
+            .get("/buckets/test-bucket")
+            .add_query_param("delimiter", "/")
+            .add_query_param("max-keys", "2")
+            .await;
+        assert_eq!(first.status_code(), 200);
+        let first = first.text();
+        assert!(first.contains("<Key>a.txt</Key>"));
+        assert!(first.contains("<CommonPrefixes><Prefix>photos/</Prefix></CommonPrefixes>"));
+        assert!(first.contains("<IsTruncated>true</IsTruncated>"));
+        assert!(first.contains("<NextMarker>photos/</NextMarker>"));
+        assert!(!first.contains("other-bucket") && !first.contains("c.txt"));
+
+        let second = server
+            .get("/buckets/test-bucket")
+            .add_query_param("delimiter", "/")
+            .add_query_param("marker", "photos/")
+            .add_query_param("encoding-type", "url")
+            .await
+            .text();
+        assert!(second.contains("<Key>z%20b.txt</Key>"));
+        assert!(!second.contains("<CommonPrefixes>"));
+        assert!(second.contains("<IsTruncated>false</IsTruncated>"));
+
+        let uploads = server
+            .get("/buckets/test-bucket")
+            .add_query_param("uploads", "")
+            .await;
+        assert_eq!(uploads.status_code(), 200);
+        assert_eq!(uploads.json::<serde_json::Value>()["uploads"], serde_json::json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_exports_require_their_own_credentials() {
+        let state = create_test_app_state().await;
+        let server = TestServer::new(create_router(state)).unwrap();
+
+        let export = server
+            .post("/admin/buckets/test-bucket/export")
+            .json(&serde_json::json!({
+                "target": { "bucket": "elsewhere", "endpoint": "http://example.com" }
+            }))
+            .await;
+        assert_eq!(export.status_code(), 422);
+    }
+
+    #[tokio::test]
+    async fn test_head_bucket() {
+        let state = create_test_app_state().await;
+        let server = TestServer::new(create_router(state)).unwrap();
+
+        let missing = server.method(axum::http::Method::HEAD, "/buckets/test-bucket").await;
+        assert_eq!(missing.status_code(), 404);
+
+        server
+            .put("/objects/test-bucket%2Fa.txt")
+            .text("data")
+            .await;
+        let existing = server.method(axum::http::Method::HEAD, "/buckets/test-bucket").await;
+        assert_eq!(existing.status_code(), 200);
+
+        let invalid = server.method(axum::http::Method::HEAD, "/buckets/NO").await;
+        assert_eq!(invalid.status_code(), 400);
+
+        // A bucket with only a versioning configuration exists too
+        let versioned = server.method(axum::http::Method::HEAD, "/buckets/versioned").await;
+        assert_eq!(versioned.status_code(), 404);
+        server
+            .put("/buckets/versioned")
+            .add_query_param("versioning", "")
+            .text("<VersioningConfiguration><Status>Enabled</Status></VersioningConfiguration>")
+            .await;
+        let versioned = server.method(axum::http::Method::HEAD, "/buckets/versioned").await;
+        assert_eq!(versioned.status_code(), 200);
     }
 }
@@ -1 +1,3 @@
+#[cfg(not(target_arch = "wasm32"))]
+pub mod grpc;
 pub mod http;
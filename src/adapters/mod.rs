@@ -1,2 +1,3 @@
 pub mod inbound;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod outbound;
@@ -0,0 +1,161 @@
+use async_trait::async_trait;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use super::{ScanVerdict, VirusScanner};
+use crate::domain::errors::{StorageError, StorageResult};
+
+/// Default size of each chunk streamed to clamd
+const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Default time allowed for a whole scan
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Scanner streaming uploads to a clamd daemon with the INSTREAM command
+#[derive(Debug, Clone)]
+pub struct ClamAvScanner {
+    address: String,
+    chunk_size: usize,
+    timeout: Duration,
+}
+
+impl ClamAvScanner {
+    /// Create a scanner for the clamd daemon listening at `address` (host:port)
+    pub fn new(address: impl Into<String>) -> Self {
+        Self {
+            address: address.into(),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    async fn instream(&self, data: &mut (dyn AsyncRead + Send + Unpin)) -> std::io::Result<String> {
+        let mut connection = TcpStream::connect(&self.address).await?;
+        connection.write_all(b"zINSTREAM\0").await?;
+
+        let mut chunk = vec![0u8; self.chunk_size];
+        let sent = async {
+            loop {
+                let n = data.read(&mut chunk).await?;
+                connection.write_all(&(n as u32).to_be_bytes()).await?;
+                if n == 0 {
+                    return Ok::<_, std::io::Error>(());
+                }
+                connection.write_all(&chunk[..n]).await?;
+            }
+        }
+        .await;
+
+        // clamd may stop reading and reply early, e.g. when its stream limit is hit
+        let mut response = Vec::new();
+        let read = connection.read_to_end(&mut response).await;
+        if response.is_empty() {
+            sent?;
+            read?;
+        }
+        Ok(String::from_utf8_lossy(&response).into_owned())
+    }
+}
+
+#[async_trait]
+impl VirusScanner for ClamAvScanner {
+    async fn scan(&self, data: &mut (dyn AsyncRead + Send + Unpin)) -> StorageResult<ScanVerdict> {
+        let response = tokio::time::timeout(self.timeout, self.instream(data))
+            .await
+            .map_err(|_| StorageError::InfrastructureError {
+                message: format!("clamd at {} timed out", self.address),
+                source: None,
+            })?
+            .map_err(|e| StorageError::InfrastructureError {
+                message: format!("Failed to scan with clamd at {}", self.address),
+                source: Some(e.to_string()),
+            })?;
+        parse_clamd_response(&response)
+    }
+}
+
+/// Parse a clamd reply such as `stream: OK` or `stream: Eicar-Signature FOUND`
+fn parse_clamd_response(response: &str) -> StorageResult<ScanVerdict> {
+    let reply = response.trim_end_matches(['\0', '\n', ' ']);
+    let result = reply.strip_prefix("stream:").unwrap_or(reply).trim();
+
+    if result == "OK" {
+        Ok(ScanVerdict::Clean)
+    } else if let Some(signature) = result.strip_suffix(" FOUND") {
+        Ok(ScanVerdict::Infected {
+            signature: signature.trim().to_string(),
+        })
+    } else {
+        Err(StorageError::InfrastructureError {
+            message: format!("clamd could not scan the upload: {}", reply),
+            source: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_parse_clamd_response() {
+        assert_eq!(
+            parse_clamd_response("stream: OK\0").unwrap(),
+            ScanVerdict::Clean
+        );
+        assert_eq!(
+            parse_clamd_response("stream: Win.Test.EICAR_HDB-1 FOUND\0").unwrap(),
+            ScanVerdict::Infected {
+                signature: "Win.Test.EICAR_HDB-1".to_string()
+            }
+        );
+        assert!(parse_clamd_response("INSTREAM size limit exceeded. ERROR\0").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_instream_protocol() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        // Minimal clamd accepting one INSTREAM session
+        let daemon = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut command = [0u8; 10];
+            socket.read_exact(&mut command).await.unwrap();
+            assert_eq!(&command, b"zINSTREAM\0");
+
+            let mut received = Vec::new();
+            loop {
+                let len = socket.read_u32().await.unwrap() as usize;
+                if len == 0 {
+                    break;
+                }
+                let mut chunk = vec![0u8; len];
+                socket.read_exact(&mut chunk).await.unwrap();
+                received.extend_from_slice(&chunk);
+            }
+            socket.write_all(b"stream: Test.Sig FOUND\0").await.unwrap();
+            received
+        });
+
+        let scanner = ClamAvScanner {
+            chunk_size: 3,
+            ..ClamAvScanner::new(address)
+        };
+        let verdict = scanner.scan(&mut &b"some payload"[..]).await.unwrap();
+        assert_eq!(
+            verdict,
+            ScanVerdict::Infected {
+                signature: "Test.Sig".to_string()
+            }
+        );
+        assert_eq!(daemon.await.unwrap(), b"some payload");
+    }
+}
@@ -0,0 +1,175 @@
+use async_trait::async_trait;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use super::{ScanVerdict, VirusScanner};
+use crate::domain::errors::{StorageError, StorageResult};
+
+/// Default size of each chunk streamed to the ICAP server
+const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Default time allowed for a whole scan
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Largest ICAP response header accepted
+const MAX_RESPONSE_HEADER: usize = 64 * 1024;
+
+/// Encapsulated HTTP response the upload is sent as
+const ENCAPSULATED_RESPONSE: &str =
+    "HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\n\r\n";
+
+/// Headers ICAP antivirus servers use to report what they found
+const INFECTION_HEADERS: [&str; 3] = ["x-infection-found", "x-virus-id", "x-violations-found"];
+
+/// Scanner sending uploads to an ICAP antivirus service as RESPMOD requests
+#[derive(Debug, Clone)]
+pub struct IcapScanner {
+    address: String,
+    service: String,
+    chunk_size: usize,
+    timeout: Duration,
+}
+
+impl IcapScanner {
+    /// Create a scanner for the service at `icap://<address>/<service>`
+    pub fn new(address: impl Into<String>, service: impl Into<String>) -> Self {
+        Self {
+            address: address.into(),
+            service: service.into(),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    async fn respmod(&self, data: &mut (dyn AsyncRead + Send + Unpin)) -> std::io::Result<String> {
+        let mut connection = TcpStream::connect(&self.address).await?;
+        let host = self.address.split(':').next().unwrap_or(&self.address);
+        let request = format!(
+            "RESPMOD icap://{}/{} ICAP/1.0\r\nHost: {}\r\nAllow: 204\r\nEncapsulated: res-hdr=0, res-body={}\r\n\r\n{}",
+            self.address,
+            self.service.trim_start_matches('/'),
+            host,
+            ENCAPSULATED_RESPONSE.len(),
+            ENCAPSULATED_RESPONSE
+        );
+        connection.write_all(request.as_bytes()).await?;
+
+        // The body is sent with HTTP chunked encoding
+        let mut chunk = vec![0u8; self.chunk_size];
+        loop {
+            let n = data.read(&mut chunk).await?;
+            if n == 0 {
+                break;
+            }
+            connection
+                .write_all(format!("{:x}\r\n", n).as_bytes())
+                .await?;
+            connection.write_all(&chunk[..n]).await?;
+            connection.write_all(b"\r\n").await?;
+        }
+        connection.write_all(b"0\r\n\r\n").await?;
+
+        let mut response = Vec::new();
+        let mut buffer = [0u8; 4096];
+        while !response.windows(4).any(|w| w == b"\r\n\r\n") {
+            let n = connection.read(&mut buffer).await?;
+            if n == 0 {
+                break;
+            }
+            response.extend_from_slice(&buffer[..n]);
+            if response.len() > MAX_RESPONSE_HEADER {
+                return Err(std::io::Error::other("ICAP response header too large"));
+            }
+        }
+        Ok(String::from_utf8_lossy(&response).into_owned())
+    }
+}
+
+#[async_trait]
+impl VirusScanner for IcapScanner {
+    async fn scan(&self, data: &mut (dyn AsyncRead + Send + Unpin)) -> StorageResult<ScanVerdict> {
+        let response = tokio::time::timeout(self.timeout, self.respmod(data))
+            .await
+            .map_err(|_| StorageError::InfrastructureError {
+                message: format!("ICAP server at {} timed out", self.address),
+                source: None,
+            })?
+            .map_err(|e| StorageError::InfrastructureError {
+                message: format!("Failed to scan with ICAP server at {}", self.address),
+                source: Some(e.to_string()),
+            })?;
+        parse_icap_response(&response)
+    }
+}
+
+/// Interpret the status line and headers of an ICAP response
+fn parse_icap_response(response: &str) -> StorageResult<ScanVerdict> {
+    let mut lines = response.split("\r\n");
+    let status = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse::<u16>().ok());
+
+    let infection = lines
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| INFECTION_HEADERS.contains(&name.trim().to_ascii_lowercase().as_str()))
+        .map(|(_, value)| infection_signature(value.trim()));
+
+    match (status, infection) {
+        (Some(200), Some(signature)) => Ok(ScanVerdict::Infected { signature }),
+        (Some(200 | 204), None) => Ok(ScanVerdict::Clean),
+        _ => Err(StorageError::InfrastructureError {
+            message: format!(
+                "Unexpected ICAP response: {}",
+                response.lines().next().unwrap_or_default()
+            ),
+            source: None,
+        }),
+    }
+}
+
+/// Extract the threat name from values like `Type=0; Resolution=2; Threat=Eicar;`
+fn infection_signature(value: &str) -> String {
+    value
+        .split(';')
+        .filter_map(|part| part.trim().split_once('='))
+        .find(|(name, _)| name.eq_ignore_ascii_case("threat"))
+        .map(|(_, threat)| threat.trim().to_string())
+        .unwrap_or_else(|| value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_icap_response() {
+        assert_eq!(
+            parse_icap_response("ICAP/1.0 204 No Content\r\nISTag: \"x\"\r\n\r\n").unwrap(),
+            ScanVerdict::Clean
+        );
+        assert_eq!(
+            parse_icap_response(
+                "ICAP/1.0 200 OK\r\nX-Infection-Found: Type=0; Resolution=2; Threat=Eicar-Test;\r\n\r\n"
+            )
+            .unwrap(),
+            ScanVerdict::Infected {
+                signature: "Eicar-Test".to_string()
+            }
+        );
+        assert_eq!(
+            parse_icap_response("ICAP/1.0 200 OK\r\nX-Virus-ID: Trojan.Generic\r\n\r\n").unwrap(),
+            ScanVerdict::Infected {
+                signature: "Trojan.Generic".to_string()
+            }
+        );
+        assert!(parse_icap_response("ICAP/1.0 500 Server Error\r\n\r\n").is_err());
+    }
+}
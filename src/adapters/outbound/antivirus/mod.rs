@@ -0,0 +1,365 @@
+//! Antivirus scanning of uploads before they are stored
+//!
+//! [`AntivirusInterceptor`] is an [`ObjectInterceptor`] that streams every upload
+//! to a virus scanner and rejects infected content. Scanners speak to a clamd
+//! daemon ([`ClamAvScanner`]) or an ICAP server ([`IcapScanner`]).
+
+mod clamav;
+mod icap;
+
+pub use clamav::ClamAvScanner;
+pub use icap::IcapScanner;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, ReadBuf};
+
+use crate::{
+    domain::{
+        errors::{StorageError, StorageResult},
+        models::{AUDIT_ACTION_MALWARE_REJECTED, AuditEvent},
+        value_objects::ObjectKey,
+    },
+    ports::{
+        interceptors::{ObjectInterceptor, PutContext},
+        repositories::AuditLogRepository,
+        storage::ObjectStore,
+    },
+};
+
+/// Default largest upload that will be scanned (25 MiB, clamd's default stream limit)
+pub const DEFAULT_MAX_SCAN_SIZE: u64 = 25 * 1024 * 1024;
+
+/// Outcome of scanning an upload
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanVerdict {
+    Clean,
+    Infected { signature: String },
+}
+
+/// A virus scanning engine
+#[async_trait]
+pub trait VirusScanner: Send + Sync + 'static {
+    /// Scan everything read from `data`, which is read to the end unless scanning fails
+    async fn scan(&self, data: &mut (dyn AsyncRead + Send + Unpin)) -> StorageResult<ScanVerdict>;
+}
+
+/// Where infected uploads are kept for later inspection
+#[derive(Clone)]
+pub struct Quarantine {
+    pub store: Arc<dyn ObjectStore>,
+    /// Bucket the infected objects are stored under, as `bucket/original-key`
+    pub bucket: String,
+}
+
+/// Interceptor rejecting uploads that a virus scanner flags as infected
+///
+/// Uploads are held in memory while they are scanned, so only uploads up to
+/// `max_scan_size` are accepted. Infected uploads are optionally copied to a
+/// quarantine bucket, and rejections are recorded in the audit log when one is
+/// configured.
+///
+/// An upload that can't be scanned, because the scanner is unreachable or
+/// fails, is rejected unless [`with_fail_open`](Self::with_fail_open) is set.
+pub struct AntivirusInterceptor {
+    scanner: Arc<dyn VirusScanner>,
+    max_scan_size: u64,
+    fail_open: bool,
+    quarantine: Option<Quarantine>,
+    audit_log: Option<Arc<dyn AuditLogRepository>>,
+}
+
+impl AntivirusInterceptor {
+    pub fn new(scanner: Arc<dyn VirusScanner>) -> Self {
+        Self {
+            scanner,
+            max_scan_size: DEFAULT_MAX_SCAN_SIZE,
+            fail_open: false,
+            quarantine: None,
+            audit_log: None,
+        }
+    }
+
+    /// Reject uploads larger than `max_scan_size` bytes instead of scanning them
+    pub fn with_max_scan_size(mut self, max_scan_size: u64) -> Self {
+        self.max_scan_size = max_scan_size;
+        self
+    }
+
+    /// Accept uploads when the scanner cannot be reached or fails, rather than
+    /// rejecting them
+    ///
+    /// Off by default. Turning it on stores unscanned content whenever the
+    /// scanner is down, so only do so where availability matters more than
+    /// keeping malware out; every upload accepted this way is logged as an
+    /// error.
+    pub fn with_fail_open(mut self, fail_open: bool) -> Self {
+        self.fail_open = fail_open;
+        self
+    }
+
+    /// Keep infected uploads in a quarantine bucket
+    pub fn with_quarantine(mut self, quarantine: Quarantine) -> Self {
+        self.quarantine = Some(quarantine);
+        self
+    }
+
+    /// Record rejected uploads in an audit log
+    pub fn with_audit_log(mut self, audit_log: Arc<dyn AuditLogRepository>) -> Self {
+        self.audit_log = Some(audit_log);
+        self
+    }
+
+    /// Quarantine and audit an infected upload
+    async fn handle_infected(
+        &self,
+        key: &ObjectKey,
+        data: Bytes,
+        content_type: Option<&str>,
+        signature: &str,
+    ) -> StorageResult<()> {
+        let mut event = AuditEvent::new(AUDIT_ACTION_MALWARE_REJECTED, key.clone())
+            .with_detail("signature", signature)
+            .with_detail("size", data.len().to_string());
+
+        if let Some(quarantine) = &self.quarantine {
+            let quarantine_key =
+                ObjectKey::new(format!("{}/{}", quarantine.bucket, key)).map_err(|e| {
+                    StorageError::InternalError {
+                        message: format!("Invalid quarantine key for {}: {}", key, e),
+                    }
+                })?;
+            quarantine
+                .store
+                .put_object(&quarantine_key, data, content_type)
+                .await?;
+            event = event.with_detail("quarantine_key", quarantine_key.as_str());
+        }
+
+        if let Some(audit_log) = &self.audit_log {
+            audit_log.record_event(event).await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ObjectInterceptor for AntivirusInterceptor {
+    async fn pre_put(&self, key: &ObjectKey, mut put: PutContext) -> StorageResult<PutContext> {
+        let mut tee = TeeReader {
+            inner: put.body,
+            buffer: Vec::new(),
+            limit: self.max_scan_size,
+        };
+
+        let verdict = self.scanner.scan(&mut tee).await;
+
+        // Drain anything the scanner left unread so the stored object is complete
+        if verdict.is_ok() || self.fail_open {
+            tokio::io::copy(&mut tee, &mut tokio::io::sink())
+                .await
+                .map_err(|e| StorageError::ValidationError {
+                    message: format!("Failed to read upload of {}: {}", key, e),
+                })?;
+        }
+        let data = Bytes::from(tee.buffer);
+
+        match verdict {
+            Ok(ScanVerdict::Clean) => {}
+            Ok(ScanVerdict::Infected { signature }) => {
+                self.handle_infected(key, data, put.content_type.as_deref(), &signature)
+                    .await?;
+                return Err(StorageError::ValidationError {
+                    message: format!(
+                        "Upload of {} rejected: malware detected ({})",
+                        key, signature
+                    ),
+                });
+            }
+            Err(e) if self.fail_open => {
+                tracing::error!(
+                    "Virus scan of {} failed, accepting it unscanned: {}",
+                    key,
+                    e
+                );
+            }
+            Err(e) => {
+                tracing::error!("Virus scan of {} failed, rejecting it: {}", key, e);
+                return Err(e);
+            }
+        }
+
+        put.body = Box::new(std::io::Cursor::new(data));
+        Ok(put)
+    }
+}
+
+/// Reader keeping a copy of everything read through it, up to a size limit
+struct TeeReader {
+    inner: Box<dyn AsyncRead + Send + Unpin>,
+    buffer: Vec<u8>,
+    limit: u64,
+}
+
+impl AsyncRead for TeeReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = &mut *self;
+        let exceeded = || {
+            std::io::Error::other(format!(
+                "Upload exceeds the {} byte limit for virus scanning",
+                this.limit
+            ))
+        };
+        // Reads keep failing once over the limit, so the rest of the upload
+        // can't be drained into the store unscanned
+        if this.buffer.len() as u64 > this.limit {
+            return Poll::Ready(Err(exceeded()));
+        }
+        let before = buf.filled().len();
+        let result = Pin::new(&mut this.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = &result {
+            this.buffer.extend_from_slice(&buf.filled()[before..]);
+            if this.buffer.len() as u64 > this.limit {
+                // A read that fails must not hand out any bytes
+                buf.set_filled(before);
+                return Poll::Ready(Err(exceeded()));
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::outbound::persistence::InMemoryAuditLogRepository;
+    use crate::adapters::outbound::storage::S3ObjectStoreAdapter;
+    use crate::domain::value_objects::BucketName;
+    use object_store::memory::InMemory;
+    use std::collections::HashMap;
+    use tokio::io::AsyncReadExt;
+
+    /// Flags any upload containing the word "virus"
+    struct KeywordScanner;
+
+    #[async_trait]
+    impl VirusScanner for KeywordScanner {
+        async fn scan(
+            &self,
+            data: &mut (dyn AsyncRead + Send + Unpin),
+        ) -> StorageResult<ScanVerdict> {
+            let mut contents = Vec::new();
+            data.read_to_end(&mut contents).await.map_err(|e| {
+                StorageError::InfrastructureError {
+                    message: e.to_string(),
+                    source: None,
+                }
+            })?;
+            if contents.windows(5).any(|w| w == b"virus") {
+                Ok(ScanVerdict::Infected {
+                    signature: "Test.Keyword".to_string(),
+                })
+            } else {
+                Ok(ScanVerdict::Clean)
+            }
+        }
+    }
+
+    fn upload(data: &'static [u8]) -> PutContext {
+        PutContext {
+            content_type: None,
+            custom_metadata: HashMap::new(),
+            body: Box::new(data),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_infected_upload_is_quarantined_and_audited() {
+        let bucket = BucketName::new("test-bucket".to_string()).unwrap();
+        let store = Arc::new(S3ObjectStoreAdapter::new(Arc::new(InMemory::new()), bucket));
+        let audit_log = Arc::new(InMemoryAuditLogRepository::new());
+        let interceptor = AntivirusInterceptor::new(Arc::new(KeywordScanner))
+            .with_quarantine(Quarantine {
+                store: store.clone(),
+                bucket: "quarantine".to_string(),
+            })
+            .with_audit_log(audit_log.clone());
+        let key = ObjectKey::new("uploads/a.bin".to_string()).unwrap();
+
+        let mut clean = interceptor
+            .pre_put(&key, upload(b"harmless"))
+            .await
+            .unwrap();
+        let mut data = Vec::new();
+        clean.body.read_to_end(&mut data).await.unwrap();
+        assert_eq!(data, b"harmless");
+
+        let rejected = interceptor.pre_put(&key, upload(b"a virus!")).await;
+        assert!(matches!(
+            rejected,
+            Err(StorageError::ValidationError { .. })
+        ));
+
+        let quarantine_key = ObjectKey::new("quarantine/uploads/a.bin".to_string()).unwrap();
+        assert_eq!(
+            &store.get_object(&quarantine_key).await.unwrap()[..],
+            b"a virus!"
+        );
+
+        let events = audit_log.list_events(Some(&key)).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].action, AUDIT_ACTION_MALWARE_REJECTED);
+        assert_eq!(events[0].details["signature"], "Test.Keyword");
+    }
+
+    /// Fails every scan, like a scanner that can't be reached
+    struct UnreachableScanner;
+
+    #[async_trait]
+    impl VirusScanner for UnreachableScanner {
+        async fn scan(
+            &self,
+            _data: &mut (dyn AsyncRead + Send + Unpin),
+        ) -> StorageResult<ScanVerdict> {
+            Err(StorageError::InfrastructureError {
+                message: "connection refused".to_string(),
+                source: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scanner_failure_rejects_unless_failing_open() {
+        let key = ObjectKey::new("uploads/a.bin".to_string()).unwrap();
+
+        let interceptor = AntivirusInterceptor::new(Arc::new(UnreachableScanner));
+        assert!(interceptor.pre_put(&key, upload(b"data")).await.is_err());
+
+        let interceptor = interceptor.with_fail_open(true);
+        let mut accepted = interceptor.pre_put(&key, upload(b"data")).await.unwrap();
+        let mut data = Vec::new();
+        accepted.body.read_to_end(&mut data).await.unwrap();
+        assert_eq!(data, b"data");
+    }
+
+    #[tokio::test]
+    async fn test_oversized_upload_is_rejected() {
+        let interceptor = AntivirusInterceptor::new(Arc::new(KeywordScanner))
+            .with_max_scan_size(4)
+            .with_fail_open(true);
+        let key = ObjectKey::new("uploads/big.bin".to_string()).unwrap();
+        assert!(
+            interceptor
+                .pre_put(&key, upload(b"too large"))
+                .await
+                .is_err()
+        );
+    }
+}
@@ -1,2 +1,4 @@
+pub mod antivirus;
+pub mod notifications;
 pub mod persistence;
 pub mod storage;
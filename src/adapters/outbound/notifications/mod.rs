@@ -0,0 +1,10 @@
+//! Notifications of object events to external systems
+//!
+//! [`WebhookEventSink`] is a [`LifecycleEventSink`] posting the changes made by
+//! lifecycle processing to an HTTP endpoint.
+//!
+//! [`LifecycleEventSink`]: crate::ports::events::LifecycleEventSink
+
+mod webhook;
+
+pub use webhook::WebhookEventSink;
@@ -0,0 +1,208 @@
+use async_trait::async_trait;
+use reqwest::{Client, Url};
+use serde_json::{Value, json};
+use std::time::Duration;
+
+use crate::{
+    domain::{
+        errors::{StorageError, StorageResult},
+        models::{LifecycleEvent, LifecycleEventType, WorkerEvent},
+    },
+    ports::events::{LifecycleEventSink, WorkerEventSink},
+};
+
+/// Default time a webhook may take to accept an event
+pub const DEFAULT_WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Source named in the lifecycle events sent to webhooks
+const EVENT_SOURCE: &str = "object-store-server:lifecycle";
+
+/// Source named in the worker events sent to webhooks
+const WORKER_EVENT_SOURCE: &str = "object-store-server:worker";
+
+/// Sink posting lifecycle and worker events to a webhook
+///
+/// Each event is sent as its own request, with a JSON body in the layout of
+/// S3 event notifications: a `Records` array holding one record whose
+/// `eventName` is the event type.
+#[derive(Debug, Clone)]
+pub struct WebhookEventSink {
+    client: Client,
+    url: Url,
+}
+
+impl WebhookEventSink {
+    pub fn new(url: Url) -> Self {
+        Self {
+            client: Self::client(DEFAULT_WEBHOOK_TIMEOUT),
+            url,
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = Self::client(timeout);
+        self
+    }
+
+    fn client(timeout: Duration) -> Client {
+        Client::builder()
+            .timeout(timeout)
+            .build()
+            .unwrap_or_default()
+    }
+
+    /// Body of the request announcing `event`
+    fn body(event: &LifecycleEvent) -> Value {
+        let (bucket, key) = event
+            .key
+            .as_str()
+            .split_once('/')
+            .unwrap_or(("", event.key.as_str()));
+
+        let mut object = json!({ "key": key });
+        if let Some(version_id) = &event.version_id {
+            object["versionId"] = json!(version_id.as_str());
+        }
+        if let Some(upload_id) = &event.upload_id {
+            object["uploadId"] = json!(upload_id);
+        }
+
+        let mut lifecycle = json!({ "ruleId": event.rule_id });
+        if event.event_type == LifecycleEventType::ObjectTransitioned {
+            lifecycle["transitionEventData"] = json!({
+                "destinationStorageClass": event.storage_class.as_ref().map(|class| class.as_str()),
+            });
+        }
+
+        json!({
+            "Records": [{
+                "eventVersion": "2.3",
+                "eventSource": EVENT_SOURCE,
+                "eventTime": event.time.to_rfc3339(),
+                "eventName": event.event_type.as_str(),
+                "s3": {
+                    "bucket": { "name": bucket },
+                    "object": object,
+                },
+                "lifecycleEventData": lifecycle,
+            }]
+        })
+    }
+
+    /// Body of the request announcing worker `event`
+    fn worker_body(event: &WorkerEvent) -> Value {
+        json!({
+            "Records": [{
+                "eventVersion": "2.3",
+                "eventSource": WORKER_EVENT_SOURCE,
+                "eventTime": event.time.to_rfc3339(),
+                "eventName": event.event_type.as_str(),
+                "workerEventData": {
+                    "worker": event.worker,
+                    "reason": event.reason,
+                },
+            }]
+        })
+    }
+
+    /// Post `body`, describing failures as a failure to notify of `subject`
+    async fn post(&self, body: &Value, subject: String) -> StorageResult<()> {
+        let failed = |reason: String| StorageError::InfrastructureError {
+            message: format!("Failed to notify {} of {}: {}", self.url, subject, reason),
+            source: None,
+        };
+
+        let response = self
+            .client
+            .post(self.url.clone())
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| failed(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(failed(format!("webhook answered {}", response.status())));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl LifecycleEventSink for WebhookEventSink {
+    async fn publish(&self, event: &LifecycleEvent) -> StorageResult<()> {
+        let subject = format!("{} for {}", event.event_type.as_str(), event.key);
+        self.post(&Self::body(event), subject).await
+    }
+}
+
+#[async_trait]
+impl WorkerEventSink for WebhookEventSink {
+    async fn publish(&self, event: &WorkerEvent) -> StorageResult<()> {
+        let subject = format!(
+            "{} of the {} worker",
+            event.event_type.as_str(),
+            event.worker
+        );
+        self.post(&Self::worker_body(event), subject).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{
+        models::LifecycleStorageClass,
+        value_objects::{ObjectKey, VersionId},
+    };
+    use chrono::Utc;
+
+    #[test]
+    fn test_body_follows_s3_event_layout() {
+        let key = ObjectKey::new("logs/2024/app.log".to_string()).unwrap();
+        let expired = LifecycleEvent::new(
+            LifecycleEventType::ObjectExpired,
+            key.clone(),
+            "r1",
+            Utc::now(),
+        )
+        .with_version_id(VersionId::new("v1".to_string()).unwrap());
+
+        let body = WebhookEventSink::body(&expired);
+        let record = &body["Records"][0];
+        assert_eq!(record["eventName"], "ObjectExpired");
+        assert_eq!(record["s3"]["bucket"]["name"], "logs");
+        assert_eq!(record["s3"]["object"]["key"], "2024/app.log");
+        assert_eq!(record["s3"]["object"]["versionId"], "v1");
+        assert_eq!(record["lifecycleEventData"]["ruleId"], "r1");
+
+        let transitioned = LifecycleEvent::new(
+            LifecycleEventType::ObjectTransitioned,
+            key,
+            "r2",
+            Utc::now(),
+        )
+        .with_storage_class(LifecycleStorageClass::Glacier);
+        let body = WebhookEventSink::body(&transitioned);
+        assert_eq!(
+            body["Records"][0]["lifecycleEventData"]["transitionEventData"]["destinationStorageClass"],
+            "GLACIER"
+        );
+    }
+
+    #[test]
+    fn test_worker_body_names_worker_and_reason() {
+        let paused = WorkerEvent::paused("lifecycle", "object store is unavailable: timeout");
+        let body = WebhookEventSink::worker_body(&paused);
+        let record = &body["Records"][0];
+        assert_eq!(record["eventName"], "WorkerPaused");
+        assert_eq!(record["eventSource"], WORKER_EVENT_SOURCE);
+        assert_eq!(record["workerEventData"]["worker"], "lifecycle");
+        assert_eq!(
+            record["workerEventData"]["reason"],
+            "object store is unavailable: timeout"
+        );
+
+        let body = WebhookEventSink::worker_body(&WorkerEvent::resumed("backup"));
+        assert_eq!(body["Records"][0]["eventName"], "WorkerResumed");
+        assert!(body["Records"][0]["workerEventData"]["reason"].is_null());
+    }
+}
@@ -0,0 +1,528 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::{
+    domain::{
+        errors::StorageResult,
+        models::{
+            Filter, ObjectMetadata, ObjectVersionInfo, ObjectVersionList, ObjectVersionPage,
+            PrefixUsage, TextQuery, VersionOrder,
+        },
+        value_objects::{ObjectKey, VersionId},
+    },
+    ports::repositories::ObjectRepository,
+};
+
+/// Fixed-size set of keys answering membership with false positives only
+struct BloomFilter {
+    bits: Vec<AtomicU64>,
+    hash_count: u32,
+    hashers: (RandomState, RandomState),
+    /// Number of keys the filter was sized for
+    capacity: usize,
+    inserted: AtomicUsize,
+}
+
+impl BloomFilter {
+    /// Size a filter holding `capacity` keys at `false_positive_rate`
+    fn new(capacity: usize, false_positive_rate: f64) -> Self {
+        let capacity = capacity.max(1);
+        let ln2 = std::f64::consts::LN_2;
+        let bit_count = (-(capacity as f64) * false_positive_rate.ln() / (ln2 * ln2)).ceil();
+        let bit_count = (bit_count as usize).max(64);
+        let hash_count = ((bit_count as f64 / capacity as f64) * ln2).round() as u32;
+        Self {
+            bits: (0..bit_count.div_ceil(64))
+                .map(|_| AtomicU64::new(0))
+                .collect(),
+            hash_count: hash_count.clamp(1, 32),
+            hashers: (RandomState::new(), RandomState::new()),
+            capacity,
+            inserted: AtomicUsize::new(0),
+        }
+    }
+
+    /// Bit positions of `key`, by double hashing
+    fn positions(&self, key: &str) -> impl Iterator<Item = usize> + '_ {
+        let bit_count = (self.bits.len() * 64) as u64;
+        let h1 = self.hashers.0.hash_one(key);
+        let h2 = self.hashers.1.hash_one(key) | 1;
+        (0..self.hash_count as u64)
+            .map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % bit_count) as usize)
+    }
+
+    fn insert(&self, key: &str) {
+        for position in self.positions(key) {
+            self.bits[position / 64].fetch_or(1 << (position % 64), Ordering::Relaxed);
+        }
+        self.inserted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn might_contain(&self, key: &str) -> bool {
+        self.positions(key).all(|position| {
+            self.bits[position / 64].load(Ordering::Relaxed) & (1 << (position % 64)) != 0
+        })
+    }
+
+    /// Whether more keys were inserted than the filter was sized for
+    fn is_saturated(&self) -> bool {
+        self.inserted.load(Ordering::Relaxed) > self.capacity
+    }
+}
+
+/// Filter of one bucket, and the state of its next build
+#[derive(Default)]
+struct BucketFilter {
+    filter: Option<Arc<BloomFilter>>,
+    built_at: Option<Instant>,
+    /// Keys written while a build is running; None when no build is running
+    written_during_build: Option<Vec<String>>,
+}
+
+/// ObjectRepository answering lookups of definitely absent keys itself
+///
+/// Every bucket, the first `/`-separated segment of a key, gets a bloom
+/// filter of the keys it holds, built from the inner repository's listings the
+/// first time the bucket is looked up. Once the filter is built, existence
+/// checks and metadata and version lookups of keys it does not contain are
+/// answered as not found without asking the inner repository, so clients
+/// polling for objects that do not exist cost no backend requests. Lookups of
+/// keys the filter may contain, and of buckets whose filter is still being
+/// built, go to the inner repository.
+///
+/// Writes through this repository add their key to the filter. Deleted keys
+/// stay in the filter until it is rebuilt, which happens every
+/// `rebuild_interval` and whenever more keys were added than the filter was
+/// sized for. Keys written by other instances sharing the database are only
+/// found once the filter has been rebuilt after the write.
+#[derive(Clone)]
+pub struct BloomFilterObjectRepository {
+    inner: Arc<dyn ObjectRepository>,
+    filters: Arc<Mutex<HashMap<String, BucketFilter>>>,
+    /// Fewest keys a bucket's filter is sized for
+    min_capacity: usize,
+    false_positive_rate: f64,
+    rebuild_interval: Duration,
+    /// Lookups answered without the inner repository
+    filtered_lookups: Arc<AtomicU64>,
+}
+
+impl BloomFilterObjectRepository {
+    /// Filter lookups of absent keys at `false_positive_rate`, sizing every
+    /// bucket's filter for twice its keys but at least `min_capacity`, and
+    /// rebuilding it every `rebuild_interval`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `false_positive_rate` is not between 0 and 1.
+    pub fn new(
+        inner: Arc<dyn ObjectRepository>,
+        min_capacity: usize,
+        false_positive_rate: f64,
+        rebuild_interval: Duration,
+    ) -> Self {
+        assert!(
+            false_positive_rate > 0.0 && false_positive_rate < 1.0,
+            "the false positive rate must be between 0 and 1"
+        );
+        Self {
+            inner,
+            filters: Arc::new(Mutex::new(HashMap::new())),
+            min_capacity,
+            false_positive_rate,
+            rebuild_interval,
+            filtered_lookups: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Number of lookups answered as not found without the inner repository
+    pub fn filtered_lookups(&self) -> u64 {
+        self.filtered_lookups.load(Ordering::Relaxed)
+    }
+
+    /// Whether `key` is definitely absent, starting a build of its bucket's
+    /// filter if it has none or the filter is due to be rebuilt
+    fn is_absent(&self, key: &ObjectKey) -> bool {
+        let Some((bucket, _)) = key.as_str().split_once('/') else {
+            return false;
+        };
+
+        let mut filters = self.filters.lock().unwrap();
+        let entry = filters.entry(bucket.to_string()).or_default();
+        let due = match (&entry.filter, entry.built_at) {
+            (Some(filter), Some(built_at)) => {
+                filter.is_saturated() || built_at.elapsed() >= self.rebuild_interval
+            }
+            _ => true,
+        };
+        if due && entry.written_during_build.is_none() {
+            entry.written_during_build = Some(Vec::new());
+            let this = self.clone();
+            let bucket = bucket.to_string();
+            tokio::spawn(async move { this.build(bucket).await });
+        }
+
+        let absent = entry
+            .filter
+            .as_ref()
+            .is_some_and(|filter| !filter.might_contain(key.as_str()));
+        if absent {
+            self.filtered_lookups.fetch_add(1, Ordering::Relaxed);
+        }
+        absent
+    }
+
+    /// Add `key` to its bucket's filter and to the filter being built for it
+    ///
+    /// Writes record their key both before and after reaching the inner
+    /// repository: before, so that lookups racing the write are not answered
+    /// as not found, and after, so that a build whose listing started during
+    /// the write and missed it still learns of the key.
+    fn record(&self, key: &ObjectKey) {
+        let Some((bucket, _)) = key.as_str().split_once('/') else {
+            return;
+        };
+
+        let mut filters = self.filters.lock().unwrap();
+        if let Some(entry) = filters.get_mut(bucket) {
+            if let Some(filter) = &entry.filter {
+                filter.insert(key.as_str());
+            }
+            if let Some(written) = &mut entry.written_during_build {
+                written.push(key.as_str().to_string());
+            }
+        }
+    }
+
+    /// Build the filter of `bucket` from the inner repository and swap it in
+    async fn build(&self, bucket: String) {
+        let prefix = format!("{}/", bucket);
+        let keys = match self.list_keys(&prefix).await {
+            Ok(keys) => keys,
+            Err(e) => {
+                tracing::warn!("Failed to build the key filter of bucket {}: {}", bucket, e);
+                // Leave the previous filter in place and retry on a later lookup
+                if let Some(entry) = self.filters.lock().unwrap().get_mut(&bucket) {
+                    entry.written_during_build = None;
+                }
+                return;
+            }
+        };
+
+        let filter = BloomFilter::new(
+            (keys.len() * 2).max(self.min_capacity),
+            self.false_positive_rate,
+        );
+        for key in &keys {
+            filter.insert(key.as_str());
+        }
+
+        let mut filters = self.filters.lock().unwrap();
+        let entry = filters.entry(bucket).or_default();
+        for key in entry.written_during_build.take().unwrap_or_default() {
+            filter.insert(&key);
+        }
+        entry.filter = Some(Arc::new(filter));
+        entry.built_at = Some(Instant::now());
+    }
+
+    /// Every key under `prefix` with a version, including deleted keys
+    async fn list_keys(&self, prefix: &str) -> StorageResult<Vec<ObjectKey>> {
+        let mut keys = self.inner.list_objects_by_prefix(prefix, None).await?;
+        keys.extend(self.inner.list_delete_markers(prefix).await?);
+        Ok(keys)
+    }
+}
+
+#[async_trait]
+impl ObjectRepository for BloomFilterObjectRepository {
+    async fn save_object_metadata(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+        metadata: &ObjectMetadata,
+    ) -> StorageResult<()> {
+        self.record(key);
+        let result = self
+            .inner
+            .save_object_metadata(key, version_id, metadata)
+            .await;
+        self.record(key);
+        result
+    }
+
+    async fn save_object_versions(
+        &self,
+        versions: &[(ObjectKey, VersionId, ObjectMetadata)],
+    ) -> StorageResult<()> {
+        for (key, _, _) in versions {
+            self.record(key);
+        }
+        let result = self.inner.save_object_versions(versions).await;
+        for (key, _, _) in versions {
+            self.record(key);
+        }
+        result
+    }
+
+    async fn get_object_metadata(
+        &self,
+        key: &ObjectKey,
+        version_id: Option<&VersionId>,
+    ) -> StorageResult<Option<ObjectMetadata>> {
+        if self.is_absent(key) {
+            return Ok(None);
+        }
+        self.inner.get_object_metadata(key, version_id).await
+    }
+
+    async fn list_object_versions(&self, key: &ObjectKey) -> StorageResult<ObjectVersionList> {
+        self.inner.list_object_versions(key).await
+    }
+
+    async fn list_object_versions_page(
+        &self,
+        key: &ObjectKey,
+        marker: Option<&VersionId>,
+        limit: usize,
+        order: VersionOrder,
+    ) -> StorageResult<Option<ObjectVersionPage>> {
+        self.inner
+            .list_object_versions_page(key, marker, limit, order)
+            .await
+    }
+
+    async fn get_version_info(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+    ) -> StorageResult<Option<ObjectVersionInfo>> {
+        if self.is_absent(key) {
+            return Ok(None);
+        }
+        self.inner.get_version_info(key, version_id).await
+    }
+
+    async fn mark_version_deleted(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+    ) -> StorageResult<()> {
+        self.record(key);
+        let result = self.inner.mark_version_deleted(key, version_id).await;
+        self.record(key);
+        result
+    }
+
+    async fn delete_version_metadata(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+    ) -> StorageResult<()> {
+        self.inner.delete_version_metadata(key, version_id).await
+    }
+
+    async fn get_latest_version_id(&self, key: &ObjectKey) -> StorageResult<Option<VersionId>> {
+        if self.is_absent(key) {
+            return Ok(None);
+        }
+        self.inner.get_latest_version_id(key).await
+    }
+
+    async fn list_objects_by_prefix(
+        &self,
+        prefix: &str,
+        max_results: Option<usize>,
+    ) -> StorageResult<Vec<ObjectKey>> {
+        self.inner.list_objects_by_prefix(prefix, max_results).await
+    }
+
+    async fn list_delete_markers(&self, prefix: &str) -> StorageResult<Vec<ObjectKey>> {
+        self.inner.list_delete_markers(prefix).await
+    }
+
+    async fn list_latest_versions(
+        &self,
+        prefix: &str,
+        start_after: Option<&str>,
+        max_results: Option<usize>,
+    ) -> StorageResult<Vec<(ObjectKey, ObjectVersionInfo)>> {
+        self.inner
+            .list_latest_versions(prefix, start_after, max_results)
+            .await
+    }
+
+    async fn prefix_usage(&self, prefix: &str) -> StorageResult<PrefixUsage> {
+        self.inner.prefix_usage(prefix).await
+    }
+
+    async fn find_objects(
+        &self,
+        filter: &Filter,
+        max_results: Option<usize>,
+    ) -> StorageResult<Vec<ObjectKey>> {
+        self.inner.find_objects(filter, max_results).await
+    }
+
+    async fn search_objects(
+        &self,
+        query: &TextQuery,
+        filter: &Filter,
+        max_results: Option<usize>,
+    ) -> StorageResult<Vec<ObjectKey>> {
+        self.inner.search_objects(query, filter, max_results).await
+    }
+
+    async fn update_object_metadata(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+        metadata: &ObjectMetadata,
+    ) -> StorageResult<()> {
+        self.record(key);
+        let result = self
+            .inner
+            .update_object_metadata(key, version_id, metadata)
+            .await;
+        self.record(key);
+        result
+    }
+
+    async fn compare_and_set_object_metadata(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+        expected: &ObjectMetadata,
+        metadata: &ObjectMetadata,
+    ) -> StorageResult<()> {
+        self.inner
+            .compare_and_set_object_metadata(key, version_id, expected, metadata)
+            .await
+    }
+
+    async fn object_exists(&self, key: &ObjectKey) -> StorageResult<bool> {
+        if self.is_absent(key) {
+            return Ok(false);
+        }
+        self.inner.object_exists(key).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::outbound::persistence::InMemoryObjectRepository;
+
+    fn metadata() -> ObjectMetadata {
+        ObjectMetadata {
+            content_type: None,
+            content_length: 1,
+            etag: None,
+            last_modified: std::time::SystemTime::now(),
+            custom_metadata: HashMap::new(),
+        }
+    }
+
+    fn key(key: &str) -> ObjectKey {
+        ObjectKey::new(key.to_string()).unwrap()
+    }
+
+    /// Wait for the filter builds started by earlier lookups to finish
+    async fn settle(repo: &BloomFilterObjectRepository) {
+        for _ in 0..100 {
+            let building = repo
+                .filters
+                .lock()
+                .unwrap()
+                .values()
+                .any(|entry| entry.written_during_build.is_some());
+            if !building {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        panic!("the filter build did not finish");
+    }
+
+    #[test]
+    fn test_bloom_filter_has_no_false_negatives() {
+        let filter = BloomFilter::new(1000, 0.01);
+        for i in 0..1000 {
+            filter.insert(&format!("bucket/key-{}", i));
+        }
+        assert!((0..1000).all(|i| filter.might_contain(&format!("bucket/key-{}", i))));
+        assert!(!filter.is_saturated());
+
+        let false_positives = (0..1000)
+            .filter(|i| filter.might_contain(&format!("bucket/other-{}", i)))
+            .count();
+        assert!(false_positives < 50, "{} false positives", false_positives);
+    }
+
+    #[tokio::test]
+    async fn test_absent_keys_are_answered_without_the_inner_repository() {
+        let inner = Arc::new(InMemoryObjectRepository::new());
+        let v1 = VersionId::new("v1".to_string()).unwrap();
+        inner
+            .save_object_metadata(&key("photos/cat.jpg"), &v1, &metadata())
+            .await
+            .unwrap();
+        let repo =
+            BloomFilterObjectRepository::new(inner.clone(), 100, 0.01, Duration::from_secs(60));
+
+        // The first lookup of a bucket goes through while its filter is built
+        assert!(!repo.object_exists(&key("photos/dog.jpg")).await.unwrap());
+        assert_eq!(repo.filtered_lookups(), 0);
+        settle(&repo).await;
+
+        assert!(!repo.object_exists(&key("photos/dog.jpg")).await.unwrap());
+        assert!(
+            repo.get_object_metadata(&key("photos/dog.jpg"), None)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(repo.filtered_lookups(), 2);
+        assert!(repo.object_exists(&key("photos/cat.jpg")).await.unwrap());
+
+        // Writes through the repository are found at once
+        repo.save_object_metadata(&key("photos/dog.jpg"), &v1, &metadata())
+            .await
+            .unwrap();
+        assert!(repo.object_exists(&key("photos/dog.jpg")).await.unwrap());
+        assert_eq!(
+            repo.get_latest_version_id(&key("photos/dog.jpg"))
+                .await
+                .unwrap(),
+            Some(v1)
+        );
+        assert_eq!(repo.filtered_lookups(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_rebuilds_pick_up_writes_made_elsewhere() {
+        let inner = Arc::new(InMemoryObjectRepository::new());
+        let repo =
+            BloomFilterObjectRepository::new(inner.clone(), 100, 0.01, Duration::from_millis(20));
+        let v1 = VersionId::new("v1".to_string()).unwrap();
+
+        assert!(!repo.object_exists(&key("photos/cat.jpg")).await.unwrap());
+        settle(&repo).await;
+        inner
+            .save_object_metadata(&key("photos/cat.jpg"), &v1, &metadata())
+            .await
+            .unwrap();
+        assert!(!repo.object_exists(&key("photos/cat.jpg")).await.unwrap());
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        // This lookup finds the filter stale and starts a rebuild
+        repo.object_exists(&key("photos/cat.jpg")).await.unwrap();
+        settle(&repo).await;
+        assert!(repo.object_exists(&key("photos/cat.jpg")).await.unwrap());
+    }
+}
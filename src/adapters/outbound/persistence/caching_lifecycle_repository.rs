@@ -0,0 +1,155 @@
+use async_trait::async_trait;
+use moka::future::Cache;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{
+    domain::{
+        errors::LifecycleResult,
+        models::{LifecycleConfiguration, LifecycleRule},
+        value_objects::BucketName,
+    },
+    ports::repositories::LifecycleRepository,
+};
+
+/// Read-through cache in front of another LifecycleRepository
+///
+/// Configurations are cached per bucket, including the absence of one, so
+/// repeated lookups during lifecycle processing reach the inner repository
+/// once. Writes through this repository invalidate the bucket's entry; writes
+/// made elsewhere become visible once the entry's time to live runs out.
+#[derive(Clone)]
+pub struct CachingLifecycleRepository {
+    inner: Arc<dyn LifecycleRepository>,
+    configurations: Cache<String, Option<LifecycleConfiguration>>,
+}
+
+impl CachingLifecycleRepository {
+    /// Cache up to `max_capacity` bucket configurations for `time_to_live`
+    pub fn new(
+        inner: Arc<dyn LifecycleRepository>,
+        max_capacity: u64,
+        time_to_live: Duration,
+    ) -> Self {
+        Self {
+            inner,
+            configurations: Cache::builder()
+                .max_capacity(max_capacity)
+                .time_to_live(time_to_live)
+                .build(),
+        }
+    }
+}
+
+#[async_trait]
+impl LifecycleRepository for CachingLifecycleRepository {
+    async fn save_configuration(
+        &self,
+        bucket: &BucketName,
+        config: &LifecycleConfiguration,
+    ) -> LifecycleResult<()> {
+        let result = self.inner.save_configuration(bucket, config).await;
+        self.configurations.invalidate(bucket.as_str()).await;
+        result
+    }
+
+    async fn get_configuration(
+        &self,
+        bucket: &BucketName,
+    ) -> LifecycleResult<Option<LifecycleConfiguration>> {
+        if let Some(config) = self.configurations.get(bucket.as_str()).await {
+            return Ok(config);
+        }
+
+        let config = self.inner.get_configuration(bucket).await?;
+        self.configurations
+            .insert(bucket.as_str().to_string(), config.clone())
+            .await;
+        Ok(config)
+    }
+
+    async fn delete_configuration(&self, bucket: &BucketName) -> LifecycleResult<()> {
+        let result = self.inner.delete_configuration(bucket).await;
+        self.configurations.invalidate(bucket.as_str()).await;
+        result
+    }
+
+    async fn configuration_exists(&self, bucket: &BucketName) -> LifecycleResult<bool> {
+        Ok(self.get_configuration(bucket).await?.is_some())
+    }
+
+    async fn get_rule(
+        &self,
+        bucket: &BucketName,
+        rule_id: &str,
+    ) -> LifecycleResult<Option<LifecycleRule>> {
+        Ok(self
+            .get_configuration(bucket)
+            .await?
+            .and_then(|config| config.rules.into_iter().find(|rule| rule.id == rule_id)))
+    }
+
+    async fn update_rule(&self, bucket: &BucketName, rule: &LifecycleRule) -> LifecycleResult<()> {
+        let result = self.inner.update_rule(bucket, rule).await;
+        self.configurations.invalidate(bucket.as_str()).await;
+        result
+    }
+
+    async fn list_configured_buckets(&self) -> LifecycleResult<Vec<BucketName>> {
+        self.inner.list_configured_buckets().await
+    }
+
+    async fn get_last_processed_time(
+        &self,
+        bucket: &BucketName,
+    ) -> LifecycleResult<Option<std::time::SystemTime>> {
+        self.inner.get_last_processed_time(bucket).await
+    }
+
+    async fn set_last_processed_time(
+        &self,
+        bucket: &BucketName,
+        time: std::time::SystemTime,
+    ) -> LifecycleResult<()> {
+        self.inner.set_last_processed_time(bucket, time).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::outbound::persistence::InMemoryLifecycleRepository;
+    use crate::domain::models::RuleStatus;
+
+    #[tokio::test]
+    async fn test_writes_invalidate_cached_configuration() {
+        let inner = Arc::new(InMemoryLifecycleRepository::new());
+        let repo = CachingLifecycleRepository::new(inner.clone(), 100, Duration::from_secs(60));
+        let bucket = BucketName::new("test-bucket".to_string()).unwrap();
+        let mut config = LifecycleConfiguration {
+            bucket: bucket.clone(),
+            rules: vec![LifecycleRule {
+                id: "rule1".to_string(),
+                status: RuleStatus::Enabled,
+                expiration_days: Some(30),
+                ..Default::default()
+            }],
+        };
+
+        // The absence of a configuration is cached too
+        assert!(!repo.configuration_exists(&bucket).await.unwrap());
+        inner.save_configuration(&bucket, &config).await.unwrap();
+        assert!(repo.get_configuration(&bucket).await.unwrap().is_none());
+
+        repo.save_configuration(&bucket, &config).await.unwrap();
+        assert!(repo.configuration_exists(&bucket).await.unwrap());
+
+        config.rules[0].status = RuleStatus::Disabled;
+        repo.update_rule(&bucket, &config.rules[0]).await.unwrap();
+        let rule = repo.get_rule(&bucket, "rule1").await.unwrap().unwrap();
+        assert_eq!(rule.status, RuleStatus::Disabled);
+
+        repo.delete_configuration(&bucket).await.unwrap();
+        assert!(repo.get_configuration(&bucket).await.unwrap().is_none());
+    }
+}
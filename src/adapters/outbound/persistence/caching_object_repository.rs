@@ -0,0 +1,416 @@
+use async_trait::async_trait;
+use moka::future::Cache;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{
+    domain::{
+        errors::StorageResult,
+        models::{
+            Filter, ObjectMetadata, ObjectVersionInfo, ObjectVersionList, ObjectVersionPage,
+            PrefixUsage, TextQuery, VersionOrder,
+        },
+        value_objects::{ObjectKey, VersionId},
+    },
+    ports::repositories::ObjectRepository,
+};
+
+/// Read-through cache in front of another ObjectRepository
+///
+/// Metadata lookups, latest version IDs and existence checks are cached per
+/// object key, including negative answers. Writes through this repository
+/// invalidate the entries of the key they touch; writes made elsewhere become
+/// visible once the entries' time to live runs out, or as soon as a
+/// compare-and-set through this repository conflicts with them. Metadata
+/// updates compare against the inner repository, never the cache. Listings,
+/// searches and version lists always go to the inner repository.
+#[derive(Clone)]
+pub struct CachingObjectRepository {
+    inner: Arc<dyn ObjectRepository>,
+    // (key, version) -> metadata, with None standing for the latest version
+    metadata: Cache<(String, Option<VersionId>), Option<ObjectMetadata>>,
+    latest_versions: Cache<String, Option<VersionId>>,
+    exists: Cache<String, bool>,
+}
+
+impl CachingObjectRepository {
+    /// Cache up to `max_capacity` entries of each kind for `time_to_live`
+    pub fn new(
+        inner: Arc<dyn ObjectRepository>,
+        max_capacity: u64,
+        time_to_live: Duration,
+    ) -> Self {
+        Self {
+            inner,
+            metadata: Cache::builder()
+                .max_capacity(max_capacity)
+                .time_to_live(time_to_live)
+                .build(),
+            latest_versions: Cache::builder()
+                .max_capacity(max_capacity)
+                .time_to_live(time_to_live)
+                .build(),
+            exists: Cache::builder()
+                .max_capacity(max_capacity)
+                .time_to_live(time_to_live)
+                .build(),
+        }
+    }
+
+    /// Drop the cached entries a write to `version_id` of `key` may have changed
+    async fn invalidate(&self, key: &ObjectKey, version_id: &VersionId) {
+        let key = key.as_str().to_string();
+        self.metadata
+            .invalidate(&(key.clone(), Some(version_id.clone())))
+            .await;
+        self.metadata.invalidate(&(key.clone(), None)).await;
+        self.latest_versions.invalidate(&key).await;
+        self.exists.invalidate(&key).await;
+    }
+}
+
+#[async_trait]
+impl ObjectRepository for CachingObjectRepository {
+    async fn save_object_metadata(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+        metadata: &ObjectMetadata,
+    ) -> StorageResult<()> {
+        let result = self
+            .inner
+            .save_object_metadata(key, version_id, metadata)
+            .await;
+        self.invalidate(key, version_id).await;
+        result
+    }
+
+    async fn save_object_versions(
+        &self,
+        versions: &[(ObjectKey, VersionId, ObjectMetadata)],
+    ) -> StorageResult<()> {
+        let result = self.inner.save_object_versions(versions).await;
+        for (key, version_id, _) in versions {
+            self.invalidate(key, version_id).await;
+        }
+        result
+    }
+
+    async fn get_object_metadata(
+        &self,
+        key: &ObjectKey,
+        version_id: Option<&VersionId>,
+    ) -> StorageResult<Option<ObjectMetadata>> {
+        let cache_key = (key.as_str().to_string(), version_id.cloned());
+        if let Some(metadata) = self.metadata.get(&cache_key).await {
+            return Ok(metadata);
+        }
+
+        let metadata = self.inner.get_object_metadata(key, version_id).await?;
+        self.metadata.insert(cache_key, metadata.clone()).await;
+        Ok(metadata)
+    }
+
+    async fn list_object_versions(&self, key: &ObjectKey) -> StorageResult<ObjectVersionList> {
+        self.inner.list_object_versions(key).await
+    }
+
+    async fn list_object_versions_page(
+        &self,
+        key: &ObjectKey,
+        marker: Option<&VersionId>,
+        limit: usize,
+        order: VersionOrder,
+    ) -> StorageResult<Option<ObjectVersionPage>> {
+        self.inner
+            .list_object_versions_page(key, marker, limit, order)
+            .await
+    }
+
+    async fn get_version_info(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+    ) -> StorageResult<Option<ObjectVersionInfo>> {
+        self.inner.get_version_info(key, version_id).await
+    }
+
+    async fn mark_version_deleted(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+    ) -> StorageResult<()> {
+        let result = self.inner.mark_version_deleted(key, version_id).await;
+        self.invalidate(key, version_id).await;
+        result
+    }
+
+    async fn delete_version_metadata(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+    ) -> StorageResult<()> {
+        let result = self.inner.delete_version_metadata(key, version_id).await;
+        self.invalidate(key, version_id).await;
+        result
+    }
+
+    async fn get_latest_version_id(&self, key: &ObjectKey) -> StorageResult<Option<VersionId>> {
+        if let Some(version_id) = self.latest_versions.get(key.as_str()).await {
+            return Ok(version_id);
+        }
+
+        let version_id = self.inner.get_latest_version_id(key).await?;
+        self.latest_versions
+            .insert(key.as_str().to_string(), version_id.clone())
+            .await;
+        Ok(version_id)
+    }
+
+    async fn list_objects_by_prefix(
+        &self,
+        prefix: &str,
+        max_results: Option<usize>,
+    ) -> StorageResult<Vec<ObjectKey>> {
+        self.inner.list_objects_by_prefix(prefix, max_results).await
+    }
+
+    async fn list_delete_markers(&self, prefix: &str) -> StorageResult<Vec<ObjectKey>> {
+        self.inner.list_delete_markers(prefix).await
+    }
+
+    async fn list_latest_versions(
+        &self,
+        prefix: &str,
+        start_after: Option<&str>,
+        max_results: Option<usize>,
+    ) -> StorageResult<Vec<(ObjectKey, ObjectVersionInfo)>> {
+        self.inner
+            .list_latest_versions(prefix, start_after, max_results)
+            .await
+    }
+
+    async fn prefix_usage(&self, prefix: &str) -> StorageResult<PrefixUsage> {
+        self.inner.prefix_usage(prefix).await
+    }
+
+    async fn find_objects(
+        &self,
+        filter: &Filter,
+        max_results: Option<usize>,
+    ) -> StorageResult<Vec<ObjectKey>> {
+        self.inner.find_objects(filter, max_results).await
+    }
+
+    async fn search_objects(
+        &self,
+        query: &TextQuery,
+        filter: &Filter,
+        max_results: Option<usize>,
+    ) -> StorageResult<Vec<ObjectKey>> {
+        self.inner.search_objects(query, filter, max_results).await
+    }
+
+    async fn update_object_metadata(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+        metadata: &ObjectMetadata,
+    ) -> StorageResult<()> {
+        let result = self
+            .inner
+            .update_object_metadata(key, version_id, metadata)
+            .await;
+        self.invalidate(key, version_id).await;
+        result
+    }
+
+    async fn compare_and_set_object_metadata(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+        expected: &ObjectMetadata,
+        metadata: &ObjectMetadata,
+    ) -> StorageResult<()> {
+        let result = self
+            .inner
+            .compare_and_set_object_metadata(key, version_id, expected, metadata)
+            .await;
+        // Also dropped on a conflict, so a retry reads the metadata that won
+        // and the latest version it may have moved to
+        self.invalidate(key, version_id).await;
+        result
+    }
+
+    /// Apply `change` in the inner repository, so each attempt compares
+    /// against what is stored there rather than against a cached copy another
+    /// instance may have changed since
+    async fn modify_object_metadata(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+        change: &(dyn for<'m> Fn(&'m mut ObjectMetadata) -> StorageResult<()> + Send + Sync),
+    ) -> StorageResult<ObjectMetadata> {
+        let result = self
+            .inner
+            .modify_object_metadata(key, version_id, change)
+            .await;
+        self.invalidate(key, version_id).await;
+        result
+    }
+
+    async fn object_exists(&self, key: &ObjectKey) -> StorageResult<bool> {
+        if let Some(exists) = self.exists.get(key.as_str()).await {
+            return Ok(exists);
+        }
+
+        let exists = self.inner.object_exists(key).await?;
+        self.exists.insert(key.as_str().to_string(), exists).await;
+        Ok(exists)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::outbound::persistence::InMemoryObjectRepository;
+    use crate::domain::errors::StorageError;
+    use std::collections::HashMap;
+
+    fn metadata(content_length: u64) -> ObjectMetadata {
+        ObjectMetadata {
+            content_type: None,
+            content_length,
+            etag: None,
+            last_modified: std::time::SystemTime::now(),
+            custom_metadata: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_writes_invalidate_cached_entries() {
+        let inner = Arc::new(InMemoryObjectRepository::new());
+        let repo = CachingObjectRepository::new(inner.clone(), 100, Duration::from_secs(60));
+        let key = ObjectKey::new("docs/readme.md".to_string()).unwrap();
+        let v1 = VersionId::new("v1".to_string()).unwrap();
+        let v2 = VersionId::new("v2".to_string()).unwrap();
+
+        // Negative answers are cached until a write through the cache
+        assert!(!repo.object_exists(&key).await.unwrap());
+        inner
+            .save_object_metadata(&key, &v1, &metadata(1))
+            .await
+            .unwrap();
+        assert!(!repo.object_exists(&key).await.unwrap());
+
+        repo.save_object_metadata(&key, &v1, &metadata(1))
+            .await
+            .unwrap();
+        assert!(repo.object_exists(&key).await.unwrap());
+        assert_eq!(
+            repo.get_latest_version_id(&key).await.unwrap(),
+            Some(v1.clone())
+        );
+        let latest = repo.get_object_metadata(&key, None).await.unwrap().unwrap();
+        assert_eq!(latest.content_length, 1);
+
+        repo.save_object_metadata(&key, &v2, &metadata(2))
+            .await
+            .unwrap();
+        assert_eq!(repo.get_latest_version_id(&key).await.unwrap(), Some(v2));
+        let latest = repo.get_object_metadata(&key, None).await.unwrap().unwrap();
+        assert_eq!(latest.content_length, 2);
+
+        repo.update_object_metadata(&key, &v1, &metadata(10))
+            .await
+            .unwrap();
+        let v1_metadata = repo
+            .get_object_metadata(&key, Some(&v1))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(v1_metadata.content_length, 10);
+    }
+
+    #[tokio::test]
+    async fn test_metadata_updates_from_another_instance_are_not_lost() {
+        // Two server instances, each with its own cache, sharing a backend
+        let inner: Arc<dyn ObjectRepository> = Arc::new(InMemoryObjectRepository::new());
+        let first = CachingObjectRepository::new(inner.clone(), 100, Duration::from_secs(60));
+        let second = CachingObjectRepository::new(inner.clone(), 100, Duration::from_secs(60));
+        let key = ObjectKey::new("docs/readme.md".to_string()).unwrap();
+        let v1 = VersionId::new("v1".to_string()).unwrap();
+        let v2 = VersionId::new("v2".to_string()).unwrap();
+
+        first
+            .save_object_metadata(&key, &v1, &metadata(1))
+            .await
+            .unwrap();
+        let cached = first
+            .get_object_metadata(&key, Some(&v1))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            first.get_latest_version_id(&key).await.unwrap(),
+            Some(v1.clone())
+        );
+
+        // The other instance labels v1 and then moves the latest version on
+        second
+            .modify_object_metadata(&key, &v1, &|metadata| {
+                metadata
+                    .custom_metadata
+                    .insert("reviewed".to_string(), "yes".to_string());
+                Ok(())
+            })
+            .await
+            .unwrap();
+        second
+            .save_object_metadata(&key, &v2, &metadata(2))
+            .await
+            .unwrap();
+
+        // A write against the stale cached copy conflicts instead of
+        // dropping the label, and the conflict drops the stale entries
+        let mut pinned = cached.clone();
+        pinned
+            .custom_metadata
+            .insert("pinned".to_string(), "true".to_string());
+        let conflict = first
+            .compare_and_set_object_metadata(&key, &v1, &cached, &pinned)
+            .await;
+        assert!(matches!(
+            conflict,
+            Err(StorageError::VersionConflict { .. })
+        ));
+        assert_eq!(
+            first.get_latest_version_id(&key).await.unwrap(),
+            Some(v2.clone())
+        );
+        let latest = first
+            .get_object_metadata(&key, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(latest.content_length, 2);
+
+        // Read-modify-write goes to the stored metadata, keeping both changes
+        let updated = first
+            .modify_object_metadata(&key, &v1, &|metadata| {
+                metadata
+                    .custom_metadata
+                    .insert("pinned".to_string(), "true".to_string());
+                Ok(())
+            })
+            .await
+            .unwrap();
+        assert_eq!(updated.custom_metadata.len(), 2);
+        let stored = second
+            .get_object_metadata(&key, Some(&v1))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored, updated);
+    }
+}
@@ -0,0 +1,133 @@
+use sqlx::PgPool;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// Time a replica gets to answer a health check
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Connection pools of a primary database and its read replicas
+///
+/// Writes always go to the primary. Reads are spread round-robin over the
+/// replicas that passed their last health check, and go to the primary while
+/// none has. Replicas start out unhealthy, so nothing is read from one before
+/// [`check_replicas`](Self::check_replicas) has seen it answer; a replica
+/// that fails a check is skipped until it answers again.
+///
+/// Replicas lag behind the primary, so a read may not yet see a write made
+/// just before it. Lookups that must see the caller's own writes, and the
+/// reads of a compare-and-set, go to the primary; only listing and search are
+/// sent to the replicas.
+pub struct DatabasePools {
+    primary: PgPool,
+    replicas: Vec<Replica>,
+    next_replica: AtomicUsize,
+}
+
+struct Replica {
+    pool: PgPool,
+    healthy: AtomicBool,
+}
+
+impl DatabasePools {
+    pub fn new(primary: PgPool) -> Self {
+        Self {
+            primary,
+            replicas: Vec::new(),
+            next_replica: AtomicUsize::new(0),
+        }
+    }
+
+    /// Send reads to `replicas` once they pass a health check
+    pub fn with_replicas(mut self, replicas: Vec<PgPool>) -> Self {
+        self.replicas = replicas
+            .into_iter()
+            .map(|pool| Replica {
+                pool,
+                healthy: AtomicBool::new(false),
+            })
+            .collect();
+        self
+    }
+
+    /// Pool writes, migrations and reads that must see the latest write go to
+    pub fn primary(&self) -> &PgPool {
+        &self.primary
+    }
+
+    /// Pool the next read that tolerates replication lag goes to
+    pub fn reader(&self) -> &PgPool {
+        if self.replicas.is_empty() {
+            return &self.primary;
+        }
+
+        let start = self.next_replica.fetch_add(1, Ordering::Relaxed);
+        (0..self.replicas.len())
+            .map(|offset| &self.replicas[(start + offset) % self.replicas.len()])
+            .find(|replica| replica.healthy.load(Ordering::Relaxed))
+            .map_or(&self.primary, |replica| &replica.pool)
+    }
+
+    /// Number of replicas currently receiving reads
+    pub fn healthy_replicas(&self) -> usize {
+        self.replicas
+            .iter()
+            .filter(|replica| replica.healthy.load(Ordering::Relaxed))
+            .count()
+    }
+
+    /// Probe every replica and update which ones receive reads
+    pub async fn check_replicas(&self) {
+        for replica in &self.replicas {
+            let probe = sqlx::query("SELECT 1").execute(&replica.pool);
+            let healthy = matches!(
+                tokio::time::timeout(HEALTH_CHECK_TIMEOUT, probe).await,
+                Ok(Ok(_))
+            );
+            if replica.healthy.swap(healthy, Ordering::Relaxed) != healthy {
+                let host = replica.pool.connect_options().get_host().to_string();
+                if healthy {
+                    tracing::info!("Read replica {} is serving reads", host);
+                } else {
+                    tracing::warn!("Read replica {} is unreachable", host);
+                }
+            }
+        }
+    }
+
+    /// Check the replicas every `interval` until the pools are dropped
+    pub fn spawn_health_checks(self: &Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        let pools: Weak<Self> = Arc::downgrade(self);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let Some(pools) = pools.upgrade() else {
+                    break;
+                };
+                pools.check_replicas().await;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lazy_pool(port: u16) -> PgPool {
+        PgPool::connect_lazy(&format!("postgres://user@127.0.0.1:{}/objects", port)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_reads_fall_back_to_primary() {
+        let pools =
+            DatabasePools::new(lazy_pool(1)).with_replicas(vec![lazy_pool(2), lazy_pool(3)]);
+        assert!(std::ptr::eq(pools.reader(), pools.primary()));
+
+        // Nothing listens on the replicas' ports, so they stay out of rotation
+        pools.check_replicas().await;
+        assert_eq!(pools.healthy_replicas(), 0);
+        assert!(std::ptr::eq(pools.reader(), pools.primary()));
+    }
+}
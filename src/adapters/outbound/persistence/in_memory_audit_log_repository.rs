@@ -0,0 +1,37 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::{
+    domain::{errors::StorageResult, models::AuditEvent, value_objects::ObjectKey},
+    ports::repositories::AuditLogRepository,
+};
+
+/// In-memory implementation of AuditLogRepository for testing and development
+#[derive(Clone, Default)]
+pub struct InMemoryAuditLogRepository {
+    events: Arc<RwLock<Vec<AuditEvent>>>,
+}
+
+impl InMemoryAuditLogRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl AuditLogRepository for InMemoryAuditLogRepository {
+    async fn record_event(&self, event: AuditEvent) -> StorageResult<()> {
+        self.events.write().await.push(event);
+        Ok(())
+    }
+
+    async fn list_events(&self, key: Option<&ObjectKey>) -> StorageResult<Vec<AuditEvent>> {
+        let events = self.events.read().await;
+        Ok(events
+            .iter()
+            .filter(|event| key.is_none_or(|key| &event.key == key))
+            .cloned()
+            .collect())
+    }
+}
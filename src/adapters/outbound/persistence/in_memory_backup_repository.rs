@@ -0,0 +1,74 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::{
+    domain::{
+        errors::StorageResult,
+        models::{BackupJob, BackupRun},
+    },
+    ports::repositories::BackupRepository,
+};
+
+/// In-memory implementation of BackupRepository for testing and development
+#[derive(Clone, Default)]
+pub struct InMemoryBackupRepository {
+    jobs: Arc<RwLock<HashMap<String, BackupJob>>>,
+    runs: Arc<RwLock<HashMap<String, Vec<BackupRun>>>>,
+}
+
+impl InMemoryBackupRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl BackupRepository for InMemoryBackupRepository {
+    async fn save_job(&self, job: &BackupJob) -> StorageResult<()> {
+        self.jobs
+            .write()
+            .await
+            .insert(job.job_id.clone(), job.clone());
+        Ok(())
+    }
+
+    async fn get_job(&self, job_id: &str) -> StorageResult<Option<BackupJob>> {
+        Ok(self.jobs.read().await.get(job_id).cloned())
+    }
+
+    async fn list_jobs(&self) -> StorageResult<Vec<BackupJob>> {
+        let mut jobs: Vec<BackupJob> = self.jobs.read().await.values().cloned().collect();
+        jobs.sort_by_key(|job| job.created);
+        Ok(jobs)
+    }
+
+    async fn delete_job(&self, job_id: &str) -> StorageResult<()> {
+        self.jobs.write().await.remove(job_id);
+        self.runs.write().await.remove(job_id);
+        Ok(())
+    }
+
+    async fn save_run(&self, run: &BackupRun) -> StorageResult<()> {
+        let mut runs = self.runs.write().await;
+        let runs = runs.entry(run.job_id.clone()).or_default();
+        match runs.iter_mut().find(|saved| saved.run_id == run.run_id) {
+            Some(saved) => *saved = run.clone(),
+            None => runs.push(run.clone()),
+        }
+        Ok(())
+    }
+
+    async fn list_runs(&self, job_id: &str) -> StorageResult<Vec<BackupRun>> {
+        let mut runs = self
+            .runs
+            .read()
+            .await
+            .get(job_id)
+            .cloned()
+            .unwrap_or_default();
+        runs.sort_by_key(|run| std::cmp::Reverse(run.started));
+        Ok(runs)
+    }
+}
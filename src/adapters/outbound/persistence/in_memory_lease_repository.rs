@@ -0,0 +1,86 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+use crate::{domain::errors::StorageResult, ports::repositories::LeaseRepository};
+
+/// A lease and when it expires
+struct Lease {
+    holder: String,
+    expires_at: Instant,
+}
+
+/// In-memory implementation of LeaseRepository for testing and development
+///
+/// Leases are only seen by the workers of this process, so they cannot
+/// coordinate separate server instances.
+#[derive(Clone, Default)]
+pub struct InMemoryLeaseRepository {
+    leases: Arc<Mutex<HashMap<String, Lease>>>,
+}
+
+impl InMemoryLeaseRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl LeaseRepository for InMemoryLeaseRepository {
+    async fn try_acquire_lease(
+        &self,
+        name: &str,
+        holder: &str,
+        ttl: Duration,
+    ) -> StorageResult<bool> {
+        let mut leases = self.leases.lock().await;
+        let now = Instant::now();
+        if let Some(lease) = leases.get(name) {
+            if lease.holder != holder && lease.expires_at > now {
+                return Ok(false);
+            }
+        }
+        leases.insert(
+            name.to_string(),
+            Lease {
+                holder: holder.to_string(),
+                expires_at: now + ttl,
+            },
+        );
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: Duration = Duration::from_secs(3600);
+
+    async fn acquire(
+        repo: &InMemoryLeaseRepository,
+        name: &str,
+        holder: &str,
+        ttl: Duration,
+    ) -> bool {
+        repo.try_acquire_lease(name, holder, ttl).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_lease_is_held_by_one_holder_until_it_expires() {
+        let repo = InMemoryLeaseRepository::new();
+
+        assert!(acquire(&repo, "lifecycle/a", "one", HOUR).await);
+        assert!(!acquire(&repo, "lifecycle/a", "two", HOUR).await);
+        // Other leases and renewals by the holder are unaffected
+        assert!(acquire(&repo, "lifecycle/b", "two", HOUR).await);
+        assert!(acquire(&repo, "lifecycle/a", "one", Duration::ZERO).await);
+
+        // The renewal above left the lease expired
+        assert!(acquire(&repo, "lifecycle/a", "two", HOUR).await);
+        assert!(!acquire(&repo, "lifecycle/a", "one", HOUR).await);
+    }
+}
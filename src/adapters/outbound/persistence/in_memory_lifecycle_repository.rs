@@ -13,7 +13,7 @@ use crate::{
 };
 
 /// In-memory implementation of LifecycleRepository for testing and development
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct InMemoryLifecycleRepository {
     data: Arc<RwLock<RepositoryData>>,
 }
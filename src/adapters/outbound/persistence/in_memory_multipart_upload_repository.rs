@@ -0,0 +1,88 @@
+use async_trait::async_trait;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::{
+    domain::{
+        errors::{StorageError, StorageResult},
+        models::{MultipartUploadInfo, UploadedPart},
+    },
+    ports::repositories::MultipartUploadRepository,
+};
+
+struct UploadEntry {
+    upload: MultipartUploadInfo,
+    parts: BTreeMap<u32, UploadedPart>,
+}
+
+/// In-memory implementation of MultipartUploadRepository for testing and development
+#[derive(Clone, Default)]
+pub struct InMemoryMultipartUploadRepository {
+    uploads: Arc<RwLock<HashMap<String, UploadEntry>>>,
+}
+
+impl InMemoryMultipartUploadRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl MultipartUploadRepository for InMemoryMultipartUploadRepository {
+    async fn create_upload(&self, upload: &MultipartUploadInfo) -> StorageResult<()> {
+        self.uploads.write().await.insert(
+            upload.upload_id.clone(),
+            UploadEntry {
+                upload: upload.clone(),
+                parts: BTreeMap::new(),
+            },
+        );
+        Ok(())
+    }
+
+    async fn get_upload(&self, upload_id: &str) -> StorageResult<Option<MultipartUploadInfo>> {
+        let uploads = self.uploads.read().await;
+        Ok(uploads.get(upload_id).map(|entry| entry.upload.clone()))
+    }
+
+    async fn list_uploads(&self, prefix: &str) -> StorageResult<Vec<MultipartUploadInfo>> {
+        let uploads = self.uploads.read().await;
+        let mut matching: Vec<MultipartUploadInfo> = uploads
+            .values()
+            .filter(|entry| entry.upload.key.as_str().starts_with(prefix))
+            .map(|entry| entry.upload.clone())
+            .collect();
+        matching.sort_by(|a, b| {
+            a.key
+                .as_str()
+                .cmp(b.key.as_str())
+                .then(a.initiated.cmp(&b.initiated))
+        });
+        Ok(matching)
+    }
+
+    async fn save_part(&self, upload_id: &str, part: &UploadedPart) -> StorageResult<()> {
+        let mut uploads = self.uploads.write().await;
+        let entry = uploads
+            .get_mut(upload_id)
+            .ok_or_else(|| StorageError::InternalError {
+                message: format!("Multipart upload '{}' is not recorded", upload_id),
+            })?;
+        entry.parts.insert(part.part_number, part.clone());
+        Ok(())
+    }
+
+    async fn list_parts(&self, upload_id: &str) -> StorageResult<Vec<UploadedPart>> {
+        let uploads = self.uploads.read().await;
+        Ok(uploads
+            .get(upload_id)
+            .map(|entry| entry.parts.values().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    async fn delete_upload(&self, upload_id: &str) -> StorageResult<()> {
+        self.uploads.write().await.remove(upload_id);
+        Ok(())
+    }
+}
@@ -1,19 +1,24 @@
 use async_trait::async_trait;
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::Bound;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 use crate::{
     domain::{
         errors::{StorageError, StorageResult},
-        models::{ObjectMetadata, ObjectVersionInfo, ObjectVersionList},
+        models::{
+            Filter, ObjectMetadata, ObjectVersionInfo, ObjectVersionList, ObjectVersionPage,
+            TextQuery, VersionOrder,
+        },
         value_objects::{ObjectKey, VersionId},
     },
     ports::repositories::ObjectRepository,
 };
 
 /// In-memory implementation of ObjectRepository for testing and development
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct InMemoryObjectRepository {
     data: Arc<RwLock<RepositoryData>>,
 }
@@ -24,6 +29,8 @@ struct RepositoryData {
     objects: HashMap<String, HashMap<String, StoredVersion>>,
     // Track latest version for each object
     latest_versions: HashMap<String, String>,
+    // Secondary indexes over the latest version of each object
+    index: ObjectIndex,
 }
 
 #[derive(Clone)]
@@ -32,6 +39,168 @@ struct StoredVersion {
     deleted: bool,
 }
 
+impl StoredVersion {
+    fn info(&self, version_id: VersionId, is_latest: bool) -> ObjectVersionInfo {
+        ObjectVersionInfo {
+            version_id,
+            last_modified: self.metadata.last_modified,
+            size: self.metadata.content_length,
+            etag: self.metadata.etag.clone(),
+            is_latest,
+            deleted: self.deleted,
+            labels: self.metadata.labels(),
+            annotations: self.metadata.annotations(),
+        }
+    }
+}
+
+impl RepositoryData {
+    /// Bring the indexes up to date with the latest live version of `key`
+    fn reindex(&mut self, key: &str) {
+        self.index.remove(key);
+        let latest = self
+            .latest_versions
+            .get(key)
+            .and_then(|version| self.objects.get(key)?.get(version))
+            .filter(|stored| !stored.deleted);
+        if let Some(stored) = latest {
+            self.index.insert(key, &stored.metadata);
+        }
+    }
+}
+
+/// Indexes answering prefix, tag and size queries without scanning every object
+///
+/// Tags are the custom metadata of the latest version.
+#[derive(Default)]
+struct ObjectIndex {
+    keys: BTreeSet<String>,
+    by_tag: HashMap<(String, String), BTreeSet<String>>,
+    by_size: BTreeMap<u64, BTreeSet<String>>,
+    entries: HashMap<String, IndexEntry>,
+}
+
+struct IndexEntry {
+    size: u64,
+    tags: HashMap<String, String>,
+    text: String,
+}
+
+impl ObjectIndex {
+    fn insert(&mut self, key: &str, metadata: &ObjectMetadata) {
+        self.keys.insert(key.to_string());
+        for (name, value) in &metadata.custom_metadata {
+            self.by_tag
+                .entry((name.clone(), value.clone()))
+                .or_default()
+                .insert(key.to_string());
+        }
+        self.by_size
+            .entry(metadata.content_length)
+            .or_default()
+            .insert(key.to_string());
+        self.entries.insert(
+            key.to_string(),
+            IndexEntry {
+                size: metadata.content_length,
+                tags: metadata.custom_metadata.clone(),
+                text: TextQuery::searchable_text(key, &metadata.custom_metadata),
+            },
+        );
+    }
+
+    fn remove(&mut self, key: &str) {
+        let Some(entry) = self.entries.remove(key) else {
+            return;
+        };
+        self.keys.remove(key);
+        for tag in entry.tags {
+            if let Some(keys) = self.by_tag.get_mut(&tag) {
+                keys.remove(key);
+                if keys.is_empty() {
+                    self.by_tag.remove(&tag);
+                }
+            }
+        }
+        if let Some(keys) = self.by_size.get_mut(&entry.size) {
+            keys.remove(key);
+            if keys.is_empty() {
+                self.by_size.remove(&entry.size);
+            }
+        }
+    }
+
+    fn keys_with_prefix(&self, prefix: &str) -> impl Iterator<Item = &String> + '_ {
+        let prefix = prefix.to_string();
+        self.keys
+            .range::<str, _>((Bound::Included(prefix.as_str()), Bound::Unbounded))
+            .take_while(move |key| key.starts_with(&prefix))
+    }
+
+    /// Keys starting with `prefix` that sort after `start_after`, in key order
+    fn keys_with_prefix_after<'a>(
+        &'a self,
+        prefix: &str,
+        start_after: &str,
+    ) -> impl Iterator<Item = &'a String> + 'a {
+        let prefix = prefix.to_string();
+        let lower = match start_after >= prefix.as_str() {
+            true => Bound::Excluded(start_after.to_string()),
+            false => Bound::Included(prefix.clone()),
+        };
+        self.keys
+            .range((lower, Bound::Unbounded))
+            .take_while(move |key| key.starts_with(&prefix))
+    }
+
+    /// Keys matching `filter`, in key order
+    fn query(&self, filter: &Filter) -> Vec<&String> {
+        let prefix = filter.prefix.as_deref().unwrap_or("");
+        let lower = filter.object_size_greater_than;
+        let upper = filter.object_size_less_than;
+
+        // Narrow the candidates with the most selective index, then check the rest
+        let candidates: Vec<&String> = if !filter.tags.is_empty() {
+            let smallest = filter
+                .tags
+                .iter()
+                .map(|(name, value)| self.by_tag.get(&(name.clone(), value.clone())))
+                .min_by_key(|keys| keys.map_or(0, BTreeSet::len));
+            match smallest.flatten() {
+                Some(keys) => keys.iter().collect(),
+                None => return Vec::new(),
+            }
+        } else if lower.is_some() || upper.is_some() {
+            if let (Some(lower), Some(upper)) = (lower, upper) {
+                if lower >= upper {
+                    return Vec::new();
+                }
+            }
+            let range = (
+                lower.map_or(Bound::Unbounded, Bound::Excluded),
+                upper.map_or(Bound::Unbounded, Bound::Excluded),
+            );
+            let mut keys: Vec<&String> = self
+                .by_size
+                .range(range)
+                .flat_map(|(_, keys)| keys)
+                .collect();
+            keys.sort();
+            keys
+        } else {
+            self.keys_with_prefix(prefix).collect()
+        };
+
+        candidates
+            .into_iter()
+            .filter(|key| {
+                let entry = &self.entries[key.as_str()];
+                filter.matches(key, &entry.tags, entry.size)
+            })
+            .collect()
+    }
+}
+
 impl InMemoryObjectRepository {
     pub fn new() -> Self {
         Self {
@@ -69,11 +238,36 @@ impl ObjectRepository for InMemoryObjectRepository {
         );
 
         // Update latest version
-        data.latest_versions.insert(key_str, version_str);
+        data.latest_versions.insert(key_str.clone(), version_str);
+        data.reindex(&key_str);
 
         Ok(())
     }
 
+    async fn save_object_versions(
+        &self,
+        versions: &[(ObjectKey, VersionId, ObjectMetadata)],
+    ) -> StorageResult<()> {
+        // One write lock covers every version, so readers see all or none
+        let mut data = self.data.write().await;
+        for (key, version_id, metadata) in versions {
+            data.objects
+                .entry(key.as_str().to_string())
+                .or_default()
+                .insert(
+                    version_id.as_str().to_string(),
+                    StoredVersion {
+                        metadata: metadata.clone(),
+                        deleted: false,
+                    },
+                );
+            data.latest_versions
+                .insert(key.as_str().to_string(), version_id.as_str().to_string());
+            data.reindex(key.as_str());
+        }
+        Ok(())
+    }
+
     async fn get_object_metadata(
         &self,
         key: &ObjectKey,
@@ -113,13 +307,11 @@ impl ObjectRepository for InMemoryObjectRepository {
             .map(|versions| {
                 versions
                     .iter()
-                    .map(|(version_id, stored)| ObjectVersionInfo {
-                        version_id: VersionId::new(version_id.clone()).unwrap(),
-                        last_modified: stored.metadata.last_modified,
-                        size: stored.metadata.content_length,
-                        etag: stored.metadata.etag.clone(),
-                        is_latest: data.latest_versions.get(key_str) == Some(version_id),
-                        deleted: stored.deleted,
+                    .map(|(version_id, stored)| {
+                        stored.info(
+                            VersionId::new(version_id.clone()).unwrap(),
+                            data.latest_versions.get(key_str) == Some(version_id),
+                        )
                     })
                     .collect()
             })
@@ -131,6 +323,70 @@ impl ObjectRepository for InMemoryObjectRepository {
         })
     }
 
+    async fn list_object_versions_page(
+        &self,
+        key: &ObjectKey,
+        marker: Option<&VersionId>,
+        limit: usize,
+        order: VersionOrder,
+    ) -> StorageResult<Option<ObjectVersionPage>> {
+        let data = self.data.read().await;
+        let latest = data.latest_versions.get(key.as_str());
+        let mut versions: Vec<(&String, &StoredVersion)> = data
+            .objects
+            .get(key.as_str())
+            .map(|versions| versions.iter().collect())
+            .unwrap_or_default();
+
+        // Only the versions on the page are copied out
+        let start = match order {
+            VersionOrder::WriteTime => {
+                versions.sort_by_key(|(version_id, stored)| {
+                    Reverse((
+                        latest == Some(*version_id),
+                        stored.metadata.last_modified,
+                        version_id.as_str(),
+                    ))
+                });
+                match marker {
+                    Some(marker) => {
+                        let Some(position) = versions
+                            .iter()
+                            .position(|(version_id, _)| version_id.as_str() == marker.as_str())
+                        else {
+                            return Ok(None);
+                        };
+                        position + 1
+                    }
+                    None => 0,
+                }
+            }
+            VersionOrder::VersionId => {
+                versions.sort_by_key(|(version_id, _)| Reverse(version_id.as_str()));
+                marker.map_or(0, |marker| {
+                    versions
+                        .partition_point(|(version_id, _)| version_id.as_str() >= marker.as_str())
+                })
+            }
+        };
+
+        let page = versions
+            .iter()
+            .skip(start)
+            .take(limit)
+            .filter_map(|(version_id, stored)| {
+                let is_latest = latest == Some(*version_id);
+                Some(stored.info(VersionId::new((*version_id).clone()).ok()?, is_latest))
+            })
+            .collect();
+        Ok(Some(ObjectVersionPage::new(
+            key.clone(),
+            page,
+            start,
+            versions.len(),
+        )))
+    }
+
     async fn get_version_info(
         &self,
         key: &ObjectKey,
@@ -151,6 +407,8 @@ impl ObjectRepository for InMemoryObjectRepository {
                 etag: stored.metadata.etag.clone(),
                 is_latest: data.latest_versions.get(key_str) == Some(&version_str.to_string()),
                 deleted: stored.deleted,
+                labels: stored.metadata.labels(),
+                annotations: stored.metadata.annotations(),
             }))
     }
 
@@ -166,6 +424,7 @@ impl ObjectRepository for InMemoryObjectRepository {
         if let Some(versions) = data.objects.get_mut(key_str) {
             if let Some(stored) = versions.get_mut(version_str) {
                 stored.deleted = true;
+                data.reindex(key_str);
                 return Ok(());
             }
         }
@@ -187,32 +446,30 @@ impl ObjectRepository for InMemoryObjectRepository {
 
         let should_update_latest =
             data.latest_versions.get(key_str) == Some(&version_str.to_string());
-        let mut should_remove_object = false;
-        let mut new_latest: Option<String> = None;
-
-        if let Some(versions) = data.objects.get_mut(key_str) {
-            if versions.remove(version_str).is_some() {
-                // Determine new latest version if needed
-                if should_update_latest {
-                    new_latest = versions
-                        .iter()
-                        .filter(|(_, v)| !v.deleted)
-                        .max_by_key(|(_, v)| v.metadata.last_modified)
-                        .map(|(k, _)| k.clone());
-                }
-
-                // Check if object should be removed
-                should_remove_object = versions.is_empty();
-            } else {
-                return Err(StorageError::VersionNotFound {
-                    key: key.clone(),
-                    version_id: version_id.clone(),
-                });
-            }
-        } else {
+        let Some(versions) = data.objects.get_mut(key_str) else {
             return Err(StorageError::ObjectNotFound { key: key.clone() });
+        };
+        if versions.remove(version_str).is_none() {
+            return Err(StorageError::VersionNotFound {
+                key: key.clone(),
+                version_id: version_id.clone(),
+            });
         }
 
+        // Determine new latest version if needed
+        let new_latest = should_update_latest
+            .then(|| {
+                versions
+                    .iter()
+                    .filter(|(_, v)| !v.deleted)
+                    .max_by_key(|(_, v)| v.metadata.last_modified)
+                    .map(|(k, _)| k.clone())
+            })
+            .flatten();
+
+        // Check if object should be removed
+        let should_remove_object = versions.is_empty();
+
         // Update latest version tracking
         if should_update_latest {
             match new_latest {
@@ -230,6 +487,7 @@ impl ObjectRepository for InMemoryObjectRepository {
             data.objects.remove(key_str);
             data.latest_versions.remove(key_str);
         }
+        data.reindex(key_str);
 
         Ok(())
     }
@@ -251,22 +509,98 @@ impl ObjectRepository for InMemoryObjectRepository {
     ) -> StorageResult<Vec<ObjectKey>> {
         let data = self.data.read().await;
 
-        let mut keys: Vec<ObjectKey> = data
-            .objects
-            .keys()
-            .filter(|k| k.starts_with(prefix))
+        // The index is sorted, so the keys come out in order
+        Ok(data
+            .index
+            .keys_with_prefix(prefix)
             .filter_map(|k| ObjectKey::new(k.clone()).ok())
-            .collect();
+            .take(max_results.unwrap_or(usize::MAX))
+            .collect())
+    }
+
+    async fn list_delete_markers(&self, prefix: &str) -> StorageResult<Vec<ObjectKey>> {
+        let data = self.data.read().await;
 
-        // Sort for consistent results
+        let mut keys: Vec<ObjectKey> = data
+            .latest_versions
+            .iter()
+            .filter(|(key, version)| {
+                key.starts_with(prefix)
+                    && data
+                        .objects
+                        .get(*key)
+                        .and_then(|versions| versions.get(*version))
+                        .is_some_and(|stored| stored.deleted)
+            })
+            .filter_map(|(key, _)| ObjectKey::new(key.clone()).ok())
+            .collect();
         keys.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        Ok(keys)
+    }
 
-        // Apply limit if specified
-        if let Some(max) = max_results {
-            keys.truncate(max);
-        }
+    async fn list_latest_versions(
+        &self,
+        prefix: &str,
+        start_after: Option<&str>,
+        max_results: Option<usize>,
+    ) -> StorageResult<Vec<(ObjectKey, ObjectVersionInfo)>> {
+        let data = self.data.read().await;
 
-        Ok(keys)
+        // Indexed keys have a live latest version
+        Ok(data
+            .index
+            .keys_with_prefix_after(prefix, start_after.unwrap_or(""))
+            .filter_map(|key| {
+                let version_id = data.latest_versions.get(key)?;
+                let stored = data.objects.get(key)?.get(version_id)?;
+                let info = ObjectVersionInfo {
+                    version_id: VersionId::new(version_id.clone()).ok()?,
+                    last_modified: stored.metadata.last_modified,
+                    size: stored.metadata.content_length,
+                    etag: stored.metadata.etag.clone(),
+                    is_latest: true,
+                    deleted: false,
+                    labels: stored.metadata.labels(),
+                    annotations: stored.metadata.annotations(),
+                };
+                Some((ObjectKey::new(key.clone()).ok()?, info))
+            })
+            .take(max_results.unwrap_or(usize::MAX))
+            .collect())
+    }
+
+    async fn find_objects(
+        &self,
+        filter: &Filter,
+        max_results: Option<usize>,
+    ) -> StorageResult<Vec<ObjectKey>> {
+        let data = self.data.read().await;
+
+        Ok(data
+            .index
+            .query(filter)
+            .into_iter()
+            .filter_map(|k| ObjectKey::new(k.clone()).ok())
+            .take(max_results.unwrap_or(usize::MAX))
+            .collect())
+    }
+
+    async fn search_objects(
+        &self,
+        query: &TextQuery,
+        filter: &Filter,
+        max_results: Option<usize>,
+    ) -> StorageResult<Vec<ObjectKey>> {
+        let data = self.data.read().await;
+
+        Ok(data
+            .index
+            .query(filter)
+            .into_iter()
+            .filter(|k| query.matches_text(&data.index.entries[k.as_str()].text))
+            .filter_map(|k| ObjectKey::new(k.clone()).ok())
+            .take(max_results.unwrap_or(usize::MAX))
+            .collect())
     }
 
     async fn update_object_metadata(
@@ -282,6 +616,7 @@ impl ObjectRepository for InMemoryObjectRepository {
         if let Some(versions) = data.objects.get_mut(key_str) {
             if let Some(stored) = versions.get_mut(version_str) {
                 stored.metadata = metadata.clone();
+                data.reindex(key_str);
                 return Ok(());
             }
         }
@@ -292,13 +627,259 @@ impl ObjectRepository for InMemoryObjectRepository {
         })
     }
 
+    async fn compare_and_set_object_metadata(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+        expected: &ObjectMetadata,
+        metadata: &ObjectMetadata,
+    ) -> StorageResult<()> {
+        let mut data = self.data.write().await;
+        let key_str = key.as_str();
+
+        let Some(stored) = data
+            .objects
+            .get_mut(key_str)
+            .and_then(|versions| versions.get_mut(version_id.as_str()))
+            .filter(|stored| !stored.deleted)
+        else {
+            return Err(StorageError::VersionNotFound {
+                key: key.clone(),
+                version_id: version_id.clone(),
+            });
+        };
+        if stored.metadata != *expected {
+            return Err(StorageError::VersionConflict {
+                key: key.clone(),
+                expected_version: Some(version_id.clone()),
+                actual_version: Some(version_id.clone()),
+            });
+        }
+        stored.metadata = metadata.clone();
+        data.reindex(key_str);
+        Ok(())
+    }
+
     async fn object_exists(&self, key: &ObjectKey) -> StorageResult<bool> {
         let data = self.data.read().await;
-        let key_str = key.as_str();
 
-        Ok(
-            data.objects.contains_key(key_str)
-                && data.objects[key_str].values().any(|v| !v.deleted),
+        // Only the latest version counts, so a delete marker hides the object
+        Ok(data.index.entries.contains_key(key.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(size: u64, tags: &[(&str, &str)]) -> ObjectMetadata {
+        ObjectMetadata {
+            content_type: None,
+            content_length: size,
+            etag: None,
+            last_modified: std::time::SystemTime::now(),
+            custom_metadata: tags
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+
+    async fn save(repo: &InMemoryObjectRepository, key: &str, version: &str, meta: ObjectMetadata) {
+        repo.save_object_metadata(
+            &ObjectKey::new(key.to_string()).unwrap(),
+            &VersionId::new(version.to_string()).unwrap(),
+            &meta,
+        )
+        .await
+        .unwrap();
+    }
+
+    async fn find(repo: &InMemoryObjectRepository, filter: Filter) -> Vec<String> {
+        repo.find_objects(&filter, None)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|key| key.as_str().to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_find_objects_uses_latest_versions() {
+        let repo = InMemoryObjectRepository::new();
+        save(&repo, "logs/a.log", "v1", metadata(10, &[("team", "ops")])).await;
+        save(&repo, "logs/b.log", "v1", metadata(500, &[("team", "web")])).await;
+        save(
+            &repo,
+            "data/c.bin",
+            "v1",
+            metadata(5000, &[("team", "ops")]),
+        )
+        .await;
+
+        let prefix = Filter::new().with_prefix("logs/".to_string());
+        assert_eq!(find(&repo, prefix).await, ["logs/a.log", "logs/b.log"]);
+
+        let tags = HashMap::from([("team".to_string(), "ops".to_string())]);
+        let ops = Filter::new().with_tags(tags.clone());
+        assert_eq!(find(&repo, ops.clone()).await, ["data/c.bin", "logs/a.log"]);
+
+        let mid_sized = Filter::new().with_size_constraints(Some(10), Some(5000));
+        assert_eq!(find(&repo, mid_sized).await, ["logs/b.log"]);
+
+        // A new version moves the object out of the old tag and size entries
+        save(&repo, "logs/a.log", "v2", metadata(20, &[("team", "web")])).await;
+        assert_eq!(find(&repo, ops.clone()).await, ["data/c.bin"]);
+
+        // Text search sees the new version's metadata alongside the key
+        let found = repo
+            .search_objects(&TextQuery::new("WEB a.log"), &Filter::new(), None)
+            .await
+            .unwrap();
+        assert_eq!(found, [ObjectKey::new("logs/a.log".to_string()).unwrap()]);
+
+        // Deleted objects drop out of every index
+        repo.mark_version_deleted(
+            &ObjectKey::new("data/c.bin".to_string()).unwrap(),
+            &VersionId::new("v1".to_string()).unwrap(),
         )
+        .await
+        .unwrap();
+        assert!(find(&repo, ops).await.is_empty());
+        assert_eq!(
+            repo.list_objects_by_prefix("", Some(1)).await.unwrap()[0].as_str(),
+            "logs/a.log"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_latest_versions_pages_through_live_objects() {
+        let repo = InMemoryObjectRepository::new();
+        for key in ["logs/a.log", "logs/b.log", "logs/c.log", "data/d.bin"] {
+            save(&repo, key, "v1", metadata(1, &[])).await;
+        }
+        save(&repo, "logs/b.log", "v2", metadata(2, &[])).await;
+        repo.mark_version_deleted(
+            &ObjectKey::new("logs/c.log".to_string()).unwrap(),
+            &VersionId::new("v1".to_string()).unwrap(),
+        )
+        .await
+        .unwrap();
+
+        let first = repo
+            .list_latest_versions("logs/", None, Some(1))
+            .await
+            .unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].0.as_str(), "logs/a.log");
+
+        let rest = repo
+            .list_latest_versions("logs/", Some("logs/a.log"), None)
+            .await
+            .unwrap();
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest[0].0.as_str(), "logs/b.log");
+        assert_eq!(rest[0].1.version_id.as_str(), "v2");
+        assert_eq!(rest[0].1.size, 2);
+
+        // A start key before the prefix lists the whole prefix
+        let all = repo
+            .list_latest_versions("logs/", Some("data/d.bin"), None)
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_version_pages_match_full_listing() {
+        let repo = InMemoryObjectRepository::new();
+        let key = ObjectKey::new("docs/a.txt".to_string()).unwrap();
+        // Written out of ID order, so the two orders differ
+        for (version, secs) in [("v3", 1), ("v1", 2), ("v4", 3), ("v2", 4)] {
+            let mut meta = metadata(secs, &[]);
+            meta.last_modified = std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs);
+            save(&repo, key.as_str(), version, meta).await;
+        }
+
+        for order in [VersionOrder::WriteTime, VersionOrder::VersionId] {
+            let mut marker = None;
+            let mut listed = Vec::new();
+            loop {
+                let page = repo
+                    .list_object_versions_page(&key, marker.as_ref(), 3, order)
+                    .await
+                    .unwrap()
+                    .unwrap();
+                let expected = repo
+                    .list_object_versions(&key)
+                    .await
+                    .unwrap()
+                    .paginate_in(order, marker.as_ref(), 3)
+                    .unwrap();
+                assert_eq!(page.versions, expected.versions);
+                assert_eq!(page.total_count, 4);
+                listed.extend(page.versions.into_iter().map(|v| v.version_id));
+                marker = page.next_version_id_marker;
+                if marker.is_none() {
+                    break;
+                }
+            }
+            assert_eq!(listed.len(), 4, "{:?}", order);
+        }
+
+        let unknown = VersionId::new("v9".to_string()).unwrap();
+        let page = repo
+            .list_object_versions_page(&key, Some(&unknown), 3, VersionOrder::WriteTime)
+            .await
+            .unwrap();
+        assert!(page.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_compare_and_set_rejects_stale_metadata() {
+        let repo = InMemoryObjectRepository::new();
+        save(&repo, "a.txt", "v1", metadata(10, &[])).await;
+        let key = ObjectKey::new("a.txt".to_string()).unwrap();
+        let version = VersionId::new("v1".to_string()).unwrap();
+        let read = repo
+            .get_object_metadata(&key, Some(&version))
+            .await
+            .unwrap()
+            .unwrap();
+
+        // Another instance labels the version after it was read here
+        let labelled = metadata(10, &[("label", "release")]);
+        repo.compare_and_set_object_metadata(&key, &version, &read, &labelled)
+            .await
+            .unwrap();
+        let pinned = metadata(10, &[("pinned", "true")]);
+        let result = repo
+            .compare_and_set_object_metadata(&key, &version, &read, &pinned)
+            .await;
+        assert!(matches!(result, Err(StorageError::VersionConflict { .. })));
+
+        // Modifying reads the metadata afresh, keeping the other update
+        let modified = repo
+            .modify_object_metadata(&key, &version, &|metadata| {
+                metadata
+                    .custom_metadata
+                    .insert("pinned".to_string(), "true".to_string());
+                Ok(())
+            })
+            .await
+            .unwrap();
+        assert_eq!(modified.custom_metadata.len(), 2);
+        let stored = repo
+            .get_object_metadata(&key, Some(&version))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored, modified);
+
+        let missing = VersionId::new("v2".to_string()).unwrap();
+        let result = repo
+            .compare_and_set_object_metadata(&key, &missing, &read, &pinned)
+            .await;
+        assert!(matches!(result, Err(StorageError::VersionNotFound { .. })));
     }
 }
@@ -0,0 +1,83 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::{
+    domain::{
+        errors::{StorageError, StorageResult},
+        models::{ObjectTransaction, StagedObject},
+    },
+    ports::repositories::TransactionRepository,
+};
+
+/// In-memory implementation of TransactionRepository for testing and development
+#[derive(Clone, Default)]
+pub struct InMemoryTransactionRepository {
+    transactions: Arc<RwLock<HashMap<String, ObjectTransaction>>>,
+}
+
+impl InMemoryTransactionRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+fn not_recorded(transaction_id: &str) -> StorageError {
+    StorageError::InternalError {
+        message: format!("Transaction '{}' is not recorded", transaction_id),
+    }
+}
+
+#[async_trait]
+impl TransactionRepository for InMemoryTransactionRepository {
+    async fn create_transaction(&self, transaction: &ObjectTransaction) -> StorageResult<()> {
+        self.transactions
+            .write()
+            .await
+            .insert(transaction.transaction_id.clone(), transaction.clone());
+        Ok(())
+    }
+
+    async fn get_transaction(
+        &self,
+        transaction_id: &str,
+    ) -> StorageResult<Option<ObjectTransaction>> {
+        Ok(self.transactions.read().await.get(transaction_id).cloned())
+    }
+
+    async fn save_staged_object(
+        &self,
+        transaction_id: &str,
+        object: &StagedObject,
+    ) -> StorageResult<()> {
+        let mut transactions = self.transactions.write().await;
+        let transaction = transactions
+            .get_mut(transaction_id)
+            .ok_or_else(|| not_recorded(transaction_id))?;
+        transaction
+            .objects
+            .retain(|staged| staged.key != object.key);
+        transaction.objects.push(object.clone());
+        Ok(())
+    }
+
+    async fn mark_committed(
+        &self,
+        transaction_id: &str,
+        committed_at: DateTime<Utc>,
+    ) -> StorageResult<()> {
+        let mut transactions = self.transactions.write().await;
+        let transaction = transactions
+            .get_mut(transaction_id)
+            .ok_or_else(|| not_recorded(transaction_id))?;
+        transaction.committed_at = Some(committed_at);
+        Ok(())
+    }
+
+    async fn delete_transaction(&self, transaction_id: &str) -> StorageResult<()> {
+        self.transactions.write().await.remove(transaction_id);
+        Ok(())
+    }
+}
@@ -1,9 +1,37 @@
+mod bloom_filter_object_repository;
+mod caching_lifecycle_repository;
+mod caching_object_repository;
+mod database_pools;
+mod in_memory_audit_log_repository;
+mod in_memory_backup_repository;
+mod in_memory_lease_repository;
 mod in_memory_lifecycle_repository;
+mod in_memory_multipart_upload_repository;
 mod in_memory_object_repository;
+mod in_memory_transaction_repository;
+mod object_store_lifecycle_repository;
+mod sharded_lifecycle_repository;
+mod sharded_object_repository;
+mod sql_lease_repository;
 mod sql_lifecycle_repository;
 mod sql_object_repository;
 
+pub use bloom_filter_object_repository::BloomFilterObjectRepository;
+pub use caching_lifecycle_repository::CachingLifecycleRepository;
+pub use caching_object_repository::CachingObjectRepository;
+pub use database_pools::DatabasePools;
+pub use in_memory_audit_log_repository::InMemoryAuditLogRepository;
+pub use in_memory_backup_repository::InMemoryBackupRepository;
+pub use in_memory_lease_repository::InMemoryLeaseRepository;
 pub use in_memory_lifecycle_repository::InMemoryLifecycleRepository;
+pub use in_memory_multipart_upload_repository::InMemoryMultipartUploadRepository;
 pub use in_memory_object_repository::InMemoryObjectRepository;
+pub use in_memory_transaction_repository::InMemoryTransactionRepository;
+pub use object_store_lifecycle_repository::{
+    LIFECYCLE_CONFIG_PREFIX, ObjectStoreLifecycleRepository,
+};
+pub use sharded_lifecycle_repository::ShardedLifecycleRepository;
+pub use sharded_object_repository::{ShardedObjectRepository, bucket_shard};
+pub use sql_lease_repository::SqlLeaseRepository;
 pub use sql_lifecycle_repository::SqlLifecycleRepository;
 pub use sql_object_repository::SqlObjectRepository;
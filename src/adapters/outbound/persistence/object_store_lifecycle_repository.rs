@@ -0,0 +1,452 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::{
+    domain::{
+        errors::{LifecycleError, LifecycleResult, StorageError},
+        models::{
+            Filter, LifecycleConfiguration, LifecycleRule, LifecycleStorageClass as StorageClass,
+            RuleStatus,
+        },
+        value_objects::{BucketName, ObjectKey},
+    },
+    ports::{repositories::LifecycleRepository, storage::ObjectStore},
+};
+
+/// Prefix in the store under which each bucket's configuration is kept
+pub const LIFECYCLE_CONFIG_PREFIX: &str = ".config/lifecycle/";
+
+/// Prefix in the store under which each bucket's last processed time is kept
+const LAST_PROCESSED_PREFIX: &str = ".config/lifecycle-processed/";
+
+/// Implementation of LifecycleRepository keeping configurations as JSON
+/// objects in the backing store itself
+///
+/// Each bucket's configuration is stored at `.config/lifecycle/<bucket>.json`,
+/// outside of every bucket's prefix, so it is never listed as an object or
+/// matched by a rule, and survives restarts wherever the objects do.
+#[derive(Clone)]
+pub struct ObjectStoreLifecycleRepository {
+    store: Arc<dyn ObjectStore>,
+    // Serializes read-modify-write of a configuration by this process
+    write_lock: Arc<Mutex<()>>,
+}
+
+impl ObjectStoreLifecycleRepository {
+    pub fn new(store: Arc<dyn ObjectStore>) -> Self {
+        Self {
+            store,
+            write_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    fn config_key(bucket: &BucketName) -> LifecycleResult<ObjectKey> {
+        Self::key(format!(
+            "{}{}.json",
+            LIFECYCLE_CONFIG_PREFIX,
+            bucket.as_str()
+        ))
+    }
+
+    fn last_processed_key(bucket: &BucketName) -> LifecycleResult<ObjectKey> {
+        Self::key(format!("{}{}", LAST_PROCESSED_PREFIX, bucket.as_str()))
+    }
+
+    fn key(key: String) -> LifecycleResult<ObjectKey> {
+        ObjectKey::new(key).map_err(|e| LifecycleError::RepositoryError {
+            message: e.to_string(),
+        })
+    }
+
+    /// Read an object, or `None` if it does not exist
+    async fn read(&self, key: &ObjectKey) -> LifecycleResult<Option<Bytes>> {
+        match self.store.get_object(key).await {
+            Ok(data) => Ok(Some(data)),
+            Err(StorageError::ObjectNotFound { .. }) => Ok(None),
+            Err(e) => Err(repository_error(e)),
+        }
+    }
+
+    async fn delete(&self, key: &ObjectKey) -> LifecycleResult<()> {
+        match self.store.delete_object(key).await {
+            Ok(()) | Err(StorageError::ObjectNotFound { .. }) => Ok(()),
+            Err(e) => Err(repository_error(e)),
+        }
+    }
+
+    async fn write_configuration(
+        &self,
+        bucket: &BucketName,
+        config: &LifecycleConfiguration,
+    ) -> LifecycleResult<()> {
+        let data = serde_json::to_vec_pretty(&StoredConfiguration::from(config))
+            .map_err(repository_error)?;
+        self.store
+            .put_object(
+                &Self::config_key(bucket)?,
+                Bytes::from(data),
+                Some("application/json"),
+            )
+            .await
+            .map_err(repository_error)?;
+        Ok(())
+    }
+}
+
+fn repository_error(e: impl std::fmt::Display) -> LifecycleError {
+    LifecycleError::RepositoryError {
+        message: e.to_string(),
+    }
+}
+
+#[async_trait]
+impl LifecycleRepository for ObjectStoreLifecycleRepository {
+    async fn save_configuration(
+        &self,
+        bucket: &BucketName,
+        config: &LifecycleConfiguration,
+    ) -> LifecycleResult<()> {
+        // Validate configuration before saving
+        config.validate().map_err(|e| LifecycleError::InvalidRule {
+            rule_id: String::new(),
+            reason: e.to_string(),
+        })?;
+
+        let _guard = self.write_lock.lock().await;
+        self.write_configuration(bucket, config).await
+    }
+
+    async fn get_configuration(
+        &self,
+        bucket: &BucketName,
+    ) -> LifecycleResult<Option<LifecycleConfiguration>> {
+        let Some(data) = self.read(&Self::config_key(bucket)?).await? else {
+            return Ok(None);
+        };
+
+        let stored: StoredConfiguration =
+            serde_json::from_slice(&data).map_err(repository_error)?;
+        Ok(Some(stored.into_configuration(bucket.clone())))
+    }
+
+    async fn delete_configuration(&self, bucket: &BucketName) -> LifecycleResult<()> {
+        let _guard = self.write_lock.lock().await;
+        self.delete(&Self::config_key(bucket)?).await?;
+        self.delete(&Self::last_processed_key(bucket)?).await
+    }
+
+    async fn configuration_exists(&self, bucket: &BucketName) -> LifecycleResult<bool> {
+        self.store
+            .object_exists(&Self::config_key(bucket)?)
+            .await
+            .map_err(repository_error)
+    }
+
+    async fn get_rule(
+        &self,
+        bucket: &BucketName,
+        rule_id: &str,
+    ) -> LifecycleResult<Option<LifecycleRule>> {
+        Ok(self
+            .get_configuration(bucket)
+            .await?
+            .and_then(|config| config.rules.into_iter().find(|rule| rule.id == rule_id)))
+    }
+
+    async fn update_rule(&self, bucket: &BucketName, rule: &LifecycleRule) -> LifecycleResult<()> {
+        let _guard = self.write_lock.lock().await;
+
+        let mut config = self.get_configuration(bucket).await?.ok_or_else(|| {
+            LifecycleError::ConfigurationNotFound {
+                bucket: bucket.clone(),
+            }
+        })?;
+
+        let existing_rule = config
+            .rules
+            .iter_mut()
+            .find(|existing_rule| existing_rule.id == rule.id)
+            .ok_or_else(|| LifecycleError::InvalidRule {
+                rule_id: rule.id.clone(),
+                reason: "Rule not found".to_string(),
+            })?;
+        *existing_rule = rule.clone();
+
+        // Validate the updated configuration
+        config.validate().map_err(|e| LifecycleError::InvalidRule {
+            rule_id: rule.id.clone(),
+            reason: e.to_string(),
+        })?;
+
+        self.write_configuration(bucket, &config).await
+    }
+
+    async fn list_configured_buckets(&self) -> LifecycleResult<Vec<BucketName>> {
+        let filter = Filter::new().with_prefix(LIFECYCLE_CONFIG_PREFIX.to_string());
+        let items = self
+            .store
+            .list_objects(&filter)
+            .await
+            .map_err(repository_error)?;
+
+        let mut buckets: Vec<BucketName> = items
+            .iter()
+            .filter_map(|item| {
+                item.key
+                    .as_str()
+                    .strip_prefix(LIFECYCLE_CONFIG_PREFIX)?
+                    .strip_suffix(".json")
+            })
+            .filter_map(|name| BucketName::new(name.to_string()).ok())
+            .collect();
+        buckets.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+
+        Ok(buckets)
+    }
+
+    async fn get_last_processed_time(
+        &self,
+        bucket: &BucketName,
+    ) -> LifecycleResult<Option<std::time::SystemTime>> {
+        let Some(data) = self.read(&Self::last_processed_key(bucket)?).await? else {
+            return Ok(None);
+        };
+
+        let time = DateTime::parse_from_rfc3339(String::from_utf8_lossy(&data).trim())
+            .map_err(repository_error)?;
+        Ok(Some(time.with_timezone(&Utc).into()))
+    }
+
+    async fn set_last_processed_time(
+        &self,
+        bucket: &BucketName,
+        time: std::time::SystemTime,
+    ) -> LifecycleResult<()> {
+        let time = DateTime::<Utc>::from(time).to_rfc3339();
+        self.store
+            .put_object(
+                &Self::last_processed_key(bucket)?,
+                Bytes::from(time),
+                Some("text/plain"),
+            )
+            .await
+            .map_err(repository_error)?;
+        Ok(())
+    }
+}
+
+/// Stored form of a lifecycle configuration, shared with
+/// [`SqlLifecycleRepository`](super::SqlLifecycleRepository)
+#[derive(Serialize, Deserialize)]
+pub(super) struct StoredConfiguration {
+    rules: Vec<StoredRule>,
+}
+
+impl From<&LifecycleConfiguration> for StoredConfiguration {
+    fn from(config: &LifecycleConfiguration) -> Self {
+        Self {
+            rules: config.rules.iter().map(StoredRule::from).collect(),
+        }
+    }
+}
+
+impl StoredConfiguration {
+    pub(super) fn into_configuration(self, bucket: BucketName) -> LifecycleConfiguration {
+        LifecycleConfiguration {
+            bucket,
+            rules: self.rules.into_iter().map(LifecycleRule::from).collect(),
+        }
+    }
+}
+
+/// Stored form of a lifecycle rule, with storage classes by name
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredRule {
+    id: String,
+    enabled: bool,
+    #[serde(default)]
+    prefix: Option<String>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
+    #[serde(default)]
+    object_size_greater_than: Option<u64>,
+    #[serde(default)]
+    object_size_less_than: Option<u64>,
+    #[serde(default)]
+    expiration_days: Option<u32>,
+    #[serde(default)]
+    expiration_date: Option<DateTime<Utc>>,
+    #[serde(default)]
+    expiration_expired_object_delete_marker: Option<bool>,
+    #[serde(default)]
+    expiration_expired_object_all_versions: Option<bool>,
+    #[serde(default)]
+    del_marker_expiration_days: Option<u32>,
+    #[serde(default)]
+    all_versions_expiration_days: Option<u32>,
+    #[serde(default)]
+    all_versions_expiration_delete_marker: Option<bool>,
+    #[serde(default)]
+    transition_days: Option<u32>,
+    #[serde(default)]
+    transition_date: Option<DateTime<Utc>>,
+    #[serde(default)]
+    transition_storage_class: Option<String>,
+    #[serde(default)]
+    noncurrent_version_expiration_noncurrent_days: Option<u32>,
+    #[serde(default)]
+    noncurrent_version_expiration_newer_versions: Option<u32>,
+    #[serde(default)]
+    noncurrent_version_transition_noncurrent_days: Option<u32>,
+    #[serde(default)]
+    noncurrent_version_transition_storage_class: Option<String>,
+    #[serde(default)]
+    noncurrent_version_transition_newer_versions: Option<u32>,
+    #[serde(default)]
+    abort_incomplete_multipart_upload_days_after_initiation: Option<u32>,
+}
+
+impl From<&LifecycleRule> for StoredRule {
+    fn from(rule: &LifecycleRule) -> Self {
+        Self {
+            id: rule.id.clone(),
+            enabled: rule.status == RuleStatus::Enabled,
+            prefix: rule.filter.prefix.clone(),
+            tags: rule.filter.tags.clone(),
+            object_size_greater_than: rule.filter.object_size_greater_than,
+            object_size_less_than: rule.filter.object_size_less_than,
+            expiration_days: rule.expiration_days,
+            expiration_date: rule.expiration_date,
+            expiration_expired_object_delete_marker: rule.expiration_expired_object_delete_marker,
+            expiration_expired_object_all_versions: rule.expiration_expired_object_all_versions,
+            del_marker_expiration_days: rule.del_marker_expiration_days,
+            all_versions_expiration_days: rule.all_versions_expiration_days,
+            all_versions_expiration_delete_marker: rule.all_versions_expiration_delete_marker,
+            transition_days: rule.transition_days,
+            transition_date: rule.transition_date,
+            transition_storage_class: rule
+                .transition_storage_class
+                .as_ref()
+                .map(|class| class.as_str().to_string()),
+            noncurrent_version_expiration_noncurrent_days: rule
+                .noncurrent_version_expiration_noncurrent_days,
+            noncurrent_version_expiration_newer_versions: rule
+                .noncurrent_version_expiration_newer_versions,
+            noncurrent_version_transition_noncurrent_days: rule
+                .noncurrent_version_transition_noncurrent_days,
+            noncurrent_version_transition_storage_class: rule
+                .noncurrent_version_transition_storage_class
+                .as_ref()
+                .map(|class| class.as_str().to_string()),
+            noncurrent_version_transition_newer_versions: rule
+                .noncurrent_version_transition_newer_versions,
+            abort_incomplete_multipart_upload_days_after_initiation: rule
+                .abort_incomplete_multipart_upload_days_after_initiation,
+        }
+    }
+}
+
+impl From<StoredRule> for LifecycleRule {
+    fn from(rule: StoredRule) -> Self {
+        Self {
+            id: rule.id,
+            status: match rule.enabled {
+                true => RuleStatus::Enabled,
+                false => RuleStatus::Disabled,
+            },
+            filter: Filter {
+                prefix: rule.prefix,
+                tags: rule.tags,
+                object_size_greater_than: rule.object_size_greater_than,
+                object_size_less_than: rule.object_size_less_than,
+            },
+            expiration_days: rule.expiration_days,
+            expiration_date: rule.expiration_date,
+            expiration_expired_object_delete_marker: rule.expiration_expired_object_delete_marker,
+            expiration_expired_object_all_versions: rule.expiration_expired_object_all_versions,
+            del_marker_expiration_days: rule.del_marker_expiration_days,
+            all_versions_expiration_days: rule.all_versions_expiration_days,
+            all_versions_expiration_delete_marker: rule.all_versions_expiration_delete_marker,
+            transition_days: rule.transition_days,
+            transition_date: rule.transition_date,
+            transition_storage_class: rule
+                .transition_storage_class
+                .as_deref()
+                .map(StorageClass::from),
+            noncurrent_version_expiration_noncurrent_days: rule
+                .noncurrent_version_expiration_noncurrent_days,
+            noncurrent_version_expiration_newer_versions: rule
+                .noncurrent_version_expiration_newer_versions,
+            noncurrent_version_transition_noncurrent_days: rule
+                .noncurrent_version_transition_noncurrent_days,
+            noncurrent_version_transition_storage_class: rule
+                .noncurrent_version_transition_storage_class
+                .as_deref()
+                .map(StorageClass::from),
+            noncurrent_version_transition_newer_versions: rule
+                .noncurrent_version_transition_newer_versions,
+            abort_incomplete_multipart_upload_days_after_initiation: rule
+                .abort_incomplete_multipart_upload_days_after_initiation,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::outbound::storage::S3ObjectStoreAdapter;
+    use object_store::memory::InMemory;
+
+    #[tokio::test]
+    async fn test_configuration_survives_a_new_repository() {
+        let bucket_name = BucketName::new("store-bucket".to_string()).unwrap();
+        let store: Arc<dyn ObjectStore> = Arc::new(S3ObjectStoreAdapter::new(
+            Arc::new(InMemory::new()),
+            bucket_name,
+        ));
+        let bucket = BucketName::new("test-bucket".to_string()).unwrap();
+
+        let config = LifecycleConfiguration {
+            bucket: bucket.clone(),
+            rules: vec![LifecycleRule {
+                id: "rule1".to_string(),
+                status: RuleStatus::Enabled,
+                filter: Filter::new().with_prefix("test-bucket/logs/".to_string()),
+                expiration_days: Some(30),
+                transition_days: Some(7),
+                transition_storage_class: Some(StorageClass::Glacier),
+                ..Default::default()
+            }],
+        };
+        let processed = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(60);
+
+        let repo = ObjectStoreLifecycleRepository::new(store.clone());
+        repo.save_configuration(&bucket, &config).await.unwrap();
+        repo.set_last_processed_time(&bucket, processed)
+            .await
+            .unwrap();
+
+        // A repository over the same store sees everything written before
+        let repo = ObjectStoreLifecycleRepository::new(store);
+        assert_eq!(repo.get_configuration(&bucket).await.unwrap(), Some(config));
+        assert_eq!(
+            repo.list_configured_buckets().await.unwrap(),
+            vec![bucket.clone()]
+        );
+        assert_eq!(
+            repo.get_last_processed_time(&bucket).await.unwrap(),
+            Some(processed)
+        );
+
+        repo.delete_configuration(&bucket).await.unwrap();
+        assert!(!repo.configuration_exists(&bucket).await.unwrap());
+        assert!(repo.list_configured_buckets().await.unwrap().is_empty());
+        assert_eq!(repo.get_last_processed_time(&bucket).await.unwrap(), None);
+    }
+}
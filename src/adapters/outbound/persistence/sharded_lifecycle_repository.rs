@@ -0,0 +1,111 @@
+use async_trait::async_trait;
+use futures::future::try_join_all;
+use std::sync::Arc;
+
+use crate::{
+    domain::{
+        errors::LifecycleResult,
+        models::{LifecycleConfiguration, LifecycleRule},
+        value_objects::BucketName,
+    },
+    ports::repositories::LifecycleRepository,
+};
+
+use super::sharded_object_repository::bucket_shard;
+
+/// LifecycleRepository partitioning configurations across several repositories
+/// by bucket
+///
+/// Buckets are placed with the same hash as
+/// [`ShardedObjectRepository`](super::ShardedObjectRepository), so with the
+/// same number of shards a bucket's configuration sits on the same shard as
+/// its object metadata.
+#[derive(Clone)]
+pub struct ShardedLifecycleRepository {
+    shards: Vec<Arc<dyn LifecycleRepository>>,
+}
+
+impl ShardedLifecycleRepository {
+    /// Partition configurations across `shards`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` is empty.
+    pub fn new(shards: Vec<Arc<dyn LifecycleRepository>>) -> Self {
+        assert!(!shards.is_empty(), "a sharded repository needs a shard");
+        Self { shards }
+    }
+
+    fn shard(&self, bucket: &BucketName) -> &dyn LifecycleRepository {
+        self.shards[bucket_shard(bucket.as_str(), self.shards.len())].as_ref()
+    }
+}
+
+#[async_trait]
+impl LifecycleRepository for ShardedLifecycleRepository {
+    async fn save_configuration(
+        &self,
+        bucket: &BucketName,
+        config: &LifecycleConfiguration,
+    ) -> LifecycleResult<()> {
+        self.shard(bucket).save_configuration(bucket, config).await
+    }
+
+    async fn get_configuration(
+        &self,
+        bucket: &BucketName,
+    ) -> LifecycleResult<Option<LifecycleConfiguration>> {
+        self.shard(bucket).get_configuration(bucket).await
+    }
+
+    async fn delete_configuration(&self, bucket: &BucketName) -> LifecycleResult<()> {
+        self.shard(bucket).delete_configuration(bucket).await
+    }
+
+    async fn configuration_exists(&self, bucket: &BucketName) -> LifecycleResult<bool> {
+        self.shard(bucket).configuration_exists(bucket).await
+    }
+
+    async fn get_rule(
+        &self,
+        bucket: &BucketName,
+        rule_id: &str,
+    ) -> LifecycleResult<Option<LifecycleRule>> {
+        self.shard(bucket).get_rule(bucket, rule_id).await
+    }
+
+    async fn update_rule(&self, bucket: &BucketName, rule: &LifecycleRule) -> LifecycleResult<()> {
+        self.shard(bucket).update_rule(bucket, rule).await
+    }
+
+    async fn list_configured_buckets(&self) -> LifecycleResult<Vec<BucketName>> {
+        let mut buckets: Vec<BucketName> = try_join_all(
+            self.shards
+                .iter()
+                .map(|shard| shard.list_configured_buckets()),
+        )
+        .await?
+        .into_iter()
+        .flatten()
+        .collect();
+        buckets.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        Ok(buckets)
+    }
+
+    async fn get_last_processed_time(
+        &self,
+        bucket: &BucketName,
+    ) -> LifecycleResult<Option<std::time::SystemTime>> {
+        self.shard(bucket).get_last_processed_time(bucket).await
+    }
+
+    async fn set_last_processed_time(
+        &self,
+        bucket: &BucketName,
+        time: std::time::SystemTime,
+    ) -> LifecycleResult<()> {
+        self.shard(bucket)
+            .set_last_processed_time(bucket, time)
+            .await
+    }
+}
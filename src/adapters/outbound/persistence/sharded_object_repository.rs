@@ -0,0 +1,338 @@
+use async_trait::async_trait;
+use futures::future::try_join_all;
+use std::sync::Arc;
+
+use crate::{
+    domain::{
+        errors::StorageResult,
+        models::{
+            Filter, ObjectMetadata, ObjectVersionInfo, ObjectVersionList, ObjectVersionPage,
+            PrefixUsage, TextQuery, VersionOrder,
+        },
+        value_objects::{ObjectKey, VersionId},
+    },
+    ports::repositories::ObjectRepository,
+};
+
+/// Index of the shard holding the metadata of `bucket`
+///
+/// Uses CRC-32 rather than the standard library hasher so that every process,
+/// whatever its Rust version, places a bucket on the same shard.
+pub fn bucket_shard(bucket: &str, shard_count: usize) -> usize {
+    crc32fast::hash(bucket.as_bytes()) as usize % shard_count
+}
+
+/// ObjectRepository partitioning metadata across several repositories by bucket
+///
+/// The bucket of an object is the first `/`-separated segment of its key, so
+/// all versions of an object, and all objects of a bucket, live on one shard.
+/// Listings and searches whose prefix names a bucket go to that bucket's shard;
+/// other listings query every shard and merge the results in key order.
+///
+/// Changing the number of shards moves most buckets to a different shard, so
+/// existing metadata has to be migrated when shards are added.
+#[derive(Clone)]
+pub struct ShardedObjectRepository {
+    shards: Vec<Arc<dyn ObjectRepository>>,
+}
+
+impl ShardedObjectRepository {
+    /// Partition metadata across `shards`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` is empty.
+    pub fn new(shards: Vec<Arc<dyn ObjectRepository>>) -> Self {
+        assert!(!shards.is_empty(), "a sharded repository needs a shard");
+        Self { shards }
+    }
+
+    fn shard(&self, key: &ObjectKey) -> &dyn ObjectRepository {
+        let bucket = key.as_str().split('/').next().unwrap_or_default();
+        self.shards[bucket_shard(bucket, self.shards.len())].as_ref()
+    }
+
+    /// Shards that can hold keys starting with `prefix`
+    fn shards_for_prefix(&self, prefix: Option<&str>) -> Vec<&dyn ObjectRepository> {
+        match prefix.and_then(|prefix| prefix.split_once('/')) {
+            Some((bucket, _)) => {
+                vec![self.shards[bucket_shard(bucket, self.shards.len())].as_ref()]
+            }
+            None => self.shards.iter().map(|shard| shard.as_ref()).collect(),
+        }
+    }
+}
+
+/// Merge per-shard results into one key-ordered list of at most `max_results`
+fn merge(results: Vec<Vec<ObjectKey>>, max_results: Option<usize>) -> Vec<ObjectKey> {
+    let mut keys: Vec<ObjectKey> = results.into_iter().flatten().collect();
+    keys.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+    keys.truncate(max_results.unwrap_or(usize::MAX));
+    keys
+}
+
+#[async_trait]
+impl ObjectRepository for ShardedObjectRepository {
+    async fn save_object_metadata(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+        metadata: &ObjectMetadata,
+    ) -> StorageResult<()> {
+        self.shard(key)
+            .save_object_metadata(key, version_id, metadata)
+            .await
+    }
+
+    /// Versions are saved together per shard, so versions of objects in
+    /// buckets on different shards become visible shard by shard
+    async fn save_object_versions(
+        &self,
+        versions: &[(ObjectKey, VersionId, ObjectMetadata)],
+    ) -> StorageResult<()> {
+        let mut by_shard: Vec<Vec<(ObjectKey, VersionId, ObjectMetadata)>> =
+            vec![Vec::new(); self.shards.len()];
+        for version in versions {
+            let bucket = version.0.as_str().split('/').next().unwrap_or_default();
+            by_shard[bucket_shard(bucket, self.shards.len())].push(version.clone());
+        }
+        for (shard, versions) in self.shards.iter().zip(&by_shard) {
+            if !versions.is_empty() {
+                shard.save_object_versions(versions).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn get_object_metadata(
+        &self,
+        key: &ObjectKey,
+        version_id: Option<&VersionId>,
+    ) -> StorageResult<Option<ObjectMetadata>> {
+        self.shard(key).get_object_metadata(key, version_id).await
+    }
+
+    async fn list_object_versions(&self, key: &ObjectKey) -> StorageResult<ObjectVersionList> {
+        self.shard(key).list_object_versions(key).await
+    }
+
+    async fn list_object_versions_page(
+        &self,
+        key: &ObjectKey,
+        marker: Option<&VersionId>,
+        limit: usize,
+        order: VersionOrder,
+    ) -> StorageResult<Option<ObjectVersionPage>> {
+        self.shard(key)
+            .list_object_versions_page(key, marker, limit, order)
+            .await
+    }
+
+    async fn get_version_info(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+    ) -> StorageResult<Option<ObjectVersionInfo>> {
+        self.shard(key).get_version_info(key, version_id).await
+    }
+
+    async fn mark_version_deleted(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+    ) -> StorageResult<()> {
+        self.shard(key).mark_version_deleted(key, version_id).await
+    }
+
+    async fn delete_version_metadata(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+    ) -> StorageResult<()> {
+        self.shard(key)
+            .delete_version_metadata(key, version_id)
+            .await
+    }
+
+    async fn get_latest_version_id(&self, key: &ObjectKey) -> StorageResult<Option<VersionId>> {
+        self.shard(key).get_latest_version_id(key).await
+    }
+
+    async fn list_objects_by_prefix(
+        &self,
+        prefix: &str,
+        max_results: Option<usize>,
+    ) -> StorageResult<Vec<ObjectKey>> {
+        let results = try_join_all(
+            self.shards_for_prefix(Some(prefix))
+                .into_iter()
+                .map(|shard| shard.list_objects_by_prefix(prefix, max_results)),
+        )
+        .await?;
+        Ok(merge(results, max_results))
+    }
+
+    async fn list_delete_markers(&self, prefix: &str) -> StorageResult<Vec<ObjectKey>> {
+        let results = try_join_all(
+            self.shards_for_prefix(Some(prefix))
+                .into_iter()
+                .map(|shard| shard.list_delete_markers(prefix)),
+        )
+        .await?;
+        Ok(merge(results, None))
+    }
+
+    async fn list_latest_versions(
+        &self,
+        prefix: &str,
+        start_after: Option<&str>,
+        max_results: Option<usize>,
+    ) -> StorageResult<Vec<(ObjectKey, ObjectVersionInfo)>> {
+        let results = try_join_all(
+            self.shards_for_prefix(Some(prefix))
+                .into_iter()
+                .map(|shard| shard.list_latest_versions(prefix, start_after, max_results)),
+        )
+        .await?;
+        let mut listing: Vec<_> = results.into_iter().flatten().collect();
+        listing.sort_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+        listing.truncate(max_results.unwrap_or(usize::MAX));
+        Ok(listing)
+    }
+
+    async fn prefix_usage(&self, prefix: &str) -> StorageResult<PrefixUsage> {
+        let results = try_join_all(
+            self.shards_for_prefix(Some(prefix))
+                .into_iter()
+                .map(|shard| shard.prefix_usage(prefix)),
+        )
+        .await?;
+        Ok(PrefixUsage {
+            prefix: prefix.to_string(),
+            object_count: results.iter().map(|usage| usage.object_count).sum(),
+            total_bytes: results.iter().map(|usage| usage.total_bytes).sum(),
+            quota: None,
+        })
+    }
+
+    async fn find_objects(
+        &self,
+        filter: &Filter,
+        max_results: Option<usize>,
+    ) -> StorageResult<Vec<ObjectKey>> {
+        let results = try_join_all(
+            self.shards_for_prefix(filter.prefix.as_deref())
+                .into_iter()
+                .map(|shard| shard.find_objects(filter, max_results)),
+        )
+        .await?;
+        Ok(merge(results, max_results))
+    }
+
+    async fn search_objects(
+        &self,
+        query: &TextQuery,
+        filter: &Filter,
+        max_results: Option<usize>,
+    ) -> StorageResult<Vec<ObjectKey>> {
+        let results = try_join_all(
+            self.shards_for_prefix(filter.prefix.as_deref())
+                .into_iter()
+                .map(|shard| shard.search_objects(query, filter, max_results)),
+        )
+        .await?;
+        Ok(merge(results, max_results))
+    }
+
+    async fn update_object_metadata(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+        metadata: &ObjectMetadata,
+    ) -> StorageResult<()> {
+        self.shard(key)
+            .update_object_metadata(key, version_id, metadata)
+            .await
+    }
+
+    async fn compare_and_set_object_metadata(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+        expected: &ObjectMetadata,
+        metadata: &ObjectMetadata,
+    ) -> StorageResult<()> {
+        self.shard(key)
+            .compare_and_set_object_metadata(key, version_id, expected, metadata)
+            .await
+    }
+
+    async fn object_exists(&self, key: &ObjectKey) -> StorageResult<bool> {
+        self.shard(key).object_exists(key).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::outbound::persistence::InMemoryObjectRepository;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_buckets_are_partitioned_across_shards() {
+        let shards: Vec<Arc<InMemoryObjectRepository>> = (0..3)
+            .map(|_| Arc::new(InMemoryObjectRepository::new()))
+            .collect();
+        let repo = ShardedObjectRepository::new(
+            shards
+                .iter()
+                .map(|shard| shard.clone() as Arc<dyn ObjectRepository>)
+                .collect(),
+        );
+        let metadata = ObjectMetadata {
+            content_type: None,
+            content_length: 1,
+            etag: None,
+            last_modified: std::time::SystemTime::now(),
+            custom_metadata: HashMap::new(),
+        };
+        let version = VersionId::new("v1".to_string()).unwrap();
+
+        let buckets = ["alpha", "bravo", "charlie", "delta", "echo", "foxtrot"];
+        for bucket in buckets {
+            for name in ["a.txt", "b.txt"] {
+                let key = ObjectKey::new(format!("{}/{}", bucket, name)).unwrap();
+                repo.save_object_metadata(&key, &version, &metadata)
+                    .await
+                    .unwrap();
+            }
+        }
+
+        // Each bucket lives entirely on the shard its name hashes to
+        for bucket in buckets {
+            let key = ObjectKey::new(format!("{}/a.txt", bucket)).unwrap();
+            for (index, shard) in shards.iter().enumerate() {
+                let expected = index == bucket_shard(bucket, shards.len());
+                assert_eq!(shard.object_exists(&key).await.unwrap(), expected);
+            }
+            assert!(repo.object_exists(&key).await.unwrap());
+        }
+
+        let bucket_listing = repo.list_objects_by_prefix("delta/", None).await.unwrap();
+        assert_eq!(bucket_listing.len(), 2);
+
+        // Listings spanning buckets merge every shard in key order
+        let all = repo.list_objects_by_prefix("", Some(5)).await.unwrap();
+        let all: Vec<&str> = all.iter().map(|key| key.as_str()).collect();
+        assert_eq!(
+            all,
+            [
+                "alpha/a.txt",
+                "alpha/b.txt",
+                "bravo/a.txt",
+                "bravo/b.txt",
+                "charlie/a.txt"
+            ]
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{
+    domain::errors::{StorageError, StorageResult},
+    ports::repositories::LeaseRepository,
+};
+
+use super::DatabasePools;
+
+/// SQL-based implementation of LeaseRepository using PostgreSQL
+///
+/// Every server instance using the database shares its leases. Expiry is
+/// measured by the database clock, so instances whose clocks drift apart
+/// still agree on when a lease is free.
+#[derive(Clone)]
+pub struct SqlLeaseRepository {
+    pools: Arc<DatabasePools>,
+}
+
+impl SqlLeaseRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self::with_pools(Arc::new(DatabasePools::new(pool)))
+    }
+
+    /// Take leases on the primary of `pools`; replicas are never read, as a
+    /// lease seen late could be taken twice
+    pub fn with_pools(pools: Arc<DatabasePools>) -> Self {
+        Self { pools }
+    }
+
+    /// Initialize database tables
+    pub async fn migrate(&self) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS worker_leases (
+                name VARCHAR PRIMARY KEY,
+                holder VARCHAR NOT NULL,
+                expires_at TIMESTAMPTZ NOT NULL
+            );
+            "#,
+        )
+        .execute(self.pools.primary())
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl LeaseRepository for SqlLeaseRepository {
+    async fn try_acquire_lease(
+        &self,
+        name: &str,
+        holder: &str,
+        ttl: Duration,
+    ) -> StorageResult<bool> {
+        // The row is only written, and returned, when the lease is free,
+        // expired or already ours; the upsert makes that check atomic
+        let row = sqlx::query(
+            r#"
+            INSERT INTO worker_leases (name, holder, expires_at)
+            VALUES ($1, $2, NOW() + make_interval(secs => $3))
+            ON CONFLICT (name)
+            DO UPDATE SET
+                holder = EXCLUDED.holder,
+                expires_at = EXCLUDED.expires_at
+            WHERE worker_leases.holder = EXCLUDED.holder
+                OR worker_leases.expires_at <= NOW()
+            RETURNING holder
+            "#,
+        )
+        .bind(name)
+        .bind(holder)
+        .bind(ttl.as_secs_f64())
+        .fetch_optional(self.pools.primary())
+        .await
+        .map_err(|e| StorageError::InfrastructureError {
+            message: format!("Database error taking lease {}: {}", name, e),
+            source: Some(e.to_string()),
+        })?;
+
+        Ok(row.is_some())
+    }
+}
@@ -1,67 +1,91 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use sqlx::{PgPool, Row};
-use uuid::Uuid;
+use std::sync::Arc;
 
 use crate::{
     domain::{
+        errors::{LifecycleError, LifecycleResult},
         models::{LifecycleConfiguration, LifecycleRule},
-        value_objects::{ObjectKey, BucketName, VersionId},
-        errors::LifecycleResult,
+        value_objects::BucketName,
     },
     ports::repositories::LifecycleRepository,
 };
 
+use super::DatabasePools;
+use super::object_store_lifecycle_repository::StoredConfiguration;
+
 /// SQL-based implementation of LifecycleRepository using PostgreSQL
+///
+/// Configurations are stored as JSON in the same form
+/// [`ObjectStoreLifecycleRepository`](super::ObjectStoreLifecycleRepository)
+/// writes them.
 #[derive(Clone)]
 pub struct SqlLifecycleRepository {
-    pool: PgPool,
+    pools: Arc<DatabasePools>,
 }
 
 impl SqlLifecycleRepository {
     pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        Self::with_pools(Arc::new(DatabasePools::new(pool)))
+    }
+
+    /// List configured buckets from the replicas of `pools`
+    ///
+    /// Everything else goes to the primary, so a saved configuration is the
+    /// one the next read of that bucket returns.
+    pub fn with_pools(pools: Arc<DatabasePools>) -> Self {
+        Self { pools }
     }
 
     /// Initialize database tables
     pub async fn migrate(&self) -> Result<(), sqlx::Error> {
-        sqlx::query(
+        sqlx::raw_sql(
             r#"
             CREATE TABLE IF NOT EXISTS lifecycle_configurations (
-                id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
-                bucket_name VARCHAR NOT NULL UNIQUE,
+                bucket_name VARCHAR PRIMARY KEY,
                 configuration JSONB NOT NULL,
-                enabled BOOLEAN NOT NULL DEFAULT true,
-                created_at TIMESTAMPTZ DEFAULT NOW(),
-                updated_at TIMESTAMPTZ DEFAULT NOW()
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
             );
 
-            CREATE INDEX IF NOT EXISTS idx_lifecycle_bucket ON lifecycle_configurations(bucket_name);
-            CREATE INDEX IF NOT EXISTS idx_lifecycle_enabled ON lifecycle_configurations(enabled);
-
-            CREATE TABLE IF NOT EXISTS lifecycle_execution_log (
-                id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
-                bucket_name VARCHAR NOT NULL,
-                object_key VARCHAR NOT NULL,
-                rule_id VARCHAR NOT NULL,
-                action VARCHAR NOT NULL,
-                execution_time TIMESTAMPTZ DEFAULT NOW(),
-                success BOOLEAN NOT NULL,
-                error_message TEXT,
-                details JSONB
+            CREATE TABLE IF NOT EXISTS lifecycle_processed_times (
+                bucket_name VARCHAR PRIMARY KEY,
+                processed_at TIMESTAMPTZ NOT NULL
             );
-
-            CREATE INDEX IF NOT EXISTS idx_lifecycle_log_bucket ON lifecycle_execution_log(bucket_name);
-            CREATE INDEX IF NOT EXISTS idx_lifecycle_log_time ON lifecycle_execution_log(execution_time);
-            CREATE INDEX IF NOT EXISTS idx_lifecycle_log_object ON lifecycle_execution_log(object_key);
             "#,
         )
-        .execute(&self.pool)
+        .execute(self.pools.primary())
         .await?;
 
         Ok(())
     }
 }
 
+fn repository_error(e: impl std::fmt::Display) -> LifecycleError {
+    LifecycleError::RepositoryError {
+        message: e.to_string(),
+    }
+}
+
+fn validate(config: &LifecycleConfiguration, rule_id: &str) -> LifecycleResult<()> {
+    config.validate().map_err(|e| LifecycleError::InvalidRule {
+        rule_id: rule_id.to_string(),
+        reason: e.to_string(),
+    })
+}
+
+fn configuration_json(config: &LifecycleConfiguration) -> LifecycleResult<serde_json::Value> {
+    serde_json::to_value(StoredConfiguration::from(config)).map_err(repository_error)
+}
+
+fn configuration_from_json(
+    bucket: &BucketName,
+    json: serde_json::Value,
+) -> LifecycleResult<LifecycleConfiguration> {
+    let stored: StoredConfiguration = serde_json::from_value(json).map_err(repository_error)?;
+    Ok(stored.into_configuration(bucket.clone()))
+}
+
 #[async_trait]
 impl LifecycleRepository for SqlLifecycleRepository {
     async fn save_configuration(
@@ -69,32 +93,23 @@ impl LifecycleRepository for SqlLifecycleRepository {
         bucket: &BucketName,
         config: &LifecycleConfiguration,
     ) -> LifecycleResult<()> {
-        let config_json = serde_json::to_value(config)
-            .map_err(|e| crate::domain::errors::LifecycleError::InvalidRule {
-                rule_id: "configuration".to_string(),
-                reason: format!("Failed to serialize lifecycle configuration: {}", e),
-            })?;
+        validate(config, "")?;
 
         sqlx::query(
             r#"
-            INSERT INTO lifecycle_configurations (bucket_name, configuration, enabled, updated_at)
-            VALUES ($1, $2, $3, NOW())
+            INSERT INTO lifecycle_configurations (bucket_name, configuration, updated_at)
+            VALUES ($1, $2, NOW())
             ON CONFLICT (bucket_name)
-            DO UPDATE SET 
+            DO UPDATE SET
                 configuration = EXCLUDED.configuration,
-                enabled = EXCLUDED.enabled,
                 updated_at = NOW()
             "#,
         )
         .bind(bucket.as_str())
-        .bind(&config_json)
-        .bind(config.enabled)
-        .execute(&self.pool)
+        .bind(configuration_json(config)?)
+        .execute(self.pools.primary())
         .await
-        .map_err(|e| crate::domain::errors::StorageError::InfrastructureError {
-            message: format!("Database error storing lifecycle configuration: {}", e),
-            source: Some(e.to_string()),
-        })?;
+        .map_err(repository_error)?;
 
         Ok(())
     }
@@ -102,268 +117,202 @@ impl LifecycleRepository for SqlLifecycleRepository {
     async fn get_configuration(
         &self,
         bucket: &BucketName,
-    ) -> StorageResult<Option<LifecycleConfiguration>> {
+    ) -> LifecycleResult<Option<LifecycleConfiguration>> {
         let row = sqlx::query(
-            r#"
-            SELECT configuration, enabled
-            FROM lifecycle_configurations 
-            WHERE bucket_name = $1
-            "#,
+            "SELECT configuration FROM lifecycle_configurations WHERE bucket_name = $1",
         )
         .bind(bucket.as_str())
-        .fetch_optional(&self.pool)
+        .fetch_optional(self.pools.primary())
         .await
-        .map_err(|e| crate::domain::errors::StorageError::InfrastructureError {
-            message: format!("Database error retrieving lifecycle configuration: {}", e),
-            source: Some(e.to_string()),
-        })?;
+        .map_err(repository_error)?;
+
+        row.map(|row| configuration_from_json(bucket, row.get("configuration")))
+            .transpose()
+    }
 
-        match row {
-            Some(row) => {
-                let mut config: LifecycleConfiguration = 
-                    serde_json::from_value(row.get("configuration"))
-                        .map_err(|e| crate::domain::errors::StorageError::InternalError {
-                            message: format!("Failed to deserialize lifecycle configuration: {}", e),
-                        })?;
-                
-                config.enabled = row.get("enabled");
-                Ok(Some(config))
-            }
-            None => Ok(None),
+    async fn delete_configuration(&self, bucket: &BucketName) -> LifecycleResult<()> {
+        let mut transaction = self
+            .pools
+            .primary()
+            .begin()
+            .await
+            .map_err(repository_error)?;
+        for table in ["lifecycle_configurations", "lifecycle_processed_times"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE bucket_name = $1", table))
+                .bind(bucket.as_str())
+                .execute(&mut *transaction)
+                .await
+                .map_err(repository_error)?;
         }
+        transaction.commit().await.map_err(repository_error)
     }
 
-    async fn delete_configuration(&self, bucket: &BucketName) -> StorageResult<bool> {
-        let result = sqlx::query(
-            r#"
-            DELETE FROM lifecycle_configurations 
-            WHERE bucket_name = $1
-            "#,
+    async fn configuration_exists(&self, bucket: &BucketName) -> LifecycleResult<bool> {
+        sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM lifecycle_configurations WHERE bucket_name = $1)",
         )
         .bind(bucket.as_str())
-        .execute(&self.pool)
+        .fetch_one(self.pools.primary())
         .await
-        .map_err(|e| crate::domain::errors::StorageError::InfrastructureError {
-            message: format!("Database error deleting lifecycle configuration: {}", e),
-            source: Some(e.to_string()),
-        })?;
+        .map_err(repository_error)
+    }
 
-        Ok(result.rows_affected() > 0)
+    async fn get_rule(
+        &self,
+        bucket: &BucketName,
+        rule_id: &str,
+    ) -> LifecycleResult<Option<LifecycleRule>> {
+        Ok(self
+            .get_configuration(bucket)
+            .await?
+            .and_then(|config| config.rules.into_iter().find(|rule| rule.id == rule_id)))
     }
 
-    async fn list_configurations(&self) -> StorageResult<Vec<(BucketName, LifecycleConfiguration)>> {
-        let rows = sqlx::query(
+    async fn update_rule(&self, bucket: &BucketName, rule: &LifecycleRule) -> LifecycleResult<()> {
+        let mut transaction = self
+            .pools
+            .primary()
+            .begin()
+            .await
+            .map_err(repository_error)?;
+
+        // The row stays locked until the updated configuration is written
+        let row = sqlx::query(
             r#"
-            SELECT bucket_name, configuration, enabled
-            FROM lifecycle_configurations 
-            ORDER BY bucket_name
+            SELECT configuration FROM lifecycle_configurations
+            WHERE bucket_name = $1
+            FOR UPDATE
             "#,
         )
-        .fetch_all(&self.pool)
+        .bind(bucket.as_str())
+        .fetch_optional(&mut *transaction)
         .await
-        .map_err(|e| crate::domain::errors::StorageError::InfrastructureError {
-            message: format!("Database error listing lifecycle configurations: {}", e),
-            source: Some(e.to_string()),
+        .map_err(repository_error)?
+        .ok_or_else(|| LifecycleError::ConfigurationNotFound {
+            bucket: bucket.clone(),
         })?;
+        let mut config = configuration_from_json(bucket, row.get("configuration"))?;
+
+        let existing_rule = config
+            .rules
+            .iter_mut()
+            .find(|existing_rule| existing_rule.id == rule.id)
+            .ok_or_else(|| LifecycleError::InvalidRule {
+                rule_id: rule.id.clone(),
+                reason: "Rule not found".to_string(),
+            })?;
+        *existing_rule = rule.clone();
+        validate(&config, &rule.id)?;
 
-        let mut configs = Vec::new();
-        for row in rows {
-            let bucket_name = BucketName::new(row.get::<String, _>("bucket_name"))
-                .map_err(|e| crate::domain::errors::StorageError::ValidationError {
-                    message: format!("Invalid bucket name: {}", e),
-                })?;
-
-            let mut config: LifecycleConfiguration = 
-                serde_json::from_value(row.get("configuration"))
-                    .map_err(|e| crate::domain::errors::StorageError::InternalError {
-                        message: format!("Failed to deserialize lifecycle configuration: {}", e),
-                    })?;
-            
-            config.enabled = row.get("enabled");
-            configs.push((bucket_name, config));
-        }
-
-        Ok(configs)
-    }
+        sqlx::query(
+            r#"
+            UPDATE lifecycle_configurations
+            SET configuration = $2, updated_at = NOW()
+            WHERE bucket_name = $1
+            "#,
+        )
+        .bind(bucket.as_str())
+        .bind(configuration_json(&config)?)
+        .execute(&mut *transaction)
+        .await
+        .map_err(repository_error)?;
 
-    async fn add_rule(
-        &self,
-        bucket: &BucketName,
-        rule: &LifecycleRule,
-    ) -> StorageResult<()> {
-        // Get existing configuration
-        let mut config = self.get_configuration(bucket).await?
-            .unwrap_or_else(|| LifecycleConfiguration {
-                rules: Vec::new(),
-                enabled: true,
-            });
-
-        // Add new rule
-        config.rules.push(rule.clone());
-
-        // Store updated configuration
-        self.store_configuration(bucket, &config).await
+        transaction.commit().await.map_err(repository_error)
     }
 
-    async fn remove_rule(
-        &self,
-        bucket: &BucketName,
-        rule_id: &str,
-    ) -> StorageResult<bool> {
-        // Get existing configuration
-        let mut config = match self.get_configuration(bucket).await? {
-            Some(config) => config,
-            None => return Ok(false),
-        };
-
-        // Remove rule by ID
-        let original_len = config.rules.len();
-        config.rules.retain(|rule| rule.id != rule_id);
-
-        if config.rules.len() == original_len {
-            return Ok(false);
-        }
-
-        // Store updated configuration
-        self.store_configuration(bucket, &config).await?;
-        Ok(true)
-    }
+    async fn list_configured_buckets(&self) -> LifecycleResult<Vec<BucketName>> {
+        let names: Vec<String> = sqlx::query_scalar(
+            "SELECT bucket_name FROM lifecycle_configurations ORDER BY bucket_name",
+        )
+        .fetch_all(self.pools.reader())
+        .await
+        .map_err(repository_error)?;
 
-    async fn get_rules_for_bucket(&self, bucket: &BucketName) -> StorageResult<Vec<LifecycleRule>> {
-        match self.get_configuration(bucket).await? {
-            Some(config) => Ok(config.rules),
-            None => Ok(Vec::new()),
-        }
+        Ok(names
+            .into_iter()
+            .filter_map(|name| BucketName::new(name).ok())
+            .collect())
     }
 
-    async fn mark_rule_executed(
+    async fn get_last_processed_time(
         &self,
         bucket: &BucketName,
-        key: &ObjectKey,
-        rule_id: &str,
-        action: &str,
-        success: bool,
-        error_message: Option<&str>,
-    ) -> StorageResult<()> {
-        sqlx::query(
-            r#"
-            INSERT INTO lifecycle_execution_log (
-                bucket_name, object_key, rule_id, action, success, error_message, execution_time
-            ) 
-            VALUES ($1, $2, $3, $4, $5, $6, NOW())
-            "#,
+    ) -> LifecycleResult<Option<std::time::SystemTime>> {
+        let time: Option<DateTime<Utc>> = sqlx::query_scalar(
+            "SELECT processed_at FROM lifecycle_processed_times WHERE bucket_name = $1",
         )
         .bind(bucket.as_str())
-        .bind(key.as_str())
-        .bind(rule_id)
-        .bind(action)
-        .bind(success)
-        .bind(error_message)
-        .execute(&self.pool)
+        .fetch_optional(self.pools.primary())
         .await
-        .map_err(|e| crate::domain::errors::StorageError::InfrastructureError {
-            message: format!("Database error logging rule execution: {}", e),
-            source: Some(e.to_string()),
-        })?;
+        .map_err(repository_error)?;
 
-        Ok(())
+        Ok(time.map(Into::into))
     }
 
-    async fn is_rule_executed(
+    async fn set_last_processed_time(
         &self,
         bucket: &BucketName,
-        key: &ObjectKey,
-        rule_id: &str,
-    ) -> StorageResult<bool> {
-        let count: i64 = sqlx::query_scalar(
+        time: std::time::SystemTime,
+    ) -> LifecycleResult<()> {
+        sqlx::query(
             r#"
-            SELECT COUNT(*) 
-            FROM lifecycle_execution_log 
-            WHERE bucket_name = $1 AND object_key = $2 AND rule_id = $3 AND success = true
+            INSERT INTO lifecycle_processed_times (bucket_name, processed_at)
+            VALUES ($1, $2)
+            ON CONFLICT (bucket_name) DO UPDATE SET processed_at = EXCLUDED.processed_at
             "#,
         )
         .bind(bucket.as_str())
-        .bind(key.as_str())
-        .bind(rule_id)
-        .fetch_one(&self.pool)
+        .bind(DateTime::<Utc>::from(time))
+        .execute(self.pools.primary())
         .await
-        .map_err(|e| crate::domain::errors::StorageError::InfrastructureError {
-            message: format!("Database error checking rule execution: {}", e),
-            source: Some(e.to_string()),
-        })?;
+        .map_err(repository_error)?;
 
-        Ok(count > 0)
+        Ok(())
     }
+}
 
-    async fn get_execution_history(
-        &self,
-        bucket: &BucketName,
-        key: Option<&ObjectKey>,
-        limit: Option<usize>,
-    ) -> StorageResult<Vec<(String, String, chrono::DateTime<chrono::Utc>, bool, Option<String>)>> {
-        let mut query_str = String::from(
-            "SELECT rule_id, action, execution_time, success, error_message 
-             FROM lifecycle_execution_log 
-             WHERE bucket_name = $1"
-        );
-        
-        let mut param_count = 1;
-        if key.is_some() {
-            param_count += 1;
-            query_str.push_str(&format!(" AND object_key = ${}", param_count));
-        }
-        
-        query_str.push_str(" ORDER BY execution_time DESC");
-        
-        if let Some(limit) = limit {
-            query_str.push_str(&format!(" LIMIT {}", limit));
-        }
-
-        let mut query = sqlx::query(&query_str).bind(bucket.as_str());
-        
-        if let Some(key) = key {
-            query = query.bind(key.as_str());
-        }
-
-        let rows = query
-            .fetch_all(&self.pool)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::{Filter, RuleStatus};
+
+    #[tokio::test]
+    #[ignore = "requires PostgreSQL; set DATABASE_URL"]
+    async fn test_configuration_round_trip() {
+        let url = std::env::var("DATABASE_URL").expect("DATABASE_URL");
+        let repository = SqlLifecycleRepository::new(PgPool::connect(&url).await.unwrap());
+        repository.migrate().await.unwrap();
+
+        let bucket = BucketName::new("sql-lifecycle-test".to_string()).unwrap();
+        let mut rule = LifecycleRule {
+            id: "expire-logs".to_string(),
+            status: RuleStatus::Enabled,
+            filter: Filter::new().with_prefix("sql-lifecycle-test/logs/".to_string()),
+            expiration_days: Some(30),
+            ..Default::default()
+        };
+        let config = LifecycleConfiguration {
+            bucket: bucket.clone(),
+            rules: vec![rule.clone()],
+        };
+        repository
+            .save_configuration(&bucket, &config)
             .await
-            .map_err(|e| crate::domain::errors::StorageError::InfrastructureError {
-                message: format!("Database error getting execution history: {}", e),
-                source: Some(e.to_string()),
-            })?;
-
-        let history = rows
-            .iter()
-            .map(|row| {
-                (
-                    row.get::<String, _>("rule_id"),
-                    row.get::<String, _>("action"),
-                    row.get("execution_time"),
-                    row.get("success"),
-                    row.get("error_message"),
-                )
-            })
-            .collect();
-
-        Ok(history)
-    }
-
-    async fn cleanup_execution_logs(&self, days_to_keep: u32) -> StorageResult<u64> {
-        let result = sqlx::query(
-            r#"
-            DELETE FROM lifecycle_execution_log 
-            WHERE execution_time < NOW() - INTERVAL '%1 days'
-            "#,
-        )
-        .bind(days_to_keep as i32)
-        .execute(&self.pool)
-        .await
-        .map_err(|e| crate::domain::errors::StorageError::InfrastructureError {
-            message: format!("Database error cleaning up execution logs: {}", e),
-            source: Some(e.to_string()),
-        })?;
+            .unwrap();
+
+        rule.expiration_days = Some(7);
+        repository.update_rule(&bucket, &rule).await.unwrap();
+        let stored = repository.get_rule(&bucket, "expire-logs").await.unwrap();
+        assert_eq!(stored.unwrap().expiration_days, Some(7));
+        assert!(
+            repository
+                .list_configured_buckets()
+                .await
+                .unwrap()
+                .contains(&bucket)
+        );
 
-        Ok(result.rows_affected())
+        repository.delete_configuration(&bucket).await.unwrap();
+        assert!(!repository.configuration_exists(&bucket).await.unwrap());
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,68 @@
+//! Non-S3 storage backends provided by the object_store crate
+//!
+//! Each function builds a raw object_store backend that is wrapped in the same
+//! adapters as the S3 store.
+
+use anyhow::{Context, Result};
+use object_store::{
+    ObjectStore as ObjectStoreBackend, azure::MicrosoftAzureBuilder,
+    gcp::GoogleCloudStorageBuilder, local::LocalFileSystem,
+};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Configuration for Azure Blob Storage
+#[derive(Debug, Clone)]
+pub struct AzureConfig {
+    pub account: String,
+    pub container: String,
+    /// Account access key; other credentials are read from the `AZURE_*` environment
+    pub access_key: Option<String>,
+}
+
+/// Configuration for Google Cloud Storage
+#[derive(Debug, Clone)]
+pub struct GcsConfig {
+    pub bucket: String,
+    /// Service account key file; otherwise credentials come from the environment
+    pub service_account_path: Option<String>,
+}
+
+/// Create an Azure Blob Storage store from configuration
+pub fn create_azure_store(config: AzureConfig) -> Result<Arc<dyn ObjectStoreBackend>> {
+    let mut builder = MicrosoftAzureBuilder::from_env()
+        .with_account(&config.account)
+        .with_container_name(&config.container);
+
+    if let Some(access_key) = &config.access_key {
+        builder = builder.with_access_key(access_key);
+    }
+
+    let store = builder.build().context("Failed to build Azure store")?;
+
+    Ok(Arc::new(store))
+}
+
+/// Create a Google Cloud Storage store from configuration
+pub fn create_gcs_store(config: GcsConfig) -> Result<Arc<dyn ObjectStoreBackend>> {
+    let mut builder = GoogleCloudStorageBuilder::from_env().with_bucket_name(&config.bucket);
+
+    if let Some(path) = &config.service_account_path {
+        builder = builder.with_service_account_path(path);
+    }
+
+    let store = builder.build().context("Failed to build GCS store")?;
+
+    Ok(Arc::new(store))
+}
+
+/// Create a store keeping objects as files under `root`, which is created if missing
+pub fn create_local_store(root: &Path) -> Result<Arc<dyn ObjectStoreBackend>> {
+    std::fs::create_dir_all(root)
+        .with_context(|| format!("Failed to create storage directory {}", root.display()))?;
+
+    let store = LocalFileSystem::new_with_prefix(root)
+        .with_context(|| format!("Failed to open storage directory {}", root.display()))?;
+
+    Ok(Arc::new(store))
+}
@@ -1,10 +1,16 @@
-use crate::adapters::outbound::storage::minio::minio::MinioLifecycleConfig;
+use crate::adapters::outbound::storage::minio::minio::{
+    MinioLifecycleConfig, lifecycle_config_to_xml, parse_lifecycle_config,
+};
+use crate::adapters::outbound::storage::signing::{SigV4Signer, UNSIGNED_PAYLOAD, payload_hash};
 use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chrono::Utc;
 use http::{Method, Request};
 use quick_xml::de::from_str;
-use reqwest::{Body, Client};
+use reqwest::{Body, Client, Url};
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::time::Duration;
 use thiserror::Error;
 
@@ -129,7 +135,7 @@ impl S3Client {
     }
 
     /// Get the base URL for the client
-    fn get_base_url(&self) -> String {
+    pub(crate) fn get_base_url(&self) -> String {
         format!("{}://{}", self.get_scheme(), self.endpoint)
     }
 
@@ -138,58 +144,81 @@ impl S3Client {
         &self.region
     }
 
+    /// Signer for requests made with the client's credentials
+    fn signer(&self) -> SigV4Signer {
+        SigV4Signer::new(
+            self.access_key.clone(),
+            self.secret_key.clone(),
+            self.session_token.clone(),
+            self.region.clone(),
+        )
+    }
+
     /// Sign and execute a request
-    async fn execute_request(
+    ///
+    /// Requests are signed with AWS Signature Version 4. Buffered bodies are
+    /// hashed into the signature; streamed bodies are sent as unsigned payloads.
+    pub(crate) async fn execute_request(
         &self,
         request: Request<Body>,
     ) -> Result<reqwest::Response, BucketError> {
-        // Convert the request to a reqwest request
         let (parts, body) = request.into_parts();
 
-        // Get method and URL from parts
-        let method = match parts.method.as_str() {
-            "GET" => reqwest::Method::GET,
-            "PUT" => reqwest::Method::PUT,
-            "POST" => reqwest::Method::POST,
-            "DELETE" => reqwest::Method::DELETE,
-            _ => {
-                return Err(BucketError::ServiceError {
+        let method =
+            reqwest::Method::from_bytes(parts.method.as_str().as_bytes()).map_err(|_| {
+                BucketError::ServiceError {
                     status_code: 405,
                     message: "Method not allowed".to_string(),
-                });
-            }
-        };
-
-        let url = parts.uri.to_string();
+                }
+            })?;
 
-        // Execute the request using reqwest
-        let mut req = self.http_client.request(method, &url);
+        let url = Url::parse(&parts.uri.to_string())
+            .map_err(|e| BucketError::SigningError(format!("Invalid request URL: {}", e)))?;
 
-        // Add headers
+        let mut headers = BTreeMap::new();
         for (key, value) in parts.headers.iter() {
-            if let Ok(value_str) = value.to_str() {
-                req = req.header(key.as_str(), value_str);
-            }
+            let value = value.to_str().map_err(|_| {
+                BucketError::SigningError(format!("Header {} is not valid text", key))
+            })?;
+            headers.insert(key.as_str().to_string(), value.to_string());
         }
 
-        // Add body if present
+        let content_hash = body
+            .as_bytes()
+            .map(payload_hash)
+            .unwrap_or_else(|| UNSIGNED_PAYLOAD.to_string());
+        self.signer().sign(
+            parts.method.as_str(),
+            &url,
+            &mut headers,
+            &content_hash,
+            Utc::now(),
+        );
+
+        let mut req = self.http_client.request(method, url);
+        for (key, value) in headers {
+            req = req.header(key, value);
+        }
         req = req.body(body);
 
         let response = req
             .send()
             .await
-            .map_err(|e| BucketError::IoError(std::io::Error::new(std::io::ErrorKind::Other, e)))?;
+            .map_err(|e| BucketError::IoError(std::io::Error::other(e)))?;
 
         // Check for errors
         let status = response.status();
         if !status.is_success() {
             let status_code = status.as_u16();
-
-            // Extract error message from response body
-            let error_message = "Service error occurred".to_string();
+            let body = response.text().await.unwrap_or_default();
             return Err(BucketError::ServiceError {
                 status_code,
-                message: error_message,
+                message: error_message(&body).unwrap_or_else(|| {
+                    status
+                        .canonical_reason()
+                        .unwrap_or("Service error occurred")
+                        .to_string()
+                }),
             });
         }
 
@@ -197,6 +226,23 @@ impl S3Client {
     }
 }
 
+/// Message of an S3 XML error response
+fn error_message(body: &str) -> Option<String> {
+    #[derive(Deserialize)]
+    struct ErrorResponse {
+        #[serde(rename = "Code")]
+        code: String,
+        #[serde(rename = "Message", default)]
+        message: Option<String>,
+    }
+
+    let error: ErrorResponse = from_str(body).ok()?;
+    Some(match error.message {
+        Some(message) => format!("{}: {}", error.code, message),
+        None => error.code,
+    })
+}
+
 #[async_trait]
 pub trait BucketOperations {
     /// Create a new bucket with the given name and options
@@ -215,6 +261,9 @@ pub trait BucketOperations {
     /// List all buckets
     async fn list_buckets(&self) -> Result<Vec<String>, BucketError>;
 
+    /// Get the region a bucket lives in
+    async fn get_bucket_location(&self, name: &str) -> Result<String, BucketError>;
+
     /// Set lifecycle configuration for a bucket
     async fn set_bucket_lifecycle(
         &self,
@@ -263,20 +312,23 @@ impl BucketOperations for S3BucketOperations {
 
         // Prepare request headers
         let mut headers = http::HeaderMap::new();
-        headers.insert("Host", self.client.endpoint.parse().unwrap());
 
         // Add object lock header if enabled
         if options.object_lock_enabled {
-            headers.insert("x-amz-bucket-object-lock-enabled", "true".parse().unwrap());
+            headers.insert(
+                "x-amz-bucket-object-lock-enabled",
+                http::HeaderValue::from_static("true"),
+            );
         }
 
         // Add any extra headers
         if let Some(extra_headers) = options.extra_headers {
             for (key, value) in extra_headers {
-                headers.insert(
-                    http::HeaderName::from_bytes(key.as_bytes()).unwrap(),
-                    http::HeaderValue::from_str(&value).unwrap(),
-                );
+                let name = http::HeaderName::from_bytes(key.as_bytes())
+                    .map_err(|e| BucketError::HttpError(e.into()))?;
+                let value = http::HeaderValue::from_str(&value)
+                    .map_err(|e| BucketError::HttpError(e.into()))?;
+                headers.insert(name, value);
             }
         }
 
@@ -300,17 +352,17 @@ impl BucketOperations for S3BucketOperations {
             "".to_string()
         } else {
             format!(
-                "<CreateBucketConfiguration><LocationConstraint>{}</LocationConstraint></CreateBucketConfiguration>",
+                "<CreateBucketConfiguration xmlns=\"http://s3.amazonaws.com/doc/2006-03-01/\"><LocationConstraint>{}</LocationConstraint></CreateBucketConfiguration>",
                 region
             )
         };
 
         // Create the request
-        let request = Request::builder()
-            .method(Method::PUT)
-            .uri(url)
-            .extension(headers)
-            .body(Body::from(body))?;
+        let mut request = Request::builder().method(Method::PUT).uri(url);
+        if let Some(request_headers) = request.headers_mut() {
+            request_headers.extend(headers);
+        }
+        let request = request.body(Body::from(body))?;
 
         // Execute the request
         let _ = self.client.execute_request(request).await?;
@@ -339,7 +391,7 @@ impl BucketOperations for S3BucketOperations {
         // Execute the request
         match self.client.execute_request(request).await {
             Ok(_) => Ok(true),
-            Err(BucketError::ServiceError { status_code, .. }) if status_code == 404 => Ok(false),
+            Err(BucketError::ServiceError { status_code: 404, .. }) => Ok(false),
             Err(e) => Err(e),
         }
     }
@@ -380,7 +432,7 @@ impl BucketOperations for S3BucketOperations {
         let body_bytes = response
             .bytes()
             .await
-            .map_err(|e| BucketError::IoError(std::io::Error::new(std::io::ErrorKind::Other, e)))?;
+            .map_err(|e| BucketError::IoError(std::io::Error::other(e)))?;
         let body = String::from_utf8_lossy(&body_bytes);
 
         // Parse XML response
@@ -408,6 +460,46 @@ impl BucketOperations for S3BucketOperations {
         Ok(result.buckets.bucket.into_iter().map(|b| b.name).collect())
     }
 
+    async fn get_bucket_location(&self, name: &str) -> Result<String, BucketError> {
+        // Validate bucket name
+        validate_bucket_name(name)?;
+
+        // Build the request URL
+        let url = format!("{}/{}?location", self.client.get_base_url(), name);
+
+        // Create the request
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(url)
+            .body(Body::from(""))?;
+
+        // Execute the request
+        let response = self.client.execute_request(request).await?;
+
+        // Parse the response
+        let body_bytes = response
+            .bytes()
+            .await
+            .map_err(|e| BucketError::IoError(std::io::Error::other(e)))?;
+        let body = String::from_utf8_lossy(&body_bytes);
+
+        #[derive(Deserialize)]
+        struct LocationConstraint {
+            #[serde(rename = "$text", default)]
+            region: Option<String>,
+        }
+
+        let location: LocationConstraint =
+            from_str(&body).map_err(|e| BucketError::XmlError(e.to_string()))?;
+
+        // S3 reports us-east-1 as an empty constraint, and eu-west-1 as EU for old buckets
+        Ok(match location.region.as_deref().map(str::trim) {
+            None | Some("") => "us-east-1".to_string(),
+            Some("EU") => "eu-west-1".to_string(),
+            Some(region) => region.to_string(),
+        })
+    }
+
     async fn set_bucket_lifecycle(
         &self,
         name: &str,
@@ -415,18 +507,21 @@ impl BucketOperations for S3BucketOperations {
     ) -> Result<(), BucketError> {
         // Validate bucket name and lifecycle config
         validate_bucket_name(name)?;
-        // For MinioLifecycleConfig, we'll skip validation since it doesn't have a validate method
-        // Convert config to XML - we'll serialize it as JSON for now
         let xml =
-            serde_json::to_string(config).map_err(|e| BucketError::XmlError(e.to_string()))?;
+            lifecycle_config_to_xml(config).map_err(|e| BucketError::XmlError(e.to_string()))?;
 
         // Build the request URL
         let url = format!("{}/{}?lifecycle", self.client.get_base_url(), name);
 
+        // S3 rejects lifecycle configurations sent without a Content-MD5 header
+        let content_md5 = BASE64.encode(md5::compute(xml.as_bytes()).0);
+
         // Create the request
         let request = Request::builder()
             .method(Method::PUT)
             .uri(url)
+            .header("content-type", "application/xml")
+            .header("content-md5", content_md5)
             .body(Body::from(xml))?;
 
         // Execute the request
@@ -455,14 +550,10 @@ impl BucketOperations for S3BucketOperations {
         let body_bytes = response
             .bytes()
             .await
-            .map_err(|e| BucketError::IoError(std::io::Error::new(std::io::ErrorKind::Other, e)))?;
+            .map_err(|e| BucketError::IoError(std::io::Error::other(e)))?;
         let body = String::from_utf8_lossy(&body_bytes);
 
-        // Parse XML response - for now, we'll try to parse as JSON
-        let config: MinioLifecycleConfig =
-            serde_json::from_str(&body).map_err(|e| BucketError::XmlError(e.to_string()))?;
-
-        Ok(config)
+        parse_lifecycle_config(&body).map_err(|e| BucketError::XmlError(e.to_string()))
     }
 }
 
@@ -480,7 +571,7 @@ impl BucketOperationsExt for S3BucketOperations {
         let url = format!("{}/{}?versioning", self.client.get_base_url(), name);
 
         // Create the request body
-        let body = "<VersioningConfiguration><Status>Enabled</Status></VersioningConfiguration>";
+        let body = "<VersioningConfiguration xmlns=\"http://s3.amazonaws.com/doc/2006-03-01/\"><Status>Enabled</Status></VersioningConfiguration>";
 
         // Create the request
         let request = Request::builder()
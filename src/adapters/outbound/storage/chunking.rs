@@ -0,0 +1,836 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use object_store::{
+    Attribute, AttributeValue, Attributes, GetOptions, GetRange, GetResult, GetResultPayload,
+    ListResult, MultipartUpload, ObjectMeta, ObjectStore, PutMode, PutMultipartOptions, PutOptions,
+    PutPayload, PutResult, UploadPart, path::Path,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::ports::runtime::{IdGenerator, UuidGenerator};
+
+/// Default size of each stored chunk (64 MiB)
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024 * 1024;
+
+/// Suffix of the manifest describing a chunked object
+const MANIFEST_SUFFIX: &str = ".chunked";
+
+/// Marker preceding the index of a stored chunk
+const CHUNK_MARKER: &str = ".chunk_";
+
+/// Prefix of user metadata names among a manifest's attributes
+const METADATA_PREFIX: &str = "metadata:";
+
+/// Manifest stored alongside the chunks of a large object
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkManifest {
+    /// Total size of the logical object in bytes
+    pub size: u64,
+
+    /// Size of each stored chunk, in order
+    pub chunk_sizes: Vec<u64>,
+
+    /// Generation the chunks were written under
+    ///
+    /// Every write stores its chunks under a new generation, so the chunks of
+    /// the live object are never overwritten; manifests written before
+    /// generations were introduced have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generation: Option<String>,
+
+    /// Attributes the object was written with, by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl ChunkManifest {
+    /// Path of a single chunk of the object at `location`
+    fn chunk_path(&self, location: &Path, index: usize) -> Path {
+        chunk_path(location, self.generation.as_deref(), index)
+    }
+
+    /// The object's attributes as the inner store takes them
+    fn object_attributes(&self) -> Attributes {
+        self.attributes
+            .iter()
+            .map(|(name, value)| {
+                (
+                    attribute_from_name(name),
+                    AttributeValue::from(value.clone()),
+                )
+            })
+            .collect()
+    }
+
+    /// Byte offset at which each chunk starts
+    fn chunk_offsets(&self) -> Vec<u64> {
+        self.chunk_sizes
+            .iter()
+            .scan(0u64, |offset, size| {
+                let start = *offset;
+                *offset += size;
+                Some(start)
+            })
+            .collect()
+    }
+
+    /// Resolve a logical byte range into (chunk index, range within that chunk) pairs
+    fn chunks_for_range(&self, range: &Range<u64>) -> Vec<(usize, Range<u64>)> {
+        self.chunk_offsets()
+            .into_iter()
+            .zip(self.chunk_sizes.iter())
+            .enumerate()
+            .filter_map(|(index, (start, size))| {
+                let end = start + size;
+                if end <= range.start || start >= range.end {
+                    return None;
+                }
+                let local_start = range.start.saturating_sub(start);
+                let local_end = range.end.min(end) - start;
+                Some((index, local_start..local_end))
+            })
+            .collect()
+    }
+}
+
+/// An ObjectStore decorator that transparently splits large objects into chunks
+///
+/// Objects up to the threshold are passed straight through to the inner store.
+/// Larger objects are written as fixed-size chunks next to a small JSON manifest
+/// and reassembled on read, so backends with a per-object size limit can still
+/// hold arbitrarily large blobs. Ranged reads only fetch the chunks they touch.
+#[derive(Debug)]
+pub struct ChunkedStore<T: ObjectStore> {
+    /// The underlying object store
+    inner: Arc<T>,
+
+    /// Size of each stored chunk
+    chunk_size: usize,
+
+    /// Objects larger than this are chunked
+    threshold: usize,
+}
+
+impl<T: ObjectStore> std::fmt::Display for ChunkedStore<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ChunkedStore({})", self.inner)
+    }
+}
+
+impl<T: ObjectStore> ChunkedStore<T> {
+    /// Create a chunked store using the default chunk size as both chunk size and threshold
+    pub fn new(store: T) -> Self {
+        Self::with_chunk_size(store, DEFAULT_CHUNK_SIZE)
+    }
+
+    /// Create a chunked store that splits objects larger than `chunk_size`
+    pub fn with_chunk_size(store: T, chunk_size: usize) -> Self {
+        let chunk_size = chunk_size.max(1);
+        ChunkedStore {
+            inner: Arc::new(store),
+            chunk_size,
+            threshold: chunk_size,
+        }
+    }
+
+    /// Only chunk objects larger than `threshold` bytes
+    pub fn with_threshold(mut self, threshold: usize) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// The configured chunk size
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// The configured chunking threshold
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// Path of the manifest for a logical object
+    fn manifest_path(location: &Path) -> Path {
+        Path::from(format!("{}{}", location.as_ref(), MANIFEST_SUFFIX))
+    }
+
+    /// Whether a stored path is an individual chunk rather than a logical object
+    fn is_chunk_path(path: &Path) -> bool {
+        path.as_ref()
+            .rsplit_once(CHUNK_MARKER)
+            .is_some_and(|(_, chunk)| {
+                let index = match chunk.rsplit_once('_') {
+                    Some((generation, index)) => {
+                        let is_generation = |b: u8| b.is_ascii_alphanumeric() || b == b'-';
+                        if generation.is_empty() || !generation.bytes().all(is_generation) {
+                            return false;
+                        }
+                        index
+                    }
+                    None => chunk,
+                };
+                !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit())
+            })
+    }
+
+    /// Map a manifest path back to the logical object location
+    fn logical_path(path: &Path) -> Option<Path> {
+        path.as_ref()
+            .strip_suffix(MANIFEST_SUFFIX)
+            .map(|location| Path::from(location.to_string()))
+    }
+
+    /// Load the manifest for a location, returning None for plain objects
+    async fn read_manifest(
+        inner: &T,
+        location: &Path,
+    ) -> object_store::Result<Option<(ChunkManifest, ObjectMeta)>> {
+        let result = match inner.get(&Self::manifest_path(location)).await {
+            Ok(result) => result,
+            Err(object_store::Error::NotFound { .. }) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let manifest_meta = result.meta.clone();
+        let bytes = result.bytes().await?;
+        let manifest: ChunkManifest =
+            serde_json::from_slice(&bytes).map_err(|e| object_store::Error::Generic {
+                store: "chunked",
+                source: Box::new(e),
+            })?;
+
+        let meta = ObjectMeta {
+            location: location.clone(),
+            last_modified: manifest_meta.last_modified,
+            size: manifest.size,
+            e_tag: manifest_meta.e_tag,
+            version: manifest_meta.version,
+        };
+
+        Ok(Some((manifest, meta)))
+    }
+
+    /// Write the manifest for a location
+    async fn write_manifest(
+        inner: &T,
+        location: &Path,
+        manifest: &ChunkManifest,
+        options: PutOptions,
+    ) -> object_store::Result<PutResult> {
+        let bytes = serde_json::to_vec(manifest).map_err(|e| object_store::Error::Generic {
+            store: "chunked",
+            source: Box::new(e),
+        })?;
+
+        inner
+            .put_opts(
+                &Self::manifest_path(location),
+                Bytes::from(bytes).into(),
+                options,
+            )
+            .await
+    }
+
+    /// Delete the chunks of a generation by index, ignoring ones that are
+    /// already gone
+    async fn delete_chunks(
+        inner: &T,
+        location: &Path,
+        generation: Option<&str>,
+        indices: Range<usize>,
+    ) -> object_store::Result<()> {
+        for index in indices {
+            match inner.delete(&chunk_path(location, generation, index)).await {
+                Ok(()) | Err(object_store::Error::NotFound { .. }) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Delete a path, ignoring it if it does not exist
+    async fn delete_if_exists(inner: &T, location: &Path) -> object_store::Result<()> {
+        match inner.delete(location).await {
+            Ok(()) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Remove the manifest and chunks of a previously chunked object, if any
+    async fn remove_chunked(inner: &T, location: &Path) -> object_store::Result<bool> {
+        match Self::read_manifest(inner, location).await? {
+            Some((manifest, _)) => {
+                Self::delete_if_exists(inner, &Self::manifest_path(location)).await?;
+                Self::delete_chunks(
+                    inner,
+                    location,
+                    manifest.generation.as_deref(),
+                    0..manifest.chunk_sizes.len(),
+                )
+                .await?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Delete what an object's previous write left behind, once the manifest
+    /// of the new write has replaced its manifest
+    async fn remove_superseded(
+        inner: &T,
+        location: &Path,
+        previous: Option<ChunkManifest>,
+    ) -> object_store::Result<()> {
+        Self::delete_if_exists(inner, location).await?;
+        match previous {
+            Some(previous) => {
+                Self::delete_chunks(
+                    inner,
+                    location,
+                    previous.generation.as_deref(),
+                    0..previous.chunk_sizes.len(),
+                )
+                .await
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Fail with AlreadyExists when PutMode::Create targets an existing logical object
+    async fn check_create_mode(&self, location: &Path, mode: &PutMode) -> object_store::Result<()> {
+        if !matches!(mode, PutMode::Create) {
+            return Ok(());
+        }
+
+        match self.head(location).await {
+            Ok(_) => Err(object_store::Error::AlreadyExists {
+                path: location.to_string(),
+                source: "Object already exists".into(),
+            }),
+            Err(object_store::Error::NotFound { .. }) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Turn a listed entry into its logical form, hiding individual chunks
+    async fn logical_meta(inner: &T, meta: ObjectMeta) -> object_store::Result<Option<ObjectMeta>> {
+        if Self::is_chunk_path(&meta.location) {
+            return Ok(None);
+        }
+
+        match Self::logical_path(&meta.location) {
+            Some(location) => Ok(Self::read_manifest(inner, &location)
+                .await?
+                .map(|(_, meta)| meta)),
+            None => Ok(Some(meta)),
+        }
+    }
+}
+
+#[async_trait]
+impl<T: ObjectStore> ObjectStore for ChunkedStore<T> {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        options: PutOptions,
+    ) -> object_store::Result<PutResult> {
+        self.check_create_mode(location, &options.mode).await?;
+
+        if payload.content_length() <= self.threshold {
+            // Small enough to store as-is, drop any chunks left from a previous write
+            let result = self.inner.put_opts(location, payload, options).await?;
+            Self::remove_chunked(&self.inner, location).await?;
+            return Ok(result);
+        }
+
+        let previous = Self::read_manifest(&self.inner, location)
+            .await?
+            .map(|(manifest, _)| manifest);
+
+        let data: Bytes = payload.into();
+        let mut manifest = ChunkManifest {
+            size: data.len() as u64,
+            chunk_sizes: Vec::with_capacity(data.len().div_ceil(self.chunk_size)),
+            generation: Some(next_generation()),
+            attributes: attribute_names(&options.attributes),
+        };
+        for (index, chunk) in data.chunks(self.chunk_size).enumerate() {
+            self.inner
+                .put(
+                    &manifest.chunk_path(location, index),
+                    data.slice_ref(chunk).into(),
+                )
+                .await?;
+            manifest.chunk_sizes.push(chunk.len() as u64);
+        }
+
+        let result = Self::write_manifest(
+            &self.inner,
+            location,
+            &manifest,
+            PutOptions {
+                mode: PutMode::Overwrite,
+                ..options
+            },
+        )
+        .await?;
+
+        // Clean up whatever the previous version of this object left behind
+        Self::remove_superseded(&self.inner, location, previous).await?;
+
+        Ok(result)
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        options: PutMultipartOptions,
+    ) -> object_store::Result<Box<dyn MultipartUpload>> {
+        Ok(Box::new(ChunkedUpload {
+            inner: self.inner.clone(),
+            location: location.clone(),
+            chunk_size: self.chunk_size,
+            chunk_sizes: Vec::new(),
+            generation: next_generation(),
+            options,
+        }))
+    }
+
+    async fn get_opts(
+        &self,
+        location: &Path,
+        options: GetOptions,
+    ) -> object_store::Result<GetResult> {
+        let (manifest, meta) = match Self::read_manifest(&self.inner, location).await? {
+            Some(found) => found,
+            None => return self.inner.get_opts(location, options).await,
+        };
+
+        options.check_preconditions(&meta)?;
+
+        let range = match &options.range {
+            Some(range) => range
+                .as_range(meta.size)
+                .map_err(|e| object_store::Error::Generic {
+                    store: "chunked",
+                    source: Box::new(e),
+                })?,
+            None => 0..meta.size,
+        };
+
+        let payload = if options.head {
+            stream::empty().boxed()
+        } else {
+            let inner = self.inner.clone();
+            let chunks: Vec<(Path, Range<u64>)> = manifest
+                .chunks_for_range(&range)
+                .into_iter()
+                .map(|(index, chunk_range)| (manifest.chunk_path(location, index), chunk_range))
+                .collect();
+            stream::iter(chunks)
+                .then(move |(chunk_path, chunk_range)| {
+                    let inner = inner.clone();
+                    async move { inner.get_range(&chunk_path, chunk_range).await }
+                })
+                .boxed()
+        };
+
+        Ok(GetResult {
+            payload: GetResultPayload::Stream(payload),
+            meta,
+            range,
+            attributes: manifest.object_attributes(),
+        })
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<u64>) -> object_store::Result<Bytes> {
+        let options = GetOptions {
+            range: Some(GetRange::Bounded(range)),
+            ..Default::default()
+        };
+        self.get_opts(location, options).await?.bytes().await
+    }
+
+    async fn head(&self, location: &Path) -> object_store::Result<ObjectMeta> {
+        match Self::read_manifest(&self.inner, location).await? {
+            Some((_, meta)) => Ok(meta),
+            None => self.inner.head(location).await,
+        }
+    }
+
+    async fn delete(&self, location: &Path) -> object_store::Result<()> {
+        if Self::remove_chunked(&self.inner, location).await? {
+            // The logical object existed, a stray plain copy is not an error
+            return Self::delete_if_exists(&self.inner, location).await;
+        }
+
+        self.inner.delete(location).await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'static, object_store::Result<ObjectMeta>> {
+        let inner = self.inner.clone();
+        self.inner
+            .list(prefix)
+            .and_then(move |meta| {
+                let inner = inner.clone();
+                async move { Self::logical_meta(&inner, meta).await }
+            })
+            .try_filter_map(|meta| async move { Ok(meta) })
+            .boxed()
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> object_store::Result<ListResult> {
+        let result = self.inner.list_with_delimiter(prefix).await?;
+
+        let mut objects = Vec::with_capacity(result.objects.len());
+        for meta in result.objects {
+            if let Some(meta) = Self::logical_meta(&self.inner, meta).await? {
+                objects.push(meta);
+            }
+        }
+
+        Ok(ListResult {
+            common_prefixes: result.common_prefixes,
+            objects,
+        })
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        let (manifest, _) = match Self::read_manifest(&self.inner, from).await? {
+            Some(found) => found,
+            None => {
+                self.inner.copy(from, to).await?;
+                Self::remove_chunked(&self.inner, to).await?;
+                return Ok(());
+            }
+        };
+
+        let previous = Self::read_manifest(&self.inner, to)
+            .await?
+            .map(|(manifest, _)| manifest);
+
+        let copied = ChunkManifest {
+            generation: Some(next_generation()),
+            ..manifest.clone()
+        };
+        for index in 0..manifest.chunk_sizes.len() {
+            self.inner
+                .copy(
+                    &manifest.chunk_path(from, index),
+                    &copied.chunk_path(to, index),
+                )
+                .await?;
+        }
+        Self::write_manifest(&self.inner, to, &copied, PutOptions::default()).await?;
+
+        Self::remove_superseded(&self.inner, to, previous).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        // Not atomic across the manifest and the plain object, but good enough
+        // for the single-writer deployments this decorator targets
+        self.check_create_mode(to, &PutMode::Create).await?;
+        self.copy(from, to).await
+    }
+}
+
+/// Multipart upload that writes each part straight into chunks
+///
+/// The chunks are written under a generation of their own, so the object
+/// being replaced stays readable until the upload completes.
+#[derive(Debug)]
+struct ChunkedUpload<T: ObjectStore> {
+    inner: Arc<T>,
+    location: Path,
+    chunk_size: usize,
+    chunk_sizes: Vec<u64>,
+    generation: String,
+    options: PutMultipartOptions,
+}
+
+#[async_trait]
+impl<T: ObjectStore> MultipartUpload for ChunkedUpload<T> {
+    fn put_part(&mut self, data: PutPayload) -> UploadPart {
+        let data: Bytes = data.into();
+
+        // Chunk indices are assigned up front so parts may complete in any order
+        let mut writes = Vec::new();
+        for chunk in data.chunks(self.chunk_size) {
+            let index = self.chunk_sizes.len();
+            self.chunk_sizes.push(chunk.len() as u64);
+            writes.push((
+                chunk_path(&self.location, Some(&self.generation), index),
+                data.slice_ref(chunk),
+            ));
+        }
+
+        let inner = self.inner.clone();
+        Box::pin(async move {
+            for (path, bytes) in writes {
+                inner.put(&path, bytes.into()).await?;
+            }
+            Ok(())
+        })
+    }
+
+    async fn complete(&mut self) -> object_store::Result<PutResult> {
+        let previous = ChunkedStore::<T>::read_manifest(&self.inner, &self.location)
+            .await?
+            .map(|(manifest, _)| manifest);
+
+        let manifest = ChunkManifest {
+            size: self.chunk_sizes.iter().sum(),
+            chunk_sizes: self.chunk_sizes.clone(),
+            generation: Some(self.generation.clone()),
+            attributes: attribute_names(&self.options.attributes),
+        };
+
+        let options = PutOptions {
+            tags: self.options.tags.clone(),
+            attributes: self.options.attributes.clone(),
+            extensions: self.options.extensions.clone(),
+            ..Default::default()
+        };
+        let result =
+            ChunkedStore::<T>::write_manifest(&self.inner, &self.location, &manifest, options)
+                .await?;
+
+        ChunkedStore::<T>::remove_superseded(&self.inner, &self.location, previous).await?;
+
+        Ok(result)
+    }
+
+    async fn abort(&mut self) -> object_store::Result<()> {
+        ChunkedStore::<T>::delete_chunks(
+            &self.inner,
+            &self.location,
+            Some(&self.generation),
+            0..self.chunk_sizes.len(),
+        )
+        .await
+    }
+}
+
+/// Path of a single chunk of a logical object, written under `generation`
+fn chunk_path(location: &Path, generation: Option<&str>, index: usize) -> Path {
+    match generation {
+        Some(generation) => Path::from(format!(
+            "{}{}{}_{:06}",
+            location.as_ref(),
+            CHUNK_MARKER,
+            generation,
+            index
+        )),
+        None => Path::from(format!("{}{}{:06}", location.as_ref(), CHUNK_MARKER, index)),
+    }
+}
+
+/// A new generation to write an object's chunks under
+fn next_generation() -> String {
+    UuidGenerator.next_id()
+}
+
+/// Name of an attribute as stored in a manifest
+///
+/// Attributes added to object_store after this was written have no name and
+/// are not kept.
+fn attribute_name(attribute: &Attribute) -> Option<String> {
+    let name = match attribute {
+        Attribute::ContentDisposition => "Content-Disposition",
+        Attribute::ContentEncoding => "Content-Encoding",
+        Attribute::ContentLanguage => "Content-Language",
+        Attribute::ContentType => "Content-Type",
+        Attribute::CacheControl => "Cache-Control",
+        Attribute::StorageClass => "Storage-Class",
+        Attribute::Metadata(key) => return Some(format!("{}{}", METADATA_PREFIX, key)),
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
+/// The attribute stored in a manifest under `name`
+fn attribute_from_name(name: &str) -> Attribute {
+    match name {
+        "Content-Disposition" => Attribute::ContentDisposition,
+        "Content-Encoding" => Attribute::ContentEncoding,
+        "Content-Language" => Attribute::ContentLanguage,
+        "Content-Type" => Attribute::ContentType,
+        "Cache-Control" => Attribute::CacheControl,
+        "Storage-Class" => Attribute::StorageClass,
+        name => Attribute::Metadata(
+            name.strip_prefix(METADATA_PREFIX)
+                .unwrap_or(name)
+                .to_string()
+                .into(),
+        ),
+    }
+}
+
+/// Attributes by the names they are stored under in a manifest
+fn attribute_names(attributes: &Attributes) -> BTreeMap<String, String> {
+    attributes
+        .iter()
+        .filter_map(|(attribute, value)| {
+            attribute_name(attribute).map(|name| (name, value.as_ref().to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use object_store::memory::InMemory;
+
+    fn test_store() -> ChunkedStore<InMemory> {
+        ChunkedStore::with_chunk_size(InMemory::new(), 4)
+    }
+
+    #[tokio::test]
+    async fn test_small_objects_pass_through() {
+        let store = test_store();
+        let path = Path::from("small.txt");
+
+        store.put(&path, Bytes::from("abc").into()).await.unwrap();
+
+        assert!(store.inner.head(&path).await.is_ok());
+        let data = store.get(&path).await.unwrap().bytes().await.unwrap();
+        assert_eq!(data, Bytes::from("abc"));
+    }
+
+    #[tokio::test]
+    async fn test_large_objects_are_chunked_and_reassembled() {
+        let store = test_store();
+        let path = Path::from("large.txt");
+
+        store
+            .put(&path, Bytes::from("hello chunked world").into())
+            .await
+            .unwrap();
+
+        // Nothing is stored at the logical path itself
+        assert!(store.inner.head(&path).await.is_err());
+
+        let meta = store.head(&path).await.unwrap();
+        assert_eq!(meta.size, 19);
+
+        let data = store.get(&path).await.unwrap().bytes().await.unwrap();
+        assert_eq!(data, Bytes::from("hello chunked world"));
+
+        // Ranged reads spanning several chunks
+        let range = store.get_range(&path, 3..13).await.unwrap();
+        assert_eq!(range, Bytes::from("lo chunked"));
+    }
+
+    #[tokio::test]
+    async fn test_list_hides_chunks() {
+        let store = test_store();
+        store
+            .put(&Path::from("dir/large"), Bytes::from("0123456789").into())
+            .await
+            .unwrap();
+        store
+            .put(&Path::from("dir/small"), Bytes::from("01").into())
+            .await
+            .unwrap();
+
+        let mut listed: Vec<(String, u64)> = store
+            .list(Some(&Path::from("dir")))
+            .map_ok(|meta| (meta.location.to_string(), meta.size))
+            .try_collect()
+            .await
+            .unwrap();
+        listed.sort();
+
+        assert_eq!(
+            listed,
+            vec![("dir/large".to_string(), 10), ("dir/small".to_string(), 2)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_overwrite_and_delete_clean_up_chunks() {
+        let store = test_store();
+        let path = Path::from("object");
+
+        store
+            .put(&path, Bytes::from("0123456789").into())
+            .await
+            .unwrap();
+        store.put(&path, Bytes::from("01").into()).await.unwrap();
+
+        let stored: Vec<ObjectMeta> = store.inner.list(None).try_collect().await.unwrap();
+        assert_eq!(stored.len(), 1);
+
+        store
+            .put(&path, Bytes::from("0123456789").into())
+            .await
+            .unwrap();
+        store.delete(&path).await.unwrap();
+
+        let stored: Vec<ObjectMeta> = store.inner.list(None).try_collect().await.unwrap();
+        assert!(stored.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_aborted_upload_leaves_the_object_intact() {
+        let store = test_store();
+        let path = Path::from("object");
+        store
+            .put(&path, Bytes::from("0123456789").into())
+            .await
+            .unwrap();
+
+        let mut upload = store.put_multipart(&path).await.unwrap();
+        upload
+            .put_part(Bytes::from("abcdefgh").into())
+            .await
+            .unwrap();
+        upload.abort().await.unwrap();
+
+        let data = store.get(&path).await.unwrap().bytes().await.unwrap();
+        assert_eq!(data, Bytes::from("0123456789"));
+    }
+
+    #[tokio::test]
+    async fn test_attributes_are_kept() {
+        let store = test_store();
+        let path = Path::from("object");
+        let attributes = Attributes::from_iter([
+            (Attribute::ContentType, "text/plain"),
+            (Attribute::Metadata("origin".into()), "test"),
+        ]);
+        let options = PutOptions {
+            attributes: attributes.clone(),
+            ..Default::default()
+        };
+        store
+            .put_opts(&path, Bytes::from("0123456789").into(), options)
+            .await
+            .unwrap();
+
+        let result = store.get(&path).await.unwrap();
+        assert_eq!(result.attributes, attributes);
+    }
+
+    #[tokio::test]
+    async fn test_multipart_upload() {
+        let store = test_store();
+        let path = Path::from("multipart");
+
+        let mut upload = store.put_multipart(&path).await.unwrap();
+        upload
+            .put_part(Bytes::from("0123456").into())
+            .await
+            .unwrap();
+        upload.put_part(Bytes::from("789").into()).await.unwrap();
+        upload.complete().await.unwrap();
+
+        let data = store.get(&path).await.unwrap().bytes().await.unwrap();
+        assert_eq!(data, Bytes::from("0123456789"));
+    }
+}
@@ -0,0 +1,400 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::{
+    FutureExt, StreamExt,
+    future::{BoxFuture, Shared},
+    stream::{self, BoxStream},
+};
+use object_store::{
+    Attributes, GetOptions, GetResult, GetResultPayload, ListResult, MultipartUpload, ObjectMeta,
+    ObjectStore, PutMultipartOptions, PutOptions, PutPayload, PutResult, UploadPart, path::Path,
+};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// An object fetched once for every GET that was waiting on it
+#[derive(Debug)]
+struct Fetched {
+    meta: ObjectMeta,
+    attributes: Attributes,
+    data: Bytes,
+}
+
+/// How a coalesced fetch ended, shared by all its waiters
+#[derive(Debug, Clone)]
+enum Outcome {
+    Fetched(Arc<Fetched>),
+    /// The object is larger than may be buffered, so each waiter fetches it
+    /// on its own
+    TooLarge,
+    NotFound(Path),
+    Failed(String),
+}
+
+type InFlight = Shared<BoxFuture<'static, Outcome>>;
+
+/// Fetches in progress, by location, with the generation that started them
+type InFlightFetches = Arc<Mutex<HashMap<Path, (u64, InFlight)>>>;
+
+/// An ObjectStore decorator that coalesces concurrent GETs of the same object
+///
+/// While a plain GET of an object, without a range, version or conditions, is
+/// being fetched from the inner store, further plain GETs of it wait for that
+/// fetch and are answered from its data instead of fetching the object again.
+/// This keeps a burst of requests for a cold, popular object to one backend
+/// request. The object is buffered to be shared, so objects larger than
+/// `max_object_size` are not: their waiters then fetch them on their own.
+///
+/// Writes through this store stop later GETs from joining a fetch started
+/// before them, so a GET issued after a write completes never sees older data.
+#[derive(Debug)]
+pub struct CoalescingStore<T: ObjectStore> {
+    /// The underlying object store
+    inner: Arc<T>,
+
+    in_flight: InFlightFetches,
+
+    /// Generation of the next fetch, telling a finished fetch whether the
+    /// entry for its location is still its own
+    next_generation: AtomicU64,
+
+    /// Largest object shared between waiters, in bytes
+    max_object_size: u64,
+}
+
+impl<T: ObjectStore> std::fmt::Display for CoalescingStore<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "CoalescingStore({})", self.inner)
+    }
+}
+
+impl<T: ObjectStore> CoalescingStore<T> {
+    /// Coalesce concurrent GETs of objects of up to `max_object_size` bytes
+    pub fn new(store: T, max_object_size: u64) -> Self {
+        CoalescingStore {
+            inner: Arc::new(store),
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            next_generation: AtomicU64::new(0),
+            max_object_size,
+        }
+    }
+
+    /// Whether a GET with these options may share another's result
+    fn can_coalesce(options: &GetOptions) -> bool {
+        options.if_match.is_none()
+            && options.if_none_match.is_none()
+            && options.if_modified_since.is_none()
+            && options.if_unmodified_since.is_none()
+            && options.range.is_none()
+            && options.version.is_none()
+            && !options.head
+    }
+
+    /// The fetch of `location` in progress, starting one if there is none
+    fn join(&self, location: &Path) -> InFlight {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if let Some((_, fetch)) = in_flight.get(location) {
+            return fetch.clone();
+        }
+
+        let generation = self.next_generation.fetch_add(1, Ordering::Relaxed);
+        let fetch = Self::fetch(
+            self.inner.clone(),
+            self.in_flight.clone(),
+            location.clone(),
+            generation,
+            self.max_object_size,
+        )
+        .boxed()
+        .shared();
+        in_flight.insert(location.clone(), (generation, fetch.clone()));
+        fetch
+    }
+
+    async fn fetch(
+        inner: Arc<T>,
+        in_flight: InFlightFetches,
+        location: Path,
+        generation: u64,
+        max_object_size: u64,
+    ) -> Outcome {
+        let outcome = match inner.get(&location).await {
+            Ok(result) if result.meta.size > max_object_size => Outcome::TooLarge,
+            Ok(result) => {
+                let meta = result.meta.clone();
+                let attributes = result.attributes.clone();
+                match result.bytes().await {
+                    Ok(data) => Outcome::Fetched(Arc::new(Fetched {
+                        meta,
+                        attributes,
+                        data,
+                    })),
+                    Err(e) => Outcome::Failed(e.to_string()),
+                }
+            }
+            Err(object_store::Error::NotFound { .. }) => Outcome::NotFound(location.clone()),
+            Err(e) => Outcome::Failed(e.to_string()),
+        };
+
+        // Later GETs fetch afresh, unless a write already replaced the entry
+        let mut in_flight = in_flight.lock().unwrap();
+        if in_flight
+            .get(&location)
+            .is_some_and(|(current, _)| *current == generation)
+        {
+            in_flight.remove(&location);
+        }
+        outcome
+    }
+
+    /// Stop later GETs of `location` from joining a fetch started before now
+    fn forget(&self, location: &Path) {
+        self.in_flight.lock().unwrap().remove(location);
+    }
+}
+
+#[async_trait]
+impl<T: ObjectStore> ObjectStore for CoalescingStore<T> {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        options: PutOptions,
+    ) -> object_store::Result<PutResult> {
+        let result = self.inner.put_opts(location, payload, options).await;
+        self.forget(location);
+        result
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        options: PutMultipartOptions,
+    ) -> object_store::Result<Box<dyn MultipartUpload>> {
+        let upload = self.inner.put_multipart_opts(location, options).await?;
+        Ok(Box::new(ForgettingUpload {
+            inner: upload,
+            location: location.clone(),
+            in_flight: self.in_flight.clone(),
+        }))
+    }
+
+    async fn get_opts(
+        &self,
+        location: &Path,
+        options: GetOptions,
+    ) -> object_store::Result<GetResult> {
+        if !Self::can_coalesce(&options) {
+            return self.inner.get_opts(location, options).await;
+        }
+
+        match self.join(location).await {
+            Outcome::Fetched(fetched) => {
+                let data = fetched.data.clone();
+                Ok(GetResult {
+                    payload: GetResultPayload::Stream(stream::once(async { Ok(data) }).boxed()),
+                    range: 0..fetched.meta.size,
+                    meta: fetched.meta.clone(),
+                    attributes: fetched.attributes.clone(),
+                })
+            }
+            Outcome::TooLarge => self.inner.get_opts(location, options).await,
+            Outcome::NotFound(path) => Err(object_store::Error::NotFound {
+                path: path.to_string(),
+                source: "object not found".into(),
+            }),
+            Outcome::Failed(message) => Err(object_store::Error::Generic {
+                store: "CoalescingStore",
+                source: message.into(),
+            }),
+        }
+    }
+
+    async fn delete(&self, location: &Path) -> object_store::Result<()> {
+        let result = self.inner.delete(location).await;
+        self.forget(location);
+        result
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'static, object_store::Result<ObjectMeta>> {
+        self.inner.list(prefix)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> object_store::Result<ListResult> {
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        let result = self.inner.copy(from, to).await;
+        self.forget(to);
+        result
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        let result = self.inner.copy_if_not_exists(from, to).await;
+        self.forget(to);
+        result
+    }
+}
+
+/// Multipart upload that stops later GETs of its location from joining
+/// earlier fetches once it replaces the object
+#[derive(Debug)]
+struct ForgettingUpload {
+    inner: Box<dyn MultipartUpload>,
+    location: Path,
+    in_flight: InFlightFetches,
+}
+
+#[async_trait]
+impl MultipartUpload for ForgettingUpload {
+    fn put_part(&mut self, data: PutPayload) -> UploadPart {
+        self.inner.put_part(data)
+    }
+
+    async fn complete(&mut self) -> object_store::Result<PutResult> {
+        let result = self.inner.complete().await;
+        self.in_flight.lock().unwrap().remove(&self.location);
+        result
+    }
+
+    async fn abort(&mut self) -> object_store::Result<()> {
+        self.inner.abort().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use object_store::memory::InMemory;
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Duration;
+
+    /// Store that counts GETs and answers them slowly, so they overlap
+    #[derive(Debug)]
+    struct SlowStore {
+        inner: InMemory,
+        gets: AtomicUsize,
+    }
+
+    impl std::fmt::Display for SlowStore {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "SlowStore")
+        }
+    }
+
+    #[async_trait]
+    impl ObjectStore for SlowStore {
+        async fn put_opts(
+            &self,
+            location: &Path,
+            payload: PutPayload,
+            options: PutOptions,
+        ) -> object_store::Result<PutResult> {
+            self.inner.put_opts(location, payload, options).await
+        }
+
+        async fn put_multipart_opts(
+            &self,
+            location: &Path,
+            options: PutMultipartOptions,
+        ) -> object_store::Result<Box<dyn MultipartUpload>> {
+            self.inner.put_multipart_opts(location, options).await
+        }
+
+        async fn get_opts(
+            &self,
+            location: &Path,
+            options: GetOptions,
+        ) -> object_store::Result<GetResult> {
+            self.gets.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            self.inner.get_opts(location, options).await
+        }
+
+        async fn delete(&self, location: &Path) -> object_store::Result<()> {
+            self.inner.delete(location).await
+        }
+
+        fn list(
+            &self,
+            prefix: Option<&Path>,
+        ) -> BoxStream<'static, object_store::Result<ObjectMeta>> {
+            self.inner.list(prefix)
+        }
+
+        async fn list_with_delimiter(
+            &self,
+            prefix: Option<&Path>,
+        ) -> object_store::Result<ListResult> {
+            self.inner.list_with_delimiter(prefix).await
+        }
+
+        async fn copy(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+            self.inner.copy(from, to).await
+        }
+
+        async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+            self.inner.copy_if_not_exists(from, to).await
+        }
+    }
+
+    fn coalescing_store(
+        max_object_size: u64,
+    ) -> (Arc<SlowStore>, CoalescingStore<Arc<dyn ObjectStore>>) {
+        let inner = Arc::new(SlowStore {
+            inner: InMemory::new(),
+            gets: AtomicUsize::new(0),
+        });
+        let store = CoalescingStore::new(inner.clone() as Arc<dyn ObjectStore>, max_object_size);
+        (inner, store)
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_gets_share_one_fetch() {
+        let (inner, store) = coalescing_store(1024);
+        let path = Path::from("viral/asset.png");
+        store
+            .put(&path, Bytes::from_static(b"popular").into())
+            .await
+            .unwrap();
+
+        let gets = (0..10).map(|_| async { store.get(&path).await?.bytes().await });
+        for data in futures::future::join_all(gets).await {
+            assert_eq!(&data.unwrap()[..], b"popular");
+        }
+        assert_eq!(inner.gets.load(Ordering::SeqCst), 1);
+
+        // Once the fetch is done, the next GET goes to the store again
+        store.get(&path).await.unwrap();
+        assert_eq!(inner.gets.load(Ordering::SeqCst), 2);
+
+        // Missing objects are reported to every waiter
+        let missing = Path::from("viral/missing.png");
+        let (first, second) = tokio::join!(store.get(&missing), store.get(&missing));
+        assert!(matches!(first, Err(object_store::Error::NotFound { .. })));
+        assert!(matches!(second, Err(object_store::Error::NotFound { .. })));
+        assert_eq!(inner.gets.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_large_and_ranged_gets_are_not_shared() {
+        let (inner, store) = coalescing_store(4);
+        let path = Path::from("viral/video.mp4");
+        store
+            .put(&path, Bytes::from_static(b"too large").into())
+            .await
+            .unwrap();
+
+        let (first, second) = tokio::join!(store.get(&path), store.get(&path));
+        assert_eq!(&first.unwrap().bytes().await.unwrap()[..], b"too large");
+        assert_eq!(&second.unwrap().bytes().await.unwrap()[..], b"too large");
+        // The shared attempt, then one fetch per waiter
+        assert_eq!(inner.gets.load(Ordering::SeqCst), 3);
+
+        let ranged = store.get_range(&path, 0..3).await.unwrap();
+        assert_eq!(&ranged[..], b"too");
+        assert_eq!(inner.gets.load(Ordering::SeqCst), 4);
+    }
+}
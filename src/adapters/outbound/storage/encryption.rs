@@ -0,0 +1,767 @@
+use aes_gcm::{
+    Aes256Gcm, Key, Nonce,
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+};
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use object_store::{
+    Attributes, GetOptions, GetRange, GetResult, GetResultPayload, ListResult, MultipartUpload,
+    ObjectMeta, ObjectStore, PutMode, PutMultipartOptions, PutOptions, PutPayload, PutResult,
+    UpdateVersion, UploadPart, path::Path,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Suffix of the envelope holding an object's wrapped data key
+const ENVELOPE_SUFFIX: &str = ".envelope";
+
+/// Length of an AES-GCM nonce
+const NONCE_LEN: usize = 12;
+
+/// Length of an AES-GCM authentication tag
+const TAG_LEN: usize = 16;
+
+/// Bytes added to every object by encryption
+const OVERHEAD: u64 = (NONCE_LEN + TAG_LEN) as u64;
+
+/// How many times a rotation rereads an envelope that changed while it was
+/// being rewrapped
+const REWRAP_ATTEMPTS: usize = 3;
+
+/// Data key of an object, wrapped by one of the master keys
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Envelope {
+    /// Identifier of the master key the data key is wrapped with
+    key_id: String,
+
+    /// Nonce used to wrap the data key
+    nonce: Vec<u8>,
+
+    /// The encrypted data key
+    wrapped_key: Vec<u8>,
+
+    /// Data key of the data this object replaced, which is still what is
+    /// stored until the new data lands after the envelope
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    previous: Option<Box<Envelope>>,
+}
+
+/// Master keys known to the store and the one new objects are encrypted under
+#[derive(Default)]
+struct Keyring {
+    active: String,
+    keys: HashMap<String, [u8; 32]>,
+}
+
+/// State of a master key rotation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RotationState {
+    Idle,
+    Running,
+    Completed,
+    Failed,
+}
+
+/// Progress of the most recent master key rotation
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyRotationStatus {
+    pub state: RotationState,
+    pub target_key_id: Option<String>,
+    pub objects_total: u64,
+    pub objects_rewrapped: u64,
+    /// Objects already wrapped under the target key
+    pub objects_skipped: u64,
+    pub objects_failed: u64,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+impl Default for KeyRotationStatus {
+    fn default() -> Self {
+        Self {
+            state: RotationState::Idle,
+            target_key_id: None,
+            objects_total: 0,
+            objects_rewrapped: 0,
+            objects_skipped: 0,
+            objects_failed: 0,
+            started_at: None,
+            finished_at: None,
+            last_error: None,
+        }
+    }
+}
+
+/// Why a master key rotation could not be started
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum KeyRotationError {
+    #[error("A key rotation is already running")]
+    AlreadyRunning,
+    #[error("Unknown master key '{0}'")]
+    UnknownKey(String),
+    /// The store couldn't list the envelopes to rewrap
+    #[error("Failed to start key rotation: {0}")]
+    Backend(String),
+}
+
+/// Control over master key rotation, exposed through the admin API
+#[async_trait]
+pub trait KeyRotationControl: Send + Sync + 'static {
+    /// Make `key_id` the active master key and start rewrapping every data key under it
+    async fn start_rotation(&self, key_id: &str) -> Result<(), KeyRotationError>;
+
+    /// Progress of the current or most recent rotation
+    async fn rotation_status(&self) -> KeyRotationStatus;
+}
+
+fn generic_error(message: impl Into<String>) -> object_store::Error {
+    object_store::Error::Generic {
+        store: "encrypted",
+        source: message.into().into(),
+    }
+}
+
+/// An ObjectStore decorator providing server-side envelope encryption
+///
+/// Every object is encrypted with AES-256-GCM under its own random data key. The
+/// data key is wrapped with the active master key and kept in a small envelope
+/// object next to the data, so rotating the master key only rewrites envelopes
+/// and never the object data itself.
+pub struct EncryptedStore<T: ObjectStore> {
+    inner: Arc<T>,
+    keyring: Arc<RwLock<Keyring>>,
+    rotation: Arc<RwLock<KeyRotationStatus>>,
+}
+
+impl<T: ObjectStore> Clone for EncryptedStore<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            keyring: self.keyring.clone(),
+            rotation: self.rotation.clone(),
+        }
+    }
+}
+
+impl<T: ObjectStore> std::fmt::Debug for EncryptedStore<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EncryptedStore")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: ObjectStore> std::fmt::Display for EncryptedStore<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "EncryptedStore({})", self.inner)
+    }
+}
+
+impl<T: ObjectStore> EncryptedStore<T> {
+    /// Create an encrypted store whose objects are encrypted under the given master key
+    pub fn new(store: T, key_id: impl Into<String>, master_key: [u8; 32]) -> Self {
+        let key_id = key_id.into();
+        let keyring = Keyring {
+            active: key_id.clone(),
+            keys: HashMap::from([(key_id, master_key)]),
+        };
+
+        Self {
+            inner: Arc::new(store),
+            keyring: Arc::new(RwLock::new(keyring)),
+            rotation: Arc::new(RwLock::new(KeyRotationStatus::default())),
+        }
+    }
+
+    /// Register an additional master key, e.g. one that older objects are still wrapped with
+    pub async fn add_master_key(&self, key_id: impl Into<String>, master_key: [u8; 32]) {
+        self.keyring
+            .write()
+            .await
+            .keys
+            .insert(key_id.into(), master_key);
+    }
+
+    /// Identifier of the master key new objects are encrypted under
+    pub async fn active_key_id(&self) -> String {
+        self.keyring.read().await.active.clone()
+    }
+
+    fn envelope_path(location: &Path) -> Path {
+        Path::from(format!("{}{}", location.as_ref(), ENVELOPE_SUFFIX))
+    }
+
+    fn is_envelope_path(location: &Path) -> bool {
+        location.as_ref().ends_with(ENVELOPE_SUFFIX)
+    }
+
+    async fn master_key(&self, key_id: &str) -> object_store::Result<[u8; 32]> {
+        self.keyring
+            .read()
+            .await
+            .keys
+            .get(key_id)
+            .copied()
+            .ok_or_else(|| generic_error(format!("Unknown master key '{}'", key_id)))
+    }
+
+    /// Wrap a data key with the given master key
+    async fn wrap(&self, key_id: &str, data_key: &[u8]) -> object_store::Result<Envelope> {
+        let master = self.master_key(key_id).await?;
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&master));
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let wrapped_key = cipher
+            .encrypt(&nonce, data_key)
+            .map_err(|_| generic_error("Failed to wrap data key"))?;
+
+        Ok(Envelope {
+            key_id: key_id.to_string(),
+            nonce: nonce.to_vec(),
+            wrapped_key,
+            previous: None,
+        })
+    }
+
+    /// Recover the data key from an envelope
+    async fn unwrap(&self, envelope: &Envelope) -> object_store::Result<Vec<u8>> {
+        if envelope.nonce.len() != NONCE_LEN {
+            return Err(generic_error("Malformed key envelope"));
+        }
+
+        let master = self.master_key(&envelope.key_id).await?;
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&master));
+        cipher
+            .decrypt(
+                Nonce::from_slice(&envelope.nonce),
+                envelope.wrapped_key.as_slice(),
+            )
+            .map_err(|_| generic_error("Failed to unwrap data key"))
+    }
+
+    async fn read_envelope(&self, location: &Path) -> object_store::Result<Envelope> {
+        Ok(self.read_envelope_version(location).await?.0)
+    }
+
+    /// Read an envelope along with the version of it that was read
+    async fn read_envelope_version(
+        &self,
+        location: &Path,
+    ) -> object_store::Result<(Envelope, UpdateVersion)> {
+        let result = self
+            .inner
+            .get(&Self::envelope_path(location))
+            .await
+            .map_err(|e| match e {
+                object_store::Error::NotFound { source, .. } => object_store::Error::NotFound {
+                    path: location.to_string(),
+                    source,
+                },
+                e => e,
+            })?;
+        let version = UpdateVersion {
+            e_tag: result.meta.e_tag.clone(),
+            version: result.meta.version.clone(),
+        };
+        let bytes = result.bytes().await?;
+        let envelope = serde_json::from_slice(&bytes).map_err(|e| generic_error(e.to_string()))?;
+        Ok((envelope, version))
+    }
+
+    async fn write_envelope(
+        &self,
+        location: &Path,
+        envelope: &Envelope,
+        mode: PutMode,
+    ) -> object_store::Result<()> {
+        let bytes = serde_json::to_vec(envelope).map_err(|e| generic_error(e.to_string()))?;
+        self.inner
+            .put_opts(
+                &Self::envelope_path(location),
+                Bytes::from(bytes).into(),
+                mode.into(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Encrypt and store an object under a fresh data key
+    async fn write_object(
+        &self,
+        location: &Path,
+        data: &[u8],
+        options: PutOptions,
+    ) -> object_store::Result<PutResult> {
+        let data_key = Aes256Gcm::generate_key(OsRng);
+        let cipher = Aes256Gcm::new(&data_key);
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, data)
+            .map_err(|_| generic_error("Failed to encrypt object"))?;
+
+        let mut stored = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+        stored.extend_from_slice(&nonce);
+        stored.extend_from_slice(&ciphertext);
+
+        let key_id = self.active_key_id().await;
+        let mut envelope = self.wrap(&key_id, &data_key).await?;
+
+        // The envelope is written first, keeping the data key of the data it
+        // replaces, so readers can decrypt whichever data is stored while the
+        // new data is written, and data is never stored without its key
+        envelope.previous = match self.read_envelope(location).await {
+            Ok(mut previous) => {
+                previous.previous = None;
+                Some(Box::new(previous))
+            }
+            Err(object_store::Error::NotFound { .. }) => None,
+            Err(e) => return Err(e),
+        };
+        self.write_envelope(location, &envelope, PutMode::Overwrite)
+            .await?;
+        self.inner
+            .put_opts(location, Bytes::from(stored).into(), options)
+            .await
+    }
+
+    /// Read and decrypt a whole object
+    async fn read_object(&self, location: &Path) -> object_store::Result<(ObjectMeta, Bytes)> {
+        let envelope = self.read_envelope(location).await?;
+
+        let result = self.inner.get(location).await?;
+        let meta = Self::logical_meta(result.meta.clone());
+        let stored = result.bytes().await?;
+        if stored.len() < NONCE_LEN + TAG_LEN {
+            return Err(generic_error(format!(
+                "{} is too short to be encrypted",
+                location
+            )));
+        }
+
+        // Data being replaced is still under the previous data key
+        for envelope in std::iter::once(&envelope).chain(envelope.previous.as_deref()) {
+            let data_key = self.unwrap(envelope).await?;
+            let cipher = Aes256Gcm::new_from_slice(&data_key)
+                .map_err(|_| generic_error("Malformed data key"))?;
+            if let Ok(plaintext) = cipher.decrypt(
+                Nonce::from_slice(&stored[..NONCE_LEN]),
+                &stored[NONCE_LEN..],
+            ) {
+                return Ok((meta, Bytes::from(plaintext)));
+            }
+        }
+        Err(generic_error(format!("Failed to decrypt {}", location)))
+    }
+
+    /// Report the plaintext size of a stored object
+    fn logical_meta(mut meta: ObjectMeta) -> ObjectMeta {
+        meta.size = meta.size.saturating_sub(OVERHEAD);
+        meta
+    }
+
+    /// Rewrap an object's data keys under `key_id`, returning false if they already were
+    ///
+    /// The envelope is only replaced if it is still the one that was read, so
+    /// an object rewritten meanwhile keeps the data key of its new data; the
+    /// rewritten envelope is then read and rewrapped again.
+    pub async fn rewrap(&self, location: &Path, key_id: &str) -> object_store::Result<bool> {
+        let mut attempts = 1;
+        loop {
+            let (envelope, version) = self.read_envelope_version(location).await?;
+            let rewrapped = std::iter::once(&envelope)
+                .chain(envelope.previous.as_deref())
+                .all(|envelope| envelope.key_id == key_id);
+            if rewrapped {
+                return Ok(false);
+            }
+
+            let mut rewrapped = self.wrap(key_id, &self.unwrap(&envelope).await?).await?;
+            if let Some(previous) = &envelope.previous {
+                let data_key = self.unwrap(previous).await?;
+                rewrapped.previous = Some(Box::new(self.wrap(key_id, &data_key).await?));
+            }
+            match self
+                .write_envelope(location, &rewrapped, PutMode::Update(version))
+                .await
+            {
+                Ok(()) => return Ok(true),
+                Err(object_store::Error::Precondition { .. }) if attempts < REWRAP_ATTEMPTS => {
+                    attempts += 1
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Locations of every envelope in the store
+    async fn envelope_paths(&self) -> object_store::Result<Vec<Path>> {
+        self.inner
+            .list(None)
+            .try_filter_map(|meta| async move {
+                Ok(Self::is_envelope_path(&meta.location).then_some(meta.location))
+            })
+            .try_collect()
+            .await
+    }
+
+    /// Rewrap the given envelopes under `key_id`, recording progress
+    async fn run_rotation(&self, key_id: String, envelopes: Vec<Path>) {
+        for envelope in envelopes {
+            let location = Path::from(
+                envelope
+                    .as_ref()
+                    .strip_suffix(ENVELOPE_SUFFIX)
+                    .unwrap_or_default()
+                    .to_string(),
+            );
+            let result = self.rewrap(&location, &key_id).await;
+
+            let mut status = self.rotation.write().await;
+            match result {
+                Ok(true) => status.objects_rewrapped += 1,
+                // Deleted since the envelopes were listed
+                Ok(false) | Err(object_store::Error::NotFound { .. }) => {
+                    status.objects_skipped += 1
+                }
+                Err(e) => {
+                    status.objects_failed += 1;
+                    status.last_error = Some(format!("{}: {}", location, e));
+                }
+            }
+        }
+
+        let mut status = self.rotation.write().await;
+        status.state = if status.objects_failed == 0 {
+            RotationState::Completed
+        } else {
+            RotationState::Failed
+        };
+        status.finished_at = Some(Utc::now());
+    }
+}
+
+#[async_trait]
+impl<T: ObjectStore> KeyRotationControl for EncryptedStore<T> {
+    async fn start_rotation(&self, key_id: &str) -> Result<(), KeyRotationError> {
+        let previous_key_id = {
+            let mut status = self.rotation.write().await;
+            if status.state == RotationState::Running {
+                return Err(KeyRotationError::AlreadyRunning);
+            }
+
+            let mut keyring = self.keyring.write().await;
+            if !keyring.keys.contains_key(key_id) {
+                return Err(KeyRotationError::UnknownKey(key_id.to_string()));
+            }
+
+            *status = KeyRotationStatus {
+                state: RotationState::Running,
+                target_key_id: Some(key_id.to_string()),
+                started_at: Some(Utc::now()),
+                ..Default::default()
+            };
+            std::mem::replace(&mut keyring.active, key_id.to_string())
+        };
+
+        // Objects written from here on are already under the new key, so the
+        // envelopes listed now are all that need rewrapping
+        let envelopes = match self.envelope_paths().await {
+            Ok(envelopes) => envelopes,
+            Err(e) => {
+                self.keyring.write().await.active = previous_key_id;
+                let mut status = self.rotation.write().await;
+                status.state = RotationState::Failed;
+                status.last_error = Some(e.to_string());
+                status.finished_at = Some(Utc::now());
+                return Err(KeyRotationError::Backend(e.to_string()));
+            }
+        };
+        self.rotation.write().await.objects_total = envelopes.len() as u64;
+
+        let store = self.clone();
+        let key_id = key_id.to_string();
+        tokio::spawn(async move { store.run_rotation(key_id, envelopes).await });
+        Ok(())
+    }
+
+    async fn rotation_status(&self) -> KeyRotationStatus {
+        self.rotation.read().await.clone()
+    }
+}
+
+#[async_trait]
+impl<T: ObjectStore> ObjectStore for EncryptedStore<T> {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        options: PutOptions,
+    ) -> object_store::Result<PutResult> {
+        let data: Bytes = payload.into();
+        self.write_object(location, &data, options).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        options: PutMultipartOptions,
+    ) -> object_store::Result<Box<dyn MultipartUpload>> {
+        Ok(Box::new(EncryptedUpload {
+            store: self.clone(),
+            location: location.clone(),
+            parts: Vec::new(),
+            options,
+        }))
+    }
+
+    async fn get_opts(
+        &self,
+        location: &Path,
+        options: GetOptions,
+    ) -> object_store::Result<GetResult> {
+        if options.head {
+            let meta = self.head(location).await?;
+            options.check_preconditions(&meta)?;
+            return Ok(GetResult {
+                payload: GetResultPayload::Stream(stream::empty().boxed()),
+                range: 0..meta.size,
+                meta,
+                attributes: Attributes::default(),
+            });
+        }
+
+        let (meta, data) = self.read_object(location).await?;
+        options.check_preconditions(&meta)?;
+
+        let range = match &options.range {
+            Some(range) => range
+                .as_range(meta.size)
+                .map_err(|e| generic_error(e.to_string()))?,
+            None => 0..meta.size,
+        };
+        let data = data.slice(range.start as usize..range.end as usize);
+
+        Ok(GetResult {
+            payload: GetResultPayload::Stream(stream::once(async move { Ok(data) }).boxed()),
+            meta,
+            range,
+            attributes: Attributes::default(),
+        })
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<u64>) -> object_store::Result<Bytes> {
+        let options = GetOptions {
+            range: Some(GetRange::Bounded(range)),
+            ..Default::default()
+        };
+        self.get_opts(location, options).await?.bytes().await
+    }
+
+    async fn head(&self, location: &Path) -> object_store::Result<ObjectMeta> {
+        self.inner.head(location).await.map(Self::logical_meta)
+    }
+
+    async fn delete(&self, location: &Path) -> object_store::Result<()> {
+        self.inner.delete(location).await?;
+        match self.inner.delete(&Self::envelope_path(location)).await {
+            Ok(()) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'static, object_store::Result<ObjectMeta>> {
+        self.inner
+            .list(prefix)
+            .try_filter_map(|meta| async move {
+                Ok((!Self::is_envelope_path(&meta.location)).then(|| Self::logical_meta(meta)))
+            })
+            .boxed()
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> object_store::Result<ListResult> {
+        let result = self.inner.list_with_delimiter(prefix).await?;
+        Ok(ListResult {
+            common_prefixes: result.common_prefixes,
+            objects: result
+                .objects
+                .into_iter()
+                .filter(|meta| !Self::is_envelope_path(&meta.location))
+                .map(Self::logical_meta)
+                .collect(),
+        })
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        // The copy shares the data key, so the envelope is copied along with the data
+        self.inner.copy(from, to).await?;
+        self.inner
+            .copy(&Self::envelope_path(from), &Self::envelope_path(to))
+            .await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        self.inner.copy_if_not_exists(from, to).await?;
+        self.inner
+            .copy(&Self::envelope_path(from), &Self::envelope_path(to))
+            .await
+    }
+}
+
+/// Multipart upload that collects parts in memory and encrypts the object on completion
+#[derive(Debug)]
+struct EncryptedUpload<T: ObjectStore> {
+    store: EncryptedStore<T>,
+    location: Path,
+    parts: Vec<Bytes>,
+    options: PutMultipartOptions,
+}
+
+#[async_trait]
+impl<T: ObjectStore> MultipartUpload for EncryptedUpload<T> {
+    fn put_part(&mut self, data: PutPayload) -> UploadPart {
+        self.parts.push(data.into());
+        Box::pin(async { Ok(()) })
+    }
+
+    async fn complete(&mut self) -> object_store::Result<PutResult> {
+        let data: Vec<u8> = self.parts.concat();
+        let options = PutOptions {
+            mode: PutMode::Overwrite,
+            tags: self.options.tags.clone(),
+            attributes: self.options.attributes.clone(),
+            extensions: self.options.extensions.clone(),
+        };
+        self.store
+            .write_object(&self.location, &data, options)
+            .await
+    }
+
+    async fn abort(&mut self) -> object_store::Result<()> {
+        self.parts.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use object_store::memory::InMemory;
+
+    fn test_store() -> EncryptedStore<InMemory> {
+        EncryptedStore::new(InMemory::new(), "key-1", [1u8; 32])
+    }
+
+    #[tokio::test]
+    async fn test_objects_are_encrypted_at_rest() {
+        let store = test_store();
+        let path = Path::from("secret.txt");
+        let data = Bytes::from("attack at dawn");
+
+        store.put(&path, data.clone().into()).await.unwrap();
+
+        let raw = store.inner.get(&path).await.unwrap().bytes().await.unwrap();
+        assert!(!raw.windows(data.len()).any(|w| w == data.as_ref()));
+
+        assert_eq!(store.get(&path).await.unwrap().bytes().await.unwrap(), data);
+        assert_eq!(store.head(&path).await.unwrap().size, data.len() as u64);
+        assert_eq!(
+            store.get_range(&path, 7..9).await.unwrap(),
+            Bytes::from("at")
+        );
+
+        let listed: Vec<ObjectMeta> = store.list(None).try_collect().await.unwrap();
+        assert_eq!(listed.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_key_rotation_rewraps_envelopes() {
+        let store = test_store();
+        let path = Path::from("object");
+        store.put(&path, Bytes::from("data").into()).await.unwrap();
+
+        store.add_master_key("key-2", [2u8; 32]).await;
+        assert_eq!(
+            store.start_rotation("missing").await,
+            Err(KeyRotationError::UnknownKey("missing".to_string()))
+        );
+        store.start_rotation("key-2").await.unwrap();
+
+        while store.rotation_status().await.state == RotationState::Running {
+            tokio::task::yield_now().await;
+        }
+
+        let status = store.rotation_status().await;
+        assert_eq!(status.state, RotationState::Completed);
+        assert_eq!(status.objects_rewrapped, 1);
+        assert_eq!(store.read_envelope(&path).await.unwrap().key_id, "key-2");
+
+        // The old master key is no longer needed to read the object
+        store.keyring.write().await.keys.remove("key-1");
+        assert_eq!(
+            store.get(&path).await.unwrap().bytes().await.unwrap(),
+            Bytes::from("data")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rewrap_keeps_the_key_of_data_written_meanwhile() {
+        let store = test_store();
+        let path = Path::from("object");
+        store.put(&path, Bytes::from("first").into()).await.unwrap();
+        store.add_master_key("key-2", [2u8; 32]).await;
+
+        // A rotation read the envelope, then the object was rewritten
+        let (envelope, version) = store.read_envelope_version(&path).await.unwrap();
+        store
+            .put(&path, Bytes::from("second").into())
+            .await
+            .unwrap();
+
+        let data_key = store.unwrap(&envelope).await.unwrap();
+        let stale = store.wrap("key-2", &data_key).await.unwrap();
+        let result = store
+            .write_envelope(&path, &stale, PutMode::Update(version))
+            .await;
+        assert!(matches!(
+            result,
+            Err(object_store::Error::Precondition { .. })
+        ));
+
+        assert!(store.rewrap(&path, "key-2").await.unwrap());
+        store.keyring.write().await.keys.remove("key-1");
+        assert_eq!(
+            store.get(&path).await.unwrap().bytes().await.unwrap(),
+            Bytes::from("second")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replaced_data_stays_readable_until_the_new_data_lands() {
+        let store = test_store();
+        let path = Path::from("object");
+        store.put(&path, Bytes::from("first").into()).await.unwrap();
+        let first = store.inner.get(&path).await.unwrap().bytes().await.unwrap();
+
+        store
+            .put(&path, Bytes::from("second").into())
+            .await
+            .unwrap();
+        assert_eq!(
+            store.get(&path).await.unwrap().bytes().await.unwrap(),
+            Bytes::from("second")
+        );
+
+        // The envelope of the overwrite is written, but not yet its data
+        store.inner.put(&path, first.into()).await.unwrap();
+        assert_eq!(
+            store.get(&path).await.unwrap().bytes().await.unwrap(),
+            Bytes::from("first")
+        );
+    }
+}
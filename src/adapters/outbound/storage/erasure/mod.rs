@@ -0,0 +1,622 @@
+//! Experimental erasure-coded store spreading every object across several backends
+//!
+//! Each object is split into data and parity shards with Reed–Solomon coding and
+//! shard `i` is stored on backend `i` under the object's own path. Objects remain
+//! readable while up to `parity_shards` backends are unavailable or have lost
+//! their shard.
+
+pub mod reed_solomon;
+
+pub use reed_solomon::ReedSolomon;
+
+use async_trait::async_trait;
+use bytes::{BufMut, Bytes, BytesMut};
+use chrono::{DateTime, Utc};
+use futures::future::join_all;
+use futures::stream::{self, BoxStream, StreamExt};
+use object_store::{
+    Attributes, GetOptions, GetRange, GetResult, GetResultPayload, ListResult, MultipartUpload,
+    ObjectMeta, ObjectStore, PutMode, PutMultipartOptions, PutOptions, PutPayload, PutResult,
+    UploadPart, path::Path,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Marks the start of every shard header
+const SHARD_MAGIC: &[u8; 4] = b"RSEC";
+
+/// Length of the header written in front of every shard
+const SHARD_HEADER_LEN: usize = 24;
+
+/// Header describing a stored shard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ShardHeader {
+    data_shards: u8,
+    parity_shards: u8,
+    index: u8,
+    /// Identifies the write a shard belongs to, so shards of different versions are never mixed
+    generation: u64,
+    /// Size of the original object in bytes
+    size: u64,
+}
+
+impl ShardHeader {
+    fn encode(&self, payload: &[u8]) -> Bytes {
+        let mut buf = BytesMut::with_capacity(SHARD_HEADER_LEN + payload.len());
+        buf.put_slice(SHARD_MAGIC);
+        buf.put_u8(self.data_shards);
+        buf.put_u8(self.parity_shards);
+        buf.put_u8(self.index);
+        buf.put_u8(0);
+        buf.put_u64_le(self.generation);
+        buf.put_u64_le(self.size);
+        buf.put_slice(payload);
+        buf.freeze()
+    }
+
+    fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() < SHARD_HEADER_LEN || &data[..4] != SHARD_MAGIC {
+            return None;
+        }
+        Some(Self {
+            data_shards: data[4],
+            parity_shards: data[5],
+            index: data[6],
+            generation: u64::from_le_bytes(data[8..16].try_into().ok()?),
+            size: u64::from_le_bytes(data[16..24].try_into().ok()?),
+        })
+    }
+}
+
+/// A shard read back from one backend
+struct StoredShard {
+    header: ShardHeader,
+    meta: ObjectMeta,
+    payload: Option<Bytes>,
+}
+
+fn generic_error(message: String) -> object_store::Error {
+    object_store::Error::Generic {
+        store: "erasure",
+        source: message.into(),
+    }
+}
+
+/// ObjectStore that erasure-codes objects across N backends
+///
+/// With `data_shards` = K and N backends, every object is stored as K data shards
+/// and N - K parity shards. Writes succeed once at least K shards are stored and
+/// reads reconstruct the object from any K shards of the newest write.
+///
+/// This store is experimental: objects are encoded in memory, so it is meant for
+/// small to medium sized objects.
+#[derive(Debug, Clone)]
+pub struct ErasureCodedStore {
+    backends: Vec<Arc<dyn ObjectStore>>,
+    codec: ReedSolomon,
+}
+
+impl std::fmt::Display for ErasureCodedStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ErasureCodedStore({}+{} across {} backends)",
+            self.codec.data_shards(),
+            self.codec.parity_shards(),
+            self.backends.len()
+        )
+    }
+}
+
+impl ErasureCodedStore {
+    /// Create a store splitting objects into `data_shards` data shards across `backends`
+    ///
+    /// Every backend beyond the first `data_shards` holds a parity shard, so the
+    /// store tolerates `backends.len() - data_shards` unavailable backends.
+    pub fn new(
+        backends: Vec<Arc<dyn ObjectStore>>,
+        data_shards: usize,
+    ) -> object_store::Result<Self> {
+        if data_shards == 0 || backends.len() <= data_shards {
+            return Err(generic_error(format!(
+                "Erasure coding needs more backends than data shards, got {} backends for {} data shards",
+                backends.len(),
+                data_shards
+            )));
+        }
+        if backends.len() > u8::MAX as usize {
+            return Err(generic_error(format!(
+                "At most {} backends are supported",
+                u8::MAX
+            )));
+        }
+
+        let codec =
+            ReedSolomon::new(data_shards, backends.len() - data_shards).map_err(generic_error)?;
+        Ok(Self { backends, codec })
+    }
+
+    /// Number of data shards each object is split into
+    pub fn data_shards(&self) -> usize {
+        self.codec.data_shards()
+    }
+
+    /// Number of parity shards, i.e. how many backends may be lost
+    pub fn parity_shards(&self) -> usize {
+        self.codec.parity_shards()
+    }
+
+    /// Encode and store an object on every backend
+    async fn write_object(&self, location: &Path, data: &[u8]) -> object_store::Result<PutResult> {
+        let generation = Utc::now()
+            .timestamp_nanos_opt()
+            .map(|nanos| nanos as u64)
+            .unwrap_or_default();
+
+        let writes = self
+            .codec
+            .encode(data)
+            .into_iter()
+            .enumerate()
+            .map(|(index, payload)| {
+                let header = ShardHeader {
+                    data_shards: self.codec.data_shards() as u8,
+                    parity_shards: self.codec.parity_shards() as u8,
+                    index: index as u8,
+                    generation,
+                    size: data.len() as u64,
+                };
+                let backend = self.backends[index].clone();
+                let shard = header.encode(&payload);
+                async move { backend.put(location, shard.into()).await }
+            });
+
+        let results = join_all(writes).await;
+        let stored = results.iter().filter(|r| r.is_ok()).count();
+        if stored < self.codec.data_shards() {
+            let cause = results
+                .into_iter()
+                .find_map(|r| r.err())
+                .map(|e| e.to_string())
+                .unwrap_or_default();
+            return Err(generic_error(format!(
+                "Only {} of {} shards of {} were stored: {}",
+                stored,
+                self.backends.len(),
+                location,
+                cause
+            )));
+        }
+
+        Ok(PutResult {
+            e_tag: Some(format!("{:x}", generation)),
+            version: None,
+        })
+    }
+
+    /// Read the shard header, and optionally the payload, from every backend
+    async fn read_shards(&self, location: &Path, with_payload: bool) -> Vec<Option<StoredShard>> {
+        let reads = self.backends.iter().map(|backend| async move {
+            let result = if with_payload {
+                backend.get(location).await.ok()?
+            } else {
+                let options = GetOptions {
+                    range: Some(GetRange::Bounded(0..SHARD_HEADER_LEN as u64)),
+                    ..Default::default()
+                };
+                backend.get_opts(location, options).await.ok()?
+            };
+            let meta = result.meta.clone();
+            let data = result.bytes().await.ok()?;
+            let header = ShardHeader::decode(&data)?;
+            Some(StoredShard {
+                header,
+                meta,
+                payload: with_payload.then(|| data.slice(SHARD_HEADER_LEN..)),
+            })
+        });
+        join_all(reads).await
+    }
+
+    /// Pick the newest write that still has enough shards to be reconstructed
+    fn newest_complete(
+        &self,
+        location: &Path,
+        shards: Vec<Option<StoredShard>>,
+    ) -> object_store::Result<(ObjectMeta, ShardHeader, Vec<Option<StoredShard>>)> {
+        let mut generations: BTreeMap<u64, Vec<Option<StoredShard>>> = BTreeMap::new();
+        for (backend, shard) in shards.into_iter().enumerate() {
+            let Some(shard) = shard else { continue };
+            if shard.header.index as usize != backend
+                || shard.header.data_shards as usize != self.codec.data_shards()
+            {
+                continue;
+            }
+            let slots = generations
+                .entry(shard.header.generation)
+                .or_insert_with(|| (0..self.backends.len()).map(|_| None).collect());
+            slots[backend] = Some(shard);
+        }
+
+        let Some((_, shards)) = generations
+            .into_iter()
+            .rev()
+            .find(|(_, shards)| shards.iter().flatten().count() >= self.codec.data_shards())
+        else {
+            return Err(object_store::Error::NotFound {
+                path: location.to_string(),
+                source: "Not enough shards available to reconstruct the object".into(),
+            });
+        };
+
+        let first = shards
+            .iter()
+            .flatten()
+            .next()
+            .expect("generation has shards");
+        let header = first.header;
+        let last_modified: DateTime<Utc> = shards
+            .iter()
+            .flatten()
+            .map(|shard| shard.meta.last_modified)
+            .max()
+            .unwrap_or(first.meta.last_modified);
+        let meta = ObjectMeta {
+            location: location.clone(),
+            last_modified,
+            size: header.size,
+            e_tag: Some(format!("{:x}", header.generation)),
+            version: None,
+        };
+
+        Ok((meta, header, shards))
+    }
+
+    /// Reconstruct a whole object from its shards
+    async fn read_object(&self, location: &Path) -> object_store::Result<(ObjectMeta, Bytes)> {
+        let shards = self.read_shards(location, true).await;
+        let (meta, header, shards) = self.newest_complete(location, shards)?;
+
+        let payloads: Vec<Option<Vec<u8>>> = shards
+            .into_iter()
+            .map(|shard| shard.and_then(|s| s.payload).map(|p| p.to_vec()))
+            .collect();
+        let data = self
+            .codec
+            .decode(&payloads, header.size as usize)
+            .map_err(generic_error)?;
+
+        Ok((meta, Bytes::from(data)))
+    }
+
+    async fn check_create_mode(&self, location: &Path, mode: &PutMode) -> object_store::Result<()> {
+        if !matches!(mode, PutMode::Create) {
+            return Ok(());
+        }
+
+        match self.head(location).await {
+            Ok(_) => Err(object_store::Error::AlreadyExists {
+                path: location.to_string(),
+                source: "Object already exists".into(),
+            }),
+            Err(object_store::Error::NotFound { .. }) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[async_trait]
+impl ObjectStore for ErasureCodedStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        options: PutOptions,
+    ) -> object_store::Result<PutResult> {
+        self.check_create_mode(location, &options.mode).await?;
+        let data: Bytes = payload.into();
+        self.write_object(location, &data).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        _options: PutMultipartOptions,
+    ) -> object_store::Result<Box<dyn MultipartUpload>> {
+        Ok(Box::new(ErasureCodedUpload {
+            store: self.clone(),
+            location: location.clone(),
+            parts: Vec::new(),
+        }))
+    }
+
+    async fn get_opts(
+        &self,
+        location: &Path,
+        options: GetOptions,
+    ) -> object_store::Result<GetResult> {
+        if options.head {
+            let meta = self.head(location).await?;
+            options.check_preconditions(&meta)?;
+            return Ok(GetResult {
+                payload: GetResultPayload::Stream(stream::empty().boxed()),
+                range: 0..meta.size,
+                meta,
+                attributes: Attributes::default(),
+            });
+        }
+
+        let (meta, data) = self.read_object(location).await?;
+        options.check_preconditions(&meta)?;
+
+        let range = match &options.range {
+            Some(range) => range
+                .as_range(meta.size)
+                .map_err(|e| generic_error(e.to_string()))?,
+            None => 0..meta.size,
+        };
+        let data = data.slice(range.start as usize..range.end as usize);
+
+        Ok(GetResult {
+            payload: GetResultPayload::Stream(stream::once(async move { Ok(data) }).boxed()),
+            meta,
+            range,
+            attributes: Attributes::default(),
+        })
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<u64>) -> object_store::Result<Bytes> {
+        let options = GetOptions {
+            range: Some(GetRange::Bounded(range)),
+            ..Default::default()
+        };
+        self.get_opts(location, options).await?.bytes().await
+    }
+
+    async fn head(&self, location: &Path) -> object_store::Result<ObjectMeta> {
+        let shards = self.read_shards(location, false).await;
+        self.newest_complete(location, shards)
+            .map(|(meta, _, _)| meta)
+    }
+
+    async fn delete(&self, location: &Path) -> object_store::Result<()> {
+        let results = join_all(self.backends.iter().map(|backend| backend.delete(location))).await;
+
+        let mut failures = Vec::new();
+        for result in results {
+            match result {
+                Ok(()) | Err(object_store::Error::NotFound { .. }) => {}
+                Err(e) => failures.push(e),
+            }
+        }
+
+        // Leftover shards on unreachable backends are harmless as long as they are
+        // too few to reconstruct the object, and a later write supersedes them
+        if failures.len() >= self.codec.data_shards() {
+            return Err(failures.remove(0));
+        }
+        Ok(())
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'static, object_store::Result<ObjectMeta>> {
+        let store = self.clone();
+        let prefix = prefix.cloned();
+
+        stream::once(async move {
+            let mut locations = BTreeSet::new();
+            for backend in &store.backends {
+                let mut listing = backend.list(prefix.as_ref());
+                while let Some(meta) = listing.next().await {
+                    // An unreachable backend only hides shards the others also hold
+                    if let Ok(meta) = meta {
+                        locations.insert(meta.location);
+                    }
+                }
+            }
+
+            let mut objects = Vec::with_capacity(locations.len());
+            for location in locations {
+                match store.head(&location).await {
+                    Ok(meta) => objects.push(Ok(meta)),
+                    Err(object_store::Error::NotFound { .. }) => {}
+                    Err(e) => objects.push(Err(e)),
+                }
+            }
+            stream::iter(objects)
+        })
+        .flatten()
+        .boxed()
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> object_store::Result<ListResult> {
+        let mut common_prefixes = BTreeSet::new();
+        let mut locations = BTreeSet::new();
+        let mut reachable = 0;
+        let mut failure = None;
+
+        for backend in &self.backends {
+            match backend.list_with_delimiter(prefix).await {
+                Ok(result) => {
+                    reachable += 1;
+                    common_prefixes.extend(result.common_prefixes);
+                    locations.extend(result.objects.into_iter().map(|meta| meta.location));
+                }
+                Err(e) => failure = Some(e),
+            }
+        }
+
+        if let Some(e) = failure.filter(|_| reachable < self.codec.data_shards()) {
+            return Err(e);
+        }
+
+        let mut objects = Vec::with_capacity(locations.len());
+        for location in locations {
+            match self.head(&location).await {
+                Ok(meta) => objects.push(meta),
+                Err(object_store::Error::NotFound { .. }) => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(ListResult {
+            common_prefixes: common_prefixes.into_iter().collect(),
+            objects,
+        })
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        let (_, data) = self.read_object(from).await?;
+        self.write_object(to, &data).await?;
+        Ok(())
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        self.check_create_mode(to, &PutMode::Create).await?;
+        self.copy(from, to).await
+    }
+}
+
+/// Multipart upload that collects parts in memory and encodes the object on completion
+#[derive(Debug)]
+struct ErasureCodedUpload {
+    store: ErasureCodedStore,
+    location: Path,
+    parts: Vec<Bytes>,
+}
+
+#[async_trait]
+impl MultipartUpload for ErasureCodedUpload {
+    fn put_part(&mut self, data: PutPayload) -> UploadPart {
+        self.parts.push(data.into());
+        Box::pin(async { Ok(()) })
+    }
+
+    async fn complete(&mut self) -> object_store::Result<PutResult> {
+        let data: Vec<u8> = self.parts.concat();
+        self.store.write_object(&self.location, &data).await
+    }
+
+    async fn abort(&mut self) -> object_store::Result<()> {
+        self.parts.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::TryStreamExt;
+    use object_store::memory::InMemory;
+
+    /// Number of shards stored on each backend
+    async fn shard_counts(store: &ErasureCodedStore) -> Vec<usize> {
+        let mut counts = Vec::new();
+        for backend in &store.backends {
+            let listed: Vec<ObjectMeta> = backend.list(None).try_collect().await.unwrap();
+            counts.push(listed.len());
+        }
+        counts
+    }
+
+    fn test_store(backends: usize, data_shards: usize) -> ErasureCodedStore {
+        let backends = (0..backends)
+            .map(|_| Arc::new(InMemory::new()) as Arc<dyn ObjectStore>)
+            .collect();
+        ErasureCodedStore::new(backends, data_shards).unwrap()
+    }
+
+    #[test]
+    fn test_rejects_invalid_layout() {
+        let backends: Vec<Arc<dyn ObjectStore>> = vec![Arc::new(InMemory::new())];
+        assert!(ErasureCodedStore::new(backends, 1).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_put_spreads_shards_and_get_reassembles() {
+        let store = test_store(6, 4);
+        let path = Path::from("dir/object");
+        let data = Bytes::from("erasure coded objects survive lost backends");
+
+        store.put(&path, data.clone().into()).await.unwrap();
+
+        let counts = shard_counts(&store).await;
+        assert!(counts.iter().all(|count| *count == 1));
+
+        assert_eq!(store.get(&path).await.unwrap().bytes().await.unwrap(), data);
+        assert_eq!(store.head(&path).await.unwrap().size, data.len() as u64);
+        assert_eq!(
+            store.get_range(&path, 8..13).await.unwrap(),
+            data.slice(8..13)
+        );
+
+        let listed: Vec<ObjectMeta> = store
+            .list(Some(&Path::from("dir")))
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].size, data.len() as u64);
+    }
+
+    #[tokio::test]
+    async fn test_reconstructs_with_lost_backends() {
+        let store = test_store(6, 4);
+        let path = Path::from("object");
+        let data = Bytes::from("0123456789abcdefghijklmnopqrstuvwxyz");
+        store.put(&path, data.clone().into()).await.unwrap();
+
+        // Losing as many backends as there are parity shards is survivable
+        store.backends[0].delete(&path).await.unwrap();
+        store.backends[3].delete(&path).await.unwrap();
+        assert_eq!(store.get(&path).await.unwrap().bytes().await.unwrap(), data);
+
+        // One more and the object is gone
+        store.backends[5].delete(&path).await.unwrap();
+        assert!(matches!(
+            store.get(&path).await,
+            Err(object_store::Error::NotFound { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_stale_shards_are_not_mixed_with_newer_writes() {
+        let store = test_store(3, 2);
+        let path = Path::from("object");
+
+        store
+            .put(&path, Bytes::from("first version").into())
+            .await
+            .unwrap();
+        let stale = store.backends[2]
+            .get(&path)
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+
+        store
+            .put(&path, Bytes::from("second version").into())
+            .await
+            .unwrap();
+        store.backends[2].put(&path, stale.into()).await.unwrap();
+
+        let data = store.get(&path).await.unwrap().bytes().await.unwrap();
+        assert_eq!(data, Bytes::from("second version"));
+    }
+
+    #[tokio::test]
+    async fn test_delete_removes_all_shards() {
+        let store = test_store(3, 2);
+        let path = Path::from("object");
+        store.put(&path, Bytes::from("data").into()).await.unwrap();
+
+        store.delete(&path).await.unwrap();
+        assert!(shard_counts(&store).await.iter().all(|count| *count == 0));
+        assert!(matches!(
+            store.head(&path).await,
+            Err(object_store::Error::NotFound { .. })
+        ));
+    }
+}
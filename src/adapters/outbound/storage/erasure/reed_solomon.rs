@@ -0,0 +1,254 @@
+//! Systematic Reed–Solomon erasure code over GF(2^8)
+//!
+//! Data is split into `data_shards` equally sized shards and `parity_shards`
+//! parity shards are computed from them with a Cauchy matrix. Any `data_shards`
+//! of the resulting shards are enough to recover the original data.
+
+/// Log and exponent tables for GF(2^8) with the polynomial x^8 + x^4 + x^3 + x^2 + 1
+const TABLES: ([u8; 512], [u8; 256]) = build_tables();
+
+const fn build_tables() -> ([u8; 512], [u8; 256]) {
+    let mut exp = [0u8; 512];
+    let mut log = [0u8; 256];
+    let mut x: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = x as u8;
+        exp[i + 255] = x as u8;
+        log[x as usize] = i as u8;
+        x <<= 1;
+        if x & 0x100 != 0 {
+            x ^= 0x11d;
+        }
+        i += 1;
+    }
+    (exp, log)
+}
+
+fn gf_mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    let (exp, log) = &TABLES;
+    exp[log[a as usize] as usize + log[b as usize] as usize]
+}
+
+fn gf_inv(a: u8) -> u8 {
+    debug_assert!(a != 0, "zero has no inverse");
+    let (exp, log) = &TABLES;
+    exp[255 - log[a as usize] as usize]
+}
+
+/// Invert a square matrix with Gauss–Jordan elimination, returning None if it is singular
+fn invert(mut matrix: Vec<Vec<u8>>) -> Option<Vec<Vec<u8>>> {
+    let n = matrix.len();
+    let mut inverse: Vec<Vec<u8>> = (0..n)
+        .map(|i| (0..n).map(|j| u8::from(i == j)).collect())
+        .collect();
+
+    for col in 0..n {
+        let pivot = (col..n).find(|&row| matrix[row][col] != 0)?;
+        matrix.swap(col, pivot);
+        inverse.swap(col, pivot);
+
+        let scale = gf_inv(matrix[col][col]);
+        for j in 0..n {
+            matrix[col][j] = gf_mul(matrix[col][j], scale);
+            inverse[col][j] = gf_mul(inverse[col][j], scale);
+        }
+
+        for row in 0..n {
+            let factor = matrix[row][col];
+            if row == col || factor == 0 {
+                continue;
+            }
+            for j in 0..n {
+                matrix[row][j] ^= gf_mul(factor, matrix[col][j]);
+                inverse[row][j] ^= gf_mul(factor, inverse[col][j]);
+            }
+        }
+    }
+
+    Some(inverse)
+}
+
+/// Reed–Solomon codec for a fixed number of data and parity shards
+#[derive(Debug, Clone)]
+pub struct ReedSolomon {
+    data_shards: usize,
+    parity_shards: usize,
+    /// Coefficients of each parity shard, one row per parity shard
+    parity_matrix: Vec<Vec<u8>>,
+}
+
+impl ReedSolomon {
+    /// Create a codec, failing if the shard counts cannot be represented in GF(2^8)
+    pub fn new(data_shards: usize, parity_shards: usize) -> Result<Self, String> {
+        if data_shards == 0 {
+            return Err("At least one data shard is required".to_string());
+        }
+        if data_shards + parity_shards > 256 {
+            return Err(format!(
+                "At most 256 shards are supported, got {}",
+                data_shards + parity_shards
+            ));
+        }
+
+        // Cauchy matrix 1 / (x_i + y_j) with x_i and y_j drawn from disjoint sets,
+        // which keeps every square submatrix of [I; C] invertible
+        let parity_matrix = (0..parity_shards)
+            .map(|i| {
+                (0..data_shards)
+                    .map(|j| gf_inv((data_shards + i) as u8 ^ j as u8))
+                    .collect()
+            })
+            .collect();
+
+        Ok(Self {
+            data_shards,
+            parity_shards,
+            parity_matrix,
+        })
+    }
+
+    pub fn data_shards(&self) -> usize {
+        self.data_shards
+    }
+
+    pub fn parity_shards(&self) -> usize {
+        self.parity_shards
+    }
+
+    pub fn total_shards(&self) -> usize {
+        self.data_shards + self.parity_shards
+    }
+
+    /// Size of each shard for an object of `len` bytes
+    pub fn shard_len(&self, len: usize) -> usize {
+        len.div_ceil(self.data_shards)
+    }
+
+    /// Split data into data shards followed by parity shards
+    pub fn encode(&self, data: &[u8]) -> Vec<Vec<u8>> {
+        let shard_len = self.shard_len(data.len());
+        let mut shards: Vec<Vec<u8>> = (0..self.data_shards)
+            .map(|i| {
+                let start = (i * shard_len).min(data.len());
+                let end = (start + shard_len).min(data.len());
+                let mut shard = data[start..end].to_vec();
+                shard.resize(shard_len, 0);
+                shard
+            })
+            .collect();
+
+        for row in &self.parity_matrix {
+            let mut parity = vec![0u8; shard_len];
+            for (coefficient, shard) in row.iter().zip(&shards) {
+                for (out, byte) in parity.iter_mut().zip(shard) {
+                    *out ^= gf_mul(*coefficient, *byte);
+                }
+            }
+            shards.push(parity);
+        }
+
+        shards
+    }
+
+    /// Recover the original `len` bytes from any `data_shards` of the shards
+    ///
+    /// `shards` holds every shard by index, with `None` for those that are unavailable.
+    pub fn decode(&self, shards: &[Option<Vec<u8>>], len: usize) -> Result<Vec<u8>, String> {
+        let shard_len = self.shard_len(len);
+        let available: Vec<usize> = shards
+            .iter()
+            .enumerate()
+            .filter(|(_, shard)| shard.as_ref().is_some_and(|s| s.len() == shard_len))
+            .map(|(index, _)| index)
+            .take(self.data_shards)
+            .collect();
+
+        if available.len() < self.data_shards {
+            return Err(format!(
+                "Only {} of the {} shards needed are available",
+                available.len(),
+                self.data_shards
+            ));
+        }
+
+        let mut data = Vec::with_capacity(self.data_shards * shard_len);
+        if available.iter().enumerate().all(|(i, index)| i == *index) {
+            // Every data shard is intact, no arithmetic needed
+            for shard in shards.iter().take(self.data_shards).flatten() {
+                data.extend_from_slice(shard);
+            }
+        } else {
+            let matrix = available
+                .iter()
+                .map(|&index| {
+                    if index < self.data_shards {
+                        (0..self.data_shards)
+                            .map(|j| u8::from(j == index))
+                            .collect()
+                    } else {
+                        self.parity_matrix[index - self.data_shards].clone()
+                    }
+                })
+                .collect();
+            let decode_matrix =
+                invert(matrix).ok_or_else(|| "Shard matrix is not invertible".to_string())?;
+
+            for row in &decode_matrix {
+                let mut shard = vec![0u8; shard_len];
+                for (coefficient, index) in row.iter().zip(&available) {
+                    let source = shards[*index].as_ref().expect("shard is available");
+                    for (out, byte) in shard.iter_mut().zip(source) {
+                        *out ^= gf_mul(*coefficient, *byte);
+                    }
+                }
+                data.extend_from_slice(&shard);
+            }
+        }
+
+        data.truncate(len);
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_inverse() {
+        for a in 1..=255u8 {
+            assert_eq!(gf_mul(a, gf_inv(a)), 1);
+        }
+    }
+
+    #[test]
+    fn test_recovers_from_any_missing_shards() {
+        let codec = ReedSolomon::new(4, 2).unwrap();
+        let data: Vec<u8> = (0..1001u32).map(|i| (i * 31 % 251) as u8).collect();
+        let shards = codec.encode(&data);
+        assert_eq!(shards.len(), 6);
+
+        // Every combination of two lost shards
+        for a in 0..6 {
+            for b in a..6 {
+                let mut available: Vec<Option<Vec<u8>>> =
+                    shards.iter().cloned().map(Some).collect();
+                available[a] = None;
+                available[b] = None;
+                assert_eq!(codec.decode(&available, data.len()).unwrap(), data);
+            }
+        }
+    }
+
+    #[test]
+    fn test_too_many_missing_shards() {
+        let codec = ReedSolomon::new(3, 1).unwrap();
+        let shards = codec.encode(b"hello world");
+        let available = vec![Some(shards[0].clone()), None, None, Some(shards[3].clone())];
+        assert!(codec.decode(&available, 11).is_err());
+    }
+}
@@ -1,6 +1,9 @@
-use crate::domain::{
-    errors::{LifecycleError, StorageError},
-    value_objects::{ObjectKey, VersionId},
+use crate::{
+    adapters::outbound::storage::timeout::OperationTimedOut,
+    domain::{
+        errors::{LifecycleError, StorageError},
+        value_objects::{ObjectKey, VersionId},
+    },
 };
 use std::io;
 use thiserror::Error as ThisError;
@@ -93,6 +96,11 @@ impl From<object_store::Error> for StorageError {
                 message: "Object not modified".to_string(),
                 source: Some(err.to_string()),
             },
+            object_store::Error::Generic { ref source, .. }
+                if source.is::<OperationTimedOut>() =>
+            {
+                StorageError::from(source.downcast_ref::<OperationTimedOut>().unwrap())
+            }
             _ => StorageError::InfrastructureError {
                 message: format!("Object store operation failed: {}", err),
                 source: Some(err.to_string()),
@@ -101,6 +109,15 @@ impl From<object_store::Error> for StorageError {
     }
 }
 
+impl From<&OperationTimedOut> for StorageError {
+    fn from(err: &OperationTimedOut) -> Self {
+        StorageError::Timeout {
+            operation: err.operation.to_string(),
+            timeout: err.timeout,
+        }
+    }
+}
+
 /// Convert infrastructure StoreError to domain StorageError
 impl From<StoreError> for StorageError {
     fn from(err: StoreError) -> Self {
@@ -221,47 +238,3 @@ impl From<serde_json::Error> for LifecycleError {
         }
     }
 }
-
-/// Convert domain StorageError to HTTP status codes for API responses
-impl From<StorageError> for http::StatusCode {
-    fn from(err: StorageError) -> Self {
-        match err {
-            StorageError::ObjectNotFound { .. } | StorageError::VersionNotFound { .. } => {
-                http::StatusCode::NOT_FOUND
-            }
-            StorageError::VersionConflict { .. } => http::StatusCode::CONFLICT,
-            StorageError::QuotaExceeded { .. } => http::StatusCode::INSUFFICIENT_STORAGE,
-            StorageError::InvalidObjectSize { .. }
-            | StorageError::InvalidStorageClass { .. }
-            | StorageError::ValidationError { .. } => http::StatusCode::BAD_REQUEST,
-            StorageError::AccessDenied { .. } => http::StatusCode::FORBIDDEN,
-            StorageError::ObjectAlreadyExists { .. } => http::StatusCode::CONFLICT,
-            StorageError::OperationNotSupported { .. }
-            | StorageError::UnsupportedOperation { .. } => http::StatusCode::NOT_IMPLEMENTED,
-            StorageError::InfrastructureError { .. } | StorageError::InternalError { .. } => {
-                http::StatusCode::INTERNAL_SERVER_ERROR
-            }
-        }
-    }
-}
-
-/// Convert domain LifecycleError to HTTP status codes for API responses
-impl From<LifecycleError> for http::StatusCode {
-    fn from(err: LifecycleError) -> Self {
-        match err {
-            LifecycleError::ValidationFailed { .. }
-            | LifecycleError::InvalidRule { .. }
-            | LifecycleError::ConflictingRules { .. }
-            | LifecycleError::InvalidExpiration { .. }
-            | LifecycleError::InvalidTransition { .. }
-            | LifecycleError::TooManyRules { .. } => http::StatusCode::BAD_REQUEST,
-            LifecycleError::ConfigurationNotFound { .. } | LifecycleError::RuleNotFound { .. } => {
-                http::StatusCode::NOT_FOUND
-            }
-            LifecycleError::ActionFailed { .. }
-            | LifecycleError::ActionExecutionFailed { .. }
-            | LifecycleError::ProcessingError { .. }
-            | LifecycleError::RepositoryError { .. } => http::StatusCode::INTERNAL_SERVER_ERROR,
-        }
-    }
-}
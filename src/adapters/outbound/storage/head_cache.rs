@@ -0,0 +1,289 @@
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use moka::future::Cache;
+use object_store::{
+    GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore,
+    PutMultipartOptions, PutOptions, PutPayload, PutResult, UploadPart, path::Path,
+};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Metadata of an object as last confirmed by the inner store
+#[derive(Debug, Clone)]
+struct CachedHead {
+    meta: ObjectMeta,
+    checked_at: Instant,
+}
+
+/// An ObjectStore decorator that caches object metadata for hot objects
+///
+/// `head` answers, and the metadata returned with fetched objects, are kept per
+/// location. For `time_to_live` after they were last confirmed they answer
+/// `head` without asking the inner store, and conditional GETs whose
+/// preconditions fail against them, such as an `If-None-Match` of the current
+/// ETag, are rejected without a round trip. Older entries with an ETag are
+/// revalidated with a conditional HEAD, which keeps them if the object is
+/// unchanged; entries without one are looked up again.
+///
+/// Writes through this store invalidate the locations they touch, so only
+/// writes made by other processes can be missed, and only until revalidation.
+#[derive(Debug)]
+pub struct HeadCachingStore<T: ObjectStore> {
+    /// The underlying object store
+    inner: Arc<T>,
+
+    heads: Cache<Path, CachedHead>,
+
+    /// How long a confirmed entry is trusted without revalidation
+    time_to_live: Duration,
+}
+
+impl<T: ObjectStore> std::fmt::Display for HeadCachingStore<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HeadCachingStore({})", self.inner)
+    }
+}
+
+impl<T: ObjectStore> HeadCachingStore<T> {
+    /// Cache the metadata of up to `max_capacity` objects, trusting each entry
+    /// for `time_to_live` before revalidating it
+    pub fn new(store: T, max_capacity: u64, time_to_live: Duration) -> Self {
+        HeadCachingStore {
+            inner: Arc::new(store),
+            heads: Cache::builder().max_capacity(max_capacity).build(),
+            time_to_live,
+        }
+    }
+
+    /// Cached metadata of `location` if it was confirmed within the time to live
+    async fn fresh(&self, location: &Path) -> Option<ObjectMeta> {
+        self.heads
+            .get(location)
+            .await
+            .filter(|cached| cached.checked_at.elapsed() < self.time_to_live)
+            .map(|cached| cached.meta)
+    }
+
+    async fn remember(&self, meta: ObjectMeta) {
+        let cached = CachedHead {
+            meta,
+            checked_at: Instant::now(),
+        };
+        self.heads
+            .insert(cached.meta.location.clone(), cached)
+            .await;
+    }
+
+    /// Ask the inner store for the metadata of `location`, revalidating the
+    /// cached entry by its ETag when there is one
+    async fn revalidate(&self, location: &Path) -> object_store::Result<ObjectMeta> {
+        let cached = self.heads.get(location).await.and_then(|cached| {
+            let e_tag = cached.meta.e_tag.clone()?;
+            Some((cached.meta, e_tag))
+        });
+
+        let result = match cached {
+            Some((meta, e_tag)) => {
+                let options = GetOptions {
+                    if_none_match: Some(e_tag),
+                    head: true,
+                    ..Default::default()
+                };
+                match self.inner.get_opts(location, options).await {
+                    Err(object_store::Error::NotModified { .. }) => Ok(meta),
+                    result => result.map(|result| result.meta),
+                }
+            }
+            None => self.inner.head(location).await,
+        };
+
+        match &result {
+            Ok(meta) => self.remember(meta.clone()).await,
+            Err(_) => self.heads.invalidate(location).await,
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl<T: ObjectStore> ObjectStore for HeadCachingStore<T> {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        options: PutOptions,
+    ) -> object_store::Result<PutResult> {
+        let result = self.inner.put_opts(location, payload, options).await;
+        self.heads.invalidate(location).await;
+        result
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        options: PutMultipartOptions,
+    ) -> object_store::Result<Box<dyn MultipartUpload>> {
+        let upload = self.inner.put_multipart_opts(location, options).await?;
+        Ok(Box::new(InvalidatingUpload {
+            inner: upload,
+            location: location.clone(),
+            heads: self.heads.clone(),
+        }))
+    }
+
+    async fn get_opts(
+        &self,
+        location: &Path,
+        options: GetOptions,
+    ) -> object_store::Result<GetResult> {
+        // A conditional GET the object is known to fail needs no round trip
+        if let Some(meta) = self.fresh(location).await {
+            options.check_preconditions(&meta)?;
+        }
+
+        let result = self.inner.get_opts(location, options).await;
+        match &result {
+            Ok(result) => self.remember(result.meta.clone()).await,
+            Err(object_store::Error::NotFound { .. }) => self.heads.invalidate(location).await,
+            Err(_) => {}
+        }
+        result
+    }
+
+    async fn head(&self, location: &Path) -> object_store::Result<ObjectMeta> {
+        match self.fresh(location).await {
+            Some(meta) => Ok(meta),
+            None => self.revalidate(location).await,
+        }
+    }
+
+    async fn delete(&self, location: &Path) -> object_store::Result<()> {
+        let result = self.inner.delete(location).await;
+        self.heads.invalidate(location).await;
+        result
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'static, object_store::Result<ObjectMeta>> {
+        self.inner.list(prefix)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> object_store::Result<ListResult> {
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        let result = self.inner.copy(from, to).await;
+        self.heads.invalidate(to).await;
+        result
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        let result = self.inner.copy_if_not_exists(from, to).await;
+        self.heads.invalidate(to).await;
+        result
+    }
+}
+
+/// Multipart upload that drops the cached metadata of its location once it
+/// replaces the object
+#[derive(Debug)]
+struct InvalidatingUpload {
+    inner: Box<dyn MultipartUpload>,
+    location: Path,
+    heads: Cache<Path, CachedHead>,
+}
+
+#[async_trait]
+impl MultipartUpload for InvalidatingUpload {
+    fn put_part(&mut self, data: PutPayload) -> UploadPart {
+        self.inner.put_part(data)
+    }
+
+    async fn complete(&mut self) -> object_store::Result<PutResult> {
+        let result = self.inner.complete().await;
+        self.heads.invalidate(&self.location).await;
+        result
+    }
+
+    async fn abort(&mut self) -> object_store::Result<()> {
+        self.inner.abort().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use object_store::memory::InMemory;
+
+    fn caching_store(
+        time_to_live: Duration,
+    ) -> (Arc<InMemory>, HeadCachingStore<Arc<dyn ObjectStore>>) {
+        let inner = Arc::new(InMemory::new());
+        let store = HeadCachingStore::new(inner.clone() as Arc<dyn ObjectStore>, 100, time_to_live);
+        (inner, store)
+    }
+
+    #[tokio::test]
+    async fn test_head_is_served_from_cache_until_revalidated() {
+        let (inner, store) = caching_store(Duration::from_secs(60));
+        let path = Path::from("hot/object.txt");
+        store
+            .put(&path, Bytes::from_static(b"first").into())
+            .await
+            .unwrap();
+
+        let cached = store.head(&path).await.unwrap();
+        assert_eq!(cached.size, 5);
+
+        // A write behind the cache's back goes unnoticed while the entry is fresh
+        inner
+            .put(&path, Bytes::from_static(b"second write").into())
+            .await
+            .unwrap();
+        assert_eq!(store.head(&path).await.unwrap().e_tag, cached.e_tag);
+
+        // A conditional GET for the cached ETag is answered from the cache
+        let options = GetOptions {
+            if_none_match: cached.e_tag.clone(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            store.get_opts(&path, options).await,
+            Err(object_store::Error::NotModified { .. })
+        ));
+
+        // Writes through the store are seen immediately
+        store
+            .put(&path, Bytes::from_static(b"third").into())
+            .await
+            .unwrap();
+        assert_ne!(store.head(&path).await.unwrap().e_tag, cached.e_tag);
+        store.delete(&path).await.unwrap();
+        assert!(matches!(
+            store.head(&path).await,
+            Err(object_store::Error::NotFound { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_stale_entries_are_revalidated_by_etag() {
+        let (inner, store) = caching_store(Duration::ZERO);
+        let path = Path::from("hot/object.txt");
+        store
+            .put(&path, Bytes::from_static(b"first").into())
+            .await
+            .unwrap();
+
+        let first = store.head(&path).await.unwrap();
+        assert_eq!(store.head(&path).await.unwrap(), first);
+
+        inner
+            .put(&path, Bytes::from_static(b"second write").into())
+            .await
+            .unwrap();
+        let second = store.head(&path).await.unwrap();
+        assert_ne!(second.e_tag, first.e_tag);
+        assert_eq!(second.size, 12);
+    }
+}
@@ -0,0 +1,295 @@
+use async_trait::async_trait;
+use futures::future::{self, Either};
+use futures::stream::BoxStream;
+use object_store::{
+    GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore,
+    PutMultipartOptions, PutOptions, PutPayload, PutResult, path::Path,
+};
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// An ObjectStore decorator that hedges slow reads against a replica
+///
+/// A GET or HEAD the primary store has not answered within `budget` is sent
+/// to the replica as well, and whichever store succeeds first answers it; the
+/// other request is dropped. Failures only count once both stores have
+/// failed, and the primary's error is reported then, so an object the replica
+/// has not caught up with yet is still found on the primary. Writes and
+/// listings only go to the primary, which the replica is expected to mirror.
+#[derive(Debug)]
+pub struct HedgedStore<T: ObjectStore> {
+    /// The store reads are sent to first, and the only one written to
+    primary: Arc<T>,
+
+    replica: Arc<dyn ObjectStore>,
+
+    /// How long the primary has to answer a read before it is hedged
+    budget: Duration,
+
+    /// Reads that were sent to the replica
+    hedged_reads: AtomicU64,
+}
+
+impl<T: ObjectStore> std::fmt::Display for HedgedStore<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HedgedStore({}, {})", self.primary, self.replica)
+    }
+}
+
+impl<T: ObjectStore> HedgedStore<T> {
+    /// Hedge reads of `primary` that take longer than `budget` against `replica`
+    pub fn new(primary: T, replica: Arc<dyn ObjectStore>, budget: Duration) -> Self {
+        HedgedStore {
+            primary: Arc::new(primary),
+            replica,
+            budget,
+            hedged_reads: AtomicU64::new(0),
+        }
+    }
+
+    /// Number of reads that were sent to the replica
+    pub fn hedged_reads(&self) -> u64 {
+        self.hedged_reads.load(Ordering::Relaxed)
+    }
+
+    /// Await `primary`, racing it against `replica` once the budget is spent
+    ///
+    /// `replica` is only polled, and so only sent, once the read is hedged.
+    async fn hedge<R>(
+        &self,
+        primary: impl Future<Output = object_store::Result<R>>,
+        replica: impl Future<Output = object_store::Result<R>>,
+    ) -> object_store::Result<R> {
+        let mut primary = std::pin::pin!(primary);
+        if let Ok(result) = tokio::time::timeout(self.budget, &mut primary).await {
+            return result;
+        }
+
+        self.hedged_reads.fetch_add(1, Ordering::Relaxed);
+        let replica = std::pin::pin!(replica);
+        match future::select(primary, replica).await {
+            Either::Left((Ok(result), _)) | Either::Right((Ok(result), _)) => Ok(result),
+            Either::Left((Err(error), replica)) => replica.await.map_err(|_| error),
+            Either::Right((Err(_), primary)) => primary.await,
+        }
+    }
+}
+
+#[async_trait]
+impl<T: ObjectStore> ObjectStore for HedgedStore<T> {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        options: PutOptions,
+    ) -> object_store::Result<PutResult> {
+        self.primary.put_opts(location, payload, options).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        options: PutMultipartOptions,
+    ) -> object_store::Result<Box<dyn MultipartUpload>> {
+        self.primary.put_multipart_opts(location, options).await
+    }
+
+    async fn get_opts(
+        &self,
+        location: &Path,
+        options: GetOptions,
+    ) -> object_store::Result<GetResult> {
+        self.hedge(
+            self.primary.get_opts(location, options.clone()),
+            self.replica.get_opts(location, options),
+        )
+        .await
+    }
+
+    async fn head(&self, location: &Path) -> object_store::Result<ObjectMeta> {
+        self.hedge(self.primary.head(location), self.replica.head(location))
+            .await
+    }
+
+    async fn delete(&self, location: &Path) -> object_store::Result<()> {
+        self.primary.delete(location).await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'static, object_store::Result<ObjectMeta>> {
+        self.primary.list(prefix)
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'static, object_store::Result<ObjectMeta>> {
+        self.primary.list_with_offset(prefix, offset)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> object_store::Result<ListResult> {
+        self.primary.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        self.primary.copy(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        self.primary.copy_if_not_exists(from, to).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use object_store::memory::InMemory;
+    use std::sync::atomic::AtomicUsize;
+
+    /// Store that counts GETs and answers them after a delay
+    #[derive(Debug)]
+    struct DelayedStore {
+        inner: InMemory,
+        delay: Duration,
+        gets: AtomicUsize,
+    }
+
+    impl std::fmt::Display for DelayedStore {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "DelayedStore")
+        }
+    }
+
+    #[async_trait]
+    impl ObjectStore for DelayedStore {
+        async fn put_opts(
+            &self,
+            location: &Path,
+            payload: PutPayload,
+            options: PutOptions,
+        ) -> object_store::Result<PutResult> {
+            self.inner.put_opts(location, payload, options).await
+        }
+
+        async fn put_multipart_opts(
+            &self,
+            location: &Path,
+            options: PutMultipartOptions,
+        ) -> object_store::Result<Box<dyn MultipartUpload>> {
+            self.inner.put_multipart_opts(location, options).await
+        }
+
+        async fn get_opts(
+            &self,
+            location: &Path,
+            options: GetOptions,
+        ) -> object_store::Result<GetResult> {
+            self.gets.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(self.delay).await;
+            self.inner.get_opts(location, options).await
+        }
+
+        async fn delete(&self, location: &Path) -> object_store::Result<()> {
+            self.inner.delete(location).await
+        }
+
+        fn list(
+            &self,
+            prefix: Option<&Path>,
+        ) -> BoxStream<'static, object_store::Result<ObjectMeta>> {
+            self.inner.list(prefix)
+        }
+
+        async fn list_with_delimiter(
+            &self,
+            prefix: Option<&Path>,
+        ) -> object_store::Result<ListResult> {
+            self.inner.list_with_delimiter(prefix).await
+        }
+
+        async fn copy(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+            self.inner.copy(from, to).await
+        }
+
+        async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+            self.inner.copy_if_not_exists(from, to).await
+        }
+    }
+
+    fn delayed_store(delay_ms: u64) -> Arc<DelayedStore> {
+        Arc::new(DelayedStore {
+            inner: InMemory::new(),
+            delay: Duration::from_millis(delay_ms),
+            gets: AtomicUsize::new(0),
+        })
+    }
+
+    async fn read(store: &HedgedStore<Arc<dyn ObjectStore>>, path: &Path) -> Bytes {
+        store.get(path).await.unwrap().bytes().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_slow_reads_are_answered_by_the_replica() {
+        let primary = delayed_store(500);
+        let replica = delayed_store(0);
+        let store = HedgedStore::new(
+            primary.clone() as Arc<dyn ObjectStore>,
+            replica.clone() as Arc<dyn ObjectStore>,
+            Duration::from_millis(20),
+        );
+        let path = Path::from("assets/logo.png");
+        primary
+            .put(&path, Bytes::from_static(b"primary").into())
+            .await
+            .unwrap();
+        replica
+            .put(&path, Bytes::from_static(b"replica").into())
+            .await
+            .unwrap();
+
+        assert_eq!(&read(&store, &path).await[..], b"replica");
+        assert_eq!(store.hedged_reads(), 1);
+        assert_eq!(primary.gets.load(Ordering::SeqCst), 1);
+        assert_eq!(replica.gets.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_fast_reads_and_replica_misses_stay_on_the_primary() {
+        let primary = delayed_store(0);
+        let replica = delayed_store(0);
+        let store = HedgedStore::new(
+            primary.clone() as Arc<dyn ObjectStore>,
+            replica.clone() as Arc<dyn ObjectStore>,
+            Duration::from_millis(50),
+        );
+        let path = Path::from("assets/logo.png");
+        store
+            .put(&path, Bytes::from_static(b"primary").into())
+            .await
+            .unwrap();
+
+        assert_eq!(&read(&store, &path).await[..], b"primary");
+        assert_eq!(store.hedged_reads(), 0);
+        assert_eq!(replica.gets.load(Ordering::SeqCst), 0);
+
+        // The replica has not caught up with the write, so the primary answers
+        let slow_primary = delayed_store(100);
+        let store = HedgedStore::new(
+            slow_primary.clone() as Arc<dyn ObjectStore>,
+            replica.clone() as Arc<dyn ObjectStore>,
+            Duration::from_millis(10),
+        );
+        slow_primary
+            .put(&path, Bytes::from_static(b"primary").into())
+            .await
+            .unwrap();
+        assert_eq!(&read(&store, &path).await[..], b"primary");
+        assert_eq!(store.hedged_reads(), 1);
+
+        let missing = store.get(&Path::from("assets/missing.png")).await;
+        assert!(matches!(missing, Err(object_store::Error::NotFound { .. })));
+    }
+}
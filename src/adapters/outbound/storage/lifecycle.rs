@@ -45,19 +45,14 @@ pub struct LifecycleRule {
 }
 
 /// Status of a lifecycle rule
-#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum RuleStatus {
+    #[default]
     Enabled,
     Disabled,
 }
 
-impl Default for RuleStatus {
-    fn default() -> Self {
-        RuleStatus::Enabled
-    }
-}
-
 /// Expiration configuration for a lifecycle rule
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ExpirationConfig {
@@ -279,9 +274,10 @@ impl<T: object_store::ObjectStore + Send + Sync + 'static> LifecycleManager<T> {
                         // Apply lifecycle rules for this bucket
                         if let Err(e) = Self::apply_lifecycle_rules(&store, &bucket, &config).await
                         {
-                            eprintln!(
+                            tracing::error!(
                                 "Error applying lifecycle rules for bucket {}: {}",
-                                bucket, e
+                                bucket,
+                                e
                             );
                         }
                     }
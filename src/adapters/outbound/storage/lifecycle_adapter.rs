@@ -2,7 +2,7 @@ use crate::adapters::outbound::storage::lifecycle::{
     ExpirationConfig, LifecycleConfiguration, LifecycleRule, RuleStatus, TransitionConfig,
 };
 use crate::adapters::outbound::storage::minio::{
-    MinioFilter, MinioLifecycleConfig, MinioLifecycleRule,
+    MinioFilter, MinioLifecycleConfig, MinioLifecycleRule, MinioTag, MinioTransition,
 };
 
 /// Convert from MinIO lifecycle model to service lifecycle model
@@ -31,6 +31,18 @@ pub fn domain_to_service(config: &MinioLifecycleConfig) -> LifecycleConfiguratio
             filter_tags: None,
         };
 
+        // Handle tag filters
+        if !domain_rule.filter.tags.is_empty() {
+            service_rule.filter_tags = Some(
+                domain_rule
+                    .filter
+                    .tags
+                    .iter()
+                    .map(|tag| (tag.key.clone(), tag.value.clone()))
+                    .collect(),
+            );
+        }
+
         // Handle expiration
         if domain_rule.expiration_days.is_some()
             || domain_rule.expiration_date.is_some()
@@ -45,21 +57,19 @@ pub fn domain_to_service(config: &MinioLifecycleConfig) -> LifecycleConfiguratio
             });
         }
 
-        // Handle transition
-        if domain_rule.transition_days.is_some()
-            || domain_rule.transition_date.is_some()
-            || domain_rule.transition_storage_class.is_some()
-        {
-            let transition = TransitionConfig {
-                days: domain_rule.transition_days.map(|d| d as u32),
-                date: domain_rule.transition_date,
-                storage_class: domain_rule
-                    .transition_storage_class
-                    .clone()
-                    .unwrap_or_default(),
-            };
-
-            service_rule.transitions = Some(vec![transition]);
+        // Handle transitions
+        if !domain_rule.transitions.is_empty() {
+            let transitions = domain_rule
+                .transitions
+                .iter()
+                .map(|transition| TransitionConfig {
+                    days: transition.days.map(|d| d as u32),
+                    date: transition.date,
+                    storage_class: transition.storage_class.clone(),
+                })
+                .collect();
+
+            service_rule.transitions = Some(transitions);
         }
 
         // Add the rule to the configuration
@@ -75,10 +85,21 @@ pub fn service_to_domain(config: &LifecycleConfiguration) -> MinioLifecycleConfi
 
     for service_rule in &config.rules {
         // Create a filter
+        let mut tags: Vec<MinioTag> = service_rule
+            .filter_tags
+            .iter()
+            .flatten()
+            .map(|(key, value)| MinioTag {
+                key: key.clone(),
+                value: value.clone(),
+            })
+            .collect();
+        tags.sort_by(|a, b| a.key.cmp(&b.key));
+
         let filter = MinioFilter {
             prefix: Some(service_rule.prefix.clone()),
-            tag: None,
-            and: None,
+            tags,
+            ..Default::default()
         };
 
         // Create a new domain rule
@@ -91,11 +112,9 @@ pub fn service_to_domain(config: &LifecycleConfiguration) -> MinioLifecycleConfi
             expiration_days: None,
             expiration_expired_object_delete_marker: None,
             noncurrent_version_expiration_noncurrent_days: None,
-            noncurrent_version_transition_noncurrent_days: None,
-            noncurrent_version_transition_storage_class: None,
-            transition_date: None,
-            transition_days: None,
-            transition_storage_class: None,
+            noncurrent_version_expiration_newer_versions: None,
+            noncurrent_version_transitions: Vec::new(),
+            transitions: Vec::new(),
         };
 
         // Handle expiration
@@ -108,11 +127,14 @@ pub fn service_to_domain(config: &LifecycleConfiguration) -> MinioLifecycleConfi
 
         // Handle transitions
         if let Some(transitions) = &service_rule.transitions {
-            if let Some(first_transition) = transitions.first() {
-                domain_rule.transition_days = first_transition.days.map(|d| d as usize);
-                domain_rule.transition_date = first_transition.date;
-                domain_rule.transition_storage_class = Some(first_transition.storage_class.clone());
-            }
+            domain_rule.transitions = transitions
+                .iter()
+                .map(|transition| MinioTransition {
+                    days: transition.days.map(|d| d as usize),
+                    date: transition.date,
+                    storage_class: transition.storage_class.clone(),
+                })
+                .collect();
         }
 
         // Add the rule to the domain configuration
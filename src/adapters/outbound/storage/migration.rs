@@ -0,0 +1,553 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use object_store::{
+    GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore,
+    PutMultipartOptions, PutOptions, PutPayload, PutResult, UploadPart, path::Path,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+/// How often a caught-up migration copies the objects written since
+const CATCH_UP_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Phase of a migration from the old backend to the new one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrationPhase {
+    /// Not started; objects only live in the old backend
+    Idle,
+    /// Existing objects are being copied to the new backend
+    Copying,
+    /// Every object has been copied and writes are followed as they happen
+    CaughtUp,
+    /// The new backend takes all traffic
+    CutOver,
+    /// Listing the old backend failed; the migration can be started again
+    Failed,
+}
+
+/// Progress of a blue/green backend migration
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationStatus {
+    pub phase: MigrationPhase,
+    pub objects_total: u64,
+    pub objects_copied: u64,
+    pub bytes_copied: u64,
+    pub objects_failed: u64,
+    /// Objects written to the old backend that still have to be copied
+    pub pending_changes: u64,
+    pub started_at: Option<DateTime<Utc>>,
+    pub caught_up_at: Option<DateTime<Utc>>,
+    pub cut_over_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+impl Default for MigrationStatus {
+    fn default() -> Self {
+        Self {
+            phase: MigrationPhase::Idle,
+            objects_total: 0,
+            objects_copied: 0,
+            bytes_copied: 0,
+            objects_failed: 0,
+            pending_changes: 0,
+            started_at: None,
+            caught_up_at: None,
+            cut_over_at: None,
+            last_error: None,
+        }
+    }
+}
+
+/// Control over a backend migration, exposed through the admin API
+#[async_trait]
+pub trait MigrationControl: Send + Sync + 'static {
+    /// Start copying every object of the old backend to the new one
+    async fn start_migration(&self) -> Result<(), String>;
+
+    /// Send all traffic to the new backend, once the migration has caught up
+    async fn cut_over(&self) -> Result<(), String>;
+
+    /// Progress of the migration
+    async fn migration_status(&self) -> MigrationStatus;
+}
+
+/// An ObjectStore moving its objects from an old backend to a new one while
+/// serving traffic
+///
+/// Until cutover, writes go to the old backend, which stays authoritative and
+/// answers listings. A background copy moves every object to the new backend
+/// and then follows the writes made since, so reads are served from the new
+/// backend first and fall back to the old one, except for objects with writes
+/// still waiting to be copied, which are read from the old backend. Once the
+/// copy has caught up, cutover drains the remaining writes while holding off
+/// new ones and flips all traffic to the new backend.
+pub struct MigratingStore {
+    old: Arc<dyn ObjectStore>,
+    new: Arc<dyn ObjectStore>,
+    /// Whether traffic has been flipped, held shared by writes to the old backend
+    cut_over: Arc<RwLock<bool>>,
+    /// Locations written since they were last copied, with a counter of writes
+    /// so a copy racing a write does not clear it
+    pending: Arc<Mutex<HashMap<Path, u64>>>,
+    status: Arc<Mutex<MigrationStatus>>,
+    automatic_cutover: bool,
+}
+
+impl Clone for MigratingStore {
+    fn clone(&self) -> Self {
+        Self {
+            old: self.old.clone(),
+            new: self.new.clone(),
+            cut_over: self.cut_over.clone(),
+            pending: self.pending.clone(),
+            status: self.status.clone(),
+            automatic_cutover: self.automatic_cutover,
+        }
+    }
+}
+
+impl std::fmt::Debug for MigratingStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MigratingStore")
+            .field("old", &self.old)
+            .field("new", &self.new)
+            .finish_non_exhaustive()
+    }
+}
+
+impl std::fmt::Display for MigratingStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "MigratingStore({} -> {})", self.old, self.new)
+    }
+}
+
+impl MigratingStore {
+    /// Migrate the objects of `old` to `new`
+    pub fn new(old: Arc<dyn ObjectStore>, new: Arc<dyn ObjectStore>) -> Self {
+        Self {
+            old,
+            new,
+            cut_over: Arc::new(RwLock::new(false)),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            status: Arc::new(Mutex::new(MigrationStatus::default())),
+            automatic_cutover: false,
+        }
+    }
+
+    /// Cut over as soon as the migration has caught up, instead of waiting for
+    /// the cutover API
+    pub fn with_automatic_cutover(mut self, automatic_cutover: bool) -> Self {
+        self.automatic_cutover = automatic_cutover;
+        self
+    }
+
+    fn is_pending(&self, location: &Path) -> bool {
+        self.pending.lock().unwrap().contains_key(location)
+    }
+
+    /// Note a write to the old backend that has to be copied
+    fn mark_pending(&self, location: &Path) {
+        let mut pending = self.pending.lock().unwrap();
+        *pending.entry(location.clone()).or_default() += 1;
+        self.status.lock().unwrap().pending_changes = pending.len() as u64;
+    }
+
+    /// Bring the new backend's copy of `location` in line with the old one
+    async fn copy_object(&self, location: &Path) -> object_store::Result<u64> {
+        match self.old.get(location).await {
+            Ok(result) => {
+                let attributes = result.attributes.clone();
+                let data = result.bytes().await?;
+                let size = data.len() as u64;
+                let options = PutOptions {
+                    attributes,
+                    ..Default::default()
+                };
+                self.new.put_opts(location, data.into(), options).await?;
+                Ok(size)
+            }
+            // Deleted since it was listed or written
+            Err(object_store::Error::NotFound { .. }) => match self.new.delete(location).await {
+                Ok(()) | Err(object_store::Error::NotFound { .. }) => Ok(0),
+                Err(e) => Err(e),
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Copy `location`, recording the outcome in the status
+    async fn copy_and_record(&self, location: &Path) -> bool {
+        let result = self.copy_object(location).await;
+        let mut status = self.status.lock().unwrap();
+        match result {
+            Ok(bytes) => {
+                status.objects_copied += 1;
+                status.bytes_copied += bytes;
+                true
+            }
+            Err(e) => {
+                status.objects_failed += 1;
+                status.last_error = Some(format!("{}: {}", location, e));
+                false
+            }
+        }
+    }
+
+    /// Copy the objects written since they were last copied
+    async fn drain_pending(&self) {
+        let pending: Vec<(Path, u64)> = self
+            .pending
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(location, writes)| (location.clone(), *writes))
+            .collect();
+
+        for (location, writes) in pending {
+            if !self.copy_and_record(&location).await {
+                continue;
+            }
+            // Objects written again while being copied stay pending
+            let mut pending = self.pending.lock().unwrap();
+            if pending.get(&location) == Some(&writes) {
+                pending.remove(&location);
+            }
+            self.status.lock().unwrap().pending_changes = pending.len() as u64;
+        }
+    }
+
+    /// Copy every object of the old backend, then follow writes until cutover
+    async fn run_migration(&self) {
+        let locations: Vec<Path> = match self
+            .old
+            .list(None)
+            .map_ok(|meta| meta.location)
+            .try_collect()
+            .await
+        {
+            Ok(locations) => locations,
+            Err(e) => {
+                let mut status = self.status.lock().unwrap();
+                status.phase = MigrationPhase::Failed;
+                status.last_error = Some(e.to_string());
+                return;
+            }
+        };
+
+        self.status.lock().unwrap().objects_total = locations.len() as u64;
+        for location in locations {
+            // Pending objects are copied with the writes that follow
+            if !self.is_pending(&location) && !self.copy_and_record(&location).await {
+                // Retried along with the writes
+                self.mark_pending(&location);
+            }
+        }
+
+        loop {
+            if *self.cut_over.read().await {
+                return;
+            }
+            self.drain_pending().await;
+
+            let caught_up = {
+                let mut status = self.status.lock().unwrap();
+                if status.phase == MigrationPhase::Copying && status.pending_changes == 0 {
+                    status.phase = MigrationPhase::CaughtUp;
+                    status.caught_up_at = Some(Utc::now());
+                }
+                status.phase == MigrationPhase::CaughtUp
+            };
+            if caught_up && self.automatic_cutover {
+                let _ = self.cut_over().await;
+                return;
+            }
+            tokio::time::sleep(CATCH_UP_INTERVAL).await;
+        }
+    }
+
+    /// The backend writes go to, and whether it is the old one
+    ///
+    /// The returned guard keeps cutover waiting until the write has been
+    /// recorded.
+    async fn write_target(
+        &self,
+    ) -> (
+        tokio::sync::RwLockReadGuard<'_, bool>,
+        &Arc<dyn ObjectStore>,
+    ) {
+        let cut_over = self.cut_over.read().await;
+        let target = if *cut_over { &self.new } else { &self.old };
+        (cut_over, target)
+    }
+
+    /// The backend serving listings
+    async fn authoritative(&self) -> Arc<dyn ObjectStore> {
+        match *self.cut_over.read().await {
+            true => self.new.clone(),
+            false => self.old.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl MigrationControl for MigratingStore {
+    async fn start_migration(&self) -> Result<(), String> {
+        {
+            let mut status = self.status.lock().unwrap();
+            match status.phase {
+                MigrationPhase::Copying | MigrationPhase::CaughtUp => {
+                    return Err("The migration is already running".to_string());
+                }
+                MigrationPhase::CutOver => {
+                    return Err("The migration has already cut over".to_string());
+                }
+                MigrationPhase::Idle | MigrationPhase::Failed => {}
+            }
+            *status = MigrationStatus {
+                phase: MigrationPhase::Copying,
+                pending_changes: status.pending_changes,
+                started_at: Some(Utc::now()),
+                ..Default::default()
+            };
+        }
+
+        let store = self.clone();
+        tokio::spawn(async move { store.run_migration().await });
+        Ok(())
+    }
+
+    async fn cut_over(&self) -> Result<(), String> {
+        if self.status.lock().unwrap().phase != MigrationPhase::CaughtUp {
+            return Err("The migration has not caught up yet".to_string());
+        }
+
+        // Holding off writes, copy the last of them and flip traffic
+        let mut cut_over = self.cut_over.write().await;
+        if *cut_over {
+            return Ok(());
+        }
+        self.drain_pending().await;
+        if !self.pending.lock().unwrap().is_empty() {
+            return Err("Some objects could not be copied to the new backend".to_string());
+        }
+        *cut_over = true;
+
+        let mut status = self.status.lock().unwrap();
+        status.phase = MigrationPhase::CutOver;
+        status.cut_over_at = Some(Utc::now());
+        Ok(())
+    }
+
+    async fn migration_status(&self) -> MigrationStatus {
+        self.status.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl ObjectStore for MigratingStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        options: PutOptions,
+    ) -> object_store::Result<PutResult> {
+        let (cut_over, target) = self.write_target().await;
+        let result = target.put_opts(location, payload, options).await;
+        if !*cut_over {
+            self.mark_pending(location);
+        }
+        result
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        options: PutMultipartOptions,
+    ) -> object_store::Result<Box<dyn MultipartUpload>> {
+        let (cut_over, target) = self.write_target().await;
+        let upload = target.put_multipart_opts(location, options).await?;
+        Ok(Box::new(MigratingUpload {
+            inner: upload,
+            location: location.clone(),
+            store: self.clone(),
+            to_old: !*cut_over,
+        }))
+    }
+
+    async fn get_opts(
+        &self,
+        location: &Path,
+        options: GetOptions,
+    ) -> object_store::Result<GetResult> {
+        if *self.cut_over.read().await {
+            return self.new.get_opts(location, options).await;
+        }
+        if self.is_pending(location) {
+            return self.old.get_opts(location, options).await;
+        }
+        match self.new.get_opts(location, options.clone()).await {
+            Err(object_store::Error::NotFound { .. }) => self.old.get_opts(location, options).await,
+            result => result,
+        }
+    }
+
+    async fn head(&self, location: &Path) -> object_store::Result<ObjectMeta> {
+        if *self.cut_over.read().await {
+            return self.new.head(location).await;
+        }
+        if self.is_pending(location) {
+            return self.old.head(location).await;
+        }
+        match self.new.head(location).await {
+            Err(object_store::Error::NotFound { .. }) => self.old.head(location).await,
+            result => result,
+        }
+    }
+
+    async fn delete(&self, location: &Path) -> object_store::Result<()> {
+        let (cut_over, target) = self.write_target().await;
+        let result = target.delete(location).await;
+        if !*cut_over {
+            self.mark_pending(location);
+        }
+        result
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'static, object_store::Result<ObjectMeta>> {
+        let store = self.clone();
+        let prefix = prefix.cloned();
+        stream::once(async move { store.authoritative().await.list(prefix.as_ref()) })
+            .flatten()
+            .boxed()
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> object_store::Result<ListResult> {
+        self.authoritative().await.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        let (cut_over, target) = self.write_target().await;
+        let result = target.copy(from, to).await;
+        if !*cut_over {
+            self.mark_pending(to);
+        }
+        result
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        let (cut_over, target) = self.write_target().await;
+        let result = target.copy_if_not_exists(from, to).await;
+        if !*cut_over {
+            self.mark_pending(to);
+        }
+        result
+    }
+}
+
+/// Multipart upload that has its object copied once it completes
+#[derive(Debug)]
+struct MigratingUpload {
+    inner: Box<dyn MultipartUpload>,
+    location: Path,
+    store: MigratingStore,
+    /// Whether the upload was started against the old backend
+    to_old: bool,
+}
+
+#[async_trait]
+impl MultipartUpload for MigratingUpload {
+    fn put_part(&mut self, data: PutPayload) -> UploadPart {
+        self.inner.put_part(data)
+    }
+
+    async fn complete(&mut self) -> object_store::Result<PutResult> {
+        let cut_over = self.store.cut_over.read().await;
+        let result = self.inner.complete().await?;
+        if self.to_old {
+            match *cut_over {
+                // Traffic flipped during the upload, so the object must be
+                // copied before its completion is reported
+                true => {
+                    self.store.copy_object(&self.location).await?;
+                }
+                false => self.store.mark_pending(&self.location),
+            }
+        }
+        Ok(result)
+    }
+
+    async fn abort(&mut self) -> object_store::Result<()> {
+        self.inner.abort().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use object_store::memory::InMemory;
+
+    async fn wait_for(store: &MigratingStore, phase: MigrationPhase) {
+        while store.migration_status().await.phase != phase {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    async fn read(store: &dyn ObjectStore, location: &str) -> Option<Bytes> {
+        match store.get(&Path::from(location)).await {
+            Ok(result) => Some(result.bytes().await.unwrap()),
+            Err(_) => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_migration_copies_objects_and_flips_writes() {
+        let old: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let new: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        for key in ["a", "b"] {
+            old.put(&Path::from(key), Bytes::from(key).into())
+                .await
+                .unwrap();
+        }
+        let store = MigratingStore::new(old.clone(), new.clone());
+
+        // Before the copy, reads fall back to the old backend
+        assert_eq!(read(&store, "a").await, Some(Bytes::from("a")));
+        assert!(store.cut_over().await.is_err());
+
+        store.start_migration().await.unwrap();
+        wait_for(&store, MigrationPhase::CaughtUp).await;
+        assert_eq!(read(new.as_ref(), "b").await, Some(Bytes::from("b")));
+
+        // Writes after catching up go to the old backend and are read from it
+        // until they are copied
+        store
+            .put(&Path::from("a"), Bytes::from("a2").into())
+            .await
+            .unwrap();
+        store.delete(&Path::from("b")).await.unwrap();
+        assert_eq!(read(&store, "a").await, Some(Bytes::from("a2")));
+        assert_eq!(read(&store, "b").await, None);
+
+        // Cutover copies the last writes and sends new ones to the new backend
+        store.cut_over().await.unwrap();
+        let status = store.migration_status().await;
+        assert_eq!(status.phase, MigrationPhase::CutOver);
+        assert_eq!(status.pending_changes, 0);
+        assert_eq!(read(new.as_ref(), "a").await, Some(Bytes::from("a2")));
+        assert_eq!(read(new.as_ref(), "b").await, None);
+
+        store
+            .put(&Path::from("c"), Bytes::from("c").into())
+            .await
+            .unwrap();
+        assert_eq!(read(new.as_ref(), "c").await, Some(Bytes::from("c")));
+        assert_eq!(read(old.as_ref(), "c").await, None);
+        assert!(store.start_migration().await.is_err());
+    }
+}
@@ -1,20 +1,17 @@
 use crate::adapters::outbound::storage::error::StoreError;
 use chrono::{DateTime, Utc};
-use quick_xml::Writer;
-use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
 use reqwest::Client;
-use std::borrow::Cow;
-use std::io::Cursor;
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
 /// MinIO-specific lifecycle configuration
-#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct MinioLifecycleConfig {
     pub rules: Vec<MinioLifecycleRule>,
 }
 
 /// MinIO-specific lifecycle rule
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct MinioLifecycleRule {
     pub id: String,
     pub status: bool,
@@ -24,19 +21,44 @@ pub struct MinioLifecycleRule {
     pub expiration_days: Option<usize>,
     pub expiration_expired_object_delete_marker: Option<bool>,
     pub noncurrent_version_expiration_noncurrent_days: Option<usize>,
-    pub noncurrent_version_transition_noncurrent_days: Option<usize>,
-    pub noncurrent_version_transition_storage_class: Option<String>,
-    pub transition_date: Option<DateTime<Utc>>,
-    pub transition_days: Option<usize>,
-    pub transition_storage_class: Option<String>,
+    pub noncurrent_version_expiration_newer_versions: Option<usize>,
+    pub noncurrent_version_transitions: Vec<MinioNoncurrentVersionTransition>,
+    pub transitions: Vec<MinioTransition>,
 }
 
 /// MinIO-specific filter
-#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
+///
+/// All conditions must match. More than one condition is written as an `And`
+/// filter.
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct MinioFilter {
     pub prefix: Option<String>,
-    pub tag: Option<String>,
-    pub and: Option<String>,
+    pub tags: Vec<MinioTag>,
+    pub object_size_greater_than: Option<u64>,
+    pub object_size_less_than: Option<u64>,
+}
+
+/// Object tag matched by a lifecycle filter
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct MinioTag {
+    pub key: String,
+    pub value: String,
+}
+
+/// Transition of current object versions to another storage class
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MinioTransition {
+    pub days: Option<usize>,
+    pub date: Option<DateTime<Utc>>,
+    pub storage_class: String,
+}
+
+/// Transition of noncurrent object versions to another storage class
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MinioNoncurrentVersionTransition {
+    pub noncurrent_days: Option<usize>,
+    pub newer_noncurrent_versions: Option<usize>,
+    pub storage_class: String,
 }
 
 /// Client for interacting with MinIO specific APIs
@@ -45,12 +67,14 @@ pub struct MinioClient {
     endpoint: String,
     access_key: String,
     secret_key: String,
-    region: String,
 }
 
 impl MinioClient {
     /// Create a new MinIO client
-    pub fn new(endpoint: &str, access_key: &str, secret_key: &str, region: &str) -> Self {
+    ///
+    /// The region is accepted to match S3 configuration, but MinIO's own APIs
+    /// don't use it.
+    pub fn new(endpoint: &str, access_key: &str, secret_key: &str, _region: &str) -> Self {
         // Create reqwest client with reasonable defaults
         let client = Client::builder()
             .timeout(Duration::from_secs(30))
@@ -62,7 +86,6 @@ impl MinioClient {
             endpoint: endpoint.to_string(),
             access_key: access_key.to_string(),
             secret_key: secret_key.to_string(),
-            region: region.to_string(),
         }
     }
 
@@ -186,630 +209,479 @@ impl MinioClient {
     }
 }
 
-/// Parse a date string in ISO 8601 format to a DateTime<Utc>
-fn parse_iso8601_date(date_str: &str) -> Result<DateTime<Utc>, StoreError> {
-    match DateTime::parse_from_rfc3339(date_str) {
-        Ok(dt) => Ok(dt.with_timezone(&Utc)),
-        Err(e) => Err(StoreError::Other(format!("Failed to parse date: {}", e))),
-    }
-}
+// S3 lifecycle configuration XML, as documented for PutBucketLifecycleConfiguration.
+// These types mirror the wire format; the Minio* types above are what callers use.
 
-/// Format a DateTime<Utc> to an ISO 8601 string
-fn format_iso8601_date(date: DateTime<Utc>) -> String {
-    date.to_rfc3339()
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename = "LifecycleConfiguration")]
+struct XmlLifecycleConfiguration {
+    #[serde(rename = "Rule", default)]
+    rules: Vec<XmlRule>,
 }
 
-// Helper function to get text content of an XML element
-fn get_element_text<'a, T: std::io::BufRead>(
-    reader: &mut quick_xml::Reader<T>,
-    buf: &'a mut Vec<u8>,
-) -> Result<Cow<'a, str>, StoreError> {
-    match reader.read_event_into(buf) {
-        Ok(Event::Text(e)) => {
-            let text = e
-                .unescape()
-                .map_err(|e| StoreError::Other(format!("Failed to unescape XML text: {}", e)))?;
-            Ok(text)
-        }
-        _ => Err(StoreError::Other("Expected text content".to_string())),
-    }
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct XmlRule {
+    #[serde(rename = "ID", default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(rename = "Status")]
+    status: String,
+    #[serde(rename = "Filter", default, skip_serializing_if = "Option::is_none")]
+    filter: Option<XmlFilter>,
+    /// Rule-level prefix of the original lifecycle schema, superseded by `Filter`
+    #[serde(rename = "Prefix", default, skip_serializing_if = "Option::is_none")]
+    prefix: Option<String>,
+    #[serde(
+        rename = "Expiration",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    expiration: Option<XmlExpiration>,
+    #[serde(rename = "Transition", default, skip_serializing_if = "Vec::is_empty")]
+    transitions: Vec<XmlTransition>,
+    #[serde(
+        rename = "NoncurrentVersionExpiration",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    noncurrent_version_expiration: Option<XmlNoncurrentVersionExpiration>,
+    #[serde(
+        rename = "NoncurrentVersionTransition",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    noncurrent_version_transitions: Vec<XmlNoncurrentVersionTransition>,
+    #[serde(
+        rename = "AbortIncompleteMultipartUpload",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    abort_incomplete_multipart_upload: Option<XmlAbortIncompleteMultipartUpload>,
 }
 
-// Helper function to parse XML to MinioLifecycleConfig
-fn parse_lifecycle_config(xml: &str) -> Result<MinioLifecycleConfig, StoreError> {
-    let mut reader = quick_xml::Reader::from_str(xml);
-    reader.trim_text(true);
-
-    let mut config = MinioLifecycleConfig::default();
-    let mut buf = Vec::new();
-
-    // Current rule being parsed and state tracking
-    let mut current_rule: Option<MinioLifecycleRule> = None;
-    let mut in_expiration = false;
-    let mut in_filter = false;
-    let mut in_transition = false;
-    let mut in_noncurrent_version_expiration = false;
-    let mut in_noncurrent_version_transition = false;
-    let _current_tag_key: Option<String> = None;
-
-    // Parse the XML
-    loop {
-        match reader.read_event_into(&mut buf) {
-            Ok(Event::Start(ref e)) => {
-                match e.name().as_ref() {
-                    b"Rule" => {
-                        // Start a new rule with default values
-                        current_rule = Some(MinioLifecycleRule {
-                            id: String::new(),
-                            status: false,
-                            filter: MinioFilter::default(),
-                            abort_incomplete_multipart_upload_days_after_initiation: None,
-                            expiration_date: None,
-                            expiration_days: None,
-                            expiration_expired_object_delete_marker: None,
-                            noncurrent_version_expiration_noncurrent_days: None,
-                            noncurrent_version_transition_noncurrent_days: None,
-                            noncurrent_version_transition_storage_class: None,
-                            transition_date: None,
-                            transition_days: None,
-                            transition_storage_class: None,
-                        });
-                    }
-                    b"Expiration" => {
-                        in_expiration = true;
-                    }
-                    b"Filter" => {
-                        in_filter = true;
-                    }
-                    b"Transition" => {
-                        in_transition = true;
-                    }
-                    b"NoncurrentVersionExpiration" => {
-                        in_noncurrent_version_expiration = true;
-                    }
-                    b"NoncurrentVersionTransition" => {
-                        in_noncurrent_version_transition = true;
-                    }
-                    b"ID" => {
-                        if let Some(ref mut rule) = current_rule {
-                            buf.clear();
-                            let text = get_element_text(&mut reader, &mut buf)?;
-                            rule.id = text.to_string();
-                        }
-                    }
-                    b"Status" => {
-                        if let Some(ref mut rule) = current_rule {
-                            buf.clear();
-                            let text = get_element_text(&mut reader, &mut buf)?;
-                            rule.status = text.to_lowercase() == "enabled";
-                        }
-                    }
-                    b"Days" => {
-                        if let Some(ref mut rule) = current_rule {
-                            buf.clear();
-                            let text = get_element_text(&mut reader, &mut buf)?;
-                            let days = text.parse::<usize>().map_err(|e| {
-                                StoreError::Other(format!("Failed to parse days: {}", e))
-                            })?;
-
-                            if in_expiration {
-                                rule.expiration_days = Some(days);
-                            } else if in_transition {
-                                rule.transition_days = Some(days);
-                            }
-                        }
-                    }
-                    b"Date" => {
-                        if let Some(ref mut rule) = current_rule {
-                            buf.clear();
-                            let text = get_element_text(&mut reader, &mut buf)?;
-                            let date = parse_iso8601_date(&text)?;
-
-                            if in_expiration {
-                                rule.expiration_date = Some(date);
-                            } else if in_transition {
-                                rule.transition_date = Some(date);
-                            }
-                        }
-                    }
-                    b"StorageClass" => {
-                        if let Some(ref mut rule) = current_rule {
-                            buf.clear();
-                            let text = get_element_text(&mut reader, &mut buf)?;
-
-                            if in_transition {
-                                rule.transition_storage_class = Some(text.to_string());
-                            } else if in_noncurrent_version_transition {
-                                rule.noncurrent_version_transition_storage_class =
-                                    Some(text.to_string());
-                            }
-                        }
-                    }
-                    b"NoncurrentDays" => {
-                        if let Some(ref mut rule) = current_rule {
-                            buf.clear();
-                            let text = get_element_text(&mut reader, &mut buf)?;
-                            let days = text.parse::<usize>().map_err(|e| {
-                                StoreError::Other(format!("Failed to parse noncurrent days: {}", e))
-                            })?;
-
-                            if in_noncurrent_version_expiration {
-                                rule.noncurrent_version_expiration_noncurrent_days = Some(days);
-                            } else if in_noncurrent_version_transition {
-                                rule.noncurrent_version_transition_noncurrent_days = Some(days);
-                            }
-                        }
-                    }
-                    b"DaysAfterInitiation" => {
-                        if let Some(ref mut rule) = current_rule {
-                            buf.clear();
-                            let text = get_element_text(&mut reader, &mut buf)?;
-                            let days = text.parse::<usize>().map_err(|e| {
-                                StoreError::Other(format!(
-                                    "Failed to parse days after initiation: {}",
-                                    e
-                                ))
-                            })?;
-                            rule.abort_incomplete_multipart_upload_days_after_initiation =
-                                Some(days);
-                        }
-                    }
-                    b"ExpiredObjectDeleteMarker" => {
-                        if let Some(ref mut rule) = current_rule {
-                            buf.clear();
-                            let text = get_element_text(&mut reader, &mut buf)?;
-                            rule.expiration_expired_object_delete_marker =
-                                Some(text.to_lowercase() == "true");
-                        }
-                    }
-                    b"Prefix" => {
-                        if let Some(ref mut rule) = current_rule {
-                            if in_filter {
-                                buf.clear();
-                                let text = get_element_text(&mut reader, &mut buf)?;
-                                rule.filter.prefix = Some(text.to_string());
-                            }
-                        }
-                    }
-                    _ => {}
-                }
-            }
-            Ok(Event::End(ref e)) => {
-                match e.name().as_ref() {
-                    b"Rule" => {
-                        // End of rule, add to config if valid
-                        if let Some(rule) = current_rule.take() {
-                            config.rules.push(rule);
-                        }
-                    }
-                    b"Expiration" => {
-                        in_expiration = false;
-                    }
-                    b"Filter" => {
-                        in_filter = false;
-                    }
-                    b"Transition" => {
-                        in_transition = false;
-                    }
-                    b"NoncurrentVersionExpiration" => {
-                        in_noncurrent_version_expiration = false;
-                    }
-                    b"NoncurrentVersionTransition" => {
-                        in_noncurrent_version_transition = false;
-                    }
-                    _ => {}
-                }
-            }
-            Ok(Event::Empty(ref e)) => {
-                // Handle self-closing tags
-                match e.name().as_ref() {
-                    b"ExpiredObjectDeleteMarker" => {
-                        if let Some(ref mut rule) = current_rule {
-                            rule.expiration_expired_object_delete_marker = Some(true);
-                        }
-                    }
-                    _ => {}
-                }
-            }
-            Ok(Event::Eof) => break,
-            Err(e) => {
-                return Err(StoreError::Other(format!("Error parsing XML: {}", e)));
-            }
-            _ => {}
-        }
-        buf.clear();
-    }
-
-    Ok(config)
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct XmlFilter {
+    #[serde(rename = "Prefix", default, skip_serializing_if = "Option::is_none")]
+    prefix: Option<String>,
+    #[serde(rename = "Tag", default, skip_serializing_if = "Option::is_none")]
+    tag: Option<XmlTag>,
+    #[serde(
+        rename = "ObjectSizeGreaterThan",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    object_size_greater_than: Option<u64>,
+    #[serde(
+        rename = "ObjectSizeLessThan",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    object_size_less_than: Option<u64>,
+    #[serde(rename = "And", default, skip_serializing_if = "Option::is_none")]
+    and: Option<XmlAnd>,
 }
 
-// Helper function to convert MinioLifecycleConfig to XML
-fn lifecycle_config_to_xml(config: &MinioLifecycleConfig) -> Result<String, StoreError> {
-    let mut writer = Writer::new(Cursor::new(Vec::new()));
-
-    // Write XML declaration
-    writer
-        .write_event(Event::Decl(quick_xml::events::BytesDecl::new(
-            "1.0",
-            Some("UTF-8"),
-            None,
-        )))
-        .map_err(|e| StoreError::Other(format!("Failed to write XML declaration: {}", e)))?;
-
-    // Start LifecycleConfiguration
-    writer
-        .write_event(Event::Start(BytesStart::new("LifecycleConfiguration")))
-        .map_err(|e| {
-            StoreError::Other(format!(
-                "Failed to write LifecycleConfiguration start: {}",
-                e
-            ))
-        })?;
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct XmlAnd {
+    #[serde(rename = "Prefix", default, skip_serializing_if = "Option::is_none")]
+    prefix: Option<String>,
+    #[serde(rename = "Tag", default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<XmlTag>,
+    #[serde(
+        rename = "ObjectSizeGreaterThan",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    object_size_greater_than: Option<u64>,
+    #[serde(
+        rename = "ObjectSizeLessThan",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    object_size_less_than: Option<u64>,
+}
 
-    // Write each rule
-    for rule in &config.rules {
-        // Start Rule
-        writer
-            .write_event(Event::Start(BytesStart::new("Rule")))
-            .map_err(|e| StoreError::Other(format!("Failed to write Rule start: {}", e)))?;
-
-        // Write ID if not empty
-        if !rule.id.is_empty() {
-            writer
-                .write_event(Event::Start(BytesStart::new("ID")))
-                .map_err(|e| StoreError::Other(format!("Failed to write ID start: {}", e)))?;
-            writer
-                .write_event(Event::Text(BytesText::new(&rule.id)))
-                .map_err(|e| StoreError::Other(format!("Failed to write ID text: {}", e)))?;
-            writer
-                .write_event(Event::End(BytesEnd::new("ID")))
-                .map_err(|e| StoreError::Other(format!("Failed to write ID end: {}", e)))?;
-        }
+#[derive(Debug, Serialize, Deserialize)]
+struct XmlTag {
+    #[serde(rename = "Key")]
+    key: String,
+    #[serde(rename = "Value")]
+    value: String,
+}
 
-        // Write Status
-        writer
-            .write_event(Event::Start(BytesStart::new("Status")))
-            .map_err(|e| StoreError::Other(format!("Failed to write Status start: {}", e)))?;
-        writer
-            .write_event(Event::Text(BytesText::new(if rule.status {
-                "Enabled"
-            } else {
-                "Disabled"
-            })))
-            .map_err(|e| StoreError::Other(format!("Failed to write Status text: {}", e)))?;
-        writer
-            .write_event(Event::End(BytesEnd::new("Status")))
-            .map_err(|e| StoreError::Other(format!("Failed to write Status end: {}", e)))?;
-
-        // Write Filter
-        writer
-            .write_event(Event::Start(BytesStart::new("Filter")))
-            .map_err(|e| StoreError::Other(format!("Failed to write Filter start: {}", e)))?;
-
-        // Write Prefix if exists
-        if let Some(ref prefix) = rule.filter.prefix {
-            writer
-                .write_event(Event::Start(BytesStart::new("Prefix")))
-                .map_err(|e| StoreError::Other(format!("Failed to write Prefix start: {}", e)))?;
-            writer
-                .write_event(Event::Text(BytesText::new(prefix)))
-                .map_err(|e| StoreError::Other(format!("Failed to write Prefix text: {}", e)))?;
-            writer
-                .write_event(Event::End(BytesEnd::new("Prefix")))
-                .map_err(|e| StoreError::Other(format!("Failed to write Prefix end: {}", e)))?;
-        }
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct XmlExpiration {
+    #[serde(rename = "Days", default, skip_serializing_if = "Option::is_none")]
+    days: Option<usize>,
+    #[serde(rename = "Date", default, skip_serializing_if = "Option::is_none")]
+    date: Option<DateTime<Utc>>,
+    #[serde(
+        rename = "ExpiredObjectDeleteMarker",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    expired_object_delete_marker: Option<bool>,
+}
 
-        // End Filter
-        writer
-            .write_event(Event::End(BytesEnd::new("Filter")))
-            .map_err(|e| StoreError::Other(format!("Failed to write Filter end: {}", e)))?;
+#[derive(Debug, Serialize, Deserialize)]
+struct XmlTransition {
+    #[serde(rename = "Days", default, skip_serializing_if = "Option::is_none")]
+    days: Option<usize>,
+    #[serde(rename = "Date", default, skip_serializing_if = "Option::is_none")]
+    date: Option<DateTime<Utc>>,
+    #[serde(rename = "StorageClass")]
+    storage_class: String,
+}
 
-        // Write Expiration if needed
-        if rule.expiration_date.is_some()
-            || rule.expiration_days.is_some()
-            || rule.expiration_expired_object_delete_marker.is_some()
-        {
-            writer
-                .write_event(Event::Start(BytesStart::new("Expiration")))
-                .map_err(|e| {
-                    StoreError::Other(format!("Failed to write Expiration start: {}", e))
-                })?;
-
-            // Write Days if exists
-            if let Some(days) = rule.expiration_days {
-                writer
-                    .write_event(Event::Start(BytesStart::new("Days")))
-                    .map_err(|e| StoreError::Other(format!("Failed to write Days start: {}", e)))?;
-                writer
-                    .write_event(Event::Text(BytesText::new(&days.to_string())))
-                    .map_err(|e| StoreError::Other(format!("Failed to write Days text: {}", e)))?;
-                writer
-                    .write_event(Event::End(BytesEnd::new("Days")))
-                    .map_err(|e| StoreError::Other(format!("Failed to write Days end: {}", e)))?;
-            }
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct XmlNoncurrentVersionExpiration {
+    #[serde(
+        rename = "NoncurrentDays",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    noncurrent_days: Option<usize>,
+    #[serde(
+        rename = "NewerNoncurrentVersions",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    newer_noncurrent_versions: Option<usize>,
+}
 
-            // Write Date if exists
-            if let Some(date) = rule.expiration_date {
-                writer
-                    .write_event(Event::Start(BytesStart::new("Date")))
-                    .map_err(|e| StoreError::Other(format!("Failed to write Date start: {}", e)))?;
-                writer
-                    .write_event(Event::Text(BytesText::new(&format_iso8601_date(date))))
-                    .map_err(|e| StoreError::Other(format!("Failed to write Date text: {}", e)))?;
-                writer
-                    .write_event(Event::End(BytesEnd::new("Date")))
-                    .map_err(|e| StoreError::Other(format!("Failed to write Date end: {}", e)))?;
-            }
+#[derive(Debug, Serialize, Deserialize)]
+struct XmlNoncurrentVersionTransition {
+    #[serde(
+        rename = "NoncurrentDays",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    noncurrent_days: Option<usize>,
+    #[serde(
+        rename = "NewerNoncurrentVersions",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    newer_noncurrent_versions: Option<usize>,
+    #[serde(rename = "StorageClass")]
+    storage_class: String,
+}
 
-            // Write ExpiredObjectDeleteMarker if exists
-            if let Some(marker) = rule.expiration_expired_object_delete_marker {
-                writer
-                    .write_event(Event::Start(BytesStart::new("ExpiredObjectDeleteMarker")))
-                    .map_err(|e| {
-                        StoreError::Other(format!(
-                            "Failed to write ExpiredObjectDeleteMarker start: {}",
-                            e
-                        ))
-                    })?;
-                writer
-                    .write_event(Event::Text(BytesText::new(&marker.to_string())))
-                    .map_err(|e| {
-                        StoreError::Other(format!(
-                            "Failed to write ExpiredObjectDeleteMarker text: {}",
-                            e
-                        ))
-                    })?;
-                writer
-                    .write_event(Event::End(BytesEnd::new("ExpiredObjectDeleteMarker")))
-                    .map_err(|e| {
-                        StoreError::Other(format!(
-                            "Failed to write ExpiredObjectDeleteMarker end: {}",
-                            e
-                        ))
-                    })?;
-            }
+#[derive(Debug, Serialize, Deserialize)]
+struct XmlAbortIncompleteMultipartUpload {
+    #[serde(rename = "DaysAfterInitiation")]
+    days_after_initiation: usize,
+}
 
-            // End Expiration
-            writer
-                .write_event(Event::End(BytesEnd::new("Expiration")))
-                .map_err(|e| StoreError::Other(format!("Failed to write Expiration end: {}", e)))?;
+impl From<XmlTag> for MinioTag {
+    fn from(tag: XmlTag) -> Self {
+        MinioTag {
+            key: tag.key,
+            value: tag.value,
         }
+    }
+}
 
-        // Write Transition if needed
-        if rule.transition_date.is_some()
-            || rule.transition_days.is_some()
-            || rule.transition_storage_class.is_some()
-        {
-            writer
-                .write_event(Event::Start(BytesStart::new("Transition")))
-                .map_err(|e| {
-                    StoreError::Other(format!("Failed to write Transition start: {}", e))
-                })?;
-
-            // Write Days if exists
-            if let Some(days) = rule.transition_days {
-                writer
-                    .write_event(Event::Start(BytesStart::new("Days")))
-                    .map_err(|e| {
-                        StoreError::Other(format!("Failed to write Transition Days start: {}", e))
-                    })?;
-                writer
-                    .write_event(Event::Text(BytesText::new(&days.to_string())))
-                    .map_err(|e| {
-                        StoreError::Other(format!("Failed to write Transition Days text: {}", e))
-                    })?;
-                writer
-                    .write_event(Event::End(BytesEnd::new("Days")))
-                    .map_err(|e| {
-                        StoreError::Other(format!("Failed to write Transition Days end: {}", e))
-                    })?;
-            }
-
-            // Write Date if exists
-            if let Some(date) = rule.transition_date {
-                writer
-                    .write_event(Event::Start(BytesStart::new("Date")))
-                    .map_err(|e| {
-                        StoreError::Other(format!("Failed to write Transition Date start: {}", e))
-                    })?;
-                writer
-                    .write_event(Event::Text(BytesText::new(&format_iso8601_date(date))))
-                    .map_err(|e| {
-                        StoreError::Other(format!("Failed to write Transition Date text: {}", e))
-                    })?;
-                writer
-                    .write_event(Event::End(BytesEnd::new("Date")))
-                    .map_err(|e| {
-                        StoreError::Other(format!("Failed to write Transition Date end: {}", e))
-                    })?;
-            }
-
-            // Write StorageClass if exists
-            if let Some(ref storage_class) = rule.transition_storage_class {
-                writer
-                    .write_event(Event::Start(BytesStart::new("StorageClass")))
-                    .map_err(|e| {
-                        StoreError::Other(format!("Failed to write StorageClass start: {}", e))
-                    })?;
-                writer
-                    .write_event(Event::Text(BytesText::new(storage_class)))
-                    .map_err(|e| {
-                        StoreError::Other(format!("Failed to write StorageClass text: {}", e))
-                    })?;
-                writer
-                    .write_event(Event::End(BytesEnd::new("StorageClass")))
-                    .map_err(|e| {
-                        StoreError::Other(format!("Failed to write StorageClass end: {}", e))
-                    })?;
-            }
-
-            // End Transition
-            writer
-                .write_event(Event::End(BytesEnd::new("Transition")))
-                .map_err(|e| StoreError::Other(format!("Failed to write Transition end: {}", e)))?;
+impl From<&MinioTag> for XmlTag {
+    fn from(tag: &MinioTag) -> Self {
+        XmlTag {
+            key: tag.key.clone(),
+            value: tag.value.clone(),
         }
+    }
+}
 
-        // Write NoncurrentVersionExpiration if needed
-        if rule.noncurrent_version_expiration_noncurrent_days.is_some() {
-            writer
-                .write_event(Event::Start(BytesStart::new("NoncurrentVersionExpiration")))
-                .map_err(|e| {
-                    StoreError::Other(format!(
-                        "Failed to write NoncurrentVersionExpiration start: {}",
-                        e
-                    ))
-                })?;
-
-            // Write NoncurrentDays
-            if let Some(days) = rule.noncurrent_version_expiration_noncurrent_days {
-                writer
-                    .write_event(Event::Start(BytesStart::new("NoncurrentDays")))
-                    .map_err(|e| {
-                        StoreError::Other(format!("Failed to write NoncurrentDays start: {}", e))
-                    })?;
-                writer
-                    .write_event(Event::Text(BytesText::new(&days.to_string())))
-                    .map_err(|e| {
-                        StoreError::Other(format!("Failed to write NoncurrentDays text: {}", e))
-                    })?;
-                writer
-                    .write_event(Event::End(BytesEnd::new("NoncurrentDays")))
-                    .map_err(|e| {
-                        StoreError::Other(format!("Failed to write NoncurrentDays end: {}", e))
-                    })?;
-            }
-
-            // End NoncurrentVersionExpiration
-            writer
-                .write_event(Event::End(BytesEnd::new("NoncurrentVersionExpiration")))
-                .map_err(|e| {
-                    StoreError::Other(format!(
-                        "Failed to write NoncurrentVersionExpiration end: {}",
-                        e
-                    ))
-                })?;
+impl From<XmlRule> for MinioLifecycleRule {
+    fn from(rule: XmlRule) -> Self {
+        let mut filter = MinioFilter {
+            prefix: rule.prefix,
+            ..Default::default()
+        };
+        if let Some(xml_filter) = rule.filter {
+            let and = xml_filter.and.unwrap_or_default();
+            filter.prefix = xml_filter.prefix.or(and.prefix).or(filter.prefix);
+            filter.tags = xml_filter
+                .tag
+                .into_iter()
+                .chain(and.tags)
+                .map(MinioTag::from)
+                .collect();
+            filter.object_size_greater_than = xml_filter
+                .object_size_greater_than
+                .or(and.object_size_greater_than);
+            filter.object_size_less_than = xml_filter
+                .object_size_less_than
+                .or(and.object_size_less_than);
         }
 
-        // Write NoncurrentVersionTransition if needed
-        if rule.noncurrent_version_transition_noncurrent_days.is_some()
-            || rule.noncurrent_version_transition_storage_class.is_some()
-        {
-            writer
-                .write_event(Event::Start(BytesStart::new("NoncurrentVersionTransition")))
-                .map_err(|e| {
-                    StoreError::Other(format!(
-                        "Failed to write NoncurrentVersionTransition start: {}",
-                        e
-                    ))
-                })?;
-
-            // Write NoncurrentDays
-            if let Some(days) = rule.noncurrent_version_transition_noncurrent_days {
-                writer
-                    .write_event(Event::Start(BytesStart::new("NoncurrentDays")))
-                    .map_err(|e| {
-                        StoreError::Other(format!("Failed to write NoncurrentDays start: {}", e))
-                    })?;
-                writer
-                    .write_event(Event::Text(BytesText::new(&days.to_string())))
-                    .map_err(|e| {
-                        StoreError::Other(format!("Failed to write NoncurrentDays text: {}", e))
-                    })?;
-                writer
-                    .write_event(Event::End(BytesEnd::new("NoncurrentDays")))
-                    .map_err(|e| {
-                        StoreError::Other(format!("Failed to write NoncurrentDays end: {}", e))
-                    })?;
-            }
-
-            // Write StorageClass
-            if let Some(ref storage_class) = rule.noncurrent_version_transition_storage_class {
-                writer
-                    .write_event(Event::Start(BytesStart::new("StorageClass")))
-                    .map_err(|e| {
-                        StoreError::Other(format!("Failed to write StorageClass start: {}", e))
-                    })?;
-                writer
-                    .write_event(Event::Text(BytesText::new(storage_class)))
-                    .map_err(|e| {
-                        StoreError::Other(format!("Failed to write StorageClass text: {}", e))
-                    })?;
-                writer
-                    .write_event(Event::End(BytesEnd::new("StorageClass")))
-                    .map_err(|e| {
-                        StoreError::Other(format!("Failed to write StorageClass end: {}", e))
-                    })?;
-            }
+        let expiration = rule.expiration.unwrap_or_default();
+        let noncurrent_expiration = rule.noncurrent_version_expiration.unwrap_or_default();
+
+        MinioLifecycleRule {
+            id: rule.id.unwrap_or_default(),
+            status: rule.status.eq_ignore_ascii_case("enabled"),
+            filter,
+            abort_incomplete_multipart_upload_days_after_initiation: rule
+                .abort_incomplete_multipart_upload
+                .map(|abort| abort.days_after_initiation),
+            expiration_date: expiration.date,
+            expiration_days: expiration.days,
+            expiration_expired_object_delete_marker: expiration.expired_object_delete_marker,
+            noncurrent_version_expiration_noncurrent_days: noncurrent_expiration.noncurrent_days,
+            noncurrent_version_expiration_newer_versions: noncurrent_expiration
+                .newer_noncurrent_versions,
+            noncurrent_version_transitions: rule
+                .noncurrent_version_transitions
+                .into_iter()
+                .map(|transition| MinioNoncurrentVersionTransition {
+                    noncurrent_days: transition.noncurrent_days,
+                    newer_noncurrent_versions: transition.newer_noncurrent_versions,
+                    storage_class: transition.storage_class,
+                })
+                .collect(),
+            transitions: rule
+                .transitions
+                .into_iter()
+                .map(|transition| MinioTransition {
+                    days: transition.days,
+                    date: transition.date,
+                    storage_class: transition.storage_class,
+                })
+                .collect(),
+        }
+    }
+}
 
-            // End NoncurrentVersionTransition
-            writer
-                .write_event(Event::End(BytesEnd::new("NoncurrentVersionTransition")))
-                .map_err(|e| {
-                    StoreError::Other(format!(
-                        "Failed to write NoncurrentVersionTransition end: {}",
-                        e
-                    ))
-                })?;
+impl From<&MinioFilter> for XmlFilter {
+    fn from(filter: &MinioFilter) -> Self {
+        let conditions = usize::from(filter.prefix.is_some())
+            + filter.tags.len()
+            + usize::from(filter.object_size_greater_than.is_some())
+            + usize::from(filter.object_size_less_than.is_some());
+
+        // A filter may hold a single condition; several must be combined with And
+        if conditions > 1 {
+            return XmlFilter {
+                and: Some(XmlAnd {
+                    prefix: filter.prefix.clone(),
+                    tags: filter.tags.iter().map(XmlTag::from).collect(),
+                    object_size_greater_than: filter.object_size_greater_than,
+                    object_size_less_than: filter.object_size_less_than,
+                }),
+                ..Default::default()
+            };
         }
 
-        // Write AbortIncompleteMultipartUpload if needed
-        if let Some(days) = rule.abort_incomplete_multipart_upload_days_after_initiation {
-            writer
-                .write_event(Event::Start(BytesStart::new(
-                    "AbortIncompleteMultipartUpload",
-                )))
-                .map_err(|e| {
-                    StoreError::Other(format!(
-                        "Failed to write AbortIncompleteMultipartUpload start: {}",
-                        e
-                    ))
-                })?;
-
-            // Write DaysAfterInitiation
-            writer
-                .write_event(Event::Start(BytesStart::new("DaysAfterInitiation")))
-                .map_err(|e| {
-                    StoreError::Other(format!("Failed to write DaysAfterInitiation start: {}", e))
-                })?;
-            writer
-                .write_event(Event::Text(BytesText::new(&days.to_string())))
-                .map_err(|e| {
-                    StoreError::Other(format!("Failed to write DaysAfterInitiation text: {}", e))
-                })?;
-            writer
-                .write_event(Event::End(BytesEnd::new("DaysAfterInitiation")))
-                .map_err(|e| {
-                    StoreError::Other(format!("Failed to write DaysAfterInitiation end: {}", e))
-                })?;
-
-            // End AbortIncompleteMultipartUpload
-            writer
-                .write_event(Event::End(BytesEnd::new("AbortIncompleteMultipartUpload")))
-                .map_err(|e| {
-                    StoreError::Other(format!(
-                        "Failed to write AbortIncompleteMultipartUpload end: {}",
-                        e
-                    ))
-                })?;
+        XmlFilter {
+            prefix: filter.prefix.clone(),
+            tag: filter.tags.first().map(XmlTag::from),
+            object_size_greater_than: filter.object_size_greater_than,
+            object_size_less_than: filter.object_size_less_than,
+            and: None,
         }
+    }
+}
 
-        // End Rule
-        writer
-            .write_event(Event::End(BytesEnd::new("Rule")))
-            .map_err(|e| StoreError::Other(format!("Failed to write Rule end: {}", e)))?;
+impl From<&MinioLifecycleRule> for XmlRule {
+    fn from(rule: &MinioLifecycleRule) -> Self {
+        let has_expiration = rule.expiration_date.is_some()
+            || rule.expiration_days.is_some()
+            || rule.expiration_expired_object_delete_marker.is_some();
+        let has_noncurrent_expiration =
+            rule.noncurrent_version_expiration_noncurrent_days.is_some()
+                || rule.noncurrent_version_expiration_newer_versions.is_some();
+
+        XmlRule {
+            id: (!rule.id.is_empty()).then(|| rule.id.clone()),
+            status: if rule.status { "Enabled" } else { "Disabled" }.to_string(),
+            filter: Some(XmlFilter::from(&rule.filter)),
+            prefix: None,
+            expiration: has_expiration.then_some(XmlExpiration {
+                days: rule.expiration_days,
+                date: rule.expiration_date,
+                expired_object_delete_marker: rule.expiration_expired_object_delete_marker,
+            }),
+            transitions: rule
+                .transitions
+                .iter()
+                .map(|transition| XmlTransition {
+                    days: transition.days,
+                    date: transition.date,
+                    storage_class: transition.storage_class.clone(),
+                })
+                .collect(),
+            noncurrent_version_expiration: has_noncurrent_expiration.then_some(
+                XmlNoncurrentVersionExpiration {
+                    noncurrent_days: rule.noncurrent_version_expiration_noncurrent_days,
+                    newer_noncurrent_versions: rule.noncurrent_version_expiration_newer_versions,
+                },
+            ),
+            noncurrent_version_transitions: rule
+                .noncurrent_version_transitions
+                .iter()
+                .map(|transition| XmlNoncurrentVersionTransition {
+                    noncurrent_days: transition.noncurrent_days,
+                    newer_noncurrent_versions: transition.newer_noncurrent_versions,
+                    storage_class: transition.storage_class.clone(),
+                })
+                .collect(),
+            abort_incomplete_multipart_upload: rule
+                .abort_incomplete_multipart_upload_days_after_initiation
+                .map(|days| XmlAbortIncompleteMultipartUpload {
+                    days_after_initiation: days,
+                }),
+        }
     }
+}
 
-    // End LifecycleConfiguration
-    writer
-        .write_event(Event::End(BytesEnd::new("LifecycleConfiguration")))
-        .map_err(|e| {
-            StoreError::Other(format!("Failed to write LifecycleConfiguration end: {}", e))
-        })?;
+/// Parse an S3 lifecycle configuration XML document
+pub fn parse_lifecycle_config(xml: &str) -> Result<MinioLifecycleConfig, StoreError> {
+    let config: XmlLifecycleConfiguration = quick_xml::de::from_str(xml)
+        .map_err(|e| StoreError::Other(format!("Error parsing XML: {}", e)))?;
+
+    Ok(MinioLifecycleConfig {
+        rules: config
+            .rules
+            .into_iter()
+            .map(MinioLifecycleRule::from)
+            .collect(),
+    })
+}
 
-    // Get the XML string
-    let result = writer.into_inner().into_inner();
-    let xml_string = String::from_utf8(result)
-        .map_err(|e| StoreError::Other(format!("Failed to convert XML to UTF-8: {}", e)))?;
+/// Write a lifecycle configuration as an S3 lifecycle configuration XML document
+pub fn lifecycle_config_to_xml(config: &MinioLifecycleConfig) -> Result<String, StoreError> {
+    let config = XmlLifecycleConfiguration {
+        rules: config.rules.iter().map(XmlRule::from).collect(),
+    };
+    let body = quick_xml::se::to_string(&config)
+        .map_err(|e| StoreError::Other(format!("Failed to write lifecycle XML: {}", e)))?;
+
+    Ok(format!(r#"<?xml version="1.0" encoding="UTF-8"?>{}"#, body))
+}
 
-    Ok(xml_string)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_full_lifecycle_configuration() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<LifecycleConfiguration xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+  <Rule>
+    <ID>archive-logs</ID>
+    <Status>Enabled</Status>
+    <Filter>
+      <And>
+        <Prefix>logs/</Prefix>
+        <Tag><Key>team</Key><Value>search</Value></Tag>
+        <Tag><Key>tier</Key><Value>cold</Value></Tag>
+        <ObjectSizeGreaterThan>1024</ObjectSizeGreaterThan>
+        <ObjectSizeLessThan>1048576</ObjectSizeLessThan>
+      </And>
+    </Filter>
+    <Transition><Days>30</Days><StorageClass>STANDARD_IA</StorageClass></Transition>
+    <Transition><Days>90</Days><StorageClass>GLACIER</StorageClass></Transition>
+    <Expiration><Days>365</Days></Expiration>
+    <NoncurrentVersionTransition>
+      <NoncurrentDays>30</NoncurrentDays>
+      <NewerNoncurrentVersions>2</NewerNoncurrentVersions>
+      <StorageClass>GLACIER</StorageClass>
+    </NoncurrentVersionTransition>
+    <NoncurrentVersionExpiration>
+      <NoncurrentDays>90</NoncurrentDays>
+      <NewerNoncurrentVersions>5</NewerNoncurrentVersions>
+    </NoncurrentVersionExpiration>
+    <AbortIncompleteMultipartUpload>
+      <DaysAfterInitiation>7</DaysAfterInitiation>
+    </AbortIncompleteMultipartUpload>
+  </Rule>
+  <Rule>
+    <ID>legacy</ID>
+    <Prefix>tmp/</Prefix>
+    <Status>Disabled</Status>
+    <Expiration>
+      <Date>2025-06-01T00:00:00Z</Date>
+      <ExpiredObjectDeleteMarker>true</ExpiredObjectDeleteMarker>
+    </Expiration>
+  </Rule>
+</LifecycleConfiguration>"#;
+
+        let config = parse_lifecycle_config(xml).unwrap();
+        assert_eq!(config.rules.len(), 2);
+
+        let rule = &config.rules[0];
+        assert_eq!(rule.id, "archive-logs");
+        assert!(rule.status);
+        assert_eq!(rule.filter.prefix.as_deref(), Some("logs/"));
+        assert_eq!(rule.filter.tags.len(), 2);
+        assert_eq!(rule.filter.tags[1].key, "tier");
+        assert_eq!(rule.filter.object_size_greater_than, Some(1024));
+        assert_eq!(rule.filter.object_size_less_than, Some(1048576));
+        assert_eq!(rule.transitions.len(), 2);
+        assert_eq!(rule.transitions[1].storage_class, "GLACIER");
+        assert_eq!(rule.expiration_days, Some(365));
+        assert_eq!(
+            rule.noncurrent_version_transitions[0].newer_noncurrent_versions,
+            Some(2)
+        );
+        assert_eq!(rule.noncurrent_version_expiration_noncurrent_days, Some(90));
+        assert_eq!(rule.noncurrent_version_expiration_newer_versions, Some(5));
+        assert_eq!(
+            rule.abort_incomplete_multipart_upload_days_after_initiation,
+            Some(7)
+        );
+
+        let legacy = &config.rules[1];
+        assert!(!legacy.status);
+        assert_eq!(legacy.filter.prefix.as_deref(), Some("tmp/"));
+        assert_eq!(
+            legacy
+                .expiration_date
+                .map(|date| date.to_rfc3339())
+                .as_deref(),
+            Some("2025-06-01T00:00:00+00:00")
+        );
+        assert_eq!(legacy.expiration_expired_object_delete_marker, Some(true));
+    }
+
+    #[test]
+    fn test_lifecycle_xml_round_trip() {
+        let config = MinioLifecycleConfig {
+            rules: vec![
+                MinioLifecycleRule {
+                    id: "tagged".to_string(),
+                    status: true,
+                    filter: MinioFilter {
+                        prefix: Some("data/".to_string()),
+                        tags: vec![MinioTag {
+                            key: "env".to_string(),
+                            value: "dev".to_string(),
+                        }],
+                        ..Default::default()
+                    },
+                    expiration_days: Some(14),
+                    transitions: vec![MinioTransition {
+                        days: Some(7),
+                        date: None,
+                        storage_class: "STANDARD_IA".to_string(),
+                    }],
+                    ..Default::default()
+                },
+                MinioLifecycleRule {
+                    id: "single".to_string(),
+                    status: false,
+                    filter: MinioFilter {
+                        object_size_less_than: Some(100),
+                        ..Default::default()
+                    },
+                    noncurrent_version_expiration_noncurrent_days: Some(30),
+                    ..Default::default()
+                },
+            ],
+        };
+
+        let xml = lifecycle_config_to_xml(&config).unwrap();
+        assert!(xml.contains("<And><Prefix>data/</Prefix><Tag><Key>env</Key>"));
+        assert!(xml.contains("<Filter><ObjectSizeLessThan>100</ObjectSizeLessThan></Filter>"));
+        assert_eq!(parse_lifecycle_config(&xml).unwrap(), config);
+    }
 }
@@ -1,3 +1,7 @@
+#[allow(clippy::module_inception)]
 pub mod minio;
 
-pub use minio::{MinioClient, MinioFilter, MinioLifecycleConfig, MinioLifecycleRule};
+pub use minio::{
+    MinioClient, MinioFilter, MinioLifecycleConfig, MinioLifecycleRule,
+    MinioNoncurrentVersionTransition, MinioTag, MinioTransition,
+};
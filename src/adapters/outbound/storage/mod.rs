@@ -2,9 +2,20 @@
 pub mod error;
 
 // Storage implementations
+pub mod backends;
 pub mod bucket;
+pub mod chunking;
+pub mod coalescing;
+pub mod encryption;
+pub mod erasure;
+pub mod head_cache;
+pub mod hedging;
 pub mod lifecycle;
 pub mod lifecycle_adapter;
+pub mod migration;
+pub mod range_cache;
+pub mod signing;
+pub mod timeout;
 pub mod versioning;
 
 // Provider-specific implementations
@@ -12,6 +23,19 @@ pub mod minio;
 pub mod s3;
 
 // Re-export key types
-pub use s3::{S3ObjectStoreAdapter, VersionedS3ObjectStoreAdapter, S3Config, create_s3_store};
+pub use s3::{
+    NativeVersionedS3Store, S3ObjectStoreAdapter, VersionedS3ObjectStoreAdapter, S3Config,
+    create_s3_store,
+};
+pub use backends::{AzureConfig, GcsConfig, create_azure_store, create_gcs_store, create_local_store};
+pub use chunking::ChunkedStore;
+pub use coalescing::CoalescingStore;
+pub use encryption::EncryptedStore;
+pub use erasure::ErasureCodedStore;
+pub use head_cache::HeadCachingStore;
+pub use hedging::HedgedStore;
+pub use migration::MigratingStore;
+pub use range_cache::RangeCachingStore;
 pub use error::StoreError;
-pub use versioning::VersionedStore;
+pub use timeout::{StorageTimeouts, TimeoutStore};
+pub use versioning::{VersionMigration, VersionPage, VersionedStore};
@@ -0,0 +1,340 @@
+use async_trait::async_trait;
+use bytes::{Bytes, BytesMut};
+use futures::{
+    StreamExt,
+    stream::{self, BoxStream},
+};
+use moka::future::Cache;
+use object_store::{
+    GetOptions, GetRange, GetResult, GetResultPayload, ListResult, MultipartUpload, ObjectMeta,
+    ObjectStore, PutMultipartOptions, PutOptions, PutPayload, PutResult, path::Path,
+};
+use std::ops::Range;
+use std::sync::Arc;
+
+/// A cached block: the object, the ETag of the contents it was read from and
+/// the index of the block within them
+type BlockKey = (Path, String, u64);
+
+/// An ObjectStore decorator that caches recently read byte ranges of large
+/// objects
+///
+/// Ranged GETs of objects of at least `min_object_size` bytes are served in
+/// blocks of `block_size` bytes aligned to the start of the object. Blocks
+/// are kept in memory, up to `max_capacity` bytes in all, so seeking back and
+/// forth in a video or reading the entries of a zip only fetches each block
+/// from the inner store once while it stays cached. Whole-object GETs and
+/// GETs of smaller objects go straight to the inner store.
+///
+/// Blocks are cached under the ETag of the object they were read from and
+/// fetched on the condition that it still matches, so a rewritten object is
+/// never served from blocks of its old contents. The ETag of each ranged GET
+/// comes from a `head` of the object, which is cheap behind a
+/// [`HeadCachingStore`](super::HeadCachingStore).
+#[derive(Debug)]
+pub struct RangeCachingStore<T: ObjectStore> {
+    /// The underlying object store
+    inner: Arc<T>,
+
+    blocks: Cache<BlockKey, Bytes>,
+
+    block_size: u64,
+
+    /// Smallest object whose ranges are cached
+    min_object_size: u64,
+}
+
+impl<T: ObjectStore> std::fmt::Display for RangeCachingStore<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RangeCachingStore({})", self.inner)
+    }
+}
+
+impl<T: ObjectStore> RangeCachingStore<T> {
+    /// Cache up to `max_capacity` bytes of blocks of `block_size` bytes, read
+    /// from objects of at least `min_object_size` bytes
+    pub fn new(store: T, max_capacity: u64, block_size: u64, min_object_size: u64) -> Self {
+        RangeCachingStore {
+            inner: Arc::new(store),
+            blocks: Cache::builder()
+                .max_capacity(max_capacity)
+                .weigher(|_, block: &Bytes| block.len().try_into().unwrap_or(u32::MAX))
+                .build(),
+            block_size: block_size.max(1),
+            min_object_size,
+        }
+    }
+
+    /// Whether a GET with these options is a plain ranged read
+    fn is_ranged_read(options: &GetOptions) -> bool {
+        options.range.is_some()
+            && options.if_match.is_none()
+            && options.if_none_match.is_none()
+            && options.if_modified_since.is_none()
+            && options.if_unmodified_since.is_none()
+            && options.version.is_none()
+            && !options.head
+    }
+
+    /// Block `index` of the object with `meta`, from the cache or the inner
+    /// store
+    async fn block(
+        &self,
+        meta: &ObjectMeta,
+        e_tag: &str,
+        index: u64,
+    ) -> object_store::Result<Bytes> {
+        let key = (meta.location.clone(), e_tag.to_string(), index);
+        if let Some(block) = self.blocks.get(&key).await {
+            return Ok(block);
+        }
+
+        let start = index * self.block_size;
+        let options = GetOptions {
+            range: Some(GetRange::Bounded(
+                start..(start + self.block_size).min(meta.size),
+            )),
+            if_match: Some(e_tag.to_string()),
+            ..Default::default()
+        };
+        let block = self
+            .inner
+            .get_opts(&meta.location, options)
+            .await?
+            .bytes()
+            .await?;
+        self.blocks.insert(key, block.clone()).await;
+        Ok(block)
+    }
+
+    /// Read `range` of the object with `meta` from its blocks
+    async fn read_range(
+        &self,
+        meta: &ObjectMeta,
+        e_tag: &str,
+        range: &Range<u64>,
+    ) -> object_store::Result<Bytes> {
+        let mut data = BytesMut::with_capacity((range.end - range.start) as usize);
+        for index in range.start / self.block_size..range.end.div_ceil(self.block_size) {
+            let block = self.block(meta, e_tag, index).await?;
+            let block_start = index * self.block_size;
+            let from = range.start.saturating_sub(block_start) as usize;
+            let to = ((range.end - block_start) as usize).min(block.len());
+            data.extend_from_slice(&block[from..to]);
+        }
+        Ok(data.freeze())
+    }
+}
+
+#[async_trait]
+impl<T: ObjectStore> ObjectStore for RangeCachingStore<T> {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        options: PutOptions,
+    ) -> object_store::Result<PutResult> {
+        self.inner.put_opts(location, payload, options).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        options: PutMultipartOptions,
+    ) -> object_store::Result<Box<dyn MultipartUpload>> {
+        self.inner.put_multipart_opts(location, options).await
+    }
+
+    async fn get_opts(
+        &self,
+        location: &Path,
+        options: GetOptions,
+    ) -> object_store::Result<GetResult> {
+        if !Self::is_ranged_read(&options) {
+            return self.inner.get_opts(location, options).await;
+        }
+
+        let meta = self.inner.head(location).await?;
+        let (Some(e_tag), Some(Ok(range))) = (
+            meta.e_tag.clone(),
+            options
+                .range
+                .as_ref()
+                .map(|range| range.as_range(meta.size)),
+        ) else {
+            // Invalid ranges are left to the inner store to report
+            return self.inner.get_opts(location, options).await;
+        };
+        if meta.size < self.min_object_size {
+            return self.inner.get_opts(location, options).await;
+        }
+
+        let data = match self.read_range(&meta, &e_tag, &range).await {
+            Ok(data) => data,
+            // The object changed since its head was read
+            Err(object_store::Error::Precondition { .. }) => {
+                return self.inner.get_opts(location, options).await;
+            }
+            Err(e) => return Err(e),
+        };
+        Ok(GetResult {
+            payload: GetResultPayload::Stream(stream::once(async { Ok(data) }).boxed()),
+            meta,
+            range,
+            attributes: Default::default(),
+        })
+    }
+
+    async fn head(&self, location: &Path) -> object_store::Result<ObjectMeta> {
+        self.inner.head(location).await
+    }
+
+    async fn delete(&self, location: &Path) -> object_store::Result<()> {
+        self.inner.delete(location).await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'static, object_store::Result<ObjectMeta>> {
+        self.inner.list(prefix)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> object_store::Result<ListResult> {
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        self.inner.copy(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        self.inner.copy_if_not_exists(from, to).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use object_store::memory::InMemory;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Store that counts the ranged GETs it answers
+    #[derive(Debug)]
+    struct CountingStore {
+        inner: InMemory,
+        ranged_gets: AtomicUsize,
+    }
+
+    impl std::fmt::Display for CountingStore {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "CountingStore")
+        }
+    }
+
+    #[async_trait]
+    impl ObjectStore for CountingStore {
+        async fn put_opts(
+            &self,
+            location: &Path,
+            payload: PutPayload,
+            options: PutOptions,
+        ) -> object_store::Result<PutResult> {
+            self.inner.put_opts(location, payload, options).await
+        }
+
+        async fn put_multipart_opts(
+            &self,
+            location: &Path,
+            options: PutMultipartOptions,
+        ) -> object_store::Result<Box<dyn MultipartUpload>> {
+            self.inner.put_multipart_opts(location, options).await
+        }
+
+        async fn get_opts(
+            &self,
+            location: &Path,
+            options: GetOptions,
+        ) -> object_store::Result<GetResult> {
+            if options.range.is_some() {
+                self.ranged_gets.fetch_add(1, Ordering::SeqCst);
+            }
+            self.inner.get_opts(location, options).await
+        }
+
+        async fn delete(&self, location: &Path) -> object_store::Result<()> {
+            self.inner.delete(location).await
+        }
+
+        fn list(
+            &self,
+            prefix: Option<&Path>,
+        ) -> BoxStream<'static, object_store::Result<ObjectMeta>> {
+            self.inner.list(prefix)
+        }
+
+        async fn list_with_delimiter(
+            &self,
+            prefix: Option<&Path>,
+        ) -> object_store::Result<ListResult> {
+            self.inner.list_with_delimiter(prefix).await
+        }
+
+        async fn copy(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+            self.inner.copy(from, to).await
+        }
+
+        async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+            self.inner.copy_if_not_exists(from, to).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ranges_are_served_from_cached_blocks() {
+        let inner = Arc::new(CountingStore {
+            inner: InMemory::new(),
+            ranged_gets: AtomicUsize::new(0),
+        });
+        let store = RangeCachingStore::new(inner.clone() as Arc<dyn ObjectStore>, 1024, 4, 8);
+        let ranged_gets = || inner.ranged_gets.load(Ordering::SeqCst);
+        let path = Path::from("media/movie.mp4");
+        store
+            .put(&path, Bytes::from_static(b"0123456789").into())
+            .await
+            .unwrap();
+
+        // Spanning blocks 0 and 1, then seeking back within them
+        assert_eq!(&store.get_range(&path, 2..6).await.unwrap()[..], b"2345");
+        assert_eq!(ranged_gets(), 2);
+        assert_eq!(
+            &store.get_range(&path, 0..8).await.unwrap()[..],
+            b"01234567"
+        );
+        assert_eq!(ranged_gets(), 2);
+
+        // The short last block and an open-ended range
+        let options = GetOptions {
+            range: Some(GetRange::Offset(7)),
+            ..Default::default()
+        };
+        let result = store.get_opts(&path, options).await.unwrap();
+        assert_eq!(result.range, 7..10);
+        assert_eq!(&result.bytes().await.unwrap()[..], b"789");
+        assert_eq!(ranged_gets(), 3);
+
+        // A rewritten object is not served from the blocks of its old contents
+        store
+            .put(&path, Bytes::from_static(b"abcdefghij").into())
+            .await
+            .unwrap();
+        assert_eq!(&store.get_range(&path, 2..6).await.unwrap()[..], b"cdef");
+        assert_eq!(ranged_gets(), 5);
+
+        // Small objects are not cached
+        let small = Path::from("media/poster.png");
+        store
+            .put(&small, Bytes::from_static(b"tiny").into())
+            .await
+            .unwrap();
+        store.get_range(&small, 0..2).await.unwrap();
+        store.get_range(&small, 0..2).await.unwrap();
+        assert_eq!(ranged_gets(), 7);
+    }
+}
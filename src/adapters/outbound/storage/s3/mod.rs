@@ -3,9 +3,11 @@
 //! This module provides S3-compatible storage adapters that implement
 //! the ObjectStore and VersionedObjectStore traits.
 
+pub mod native_versioning;
 pub mod s3_adapter;
 pub mod versioned_s3_adapter;
 
+pub use native_versioning::NativeVersionedS3Store;
 pub use s3_adapter::S3ObjectStoreAdapter;
 pub use versioned_s3_adapter::VersionedS3ObjectStoreAdapter;
 
@@ -0,0 +1,595 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use http::{Method, Request};
+use object_store::{GetOptions, ObjectStore as ObjectStoreBackend, path::Path as ObjectPath};
+use quick_xml::de::from_str;
+use reqwest::{Body, Url};
+use serde::Deserialize;
+use std::ops::Range;
+use std::sync::Arc;
+use tokio_util::io::StreamReader;
+
+use crate::{
+    adapters::outbound::storage::{
+        bucket::{BucketError, S3Client},
+        s3::S3ObjectStoreAdapter,
+    },
+    domain::{
+        errors::{StorageError, StorageResult},
+        value_objects::{ObjectKey, VersionId},
+    },
+    ports::storage::{
+        ObjectInfo, ObjectStore, StorageVersionMetadata, StorageVersionedObject,
+        VersionedObjectStore,
+    },
+};
+
+/// Header S3 names the version a write created in
+const VERSION_ID_HEADER: &str = "x-amz-version-id";
+
+/// Versioned store using the versioning of an S3 or MinIO bucket
+///
+/// Every write of an object becomes a version of it in the bucket, under the
+/// version ID the backend assigns, instead of replaced versions being copied
+/// to `.v_<id>` paths next to the object. Versions are read
+/// through the backend's `versionId` parameter and listed with its
+/// `?versions` listing. The bucket must have versioning enabled: writes to
+/// an unversioned bucket get no version ID and fail.
+#[derive(Clone)]
+pub struct NativeVersionedS3Store {
+    base_adapter: Arc<S3ObjectStoreAdapter>,
+    store: Arc<dyn ObjectStoreBackend>,
+    client: S3Client,
+    bucket: String,
+}
+
+impl NativeVersionedS3Store {
+    /// Create a store for `bucket`, reading and writing objects through
+    /// `base_adapter` and `store` and managing versions through `client`
+    pub fn new(
+        base_adapter: Arc<S3ObjectStoreAdapter>,
+        store: Arc<dyn ObjectStoreBackend>,
+        client: S3Client,
+        bucket: impl Into<String>,
+    ) -> Self {
+        Self {
+            base_adapter,
+            store,
+            client,
+            bucket: bucket.into(),
+        }
+    }
+
+    /// URL of the bucket, or of `key` in it, addressing `version_id` if given
+    fn url(&self, key: Option<&ObjectKey>, version_id: Option<&VersionId>) -> StorageResult<Url> {
+        let invalid = |reason: String| StorageError::StorageBackendError {
+            message: format!("Invalid URL for bucket {}: {}", self.bucket, reason),
+        };
+
+        let mut url =
+            Url::parse(&self.client.get_base_url()).map_err(|e| invalid(e.to_string()))?;
+        {
+            let mut segments = url
+                .path_segments_mut()
+                .map_err(|_| invalid("the endpoint cannot have a path".to_string()))?;
+            segments.pop_if_empty().push(&self.bucket);
+            if let Some(key) = key {
+                segments.extend(key.as_str().split('/'));
+            }
+        }
+        if let Some(version_id) = version_id {
+            url.query_pairs_mut()
+                .append_pair("versionId", version_id.as_str());
+        }
+        Ok(url)
+    }
+
+    /// Sign and send a request without a body to `url`
+    async fn send(
+        &self,
+        method: Method,
+        url: &Url,
+        headers: &[(&str, String)],
+    ) -> Result<reqwest::Response, BucketError> {
+        let mut request = Request::builder().method(method).uri(url.as_str());
+        for (name, value) in headers {
+            request = request.header(*name, value);
+        }
+        self.client
+            .execute_request(request.body(Body::from(""))?)
+            .await
+    }
+
+    /// Get `version_id` of `key` as `options` ask, for instance only its
+    /// metadata or a byte range of it
+    async fn get_version(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+        options: GetOptions,
+    ) -> StorageResult<object_store::GetResult> {
+        let options = GetOptions {
+            version: Some(version_id.as_str().to_string()),
+            ..options
+        };
+        self.store
+            .get_opts(&ObjectPath::from(key.as_str()), options)
+            .await
+            .map_err(|e| match e {
+                object_store::Error::NotFound { .. } => version_not_found(key, version_id),
+                e => S3ObjectStoreAdapter::convert_error(e),
+            })
+    }
+
+    /// Version ID of the current version of `key`, if it has one
+    async fn latest_version_id(&self, key: &ObjectKey) -> StorageResult<Option<String>> {
+        match self.store.head(&ObjectPath::from(key.as_str())).await {
+            Ok(meta) => Ok(meta.version),
+            Err(object_store::Error::NotFound { .. }) => Ok(None),
+            Err(e) => Err(S3ObjectStoreAdapter::convert_error(e)),
+        }
+    }
+
+    /// One page of the versions of keys starting with `key`, continuing after
+    /// `marker`, a key and version ID
+    async fn list_page(
+        &self,
+        key: &ObjectKey,
+        marker: Option<&(String, String)>,
+    ) -> StorageResult<VersionListing> {
+        let mut url = self.url(None, None)?;
+        {
+            let mut query = url.query_pairs_mut();
+            query
+                .append_key_only("versions")
+                .append_pair("prefix", key.as_str());
+            if let Some((key_marker, version_id_marker)) = marker {
+                query
+                    .append_pair("key-marker", key_marker)
+                    .append_pair("version-id-marker", version_id_marker);
+            }
+        }
+
+        let body = self
+            .send(Method::GET, &url, &[])
+            .await
+            .map_err(|e| backend_error(key, e))?
+            .text()
+            .await
+            .map_err(|e| StorageError::StorageBackendError {
+                message: format!("Failed to read the versions of {}: {}", key, e),
+            })?;
+        VersionListing::parse(&body)
+    }
+}
+
+/// Error for a version the backend does not have
+fn version_not_found(key: &ObjectKey, version_id: &VersionId) -> StorageError {
+    StorageError::VersionNotFound {
+        key: key.clone(),
+        version_id: version_id.clone(),
+    }
+}
+
+/// Error for a failed request about `key`
+fn backend_error(key: &ObjectKey, error: BucketError) -> StorageError {
+    StorageError::StorageBackendError {
+        message: format!("Request for {} failed: {}", key, error),
+    }
+}
+
+/// Version ID the backend assigned, which must be one this server accepts
+fn backend_version_id(version_id: String) -> StorageResult<VersionId> {
+    VersionId::new(version_id.clone()).map_err(|e| StorageError::StorageBackendError {
+        message: format!(
+            "Backend assigned unusable version ID '{}': {}",
+            version_id, e
+        ),
+    })
+}
+
+/// Version ID the response to a write names, required of a versioned bucket
+fn written_version_id(
+    bucket: &str,
+    key: &ObjectKey,
+    version_id: Option<String>,
+) -> StorageResult<String> {
+    version_id.ok_or_else(|| StorageError::StorageBackendError {
+        message: format!(
+            "Bucket {} assigned no version ID to {}; native versioning requires versioning to be enabled on the bucket",
+            bucket, key
+        ),
+    })
+}
+
+/// A page of a bucket's `?versions` listing
+#[derive(Debug, Default, PartialEq)]
+struct VersionListing {
+    versions: Vec<ListedVersion>,
+    /// Key and version ID to continue after, when there are more pages
+    next_marker: Option<(String, String)>,
+}
+
+/// A version or delete marker in a `?versions` listing
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct ListedVersion {
+    #[serde(rename = "Key")]
+    key: String,
+    #[serde(rename = "VersionId")]
+    version_id: String,
+    #[serde(rename = "IsLatest", default)]
+    is_latest: bool,
+    #[serde(rename = "LastModified")]
+    last_modified: DateTime<Utc>,
+    #[serde(rename = "ETag", default)]
+    etag: Option<String>,
+    #[serde(rename = "Size", default)]
+    size: u64,
+    #[serde(skip)]
+    is_delete_marker: bool,
+}
+
+impl VersionListing {
+    /// Parse a `ListVersionsResult` document
+    fn parse(xml: &str) -> StorageResult<Self> {
+        // Versions and delete markers are interleaved, so the document is
+        // read as a sequence of elements rather than as lists of each
+        #[derive(Deserialize)]
+        struct ListVersionsResult {
+            #[serde(rename = "$value", default)]
+            elements: Vec<Element>,
+        }
+
+        #[derive(Deserialize)]
+        enum Element {
+            IsTruncated(bool),
+            NextKeyMarker(String),
+            NextVersionIdMarker(String),
+            Version(ListedVersion),
+            DeleteMarker(ListedVersion),
+            #[serde(other)]
+            Other,
+        }
+
+        let result: ListVersionsResult =
+            from_str(xml).map_err(|e| StorageError::StorageBackendError {
+                message: format!("Invalid version listing: {}", e),
+            })?;
+
+        let mut listing = VersionListing::default();
+        let (mut truncated, mut next_key, mut next_version_id) = (false, None, None);
+        for element in result.elements {
+            match element {
+                Element::IsTruncated(value) => truncated = value,
+                Element::NextKeyMarker(key) => next_key = Some(key),
+                Element::NextVersionIdMarker(version_id) => next_version_id = Some(version_id),
+                Element::Version(version) => listing.versions.push(version),
+                Element::DeleteMarker(marker) => listing.versions.push(ListedVersion {
+                    is_delete_marker: true,
+                    ..marker
+                }),
+                Element::Other => {}
+            }
+        }
+        if truncated {
+            listing.next_marker = next_key.zip(next_version_id);
+        }
+        Ok(listing)
+    }
+}
+
+#[async_trait]
+impl VersionedObjectStore for NativeVersionedS3Store {
+    async fn put_object_version(
+        &self,
+        key: &ObjectKey,
+        _version_id: Option<&VersionId>,
+        data: Bytes,
+        content_type: Option<&str>,
+    ) -> StorageResult<ObjectInfo> {
+        let info = self
+            .base_adapter
+            .put_object(key, data, content_type)
+            .await?;
+        written_version_id(&self.bucket, key, info.version_id.clone())?;
+        Ok(info)
+    }
+
+    async fn get_object_version(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+    ) -> StorageResult<Bytes> {
+        self.get_version(key, version_id, GetOptions::default())
+            .await?
+            .bytes()
+            .await
+            .map_err(S3ObjectStoreAdapter::convert_error)
+    }
+
+    async fn get_object_version_stream(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+        range: Option<Range<u64>>,
+    ) -> StorageResult<Box<dyn tokio::io::AsyncRead + Send + Unpin>> {
+        let options = GetOptions {
+            range: range.map(Into::into),
+            ..Default::default()
+        };
+        let result = self.get_version(key, version_id, options).await?;
+        let stream = result.into_stream().map_err(std::io::Error::other);
+        Ok(Box::new(StreamReader::new(stream)))
+    }
+
+    async fn delete_object_version(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+    ) -> StorageResult<()> {
+        // Deleting by version ID removes the version rather than adding a delete marker
+        let url = self.url(Some(key), Some(version_id))?;
+        match self.send(Method::DELETE, &url, &[]).await {
+            Ok(_) => Ok(()),
+            Err(BucketError::ServiceError {
+                status_code: 404, ..
+            }) => Err(version_not_found(key, version_id)),
+            Err(e) => Err(backend_error(key, e)),
+        }
+    }
+
+    async fn list_object_versions(
+        &self,
+        key: &ObjectKey,
+    ) -> StorageResult<Vec<StorageVersionMetadata>> {
+        let mut versions = Vec::new();
+        let mut marker = None;
+        loop {
+            let page = self.list_page(key, marker.as_ref()).await?;
+            for version in page.versions {
+                // The prefix also matches longer keys
+                if version.key != key.as_str() {
+                    continue;
+                }
+                versions.push(StorageVersionMetadata {
+                    version_id: backend_version_id(version.version_id)?,
+                    key: key.clone(),
+                    size: version.size,
+                    last_modified: version.last_modified,
+                    etag: version.etag,
+                    is_latest: version.is_latest,
+                    is_delete_marker: version.is_delete_marker,
+                });
+            }
+
+            // Keys are listed in order, so once the listing moves past the key
+            // there are no versions of it left
+            marker = match page.next_marker {
+                Some(next) if next.0 == key.as_str() => Some(next),
+                _ => break,
+            };
+        }
+        Ok(versions)
+    }
+
+    async fn head_object_version(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+    ) -> StorageResult<StorageVersionMetadata> {
+        let options = GetOptions {
+            head: true,
+            ..Default::default()
+        };
+        let meta = self.get_version(key, version_id, options).await?.meta;
+        let latest = self.latest_version_id(key).await?;
+
+        Ok(StorageVersionMetadata {
+            version_id: version_id.clone(),
+            key: key.clone(),
+            size: meta.size,
+            last_modified: meta.last_modified,
+            etag: meta.e_tag,
+            is_latest: latest.as_deref() == Some(version_id.as_str()),
+            is_delete_marker: false,
+        })
+    }
+
+    async fn copy_object_version(
+        &self,
+        source_key: &ObjectKey,
+        source_version_id: &VersionId,
+        dest_key: &ObjectKey,
+    ) -> StorageResult<ObjectInfo> {
+        #[derive(Deserialize)]
+        struct CopyObjectResult {
+            #[serde(rename = "ETag", default)]
+            etag: Option<String>,
+        }
+
+        let source = self.url(Some(source_key), Some(source_version_id))?;
+        let copy_source = format!("{}?{}", source.path(), source.query().unwrap_or_default());
+        let response = match self
+            .send(
+                Method::PUT,
+                &self.url(Some(dest_key), None)?,
+                &[("x-amz-copy-source", copy_source)],
+            )
+            .await
+        {
+            Ok(response) => response,
+            Err(BucketError::ServiceError {
+                status_code: 404, ..
+            }) => return Err(version_not_found(source_key, source_version_id)),
+            Err(e) => return Err(backend_error(source_key, e)),
+        };
+
+        let version_id = response
+            .headers()
+            .get(VERSION_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let version_id = written_version_id(&self.bucket, dest_key, version_id)?;
+        let body = response.text().await.unwrap_or_default();
+        let etag = from_str::<CopyObjectResult>(&body)
+            .ok()
+            .and_then(|result| result.etag);
+
+        Ok(ObjectInfo {
+            key: dest_key.clone(),
+            size: 0, // The copy response does not give the size
+            etag,
+            version_id: Some(version_id),
+            last_modified: Utc::now(),
+        })
+    }
+
+    async fn restore_object_version(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+    ) -> StorageResult<ObjectInfo> {
+        // Copying a version onto its own key makes the copy the latest version
+        self.copy_object_version(key, version_id, key).await
+    }
+
+    async fn get_latest_version(&self, key: &ObjectKey) -> StorageResult<StorageVersionedObject> {
+        let result = self
+            .store
+            .get(&ObjectPath::from(key.as_str()))
+            .await
+            .map_err(|e| match e {
+                object_store::Error::NotFound { .. } => {
+                    StorageError::ObjectNotFound { key: key.clone() }
+                }
+                e => S3ObjectStoreAdapter::convert_error(e),
+            })?;
+        let meta = result.meta.clone();
+        let version_id = written_version_id(&self.bucket, key, meta.version)?;
+        let version_id = backend_version_id(version_id)?;
+        let data = result
+            .bytes()
+            .await
+            .map_err(S3ObjectStoreAdapter::convert_error)?;
+
+        Ok(StorageVersionedObject {
+            key: key.clone(),
+            version_id: version_id.clone(),
+            data,
+            metadata: StorageVersionMetadata {
+                version_id,
+                key: key.clone(),
+                size: meta.size,
+                last_modified: meta.last_modified,
+                etag: meta.e_tag,
+                is_latest: true,
+                is_delete_marker: false,
+            },
+        })
+    }
+
+    async fn version_exists(&self, key: &ObjectKey, version_id: &VersionId) -> StorageResult<bool> {
+        let options = GetOptions {
+            head: true,
+            ..Default::default()
+        };
+        match self.get_version(key, version_id, options).await {
+            Ok(_) => Ok(true),
+            Err(StorageError::VersionNotFound { .. }) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::value_objects::BucketName;
+    use object_store::memory::InMemory;
+
+    fn store() -> NativeVersionedS3Store {
+        let backend = Arc::new(InMemory::new());
+        let adapter = S3ObjectStoreAdapter::new(
+            backend.clone(),
+            BucketName::new("photos".to_string()).unwrap(),
+        );
+        let client = S3Client::new(
+            "minio:9000".to_string(),
+            None,
+            "access".to_string(),
+            "secret".to_string(),
+            false,
+            None,
+        );
+        NativeVersionedS3Store::new(Arc::new(adapter), backend, client, "photos")
+    }
+
+    #[test]
+    fn test_urls_address_versions() {
+        let store = store();
+        let key = ObjectKey::new("albums/summer 2024/a+b.jpg".to_string()).unwrap();
+        let version_id = VersionId::new("3HL4kqtJ.lcpXroDTDm_JVBH40".to_string()).unwrap();
+
+        assert_eq!(
+            store.url(Some(&key), Some(&version_id)).unwrap().as_str(),
+            "http://minio:9000/photos/albums/summer%202024/a+b.jpg?versionId=3HL4kqtJ.lcpXroDTDm_JVBH40"
+        );
+        assert_eq!(
+            store.url(None, None).unwrap().as_str(),
+            "http://minio:9000/photos"
+        );
+    }
+
+    #[test]
+    fn test_parse_version_listing() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <ListVersionsResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+                <Name>photos</Name>
+                <Prefix>a.jpg</Prefix>
+                <KeyMarker></KeyMarker>
+                <MaxKeys>2</MaxKeys>
+                <IsTruncated>true</IsTruncated>
+                <NextKeyMarker>a.jpg</NextKeyMarker>
+                <NextVersionIdMarker>v2</NextVersionIdMarker>
+                <DeleteMarker>
+                    <Key>a.jpg</Key>
+                    <VersionId>v3</VersionId>
+                    <IsLatest>true</IsLatest>
+                    <LastModified>2024-05-01T10:00:02.000Z</LastModified>
+                </DeleteMarker>
+                <Version>
+                    <Key>a.jpg</Key>
+                    <VersionId>v2</VersionId>
+                    <IsLatest>false</IsLatest>
+                    <LastModified>2024-05-01T10:00:01.000Z</LastModified>
+                    <ETag>&quot;9b2cf535f27731c974343645a3985328&quot;</ETag>
+                    <Size>5</Size>
+                    <StorageClass>STANDARD</StorageClass>
+                </Version>
+            </ListVersionsResult>"#;
+
+        let listing = VersionListing::parse(xml).unwrap();
+        assert_eq!(
+            listing.next_marker,
+            Some(("a.jpg".to_string(), "v2".to_string()))
+        );
+        assert_eq!(listing.versions.len(), 2);
+        assert!(listing.versions[0].is_delete_marker);
+        assert!(listing.versions[0].is_latest);
+        assert_eq!(listing.versions[1].version_id, "v2");
+        assert_eq!(listing.versions[1].size, 5);
+        assert_eq!(
+            listing.versions[1].etag.as_deref(),
+            Some("\"9b2cf535f27731c974343645a3985328\"")
+        );
+        assert!(!listing.versions[1].is_delete_marker);
+
+        let last_page = VersionListing::parse(
+            "<ListVersionsResult><IsTruncated>false</IsTruncated></ListVersionsResult>",
+        )
+        .unwrap();
+        assert_eq!(last_page, VersionListing::default());
+    }
+}
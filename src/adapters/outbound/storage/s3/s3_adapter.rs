@@ -2,34 +2,91 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use object_store::{
     ObjectStore as ObjectStoreBackend, 
+    GetOptions,
     path::Path as ObjectPath,
     PutPayload,
     ObjectMeta,
+    Attribute,
+    Attributes,
+    WriteMultipart,
+    buffered::BufWriter,
 };
 
 use crate::{
+    adapters::outbound::storage::timeout::OperationTimedOut,
     domain::{
-        models::{ObjectMetadata, Filter},
+        models::{ObjectMetadata, Filter, TRANSACTION_STAGING_PREFIX, VERSION_STAGING_PREFIX},
         value_objects::{ObjectKey, BucketName},
         errors::{StorageError, StorageResult},
     },
     ports::storage::{ObjectStore, ObjectInfo, ObjectListItem, CompletedPart, MultipartUpload, PresignedUrlMethod},
 };
 use std::collections::HashMap;
-use bytes::Bytes;
-use tokio_util::io::ReaderStream;
+use std::ops::Range;
+use bytes::{Bytes, BytesMut};
+use futures::TryStreamExt;
+use percent_encoding::{NON_ALPHANUMERIC, utf8_percent_encode};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio_util::io::StreamReader;
+
+/// Prefix under which the parts of in-progress multipart uploads are staged
+const MULTIPART_STAGING_PREFIX: &str = ".multipart";
+
+/// Size of the parts streamed uploads are split into
+pub const DEFAULT_UPLOAD_PART_SIZE: usize = 8 * 1024 * 1024;
+
+/// Number of parts of one streamed upload sent to the backend at a time
+pub const DEFAULT_UPLOAD_CONCURRENCY: usize = 4;
 
 /// S3 storage adapter that implements the ObjectStore trait
 #[derive(Clone)]
 pub struct S3ObjectStoreAdapter {
     store: Arc<dyn ObjectStoreBackend>,
     bucket: BucketName,
+    part_size: usize,
+    upload_concurrency: usize,
 }
 
 impl S3ObjectStoreAdapter {
     /// Create a new S3 adapter
     pub fn new(store: Arc<dyn ObjectStoreBackend>, bucket: BucketName) -> Self {
-        Self { store, bucket }
+        Self {
+            store,
+            bucket,
+            part_size: DEFAULT_UPLOAD_PART_SIZE,
+            upload_concurrency: DEFAULT_UPLOAD_CONCURRENCY,
+        }
+    }
+
+    /// Split streamed uploads into parts of `part_size` bytes, uploading up to
+    /// `concurrency` parts at a time
+    ///
+    /// S3 rejects parts smaller than 5 MiB other than the last one.
+    pub fn with_parallel_upload(mut self, part_size: usize, concurrency: usize) -> Self {
+        self.part_size = part_size.max(1);
+        self.upload_concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Feed `part` and the rest of `reader` to `writer`, waiting for an upload
+    /// slot before each part so at most `upload_concurrency` are in flight
+    async fn write_parts(
+        &self,
+        writer: &mut WriteMultipart,
+        mut part: Bytes,
+        reader: &mut (impl AsyncRead + Unpin),
+    ) -> StorageResult<u64> {
+        let mut size = 0;
+        while !part.is_empty() {
+            writer
+                .wait_for_capacity(self.upload_concurrency)
+                .await
+                .map_err(Self::convert_error)?;
+            size += part.len() as u64;
+            writer.put(part);
+            part = read_part(reader, self.part_size).await?;
+        }
+        Ok(size)
     }
 
     /// Convert ObjectKey to object_store Path
@@ -37,6 +94,32 @@ impl S3ObjectStoreAdapter {
         ObjectPath::from(key.as_str())
     }
 
+    /// Path a part of a multipart upload is staged at until the upload completes
+    fn part_path(upload_id: &str, part_number: u32) -> ObjectPath {
+        ObjectPath::from(format!(
+            "{}/{}/{:05}",
+            MULTIPART_STAGING_PREFIX, upload_id, part_number
+        ))
+    }
+
+    /// Delete every part staged for a multipart upload
+    async fn delete_staged_parts(&self, upload_id: &str) -> StorageResult<()> {
+        let prefix = ObjectPath::from(format!("{}/{}", MULTIPART_STAGING_PREFIX, upload_id));
+        let parts: Vec<ObjectMeta> = self
+            .store
+            .list(Some(&prefix))
+            .try_collect()
+            .await
+            .map_err(Self::convert_error)?;
+        for part in parts {
+            self.store
+                .delete(&part.location)
+                .await
+                .map_err(Self::convert_error)?;
+        }
+        Ok(())
+    }
+
     /// Convert object_store ObjectMeta to our ObjectListItem
     fn to_object_list_item(&self, meta: ObjectMeta) -> ObjectListItem {
         let key = ObjectKey::new(meta.location.to_string()).unwrap();
@@ -44,20 +127,25 @@ impl S3ObjectStoreAdapter {
             key,
             size: meta.size,
             etag: meta.e_tag,
-            last_modified: meta.last_modified.into(),
+            last_modified: meta.last_modified,
             content_type: None, // S3 doesn't provide content-type in list operations
         }
     }
 
     /// Convert StorageError from object_store errors
-    fn convert_error(err: object_store::Error) -> StorageError {
+    pub(crate) fn convert_error(err: object_store::Error) -> StorageError {
         match err {
-            object_store::Error::NotFound { .. } => StorageError::ObjectNotFound {
-                key: "unknown".to_string(),
+            object_store::Error::NotFound { path, .. } => StorageError::ObjectNotFound {
+                key: error_key(path),
             },
-            object_store::Error::AlreadyExists { .. } => StorageError::ObjectAlreadyExists {
-                key: "unknown".to_string(),
+            object_store::Error::AlreadyExists { path, .. } => StorageError::ObjectAlreadyExists {
+                key: error_key(path),
             },
+            object_store::Error::Generic { ref source, .. }
+                if source.is::<OperationTimedOut>() =>
+            {
+                StorageError::from(source.downcast_ref::<OperationTimedOut>().unwrap())
+            }
             _ => StorageError::StorageBackendError {
                 message: err.to_string(),
             },
@@ -65,6 +153,34 @@ impl S3ObjectStoreAdapter {
     }
 }
 
+/// Key of the object an error of the backend refers to
+fn error_key(path: String) -> ObjectKey {
+    ObjectKey::new(path)
+        .unwrap_or_else(|_| ObjectKey::new("unknown".to_string()).expect("valid key"))
+}
+
+/// Read the next `part_size` bytes of `reader`, or what is left of it
+async fn read_part(
+    reader: &mut (impl AsyncRead + Unpin),
+    part_size: usize,
+) -> StorageResult<Bytes> {
+    let mut part = BytesMut::with_capacity(part_size);
+    while part.len() < part_size {
+        let remaining = (part_size - part.len()) as u64;
+        let read = (&mut *reader)
+            .take(remaining)
+            .read_buf(&mut part)
+            .await
+            .map_err(|e| StorageError::StorageBackendError {
+                message: e.to_string(),
+            })?;
+        if read == 0 {
+            break;
+        }
+    }
+    Ok(part.freeze())
+}
+
 #[async_trait]
 impl ObjectStore for S3ObjectStoreAdapter {
     async fn put_object(
@@ -74,20 +190,21 @@ impl ObjectStore for S3ObjectStoreAdapter {
         content_type: Option<&str>,
     ) -> StorageResult<ObjectInfo> {
         let path = self.to_object_path(key);
-        
-        let mut payload = PutPayload::from(data);
+        let size = data.len() as u64;
+
+        let mut attributes = Attributes::new();
         if let Some(ct) = content_type {
-            payload = payload.with_content_type(ct);
+            attributes.insert(Attribute::ContentType, ct.to_string().into());
         }
 
         let result = self.store
-            .put(&path, payload)
+            .put_opts(&path, PutPayload::from(data), attributes.into())
             .await
             .map_err(Self::convert_error)?;
 
         Ok(ObjectInfo {
             key: key.clone(),
-            size: data.len() as u64, // Use the data size we just uploaded
+            size,
             etag: result.e_tag,
             version_id: result.version,
             last_modified: chrono::Utc::now(), // Use current time for new objects
@@ -112,15 +229,81 @@ impl ObjectStore for S3ObjectStoreAdapter {
         Ok(bytes)
     }
 
+    async fn put_object_stream(
+        &self,
+        key: &ObjectKey,
+        mut reader: Box<dyn tokio::io::AsyncRead + Send + Unpin>,
+        content_type: Option<&str>,
+    ) -> StorageResult<ObjectInfo> {
+        let path = self.to_object_path(key);
+        let mut attributes = Attributes::new();
+        if let Some(ct) = content_type {
+            attributes.insert(Attribute::ContentType, ct.to_string().into());
+        }
+
+        // Bodies that fit in one part are stored with a single put
+        let first = read_part(&mut reader, self.part_size).await?;
+        if first.len() < self.part_size {
+            let size = first.len() as u64;
+            let result = self
+                .store
+                .put_opts(&path, first.into(), attributes.into())
+                .await
+                .map_err(Self::convert_error)?;
+
+            return Ok(ObjectInfo {
+                key: key.clone(),
+                size,
+                etag: result.e_tag,
+                version_id: result.version,
+                last_modified: chrono::Utc::now(),
+            });
+        }
+
+        let upload = self
+            .store
+            .put_multipart_opts(&path, attributes.into())
+            .await
+            .map_err(Self::convert_error)?;
+        let mut writer = WriteMultipart::new_with_chunk_size(upload, self.part_size);
+        let size = match self.write_parts(&mut writer, first, &mut reader).await {
+            Ok(size) => size,
+            Err(e) => {
+                // Don't leave the parts uploaded so far behind in the backend
+                let _ = writer.abort().await;
+                return Err(e);
+            }
+        };
+        let result = writer.finish().await.map_err(Self::convert_error)?;
+
+        Ok(ObjectInfo {
+            key: key.clone(),
+            size,
+            etag: result.e_tag,
+            version_id: result.version,
+            last_modified: chrono::Utc::now(),
+        })
+    }
+
     async fn get_object_stream(
         &self,
         key: &ObjectKey,
+        range: Option<Range<u64>>,
     ) -> StorageResult<Box<dyn tokio::io::AsyncRead + Send + Unpin>> {
-        // For now, convert to bytes and create a cursor - not ideal for large objects
-        // In a real implementation, you'd use proper streaming
-        let data = self.get_object(key).await?;
-        let cursor = std::io::Cursor::new(data);
-        Ok(Box::new(cursor))
+        let path = self.to_object_path(key);
+
+        // Only the requested range is fetched from the backend
+        let options = GetOptions {
+            range: range.map(Into::into),
+            ..Default::default()
+        };
+        let result = self.store
+            .get_opts(&path, options)
+            .await
+            .map_err(Self::convert_error)?;
+
+        let stream = result.into_stream().map_err(std::io::Error::other);
+        Ok(Box::new(StreamReader::new(stream)))
     }
 
     async fn delete_object(&self, key: &ObjectKey) -> StorageResult<()> {
@@ -137,13 +320,20 @@ impl ObjectStore for S3ObjectStoreAdapter {
     async fn list_objects(&self, filter: &Filter) -> StorageResult<Vec<ObjectListItem>> {
         let prefix = filter.prefix.as_ref().map(|p| ObjectPath::from(p.as_str()));
         
-        use futures::TryStreamExt;
         let mut list_stream = self.store.list(prefix.as_ref());
         let mut objects = Vec::new();
 
         while let Some(meta) = list_stream.try_next().await.map_err(Self::convert_error)? {
             // Apply client-side filtering
             let key_str = meta.location.to_string();
+
+            // Staged multipart parts and transaction uploads are not objects
+            if key_str.starts_with(MULTIPART_STAGING_PREFIX)
+                || key_str.starts_with(TRANSACTION_STAGING_PREFIX)
+                || key_str.starts_with(VERSION_STAGING_PREFIX)
+            {
+                continue;
+            }
             
             // Apply prefix filter (already handled by object_store, but double-check)
             if let Some(prefix) = &filter.prefix {
@@ -152,20 +342,6 @@ impl ObjectStore for S3ObjectStoreAdapter {
                 }
             }
 
-            // Apply suffix filter
-            if let Some(suffix) = &filter.suffix {
-                if !key_str.ends_with(suffix.as_str()) {
-                    continue;
-                }
-            }
-
-            // Apply max_keys limit
-            if let Some(max_keys) = filter.max_keys {
-                if objects.len() >= max_keys as usize {
-                    break;
-                }
-            }
-
             objects.push(self.to_object_list_item(meta));
         }
 
@@ -184,7 +360,7 @@ impl ObjectStore for S3ObjectStoreAdapter {
             content_type: None,
             content_length: meta.size,
             etag: meta.e_tag,
-            last_modified: meta.last_modified,
+            last_modified: meta.last_modified.into(),
             custom_metadata: HashMap::new(),
         })
     }
@@ -197,17 +373,23 @@ impl ObjectStore for S3ObjectStoreAdapter {
         let source_path = self.to_object_path(source_key);
         let dest_path = self.to_object_path(dest_key);
         
-        let result = self.store
+        self.store
             .copy(&source_path, &dest_path)
             .await
             .map_err(Self::convert_error)?;
 
+        // A copy reports nothing about the new object, so it is looked up
+        let meta = self.store
+            .head(&dest_path)
+            .await
+            .map_err(Self::convert_error)?;
+
         Ok(ObjectInfo {
             key: dest_key.clone(),
-            size: 0, // We don't know the size from copy operation
-            etag: result.e_tag,
-            version_id: result.version,
-            last_modified: chrono::Utc::now(),
+            size: meta.size,
+            etag: meta.e_tag,
+            version_id: meta.version,
+            last_modified: meta.last_modified,
         })
     }
 
@@ -219,27 +401,48 @@ impl ObjectStore for S3ObjectStoreAdapter {
         }
     }
 
-    // Multipart upload methods - S3 native support
-    async fn initiate_multipart_upload(&self, key: &ObjectKey) -> StorageResult<String> {
-        // For now, return a simple upload ID. In a real implementation,
-        // you would use the S3 multipart upload API
+    // Multipart uploads stage each part as an object of its own and
+    // concatenate the parts into the final object on completion
+    async fn initiate_multipart_upload(&self, _key: &ObjectKey) -> StorageResult<String> {
         Ok(format!("upload-{}", uuid::Uuid::new_v4()))
     }
 
     async fn upload_part(
         &self,
-        key: &ObjectKey,
+        _key: &ObjectKey,
         upload_id: &str,
         part_number: u32,
         data: Bytes,
     ) -> StorageResult<CompletedPart> {
-        // This is a simplified implementation. In reality, you would:
-        // 1. Use S3's upload_part API
-        // 2. Store the part ETag for later completion
-        Ok(CompletedPart {
-            part_number,
-            etag: format!("etag-{}-{}", upload_id, part_number),
-        })
+        let etag = format!("{:x}", md5::compute(&data));
+
+        self.store
+            .put(&Self::part_path(upload_id, part_number), PutPayload::from(data))
+            .await
+            .map_err(Self::convert_error)?;
+
+        Ok(CompletedPart { part_number, etag })
+    }
+
+    async fn upload_part_copy(
+        &self,
+        key: &ObjectKey,
+        upload_id: &str,
+        part_number: u32,
+        source_key: &ObjectKey,
+        range: Option<Range<u64>>,
+    ) -> StorageResult<CompletedPart> {
+        let source = self.to_object_path(source_key);
+        let data = match range {
+            Some(range) => self.store.get_range(&source, range).await,
+            None => match self.store.get(&source).await {
+                Ok(result) => result.bytes().await,
+                Err(e) => Err(e),
+            },
+        }
+        .map_err(Self::convert_error)?;
+
+        self.upload_part(key, upload_id, part_number, data).await
     }
 
     async fn complete_multipart_upload(
@@ -248,23 +451,41 @@ impl ObjectStore for S3ObjectStoreAdapter {
         upload_id: &str,
         parts: Vec<CompletedPart>,
     ) -> StorageResult<ObjectInfo> {
-        // Simplified implementation. In reality, you would:
-        // 1. Call S3's complete_multipart_upload API
-        // 2. Provide the list of part ETags
+        let mut writer = BufWriter::new(self.store.clone(), self.to_object_path(key));
+        let mut size = 0;
+
+        for part in &parts {
+            let result = self.store
+                .get(&Self::part_path(upload_id, part.part_number))
+                .await
+                .map_err(Self::convert_error)?;
+            let stream = result.into_stream().map_err(std::io::Error::other);
+            size += tokio::io::copy(&mut StreamReader::new(stream), &mut writer)
+                .await
+                .map_err(|e| StorageError::StorageBackendError {
+                    message: e.to_string(),
+                })?;
+        }
+        writer
+            .shutdown()
+            .await
+            .map_err(|e| StorageError::StorageBackendError {
+                message: e.to_string(),
+            })?;
+
+        self.delete_staged_parts(upload_id).await?;
+
         Ok(ObjectInfo {
             key: key.clone(),
-            size: parts.iter().map(|_| 0u64).sum(), // Simplified - we don't track part sizes
-            etag: Some(format!("final-etag-{}", upload_id)),
+            size,
+            etag: None,
             version_id: None,
             last_modified: chrono::Utc::now(),
         })
     }
 
-    async fn abort_multipart_upload(&self, key: &ObjectKey, upload_id: &str) -> StorageResult<()> {
-        // Simplified implementation. In reality, you would:
-        // 1. Call S3's abort_multipart_upload API
-        // 2. Clean up any uploaded parts
-        Ok(())
+    async fn abort_multipart_upload(&self, _key: &ObjectKey, upload_id: &str) -> StorageResult<()> {
+        self.delete_staged_parts(upload_id).await
     }
 
     async fn get_presigned_url(
@@ -284,6 +505,30 @@ impl ObjectStore for S3ObjectStoreAdapter {
         ))
     }
 
+    async fn get_presigned_multipart_url(
+        &self,
+        key: &ObjectKey,
+        upload_id: &str,
+        part_number: Option<u32>,
+        expiration_seconds: u64,
+    ) -> StorageResult<String> {
+        // Parts are uploaded with UploadPart and the upload finished with
+        // CompleteMultipartUpload, addressed by the same query parameters as in S3
+        let (method, part) = match part_number {
+            Some(part_number) => ("PUT", format!("&partNumber={}", part_number)),
+            None => ("POST", String::new()),
+        };
+        Ok(format!(
+            "https://s3.amazonaws.com/{}/{}?method={}&uploadId={}{}&expires={}",
+            self.bucket.as_str(),
+            key.as_str(),
+            method,
+            utf8_percent_encode(upload_id, NON_ALPHANUMERIC),
+            part,
+            expiration_seconds
+        ))
+    }
+
     async fn list_multipart_uploads(&self) -> StorageResult<Vec<MultipartUpload>> {
         // In a real implementation, you would call ListMultipartUploads
         Ok(Vec::new())
@@ -291,15 +536,40 @@ impl ObjectStore for S3ObjectStoreAdapter {
 
     async fn set_object_metadata(
         &self,
-        key: &ObjectKey,
-        metadata: HashMap<String, String>,
+        _key: &ObjectKey,
+        _metadata: HashMap<String, String>,
     ) -> StorageResult<()> {
         // In a real implementation, you would use S3's object tagging or metadata APIs
         Ok(())
     }
 
-    async fn get_object_metadata(&self, key: &ObjectKey) -> StorageResult<HashMap<String, String>> {
+    async fn get_object_metadata(&self, _key: &ObjectKey) -> StorageResult<HashMap<String, String>> {
         // In a real implementation, you would retrieve S3 object tags and metadata
         Ok(HashMap::new())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use object_store::memory::InMemory;
+
+    #[tokio::test]
+    async fn test_streamed_uploads_are_split_into_parts() {
+        let adapter = S3ObjectStoreAdapter::new(
+            Arc::new(InMemory::new()),
+            BucketName::new("test-bucket".to_string()).unwrap(),
+        )
+        .with_parallel_upload(4, 2);
+
+        for data in [&b"tiny"[..3], b"exactly8", b"split into several parts"] {
+            let key = ObjectKey::new(format!("docs/{}.txt", data.len())).unwrap();
+            let info = adapter
+                .put_object_stream(&key, Box::new(data), Some("text/plain"))
+                .await
+                .unwrap();
+            assert_eq!(info.size, data.len() as u64);
+            assert_eq!(&adapter.get_object(&key).await.unwrap()[..], data);
+        }
+    }
+}
@@ -1,18 +1,25 @@
 use std::sync::Arc;
 use async_trait::async_trait;
 use bytes::Bytes;
-use object_store::ObjectStore as ObjectStoreBackend;
+use futures::TryStreamExt;
+use std::ops::Range;
+use object_store::{GetOptions, ObjectStore as ObjectStoreBackend, path::Path as ObjectPath};
+use tokio_util::io::StreamReader;
 
 use crate::{
     domain::{
+        models::VERSION_STAGING_PREFIX,
         value_objects::{ObjectKey, VersionId},
-        errors::StorageResult,
+        errors::{StorageError, StorageResult},
     },
-    ports::storage::{VersionedObjectStore, ObjectInfo, StorageVersionMetadata, StorageVersionedObject},
+    ports::storage::{ObjectStore, VersionedObjectStore, ObjectInfo, StorageVersionMetadata, StorageVersionedObject},
     adapters::outbound::storage::s3::S3ObjectStoreAdapter,
 };
 
 /// Versioned S3 storage adapter that implements the VersionedObjectStore trait
+///
+/// Versions are kept by the adapter itself rather than by bucket versioning,
+/// so it works against any backend.
 #[derive(Clone)]
 pub struct VersionedS3ObjectStoreAdapter {
     base_adapter: Arc<S3ObjectStoreAdapter>,
@@ -27,6 +34,37 @@ impl VersionedS3ObjectStoreAdapter {
             store,
         }
     }
+
+    /// Path the data of one version of an object is kept at
+    fn version_path(key: &ObjectKey, version_id: &VersionId) -> ObjectPath {
+        ObjectPath::from(format!("{}/{}/{}", VERSION_STAGING_PREFIX, version_id, key))
+    }
+
+    fn convert_error(key: &ObjectKey, version_id: &VersionId, err: object_store::Error) -> StorageError {
+        match err {
+            object_store::Error::NotFound { .. } => StorageError::VersionNotFound {
+                key: key.clone(),
+                version_id: version_id.clone(),
+            },
+            err => S3ObjectStoreAdapter::convert_error(err),
+        }
+    }
+
+    async fn get_version(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+        range: Option<Range<u64>>,
+    ) -> StorageResult<object_store::GetResult> {
+        let options = GetOptions {
+            range: range.map(Into::into),
+            ..Default::default()
+        };
+        self.store
+            .get_opts(&Self::version_path(key, version_id), options)
+            .await
+            .map_err(|e| Self::convert_error(key, version_id, e))
+    }
 }
 
 #[async_trait]
@@ -34,12 +72,19 @@ impl VersionedObjectStore for VersionedS3ObjectStoreAdapter {
     async fn put_object_version(
         &self,
         key: &ObjectKey,
+        version_id: Option<&VersionId>,
         data: Bytes,
         content_type: Option<&str>,
     ) -> StorageResult<ObjectInfo> {
-        // S3 automatically handles versioning if enabled on the bucket
-        // The version ID will be returned in the response
-        self.base_adapter.put_object(key, data, content_type).await
+        let version_id = version_id.cloned().unwrap_or_else(VersionId::generate);
+        self.store
+            .put(&Self::version_path(key, &version_id), data.clone().into())
+            .await
+            .map_err(S3ObjectStoreAdapter::convert_error)?;
+
+        let mut info = self.base_adapter.put_object(key, data, content_type).await?;
+        info.version_id = Some(version_id.to_string());
+        Ok(info)
     }
 
     async fn get_object_version(
@@ -47,23 +92,25 @@ impl VersionedObjectStore for VersionedS3ObjectStoreAdapter {
         key: &ObjectKey,
         version_id: &VersionId,
     ) -> StorageResult<Bytes> {
-        // For S3, we would use GetObjectRequest with VersionId parameter
-        // This is a simplified implementation that doesn't actually use versioning
-        // In a real implementation, you would:
-        // 1. Use the S3 SDK directly for version-specific operations
-        // 2. Pass the version_id parameter to the get_object call
-        
-        // For now, fall back to getting the latest version
-        self.base_adapter.get_object(key).await
+        self.get_version(key, version_id, None)
+            .await?
+            .bytes()
+            .await
+            .map_err(S3ObjectStoreAdapter::convert_error)
     }
 
     async fn get_object_version_stream(
         &self,
         key: &ObjectKey,
         version_id: &VersionId,
+        range: Option<Range<u64>>,
     ) -> StorageResult<Box<dyn tokio::io::AsyncRead + Send + Unpin>> {
-        // Similar to get_object_version, this would use version-specific streaming
-        self.base_adapter.get_object_stream(key).await
+        let stream = self
+            .get_version(key, version_id, range)
+            .await?
+            .into_stream()
+            .map_err(std::io::Error::other);
+        Ok(Box::new(StreamReader::new(stream)))
     }
 
     async fn delete_object_version(
@@ -71,11 +118,10 @@ impl VersionedObjectStore for VersionedS3ObjectStoreAdapter {
         key: &ObjectKey,
         version_id: &VersionId,
     ) -> StorageResult<()> {
-        // For S3, we would use DeleteObjectRequest with VersionId parameter
-        // This would delete the specific version, not add a delete marker
-        
-        // For now, fall back to deleting the object (which adds a delete marker if versioning is enabled)
-        self.base_adapter.delete_object(key).await
+        self.store
+            .delete(&Self::version_path(key, version_id))
+            .await
+            .map_err(|e| Self::convert_error(key, version_id, e))
     }
 
     async fn list_object_versions(&self, key: &ObjectKey) -> StorageResult<Vec<StorageVersionMetadata>> {
@@ -88,7 +134,7 @@ impl VersionedObjectStore for VersionedS3ObjectStoreAdapter {
                 let version_metadata = StorageVersionMetadata {
                     version_id: VersionId::new("latest".to_string()).unwrap(),
                     key: key.clone(),
-                    size: metadata.size,
+                    size: metadata.content_length,
                     last_modified: metadata.last_modified.into(),
                     etag: metadata.etag,
                     is_latest: true,
@@ -105,18 +151,20 @@ impl VersionedObjectStore for VersionedS3ObjectStoreAdapter {
         key: &ObjectKey,
         version_id: &VersionId,
     ) -> StorageResult<StorageVersionMetadata> {
-        // For S3, we would use HeadObjectRequest with VersionId parameter
-        
-        // For now, fall back to head_object and create version metadata
-        let metadata = self.base_adapter.head_object(key).await?;
-        
+        let meta = self
+            .store
+            .head(&Self::version_path(key, version_id))
+            .await
+            .map_err(|e| Self::convert_error(key, version_id, e))?;
+
+        // Which version is the latest is known to the repository, not here
         Ok(StorageVersionMetadata {
             version_id: version_id.clone(),
             key: key.clone(),
-            size: metadata.size,
-            last_modified: metadata.last_modified.into(),
-            etag: metadata.etag,
-            is_latest: true,
+            size: meta.size,
+            last_modified: meta.last_modified,
+            etag: meta.e_tag,
+            is_latest: false,
             is_delete_marker: false,
         })
     }
@@ -127,10 +175,8 @@ impl VersionedObjectStore for VersionedS3ObjectStoreAdapter {
         source_version_id: &VersionId,
         dest_key: &ObjectKey,
     ) -> StorageResult<ObjectInfo> {
-        // For S3, we would use CopyObjectRequest with VersionId parameter for the source
-        
-        // For now, fall back to regular copy (copies the latest version)
-        self.base_adapter.copy_object(source_key, dest_key).await
+        let data = self.get_object_version(source_key, source_version_id).await?;
+        self.base_adapter.put_object(dest_key, data, None).await
     }
 
     async fn restore_object_version(
@@ -144,7 +190,7 @@ impl VersionedObjectStore for VersionedS3ObjectStoreAdapter {
         let data = self.get_object_version(key, version_id).await?;
         
         // Put it as a new version (making it the latest)
-        self.put_object_version(key, data, None).await
+        self.put_object_version(key, None, data, None).await
     }
 
     async fn get_latest_version(&self, key: &ObjectKey) -> StorageResult<StorageVersionedObject> {
@@ -158,7 +204,7 @@ impl VersionedObjectStore for VersionedS3ObjectStoreAdapter {
             metadata: StorageVersionMetadata {
                 version_id: VersionId::new("latest".to_string()).unwrap(),
                 key: key.clone(),
-                size: metadata.size,
+                size: metadata.content_length,
                 last_modified: metadata.last_modified.into(),
                 etag: metadata.etag,
                 is_latest: true,
@@ -168,9 +214,10 @@ impl VersionedObjectStore for VersionedS3ObjectStoreAdapter {
     }
 
     async fn version_exists(&self, key: &ObjectKey, version_id: &VersionId) -> StorageResult<bool> {
-        // For S3, we would use HeadObjectRequest with VersionId parameter
-        
-        // For now, check if the object exists (simplified)
-        self.base_adapter.object_exists(key).await
+        match self.store.head(&Self::version_path(key, version_id)).await {
+            Ok(_) => Ok(true),
+            Err(object_store::Error::NotFound { .. }) => Ok(false),
+            Err(e) => Err(S3ObjectStoreAdapter::convert_error(e)),
+        }
     }
-}
\ No newline at end of file
+}
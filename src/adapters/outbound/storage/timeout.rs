@@ -0,0 +1,398 @@
+use async_trait::async_trait;
+use futures::{
+    FutureExt, StreamExt,
+    stream::{self, BoxStream},
+};
+use object_store::{
+    GetOptions, GetResult, GetResultPayload, ListResult, MultipartUpload, ObjectMeta, ObjectStore,
+    PutMultipartOptions, PutOptions, PutPayload, PutResult, UploadPart, path::Path,
+};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Default time a read may take, and may wait for each chunk of a download
+pub const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Default time a write, copy, delete or multipart call may take
+pub const DEFAULT_WRITE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Default time a listing may take, and may wait for each page of it
+pub const DEFAULT_LIST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long each kind of backend call may take
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StorageTimeouts {
+    /// GETs and HEADs; for a streamed GET, also the longest wait for a chunk
+    pub read: Duration,
+    /// PUTs, copies, deletes and each call of a multipart upload
+    pub write: Duration,
+    /// Listings; for a streamed listing, also the longest wait for an entry
+    pub list: Duration,
+}
+
+impl Default for StorageTimeouts {
+    fn default() -> Self {
+        StorageTimeouts {
+            read: DEFAULT_READ_TIMEOUT,
+            write: DEFAULT_WRITE_TIMEOUT,
+            list: DEFAULT_LIST_TIMEOUT,
+        }
+    }
+}
+
+/// A backend call that did not finish within its timeout
+///
+/// Reported as the source of an [`object_store::Error::Generic`], which the
+/// storage adapters turn into a `StorageError::Timeout`.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("{operation} did not finish within {timeout:?}")]
+pub struct OperationTimedOut {
+    pub operation: &'static str,
+    pub timeout: Duration,
+}
+
+impl From<OperationTimedOut> for object_store::Error {
+    fn from(error: OperationTimedOut) -> Self {
+        object_store::Error::Generic {
+            store: "TimeoutStore",
+            source: Box::new(error),
+        }
+    }
+}
+
+/// An ObjectStore decorator bounding how long each call to the inner store
+/// may take
+///
+/// A call that runs past its timeout fails with [`OperationTimedOut`], so a
+/// wedged backend cannot hold the task waiting on it forever. Streamed
+/// downloads and listings are bounded per chunk rather than as a whole, as
+/// their length depends on the object and the reader.
+///
+/// Calls are cancelled by dropping them: nothing is spawned here, so when a
+/// client disconnects and the server drops its handler, or a timeout fires,
+/// the backend request in flight is dropped with it.
+#[derive(Debug)]
+pub struct TimeoutStore<T: ObjectStore> {
+    /// The underlying object store
+    inner: Arc<T>,
+
+    timeouts: StorageTimeouts,
+}
+
+impl<T: ObjectStore> std::fmt::Display for TimeoutStore<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "TimeoutStore({})", self.inner)
+    }
+}
+
+impl<T: ObjectStore> TimeoutStore<T> {
+    pub fn new(store: T, timeouts: StorageTimeouts) -> Self {
+        TimeoutStore {
+            inner: Arc::new(store),
+            timeouts,
+        }
+    }
+}
+
+/// Run `call`, failing it once `timeout` has passed
+async fn within<R>(
+    operation: &'static str,
+    timeout: Duration,
+    call: impl Future<Output = object_store::Result<R>>,
+) -> object_store::Result<R> {
+    tokio::time::timeout(timeout, call)
+        .await
+        .unwrap_or_else(|_| Err(OperationTimedOut { operation, timeout }.into()))
+}
+
+/// Fail `stream` once it takes longer than `timeout` to produce an item,
+/// ending it after the error
+fn within_each<R: Send + 'static>(
+    operation: &'static str,
+    timeout: Duration,
+    stream: BoxStream<'static, object_store::Result<R>>,
+) -> BoxStream<'static, object_store::Result<R>> {
+    stream::unfold(Some(stream), move |stream| async move {
+        let mut stream = stream?;
+        match tokio::time::timeout(timeout, stream.next()).await {
+            Ok(item) => item.map(|item| (item, Some(stream))),
+            Err(_) => Some((Err(OperationTimedOut { operation, timeout }.into()), None)),
+        }
+    })
+    .boxed()
+}
+
+#[async_trait]
+impl<T: ObjectStore> ObjectStore for TimeoutStore<T> {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        options: PutOptions,
+    ) -> object_store::Result<PutResult> {
+        within(
+            "put",
+            self.timeouts.write,
+            self.inner.put_opts(location, payload, options),
+        )
+        .await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        options: PutMultipartOptions,
+    ) -> object_store::Result<Box<dyn MultipartUpload>> {
+        let upload = within(
+            "multipart upload initiation",
+            self.timeouts.write,
+            self.inner.put_multipart_opts(location, options),
+        )
+        .await?;
+        Ok(Box::new(TimeoutUpload {
+            inner: upload,
+            timeout: self.timeouts.write,
+        }))
+    }
+
+    async fn get_opts(
+        &self,
+        location: &Path,
+        options: GetOptions,
+    ) -> object_store::Result<GetResult> {
+        let mut result = within(
+            "get",
+            self.timeouts.read,
+            self.inner.get_opts(location, options),
+        )
+        .await?;
+        // Reads of local files do not wait on a remote backend
+        if let GetResultPayload::Stream(body) = result.payload {
+            result.payload = GetResultPayload::Stream(within_each("get", self.timeouts.read, body));
+        }
+        Ok(result)
+    }
+
+    async fn head(&self, location: &Path) -> object_store::Result<ObjectMeta> {
+        within("head", self.timeouts.read, self.inner.head(location)).await
+    }
+
+    async fn delete(&self, location: &Path) -> object_store::Result<()> {
+        within("delete", self.timeouts.write, self.inner.delete(location)).await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'static, object_store::Result<ObjectMeta>> {
+        within_each("list", self.timeouts.list, self.inner.list(prefix))
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'static, object_store::Result<ObjectMeta>> {
+        within_each(
+            "list",
+            self.timeouts.list,
+            self.inner.list_with_offset(prefix, offset),
+        )
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> object_store::Result<ListResult> {
+        within(
+            "list",
+            self.timeouts.list,
+            self.inner.list_with_delimiter(prefix),
+        )
+        .await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        within("copy", self.timeouts.write, self.inner.copy(from, to)).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        within(
+            "copy",
+            self.timeouts.write,
+            self.inner.copy_if_not_exists(from, to),
+        )
+        .await
+    }
+}
+
+/// A multipart upload whose calls are each bounded by the write timeout
+#[derive(Debug)]
+struct TimeoutUpload {
+    inner: Box<dyn MultipartUpload>,
+    timeout: Duration,
+}
+
+#[async_trait]
+impl MultipartUpload for TimeoutUpload {
+    fn put_part(&mut self, data: PutPayload) -> UploadPart {
+        within("part upload", self.timeout, self.inner.put_part(data)).boxed()
+    }
+
+    async fn complete(&mut self) -> object_store::Result<PutResult> {
+        within(
+            "multipart upload completion",
+            self.timeout,
+            self.inner.complete(),
+        )
+        .await
+    }
+
+    async fn abort(&mut self) -> object_store::Result<()> {
+        within("multipart upload abort", self.timeout, self.inner.abort()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::errors::StorageError;
+    use bytes::Bytes;
+    use object_store::memory::InMemory;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    /// Counts GETs in flight while it is held
+    struct InFlight(Arc<AtomicUsize>);
+
+    impl Drop for InFlight {
+        fn drop(&mut self) {
+            self.0.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Store whose GETs never answer while it is wedged
+    #[derive(Debug)]
+    struct WedgedStore {
+        inner: InMemory,
+        wedged: AtomicBool,
+        gets_in_flight: Arc<AtomicUsize>,
+    }
+
+    impl std::fmt::Display for WedgedStore {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "WedgedStore")
+        }
+    }
+
+    #[async_trait]
+    impl ObjectStore for WedgedStore {
+        async fn put_opts(
+            &self,
+            location: &Path,
+            payload: PutPayload,
+            options: PutOptions,
+        ) -> object_store::Result<PutResult> {
+            self.inner.put_opts(location, payload, options).await
+        }
+
+        async fn put_multipart_opts(
+            &self,
+            location: &Path,
+            options: PutMultipartOptions,
+        ) -> object_store::Result<Box<dyn MultipartUpload>> {
+            self.inner.put_multipart_opts(location, options).await
+        }
+
+        async fn get_opts(
+            &self,
+            location: &Path,
+            options: GetOptions,
+        ) -> object_store::Result<GetResult> {
+            self.gets_in_flight.fetch_add(1, Ordering::SeqCst);
+            let _in_flight = InFlight(self.gets_in_flight.clone());
+            if self.wedged.load(Ordering::SeqCst) {
+                futures::future::pending::<()>().await;
+            }
+            self.inner.get_opts(location, options).await
+        }
+
+        async fn delete(&self, location: &Path) -> object_store::Result<()> {
+            self.inner.delete(location).await
+        }
+
+        fn list(
+            &self,
+            prefix: Option<&Path>,
+        ) -> BoxStream<'static, object_store::Result<ObjectMeta>> {
+            self.inner.list(prefix)
+        }
+
+        async fn list_with_delimiter(
+            &self,
+            prefix: Option<&Path>,
+        ) -> object_store::Result<ListResult> {
+            self.inner.list_with_delimiter(prefix).await
+        }
+
+        async fn copy(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+            self.inner.copy(from, to).await
+        }
+
+        async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+            self.inner.copy_if_not_exists(from, to).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wedged_calls_time_out_and_are_dropped() {
+        let inner = Arc::new(WedgedStore {
+            inner: InMemory::new(),
+            wedged: AtomicBool::new(false),
+            gets_in_flight: Arc::new(AtomicUsize::new(0)),
+        });
+        let store = TimeoutStore::new(
+            inner.clone() as Arc<dyn ObjectStore>,
+            StorageTimeouts {
+                read: Duration::from_millis(50),
+                ..StorageTimeouts::default()
+            },
+        );
+        let path = Path::from("reports/daily.csv");
+        store
+            .put(&path, Bytes::from_static(b"a,b").into())
+            .await
+            .unwrap();
+
+        // Calls answered in time are passed through
+        let data = store.get(&path).await.unwrap().bytes().await.unwrap();
+        assert_eq!(&data[..], b"a,b");
+
+        inner.wedged.store(true, Ordering::SeqCst);
+        let error = store.get(&path).await.unwrap_err();
+        let object_store::Error::Generic { source, .. } = &error else {
+            panic!("Expected a timeout, got {:?}", error);
+        };
+        assert_eq!(
+            source
+                .downcast_ref::<OperationTimedOut>()
+                .unwrap()
+                .operation,
+            "get"
+        );
+        // The wedged call was cancelled rather than left running
+        assert_eq!(inner.gets_in_flight.load(Ordering::SeqCst), 0);
+
+        assert!(matches!(
+            StorageError::from(error),
+            StorageError::Timeout { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_stalled_streams_fail_once_an_item_is_late() {
+        let stalled = stream::iter([Ok(1)]).chain(stream::pending()).boxed();
+        let items: Vec<object_store::Result<u32>> =
+            within_each("list", Duration::from_millis(20), stalled)
+                .collect()
+                .await;
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(*items[0].as_ref().unwrap(), 1);
+        assert!(items[1].is_err());
+    }
+}
@@ -1,16 +1,21 @@
 use async_trait::async_trait;
 use bytes::Bytes;
 use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
 use futures::stream::BoxStream;
-use object_store::{MultipartUpload, PutPayload, PutResult};
+use object_store::{GetOptions, GetResult, MultipartUpload, PutPayload, PutResult};
 use object_store::{ObjectMeta, ObjectStore, path::Path};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::ops::Range;
-use std::sync::{Arc, RwLock};
-use uuid::Uuid;
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use tokio::sync::OwnedMutexGuard;
 
 use crate::adapters::outbound::storage::error::StoreError;
+use crate::ports::runtime::{Clock, IdGenerator, SystemClock, UuidGenerator, VersionIdGenerator};
+
+/// Marker between an object's path and a version ID in a versioned path
+const VERSION_MARKER: &str = ".v_";
 
 /// Metadata about a single version of an object
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,18 +36,173 @@ pub struct VersionMetadata {
     pub user_metadata: Option<HashMap<String, String>>,
 }
 
+/// Versions of one object, and which of them is stored at the object's path
+#[derive(Debug, Clone, Default)]
+struct VersionHistory {
+    /// Versions ordered by creation time
+    versions: Vec<VersionMetadata>,
+
+    /// Version whose body is at the object's path rather than a versioned path
+    current: Option<String>,
+}
+
+/// Version histories by object path
+///
+/// Each object's history has its own lock, so listing or updating the versions
+/// of one object doesn't hold up the others. The map itself is only
+/// write-locked to add or drop an object.
+#[derive(Debug, Default)]
+struct VersionHistories {
+    histories: RwLock<HashMap<String, Arc<RwLock<VersionHistory>>>>,
+}
+
+impl VersionHistories {
+    /// Run `f` on the history of `path`, if it has one
+    fn read<R>(
+        &self,
+        path: &str,
+        f: impl FnOnce(&VersionHistory) -> R,
+    ) -> Result<Option<R>, StoreError> {
+        let history = self
+            .histories
+            .read()
+            .map_err(lock_error)?
+            .get(path)
+            .cloned();
+        match history {
+            Some(history) => {
+                let history = history.read().map_err(lock_error)?;
+                Ok(Some(f(&history)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Run `f` on the history of `path`, starting one if it has none
+    fn update<R>(
+        &self,
+        path: &str,
+        f: impl FnOnce(&mut VersionHistory) -> R,
+    ) -> Result<R, StoreError> {
+        {
+            // The map stays read-locked so the history can't be dropped meanwhile
+            let histories = self.histories.read().map_err(lock_error)?;
+            if let Some(history) = histories.get(path) {
+                let mut history = history.write().map_err(lock_error)?;
+                return Ok(f(&mut history));
+            }
+        }
+
+        let mut histories = self.histories.write().map_err(lock_error)?;
+        let mut history = histories
+            .entry(path.to_string())
+            .or_default()
+            .write()
+            .map_err(lock_error)?;
+        Ok(f(&mut history))
+    }
+
+    /// Add `history` for `path` unless it already has one
+    fn insert_if_absent(&self, path: String, history: VersionHistory) -> Result<(), StoreError> {
+        self.histories
+            .write()
+            .map_err(lock_error)?
+            .entry(path)
+            .or_insert_with(|| Arc::new(RwLock::new(history)));
+        Ok(())
+    }
+
+    fn contains(&self, path: &str) -> Result<bool, StoreError> {
+        Ok(self
+            .histories
+            .read()
+            .map_err(lock_error)?
+            .contains_key(path))
+    }
+
+    /// Drop the history of `path` if it has no versions left
+    fn remove_if_empty(&self, path: &str) -> Result<(), StoreError> {
+        let mut histories = self.histories.write().map_err(lock_error)?;
+        let empty = match histories.get(path) {
+            Some(history) => history.read().map_err(lock_error)?.versions.is_empty(),
+            None => false,
+        };
+        if empty {
+            histories.remove(path);
+        }
+        Ok(())
+    }
+}
+
+/// Locks serializing the writes to each object path
+///
+/// A write moves the current version aside, writes the new body and records
+/// it, and those steps must not interleave with another write to the same
+/// path or with a read resolving where a version is stored. Locks are dropped
+/// from the map once nothing holds or waits for them.
+#[derive(Debug, Default)]
+struct PathLocks {
+    locks: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl PathLocks {
+    /// Wait for the lock of `path`
+    async fn lock(&self, path: &Path) -> OwnedMutexGuard<()> {
+        let lock = {
+            let mut locks = self.locks.lock().unwrap_or_else(PoisonError::into_inner);
+            locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+            locks.entry(path.as_ref().to_string()).or_default().clone()
+        };
+        lock.lock_owned().await
+    }
+}
+
+/// A page of an object's versions
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VersionPage {
+    /// Versions on this page, oldest first
+    pub versions: Vec<VersionMetadata>,
+
+    /// Version to list the next page after, set when more versions follow
+    pub next_marker: Option<String>,
+}
+
+/// Outcome of [`VersionedStore::migrate_legacy_versions`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VersionMigration {
+    /// Versions found at versioned paths and recorded
+    pub versions_found: usize,
+
+    /// Versioned copies deleted because the object's path holds the same body
+    pub duplicates_removed: usize,
+}
+
 #[derive(Debug)]
 /// An enhanced ObjectStore with automatic versioning support
+///
+/// Each version's body is stored once. The current version of an object is
+/// kept at the object's own path, so plain reads need no lookup; when a write
+/// replaces it, it is first copied within the backend to a versioned path
+/// (`<path>.v_<version ID>`), where older versions live.
 pub struct VersionedStore<T: ObjectStore> {
     /// The underlying object store
-    inner: T,
+    inner: Arc<T>,
 
     /// In-memory cache of version metadata
-    /// Maps object path -> list of versions (ordered by creation time)
-    versions: Arc<RwLock<HashMap<String, Vec<VersionMetadata>>>>,
+    /// Maps object path -> its versions and which of them is current
+    versions: Arc<VersionHistories>,
+
+    /// Locks held while an object's path is written or a version is located
+    locks: Arc<PathLocks>,
 
     /// Whether versioning is enabled
     versioning_enabled: bool,
+
+    /// Source of version creation times
+    clock: Arc<dyn Clock>,
+
+    /// Source of version IDs
+    version_ids: Arc<dyn VersionIdGenerator>,
 }
 
 impl<T: ObjectStore> std::fmt::Display for VersionedStore<T> {
@@ -54,219 +214,482 @@ impl<T: ObjectStore> std::fmt::Display for VersionedStore<T> {
 impl<T: ObjectStore> VersionedStore<T> {
     pub fn new(store: T) -> Self {
         VersionedStore {
-            inner: store,
-            versions: Arc::new(RwLock::new(HashMap::new())),
+            inner: Arc::new(store),
+            versions: Arc::new(VersionHistories::default()),
+            locks: Arc::new(PathLocks::default()),
             versioning_enabled: true,
+            clock: Arc::new(SystemClock),
+            version_ids: Arc::new(UuidGenerator),
         }
     }
 
+    /// Record version creation times from `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Draw version IDs from `version_ids` instead of random UUIDs
+    ///
+    /// Versions are stored under their ID, so where the generator defers to
+    /// the backend they get random UUIDs.
+    pub fn with_version_id_generator(mut self, version_ids: Arc<dyn VersionIdGenerator>) -> Self {
+        self.version_ids = version_ids;
+        self
+    }
+
     pub fn enable_versioning(&mut self, enabled: bool) {
         self.versioning_enabled = enabled;
     }
 
-    /// Create a versioned path by appending a version ID to the original path
-    fn versioned_path(&self, path: &Path, version_id: &str) -> Path {
-        let path_str = path.as_ref().to_string();
-        let versioned_path = format!("{}.v_{}", path_str, version_id);
-        println!(
-            "Created versioned path: {} from original path: {}",
-            versioned_path, path_str
-        );
-        Path::from(versioned_path)
+    /// Where the body of a version is stored
+    fn version_location(&self, path: &Path, version_id: &str) -> Result<Path, StoreError> {
+        let is_current = self
+            .versions
+            .read(path.as_ref(), |history| {
+                history.current.as_deref() == Some(version_id)
+            })?
+            .unwrap_or(false);
+        Ok(match is_current {
+            true => path.clone(),
+            false => versioned_path(path, version_id),
+        })
+    }
+
+    /// Copy the current version of `location` to its versioned path, ahead of
+    /// a write replacing it
+    async fn archive_current(&self, location: &Path) -> object_store::Result<()> {
+        archive_current(
+            self.inner.as_ref(),
+            &self.versions,
+            location,
+            self.clock.as_ref(),
+            self.version_ids.as_ref(),
+        )
+        .await
+    }
+
+    /// Record a version just written to `location` as its current version
+    fn record_current(
+        &self,
+        location: &Path,
+        size: usize,
+        etag: Option<String>,
+    ) -> object_store::Result<()> {
+        let metadata = VersionMetadata {
+            version_id: next_version_id(self.version_ids.as_ref()),
+            created_at: self.clock.now(),
+            size,
+            etag,
+            user_metadata: None,
+        };
+        record_current(&self.versions, location, metadata).map_err(generic_error)
     }
 
     /// Get a specific version of an object
     pub async fn get_version(&self, path: &Path, version_id: &str) -> Result<Bytes, StoreError> {
-        let versioned_path = self.versioned_path(path, version_id);
-
-        // Get the object from the inner store
-        let result = self.inner.get(&versioned_path).await?;
+        let result = self.get_version_stream(path, version_id).await?;
 
         // Convert to bytes
         Ok(result.bytes().await?)
     }
 
+    /// Get a byte range of a specific version of an object
+    pub async fn get_version_range(
+        &self,
+        path: &Path,
+        version_id: &str,
+        range: Range<u64>,
+    ) -> Result<Bytes, StoreError> {
+        let options = GetOptions {
+            range: Some(range.into()),
+            ..Default::default()
+        };
+        let (_, result) = self.get_version_opts(path, version_id, options).await?;
+
+        Ok(result.bytes().await?)
+    }
+
+    /// Get a specific version of an object as a stream
+    pub async fn get_version_stream(
+        &self,
+        path: &Path,
+        version_id: &str,
+    ) -> Result<GetResult, StoreError> {
+        let (_, result) = self
+            .get_version_opts(path, version_id, GetOptions::default())
+            .await?;
+
+        Ok(result)
+    }
+
+    /// Get a specific version of an object as a stream, with its metadata
+    ///
+    /// `options` can ask for a byte range of the version or make the read
+    /// conditional, as with [`ObjectStore::get_opts`].
+    pub async fn get_version_opts(
+        &self,
+        path: &Path,
+        version_id: &str,
+        options: GetOptions,
+    ) -> Result<(VersionMetadata, GetResult), StoreError> {
+        // The version can't move between being located and being opened
+        let _lock = self.locks.lock(path).await;
+        let location = self.version_location(path, version_id)?;
+        let result = self
+            .inner
+            .get_opts(&location, options)
+            .await
+            .map_err(|e| version_error(e, path, version_id))?;
+
+        let metadata = match self.get_version_metadata(path, version_id)? {
+            Some(metadata) => metadata,
+            None => untracked_version(version_id, &result.meta),
+        };
+        Ok((metadata, result))
+    }
+
+    /// Get the metadata of a specific version of an object
+    ///
+    /// Versions this store does not track, such as ones stored before it
+    /// started, are described by their stored object.
+    pub async fn head_version(
+        &self,
+        path: &Path,
+        version_id: &str,
+    ) -> Result<VersionMetadata, StoreError> {
+        if let Some(metadata) = self.get_version_metadata(path, version_id)? {
+            return Ok(metadata);
+        }
+
+        let _lock = self.locks.lock(path).await;
+        let location = self.version_location(path, version_id)?;
+        let meta = self
+            .inner
+            .head(&location)
+            .await
+            .map_err(|e| version_error(e, path, version_id))?;
+        Ok(untracked_version(version_id, &meta))
+    }
+
     /// List all versions of an object
     pub fn list_versions(&self, path: &Path) -> Result<Vec<VersionMetadata>, StoreError> {
-        let versions = self
-            .versions
-            .read()
-            .map_err(|e| StoreError::Other(format!("Failed to acquire read lock: {}", e)))?;
-
         let path_str = path.as_ref().to_string();
         println!("Listing versions for path: {}", path_str);
 
-        // Print all keys in the versions map
-        println!(
-            "All keys in versions map: {:?}",
-            versions.keys().collect::<Vec<_>>()
-        );
-
         // Return cloned versions if they exist, otherwise empty vec
-        let result = versions
-            .get(&path_str)
-            .map(|v| v.clone())
+        let result = self
+            .versions
+            .read(&path_str, |history| history.versions.clone())?
             .unwrap_or_else(Vec::new);
 
         println!("Found {} versions for path {}", result.len(), path_str);
         Ok(result)
     }
 
+    /// List up to `limit` versions of an object, oldest first, after the
+    /// version `marker`
+    ///
+    /// Only the versions on the page are copied, so objects with long
+    /// histories can be listed a page at a time.
+    pub fn list_versions_page(
+        &self,
+        path: &Path,
+        marker: Option<&str>,
+        limit: usize,
+    ) -> Result<VersionPage, StoreError> {
+        let page = self.versions.read(path.as_ref(), |history| {
+            let start = match marker {
+                Some(marker) => {
+                    history
+                        .versions
+                        .iter()
+                        .position(|v| v.version_id == marker)?
+                        + 1
+                }
+                None => 0,
+            };
+            let versions: Vec<VersionMetadata> = history
+                .versions
+                .iter()
+                .skip(start)
+                .take(limit)
+                .cloned()
+                .collect();
+            let next_marker = (start + versions.len() < history.versions.len())
+                .then(|| versions.last().map(|v| v.version_id.clone()))
+                .flatten();
+            Some(VersionPage {
+                versions,
+                next_marker,
+            })
+        })?;
+
+        match (page.flatten(), marker) {
+            (Some(page), _) => Ok(page),
+            (None, None) => Ok(VersionPage::default()),
+            (None, Some(marker)) => {
+                Err(StoreError::VersionNotFound(format!("{}:{}", path, marker)))
+            }
+        }
+    }
+
     /// Get metadata for a specific version
     pub fn get_version_metadata(
         &self,
         path: &Path,
         version_id: &str,
     ) -> Result<Option<VersionMetadata>, StoreError> {
-        let versions = self
-            .versions
-            .read()
-            .map_err(|e| StoreError::Other(format!("Failed to acquire read lock: {}", e)))?;
-
-        let path_str = path.as_ref().to_string();
-
-        if let Some(version_list) = versions.get(&path_str) {
-            return Ok(version_list
+        let metadata = self.versions.read(path.as_ref(), |history| {
+            history
+                .versions
                 .iter()
                 .find(|v| v.version_id == version_id)
-                .cloned());
-        }
+                .cloned()
+        })?;
 
-        Ok(None)
+        Ok(metadata.flatten())
     }
 
-    /// Add version metadata to the versions map
-    fn add_version_metadata(
-        &self,
-        path: &Path,
-        version_id: String,
-        size: usize,
-        user_metadata: Option<HashMap<String, String>>,
-        etag: Option<String>,
-    ) -> Result<(), StoreError> {
-        let mut versions = self
+    /// Delete a specific version of an object
+    ///
+    /// Deleting the current version makes the newest remaining version
+    /// current, moving it back to the object's path. It is copied over the
+    /// deleted version before its versioned copy goes, so the object's path
+    /// holds a body throughout.
+    pub async fn delete_version(&self, path: &Path, version_id: &str) -> Result<(), StoreError> {
+        let _lock = self.locks.lock(path).await;
+        let path_str = path.as_ref().to_string();
+        let (is_current, previous) = self
             .versions
-            .write()
-            .map_err(|e| StoreError::Other(format!("Failed to acquire write lock: {}", e)))?;
+            .read(&path_str, |history| {
+                if history.current.as_deref() != Some(version_id) {
+                    return (false, None);
+                }
+                let previous = history
+                    .versions
+                    .iter()
+                    .rev()
+                    .find(|v| v.version_id != version_id)
+                    .map(|v| v.version_id.clone());
+                (true, previous)
+            })?
+            .unwrap_or((false, None));
+
+        if is_current {
+            match &previous {
+                Some(previous) => {
+                    let previous_path = versioned_path(path, previous);
+                    self.inner.copy(&previous_path, path).await?;
+                    self.inner.delete(&previous_path).await?;
+                }
+                None => self.inner.delete(path).await?,
+            }
+        } else {
+            self.inner.delete(&versioned_path(path, version_id)).await?;
+        }
 
-        let path_str = path.as_ref().to_string();
-        println!(
-            "Adding version metadata for path: {}, version: {}",
-            path_str, version_id
-        );
+        // Update metadata
+        self.versions.update(&path_str, |history| {
+            history.versions.retain(|v| v.version_id != version_id);
+            if is_current {
+                history.current = previous;
+            }
+        })?;
 
-        // Create metadata entry
-        let metadata = VersionMetadata {
-            version_id,
-            created_at: Utc::now(),
-            size,
-            etag,
-            user_metadata,
-        };
+        // If no versions left, remove the entry
+        self.versions.remove_if_empty(&path_str)
+    }
 
-        // Get or create version list for this path
-        let version_list = versions.entry(path_str.clone()).or_insert_with(Vec::new);
+    /// Record the versions stored by the earlier layout, which kept a copy of
+    /// every version at its versioned path, the current one included
+    ///
+    /// Versions are found by their versioned paths under `prefix` and ordered
+    /// by when they were written. Where an object's path holds the same body
+    /// as its newest version, that version becomes current and its versioned
+    /// copy is deleted, so no body is stored twice. Objects whose versions are
+    /// already tracked are left alone, so the migration can be run again, for
+    /// instance after a restart.
+    pub async fn migrate_legacy_versions(
+        &self,
+        prefix: Option<&Path>,
+    ) -> Result<VersionMigration, StoreError> {
+        let objects: Vec<ObjectMeta> = self.inner.list(prefix).try_collect().await?;
+
+        let mut bodies = HashMap::new();
+        let mut found: BTreeMap<String, Vec<(String, ObjectMeta)>> = BTreeMap::new();
+        for meta in objects {
+            let location = meta.location.as_ref().to_string();
+            match location.rsplit_once(VERSION_MARKER) {
+                Some((path, version_id)) => found
+                    .entry(path.to_string())
+                    .or_default()
+                    .push((version_id.to_string(), meta)),
+                None => {
+                    bodies.insert(location, meta);
+                }
+            }
+        }
 
-        // Add the new version
-        version_list.push(metadata);
+        let mut migration = VersionMigration::default();
+        for (path_str, mut legacy) in found {
+            if self.versions.contains(&path_str)? {
+                continue;
+            }
+            legacy.sort_by_key(|(_, meta)| meta.last_modified);
+
+            let mut current = None;
+            if let (Some((version_id, newest)), Some(body)) = (legacy.last(), bodies.get(&path_str))
+            {
+                if self.same_body(newest, body).await? {
+                    self.inner.delete(&newest.location).await?;
+                    current = Some(version_id.clone());
+                    migration.duplicates_removed += 1;
+                }
+            }
 
-        println!(
-            "Current versions for path {}: {}",
-            path_str,
-            version_list.len()
-        );
+            migration.versions_found += legacy.len();
+            let history = VersionHistory {
+                versions: legacy
+                    .into_iter()
+                    .map(|(version_id, meta)| untracked_version(&version_id, &meta))
+                    .collect(),
+                current,
+            };
+            self.versions.insert_if_absent(path_str, history)?;
+        }
 
-        Ok(())
+        Ok(migration)
     }
 
-    /// Delete a specific version of an object
-    pub async fn delete_version(&self, path: &Path, version_id: &str) -> Result<(), StoreError> {
-        let versioned_path = self.versioned_path(path, version_id);
-        println!(
-            "Deleting version for path: {}, version: {}",
-            path.as_ref(),
-            version_id
-        );
-
-        // Delete from the underlying store
-        println!("Deleting from underlying store: {}", versioned_path);
-        let result = self.inner.delete(&versioned_path).await;
-        match &result {
-            Ok(_) => println!(
-                "Successfully deleted from underlying store: {}",
-                versioned_path
-            ),
-            Err(e) => println!(
-                "Failed to delete from underlying store: {} - error: {}",
-                versioned_path, e
-            ),
+    /// Whether two stored objects have the same body
+    ///
+    /// ETags differ between copies on some backends, so bodies of the same
+    /// size with different ETags are compared.
+    async fn same_body(&self, a: &ObjectMeta, b: &ObjectMeta) -> Result<bool, StoreError> {
+        if a.size != b.size {
+            return Ok(false);
+        }
+        if a.e_tag.is_some() && a.e_tag == b.e_tag {
+            return Ok(true);
         }
-        result?;
+        let a = self.inner.get(&a.location).await?.bytes().await?;
+        let b = self.inner.get(&b.location).await?.bytes().await?;
+        Ok(a == b)
+    }
+}
 
-        // Update metadata
-        let mut versions = self
-            .versions
-            .write()
-            .map_err(|e| StoreError::Other(format!("Failed to acquire write lock: {}", e)))?;
+/// Create a versioned path by appending a version ID to the original path
+fn versioned_path(path: &Path, version_id: &str) -> Path {
+    Path::from(format!("{}{}{}", path.as_ref(), VERSION_MARKER, version_id))
+}
 
-        let path_str = path.as_ref().to_string();
-        println!(
-            "All keys in versions map before deletion: {:?}",
-            versions.keys().collect::<Vec<_>>()
-        );
+fn next_version_id(version_ids: &dyn VersionIdGenerator) -> String {
+    version_ids
+        .next_version_id()
+        .unwrap_or_else(|| UuidGenerator.next_id())
+}
+
+/// Metadata of a version this store does not track, from its stored object
+fn untracked_version(version_id: &str, meta: &ObjectMeta) -> VersionMetadata {
+    VersionMetadata {
+        version_id: version_id.to_string(),
+        created_at: meta.last_modified,
+        size: meta.size as usize,
+        etag: meta.e_tag.clone(),
+        user_metadata: None,
+    }
+}
 
-        if let Some(version_list) = versions.get_mut(&path_str) {
-            println!(
-                "Before deletion, versions for path {}: {:?}",
-                path_str, version_list
-            );
+/// Report a version missing from the inner store as not found
+fn version_error(e: object_store::Error, path: &Path, version_id: &str) -> StoreError {
+    match e {
+        object_store::Error::NotFound { .. } => {
+            StoreError::VersionNotFound(format!("{}:{}", path, version_id))
+        }
+        e => StoreError::ObjectStore(e),
+    }
+}
 
-            // Remove the version from the list
-            version_list.retain(|v| v.version_id != version_id);
+fn lock_error(e: impl std::fmt::Display) -> StoreError {
+    StoreError::Other(format!("Failed to acquire version lock: {}", e))
+}
 
-            println!(
-                "After deletion, versions for path {}: {:?}",
-                path_str, version_list
-            );
+fn generic_error(e: StoreError) -> object_store::Error {
+    object_store::Error::Generic {
+        store: "versioned",
+        source: Box::new(e),
+    }
+}
 
-            // If no versions left, remove the entry
-            if version_list.is_empty() {
-                println!("Removing path from versions map: {}", path_str);
-                versions.remove(&path_str);
+/// Copy the current version of `location` to its versioned path, so a write
+/// can replace the body at `location`
+///
+/// A body at `location` that is not tracked as a version, such as one written
+/// before the store started, is kept as a version of its own.
+async fn archive_current<T: ObjectStore>(
+    inner: &T,
+    versions: &VersionHistories,
+    location: &Path,
+    clock: &dyn Clock,
+    version_ids: &dyn VersionIdGenerator,
+) -> object_store::Result<()> {
+    let path_str = location.as_ref().to_string();
+    let tracked = versions
+        .read(&path_str, |history| history.current.clone())
+        .map_err(generic_error)?
+        .flatten();
+
+    let current = match tracked {
+        Some(version_id) => version_id,
+        None => match inner.head(location).await {
+            Ok(meta) => {
+                let version_id = next_version_id(version_ids);
+                let metadata = VersionMetadata {
+                    version_id: version_id.clone(),
+                    created_at: clock.now().min(meta.last_modified),
+                    size: meta.size as usize,
+                    etag: meta.e_tag,
+                    user_metadata: None,
+                };
+                record_current(versions, location, metadata).map_err(generic_error)?;
+                version_id
             }
-        } else {
-            println!("Path not found in versions map: {}", path_str);
-        }
+            Err(object_store::Error::NotFound { .. }) => return Ok(()),
+            Err(e) => return Err(e),
+        },
+    };
+
+    inner
+        .copy(location, &versioned_path(location, &current))
+        .await?;
+
+    versions
+        .update(&path_str, |history| {
+            if history.current.as_deref() == Some(current.as_str()) {
+                history.current = None;
+            }
+        })
+        .map_err(generic_error)
+}
 
-        Ok(())
-    }
+/// Add a version whose body is at `location` as the current version
+fn record_current(
+    versions: &VersionHistories,
+    location: &Path,
+    metadata: VersionMetadata,
+) -> Result<(), StoreError> {
+    versions.update(location.as_ref(), |history| {
+        history.current = Some(metadata.version_id.clone());
+        history.versions.push(metadata);
+    })
 }
 
 // Implement ObjectStore for VersionedStore to allow it to be used as a regular store
 #[async_trait]
 impl<T: ObjectStore + Send + Sync> ObjectStore for VersionedStore<T> {
     async fn put(&self, location: &Path, bytes: PutPayload) -> object_store::Result<PutResult> {
-        if self.versioning_enabled {
-            // Generate a new version ID
-            let version_id = Uuid::new_v4().to_string();
-
-            // Store at versioned path
-            let versioned_path = self.versioned_path(location, &version_id);
-            self.inner.put(&versioned_path, bytes.clone()).await?;
-
-            // Update version metadata
-            self.add_version_metadata(location, version_id, bytes.content_length(), None, None)
-                .map_err(|e| object_store::Error::Generic {
-                    store: "versioned",
-                    source: Box::new(e),
-                })?;
-
-            // Also store at the original path for compatibility
-            self.inner.put(location, bytes).await
-        } else {
-            // If versioning is disabled, just pass through
-            self.inner.put(location, bytes).await
-        }
+        self.put_opts(location, bytes, Default::default()).await
     }
 
     async fn put_opts(
@@ -276,24 +699,15 @@ impl<T: ObjectStore + Send + Sync> ObjectStore for VersionedStore<T> {
         options: object_store::PutOptions,
     ) -> object_store::Result<PutResult> {
         if self.versioning_enabled {
-            // Generate a new version ID
-            let version_id = Uuid::new_v4().to_string();
-
-            // Store at versioned path
-            let versioned_path = self.versioned_path(location, &version_id);
-            self.inner
-                .put_opts(&versioned_path, bytes.clone(), options.clone())
-                .await?;
-
-            // Update version metadata
-            self.add_version_metadata(location, version_id, bytes.content_length(), None, None)
-                .map_err(|e| object_store::Error::Generic {
-                    store: "versioned",
-                    source: Box::new(e),
-                })?;
-
-            // Also store at the original path for compatibility
-            self.inner.put_opts(location, bytes, options).await
+            // The version being replaced moves aside, and the new one is
+            // stored once, at the object's path
+            let _lock = self.locks.lock(location).await;
+            self.archive_current(location).await?;
+
+            let size = bytes.content_length();
+            let result = self.inner.put_opts(location, bytes, options).await?;
+            self.record_current(location, size, result.e_tag.clone())?;
+            Ok(result)
         } else {
             // If versioning is disabled, just pass
             self.inner.put_opts(location, bytes, options).await
@@ -303,23 +717,39 @@ impl<T: ObjectStore + Send + Sync> ObjectStore for VersionedStore<T> {
     async fn put_multipart_opts(
         &self,
         location: &Path,
-        options: object_store::PutMultipartOpts,
+        options: object_store::PutMultipartOptions,
     ) -> object_store::Result<Box<dyn MultipartUpload>> {
-        // Pass through to inner store
-        self.inner.put_multipart_opts(location, options).await
+        if self.versioning_enabled {
+            // Upload to the object's path; the current version moves aside on completion
+            let upload = self.inner.put_multipart_opts(location, options).await?;
+
+            Ok(Box::new(VersionedUpload {
+                upload,
+                inner: self.inner.clone(),
+                versions: self.versions.clone(),
+                locks: self.locks.clone(),
+                location: location.clone(),
+                size: 0,
+                clock: self.clock.clone(),
+                version_ids: self.version_ids.clone(),
+            }))
+        } else {
+            // If versioning is disabled, just pass through
+            self.inner.put_multipart_opts(location, options).await
+        }
     }
 
-    async fn get(&self, location: &Path) -> object_store::Result<object_store::GetResult> {
-        // Always get from the original path
+    async fn get(&self, location: &Path) -> object_store::Result<GetResult> {
+        // The current version is at the original path
         self.inner.get(location).await
     }
 
     async fn get_opts(
         &self,
         location: &Path,
-        options: object_store::GetOptions,
-    ) -> object_store::Result<object_store::GetResult> {
-        // Always get from the original path
+        options: GetOptions,
+    ) -> object_store::Result<GetResult> {
+        // The current version is at the original path
         self.inner.get_opts(location, options).await
     }
 
@@ -335,35 +765,11 @@ impl<T: ObjectStore + Send + Sync> ObjectStore for VersionedStore<T> {
 
     async fn delete(&self, location: &Path) -> object_store::Result<()> {
         if self.versioning_enabled {
-            // In versioned mode, we don't actually delete - we create a delete marker
-            // but this simplified implementation will delete the latest version
-
-            // First, delete from the original path
-            self.inner.delete(location).await?;
-
-            // Update version metadata - in a real implementation, we might
-            // add a delete marker instead of actually removing the version
-            let versions = self
-                .versions
-                .read()
-                .map_err(|e| object_store::Error::Generic {
-                    store: "versioned",
-                    source: Box::new(std::io::Error::new(
-                        std::io::ErrorKind::Other,
-                        format!("Failed to acquire read lock: {}", e),
-                    )),
-                })?;
-
-            let path_str = location.as_ref().to_string();
-
-            // If this object has versions, we should mark them as deleted
-            // but for simplicity in this example, we'll do nothing
-            if versions.contains_key(&path_str) {
-                // In a real implementation, we'd mark all versions as deleted
-                // or apply the deletion policy based on lifecycle rules
-            }
-
-            Ok(())
+            // Deleting the object keeps its versions, so the current one
+            // moves aside before the object's path is deleted
+            let _lock = self.locks.lock(location).await;
+            self.archive_current(location).await?;
+            self.inner.delete(location).await
         } else {
             // If versioning is disabled, just pass through
             self.inner.delete(location).await
@@ -385,27 +791,13 @@ impl<T: ObjectStore + Send + Sync> ObjectStore for VersionedStore<T> {
 
     async fn copy(&self, from: &Path, to: &Path) -> object_store::Result<()> {
         if self.versioning_enabled {
-            // Generate a new version ID for the destination
-            let version_id = Uuid::new_v4().to_string();
-
-            // Get the content
-            let content = self.inner.get(from).await?;
-            let put_payload = content.bytes().await?;
-            let put_len = put_payload.len();
-
-            // Store at versioned path
-            let versioned_path = self.versioned_path(to, &version_id);
-            self.inner.put(&versioned_path, put_payload.into()).await?;
-
-            // Update version metadata
-            self.add_version_metadata(to, version_id, put_len, None, None)
-                .map_err(|e| object_store::Error::Generic {
-                    store: "versioned",
-                    source: Box::new(e),
-                })?;
-
-            // Also store at the original path
-            self.inner.copy(from, to).await
+            let _lock = self.locks.lock(to).await;
+            self.archive_current(to).await?;
+
+            // Copied within the backend; the copy's size and ETag are read back
+            self.inner.copy(from, to).await?;
+            let meta = self.inner.head(to).await?;
+            self.record_current(to, meta.size as usize, meta.e_tag)
         } else {
             // If versioning is disabled, just pass through
             self.inner.copy(from, to).await
@@ -418,3 +810,276 @@ impl<T: ObjectStore + Send + Sync> ObjectStore for VersionedStore<T> {
         self.copy(from, to).await
     }
 }
+
+/// Multipart upload that records a new version once it completes
+#[derive(Debug)]
+struct VersionedUpload<T: ObjectStore> {
+    upload: Box<dyn MultipartUpload>,
+    inner: Arc<T>,
+    versions: Arc<VersionHistories>,
+    locks: Arc<PathLocks>,
+    location: Path,
+    size: usize,
+    clock: Arc<dyn Clock>,
+    version_ids: Arc<dyn VersionIdGenerator>,
+}
+
+#[async_trait]
+impl<T: ObjectStore> MultipartUpload for VersionedUpload<T> {
+    fn put_part(&mut self, data: PutPayload) -> object_store::UploadPart {
+        self.size += data.content_length();
+        self.upload.put_part(data)
+    }
+
+    async fn complete(&mut self) -> object_store::Result<PutResult> {
+        // Completing replaces the body at the object's path
+        let _lock = self.locks.lock(&self.location).await;
+        archive_current(
+            self.inner.as_ref(),
+            &self.versions,
+            &self.location,
+            self.clock.as_ref(),
+            self.version_ids.as_ref(),
+        )
+        .await?;
+        let result = self.upload.complete().await?;
+
+        let metadata = VersionMetadata {
+            version_id: next_version_id(self.version_ids.as_ref()),
+            created_at: self.clock.now(),
+            size: self.size,
+            etag: result.e_tag.clone(),
+            user_metadata: None,
+        };
+        record_current(&self.versions, &self.location, metadata).map_err(generic_error)?;
+
+        Ok(result)
+    }
+
+    async fn abort(&mut self) -> object_store::Result<()> {
+        self.upload.abort().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use object_store::memory::InMemory;
+
+    async fn stored_paths(store: &Arc<dyn ObjectStore>) -> Vec<String> {
+        let mut paths: Vec<String> = store
+            .list(None)
+            .map_ok(|meta| meta.location.to_string())
+            .try_collect()
+            .await
+            .unwrap();
+        paths.sort();
+        paths
+    }
+
+    #[tokio::test]
+    async fn test_each_version_is_stored_once() {
+        let backend: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let store = VersionedStore::new(backend.clone());
+        let path = Path::from("docs/a.txt");
+
+        store.put(&path, PutPayload::from("one")).await.unwrap();
+        assert_eq!(stored_paths(&backend).await, vec!["docs/a.txt"]);
+
+        store.put(&path, PutPayload::from("two")).await.unwrap();
+        let versions = store.list_versions(&path).unwrap();
+        assert_eq!(versions.len(), 2);
+        let (first, second) = (&versions[0].version_id, &versions[1].version_id);
+        assert_eq!(
+            stored_paths(&backend).await,
+            vec!["docs/a.txt".to_string(), format!("docs/a.txt.v_{}", first)]
+        );
+        assert_eq!(store.get_version(&path, first).await.unwrap(), "one");
+        assert_eq!(store.get_version(&path, second).await.unwrap(), "two");
+        let latest = store.get(&path).await.unwrap().bytes().await.unwrap();
+        assert_eq!(latest, "two");
+
+        // Deleting the current version makes the previous one current again
+        store.delete_version(&path, second).await.unwrap();
+        assert_eq!(stored_paths(&backend).await, vec!["docs/a.txt"]);
+        let latest = store.get(&path).await.unwrap().bytes().await.unwrap();
+        assert_eq!(latest, "one");
+        assert_eq!(store.get_version(&path, first).await.unwrap(), "one");
+
+        // Deleting the object keeps its versions
+        store.delete(&path).await.unwrap();
+        assert_eq!(
+            stored_paths(&backend).await,
+            vec![format!("docs/a.txt.v_{}", first)]
+        );
+        assert_eq!(store.get_version(&path, first).await.unwrap(), "one");
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_writes_keep_every_version() {
+        let store = VersionedStore::new(InMemory::new());
+        let path = Path::from("a.txt");
+        let bodies: Vec<String> = (0..8).map(|i| format!("body {}", i)).collect();
+
+        futures::future::try_join_all(
+            bodies
+                .iter()
+                .map(|body| store.put(&path, PutPayload::from(body.clone()))),
+        )
+        .await
+        .unwrap();
+
+        let versions = store.list_versions(&path).unwrap();
+        assert_eq!(versions.len(), bodies.len());
+        let mut stored = Vec::new();
+        for version in &versions {
+            let body = store.get_version(&path, &version.version_id).await.unwrap();
+            stored.push(String::from_utf8(body.to_vec()).unwrap());
+        }
+        stored.sort();
+        assert_eq!(stored, bodies);
+    }
+
+    #[tokio::test]
+    async fn test_untracked_objects_are_kept_as_versions() {
+        let backend: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let path = Path::from("a.txt");
+        backend
+            .put(&path, PutPayload::from("before"))
+            .await
+            .unwrap();
+
+        let store = VersionedStore::new(backend.clone());
+        store.put(&path, PutPayload::from("after")).await.unwrap();
+
+        let versions = store.list_versions(&path).unwrap();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(
+            store
+                .get_version(&path, &versions[0].version_id)
+                .await
+                .unwrap(),
+            "before"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_version_ranges_and_metadata() {
+        let backend: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let store = VersionedStore::new(backend.clone());
+        let path = Path::from("a.txt");
+        store
+            .put(&path, PutPayload::from("0123456789"))
+            .await
+            .unwrap();
+        store.put(&path, PutPayload::from("abc")).await.unwrap();
+        let first = store.list_versions(&path).unwrap()[0].clone();
+
+        assert_eq!(
+            store
+                .get_version_range(&path, &first.version_id, 2..5)
+                .await
+                .unwrap(),
+            "234"
+        );
+        let options = GetOptions {
+            range: Some(object_store::GetRange::Suffix(3)),
+            ..Default::default()
+        };
+        let (metadata, result) = store
+            .get_version_opts(&path, &first.version_id, options)
+            .await
+            .unwrap();
+        assert_eq!(metadata.size, 10);
+        assert_eq!(metadata.etag, first.etag);
+        assert_eq!(result.range, 7..10);
+        assert_eq!(result.bytes().await.unwrap(), "789");
+
+        // Untracked versions are described by their stored object
+        backend
+            .put(&Path::from("b.txt.v_old"), PutPayload::from("old"))
+            .await
+            .unwrap();
+        let metadata = store
+            .head_version(&Path::from("b.txt"), "old")
+            .await
+            .unwrap();
+        assert_eq!(metadata.size, 3);
+        assert!(matches!(
+            store.head_version(&path, "missing").await,
+            Err(StoreError::VersionNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_list_versions_page() {
+        let store = VersionedStore::new(InMemory::new());
+        let path = Path::from("a.txt");
+        for body in ["one", "two", "three"] {
+            store.put(&path, PutPayload::from(body)).await.unwrap();
+        }
+        let all = store.list_versions(&path).unwrap();
+
+        let first = store.list_versions_page(&path, None, 2).unwrap();
+        assert_eq!(first.versions.len(), 2);
+        assert_eq!(first.versions[0].version_id, all[0].version_id);
+        let marker = first.next_marker.unwrap();
+        assert_eq!(marker, all[1].version_id);
+
+        let last = store.list_versions_page(&path, Some(&marker), 2).unwrap();
+        assert_eq!(last.versions.len(), 1);
+        assert_eq!(last.versions[0].version_id, all[2].version_id);
+        assert_eq!(last.next_marker, None);
+
+        assert!(matches!(
+            store.list_versions_page(&path, Some("missing"), 2),
+            Err(StoreError::VersionNotFound(_))
+        ));
+        let untracked = store
+            .list_versions_page(&Path::from("b.txt"), None, 2)
+            .unwrap();
+        assert!(untracked.versions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_migrate_legacy_versions() {
+        let backend: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        for (path, body) in [
+            ("a.txt.v_1", "one"),
+            ("a.txt.v_2", "two"),
+            ("a.txt", "two"),
+            ("gone.txt.v_1", "deleted"),
+        ] {
+            backend
+                .put(&Path::from(path), PutPayload::from(body))
+                .await
+                .unwrap();
+        }
+
+        let store = VersionedStore::new(backend.clone());
+        let migration = store.migrate_legacy_versions(None).await.unwrap();
+        assert_eq!(
+            migration,
+            VersionMigration {
+                versions_found: 3,
+                duplicates_removed: 1,
+            }
+        );
+        assert_eq!(
+            stored_paths(&backend).await,
+            vec!["a.txt", "a.txt.v_1", "gone.txt.v_1"]
+        );
+
+        let path = Path::from("a.txt");
+        assert_eq!(store.get_version(&path, "1").await.unwrap(), "one");
+        assert_eq!(store.get_version(&path, "2").await.unwrap(), "two");
+        assert_eq!(
+            store.list_versions(&Path::from("gone.txt")).unwrap().len(),
+            1
+        );
+
+        // Already tracked objects are left alone
+        let again = store.migrate_legacy_versions(None).await.unwrap();
+        assert_eq!(again, VersionMigration::default());
+    }
+}
@@ -0,0 +1,409 @@
+use anyhow::{Context, Result, bail};
+use clap::{Parser, ValueEnum};
+use reqwest::{Client, Url};
+use std::collections::HashMap;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+mod stats;
+
+use stats::{LatencyStats, Sample};
+
+#[derive(Parser, Debug, Clone)]
+#[command(name = "object-store-bench")]
+#[command(about = "Load and soak benchmark for the object store server", long_about = None)]
+struct Cli {
+    /// Server URL
+    #[arg(
+        short,
+        long,
+        env = "OBJECT_STORE_URL",
+        default_value = "http://localhost:3000"
+    )]
+    url: String,
+
+    /// Workload to run
+    #[arg(short, long, value_enum, default_value_t = Workload::Mixed)]
+    workload: Workload,
+
+    /// Number of concurrent workers
+    #[arg(short, long, default_value_t = 16)]
+    concurrency: usize,
+
+    /// How long to run the benchmark, in seconds
+    #[arg(short, long, default_value_t = 30)]
+    duration: u64,
+
+    /// Smallest object size in bytes
+    #[arg(long, default_value_t = 4 * 1024)]
+    min_size: usize,
+
+    /// Largest object size in bytes, sizes are drawn uniformly between min and max
+    #[arg(long, default_value_t = 1024 * 1024)]
+    max_size: usize,
+
+    /// Number of objects uploaded up front for GET operations to read
+    #[arg(long, default_value_t = 100)]
+    seed_objects: usize,
+
+    /// Relative weight of PUT operations in the mixed workload
+    #[arg(long, default_value_t = 1)]
+    put_weight: u32,
+
+    /// Relative weight of GET operations in the mixed workload
+    #[arg(long, default_value_t = 8)]
+    get_weight: u32,
+
+    /// Relative weight of LIST operations in the mixed workload
+    #[arg(long, default_value_t = 1)]
+    list_weight: u32,
+
+    /// Maximum number of results requested by LIST operations
+    #[arg(long, default_value_t = 100)]
+    list_max_results: usize,
+
+    /// Delete every object created by the run when it finishes
+    #[arg(long)]
+    cleanup: bool,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Workload {
+    Put,
+    Get,
+    List,
+    Mixed,
+}
+
+/// A single benchmark operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Operation {
+    Put,
+    Get,
+    List,
+}
+
+impl Operation {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Operation::Put => "PUT",
+            Operation::Get => "GET",
+            Operation::List => "LIST",
+        }
+    }
+}
+
+impl Cli {
+    /// Operation weights for the selected workload
+    fn weights(&self) -> Vec<(Operation, u32)> {
+        match self.workload {
+            Workload::Put => vec![(Operation::Put, 1)],
+            Workload::Get => vec![(Operation::Get, 1)],
+            Workload::List => vec![(Operation::List, 1)],
+            Workload::Mixed => vec![
+                (Operation::Put, self.put_weight),
+                (Operation::Get, self.get_weight),
+                (Operation::List, self.list_weight),
+            ],
+        }
+    }
+}
+
+/// Small xorshift generator, good enough for picking sizes and operations
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Rng(seed | 1)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    /// Uniform value in `min..=max`
+    fn range(&mut self, min: usize, max: usize) -> usize {
+        if max <= min {
+            return min;
+        }
+        min + (self.next_u64() % (max - min + 1) as u64) as usize
+    }
+
+    fn pick(&mut self, weights: &[(Operation, u32)]) -> Operation {
+        let total: u32 = weights.iter().map(|(_, w)| w).sum();
+        let mut roll = (self.next_u64() % total.max(1) as u64) as u32;
+        for (op, weight) in weights {
+            if roll < *weight {
+                return *op;
+            }
+            roll -= weight;
+        }
+        weights[0].0
+    }
+}
+
+fn object_url(base_url: &Url, key: &str) -> Url {
+    let mut url = base_url.clone();
+    url.path_segments_mut()
+        .expect("server URL must be a base")
+        .pop_if_empty()
+        .push("objects")
+        .push(key);
+    url
+}
+
+async fn put_object(client: &Client, base_url: &Url, key: &str, size: usize) -> Result<()> {
+    client
+        .put(object_url(base_url, key))
+        .header("content-type", "application/octet-stream")
+        .body(vec![0x5a; size])
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+async fn get_object(client: &Client, base_url: &Url, key: &str) -> Result<usize> {
+    let data = client
+        .get(object_url(base_url, key))
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    Ok(data.len())
+}
+
+async fn list_objects(client: &Client, base_url: &Url, prefix: &str, max: usize) -> Result<()> {
+    let mut url = base_url.join("objects")?;
+    url.query_pairs_mut()
+        .append_pair("prefix", prefix)
+        .append_pair("max_results", &max.to_string());
+    client.get(url).send().await?.error_for_status()?;
+    Ok(())
+}
+
+async fn delete_object(client: &Client, base_url: &Url, key: &str) -> Result<()> {
+    client
+        .delete(object_url(base_url, key))
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// Run one worker until the deadline, returning its samples and the keys it created
+async fn run_worker(
+    cli: Cli,
+    client: Client,
+    base_url: Url,
+    run_id: String,
+    worker: usize,
+    seed_keys: Vec<String>,
+    deadline: Instant,
+) -> (Vec<Sample>, Vec<String>) {
+    let weights = cli.weights();
+    let mut rng = Rng::new(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64
+            ^ (worker as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15),
+    );
+    let mut samples = Vec::new();
+    let mut created = Vec::new();
+
+    while Instant::now() < deadline {
+        let op = rng.pick(&weights);
+        let started = Instant::now();
+
+        let result = match op {
+            Operation::Put => {
+                let key = format!("{}-w{}-{}", run_id, worker, created.len());
+                let size = rng.range(cli.min_size, cli.max_size);
+                let result = put_object(&client, &base_url, &key, size).await;
+                if result.is_ok() {
+                    created.push(key);
+                }
+                result.map(|_| size)
+            }
+            Operation::Get if !seed_keys.is_empty() => {
+                let key = &seed_keys[rng.range(0, seed_keys.len() - 1)];
+                get_object(&client, &base_url, key).await
+            }
+            Operation::Get => Err(anyhow::anyhow!("No seed objects to read")),
+            Operation::List => list_objects(&client, &base_url, &run_id, cli.list_max_results)
+                .await
+                .map(|_| 0),
+        };
+
+        samples.push(Sample {
+            operation: op,
+            latency: started.elapsed(),
+            bytes: *result.as_ref().unwrap_or(&0),
+            success: result.is_ok(),
+        });
+    }
+
+    (samples, created)
+}
+
+fn print_report(samples: &[Sample], elapsed: Duration) {
+    let mut by_op: HashMap<Operation, Vec<&Sample>> = HashMap::new();
+    for sample in samples {
+        by_op.entry(sample.operation).or_default().push(sample);
+    }
+
+    let secs = elapsed.as_secs_f64().max(f64::EPSILON);
+    println!(
+        "{:<6} {:>9} {:>7} {:>10} {:>10} {:>9} {:>9} {:>9} {:>9}",
+        "op", "ops", "errors", "ops/s", "MiB/s", "p50 ms", "p90 ms", "p99 ms", "max ms"
+    );
+
+    let mut ops: Vec<_> = by_op.keys().copied().collect();
+    ops.sort();
+    for op in ops {
+        let op_samples = &by_op[&op];
+        let errors = op_samples.iter().filter(|s| !s.success).count();
+        let bytes: usize = op_samples.iter().map(|s| s.bytes).sum();
+        let stats = LatencyStats::from_latencies(
+            op_samples
+                .iter()
+                .filter(|s| s.success)
+                .map(|s| s.latency)
+                .collect(),
+        );
+
+        println!(
+            "{:<6} {:>9} {:>7} {:>10.1} {:>10.2} {:>9.2} {:>9.2} {:>9.2} {:>9.2}",
+            op.as_str(),
+            op_samples.len(),
+            errors,
+            op_samples.len() as f64 / secs,
+            bytes as f64 / (1024.0 * 1024.0) / secs,
+            stats.p50.as_secs_f64() * 1000.0,
+            stats.p90.as_secs_f64() * 1000.0,
+            stats.p99.as_secs_f64() * 1000.0,
+            stats.max.as_secs_f64() * 1000.0,
+        );
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    if cli.min_size > cli.max_size {
+        bail!("--min-size must not be larger than --max-size");
+    }
+    if cli.weights().iter().all(|(_, w)| *w == 0) {
+        bail!("At least one operation weight must be non-zero");
+    }
+
+    let base_url = Url::parse(&cli.url).context("Invalid server URL")?;
+    let client = Client::builder()
+        .pool_max_idle_per_host(cli.concurrency)
+        .build()?;
+
+    let run_id = format!(
+        "bench-{}",
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis()
+    );
+
+    // Seed objects for GET operations
+    let needs_seed = cli
+        .weights()
+        .iter()
+        .any(|(op, w)| *op == Operation::Get && *w > 0);
+    let mut seed_keys = Vec::new();
+    if needs_seed {
+        println!("Seeding {} objects...", cli.seed_objects);
+        let mut rng = Rng::new(0x2545_f491_4f6c_dd1d);
+        for i in 0..cli.seed_objects {
+            let key = format!("{}-seed-{}", run_id, i);
+            let size = rng.range(cli.min_size, cli.max_size);
+            put_object(&client, &base_url, &key, size)
+                .await
+                .with_context(|| format!("Failed to seed object {}", key))?;
+            seed_keys.push(key);
+        }
+    }
+
+    println!(
+        "Running {:?} workload with {} workers for {}s against {}",
+        cli.workload, cli.concurrency, cli.duration, cli.url
+    );
+
+    let started = Instant::now();
+    let deadline = started + Duration::from_secs(cli.duration);
+    let workers: Vec<_> = (0..cli.concurrency.max(1))
+        .map(|worker| {
+            tokio::spawn(run_worker(
+                cli.clone(),
+                client.clone(),
+                base_url.clone(),
+                run_id.clone(),
+                worker,
+                seed_keys.clone(),
+                deadline,
+            ))
+        })
+        .collect();
+
+    let mut samples = Vec::new();
+    let mut created = seed_keys;
+    for worker in workers {
+        let (worker_samples, worker_created) = worker.await?;
+        samples.extend(worker_samples);
+        created.extend(worker_created);
+    }
+    let elapsed = started.elapsed();
+
+    print_report(&samples, elapsed);
+
+    if cli.cleanup {
+        println!("Deleting {} objects...", created.len());
+        for key in &created {
+            if let Err(e) = delete_object(&client, &base_url, key).await {
+                eprintln!("Failed to delete {}: {}", key, e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rng_range_bounds() {
+        let mut rng = Rng::new(42);
+        for _ in 0..1000 {
+            let value = rng.range(10, 20);
+            assert!((10..=20).contains(&value));
+        }
+        assert_eq!(rng.range(5, 5), 5);
+    }
+
+    #[test]
+    fn test_pick_respects_zero_weights() {
+        let mut rng = Rng::new(7);
+        let weights = vec![
+            (Operation::Put, 0),
+            (Operation::Get, 1),
+            (Operation::List, 0),
+        ];
+        for _ in 0..100 {
+            assert_eq!(rng.pick(&weights), Operation::Get);
+        }
+    }
+}
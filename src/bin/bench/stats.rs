@@ -0,0 +1,67 @@
+use crate::Operation;
+use std::time::Duration;
+
+/// Outcome of a single benchmark operation
+#[derive(Debug, Clone)]
+pub struct Sample {
+    pub operation: Operation,
+    pub latency: Duration,
+    pub bytes: usize,
+    pub success: bool,
+}
+
+/// Latency percentiles for a set of operations
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LatencyStats {
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl LatencyStats {
+    /// Compute percentiles using the nearest-rank method
+    pub fn from_latencies(mut latencies: Vec<Duration>) -> Self {
+        if latencies.is_empty() {
+            return Self::default();
+        }
+        latencies.sort_unstable();
+
+        Self {
+            p50: percentile(&latencies, 50.0),
+            p90: percentile(&latencies, 90.0),
+            p99: percentile(&latencies, 99.0),
+            max: latencies[latencies.len() - 1],
+        }
+    }
+}
+
+/// Nearest-rank percentile of an already sorted, non-empty slice
+fn percentile(sorted: &[Duration], pct: f64) -> Duration {
+    let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles() {
+        let latencies = (1..=100).map(Duration::from_millis).collect();
+        let stats = LatencyStats::from_latencies(latencies);
+
+        assert_eq!(stats.p50, Duration::from_millis(50));
+        assert_eq!(stats.p90, Duration::from_millis(90));
+        assert_eq!(stats.p99, Duration::from_millis(99));
+        assert_eq!(stats.max, Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_empty_latencies() {
+        assert_eq!(
+            LatencyStats::from_latencies(vec![]),
+            LatencyStats::default()
+        );
+    }
+}
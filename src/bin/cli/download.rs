@@ -0,0 +1,264 @@
+use anyhow::{Context, Result};
+use futures::stream::{self, StreamExt};
+use object_store_server::client::{Client, ClientError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::io::SeekFrom;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use tokio::fs::{self, OpenOptions};
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+
+/// Default size of each ranged request (8 MiB)
+pub const DEFAULT_CHUNK_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Default number of concurrent ranged requests
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Times a download starts over after the object is replaced mid-download
+const MAX_RESTARTS: usize = 3;
+
+/// Options controlling how an object is downloaded
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    /// Size of each ranged request
+    pub chunk_size: u64,
+
+    /// Number of ranged requests in flight at once
+    pub concurrency: usize,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+}
+
+/// Progress of a partially completed download, persisted next to the output file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct DownloadState {
+    size: u64,
+    etag: Option<String>,
+    chunk_size: u64,
+    completed: BTreeSet<u64>,
+}
+
+impl DownloadState {
+    fn new(size: u64, etag: Option<String>, chunk_size: u64) -> Self {
+        Self {
+            size,
+            etag,
+            chunk_size,
+            completed: BTreeSet::new(),
+        }
+    }
+
+    /// Whether a previously saved state still describes the same remote object
+    fn matches(&self, other: &DownloadState) -> bool {
+        self.size == other.size && self.etag == other.etag && self.chunk_size == other.chunk_size
+    }
+
+    fn chunk_count(&self) -> u64 {
+        self.size.div_ceil(self.chunk_size)
+    }
+
+    fn chunk_range(&self, index: u64) -> Range<u64> {
+        let start = index * self.chunk_size;
+        start..(start + self.chunk_size).min(self.size)
+    }
+
+    fn pending_chunks(&self) -> Vec<u64> {
+        (0..self.chunk_count())
+            .filter(|index| !self.completed.contains(index))
+            .collect()
+    }
+}
+
+/// Path of the resume state file for an output file
+fn state_path(output: &Path) -> PathBuf {
+    let mut path = output.as_os_str().to_owned();
+    path.push(".download");
+    PathBuf::from(path)
+}
+
+async fn load_state(path: &Path) -> Option<DownloadState> {
+    let data = fs::read(path).await.ok()?;
+    serde_json::from_slice(&data).ok()
+}
+
+async fn save_state(path: &Path, state: &DownloadState) -> Result<()> {
+    fs::write(path, serde_json::to_vec(state)?)
+        .await
+        .with_context(|| format!("Failed to write download state to {}", path.display()))
+}
+
+/// Download an object to `output`, using concurrent range requests for large objects
+///
+/// The output file is preallocated to the object's size and each range is written
+/// in place. Completed ranges are recorded in a `.download` file beside the output,
+/// so an interrupted download picks up where it left off when run again. Ranges
+/// are only fetched while the object keeps the ETag the download started with;
+/// once it is replaced, the download starts over.
+pub async fn download_object(
+    client: &Client,
+    key: &str,
+    output: &Path,
+    options: &DownloadOptions,
+) -> Result<u64> {
+    let mut restarts = 0;
+    loop {
+        match download_ranges(client, key, output, options).await {
+            Err(e) if restarts < MAX_RESTARTS && object_replaced(&e) => {
+                // The ranges fetched so far belong to the replaced object
+                fs::remove_file(state_path(output)).await.ok();
+                restarts += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Whether a download failed because the object no longer has the ETag it
+/// started with
+fn object_replaced(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<ClientError>()
+        .and_then(ClientError::status)
+        == Some(412)
+}
+
+/// Download the ranges of an object the `.download` state doesn't record
+async fn download_ranges(
+    client: &Client,
+    key: &str,
+    output: &Path,
+    options: &DownloadOptions,
+) -> Result<u64> {
+    let head = client
+        .head_object(key)
+        .await
+        .context("Failed to fetch object metadata")?;
+    let size = head
+        .content_length
+        .context("Server did not report the object size")?;
+
+    let chunk_size = options.chunk_size.max(1);
+    if !head.accepts_ranges || size <= chunk_size {
+        return download_whole(client, key, output).await;
+    }
+
+    let state_file = state_path(output);
+    let fresh = DownloadState::new(size, head.etag, chunk_size);
+    let mut state = match load_state(&state_file).await {
+        Some(saved) if saved.matches(&fresh) && fs::metadata(output).await.is_ok() => saved,
+        _ => fresh,
+    };
+
+    // Preallocate the output; on most filesystems this creates a sparse file
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(output)
+        .await
+        .with_context(|| format!("Failed to open {}", output.display()))?;
+    file.set_len(size).await?;
+    drop(file);
+
+    let pending: Vec<(u64, Range<u64>)> = state
+        .pending_chunks()
+        .into_iter()
+        .map(|index| (index, state.chunk_range(index)))
+        .collect();
+
+    let mut downloads = stream::iter(pending)
+        .map(|(index, range)| {
+            let client = client.clone();
+            let etag = state.etag.clone();
+            async move {
+                download_range(&client, key, output, range, etag.as_deref())
+                    .await
+                    .map(|_| index)
+            }
+        })
+        .buffer_unordered(options.concurrency.max(1));
+
+    while let Some(result) = downloads.next().await {
+        match result {
+            Ok(index) => {
+                state.completed.insert(index);
+                save_state(&state_file, &state).await?;
+            }
+            Err(e) if object_replaced(&e) => return Err(e),
+            Err(e) => {
+                save_state(&state_file, &state).await?;
+                return Err(e.context("Download interrupted, run the command again to resume"));
+            }
+        }
+    }
+
+    fs::remove_file(&state_file).await.ok();
+    Ok(size)
+}
+
+/// Download an object in a single request, streaming it to the output file
+async fn download_whole(client: &Client, key: &str, output: &Path) -> Result<u64> {
+    let download = client
+        .get_object(key)
+        .await
+        .context("Failed to download object")?;
+
+    let mut file = fs::File::create(output)
+        .await
+        .with_context(|| format!("Failed to write {}", output.display()))?;
+    Ok(download.write_to(&mut file).await?)
+}
+
+/// Fetch one byte range, provided the object still has the ETag `etag`, and
+/// write it at its offset in the output file
+async fn download_range(
+    client: &Client,
+    key: &str,
+    output: &Path,
+    range: Range<u64>,
+    etag: Option<&str>,
+) -> Result<()> {
+    let start = range.start;
+    let data = match etag {
+        Some(etag) => client.get_object_range_if_match(key, range, etag).await?,
+        None => client.get_object_range(key, range).await?,
+    };
+
+    let mut file = OpenOptions::new().write(true).open(output).await?;
+    file.seek(SeekFrom::Start(start)).await?;
+    file.write_all(&data).await?;
+    file.flush().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_planning() {
+        let mut state = DownloadState::new(25, None, 10);
+        assert_eq!(state.chunk_count(), 3);
+        assert_eq!(state.chunk_range(0), 0..10);
+        assert_eq!(state.chunk_range(2), 20..25);
+
+        state.completed.insert(1);
+        assert_eq!(state.pending_chunks(), vec![0, 2]);
+    }
+
+    #[test]
+    fn test_state_matches_same_object_only() {
+        let saved = DownloadState::new(25, Some("abc".to_string()), 10);
+        assert!(saved.matches(&DownloadState::new(25, Some("abc".to_string()), 10)));
+        assert!(!saved.matches(&DownloadState::new(25, Some("def".to_string()), 10)));
+        assert!(!saved.matches(&DownloadState::new(26, Some("abc".to_string()), 10)));
+    }
+}
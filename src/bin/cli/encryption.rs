@@ -0,0 +1,205 @@
+use aes_gcm::{
+    Aes256Gcm, Nonce,
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+};
+use anyhow::{Context, Result, bail};
+use std::path::PathBuf;
+use std::process::Stdio;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Marks payloads encrypted with a local keyfile, followed by the format version
+const KEYFILE_MAGIC: &[u8; 8] = b"OSCENC01";
+
+const NONCE_LEN: usize = 12;
+
+/// Prefix of age X25519 recipients
+const AGE_RECIPIENT_PREFIX: &str = "age1";
+
+/// Marker of an age identity file
+const AGE_IDENTITY_MARKER: &str = "AGE-SECRET-KEY-";
+
+/// Key used to encrypt payloads before upload and decrypt them after download
+///
+/// The server only ever sees ciphertext. Age encryption shells out to the `age`
+/// binary, which must be on the `PATH`.
+#[derive(Clone)]
+pub enum ClientKey {
+    /// 256-bit AES-GCM key read from a keyfile
+    Keyfile([u8; 32]),
+
+    /// age recipient, which can encrypt but not decrypt
+    AgeRecipient(String),
+
+    /// age identity file, which can both encrypt and decrypt
+    AgeIdentity(PathBuf),
+}
+
+impl std::fmt::Debug for ClientKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientKey::Keyfile(_) => write!(f, "Keyfile(..)"),
+            ClientKey::AgeRecipient(recipient) => write!(f, "AgeRecipient({})", recipient),
+            ClientKey::AgeIdentity(path) => write!(f, "AgeIdentity({})", path.display()),
+        }
+    }
+}
+
+impl ClientKey {
+    /// Resolve an `--encrypt-with` value
+    ///
+    /// Values starting with `age1` are age recipients. Anything else is a path to
+    /// either an age identity file or a keyfile holding 32 raw bytes or 64 hex digits.
+    pub async fn load(spec: &str) -> Result<Self> {
+        if spec.starts_with(AGE_RECIPIENT_PREFIX) {
+            return Ok(ClientKey::AgeRecipient(spec.to_string()));
+        }
+
+        let path = PathBuf::from(spec);
+        let contents = fs::read(&path)
+            .await
+            .with_context(|| format!("Failed to read key file {}", path.display()))?;
+
+        if String::from_utf8_lossy(&contents).contains(AGE_IDENTITY_MARKER) {
+            return Ok(ClientKey::AgeIdentity(path));
+        }
+
+        parse_keyfile(&contents)
+            .map(ClientKey::Keyfile)
+            .with_context(|| format!("Invalid key file {}", path.display()))
+    }
+
+    /// Encrypt a payload before it leaves this machine
+    pub async fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            ClientKey::Keyfile(key) => {
+                let cipher = Aes256Gcm::new(key.into());
+                let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+                let ciphertext = cipher
+                    .encrypt(&nonce, plaintext)
+                    .map_err(|_| anyhow::anyhow!("Failed to encrypt payload"))?;
+
+                let mut out =
+                    Vec::with_capacity(KEYFILE_MAGIC.len() + NONCE_LEN + ciphertext.len());
+                out.extend_from_slice(KEYFILE_MAGIC);
+                out.extend_from_slice(&nonce);
+                out.extend_from_slice(&ciphertext);
+                Ok(out)
+            }
+            ClientKey::AgeRecipient(recipient) => {
+                run_age(&["-e", "-r", recipient], plaintext).await
+            }
+            ClientKey::AgeIdentity(path) => {
+                let identity = path.to_string_lossy();
+                run_age(&["-e", "-i", &identity], plaintext).await
+            }
+        }
+    }
+
+    /// Decrypt a payload downloaded from the server
+    pub async fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            ClientKey::Keyfile(key) => {
+                let header_len = KEYFILE_MAGIC.len() + NONCE_LEN;
+                if ciphertext.len() < header_len
+                    || &ciphertext[..KEYFILE_MAGIC.len()] != KEYFILE_MAGIC
+                {
+                    bail!("Object was not encrypted with a keyfile");
+                }
+
+                let nonce = Nonce::from_slice(&ciphertext[KEYFILE_MAGIC.len()..header_len]);
+                Aes256Gcm::new(key.into())
+                    .decrypt(nonce, &ciphertext[header_len..])
+                    .map_err(|_| {
+                        anyhow::anyhow!("Failed to decrypt object: wrong key or corrupted data")
+                    })
+            }
+            ClientKey::AgeRecipient(_) => {
+                bail!("An age recipient cannot decrypt; pass the age identity file instead")
+            }
+            ClientKey::AgeIdentity(path) => {
+                let identity = path.to_string_lossy();
+                run_age(&["-d", "-i", &identity], ciphertext).await
+            }
+        }
+    }
+}
+
+/// Parse a keyfile holding either 32 raw bytes or 64 hex digits
+fn parse_keyfile(contents: &[u8]) -> Result<[u8; 32]> {
+    if let Ok(key) = <[u8; 32]>::try_from(contents) {
+        return Ok(key);
+    }
+
+    let text = std::str::from_utf8(contents)
+        .map(str::trim)
+        .unwrap_or_default();
+    if text.len() != 64 {
+        bail!("Expected 32 raw bytes or 64 hex digits");
+    }
+
+    let mut key = [0u8; 32];
+    for (i, byte) in key.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&text[i * 2..i * 2 + 2], 16)
+            .context("Expected 32 raw bytes or 64 hex digits")?;
+    }
+    Ok(key)
+}
+
+/// Pipe data through the `age` binary
+async fn run_age(args: &[&str], input: &[u8]) -> Result<Vec<u8>> {
+    let mut child = Command::new("age")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run age; is it installed and on the PATH?")?;
+
+    let mut stdin = child.stdin.take().context("Failed to open age stdin")?;
+    let input = input.to_vec();
+    let writer = tokio::spawn(async move {
+        stdin.write_all(&input).await?;
+        stdin.shutdown().await
+    });
+
+    let output = child.wait_with_output().await?;
+    writer.await??;
+
+    if !output.status.success() {
+        bail!(
+            "age failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_keyfile_round_trip() {
+        let key = ClientKey::Keyfile([7u8; 32]);
+        let ciphertext = key.encrypt(b"secret payload").await.unwrap();
+        assert!(ciphertext.starts_with(KEYFILE_MAGIC));
+        assert_eq!(key.decrypt(&ciphertext).await.unwrap(), b"secret payload");
+
+        let other = ClientKey::Keyfile([8u8; 32]);
+        assert!(other.decrypt(&ciphertext).await.is_err());
+        assert!(key.decrypt(b"plain data").await.is_err());
+    }
+
+    #[test]
+    fn test_parse_keyfile() {
+        assert_eq!(parse_keyfile(&[1u8; 32]).unwrap(), [1u8; 32]);
+        assert_eq!(
+            parse_keyfile(format!("{}\n", "ab".repeat(32)).as_bytes()).unwrap(),
+            [0xab; 32]
+        );
+        assert!(parse_keyfile(b"too short").is_err());
+        assert!(parse_keyfile("zz".repeat(32).as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,134 @@
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, NaiveDate, Utc};
+use object_store_server::adapters::inbound::http::dto::{LifecycleRuleDto, PrefixRewriteDto};
+use serde_json::Value;
+use std::path::Path;
+
+/// Rule prefix rewrite given as `FROM=TO`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrefixRewrite {
+    pub from: String,
+    pub to: String,
+}
+
+impl std::str::FromStr for PrefixRewrite {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        let (from, to) = value
+            .split_once('=')
+            .ok_or_else(|| format!("Expected FROM=TO, got {}", value))?;
+        Ok(PrefixRewrite {
+            from: from.to_string(),
+            to: to.to_string(),
+        })
+    }
+}
+
+impl From<PrefixRewrite> for PrefixRewriteDto {
+    fn from(rewrite: PrefixRewrite) -> Self {
+        PrefixRewriteDto {
+            from: rewrite.from,
+            to: rewrite.to,
+        }
+    }
+}
+
+/// Parse an `--as-of` value: a date (midnight UTC) or an RFC 3339 timestamp
+pub fn parse_as_of(value: &str) -> Result<DateTime<Utc>> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap().and_utc());
+    }
+    DateTime::parse_from_rfc3339(value)
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .with_context(|| format!("Invalid date {}; expected YYYY-MM-DD or RFC 3339", value))
+}
+
+/// Read lifecycle rules from a YAML or JSON file
+///
+/// The file holds either a list of rules or a configuration with a `rules` list.
+pub async fn load_rules(path: &Path) -> Result<Value> {
+    let contents = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let document: Value = serde_yaml::from_str(&contents)
+        .with_context(|| format!("Invalid rules file {}", path.display()))?;
+
+    match document {
+        Value::Array(_) => Ok(document),
+        Value::Object(mut config) => match config.remove("rules") {
+            Some(rules @ Value::Array(_)) => Ok(rules),
+            _ => bail!("{} has no list of rules", path.display()),
+        },
+        _ => bail!("{} has no list of rules", path.display()),
+    }
+}
+
+/// Read the lifecycle rules of a YAML or JSON file as sent to the server
+pub async fn load_rule_dtos(path: &Path) -> Result<Vec<LifecycleRuleDto>> {
+    serde_json::from_value(load_rules(path).await?)
+        .with_context(|| format!("Invalid lifecycle rules in {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_prefix_rewrite() {
+        let rewrite: PrefixRewrite = "logs/=archive/logs/".parse().unwrap();
+        assert_eq!(rewrite.from, "logs/");
+        assert_eq!(rewrite.to, "archive/logs/");
+
+        let scope: PrefixRewrite = "=tenant-a/".parse().unwrap();
+        assert_eq!(scope.from, "");
+        assert!("logs/".parse::<PrefixRewrite>().is_err());
+    }
+
+    #[test]
+    fn test_parse_as_of() {
+        assert_eq!(
+            parse_as_of("2025-06-01").unwrap().to_rfc3339(),
+            "2025-06-01T00:00:00+00:00"
+        );
+        assert_eq!(
+            parse_as_of("2025-06-01T12:30:00+02:00")
+                .unwrap()
+                .to_rfc3339(),
+            "2025-06-01T10:30:00+00:00"
+        );
+        assert!(parse_as_of("June 1st").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_load_rules() {
+        let dir = std::env::temp_dir().join(format!("lifecycle-rules-{}", std::process::id()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+
+        let config = dir.join("config.yaml");
+        tokio::fs::write(
+            &config,
+            "rules:\n  - id: expire-tmp\n    status: Enabled\n    expiration_days: 7\n",
+        )
+        .await
+        .unwrap();
+        let rules = load_rules(&config).await.unwrap();
+        assert_eq!(rules[0]["id"], "expire-tmp");
+        assert_eq!(rules[0]["expiration_days"], 7);
+
+        let list = dir.join("rules.json");
+        tokio::fs::write(&list, r#"[{"id": "a"}, {"id": "b"}]"#)
+            .await
+            .unwrap();
+        assert_eq!(
+            load_rules(&list).await.unwrap().as_array().unwrap().len(),
+            2
+        );
+
+        let empty = dir.join("empty.yaml");
+        tokio::fs::write(&empty, "bucket: logs\n").await.unwrap();
+        assert!(load_rules(&empty).await.is_err());
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+}
@@ -1,5 +1,19 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
+use object_store_server::{
+    adapters::inbound::http::dto::{
+        ApplyLifecycleTemplateDto, CopyLifecycleDto, SimulateLifecycleDto,
+    },
+    client::{Client, ListVersionsOptions, PutObjectOptions},
+};
+use std::path::PathBuf;
+
+mod download;
+mod encryption;
+mod lifecycle;
+
+use download::{DEFAULT_CHUNK_SIZE, DEFAULT_CONCURRENCY, DownloadOptions};
+use encryption::ClientKey;
 
 #[derive(Parser, Debug)]
 #[command(name = "object-store-cli")]
@@ -28,6 +42,9 @@ enum Commands {
         /// Bucket name
         #[arg(short, long)]
         bucket: Option<String>,
+        /// Encrypt locally before upload with a keyfile or age recipient
+        #[arg(long, value_name = "KEYFILE|AGE_RECIPIENT")]
+        encrypt_with: Option<String>,
     },
     
     /// Download an object
@@ -40,6 +57,15 @@ enum Commands {
         /// Bucket name
         #[arg(short, long)]
         bucket: Option<String>,
+        /// Number of concurrent range requests
+        #[arg(long, default_value_t = DEFAULT_CONCURRENCY)]
+        concurrency: usize,
+        /// Size in bytes of each range request
+        #[arg(long, default_value_t = DEFAULT_CHUNK_SIZE)]
+        chunk_size: u64,
+        /// Decrypt after download with the keyfile or age identity used for upload
+        #[arg(long, value_name = "KEYFILE|AGE_IDENTITY")]
+        encrypt_with: Option<String>,
     },
     
     /// List objects
@@ -80,14 +106,29 @@ enum LifecycleCommands {
     Get {
         /// Bucket name
         bucket: String,
+        /// Export the configuration to a file instead of printing it
+        #[arg(short, long)]
+        output: Option<PathBuf>,
     },
     
     /// Set lifecycle configuration
     Set {
         /// Bucket name
         bucket: String,
-        /// Configuration file path
-        config: String,
+        /// Configuration file path (YAML or JSON), e.g. one exported with `get`
+        config: PathBuf,
+    },
+
+    /// Copy a bucket's lifecycle configuration to other buckets
+    Copy {
+        /// Bucket to copy the configuration from
+        source: String,
+        /// Buckets to apply the configuration to, replacing their own
+        #[arg(required = true)]
+        targets: Vec<String>,
+        /// Rewrite rule prefixes starting with FROM to start with TO
+        #[arg(long, value_name = "FROM=TO")]
+        rewrite_prefix: Option<lifecycle::PrefixRewrite>,
     },
     
     /// Delete lifecycle configuration
@@ -95,6 +136,35 @@ enum LifecycleCommands {
         /// Bucket name
         bucket: String,
     },
+
+    /// Show which existing objects rules would expire or transition at a date
+    Simulate {
+        /// Bucket name
+        bucket: String,
+        /// YAML or JSON rules file; defaults to the bucket's active configuration
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+        /// Date to evaluate the rules at (YYYY-MM-DD or RFC 3339); defaults to now
+        #[arg(long, value_parser = lifecycle::parse_as_of)]
+        as_of: Option<chrono::DateTime<chrono::Utc>>,
+    },
+
+    /// List the predefined lifecycle rule templates
+    Templates,
+
+    /// Add a rule from a predefined template, e.g. expire-temp-after-7d
+    ApplyTemplate {
+        /// Bucket name
+        bucket: String,
+        /// Template name
+        template: String,
+        /// Prefix the rule applies to, overriding the template default
+        #[arg(short, long)]
+        prefix: Option<String>,
+        /// Rule ID, defaulting to the template name
+        #[arg(long)]
+        rule_id: Option<String>,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -132,15 +202,348 @@ enum VersionCommands {
         #[arg(short, long)]
         bucket: Option<String>,
     },
+
+    /// Make an earlier version current again by copying it as the latest
+    Rollback {
+        /// Bucket name
+        bucket: String,
+        /// Object key
+        key: String,
+        /// How many versions to go back
+        #[arg(short, long, default_value_t = 1)]
+        steps: usize,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
-    
-    // TODO: Implement CLI commands
-    println!("CLI command not yet implemented: {:?}", cli.command);
-    println!("Server URL: {}", cli.url);
-    
+    let client = build_client(&cli.url, cli.api_key.as_deref())?;
+
+    match cli.command {
+        Commands::Put {
+            key,
+            file,
+            bucket,
+            encrypt_with,
+        } => {
+            let key = qualified_key(bucket.as_deref(), &key);
+            let mut data = tokio::fs::read(&file)
+                .await
+                .with_context(|| format!("Failed to read {}", file))?;
+            if let Some(spec) = encrypt_with {
+                data = ClientKey::load(&spec).await?.encrypt(&data).await?;
+            }
+
+            let size = data.len();
+            client
+                .put_object(&key, data, PutObjectOptions::default())
+                .await
+                .context("Failed to upload object")?;
+            println!("Uploaded {} ({} bytes) to {}", file, size, key);
+        }
+        Commands::Get {
+            key,
+            output,
+            bucket,
+            concurrency,
+            chunk_size,
+            encrypt_with,
+        } => {
+            let key = qualified_key(bucket.as_deref(), &key);
+            let output = PathBuf::from(output.unwrap_or_else(|| default_output(&key)));
+            let options = DownloadOptions {
+                chunk_size,
+                concurrency,
+            };
+
+            let mut size = download::download_object(&client, &key, &output, &options).await?;
+
+            if let Some(spec) = encrypt_with {
+                let client_key = ClientKey::load(&spec).await?;
+                let ciphertext = tokio::fs::read(&output).await?;
+                let plaintext = client_key.decrypt(&ciphertext).await?;
+                tokio::fs::write(&output, &plaintext)
+                    .await
+                    .with_context(|| format!("Failed to write {}", output.display()))?;
+                size = plaintext.len() as u64;
+            }
+
+            println!(
+                "Downloaded {} ({} bytes) to {}",
+                key,
+                size,
+                output.display()
+            );
+        }
+        Commands::List { prefix, bucket } => {
+            let prefix = match bucket {
+                Some(bucket) => Some(qualified_key(
+                    Some(&bucket),
+                    prefix.as_deref().unwrap_or(""),
+                )),
+                None => prefix,
+            };
+            let listing = client
+                .list_objects(prefix.as_deref(), None)
+                .await
+                .context("Failed to list objects")?;
+            for object in &listing.objects {
+                println!(
+                    "{:>12}  {}  {}",
+                    object.size,
+                    object.last_modified.to_rfc3339(),
+                    object.key
+                );
+            }
+            println!("{} objects", listing.total_count);
+        }
+        Commands::Delete { key, bucket } => {
+            let key = qualified_key(bucket.as_deref(), &key);
+            client
+                .delete_object(&key)
+                .await
+                .with_context(|| format!("Failed to delete {}", key))?;
+            println!("Deleted {}", key);
+        }
+        Commands::Lifecycle {
+            command: LifecycleCommands::Get { bucket, output },
+        } => {
+            let config = client
+                .get_lifecycle_configuration(&bucket)
+                .await
+                .with_context(|| format!("Failed to get lifecycle configuration of {}", bucket))?;
+            let json = serde_json::to_string_pretty(&config)?;
+            match output {
+                Some(path) => {
+                    tokio::fs::write(&path, json)
+                        .await
+                        .with_context(|| format!("Failed to write {}", path.display()))?;
+                    println!(
+                        "Exported lifecycle configuration of {} to {}",
+                        bucket,
+                        path.display()
+                    );
+                }
+                None => println!("{}", json),
+            }
+        }
+        Commands::Lifecycle {
+            command: LifecycleCommands::Set { bucket, config },
+        } => {
+            let rules = lifecycle::load_rule_dtos(&config).await?;
+            let count = rules.len();
+            client
+                .set_lifecycle_configuration(&bucket, rules)
+                .await
+                .with_context(|| format!("Failed to set lifecycle configuration of {}", bucket))?;
+            println!("Applied {} lifecycle rules to {}", count, bucket);
+        }
+        Commands::Lifecycle {
+            command:
+                LifecycleCommands::Copy {
+                    source,
+                    targets,
+                    rewrite_prefix,
+                },
+        } => {
+            let request = CopyLifecycleDto {
+                targets,
+                rewrite_prefix: rewrite_prefix.map(Into::into),
+            };
+            let copied = client
+                .copy_lifecycle_configuration(&source, &request)
+                .await
+                .with_context(|| format!("Failed to copy lifecycle configuration of {}", source))?;
+            println!(
+                "Copied lifecycle configuration of {} to {}",
+                source,
+                copied.join(", ")
+            );
+        }
+        Commands::Lifecycle {
+            command: LifecycleCommands::Delete { bucket },
+        } => {
+            client
+                .delete_lifecycle_configuration(&bucket)
+                .await
+                .with_context(|| {
+                    format!("Failed to delete lifecycle configuration of {}", bucket)
+                })?;
+            println!("Deleted lifecycle configuration of {}", bucket);
+        }
+        Commands::Lifecycle {
+            command:
+                LifecycleCommands::Simulate {
+                    bucket,
+                    config,
+                    as_of,
+                },
+        } => {
+            let rules = match config {
+                Some(path) => Some(lifecycle::load_rule_dtos(&path).await?),
+                None => None,
+            };
+            let as_of = as_of.unwrap_or_else(chrono::Utc::now);
+            let results = client
+                .simulate_lifecycle(
+                    &bucket,
+                    &SimulateLifecycleDto {
+                        rules,
+                        as_of: Some(as_of),
+                    },
+                )
+                .await
+                .context("Failed to simulate lifecycle rules")?;
+
+            for object in &results.affected_objects {
+                for action in &object.actions {
+                    println!("{}\t{}\t{}", object.key, action.rule_id, action.reason);
+                }
+            }
+            println!(
+                "{} of {} objects in {} would be affected as of {}",
+                results.affected_objects.len(),
+                results.objects_evaluated,
+                bucket,
+                as_of.to_rfc3339()
+            );
+        }
+        Commands::Lifecycle {
+            command: LifecycleCommands::Templates,
+        } => {
+            let templates = client
+                .list_lifecycle_templates()
+                .await
+                .context("Failed to list lifecycle templates")?;
+            for template in templates {
+                println!("{:<30} {}", template.name, template.description);
+            }
+        }
+        Commands::Lifecycle {
+            command:
+                LifecycleCommands::ApplyTemplate {
+                    bucket,
+                    template,
+                    prefix,
+                    rule_id,
+                },
+        } => {
+            let rule = client
+                .apply_lifecycle_template(
+                    &bucket,
+                    &template,
+                    &ApplyLifecycleTemplateDto { prefix, rule_id },
+                )
+                .await
+                .with_context(|| format!("Failed to apply lifecycle template {}", template))?;
+            println!(
+                "Added lifecycle rule {} to {} from template {}",
+                rule.id, bucket, template
+            );
+        }
+        Commands::Version {
+            command: VersionCommands::List { key, bucket },
+        } => {
+            let key = qualified_key(bucket.as_deref(), &key);
+            let mut options = ListVersionsOptions::default();
+            loop {
+                let page = client
+                    .list_versions(&key, &options)
+                    .await
+                    .with_context(|| format!("Failed to list versions of {}", key))?;
+                for version in &page.versions {
+                    println!(
+                        "{}  {:>12}  {}{}",
+                        version.version_id,
+                        version.size,
+                        version.last_modified.to_rfc3339(),
+                        if version.is_latest { "  (latest)" } else { "" }
+                    );
+                }
+                match page.next_version_id_marker {
+                    Some(marker) if page.is_truncated => options.version_id_marker = Some(marker),
+                    _ => break,
+                }
+            }
+        }
+        Commands::Version {
+            command:
+                VersionCommands::Get {
+                    key,
+                    version_id,
+                    output,
+                    bucket,
+                },
+        } => {
+            let key = qualified_key(bucket.as_deref(), &key);
+            let output = PathBuf::from(output.unwrap_or_else(|| default_output(&key)));
+            let download = client
+                .get_version(&key, &version_id)
+                .await
+                .with_context(|| format!("Failed to download version {} of {}", version_id, key))?;
+            let mut file = tokio::fs::File::create(&output)
+                .await
+                .with_context(|| format!("Failed to write {}", output.display()))?;
+            let size = download.write_to(&mut file).await?;
+            println!(
+                "Downloaded version {} of {} ({} bytes) to {}",
+                version_id,
+                key,
+                size,
+                output.display()
+            );
+        }
+        Commands::Version {
+            command:
+                VersionCommands::Delete {
+                    key,
+                    version_id,
+                    bucket,
+                },
+        } => {
+            let key = qualified_key(bucket.as_deref(), &key);
+            client
+                .delete_version(&key, &version_id)
+                .await
+                .with_context(|| format!("Failed to delete version {} of {}", version_id, key))?;
+            println!("Deleted version {} of {}", version_id, key);
+        }
+        Commands::Version {
+            command: VersionCommands::Rollback { bucket, key, steps },
+        } => {
+            let rollback = client
+                .rollback_object(&bucket, &key, steps)
+                .await
+                .with_context(|| format!("Failed to roll back {}", key))?;
+            println!(
+                "Rolled {} back {} versions to {} (new version {})",
+                rollback.key, steps, rollback.restored_version_id, rollback.new_version_id
+            );
+        }
+    }
+
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Build the API client, attaching the API key to every request when set
+fn build_client(url: &str, api_key: Option<&str>) -> Result<Client> {
+    let mut builder = Client::builder(url);
+    if let Some(api_key) = api_key {
+        builder = builder.api_key(api_key);
+    }
+    Ok(builder.build()?)
+}
+
+/// Object keys are namespaced by bucket as `bucket/key`
+fn qualified_key(bucket: Option<&str>, key: &str) -> String {
+    match bucket {
+        Some(bucket) => format!("{}/{}", bucket, key),
+        None => key.to_string(),
+    }
+}
+
+/// Default output file name: the last segment of the key
+fn default_output(key: &str) -> String {
+    key.rsplit('/').next().unwrap_or(key).to_string()
+}
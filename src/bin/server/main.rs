@@ -1,27 +1,55 @@
 use anyhow::{Context, Result};
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use object_store_server::{
-    app::{AppBuilder, AppConfig, RepositoryBackend, StorageBackend},
-    adapters::inbound::http::router::{create_router, AppState},
+    app::AppBuilder,
+    config::EnvConfig,
+    adapters::inbound::grpc::{GrpcObjectService, ObjectsServer},
+    adapters::inbound::http::{
+        middleware::{
+            apply_default_cache_control, enforce_request_deadline, limit_upload_memory,
+            enforce_region, mirror_reads, require_cdn_signature,
+            load_shed::{DEFAULT_MAX_CONCURRENCY, DEFAULT_MAX_IN_FLIGHT, DEFAULT_RETRY_AFTER},
+            upload_budget::DEFAULT_UPLOAD_MEMORY_BUDGET,
+            CdnOrigin, DefaultCacheControl, LoadShedConfig, LoadShedLayer, RegionMismatch,
+            RequestMirror, ServerRegion, UploadBudget,
+        },
+        router::{create_backup_router, create_bucket_location_router, create_router, AppState},
+    },
+    adapters::outbound::storage::{
+        create_local_store, S3ObjectStoreAdapter, StorageTimeouts,
+        timeout::{DEFAULT_LIST_TIMEOUT, DEFAULT_READ_TIMEOUT, DEFAULT_WRITE_TIMEOUT},
+    },
+    domain::{
+        models::{PrefixQuota, DEFAULT_SOFT_LIMIT_PERCENT},
+        value_objects::BucketName,
+    },
+    ports::{runtime::VersionIdScheme, services::ObjectService},
 };
-use std::{net::SocketAddr, sync::Arc};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
 use tokio::net::TcpListener;
 use tracing::info;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use tracing_subscriber::{filter::LevelFilter, layer::SubscriberExt, util::SubscriberInitExt};
 
 #[derive(Parser, Debug)]
 #[command(name = "object-store-server")]
 #[command(about = "A hexagonal architecture object storage server", long_about = None)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Server port to listen on
     #[arg(short, long, env = "SERVER_PORT", default_value = "3000")]
     port: u16,
 
+    /// Port to also serve the gRPC streaming object API on
+    #[arg(long, env = "GRPC_PORT")]
+    grpc_port: Option<u16>,
+
     /// Server host to bind to
     #[arg(long, env = "SERVER_HOST", default_value = "0.0.0.0")]
     host: String,
 
-    /// Storage backend type
+    /// Storage backend type: memory, s3, minio, azure, gcs or local
     #[arg(long, env = "STORAGE_BACKEND", default_value = "memory")]
     storage_backend: String,
 
@@ -41,6 +69,10 @@ struct Cli {
     #[arg(long, env = "S3_REGION", default_value = "us-east-1")]
     s3_region: String,
 
+    /// How to answer requests signed for another region: reject or redirect
+    #[arg(long, env = "REGION_MISMATCH", default_value = "reject")]
+    region_mismatch: String,
+
     /// S3 access key
     #[arg(long, env = "S3_ACCESS_KEY")]
     s3_access_key: Option<String>,
@@ -49,6 +81,62 @@ struct Cli {
     #[arg(long, env = "S3_SECRET_KEY")]
     s3_secret_key: Option<String>,
 
+    /// Create the S3/MinIO bucket at startup if it does not exist
+    #[arg(long, env = "ENSURE_BUCKET", default_value = "false")]
+    ensure_bucket: bool,
+
+    /// Enable versioning on a bucket created by --ensure-bucket
+    #[arg(long, env = "ENSURE_BUCKET_VERSIONING", default_value = "false")]
+    ensure_bucket_versioning: bool,
+
+    /// Enable object lock on a bucket created by --ensure-bucket
+    #[arg(long, env = "ENSURE_BUCKET_OBJECT_LOCK", default_value = "false")]
+    ensure_bucket_object_lock: bool,
+
+    /// Keep object versions with the S3/MinIO bucket's own versioning, which must be enabled
+    #[arg(long, env = "NATIVE_VERSIONING", default_value = "false")]
+    native_versioning: bool,
+
+    /// Azure storage account (for the Azure backend)
+    #[arg(long, env = "AZURE_STORAGE_ACCOUNT")]
+    azure_account: Option<String>,
+
+    /// Azure container name
+    #[arg(long, env = "AZURE_CONTAINER")]
+    azure_container: Option<String>,
+
+    /// Azure storage account access key
+    #[arg(long, env = "AZURE_STORAGE_ACCESS_KEY")]
+    azure_access_key: Option<String>,
+
+    /// GCS bucket name (for the GCS backend)
+    #[arg(long, env = "GCS_BUCKET")]
+    gcs_bucket: Option<String>,
+
+    /// Path to a GCS service account key file
+    #[arg(long, env = "GOOGLE_APPLICATION_CREDENTIALS")]
+    gcs_service_account_path: Option<String>,
+
+    /// Directory objects are stored in (for the local backend)
+    #[arg(long, env = "LOCAL_STORAGE_ROOT")]
+    local_root: Option<PathBuf>,
+
+    /// Check that the backends respond within this many milliseconds before serving
+    #[arg(long, env = "CONNECTIVITY_CHECK_TIMEOUT_MS")]
+    connectivity_check_timeout_ms: Option<u64>,
+
+    /// Milliseconds a storage backend read may take, or wait for each chunk of a download
+    #[arg(long, env = "STORAGE_READ_TIMEOUT_MS", default_value_t = DEFAULT_READ_TIMEOUT.as_millis() as u64)]
+    storage_read_timeout_ms: u64,
+
+    /// Milliseconds a storage backend write, copy or delete may take
+    #[arg(long, env = "STORAGE_WRITE_TIMEOUT_MS", default_value_t = DEFAULT_WRITE_TIMEOUT.as_millis() as u64)]
+    storage_write_timeout_ms: u64,
+
+    /// Milliseconds a storage backend listing may take, or wait for each page
+    #[arg(long, env = "STORAGE_LIST_TIMEOUT_MS", default_value_t = DEFAULT_LIST_TIMEOUT.as_millis() as u64)]
+    storage_list_timeout_ms: u64,
+
     /// Use SSL for MinIO connection
     #[arg(long, env = "MINIO_USE_SSL", default_value = "false")]
     minio_use_ssl: bool,
@@ -57,76 +145,158 @@ struct Cli {
     #[arg(long, env = "DATABASE_URL")]
     database_url: Option<String>,
 
+    /// Comma-separated connection strings of read replicas to send reads to
+    #[arg(long, env = "DATABASE_READ_URLS", value_delimiter = ',')]
+    database_read_urls: Vec<String>,
+
     /// Log level
     #[arg(long, env = "LOG_LEVEL", default_value = "info")]
     log_level: String,
+
+    /// Memory budget in bytes shared by all in-flight uploads
+    #[arg(long, env = "UPLOAD_MEMORY_BUDGET", default_value_t = DEFAULT_UPLOAD_MEMORY_BUDGET)]
+    upload_memory_budget: usize,
+
+    /// Requests admitted at once before new ones are rejected with 503
+    #[arg(long, env = "MAX_IN_FLIGHT_REQUESTS", default_value_t = DEFAULT_MAX_IN_FLIGHT)]
+    max_in_flight_requests: usize,
+
+    /// Requests executing at once; the rest wait in a queue
+    #[arg(long, env = "MAX_CONCURRENT_REQUESTS", default_value_t = DEFAULT_MAX_CONCURRENCY)]
+    max_concurrent_requests: usize,
+
+    /// Milliseconds a request may wait in the queue before it is rejected with 503
+    #[arg(long, env = "MAX_QUEUE_AGE_MS", default_value = "1000")]
+    max_queue_age_ms: u64,
+
+    /// Cache-Control sent with objects that have none of their own, e.g. "public, max-age=300"
+    #[arg(long, env = "DEFAULT_CACHE_CONTROL")]
+    default_cache_control: Option<String>,
+
+    /// Comma-separated KEY_PAIR_ID=SECRET pairs; when set, reads must carry a signed URL or cookies
+    #[arg(long, env = "CDN_KEY_PAIRS", value_delimiter = ',')]
+    cdn_key_pairs: Vec<String>,
+
+    /// Public URL of the CDN, prefixed to request paths when matching signed policies
+    #[arg(long, env = "CDN_BASE_URL")]
+    cdn_base_url: Option<String>,
+
+    /// Secondary server to mirror a share of reads to, discarding its responses
+    #[arg(long, env = "MIRROR_URL")]
+    mirror_url: Option<String>,
+
+    /// Percentage of reads mirrored to --mirror-url
+    #[arg(long, env = "MIRROR_PERCENT", default_value = "100")]
+    mirror_percent: f64,
+
+    /// Forward clients' Authorization headers, cookies and API keys to --mirror-url
+    #[arg(long, env = "MIRROR_CREDENTIALS", default_value = "false")]
+    mirror_credentials: bool,
+
+    /// Directory to write scheduled bucket backups to, enabling the backup admin API
+    #[arg(long, env = "BACKUP_DIR")]
+    backup_dir: Option<PathBuf>,
+
+    /// Keep lifecycle configurations as objects in the storage backend
+    #[arg(long, env = "LIFECYCLE_CONFIG_IN_STORE", default_value = "false")]
+    lifecycle_config_in_store: bool,
+
+    /// Comma-separated BUCKET=MAX_BYTES pairs; writes beyond a bucket's limit are refused
+    #[arg(long, env = "BUCKET_QUOTAS", value_delimiter = ',')]
+    bucket_quotas: Vec<String>,
+
+    /// Percentage of a bucket quota at which writes are answered with a warning header
+    #[arg(long, env = "QUOTA_SOFT_LIMIT_PERCENT", default_value_t = DEFAULT_SOFT_LIMIT_PERCENT)]
+    quota_soft_limit_percent: u8,
+
+    /// Scheme of new version IDs: uuid, uuidv7, ulid, or backend to use the IDs a versioned S3/MinIO bucket assigns
+    #[arg(long, env = "VERSION_ID_SCHEME", default_value = "uuid")]
+    version_id_scheme: VersionIdScheme,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Check the configuration and backends instead of serving, exiting
+    /// non-zero if any check fails
+    ///
+    /// Builds the configured backends, probes each of them, and writes, reads
+    /// and deletes a scratch object in the storage backend, printing a report
+    /// of every check.
+    Check {
+        /// Milliseconds each check may take
+        #[arg(long, env = "CHECK_TIMEOUT_MS", default_value = "10000")]
+        timeout_ms: u64,
+    },
 }
 
 impl Cli {
-    fn to_app_config(&self) -> Result<AppConfig> {
-        let storage_backend = match self.storage_backend.as_str() {
-            "memory" => StorageBackend::InMemory,
-            "s3" => {
-                let bucket = self.s3_bucket.clone()
-                    .context("S3_BUCKET is required for S3 backend")?;
-                let access_key = self.s3_access_key.clone();
-                let secret_key = self.s3_secret_key.clone();
-                
-                StorageBackend::S3 {
-                    bucket,
-                    region: self.s3_region.clone(),
-                    access_key,
-                    secret_key,
-                }
-            }
-            "minio" => {
-                let endpoint = self.s3_endpoint.clone()
-                    .context("S3_ENDPOINT is required for MinIO backend")?;
-                let bucket = self.s3_bucket.clone()
-                    .context("S3_BUCKET is required for MinIO backend")?;
-                let access_key = self.s3_access_key.clone()
-                    .context("S3_ACCESS_KEY is required for MinIO backend")?;
-                let secret_key = self.s3_secret_key.clone()
-                    .context("S3_SECRET_KEY is required for MinIO backend")?;
-                
-                StorageBackend::MinIO {
-                    endpoint,
-                    bucket,
-                    access_key,
-                    secret_key,
-                    use_ssl: self.minio_use_ssl,
-                }
-            }
-            _ => anyhow::bail!("Unknown storage backend: {}", self.storage_backend),
-        };
+    /// The server's configuration, read as [`EnvConfig`] reads the
+    /// environment with the command line's values taking precedence
+    fn env_config(&self) -> Result<EnvConfig> {
+        Ok(EnvConfig::from_vars(|name| self.env_var(name))?)
+    }
 
-        let repository_backend = match self.repository_backend.as_str() {
-            "memory" => RepositoryBackend::InMemory,
-            "database" | "db" => {
-                let connection_string = self.database_url.clone()
-                    .context("DATABASE_URL is required for database backend")?;
-                RepositoryBackend::Database { connection_string }
-            }
-            _ => anyhow::bail!("Unknown repository backend: {}", self.repository_backend),
-        };
+    /// Value of the variable `name`, from the option that sets it when there is one
+    fn env_var(&self, name: &str) -> Option<String> {
+        let flag = |set: bool| set.then(|| "true".to_string());
+        let env = || std::env::var(name).ok();
+        match name {
+            "STORAGE_BACKEND" => Some(self.storage_backend.clone()),
+            "REPOSITORY_BACKEND" => Some(match self.repository_backend.as_str() {
+                "db" => "database".to_string(),
+                other => other.to_string(),
+            }),
+            "S3_BUCKET" => self.s3_bucket.clone(),
+            "S3_REGION" => Some(self.s3_region.clone()),
+            "S3_ACCESS_KEY" => self.s3_access_key.clone(),
+            "S3_SECRET_KEY" => self.s3_secret_key.clone(),
+            // The MinIO backend is configured through the S3 options too,
+            // falling back to its own variables when they aren't given
+            "MINIO_ENDPOINT" => self.s3_endpoint.clone().or_else(env),
+            "MINIO_BUCKET" => self.s3_bucket.clone().or_else(env),
+            "MINIO_ACCESS_KEY" => self.s3_access_key.clone().or_else(env),
+            "MINIO_SECRET_KEY" => self.s3_secret_key.clone().or_else(env),
+            "MINIO_USE_SSL" => flag(self.minio_use_ssl),
+            "AZURE_STORAGE_ACCOUNT" => self.azure_account.clone(),
+            "AZURE_CONTAINER" => self.azure_container.clone(),
+            "AZURE_STORAGE_ACCESS_KEY" => self.azure_access_key.clone(),
+            "GCS_BUCKET" => self.gcs_bucket.clone(),
+            "GOOGLE_APPLICATION_CREDENTIALS" => self.gcs_service_account_path.clone(),
+            "LOCAL_STORAGE_ROOT" => self
+                .local_root
+                .as_ref()
+                .map(|root| root.display().to_string()),
+            "ENSURE_BUCKET" => flag(self.ensure_bucket),
+            "ENSURE_BUCKET_VERSIONING" => flag(self.ensure_bucket_versioning),
+            "ENSURE_BUCKET_OBJECT_LOCK" => flag(self.ensure_bucket_object_lock),
+            "NATIVE_VERSIONING" => flag(self.native_versioning),
+            "DATABASE_URL" => self.database_url.clone(),
+            "DATABASE_READ_URLS" => Some(self.database_read_urls.join(",")),
+            _ => env(),
+        }
+    }
 
-        Ok(AppConfig {
-            storage_backend,
-            repository_backend,
-        })
+    fn server_region(&self) -> Result<ServerRegion> {
+        let on_mismatch = match self.region_mismatch.as_str() {
+            "reject" => RegionMismatch::Reject,
+            "redirect" => RegionMismatch::Redirect,
+            _ => anyhow::bail!("Unknown region mismatch policy: {}", self.region_mismatch),
+        };
+        Ok(ServerRegion::new(self.s3_region.clone()).with_mismatch(on_mismatch))
     }
 
     fn init_logging(&self) -> Result<()> {
-        let env_filter = match self.log_level.to_lowercase().as_str() {
-            "trace" => "trace",
-            "debug" => "debug",
-            "info" => "info",
-            "warn" => "warn",
-            "error" => "error",
-            _ => "info",
+        let level = match self.log_level.to_lowercase().as_str() {
+            "trace" => LevelFilter::TRACE,
+            "debug" => LevelFilter::DEBUG,
+            "info" => LevelFilter::INFO,
+            "warn" => LevelFilter::WARN,
+            "error" => LevelFilter::ERROR,
+            _ => LevelFilter::INFO,
         };
 
         tracing_subscriber::registry()
+            .with(level)
             .with(tracing_subscriber::fmt::layer())
             .init();
 
@@ -150,22 +320,141 @@ async fn main() -> Result<()> {
     info!("Repository backend: {}", cli.repository_backend);
 
     // Create app configuration
-    let config = cli.to_app_config()?;
+    let env_config = cli.env_config()?;
+    if let Some(scheduler) = &env_config.lifecycle_scheduler {
+        info!("Running lifecycle rules every {:?}", scheduler.interval);
+    }
+    let server_region = cli.server_region()?;
 
     // Build the application
-    let app_builder = AppBuilder::new().with_config(config);
+    let mut app_builder = AppBuilder::new()
+        .with_env_config(env_config)
+        .with_storage_timeouts(StorageTimeouts {
+            read: Duration::from_millis(cli.storage_read_timeout_ms),
+            write: Duration::from_millis(cli.storage_write_timeout_ms),
+            list: Duration::from_millis(cli.storage_list_timeout_ms),
+        });
+    if let Some(timeout_ms) = cli.connectivity_check_timeout_ms {
+        app_builder = app_builder.with_connectivity_check(Duration::from_millis(timeout_ms));
+    }
+    for quota in &cli.bucket_quotas {
+        let (bucket, max_bytes) = quota
+            .split_once('=')
+            .context("Bucket quotas must be given as BUCKET=MAX_BYTES")?;
+        let max_bytes: u64 = max_bytes.parse().context("Invalid bucket quota size")?;
+        let quota = PrefixQuota::for_bucket(&BucketName::new(bucket.to_string())?)
+            .with_max_bytes(max_bytes)
+            .with_soft_limit_percent(Some(cli.quota_soft_limit_percent));
+        info!("Limiting bucket {} to {} bytes", bucket, max_bytes);
+        app_builder = app_builder.with_prefix_quota(quota);
+    }
+    if cli.version_id_scheme != VersionIdScheme::default() {
+        info!("Using {:?} version IDs", cli.version_id_scheme);
+        app_builder =
+            app_builder.with_version_id_generator(cli.version_id_scheme.generator());
+    }
+    if cli.lifecycle_config_in_store {
+        info!("Keeping lifecycle configurations in the storage backend");
+        app_builder = app_builder.with_lifecycle_config_in_store();
+    }
+    if let Some(backup_dir) = &cli.backup_dir {
+        info!("Writing bucket backups to {}", backup_dir.display());
+        let store = create_local_store(backup_dir).context("Failed to open backup directory")?;
+        let bucket = BucketName::new("backups".to_string())?;
+        app_builder =
+            app_builder.with_backup_target(Arc::new(S3ObjectStoreAdapter::new(store, bucket)));
+    }
+
+    if let Some(Command::Check { timeout_ms }) = &cli.command {
+        let report = app_builder.self_test(Duration::from_millis(*timeout_ms)).await;
+        println!("{}", report);
+        if !report.passed() {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     let app_services = app_builder.build().await
         .context("Failed to build application")?;
 
     // Create the application state for the router
+    let object_service: Arc<dyn ObjectService> = Arc::new(app_services.object_service);
     let state = AppState {
-        object_service: Arc::new(app_services.object_service),
+        object_service: object_service.clone(),
         lifecycle_service: Arc::new(app_services.lifecycle_service),
         versioning_service: Arc::new(app_services.versioning_service),
     };
 
-    // Create the router
-    let router = create_router(state);
+    // Create the router, applying backpressure once the upload budget is used up
+    let upload_budget = UploadBudget::new(cli.upload_memory_budget);
+    let router = create_router(state)
+        .merge(create_bucket_location_router(server_region.clone()))
+        .merge(match app_services.backup_service {
+            Some(backups) => create_backup_router(Arc::new(backups)),
+            None => axum::Router::new(),
+        })
+        .layer(axum::middleware::from_fn_with_state(
+            upload_budget,
+            limit_upload_memory,
+        ));
+
+    // Let caches keep objects that do not say how long they may be cached
+    let router = match &cli.default_cache_control {
+        Some(value) => router.layer(axum::middleware::from_fn_with_state(
+            DefaultCacheControl::new(value).context("Invalid default Cache-Control")?,
+            apply_default_cache_control,
+        )),
+        None => router,
+    };
+
+    // Act as a secure CDN origin, serving reads only to signed URLs and cookies
+    let router = if cli.cdn_key_pairs.is_empty() {
+        router
+    } else {
+        let mut origin = CdnOrigin::new();
+        for pair in &cli.cdn_key_pairs {
+            let (key_pair_id, secret) = pair
+                .split_once('=')
+                .context("CDN key pairs must be given as KEY_PAIR_ID=SECRET")?;
+            origin = origin.with_key_pair(key_pair_id, secret.as_bytes());
+        }
+        if let Some(base_url) = &cli.cdn_base_url {
+            origin = origin.with_base_url(base_url);
+        }
+        info!("CDN origin mode: reads require a signed URL or cookies");
+        router.layer(axum::middleware::from_fn_with_state(origin, require_cdn_signature))
+    };
+
+    // Shadow a share of reads to a secondary server, e.g. to validate a migration
+    let router = match &cli.mirror_url {
+        Some(url) => {
+            info!("Mirroring {}% of reads to {}", cli.mirror_percent, url);
+            let mirror = RequestMirror::new(url, cli.mirror_percent);
+            let mirror = match cli.mirror_credentials {
+                true => mirror.with_credentials(),
+                false => mirror,
+            };
+            router.layer(axum::middleware::from_fn_with_state(mirror, mirror_reads))
+        }
+        None => router,
+    };
+
+    // Turn away requests signed for another region before they reach a handler
+    let router = router.layer(axum::middleware::from_fn_with_state(
+        server_region,
+        enforce_region,
+    ));
+
+    // Cancel work for requests whose client-supplied deadline has passed
+    let router = router.layer(axum::middleware::from_fn(enforce_request_deadline));
+
+    // Shed load with 503 responses once too many requests are in flight or queued
+    let router = router.layer(LoadShedLayer::new(LoadShedConfig {
+        max_in_flight: cli.max_in_flight_requests,
+        max_concurrency: cli.max_concurrent_requests,
+        max_queue_age: Duration::from_millis(cli.max_queue_age_ms),
+        retry_after: DEFAULT_RETRY_AFTER,
+    }));
 
     // Bind to address
     let addr: SocketAddr = format!("{}:{}", cli.host, cli.port).parse()?;
@@ -173,10 +462,28 @@ async fn main() -> Result<()> {
     
     info!("Server listening on http://{}", addr);
 
-    // Start the server
-    axum::serve(listener, router)
-        .await
-        .context("Failed to start server")?;
+    let http_server = async {
+        axum::serve(listener, router)
+            .await
+            .context("Failed to start server")
+    };
+
+    // Serve streaming uploads and downloads over gRPC alongside the HTTP API
+    match cli.grpc_port {
+        Some(grpc_port) => {
+            let grpc_addr: SocketAddr = format!("{}:{}", cli.host, grpc_port).parse()?;
+            info!("gRPC server listening on http://{}", grpc_addr);
+            let grpc_server = async {
+                tonic::transport::Server::builder()
+                    .add_service(ObjectsServer::new(GrpcObjectService::new(object_service)))
+                    .serve(grpc_addr)
+                    .await
+                    .context("Failed to start gRPC server")
+            };
+            tokio::try_join!(http_server, grpc_server)?;
+        }
+        None => http_server.await?,
+    }
 
     Ok(())
 }
@@ -184,12 +491,14 @@ async fn main() -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use object_store_server::app::{RepositoryBackend, StorageBackend};
 
     #[test]
     fn test_cli_parsing() {
-        let cli = Cli::parse_from(&[
+        let cli = Cli::parse_from([
             "object-store-server",
             "--port", "8080",
+            "--grpc-port", "50051",
             "--storage-backend", "s3",
             "--s3-bucket", "test-bucket",
             "--s3-access-key", "test-key",
@@ -197,20 +506,105 @@ mod tests {
         ]);
 
         assert_eq!(cli.port, 8080);
+        assert_eq!(cli.grpc_port, Some(50051));
         assert_eq!(cli.storage_backend, "s3");
         assert_eq!(cli.s3_bucket, Some("test-bucket".to_string()));
+        assert!(cli.command.is_none());
+    }
+
+    #[test]
+    fn test_check_command_parsing() {
+        let cli = Cli::parse_from([
+            "object-store-server",
+            "--storage-backend", "local",
+            "--local-root", "/var/lib/objects",
+            "check",
+            "--timeout-ms", "500",
+        ]);
+
+        match cli.command {
+            Some(Command::Check { timeout_ms }) => assert_eq!(timeout_ms, 500),
+            _ => panic!("Expected the check command"),
+        }
+        assert_eq!(cli.storage_backend, "local");
     }
 
     #[test]
     fn test_memory_config() {
-        let cli = Cli::parse_from(&[
+        let cli = Cli::parse_from([
             "object-store-server",
         ]);
 
-        let config = cli.to_app_config().unwrap();
+        let config = cli.env_config().unwrap().app;
         match config.storage_backend {
             StorageBackend::InMemory => (),
             _ => panic!("Expected InMemory backend"),
         }
+        assert!(config.ensure_bucket.is_none());
+    }
+
+    #[test]
+    fn test_ensure_bucket_config() {
+        let cli = Cli::parse_from([
+            "object-store-server",
+            "--storage-backend", "minio",
+            "--s3-endpoint", "http://localhost:9000",
+            "--s3-bucket", "test-bucket",
+            "--s3-access-key", "test-key",
+            "--s3-secret-key", "test-secret",
+            "--ensure-bucket",
+            "--ensure-bucket-versioning",
+            "--native-versioning",
+        ]);
+
+        assert_eq!(cli.env_var("MINIO_BUCKET").as_deref(), Some("test-bucket"));
+        assert_eq!(
+            cli.env_var("MINIO_ENDPOINT").as_deref(),
+            Some("http://localhost:9000")
+        );
+
+        let config = cli.env_config().unwrap().app;
+        assert!(config.native_versioning);
+        let ensure_bucket = config.ensure_bucket.unwrap();
+        assert!(ensure_bucket.versioning_enabled);
+        assert!(!ensure_bucket.object_lock_enabled);
+    }
+
+    #[test]
+    fn test_non_s3_backend_config() {
+        let cli = Cli::parse_from([
+            "object-store-server",
+            "--storage-backend", "local",
+            "--local-root", "/var/lib/objects",
+        ]);
+        match cli.env_config().unwrap().app.storage_backend {
+            StorageBackend::LocalFs { root } => assert_eq!(root, PathBuf::from("/var/lib/objects")),
+            _ => panic!("Expected LocalFs backend"),
+        }
+
+        let cli = Cli::parse_from([
+            "object-store-server",
+            "--storage-backend", "azure",
+            "--azure-account", "devstore",
+        ]);
+        assert!(cli.env_config().is_err());
+    }
+
+    #[test]
+    fn test_database_repository_config() {
+        let cli = Cli::parse_from([
+            "object-store-server",
+            "--repository-backend", "db",
+            "--database-url", "postgres://localhost/objects",
+            "--database-read-urls", "postgres://replica-1/objects,postgres://replica-2/objects",
+        ]);
+
+        match cli.env_config().unwrap().app.repository_backend {
+            RepositoryBackend::Database { connection_string, read_replicas } => {
+                assert_eq!(connection_string, "postgres://localhost/objects");
+                assert_eq!(read_replicas.len(), 2);
+            }
+            _ => panic!("Expected Database backend"),
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,31 @@
+use reqwest::Method;
+
+use super::{Client, ClientError};
+use crate::adapters::inbound::http::dto::{BucketVersioningDto, VersioningStatusDto};
+
+impl Client {
+    pub async fn get_bucket_versioning(
+        &self,
+        bucket: &str,
+    ) -> Result<VersioningStatusDto, ClientError> {
+        let url = self.url(&["buckets", bucket, "versioning"]);
+        let versioning: BucketVersioningDto =
+            self.send_json(self.request(Method::GET, url)).await?;
+        Ok(versioning.status)
+    }
+
+    /// Enable or suspend versioning of a bucket
+    pub async fn set_bucket_versioning(
+        &self,
+        bucket: &str,
+        status: VersioningStatusDto,
+    ) -> Result<(), ClientError> {
+        let url = self.url(&["buckets", bucket, "versioning"]);
+        self.send(
+            self.request(Method::PUT, url)
+                .json(&BucketVersioningDto { status }),
+        )
+        .await?;
+        Ok(())
+    }
+}
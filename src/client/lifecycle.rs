@@ -0,0 +1,128 @@
+use reqwest::Method;
+use serde_json::Value;
+
+use super::{Client, ClientError};
+use crate::adapters::inbound::http::dto::{
+    ApplyLifecycleTemplateDto, CopyLifecycleDto, LifecycleConfigurationDto,
+    LifecycleProcessingStatusDto, LifecycleRuleDto, LifecycleSimulationResponseDto,
+    LifecycleTemplateDto, SimulateLifecycleDto, SuccessResponseDto,
+};
+
+impl Client {
+    fn lifecycle_url(&self, bucket: &str, segments: &[&str]) -> reqwest::Url {
+        let mut path = vec!["buckets", bucket, "lifecycle"];
+        path.extend(segments);
+        self.url(&path)
+    }
+
+    pub async fn get_lifecycle_configuration(
+        &self,
+        bucket: &str,
+    ) -> Result<LifecycleConfigurationDto, ClientError> {
+        let url = self.lifecycle_url(bucket, &[]);
+        self.send_json(self.request(Method::GET, url)).await
+    }
+
+    /// Replace a bucket's lifecycle configuration with `rules`
+    pub async fn set_lifecycle_configuration(
+        &self,
+        bucket: &str,
+        rules: Vec<LifecycleRuleDto>,
+    ) -> Result<(), ClientError> {
+        let config = LifecycleConfigurationDto {
+            bucket: bucket.to_string(),
+            rules,
+        };
+        let url = self.lifecycle_url(bucket, &[]);
+        self.send(self.request(Method::PUT, url).json(&config))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_lifecycle_configuration(&self, bucket: &str) -> Result<(), ClientError> {
+        let url = self.lifecycle_url(bucket, &[]);
+        self.send(self.request(Method::DELETE, url)).await?;
+        Ok(())
+    }
+
+    /// Copy a bucket's lifecycle configuration to the target buckets
+    ///
+    /// Returns the buckets that were updated.
+    pub async fn copy_lifecycle_configuration(
+        &self,
+        source: &str,
+        request: &CopyLifecycleDto,
+    ) -> Result<Vec<String>, ClientError> {
+        let url = self.lifecycle_url(source, &["copy"]);
+        let response: SuccessResponseDto = self
+            .send_json(self.request(Method::POST, url).json(request))
+            .await?;
+        success_data(response, Some("targets"))
+    }
+
+    /// List the lifecycle rule templates the server provides
+    pub async fn list_lifecycle_templates(&self) -> Result<Vec<LifecycleTemplateDto>, ClientError> {
+        let url = self.url(&["lifecycle", "templates"]);
+        self.send_json(self.request(Method::GET, url)).await
+    }
+
+    /// Add the rule described by a named template to a bucket's lifecycle
+    /// configuration
+    ///
+    /// Returns the rule the server added.
+    pub async fn apply_lifecycle_template(
+        &self,
+        bucket: &str,
+        template: &str,
+        options: &ApplyLifecycleTemplateDto,
+    ) -> Result<LifecycleRuleDto, ClientError> {
+        let url = self.lifecycle_url(bucket, &["templates", template]);
+        let response: SuccessResponseDto = self
+            .send_json(self.request(Method::POST, url).json(options))
+            .await?;
+        success_data(response, None)
+    }
+
+    /// Ask the server which objects in a bucket the rules would act on,
+    /// without changing anything
+    pub async fn simulate_lifecycle(
+        &self,
+        bucket: &str,
+        request: &SimulateLifecycleDto,
+    ) -> Result<LifecycleSimulationResponseDto, ClientError> {
+        let url = self.lifecycle_url(bucket, &["simulate"]);
+        self.send_json(self.request(Method::POST, url).json(request))
+            .await
+    }
+
+    /// Start lifecycle processing of a bucket in the background
+    pub async fn run_lifecycle(
+        &self,
+        bucket: &str,
+    ) -> Result<LifecycleProcessingStatusDto, ClientError> {
+        let url = self.lifecycle_url(bucket, &["run"]);
+        self.send_json(self.request(Method::POST, url)).await
+    }
+
+    pub async fn lifecycle_status(
+        &self,
+        bucket: &str,
+    ) -> Result<LifecycleProcessingStatusDto, ClientError> {
+        let url = self.lifecycle_url(bucket, &["status"]);
+        self.send_json(self.request(Method::GET, url)).await
+    }
+}
+
+/// The `data` of a success response, or the given field of it
+fn success_data<T: serde::de::DeserializeOwned>(
+    response: SuccessResponseDto,
+    field: Option<&str>,
+) -> Result<T, ClientError> {
+    let mut data = response
+        .data
+        .ok_or_else(|| ClientError::InvalidResponse("Response has no data".to_string()))?;
+    if let Some(field) = field {
+        data = data.get_mut(field).map(Value::take).unwrap_or_default();
+    }
+    serde_json::from_value(data).map_err(|e| ClientError::InvalidResponse(e.to_string()))
+}
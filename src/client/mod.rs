@@ -0,0 +1,393 @@
+//! Typed async client for the server's HTTP API
+//!
+//! [`Client`] wraps the object, version, lifecycle and bucket endpoints,
+//! exchanging the same DTOs the server's handlers use, so services talking to
+//! the server don't build URLs and parse JSON by hand. Requests that can be
+//! replayed are retried on connection failures and transient server errors
+//! according to the client's [`RetryPolicy`]. Object data is streamed in both
+//! directions.
+//!
+//! The client also builds for `wasm32-unknown-unknown`, where requests go
+//! through the browser's `fetch`. Streamed uploads, writing downloads to a
+//! tokio writer and request timeouts are only available on native targets.
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), object_store_server::client::ClientError> {
+//! use object_store_server::client::Client;
+//!
+//! let client = Client::builder("http://localhost:3000")
+//!     .api_key("secret")
+//!     .build()?;
+//! client.put_object("photos/cat.jpg", b"...".to_vec(), Default::default()).await?;
+//! let data = client.get_object("photos/cat.jpg").await?.bytes().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod buckets;
+mod lifecycle;
+mod objects;
+mod versions;
+
+pub use objects::{ByteStream, ObjectDownload, ObjectHead, PutObjectOptions};
+pub use versions::{CreatedVersion, ListVersionsOptions, RestoredVersion};
+
+use reqwest::{Method, RequestBuilder, Response, StatusCode, Url};
+use std::time::Duration;
+
+use crate::adapters::inbound::http::dto::ErrorResponseDto;
+
+/// Errors returned by [`Client`]
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("Invalid server URL: {0}")]
+    InvalidUrl(String),
+
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    /// The server answered with an error status
+    #[error("Server error: {status} - {message}")]
+    Api {
+        status: u16,
+        /// Error code from the server's error response, e.g. `NOT_FOUND`
+        error: String,
+        message: String,
+    },
+
+    #[error("Invalid response: {0}")]
+    InvalidResponse(String),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl ClientError {
+    /// HTTP status of an error answered by the server
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            ClientError::Api { status, .. } => Some(*status),
+            ClientError::Http(e) => e.status().map(|status| status.as_u16()),
+            _ => None,
+        }
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        self.status() == Some(StatusCode::NOT_FOUND.as_u16())
+    }
+}
+
+/// How requests failing with a transient error are retried
+///
+/// Connection errors, timeouts, 429 and 5xx responses other than 501 are
+/// retried with exponential backoff. Only requests with an idempotent method
+/// and a body that can be sent again are retried; streamed uploads and POSTs
+/// are sent once.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt
+    pub max_retries: u32,
+
+    /// Delay before the first retry, doubled for each one after it
+    pub initial_backoff: Duration,
+
+    /// Longest delay between two attempts
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Send every request once
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Default::default()
+        }
+    }
+
+    /// Delay before retry number `attempt`, counting from zero
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff)
+    }
+
+    fn is_retryable_status(status: StatusCode) -> bool {
+        status == StatusCode::TOO_MANY_REQUESTS
+            || (status.is_server_error() && status != StatusCode::NOT_IMPLEMENTED)
+    }
+}
+
+/// Builder for a [`Client`]
+#[derive(Debug)]
+pub struct ClientBuilder {
+    base_url: String,
+    api_key: Option<String>,
+    retry: RetryPolicy,
+    #[cfg(not(target_arch = "wasm32"))]
+    timeout: Option<Duration>,
+    http: Option<reqwest::Client>,
+}
+
+impl ClientBuilder {
+    /// Send the API key as a bearer token with every request
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    pub fn retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Give up on a request, including reading its response, after `timeout`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Send requests through an existing HTTP client, e.g. one with a proxy
+    /// or custom TLS roots configured
+    ///
+    /// The client's own timeout applies instead of [`timeout`](Self::timeout).
+    pub fn http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = Some(http);
+        self
+    }
+
+    pub fn build(self) -> Result<Client, ClientError> {
+        let base_url =
+            Url::parse(&self.base_url).map_err(|e| ClientError::InvalidUrl(e.to_string()))?;
+        if base_url.cannot_be_a_base() {
+            return Err(ClientError::InvalidUrl(format!(
+                "{} cannot be a base URL",
+                self.base_url
+            )));
+        }
+
+        let http = match self.http {
+            Some(http) => http,
+            None => {
+                #[allow(unused_mut)]
+                let mut builder = reqwest::Client::builder();
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(timeout) = self.timeout {
+                    builder = builder.timeout(timeout);
+                }
+                builder.build()?
+            }
+        };
+
+        Ok(Client {
+            http,
+            base_url,
+            api_key: self.api_key,
+            retry: self.retry,
+        })
+    }
+}
+
+/// Client for the server's HTTP API
+///
+/// Cloning is cheap and clones share their connection pool.
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: Url,
+    api_key: Option<String>,
+    retry: RetryPolicy,
+}
+
+impl Client {
+    /// Client for the server at `base_url` with the default settings
+    pub fn new(base_url: impl Into<String>) -> Result<Self, ClientError> {
+        Self::builder(base_url).build()
+    }
+
+    pub fn builder(base_url: impl Into<String>) -> ClientBuilder {
+        ClientBuilder {
+            base_url: base_url.into(),
+            api_key: None,
+            retry: RetryPolicy::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            timeout: None,
+            http: None,
+        }
+    }
+
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    /// URL of the endpoint made of the given path segments, each escaped as a
+    /// single segment
+    fn url(&self, segments: &[&str]) -> Url {
+        let mut url = self.base_url.clone();
+        if let Ok(mut path) = url.path_segments_mut() {
+            path.pop_if_empty().extend(segments);
+        }
+        url
+    }
+
+    fn request(&self, method: Method, url: Url) -> RequestBuilder {
+        let request = self.http.request(method, url);
+        match &self.api_key {
+            Some(api_key) => request.bearer_auth(api_key),
+            None => request,
+        }
+    }
+
+    /// Send a request, retrying it on transient errors when it can be replayed,
+    /// and turn error statuses into [`ClientError::Api`]
+    async fn send(&self, request: RequestBuilder) -> Result<Response, ClientError> {
+        let idempotent = request
+            .try_clone()
+            .and_then(|request| request.build().ok())
+            .is_some_and(|request| {
+                matches!(
+                    *request.method(),
+                    Method::GET | Method::HEAD | Method::PUT | Method::DELETE
+                )
+            });
+
+        let mut attempt = 0;
+        loop {
+            let Some(current) = request
+                .try_clone()
+                .filter(|_| idempotent && attempt < self.retry.max_retries)
+            else {
+                return check_status(request.send().await?).await;
+            };
+
+            let result = current.send().await;
+            let retryable = match &result {
+                Ok(response) => RetryPolicy::is_retryable_status(response.status()),
+                Err(e) => is_transient(e),
+            };
+            if !retryable {
+                return check_status(result?).await;
+            }
+
+            sleep(self.retry.backoff(attempt)).await;
+            attempt += 1;
+        }
+    }
+
+    async fn send_json<T: serde::de::DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<T, ClientError> {
+        let response = self.send(request).await?;
+        let body = response.bytes().await?;
+        serde_json::from_slice(&body).map_err(|e| ClientError::InvalidResponse(e.to_string()))
+    }
+}
+
+/// Whether a request that got no answer failed in a way worth retrying
+fn is_transient(error: &reqwest::Error) -> bool {
+    #[cfg(not(target_arch = "wasm32"))]
+    return error.is_connect() || error.is_timeout();
+    // A failed fetch is reported as a request error in the browser
+    #[cfg(target_arch = "wasm32")]
+    return error.is_request() || error.is_timeout();
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await
+}
+
+/// Wait on a `setTimeout` of the global scope, which windows and workers both
+/// have
+#[cfg(target_arch = "wasm32")]
+async fn sleep(duration: Duration) {
+    use wasm_bindgen::{JsCast, JsValue};
+
+    let millis = duration.as_millis().min(i32::MAX as u128) as i32;
+    let promise = js_sys::Promise::new(&mut |resolve, _| {
+        let set_timeout = js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("setTimeout"))
+            .ok()
+            .and_then(|set_timeout| set_timeout.dyn_into::<js_sys::Function>().ok());
+        let _ = match set_timeout {
+            Some(set_timeout) => set_timeout.call2(&JsValue::NULL, &resolve, &millis.into()),
+            None => resolve.call0(&JsValue::NULL),
+        };
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
+
+/// `url` with the query parameters that have a value
+fn with_query(mut url: Url, params: &[(&str, Option<String>)]) -> Url {
+    let mut params = params
+        .iter()
+        .filter_map(|(name, value)| Some((*name, value.as_deref()?)))
+        .peekable();
+    if params.peek().is_some() {
+        url.query_pairs_mut().extend_pairs(params);
+    }
+    url
+}
+
+/// Pass successful responses through and read the error response of others
+async fn check_status(response: Response) -> Result<Response, ClientError> {
+    let status = response.status();
+    if status.is_success() || status == StatusCode::NOT_MODIFIED {
+        return Ok(response);
+    }
+
+    let body = response.text().await.unwrap_or_default();
+    Err(match serde_json::from_str::<ErrorResponseDto>(&body) {
+        Ok(error) => ClientError::Api {
+            status: status.as_u16(),
+            error: error.error,
+            message: error.message,
+        },
+        Err(_) => ClientError::Api {
+            status: status.as_u16(),
+            error: status.canonical_reason().unwrap_or_default().to_string(),
+            message: body,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_urls_escape_segments() {
+        let client = Client::new("http://localhost:3000/api/").unwrap();
+        assert_eq!(
+            client.url(&["objects", "photos/2024/a b.jpg"]).as_str(),
+            "http://localhost:3000/api/objects/photos%2F2024%2Fa%20b.jpg"
+        );
+
+        assert!(matches!(
+            Client::new("mailto:ops@example.com"),
+            Err(ClientError::InvalidUrl(_))
+        ));
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_limit() {
+        let retry = RetryPolicy {
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(350),
+        };
+        assert_eq!(retry.backoff(0), Duration::from_millis(100));
+        assert_eq!(retry.backoff(1), Duration::from_millis(200));
+        assert_eq!(retry.backoff(2), Duration::from_millis(350));
+    }
+}
@@ -0,0 +1,306 @@
+use bytes::{Bytes, BytesMut};
+use chrono::{DateTime, Utc};
+use futures::{StreamExt, TryStreamExt};
+use reqwest::{Method, RequestBuilder, Response, StatusCode, header};
+use std::ops::Range;
+#[cfg(not(target_arch = "wasm32"))]
+use {
+    futures::Stream,
+    reqwest::Body,
+    tokio::io::{AsyncWrite, AsyncWriteExt},
+};
+
+use super::{Client, ClientError, with_query};
+use crate::{
+    adapters::inbound::http::dto::{
+        ListObjectsResponseDto, PresignRequestDto, PresignedUrlResponseDto,
+    },
+    domain::models::DELETE_AT_METADATA_KEY,
+};
+
+/// Optional headers of an object upload
+#[derive(Debug, Clone, Default)]
+pub struct PutObjectOptions {
+    pub content_type: Option<String>,
+
+    /// `Cache-Control` sent with reads of the object
+    pub cache_control: Option<String>,
+
+    /// When the server deletes the object
+    pub delete_at: Option<DateTime<Utc>>,
+}
+
+impl PutObjectOptions {
+    fn apply(&self, mut request: RequestBuilder) -> RequestBuilder {
+        if let Some(content_type) = &self.content_type {
+            request = request.header(header::CONTENT_TYPE, content_type);
+        }
+        if let Some(cache_control) = &self.cache_control {
+            request = request.header(header::CACHE_CONTROL, cache_control);
+        }
+        if let Some(delete_at) = self.delete_at {
+            request = request.header(DELETE_AT_METADATA_KEY, delete_at.to_rfc3339());
+        }
+        request
+    }
+}
+
+/// Metadata of an object, as reported in the headers of a HEAD or GET
+#[derive(Debug, Clone)]
+pub struct ObjectHead {
+    /// Size of the object, or of the returned range for a ranged GET
+    pub content_length: Option<u64>,
+    pub content_type: Option<String>,
+    pub etag: Option<String>,
+    pub last_modified: Option<DateTime<Utc>>,
+    pub cache_control: Option<String>,
+    /// Whether the server serves byte ranges of the object
+    pub accepts_ranges: bool,
+}
+
+impl ObjectHead {
+    fn from_response(response: &Response) -> Self {
+        let value = |name: header::HeaderName| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+
+        ObjectHead {
+            content_length: value(header::CONTENT_LENGTH).and_then(|v| v.parse().ok()),
+            content_type: value(header::CONTENT_TYPE),
+            etag: value(header::ETAG),
+            last_modified: value(header::LAST_MODIFIED)
+                .and_then(|v| DateTime::parse_from_rfc2822(&v).ok())
+                .map(|v| v.with_timezone(&Utc)),
+            cache_control: value(header::CACHE_CONTROL),
+            accepts_ranges: value(header::ACCEPT_RANGES)
+                .is_some_and(|v| v.eq_ignore_ascii_case("bytes")),
+        }
+    }
+}
+
+/// Chunks of object data as they arrive
+#[cfg(not(target_arch = "wasm32"))]
+pub type ByteStream = futures::stream::BoxStream<'static, Result<Bytes, ClientError>>;
+
+/// Chunks of object data as they arrive; browser streams cannot leave their
+/// thread
+#[cfg(target_arch = "wasm32")]
+pub type ByteStream = futures::stream::LocalBoxStream<'static, Result<Bytes, ClientError>>;
+
+/// Object data being streamed from the server
+pub struct ObjectDownload {
+    pub head: ObjectHead,
+    body: ByteStream,
+}
+
+impl std::fmt::Debug for ObjectDownload {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ObjectDownload")
+            .field("head", &self.head)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ObjectDownload {
+    pub(super) fn from_response(response: Response) -> Self {
+        let head = ObjectHead::from_response(&response);
+        let body = response.bytes_stream().map_err(ClientError::from);
+        ObjectDownload {
+            head,
+            #[cfg(not(target_arch = "wasm32"))]
+            body: body.boxed(),
+            #[cfg(target_arch = "wasm32")]
+            body: body.boxed_local(),
+        }
+    }
+
+    /// The data as a stream of chunks
+    pub fn into_stream(self) -> ByteStream {
+        self.body
+    }
+
+    /// Collect all of the data in memory
+    pub async fn bytes(mut self) -> Result<Bytes, ClientError> {
+        let mut data = BytesMut::with_capacity(self.head.content_length.unwrap_or(0) as usize);
+        while let Some(chunk) = self.body.next().await {
+            data.extend_from_slice(&chunk?);
+        }
+        Ok(data.freeze())
+    }
+
+    /// Write the data to `writer` as it arrives, returning the bytes written
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn write_to<W: AsyncWrite + Unpin>(
+        mut self,
+        writer: &mut W,
+    ) -> Result<u64, ClientError> {
+        let mut written = 0;
+        while let Some(chunk) = self.body.next().await {
+            let chunk = chunk?;
+            writer.write_all(&chunk).await?;
+            written += chunk.len() as u64;
+        }
+        writer.flush().await?;
+        Ok(written)
+    }
+}
+
+impl Client {
+    /// URL of an object, with the key escaped as a single path segment
+    fn object_url(&self, key: &str) -> reqwest::Url {
+        self.url(&["objects", key])
+    }
+
+    /// Upload an object held in memory
+    ///
+    /// The upload is retried on transient errors.
+    pub async fn put_object(
+        &self,
+        key: &str,
+        data: impl Into<Bytes>,
+        options: PutObjectOptions,
+    ) -> Result<(), ClientError> {
+        let request = self
+            .request(Method::PUT, self.object_url(key))
+            .body(data.into());
+        self.send(options.apply(request)).await?;
+        Ok(())
+    }
+
+    /// Upload an object from a stream of chunks without buffering it
+    ///
+    /// A streamed body cannot be replayed, so the upload is not retried.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn put_object_stream<S, E>(
+        &self,
+        key: &str,
+        data: S,
+        options: PutObjectOptions,
+    ) -> Result<(), ClientError>
+    where
+        S: Stream<Item = Result<Bytes, E>> + Send + 'static,
+        E: Into<Box<dyn std::error::Error + Send + Sync>> + 'static,
+    {
+        let request = self
+            .request(Method::PUT, self.object_url(key))
+            .body(Body::wrap_stream(data));
+        self.send(options.apply(request)).await?;
+        Ok(())
+    }
+
+    /// Start downloading an object
+    pub async fn get_object(&self, key: &str) -> Result<ObjectDownload, ClientError> {
+        let response = self
+            .send(self.request(Method::GET, self.object_url(key)))
+            .await?;
+        Ok(ObjectDownload::from_response(response))
+    }
+
+    /// Download the bytes of an object within `range`
+    ///
+    /// Fails if the server answers with anything but exactly that range.
+    pub async fn get_object_range(
+        &self,
+        key: &str,
+        range: Range<u64>,
+    ) -> Result<Bytes, ClientError> {
+        self.fetch_range(key, range, None).await
+    }
+
+    /// Download the bytes of an object within `range`, provided the object
+    /// still has the ETag `etag`
+    ///
+    /// Once the object has been replaced the server answers with 412, so
+    /// ranges of different versions are never mixed.
+    pub async fn get_object_range_if_match(
+        &self,
+        key: &str,
+        range: Range<u64>,
+        etag: &str,
+    ) -> Result<Bytes, ClientError> {
+        self.fetch_range(key, range, Some(etag)).await
+    }
+
+    async fn fetch_range(
+        &self,
+        key: &str,
+        range: Range<u64>,
+        if_match: Option<&str>,
+    ) -> Result<Bytes, ClientError> {
+        if range.is_empty() {
+            return Ok(Bytes::new());
+        }
+
+        let mut request = self.request(Method::GET, self.object_url(key)).header(
+            header::RANGE,
+            format!("bytes={}-{}", range.start, range.end - 1),
+        );
+        if let Some(etag) = if_match {
+            request = request.header(header::IF_MATCH, etag);
+        }
+        let response = self.send(request).await?;
+        if response.status() != StatusCode::PARTIAL_CONTENT {
+            return Err(ClientError::InvalidResponse(format!(
+                "Server ignored range request for bytes {:?}",
+                range
+            )));
+        }
+
+        let data = response.bytes().await?;
+        if data.len() as u64 != range.end - range.start {
+            return Err(ClientError::InvalidResponse(format!(
+                "Expected {} bytes for range {:?}, received {}",
+                range.end - range.start,
+                range,
+                data.len()
+            )));
+        }
+        Ok(data)
+    }
+
+    /// Look up the metadata of an object without reading its data
+    pub async fn head_object(&self, key: &str) -> Result<ObjectHead, ClientError> {
+        let response = self
+            .send(self.request(Method::HEAD, self.object_url(key)))
+            .await?;
+        Ok(ObjectHead::from_response(&response))
+    }
+
+    pub async fn delete_object(&self, key: &str) -> Result<(), ClientError> {
+        self.send(self.request(Method::DELETE, self.object_url(key)))
+            .await?;
+        Ok(())
+    }
+
+    /// List objects whose keys start with `prefix`, at most `max_results`
+    pub async fn list_objects(
+        &self,
+        prefix: Option<&str>,
+        max_results: Option<usize>,
+    ) -> Result<ListObjectsResponseDto, ClientError> {
+        let url = with_query(
+            self.url(&["objects"]),
+            &[
+                ("prefix", prefix.map(str::to_string)),
+                ("max_results", max_results.map(|max| max.to_string())),
+            ],
+        );
+        self.send_json(self.request(Method::GET, url)).await
+    }
+
+    /// Create a presigned URL for reading, writing or deleting an object
+    /// without credentials
+    pub async fn presign_object(
+        &self,
+        key: &str,
+        request: &PresignRequestDto,
+    ) -> Result<PresignedUrlResponseDto, ClientError> {
+        let url = self.url(&["objects", key, "presign"]);
+        self.send_json(self.request(Method::POST, url).json(request))
+            .await
+    }
+}
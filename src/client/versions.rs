@@ -0,0 +1,153 @@
+use bytes::Bytes;
+use reqwest::{Method, header};
+use serde::Deserialize;
+
+use super::{Client, ClientError, ObjectDownload, with_query};
+use crate::adapters::inbound::http::dto::{ListVersionsResponseDto, VersionLabelsDto};
+
+/// A version created by an upload
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreatedVersion {
+    pub key: String,
+    pub version_id: String,
+}
+
+/// The version made current again by a restore or rollback
+#[derive(Debug, Clone, Deserialize)]
+pub struct RestoredVersion {
+    pub key: String,
+    /// Earlier version whose contents were copied
+    pub restored_version_id: String,
+    /// Version holding the copy, now the latest
+    pub new_version_id: String,
+}
+
+/// Which versions of an object to list
+#[derive(Debug, Clone, Default)]
+pub struct ListVersionsOptions {
+    /// Versions per page, capped by the server
+    pub max_keys: Option<usize>,
+
+    /// Continue after this version, the `next_version_id_marker` of the
+    /// previous page
+    pub version_id_marker: Option<String>,
+
+    /// Only list versions carrying this label
+    pub label: Option<String>,
+}
+
+impl Client {
+    fn version_url(&self, key: &str, version_id: &str) -> reqwest::Url {
+        self.url(&["versioned-objects", key, "versions", version_id])
+    }
+
+    /// Upload a new version of an object
+    pub async fn put_versioned_object(
+        &self,
+        key: &str,
+        data: impl Into<Bytes>,
+        content_type: Option<&str>,
+    ) -> Result<CreatedVersion, ClientError> {
+        let mut request = self
+            .request(Method::PUT, self.url(&["versioned-objects", key]))
+            .body(data.into());
+        if let Some(content_type) = content_type {
+            request = request.header(header::CONTENT_TYPE, content_type);
+        }
+        self.send_json(request).await
+    }
+
+    /// Start downloading the latest version of an object
+    pub async fn get_latest_version(&self, key: &str) -> Result<ObjectDownload, ClientError> {
+        let url = self.url(&["versioned-objects", key, "latest"]);
+        let response = self.send(self.request(Method::GET, url)).await?;
+        Ok(ObjectDownload::from_response(response))
+    }
+
+    /// Start downloading a specific version of an object
+    pub async fn get_version(
+        &self,
+        key: &str,
+        version_id: &str,
+    ) -> Result<ObjectDownload, ClientError> {
+        let response = self
+            .send(self.request(Method::GET, self.version_url(key, version_id)))
+            .await?;
+        Ok(ObjectDownload::from_response(response))
+    }
+
+    /// List one page of the versions of an object, newest first
+    pub async fn list_versions(
+        &self,
+        key: &str,
+        options: &ListVersionsOptions,
+    ) -> Result<ListVersionsResponseDto, ClientError> {
+        let url = with_query(
+            self.url(&["versioned-objects", key, "versions"]),
+            &[
+                ("max_keys", options.max_keys.map(|max| max.to_string())),
+                ("version_id_marker", options.version_id_marker.clone()),
+                ("label", options.label.clone()),
+            ],
+        );
+        self.send_json(self.request(Method::GET, url)).await
+    }
+
+    pub async fn delete_version(&self, key: &str, version_id: &str) -> Result<(), ClientError> {
+        self.send(self.request(Method::DELETE, self.version_url(key, version_id)))
+            .await?;
+        Ok(())
+    }
+
+    /// Make an earlier version current again by copying it as the latest
+    pub async fn restore_version(
+        &self,
+        key: &str,
+        version_id: &str,
+    ) -> Result<RestoredVersion, ClientError> {
+        let url = self.url(&["versioned-objects", key, "versions", version_id, "restore"]);
+        self.send_json(self.request(Method::POST, url)).await
+    }
+
+    /// Make the version `steps` versions back current again
+    ///
+    /// `key` is relative to the bucket.
+    pub async fn rollback_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        steps: usize,
+    ) -> Result<RestoredVersion, ClientError> {
+        let url = with_query(
+            self.url(&["buckets", bucket, "objects", key, "rollback"]),
+            &[("steps", Some(steps.to_string()))],
+        );
+        self.send_json(self.request(Method::POST, url)).await
+    }
+
+    /// Pin or unpin a version; a pinned version cannot be deleted
+    pub async fn set_version_pinned(
+        &self,
+        key: &str,
+        version_id: &str,
+        pinned: bool,
+    ) -> Result<(), ClientError> {
+        let url = self.url(&["versioned-objects", key, "versions", version_id, "pin"]);
+        let method = if pinned { Method::PUT } else { Method::DELETE };
+        self.send(self.request(method, url)).await?;
+        Ok(())
+    }
+
+    /// Replace the labels and annotations of a version
+    pub async fn label_version(
+        &self,
+        key: &str,
+        version_id: &str,
+        labels: &VersionLabelsDto,
+    ) -> Result<(), ClientError> {
+        let url = self.url(&["versioned-objects", key, "versions", version_id, "labels"]);
+        self.send(self.request(Method::PUT, url).json(labels))
+            .await?;
+        Ok(())
+    }
+}
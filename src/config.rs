@@ -0,0 +1,572 @@
+//! Application configuration read from environment variables
+//!
+//! [`EnvConfig::from_env`] reads and validates every variable below, and reports
+//! all problems at once rather than stopping at the first one.
+//!
+//! | Variable | Meaning |
+//! |---|---|
+//! | `APP_PROFILE` | `development` (default) or `production` |
+//! | `STORAGE_BACKEND` | `memory` (default), `s3`, `minio`, `azure`, `gcs` or `local` |
+//! | `S3_BUCKET`, `S3_REGION` | Bucket and region of the `s3` backend (required) |
+//! | `S3_ACCESS_KEY`, `S3_SECRET_KEY` | Credentials of the `s3` backend, set together |
+//! | `MINIO_ENDPOINT`, `MINIO_BUCKET` | Endpoint and bucket of the `minio` backend (required) |
+//! | `MINIO_ACCESS_KEY`, `MINIO_SECRET_KEY` | Credentials of the `minio` backend (required) |
+//! | `MINIO_USE_SSL` | Connect to MinIO over TLS (`true`/`false`) |
+//! | `AZURE_STORAGE_ACCOUNT`, `AZURE_CONTAINER` | Account and container of the `azure` backend (required) |
+//! | `AZURE_STORAGE_ACCESS_KEY` | Access key of the `azure` backend |
+//! | `GCS_BUCKET` | Bucket of the `gcs` backend (required) |
+//! | `GOOGLE_APPLICATION_CREDENTIALS` | Service account key file of the `gcs` backend |
+//! | `LOCAL_STORAGE_ROOT` | Directory of the `local` backend (required) |
+//! | `ENSURE_BUCKET` | Create the S3/MinIO bucket at startup (`true`/`false`) |
+//! | `ENSURE_BUCKET_VERSIONING`, `ENSURE_BUCKET_OBJECT_LOCK` | Options for that bucket |
+//! | `NATIVE_VERSIONING` | Keep versions with the S3/MinIO bucket's own versioning (`true`/`false`) |
+//! | `REPOSITORY_BACKEND` | `memory` (default) or `database` |
+//! | `DATABASE_URL` | PostgreSQL connection string of the `database` repository (required) |
+//! | `DATABASE_READ_URLS` | Comma-separated connection strings of read replicas for that repository |
+//! | `LIFECYCLE_SCHEDULER_ENABLED` | Run lifecycle processing in the background (`true`/`false`) |
+//! | `LIFECYCLE_INTERVAL_SECS` | Seconds between lifecycle runs, dividing a minute, an hour or a day evenly (default 3600) |
+//! | `NOTIFICATION_WEBHOOKS` | Comma-separated `http(s)` URLs notified of object and worker events |
+//! | `VERSION_ID_SCHEME` | `uuid` (default), `uuidv7`, `ulid` or `backend` |
+//!
+//! The `production` profile additionally requires a persistent storage backend
+//! and the `database` repository.
+//!
+//! The server doesn't terminate TLS or authenticate clients itself; put it
+//! behind a proxy that does.
+
+use reqwest::Url;
+use std::time::Duration;
+
+use crate::app::{AppConfig, AppError, EnsureBucket, RepositoryBackend, StorageBackend};
+use crate::ports::runtime::VersionIdScheme;
+
+/// Default time between background lifecycle runs
+pub const DEFAULT_LIFECYCLE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Set of defaults and requirements the configuration is checked against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Profile {
+    /// In-memory backends are allowed and nothing beyond the backends is required
+    #[default]
+    Development,
+    /// Data must be persisted
+    Production,
+}
+
+/// Schedule of the background lifecycle processing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LifecycleSchedulerConfig {
+    pub interval: Duration,
+    /// Cron expression firing every `interval`, as
+    /// [`AppBuilder::with_lifecycle_schedule`](crate::app::AppBuilder::with_lifecycle_schedule)
+    /// takes it
+    pub schedule: String,
+}
+
+/// Cron expression firing every `secs` seconds, for intervals that divide a
+/// minute, an hour or a day evenly
+fn interval_schedule(secs: u64) -> Option<String> {
+    let every = |unit: u64, range: u64| {
+        (secs.is_multiple_of(unit) && range.is_multiple_of(secs)).then(|| secs / unit)
+    };
+    if secs < 60 {
+        every(1, 60).map(|step| format!("*/{} * * * * *", step))
+    } else if secs < 3600 {
+        every(60, 3600).map(|step| format!("0 */{} * * * *", step))
+    } else if secs < 86400 {
+        every(3600, 86400).map(|step| format!("0 0 */{} * * *", step))
+    } else {
+        (secs == 86400).then(|| "0 0 0 * * *".to_string())
+    }
+}
+
+/// Webhook notified of object and worker events
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotificationTarget {
+    pub url: Url,
+}
+
+/// Everything configurable through the environment
+#[derive(Debug, Clone)]
+pub struct EnvConfig {
+    pub profile: Profile,
+    pub app: AppConfig,
+    pub lifecycle_scheduler: Option<LifecycleSchedulerConfig>,
+    pub notification_targets: Vec<NotificationTarget>,
+    pub version_id_scheme: VersionIdScheme,
+}
+
+impl EnvConfig {
+    /// Read the configuration from the process environment
+    pub fn from_env() -> Result<Self, AppError> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Read the configuration from variables looked up by `var`
+    ///
+    /// Empty values count as unset. Every missing, malformed or inconsistent
+    /// variable is reported in one [`AppError::InvalidEnvironment`].
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, AppError> {
+        let mut env = EnvReader {
+            var: &|name: &str| var(name).filter(|value| !value.trim().is_empty()),
+            problems: Vec::new(),
+        };
+
+        let profile = match env.get("APP_PROFILE").as_deref() {
+            None | Some("development") => Profile::Development,
+            Some("production") => Profile::Production,
+            Some(other) => {
+                env.problem(format!(
+                    "APP_PROFILE must be development or production, got '{}'",
+                    other
+                ));
+                Profile::Development
+            }
+        };
+
+        let storage_backend = env.storage_backend();
+        let repository_backend = env.repository_backend();
+        let ensure_bucket = env.ensure_bucket(&storage_backend);
+        let native_versioning = env.native_versioning(&storage_backend);
+        let lifecycle_scheduler = env.lifecycle_scheduler();
+        let notification_targets = env.notification_targets();
+        let version_id_scheme = env.version_id_scheme();
+
+        if profile == Profile::Production {
+            if matches!(storage_backend, Some(StorageBackend::InMemory)) {
+                env.problem(
+                    "STORAGE_BACKEND must be a persistent backend in production, not memory"
+                        .to_string(),
+                );
+            }
+            if matches!(repository_backend, Some(RepositoryBackend::InMemory)) {
+                env.problem("REPOSITORY_BACKEND must be database in production".to_string());
+            }
+        }
+
+        match (storage_backend, repository_backend) {
+            (Some(storage_backend), Some(repository_backend)) if env.problems.is_empty() => {
+                Ok(EnvConfig {
+                    profile,
+                    app: AppConfig {
+                        storage_backend,
+                        repository_backend,
+                        ensure_bucket,
+                        native_versioning,
+                    },
+                    lifecycle_scheduler,
+                    notification_targets,
+                    version_id_scheme,
+                })
+            }
+            _ => Err(AppError::InvalidEnvironment {
+                problems: env.problems,
+            }),
+        }
+    }
+}
+
+/// Reads variables and collects the problems found with them
+struct EnvReader<'a> {
+    var: &'a dyn Fn(&str) -> Option<String>,
+    problems: Vec<String>,
+}
+
+impl EnvReader<'_> {
+    fn get(&self, name: &str) -> Option<String> {
+        (self.var)(name)
+    }
+
+    fn problem(&mut self, problem: String) {
+        self.problems.push(problem);
+    }
+
+    /// Variable that must be set, recording a problem naming `purpose` if it is not
+    fn required(&mut self, name: &str, purpose: &str) -> Option<String> {
+        let value = self.get(name);
+        if value.is_none() {
+            self.problem(format!("{} is required {}", name, purpose));
+        }
+        value
+    }
+
+    fn flag(&mut self, name: &str) -> bool {
+        match self.get(name).map(|value| value.to_lowercase()).as_deref() {
+            None | Some("false") | Some("0") => false,
+            Some("true") | Some("1") => true,
+            Some(other) => {
+                self.problem(format!("{} must be true or false, got '{}'", name, other));
+                false
+            }
+        }
+    }
+
+    /// Both variables of a pair, or neither
+    fn pair(&mut self, first: &str, second: &str) -> Option<(String, String)> {
+        match (self.get(first), self.get(second)) {
+            (Some(a), Some(b)) => Some((a, b)),
+            (None, None) => None,
+            (Some(_), None) => {
+                self.problem(format!("{} is set but {} is not; set both", first, second));
+                None
+            }
+            (None, Some(_)) => {
+                self.problem(format!("{} is set but {} is not; set both", second, first));
+                None
+            }
+        }
+    }
+
+    fn storage_backend(&mut self) -> Option<StorageBackend> {
+        let backend = self.get("STORAGE_BACKEND");
+        match backend.as_deref() {
+            None | Some("memory") => Some(StorageBackend::InMemory),
+            Some("s3") => {
+                let purpose = "for the s3 storage backend";
+                let bucket = self.required("S3_BUCKET", purpose);
+                let region = self.required("S3_REGION", purpose);
+                let credentials = self.pair("S3_ACCESS_KEY", "S3_SECRET_KEY");
+                let (access_key, secret_key) = credentials.unzip();
+                Some(StorageBackend::S3 {
+                    bucket: bucket?,
+                    region: region?,
+                    access_key,
+                    secret_key,
+                })
+            }
+            Some("minio") => {
+                let purpose = "for the minio storage backend";
+                let endpoint = self.required("MINIO_ENDPOINT", purpose);
+                let bucket = self.required("MINIO_BUCKET", purpose);
+                let access_key = self.required("MINIO_ACCESS_KEY", purpose);
+                let secret_key = self.required("MINIO_SECRET_KEY", purpose);
+                let use_ssl = self.flag("MINIO_USE_SSL");
+                Some(StorageBackend::MinIO {
+                    endpoint: endpoint?,
+                    bucket: bucket?,
+                    access_key: access_key?,
+                    secret_key: secret_key?,
+                    use_ssl,
+                })
+            }
+            Some("azure") => {
+                let purpose = "for the azure storage backend";
+                let account = self.required("AZURE_STORAGE_ACCOUNT", purpose);
+                let container = self.required("AZURE_CONTAINER", purpose);
+                Some(StorageBackend::Azure {
+                    account: account?,
+                    container: container?,
+                    access_key: self.get("AZURE_STORAGE_ACCESS_KEY"),
+                })
+            }
+            Some("gcs") => {
+                let bucket = self.required("GCS_BUCKET", "for the gcs storage backend");
+                Some(StorageBackend::Gcs {
+                    bucket: bucket?,
+                    service_account_path: self.get("GOOGLE_APPLICATION_CREDENTIALS"),
+                })
+            }
+            Some("local") => {
+                let root = self.required("LOCAL_STORAGE_ROOT", "for the local storage backend");
+                Some(StorageBackend::LocalFs { root: root?.into() })
+            }
+            Some(other) => {
+                self.problem(format!(
+                    "STORAGE_BACKEND must be one of memory, s3, minio, azure, gcs or local, got '{}'",
+                    other
+                ));
+                None
+            }
+        }
+    }
+
+    fn repository_backend(&mut self) -> Option<RepositoryBackend> {
+        match self.get("REPOSITORY_BACKEND").as_deref() {
+            None | Some("memory") => Some(RepositoryBackend::InMemory),
+            Some("database") => {
+                let connection_string =
+                    self.required("DATABASE_URL", "for the database repository backend")?;
+                let read_replicas = self
+                    .get("DATABASE_READ_URLS")
+                    .iter()
+                    .flat_map(|urls| urls.split(','))
+                    .map(str::trim)
+                    .filter(|url| !url.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                Some(RepositoryBackend::Database {
+                    connection_string,
+                    read_replicas,
+                })
+            }
+            Some(other) => {
+                self.problem(format!(
+                    "REPOSITORY_BACKEND must be memory or database, got '{}'",
+                    other
+                ));
+                None
+            }
+        }
+    }
+
+    fn ensure_bucket(&mut self, storage_backend: &Option<StorageBackend>) -> Option<EnsureBucket> {
+        let versioning_enabled = self.flag("ENSURE_BUCKET_VERSIONING");
+        let object_lock_enabled = self.flag("ENSURE_BUCKET_OBJECT_LOCK");
+        if !self.flag("ENSURE_BUCKET") {
+            return None;
+        }
+
+        match storage_backend {
+            Some(StorageBackend::S3 { .. }) | Some(StorageBackend::MinIO { .. }) | None => {}
+            Some(_) => self.problem(
+                "ENSURE_BUCKET is only supported with the s3 and minio storage backends"
+                    .to_string(),
+            ),
+        }
+        Some(EnsureBucket {
+            versioning_enabled,
+            object_lock_enabled,
+        })
+    }
+
+    fn native_versioning(&mut self, storage_backend: &Option<StorageBackend>) -> bool {
+        if !self.flag("NATIVE_VERSIONING") {
+            return false;
+        }
+
+        match storage_backend {
+            Some(StorageBackend::S3 { .. }) | Some(StorageBackend::MinIO { .. }) | None => {}
+            Some(_) => self.problem(
+                "NATIVE_VERSIONING is only supported with the s3 and minio storage backends"
+                    .to_string(),
+            ),
+        }
+        true
+    }
+
+    fn lifecycle_scheduler(&mut self) -> Option<LifecycleSchedulerConfig> {
+        let enabled = self.flag("LIFECYCLE_SCHEDULER_ENABLED");
+        let interval = match self.get("LIFECYCLE_INTERVAL_SECS") {
+            None => DEFAULT_LIFECYCLE_INTERVAL,
+            Some(value) => match value.trim().parse::<u64>() {
+                Ok(secs) if secs > 0 => Duration::from_secs(secs),
+                _ => {
+                    self.problem(format!(
+                        "LIFECYCLE_INTERVAL_SECS must be a positive number of seconds, got '{}'",
+                        value
+                    ));
+                    return None;
+                }
+            },
+        };
+        let Some(schedule) = interval_schedule(interval.as_secs()) else {
+            self.problem(format!(
+                "LIFECYCLE_INTERVAL_SECS must divide a minute, an hour or a day evenly, got {}",
+                interval.as_secs()
+            ));
+            return None;
+        };
+        enabled.then_some(LifecycleSchedulerConfig { interval, schedule })
+    }
+
+    fn version_id_scheme(&mut self) -> VersionIdScheme {
+        let Some(value) = self.get("VERSION_ID_SCHEME") else {
+            return VersionIdScheme::default();
+        };
+        value.trim().parse().unwrap_or_else(|_| {
+            self.problem(format!(
+                "VERSION_ID_SCHEME must be uuid, uuidv7, ulid or backend, got '{}'",
+                value
+            ));
+            VersionIdScheme::default()
+        })
+    }
+
+    fn notification_targets(&mut self) -> Vec<NotificationTarget> {
+        let Some(value) = self.get("NOTIFICATION_WEBHOOKS") else {
+            return Vec::new();
+        };
+
+        let mut targets = Vec::new();
+        for entry in value
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            match Url::parse(entry) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => {
+                    targets.push(NotificationTarget { url })
+                }
+                _ => self.problem(format!(
+                    "NOTIFICATION_WEBHOOKS entry '{}' is not an http(s) URL",
+                    entry
+                )),
+            }
+        }
+        targets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn from_vars(vars: &[(&str, &str)]) -> Result<EnvConfig, AppError> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        EnvConfig::from_vars(|name| vars.get(name).cloned())
+    }
+
+    fn problems(result: Result<EnvConfig, AppError>) -> Vec<String> {
+        match result {
+            Err(AppError::InvalidEnvironment { problems }) => problems,
+            other => panic!(
+                "Expected invalid environment, got {:?}",
+                other.map(|c| c.app)
+            ),
+        }
+    }
+
+    #[test]
+    fn test_defaults() {
+        let config = from_vars(&[]).unwrap();
+        assert_eq!(config.profile, Profile::Development);
+        assert!(matches!(
+            config.app.storage_backend,
+            StorageBackend::InMemory
+        ));
+        assert!(matches!(
+            config.app.repository_backend,
+            RepositoryBackend::InMemory
+        ));
+        assert!(!config.app.native_versioning);
+        assert!(config.lifecycle_scheduler.is_none());
+        assert!(config.notification_targets.is_empty());
+        assert_eq!(config.version_id_scheme, VersionIdScheme::Uuid);
+    }
+
+    #[test]
+    fn test_full_configuration() {
+        let config = from_vars(&[
+            ("STORAGE_BACKEND", "minio"),
+            ("MINIO_ENDPOINT", "http://localhost:9000"),
+            ("MINIO_BUCKET", "objects"),
+            ("MINIO_ACCESS_KEY", "minioadmin"),
+            ("MINIO_SECRET_KEY", "minioadmin"),
+            ("MINIO_USE_SSL", "false"),
+            ("ENSURE_BUCKET", "true"),
+            ("NATIVE_VERSIONING", "true"),
+            ("LIFECYCLE_SCHEDULER_ENABLED", "true"),
+            ("LIFECYCLE_INTERVAL_SECS", "600"),
+            (
+                "NOTIFICATION_WEBHOOKS",
+                "https://hooks.example.com/a, http://localhost:8080/b",
+            ),
+            ("VERSION_ID_SCHEME", "ulid"),
+        ])
+        .unwrap();
+
+        assert!(matches!(
+            config.app.storage_backend,
+            StorageBackend::MinIO { .. }
+        ));
+        assert!(config.app.ensure_bucket.is_some());
+        assert!(config.app.native_versioning);
+        let scheduler = config.lifecycle_scheduler.unwrap();
+        assert_eq!(scheduler.interval, Duration::from_secs(600));
+        assert_eq!(scheduler.schedule, "0 */10 * * * *");
+        assert_eq!(config.notification_targets.len(), 2);
+        assert_eq!(config.version_id_scheme, VersionIdScheme::Ulid);
+    }
+
+    #[test]
+    fn test_all_problems_are_reported() {
+        let problems = problems(from_vars(&[
+            ("STORAGE_BACKEND", "s3"),
+            ("S3_ACCESS_KEY", "key"),
+            ("REPOSITORY_BACKEND", "database"),
+            ("LIFECYCLE_INTERVAL_SECS", "soon"),
+            ("NOTIFICATION_WEBHOOKS", "ftp://example.com"),
+            ("VERSION_ID_SCHEME", "snowflake"),
+        ]));
+
+        assert_eq!(
+            problems,
+            vec![
+                "S3_BUCKET is required for the s3 storage backend",
+                "S3_REGION is required for the s3 storage backend",
+                "S3_ACCESS_KEY is set but S3_SECRET_KEY is not; set both",
+                "DATABASE_URL is required for the database repository backend",
+                "LIFECYCLE_INTERVAL_SECS must be a positive number of seconds, got 'soon'",
+                "NOTIFICATION_WEBHOOKS entry 'ftp://example.com' is not an http(s) URL",
+                "VERSION_ID_SCHEME must be uuid, uuidv7, ulid or backend, got 'snowflake'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_native_versioning_requires_s3_or_minio() {
+        let problems = problems(from_vars(&[
+            ("STORAGE_BACKEND", "local"),
+            ("LOCAL_STORAGE_ROOT", "/var/lib/objects"),
+            ("NATIVE_VERSIONING", "true"),
+        ]));
+        assert_eq!(
+            problems,
+            vec!["NATIVE_VERSIONING is only supported with the s3 and minio storage backends"]
+        );
+    }
+
+    #[test]
+    fn test_production_profile_requirements() {
+        let problems = problems(from_vars(&[("APP_PROFILE", "production")]));
+        assert_eq!(problems.len(), 2);
+
+        let config = from_vars(&[
+            ("APP_PROFILE", "production"),
+            ("STORAGE_BACKEND", "local"),
+            ("LOCAL_STORAGE_ROOT", "/var/lib/objects"),
+            ("REPOSITORY_BACKEND", "database"),
+            ("DATABASE_URL", "postgres://localhost/objects"),
+            (
+                "DATABASE_READ_URLS",
+                "postgres://replica-1/objects, postgres://replica-2/objects",
+            ),
+        ])
+        .unwrap();
+        assert_eq!(config.profile, Profile::Production);
+        assert!(matches!(
+            &config.app.repository_backend,
+            RepositoryBackend::Database { read_replicas, .. } if read_replicas.len() == 2
+        ));
+    }
+
+    #[test]
+    fn test_lifecycle_intervals_map_to_schedules() {
+        for (secs, schedule) in [
+            ("15", "*/15 * * * * *"),
+            ("60", "0 */1 * * * *"),
+            ("3600", "0 0 */1 * * *"),
+            ("21600", "0 0 */6 * * *"),
+        ] {
+            let config = from_vars(&[
+                ("LIFECYCLE_SCHEDULER_ENABLED", "true"),
+                ("LIFECYCLE_INTERVAL_SECS", secs),
+            ])
+            .unwrap();
+            assert_eq!(config.lifecycle_scheduler.unwrap().schedule, schedule);
+        }
+
+        let problems = problems(from_vars(&[
+            ("LIFECYCLE_SCHEDULER_ENABLED", "true"),
+            ("LIFECYCLE_INTERVAL_SECS", "7000"),
+        ]));
+        assert_eq!(
+            problems,
+            vec!["LIFECYCLE_INTERVAL_SECS must divide a minute, an hour or a day evenly, got 7000"]
+        );
+    }
+}
@@ -0,0 +1,148 @@
+//! Proptest strategies producing valid domain values
+//!
+//! Available with the `test-util` feature. Every value generated here passes
+//! the validation of its type, so downstream code can be fuzzed with inputs
+//! the server would accept. The types also implement [`Arbitrary`], making
+//! `any::<ObjectKey>()` and friends work.
+
+use chrono::{DateTime, TimeZone, Utc};
+use proptest::prelude::*;
+
+use super::models::{Filter, LifecycleRule, LifecycleStorageClass as StorageClass, RuleStatus};
+use super::value_objects::{BucketName, ObjectKey};
+
+/// Valid S3 bucket names: 3 to 63 lowercase letters, digits and single hyphens
+pub fn bucket_name() -> impl Strategy<Value = BucketName> {
+    "[a-z0-9][a-z0-9-]{1,61}[a-z0-9]"
+        .prop_filter_map("valid bucket name", |name| BucketName::new(name).ok())
+}
+
+/// Valid object keys of one to five `/`-separated segments
+///
+/// Segments may hold any character other than `/` and NUL, including non-ASCII.
+pub fn object_key() -> impl Strategy<Value = ObjectKey> {
+    prop::collection::vec("[^/\\x00]{1,32}", 1..=5)
+        .prop_filter_map("valid object key", |segments| {
+            ObjectKey::new(segments.join("/")).ok()
+        })
+}
+
+/// Filters with any combination of prefix, tags and size bounds
+///
+/// When both size bounds are present the lower one is below the upper one.
+pub fn filter() -> impl Strategy<Value = Filter> {
+    (
+        proptest::option::of("[a-z0-9_-]{1,12}(/[a-z0-9_-]{1,12}){0,2}/?"),
+        prop::collection::hash_map("[a-zA-Z0-9_-]{1,16}", "[a-zA-Z0-9_ -]{0,16}", 0..3),
+        proptest::option::of(0u64..1 << 40),
+        proptest::option::of(1u64..1 << 20),
+    )
+        .prop_map(|(prefix, tags, greater_than, span)| Filter {
+            prefix,
+            tags,
+            object_size_greater_than: greater_than,
+            object_size_less_than: span.map(|span| greater_than.unwrap_or(0) + span),
+        })
+}
+
+/// Enabled or disabled lifecycle rules with at least one action
+pub fn lifecycle_rule() -> impl Strategy<Value = LifecycleRule> {
+    let expiration = prop_oneof![
+        Just((None, None)),
+        (1u32..3650).prop_map(|days| (Some(days), None)),
+        midnight().prop_map(|date| (None, Some(date))),
+    ];
+    let transition = proptest::option::of((1u32..3650, storage_class()));
+    let noncurrent = proptest::option::of((1u32..3650, proptest::option::of(1u32..100)));
+    let abort_multipart = proptest::option::of(1u32..365);
+
+    (
+        "[a-zA-Z0-9_.-]{1,64}",
+        prop_oneof![Just(RuleStatus::Enabled), Just(RuleStatus::Disabled)],
+        filter(),
+        expiration,
+        transition,
+        noncurrent,
+        abort_multipart,
+    )
+        .prop_map(
+            |(id, status, filter, expiration, transition, noncurrent, abort_multipart)| {
+                LifecycleRule {
+                    id,
+                    status,
+                    filter,
+                    expiration_days: expiration.0,
+                    expiration_date: expiration.1,
+                    transition_days: transition.as_ref().map(|(days, _)| *days),
+                    transition_storage_class: transition.map(|(_, class)| class),
+                    noncurrent_version_expiration_noncurrent_days: noncurrent.map(|(days, _)| days),
+                    noncurrent_version_expiration_newer_versions: noncurrent
+                        .and_then(|(_, newer)| newer),
+                    abort_incomplete_multipart_upload_days_after_initiation: abort_multipart,
+                    ..LifecycleRule::default()
+                }
+            },
+        )
+        .prop_filter("rule has an action", |rule| rule.validate().is_ok())
+}
+
+/// Storage classes other than custom ones
+pub fn storage_class() -> impl Strategy<Value = StorageClass> {
+    prop_oneof![
+        Just(StorageClass::Standard),
+        Just(StorageClass::InfrequentAccess),
+        Just(StorageClass::Glacier),
+        Just(StorageClass::DeepArchive),
+    ]
+}
+
+/// Midnights UTC between 1970 and 2100, as S3 requires for rule dates
+fn midnight() -> impl Strategy<Value = DateTime<Utc>> {
+    (0i64..47_482).prop_map(|days| Utc.timestamp_opt(days * 86_400, 0).unwrap())
+}
+
+macro_rules! arbitrary_from {
+    ($($ty:ty => $strategy:ident),* $(,)?) => {
+        $(
+            impl Arbitrary for $ty {
+                type Parameters = ();
+                type Strategy = BoxedStrategy<Self>;
+
+                fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+                    $strategy().boxed()
+                }
+            }
+        )*
+    };
+}
+
+arbitrary_from! {
+    BucketName => bucket_name,
+    ObjectKey => object_key,
+    Filter => filter,
+    LifecycleRule => lifecycle_rule,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn test_generated_values_are_valid(
+            bucket in any::<BucketName>(),
+            key in any::<ObjectKey>(),
+            rule in any::<LifecycleRule>(),
+        ) {
+            prop_assert!(BucketName::new(bucket.as_str().to_string()).is_ok());
+            prop_assert!(ObjectKey::new(key.as_str().to_string()).is_ok());
+            prop_assert!(rule.validate().is_ok());
+            if let (Some(low), Some(high)) = (
+                rule.filter.object_size_greater_than,
+                rule.filter.object_size_less_than,
+            ) {
+                prop_assert!(low < high);
+            }
+        }
+    }
+}
@@ -47,6 +47,12 @@ pub enum LifecycleError {
 
     /// Action execution failed
     ActionExecutionFailed { action: String, reason: String },
+
+    /// Lifecycle processing of the bucket is already underway
+    ProcessingInProgress { bucket: BucketName },
+
+    /// Lifecycle processing of the bucket is not underway
+    ProcessingNotRunning { bucket: BucketName },
 }
 
 impl std::fmt::Display for LifecycleError {
@@ -117,6 +123,16 @@ impl std::fmt::Display for LifecycleError {
             LifecycleError::ActionExecutionFailed { action, reason } => {
                 write!(f, "Action '{}' execution failed: {}", action, reason)
             }
+            LifecycleError::ProcessingInProgress { bucket } => {
+                write!(
+                    f,
+                    "Lifecycle processing already running for bucket: {}",
+                    bucket
+                )
+            }
+            LifecycleError::ProcessingNotRunning { bucket } => {
+                write!(f, "Lifecycle processing not running for bucket: {}", bucket)
+            }
         }
     }
 }
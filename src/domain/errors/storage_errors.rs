@@ -1,4 +1,10 @@
-use crate::domain::value_objects::{ObjectKey, VersionId};
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+
+use crate::domain::{
+    models::QuotaKind,
+    value_objects::{ObjectKey, VersionId},
+};
 
 /// Errors that can occur during storage operations
 #[derive(Debug, Clone)]
@@ -12,6 +18,37 @@ pub enum StorageError {
         version_id: VersionId,
     },
 
+    /// Object is past its expiration but the lifecycle rules have not yet been applied
+    ObjectExpired {
+        key: ObjectKey,
+        rule_id: String,
+        expired_at: DateTime<Utc>,
+    },
+
+    /// Multipart upload not found, or not in progress for the object
+    UploadNotFound { key: ObjectKey, upload_id: String },
+
+    /// A part listed to complete a multipart upload was not uploaded, or was
+    /// uploaded with another ETag
+    InvalidPart {
+        upload_id: String,
+        part_number: u32,
+        etag: String,
+    },
+
+    /// The parts listed to complete a multipart upload are not in ascending
+    /// part number order
+    InvalidPartOrder { upload_id: String },
+
+    /// Transaction not found, or already finished
+    TransactionNotFound { transaction_id: String },
+
+    /// No manifest has been published for the dataset
+    DatasetNotFound { name: String },
+
+    /// Backup job or run not found
+    BackupNotFound { id: String },
+
     /// Version conflict during concurrent operations
     VersionConflict {
         key: ObjectKey,
@@ -22,6 +59,14 @@ pub enum StorageError {
     /// Storage quota exceeded
     QuotaExceeded { used: u64, limit: u64 },
 
+    /// A write would take a key prefix beyond one of its quota limits
+    PrefixQuotaExceeded {
+        prefix: String,
+        kind: QuotaKind,
+        used: u64,
+        limit: u64,
+    },
+
     /// Invalid object size
     InvalidObjectSize {
         size: u64,
@@ -58,6 +103,12 @@ pub enum StorageError {
 
     /// Storage backend error
     StorageBackendError { message: String },
+
+    /// The storage backend did not answer within the operation's timeout
+    Timeout {
+        operation: String,
+        timeout: Duration,
+    },
 }
 
 impl std::fmt::Display for StorageError {
@@ -69,6 +120,47 @@ impl std::fmt::Display for StorageError {
             StorageError::VersionNotFound { key, version_id } => {
                 write!(f, "Version '{}' not found for object: {}", version_id, key)
             }
+            StorageError::ObjectExpired {
+                key,
+                rule_id,
+                expired_at,
+            } => {
+                write!(
+                    f,
+                    "Object expired at {} by rule '{}': {}",
+                    expired_at, rule_id, key
+                )
+            }
+            StorageError::UploadNotFound { key, upload_id } => {
+                write!(
+                    f,
+                    "Multipart upload '{}' not found for object: {}",
+                    upload_id, key
+                )
+            }
+            StorageError::InvalidPart {
+                upload_id,
+                part_number,
+                etag,
+            } => {
+                write!(
+                    f,
+                    "Part {} of multipart upload '{}' was not uploaded with ETag {}",
+                    part_number, upload_id, etag
+                )
+            }
+            StorageError::InvalidPartOrder { upload_id } => {
+                write!(
+                    f,
+                    "Parts of multipart upload '{}' must be listed in ascending part number order",
+                    upload_id
+                )
+            }
+            StorageError::TransactionNotFound { transaction_id } => {
+                write!(f, "Transaction not found: {}", transaction_id)
+            }
+            StorageError::DatasetNotFound { name } => write!(f, "Dataset not found: {}", name),
+            StorageError::BackupNotFound { id } => write!(f, "Backup not found: {}", id),
             StorageError::VersionConflict {
                 key,
                 expected_version,
@@ -87,6 +179,18 @@ impl std::fmt::Display for StorageError {
                     used, limit
                 )
             }
+            StorageError::PrefixQuotaExceeded {
+                prefix,
+                kind,
+                used,
+                limit,
+            } => {
+                write!(
+                    f,
+                    "Quota for prefix '{}' exceeded: {} {} of {} allowed",
+                    prefix, used, kind, limit
+                )
+            }
             StorageError::InvalidObjectSize { size, min, max } => {
                 let mut msg = format!("Invalid object size: {} bytes", size);
                 if let Some(min) = min {
@@ -128,6 +232,13 @@ impl std::fmt::Display for StorageError {
             StorageError::StorageBackendError { message } => {
                 write!(f, "Storage backend error: {}", message)
             }
+            StorageError::Timeout { operation, timeout } => {
+                write!(
+                    f,
+                    "Storage backend {} did not finish within {:?}",
+                    operation, timeout
+                )
+            }
         }
     }
 }
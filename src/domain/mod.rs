@@ -1,3 +1,5 @@
+#[cfg(feature = "test-util")]
+pub mod arbitrary;
 pub mod errors;
 pub mod models;
 pub mod value_objects;
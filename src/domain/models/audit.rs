@@ -0,0 +1,38 @@
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+use crate::domain::value_objects::ObjectKey;
+
+/// Audit action recorded when a corrupted object is restored from a replica
+pub const AUDIT_ACTION_OBJECT_REPAIRED: &str = "object.repaired";
+
+/// Audit action recorded when an upload is rejected for containing malware
+pub const AUDIT_ACTION_MALWARE_REJECTED: &str = "object.malware_rejected";
+
+/// Audit action recorded when the key index is brought back in line with the store
+pub const AUDIT_ACTION_INDEX_REPAIRED: &str = "index.repaired";
+
+/// An entry in the audit log describing an action taken on an object
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEvent {
+    pub timestamp: DateTime<Utc>,
+    pub action: String,
+    pub key: ObjectKey,
+    pub details: HashMap<String, String>,
+}
+
+impl AuditEvent {
+    pub fn new(action: &str, key: ObjectKey) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            action: action.to_string(),
+            key,
+            details: HashMap::new(),
+        }
+    }
+
+    pub fn with_detail(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.details.insert(name.to_string(), value.into());
+        self
+    }
+}
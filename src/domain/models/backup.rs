@@ -0,0 +1,181 @@
+use chrono::{DateTime, Utc};
+use percent_encoding::{AsciiSet, CONTROLS, percent_decode_str, utf8_percent_encode};
+
+use crate::domain::{
+    errors::ValidationError,
+    value_objects::{BucketName, ObjectKey},
+};
+
+/// Prefix in the backup target under which backups are written
+pub const BACKUP_PREFIX: &str = "backups";
+
+/// Characters escaped in the keys of a stored manifest, so that each entry
+/// stays on its own line
+const MANIFEST_KEY_ESCAPES: &AsciiSet = &CONTROLS.add(b'%');
+
+/// Which objects a backup run copies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackupMode {
+    /// Copy every current object
+    Full,
+    /// Copy only the objects whose current version is not in the previous
+    /// successful run
+    Incremental,
+}
+
+/// A recurring backup of a bucket to the backup target
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackupJob {
+    pub job_id: String,
+    pub bucket: BucketName,
+    /// Cron expression with a leading seconds field, evaluated in UTC
+    pub schedule: String,
+    pub mode: BackupMode,
+    pub created: DateTime<Utc>,
+    pub last_run: Option<DateTime<Utc>>,
+}
+
+/// Outcome of a backup run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackupRunState {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+/// One execution of a backup job
+///
+/// Every successful run leaves a manifest in the target listing all objects
+/// of the bucket at the time, so any run can be restored on its own, whether
+/// its objects were copied by it or by an earlier run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackupRun {
+    pub run_id: String,
+    pub job_id: String,
+    pub mode: BackupMode,
+    pub state: BackupRunState,
+    pub started: DateTime<Utc>,
+    pub finished: Option<DateTime<Utc>>,
+    pub objects_total: u64,
+    pub objects_copied: u64,
+    pub bytes_copied: u64,
+    pub error: Option<String>,
+}
+
+/// Counts of a restore from a backup run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BackupRestore {
+    pub objects_restored: u64,
+    /// Existing objects left in place
+    pub objects_skipped: u64,
+    pub bytes_restored: u64,
+}
+
+/// An object in a backup run's manifest
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackupEntry {
+    /// Key of the object, relative to the bucket
+    pub key: String,
+    pub size: u64,
+    /// Name of the copy in the target, derived from the key and version so
+    /// that unchanged objects share it across runs
+    pub blob: String,
+}
+
+impl BackupEntry {
+    /// Entry for `key` at `version`, an identifier of its current contents
+    pub fn new(key: &str, version: &str, size: u64) -> Self {
+        Self {
+            key: key.to_string(),
+            size,
+            blob: format!("{:x}", md5::compute(format!("{}\0{}", key, version))),
+        }
+    }
+}
+
+impl BackupRun {
+    /// Key in the target of the copy named `blob` made by job `job_id`
+    pub fn blob_key(job_id: &str, blob: &str) -> Result<ObjectKey, ValidationError> {
+        ObjectKey::new(format!("{}/{}/objects/{}", BACKUP_PREFIX, job_id, blob))
+    }
+
+    /// Key in the target of this run's manifest
+    pub fn manifest_key(&self) -> Result<ObjectKey, ValidationError> {
+        ObjectKey::new(format!(
+            "{}/{}/runs/{}.manifest",
+            BACKUP_PREFIX, self.job_id, self.run_id
+        ))
+    }
+
+    /// Stored form of a manifest: one `<blob> <size> <key>` line per entry
+    pub fn encode_manifest(entries: &[BackupEntry]) -> Vec<u8> {
+        entries
+            .iter()
+            .map(|entry| {
+                format!(
+                    "{} {} {}\n",
+                    entry.blob,
+                    entry.size,
+                    utf8_percent_encode(&entry.key, MANIFEST_KEY_ESCAPES)
+                )
+            })
+            .collect::<String>()
+            .into_bytes()
+    }
+
+    /// Entries of a stored manifest
+    pub fn decode_manifest(data: &[u8]) -> Result<Vec<BackupEntry>, ValidationError> {
+        let invalid = |line: &str| ValidationError::InvalidField {
+            field: "manifest".to_string(),
+            value: line.to_string(),
+            expected: "<blob> <size> <key>".to_string(),
+        };
+
+        String::from_utf8_lossy(data)
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let mut fields = line.splitn(3, ' ');
+                let (Some(blob), Some(size), Some(key)) =
+                    (fields.next(), fields.next(), fields.next())
+                else {
+                    return Err(invalid(line));
+                };
+                Ok(BackupEntry {
+                    key: percent_decode_str(key)
+                        .decode_utf8()
+                        .map_err(|_| invalid(line))?
+                        .into_owned(),
+                    size: size.parse().map_err(|_| invalid(line))?,
+                    blob: blob.to_string(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_round_trip_and_blob_names() {
+        let entries = vec![
+            BackupEntry::new("reports/q1 100%.csv", "v1", 10),
+            BackupEntry::new("odd\nname", "\"etag\"", 0),
+        ];
+        let decoded = BackupRun::decode_manifest(&BackupRun::encode_manifest(&entries)).unwrap();
+        assert_eq!(decoded, entries);
+        assert!(BackupRun::decode_manifest(b"blob-only").is_err());
+
+        // Blobs are shared by unchanged objects and differ between versions
+        assert_eq!(
+            BackupEntry::new("a", "v1", 1).blob,
+            BackupEntry::new("a", "v1", 1).blob
+        );
+        assert_ne!(
+            BackupEntry::new("a", "v1", 1).blob,
+            BackupEntry::new("a", "v2", 1).blob
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use chrono::{DateTime, Utc};
+use percent_encoding::{AsciiSet, CONTROLS, percent_decode_str, utf8_percent_encode};
+
+use crate::domain::{
+    errors::ValidationError,
+    value_objects::{ObjectKey, VersionId},
+};
+
+/// Prefix under which dataset manifests are stored, one versioned object per
+/// dataset
+pub const DATASET_MANIFEST_PREFIX: &str = ".datasets";
+
+/// Content type of stored dataset manifests
+pub const DATASET_MANIFEST_CONTENT_TYPE: &str = "text/plain; charset=utf-8";
+
+/// Characters escaped in the keys of a stored manifest, so that each entry
+/// stays on its own line
+const MANIFEST_KEY_ESCAPES: &AsciiSet = &CONTROLS.add(b'%');
+
+/// One object version that is part of a dataset
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatasetEntry {
+    pub key: ObjectKey,
+    pub version_id: VersionId,
+    /// Size of the version, when the manifest has been resolved
+    pub size: Option<u64>,
+    /// ETag of the version, when the manifest has been resolved
+    pub etag: Option<String>,
+}
+
+/// A published set of object versions, read as one version of a dataset
+///
+/// Each publish stores a new version of the dataset's manifest object, so
+/// consumers switch from one set of versions to the next in a single step and
+/// earlier manifests stay readable by their version.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatasetManifest {
+    pub name: String,
+    pub manifest_version: VersionId,
+    pub published: DateTime<Utc>,
+    pub entries: Vec<DatasetEntry>,
+}
+
+impl DatasetManifest {
+    /// Key of the versioned object holding the manifests of dataset `name`
+    pub fn manifest_key(name: &str) -> Result<ObjectKey, ValidationError> {
+        if name.is_empty() || name.contains('/') {
+            return Err(ValidationError::InvalidField {
+                field: "dataset".to_string(),
+                value: name.to_string(),
+                expected: "a non-empty name without '/'".to_string(),
+            });
+        }
+        ObjectKey::new(format!("{}/{}", DATASET_MANIFEST_PREFIX, name))
+    }
+
+    /// Stored form of a manifest: one `<version id> <key>` line per entry
+    pub fn encode_entries(entries: &[DatasetEntry]) -> Vec<u8> {
+        entries
+            .iter()
+            .map(|entry| {
+                format!(
+                    "{} {}\n",
+                    entry.version_id,
+                    utf8_percent_encode(entry.key.as_str(), MANIFEST_KEY_ESCAPES)
+                )
+            })
+            .collect::<String>()
+            .into_bytes()
+    }
+
+    /// Entries of a stored manifest, unresolved
+    pub fn decode_entries(data: &[u8]) -> Result<Vec<DatasetEntry>, ValidationError> {
+        let invalid = |line: &str| ValidationError::InvalidField {
+            field: "manifest".to_string(),
+            value: line.to_string(),
+            expected: "<version id> <key>".to_string(),
+        };
+
+        String::from_utf8_lossy(data)
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let (version_id, key) = line.split_once(' ').ok_or_else(|| invalid(line))?;
+                let key = percent_decode_str(key)
+                    .decode_utf8()
+                    .map_err(|_| invalid(line))?;
+                Ok(DatasetEntry {
+                    key: ObjectKey::new(key.into_owned())?,
+                    version_id: VersionId::new(version_id.to_string())?,
+                    size: None,
+                    etag: None,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_entries_round_trip() {
+        let entries = vec![
+            DatasetEntry {
+                key: ObjectKey::new("ml/train/part 0%.csv".to_string()).unwrap(),
+                version_id: VersionId::new("v1".to_string()).unwrap(),
+                size: Some(10),
+                etag: None,
+            },
+            DatasetEntry {
+                key: ObjectKey::new("ml/train/odd\nname".to_string()).unwrap(),
+                version_id: VersionId::new("v2".to_string()).unwrap(),
+                size: None,
+                etag: None,
+            },
+        ];
+
+        let decoded =
+            DatasetManifest::decode_entries(&DatasetManifest::encode_entries(&entries)).unwrap();
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded[0].key, entries[0].key);
+        assert_eq!(decoded[0].size, None);
+        assert_eq!(decoded[1].key, entries[1].key);
+        assert_eq!(decoded[1].version_id, entries[1].version_id);
+
+        assert!(DatasetManifest::decode_entries(b"no-key-here").is_err());
+        assert!(DatasetManifest::manifest_key("a/b").is_err());
+        assert_eq!(
+            DatasetManifest::manifest_key("train").unwrap().as_str(),
+            ".datasets/train"
+        );
+    }
+}
@@ -0,0 +1,328 @@
+use std::collections::HashMap;
+
+use crate::domain::errors::ValidationError;
+
+/// Block size used for signatures when the client does not choose one
+pub const DEFAULT_DELTA_BLOCK_SIZE: u64 = 8 * 1024;
+
+/// Largest block size a signature may be computed with
+pub const MAX_DELTA_BLOCK_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Checksums of one block of an object version
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockSignature {
+    pub index: u64,
+    /// Rolling checksum, cheap to compute at every offset of the new data
+    pub weak: u32,
+    /// MD5 of the block, confirming a weak match
+    pub strong: String,
+}
+
+/// Block checksums of an object version, from which a client holding a newer
+/// copy of the data computes an [`ObjectDelta`] against it
+///
+/// Blocks are `block_size` bytes long, except possibly the last one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectSignature {
+    pub block_size: u64,
+    pub size: u64,
+    pub blocks: Vec<BlockSignature>,
+}
+
+impl ObjectSignature {
+    /// Signature of `data` split into blocks of `block_size` bytes
+    pub fn compute(data: &[u8], block_size: u64) -> Result<Self, ValidationError> {
+        validate_block_size(block_size)?;
+
+        let blocks = data
+            .chunks(block_size as usize)
+            .enumerate()
+            .map(|(index, block)| BlockSignature {
+                index: index as u64,
+                weak: RollingChecksum::new(block).value(),
+                strong: strong_checksum(block),
+            })
+            .collect();
+
+        Ok(Self {
+            block_size,
+            size: data.len() as u64,
+            blocks,
+        })
+    }
+
+    /// Length of block `index`
+    fn block_len(&self, index: u64) -> u64 {
+        self.size
+            .saturating_sub(index * self.block_size)
+            .min(self.block_size)
+    }
+}
+
+/// One step of rebuilding an object version from a base version
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeltaInstruction {
+    /// Reuse a block of the base version, which must still have `checksum`
+    Copy { block: u64, checksum: String },
+    /// Bytes not found in the base version
+    Literal(Vec<u8>),
+}
+
+/// A new version of an object expressed as blocks of a base version and the
+/// bytes in between, in the style of rsync
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectDelta {
+    pub block_size: u64,
+    pub instructions: Vec<DeltaInstruction>,
+}
+
+impl ObjectDelta {
+    /// Delta turning the version described by `signature` into `data`
+    ///
+    /// Every offset of `data` is tried against the blocks of the signature,
+    /// so blocks are reused even when bytes were inserted or removed before
+    /// them.
+    pub fn compute(signature: &ObjectSignature, data: &[u8]) -> Self {
+        let block_size = signature.block_size as usize;
+        let mut blocks_by_weak: HashMap<u32, Vec<&BlockSignature>> = HashMap::new();
+        for block in &signature.blocks {
+            blocks_by_weak.entry(block.weak).or_default().push(block);
+        }
+        let find_block = |window: &[u8], weak: u32| {
+            let candidates = blocks_by_weak.get(&weak)?;
+            let strong = strong_checksum(window);
+            candidates
+                .iter()
+                .find(|block| {
+                    signature.block_len(block.index) == window.len() as u64
+                        && block.strong == strong
+                })
+                .map(|block| block.index)
+        };
+
+        let mut delta = DeltaBuilder::default();
+        let mut offset = 0;
+        let mut rolling: Option<RollingChecksum> = None;
+        while offset + block_size <= data.len() {
+            let window = &data[offset..offset + block_size];
+            let checksum = rolling.unwrap_or_else(|| RollingChecksum::new(window));
+            if let Some(block) = find_block(window, checksum.value()) {
+                delta.copy(block, window);
+                offset += block_size;
+                rolling = None;
+                continue;
+            }
+
+            delta.literal(data[offset]);
+            rolling = data.get(offset + block_size).map(|&next| {
+                let mut checksum = checksum;
+                checksum.roll(data[offset], next);
+                checksum
+            });
+            offset += 1;
+        }
+
+        // The remaining bytes can only match a short last block
+        let tail = &data[offset..];
+        if !tail.is_empty() {
+            match find_block(tail, RollingChecksum::new(tail).value()) {
+                Some(block) => delta.copy(block, tail),
+                None => tail.iter().for_each(|&byte| delta.literal(byte)),
+            }
+        }
+
+        Self {
+            block_size: signature.block_size,
+            instructions: delta.finish(),
+        }
+    }
+
+    /// Rebuild the new version from the data of the base version
+    ///
+    /// Fails if a copied block is out of range or no longer has the checksum
+    /// the delta was computed against.
+    pub fn apply(&self, base: &[u8]) -> Result<Vec<u8>, ValidationError> {
+        validate_block_size(self.block_size)?;
+
+        let mut data = Vec::with_capacity(base.len());
+        for instruction in &self.instructions {
+            match instruction {
+                DeltaInstruction::Copy { block, checksum } => {
+                    let start = block.saturating_mul(self.block_size);
+                    if start >= base.len() as u64 {
+                        return Err(invalid_delta(
+                            block.to_string(),
+                            "a block of the base version",
+                        ));
+                    }
+                    let end = (start + self.block_size).min(base.len() as u64);
+                    let bytes = &base[start as usize..end as usize];
+                    if strong_checksum(bytes) != *checksum {
+                        return Err(invalid_delta(
+                            block.to_string(),
+                            "a block unchanged since the signature was computed",
+                        ));
+                    }
+                    data.extend_from_slice(bytes);
+                }
+                DeltaInstruction::Literal(bytes) => data.extend_from_slice(bytes),
+            }
+        }
+        Ok(data)
+    }
+
+    /// Number of bytes sent as literals rather than reused from the base
+    pub fn literal_bytes(&self) -> u64 {
+        self.instructions
+            .iter()
+            .map(|instruction| match instruction {
+                DeltaInstruction::Copy { .. } => 0,
+                DeltaInstruction::Literal(bytes) => bytes.len() as u64,
+            })
+            .sum()
+    }
+}
+
+/// Accumulates instructions, merging consecutive literal bytes
+#[derive(Default)]
+struct DeltaBuilder {
+    instructions: Vec<DeltaInstruction>,
+    literal: Vec<u8>,
+}
+
+impl DeltaBuilder {
+    fn copy(&mut self, block: u64, bytes: &[u8]) {
+        self.flush();
+        self.instructions.push(DeltaInstruction::Copy {
+            block,
+            checksum: strong_checksum(bytes),
+        });
+    }
+
+    fn literal(&mut self, byte: u8) {
+        self.literal.push(byte);
+    }
+
+    fn flush(&mut self) {
+        if !self.literal.is_empty() {
+            let literal = std::mem::take(&mut self.literal);
+            self.instructions.push(DeltaInstruction::Literal(literal));
+        }
+    }
+
+    fn finish(mut self) -> Vec<DeltaInstruction> {
+        self.flush();
+        self.instructions
+    }
+}
+
+/// rsync's weak checksum, which can be moved along the data one byte at a time
+#[derive(Debug, Clone, Copy)]
+struct RollingChecksum {
+    a: u32,
+    b: u32,
+    len: u32,
+}
+
+impl RollingChecksum {
+    fn new(block: &[u8]) -> Self {
+        let len = block.len() as u32;
+        let (a, b) = block
+            .iter()
+            .enumerate()
+            .fold((0u32, 0u32), |(a, b), (i, &byte)| {
+                let byte = byte as u32;
+                (
+                    a.wrapping_add(byte),
+                    b.wrapping_add((len - i as u32).wrapping_mul(byte)),
+                )
+            });
+        Self { a, b, len }
+    }
+
+    /// Drop `out` from the start of the window and add `next` at its end
+    fn roll(&mut self, out: u8, next: u8) {
+        self.a = self.a.wrapping_sub(out as u32).wrapping_add(next as u32);
+        self.b = self
+            .b
+            .wrapping_sub(self.len.wrapping_mul(out as u32))
+            .wrapping_add(self.a);
+    }
+
+    fn value(&self) -> u32 {
+        (self.a & 0xffff) | (self.b << 16)
+    }
+}
+
+fn strong_checksum(block: &[u8]) -> String {
+    format!("{:x}", md5::compute(block))
+}
+
+fn validate_block_size(block_size: u64) -> Result<(), ValidationError> {
+    if block_size == 0 || block_size > MAX_DELTA_BLOCK_SIZE {
+        return Err(ValidationError::InvalidField {
+            field: "block_size".to_string(),
+            value: block_size.to_string(),
+            expected: format!("between 1 and {} bytes", MAX_DELTA_BLOCK_SIZE),
+        });
+    }
+    Ok(())
+}
+
+fn invalid_delta(block: String, expected: &str) -> ValidationError {
+    ValidationError::InvalidField {
+        field: "delta.block".to_string(),
+        value: block,
+        expected: expected.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delta_reuses_shifted_blocks() {
+        let base: Vec<u8> = (0..64u8).collect();
+        let signature = ObjectSignature::compute(&base, 16).unwrap();
+        assert_eq!(signature.blocks.len(), 4);
+
+        // Insert bytes before the second block and change the last one
+        let mut new = base[..16].to_vec();
+        new.extend_from_slice(b"inserted");
+        new.extend_from_slice(&base[16..48]);
+        new.extend_from_slice(b"new tail");
+
+        let delta = ObjectDelta::compute(&signature, &new);
+        let copied: Vec<u64> = delta
+            .instructions
+            .iter()
+            .filter_map(|instruction| match instruction {
+                DeltaInstruction::Copy { block, .. } => Some(*block),
+                DeltaInstruction::Literal(_) => None,
+            })
+            .collect();
+        assert_eq!(copied, vec![0, 1, 2]);
+        assert_eq!(delta.literal_bytes(), 16);
+        assert_eq!(delta.apply(&base).unwrap(), new);
+
+        // A base that changed since the signature is refused
+        let mut changed = base.clone();
+        changed[20] = 0xff;
+        assert!(delta.apply(&changed).is_err());
+        assert!(ObjectSignature::compute(&base, 0).is_err());
+    }
+
+    #[test]
+    fn test_rolling_checksum_matches_fresh_checksum() {
+        let data = b"the quick brown fox jumps over the lazy dog";
+        let mut rolling = RollingChecksum::new(&data[..8]);
+        for offset in 1..data.len() - 8 {
+            rolling.roll(data[offset - 1], data[offset + 7]);
+            assert_eq!(
+                rolling.value(),
+                RollingChecksum::new(&data[offset..offset + 8]).value()
+            );
+        }
+    }
+}
@@ -108,3 +108,76 @@ impl Filter {
             && self.object_size_less_than.is_none()
     }
 }
+
+/// Free-text query over object keys and custom metadata values
+///
+/// The query is split on whitespace into terms. An object matches when every
+/// term occurs, ignoring case, somewhere in its key or in one of its metadata
+/// values, so `invoice 2024-03` finds `billing/acme-invoice.pdf` tagged with
+/// `period: 2024-03`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TextQuery {
+    terms: Vec<String>,
+}
+
+impl TextQuery {
+    pub fn new(query: &str) -> Self {
+        Self {
+            terms: query.split_whitespace().map(str::to_lowercase).collect(),
+        }
+    }
+
+    /// Lowercase terms that must all match
+    pub fn terms(&self) -> &[String] {
+        &self.terms
+    }
+
+    /// Check if the query has no terms (matches everything)
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Text a query is matched against: the key and metadata values, lowercased
+    pub fn searchable_text(key: &str, metadata: &HashMap<String, String>) -> String {
+        let mut values: Vec<&str> = metadata.values().map(String::as_str).collect();
+        values.sort_unstable();
+        std::iter::once(key)
+            .chain(values)
+            .collect::<Vec<_>>()
+            .join("\n")
+            .to_lowercase()
+    }
+
+    /// Check if every term occurs in the key or a metadata value
+    pub fn matches(&self, key: &str, metadata: &HashMap<String, String>) -> bool {
+        self.matches_text(&Self::searchable_text(key, metadata))
+    }
+
+    /// Check if every term occurs in text built by [`searchable_text`](Self::searchable_text)
+    pub fn matches_text(&self, searchable_text: &str) -> bool {
+        self.terms
+            .iter()
+            .all(|term| searchable_text.contains(term.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_query_matches_key_and_metadata_values() {
+        let metadata = HashMap::from([
+            ("customer".to_string(), "ACME Corp".to_string()),
+            ("period".to_string(), "2024-03".to_string()),
+        ]);
+        let key = "billing/Invoice-0042.pdf";
+
+        assert!(TextQuery::new("invoice 2024-03").matches(key, &metadata));
+        assert!(TextQuery::new("  acme  ").matches(key, &metadata));
+        assert!(TextQuery::new("").matches(key, &metadata));
+        assert!(!TextQuery::new("invoice 2024-04").matches(key, &metadata));
+        // Metadata names are not searched, only values
+        assert!(!TextQuery::new("customer").matches(key, &metadata));
+    }
+}
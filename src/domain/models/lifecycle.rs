@@ -49,18 +49,13 @@ pub struct LifecycleRule {
 }
 
 /// Status of a lifecycle rule
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default)]
 pub enum RuleStatus {
     Enabled,
+    #[default]
     Disabled,
 }
 
-impl Default for RuleStatus {
-    fn default() -> Self {
-        RuleStatus::Disabled
-    }
-}
-
 /// Actions that can be taken by lifecycle rules
 #[derive(Debug, Clone, PartialEq)]
 pub enum LifecycleAction {
@@ -86,6 +81,8 @@ pub enum LifecycleAction {
         days: u32,
         storage_class: StorageClass,
     },
+    /// Delete an object at the time scheduled in its own metadata
+    ScheduledDeletion { delete_at: DateTime<Utc> },
 }
 
 /// Storage classes for lifecycle transitions
@@ -108,8 +105,10 @@ impl StorageClass {
             StorageClass::Custom(s) => s,
         }
     }
+}
 
-    pub fn from_str(s: &str) -> Self {
+impl From<&str> for StorageClass {
+    fn from(s: &str) -> Self {
         match s {
             "STANDARD" => StorageClass::Standard,
             "STANDARD_IA" => StorageClass::InfrequentAccess,
@@ -128,6 +127,7 @@ pub struct EvaluateLifecycleRequest {
     pub object_tags: HashMap<String, String>,
     pub is_delete_marker: bool,
     pub is_current_version: bool,
+    pub delete_at: Option<DateTime<Utc>>,
 }
 
 /// Result of lifecycle evaluation
@@ -229,6 +229,101 @@ impl LifecycleConfiguration {
 
         Ok(())
     }
+
+    /// Copy of this configuration for another bucket
+    ///
+    /// With a prefix rewrite, rule prefixes are rewritten as they would match
+    /// in the target bucket.
+    pub fn copy_to(&self, bucket: BucketName, prefix_rewrite: Option<&PrefixRewrite>) -> Self {
+        let mut rules = self.rules.clone();
+        if let Some(rewrite) = prefix_rewrite {
+            for rule in &mut rules {
+                rule.filter.prefix = rewrite.apply(rule.filter.prefix.as_deref());
+            }
+        }
+
+        LifecycleConfiguration { bucket, rules }
+    }
+}
+
+/// Rule-level differences between a bucket's active lifecycle configuration
+/// and a proposed one, matching rules by ID
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LifecycleConfigurationDiff {
+    /// Rules only in the proposed configuration
+    pub added: Vec<LifecycleRule>,
+    /// Rules only in the active configuration
+    pub removed: Vec<LifecycleRule>,
+    /// Rules in both whose settings differ
+    pub modified: Vec<RuleChange>,
+    /// IDs of rules identical in both
+    pub unchanged: Vec<String>,
+}
+
+/// A rule present in both configurations with different settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleChange {
+    pub before: LifecycleRule,
+    pub after: LifecycleRule,
+}
+
+impl LifecycleConfigurationDiff {
+    /// Compare a proposed configuration against the active one, if any
+    pub fn between(
+        active: Option<&LifecycleConfiguration>,
+        proposed: &LifecycleConfiguration,
+    ) -> Self {
+        let active_rules = active
+            .map(|config| config.rules.as_slice())
+            .unwrap_or_default();
+        let mut diff = LifecycleConfigurationDiff::default();
+
+        for rule in &proposed.rules {
+            match active_rules.iter().find(|active| active.id == rule.id) {
+                None => diff.added.push(rule.clone()),
+                Some(active) if active == rule => diff.unchanged.push(rule.id.clone()),
+                Some(active) => diff.modified.push(RuleChange {
+                    before: active.clone(),
+                    after: rule.clone(),
+                }),
+            }
+        }
+
+        diff.removed = active_rules
+            .iter()
+            .filter(|active| !proposed.rules.iter().any(|rule| rule.id == active.id))
+            .cloned()
+            .collect();
+
+        diff
+    }
+
+    /// Whether the proposed configuration would change anything
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// Rewrites rule prefixes starting with `from` to start with `to` instead
+///
+/// An empty `from` matches every rule, including rules without a prefix, so it
+/// scopes all rules under `to`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrefixRewrite {
+    pub from: String,
+    pub to: String,
+}
+
+impl PrefixRewrite {
+    /// Rewrite a rule prefix; prefixes not starting with `from` are kept
+    pub fn apply(&self, prefix: Option<&str>) -> Option<String> {
+        let prefix = prefix.unwrap_or_default();
+        let rewritten = match prefix.strip_prefix(self.from.as_str()) {
+            Some(rest) => format!("{}{}", self.to, rest),
+            None => prefix.to_string(),
+        };
+        (!rewritten.is_empty()).then_some(rewritten)
+    }
 }
 
 /// Validation errors for lifecycle configuration
@@ -260,3 +355,72 @@ impl std::fmt::Display for ValidationError {
 }
 
 impl std::error::Error for ValidationError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: &str, expiration_days: u32) -> LifecycleRule {
+        LifecycleRule {
+            id: id.to_string(),
+            status: RuleStatus::Enabled,
+            expiration_days: Some(expiration_days),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_configuration_diff() {
+        let bucket = BucketName::new("test-bucket".to_string()).unwrap();
+        let active = LifecycleConfiguration {
+            bucket: bucket.clone(),
+            rules: vec![rule("keep", 7), rule("change", 7), rule("drop", 7)],
+        };
+        let proposed = LifecycleConfiguration {
+            bucket,
+            rules: vec![rule("keep", 7), rule("change", 30), rule("new", 1)],
+        };
+
+        let diff = LifecycleConfigurationDiff::between(Some(&active), &proposed);
+        assert_eq!(diff.unchanged, vec!["keep".to_string()]);
+        assert_eq!(diff.modified.len(), 1);
+        assert_eq!(diff.modified[0].before.expiration_days, Some(7));
+        assert_eq!(diff.modified[0].after.expiration_days, Some(30));
+        assert_eq!(diff.added[0].id, "new");
+        assert_eq!(diff.removed[0].id, "drop");
+
+        // Without an active configuration every rule is new
+        let diff = LifecycleConfigurationDiff::between(None, &proposed);
+        assert_eq!(diff.added.len(), 3);
+        assert!(LifecycleConfigurationDiff::between(Some(&proposed), &proposed).is_empty());
+    }
+
+    #[test]
+    fn test_prefix_rewrite() {
+        let rewrite = PrefixRewrite {
+            from: "logs/".to_string(),
+            to: "archive/logs/".to_string(),
+        };
+        assert_eq!(
+            rewrite.apply(Some("logs/app/")).as_deref(),
+            Some("archive/logs/app/")
+        );
+        assert_eq!(rewrite.apply(Some("tmp/")).as_deref(), Some("tmp/"));
+        assert_eq!(rewrite.apply(None), None);
+
+        // An empty source prefix scopes every rule under the new prefix
+        let scope = PrefixRewrite {
+            from: String::new(),
+            to: "tenant-a/".to_string(),
+        };
+        assert_eq!(scope.apply(None).as_deref(), Some("tenant-a/"));
+        assert_eq!(scope.apply(Some("tmp/")).as_deref(), Some("tenant-a/tmp/"));
+
+        // Rewriting to an empty prefix removes the filter
+        let unscope = PrefixRewrite {
+            from: "tenant-a/".to_string(),
+            to: String::new(),
+        };
+        assert_eq!(unscope.apply(Some("tenant-a/")), None);
+    }
+}
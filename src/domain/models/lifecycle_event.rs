@@ -0,0 +1,76 @@
+use chrono::{DateTime, Utc};
+
+use super::lifecycle::StorageClass;
+use crate::domain::value_objects::{ObjectKey, VersionId};
+
+/// Kind of change lifecycle processing made
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LifecycleEventType {
+    /// An object or one of its versions was deleted by a rule or its own
+    /// deletion schedule
+    ObjectExpired,
+    /// An object was moved to another storage class
+    ObjectTransitioned,
+    /// An incomplete multipart upload was aborted
+    MultipartAborted,
+}
+
+impl LifecycleEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LifecycleEventType::ObjectExpired => "ObjectExpired",
+            LifecycleEventType::ObjectTransitioned => "ObjectTransitioned",
+            LifecycleEventType::MultipartAborted => "MultipartAborted",
+        }
+    }
+}
+
+/// A change made by lifecycle processing rather than by a client
+#[derive(Debug, Clone, PartialEq)]
+pub struct LifecycleEvent {
+    pub event_type: LifecycleEventType,
+    pub time: DateTime<Utc>,
+    pub key: ObjectKey,
+    /// Rule that caused the change
+    pub rule_id: String,
+    /// Version expired, when only one version of the object was
+    pub version_id: Option<VersionId>,
+    /// Storage class the object was transitioned to
+    pub storage_class: Option<StorageClass>,
+    /// Multipart upload that was aborted
+    pub upload_id: Option<String>,
+}
+
+impl LifecycleEvent {
+    pub fn new(
+        event_type: LifecycleEventType,
+        key: ObjectKey,
+        rule_id: impl Into<String>,
+        time: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            event_type,
+            time,
+            key,
+            rule_id: rule_id.into(),
+            version_id: None,
+            storage_class: None,
+            upload_id: None,
+        }
+    }
+
+    pub fn with_version_id(mut self, version_id: VersionId) -> Self {
+        self.version_id = Some(version_id);
+        self
+    }
+
+    pub fn with_storage_class(mut self, storage_class: StorageClass) -> Self {
+        self.storage_class = Some(storage_class);
+        self
+    }
+
+    pub fn with_upload_id(mut self, upload_id: impl Into<String>) -> Self {
+        self.upload_id = Some(upload_id.into());
+        self
+    }
+}
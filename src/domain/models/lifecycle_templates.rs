@@ -0,0 +1,135 @@
+use super::filter::Filter;
+use super::lifecycle::{LifecycleRule, RuleStatus, StorageClass};
+
+/// Predefined lifecycle rules for common retention policies
+///
+/// Templates are addressed by name and expand into a complete, enabled
+/// [`LifecycleRule`], so the common cases don't need to be hand-authored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LifecycleTemplate {
+    /// Delete temporary objects a week after they were created
+    ExpireTempAfter7d,
+    /// Move logs to archive storage after a month
+    ArchiveLogsAfter30d,
+    /// Delete noncurrent versions and abandoned multipart uploads
+    CleanupNoncurrentVersions,
+}
+
+impl LifecycleTemplate {
+    /// All available templates
+    pub const ALL: [LifecycleTemplate; 3] = [
+        LifecycleTemplate::ExpireTempAfter7d,
+        LifecycleTemplate::ArchiveLogsAfter30d,
+        LifecycleTemplate::CleanupNoncurrentVersions,
+    ];
+
+    /// Look up a template by its name
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|template| template.name() == name)
+    }
+
+    /// Name the template is addressed by
+    pub fn name(&self) -> &'static str {
+        match self {
+            LifecycleTemplate::ExpireTempAfter7d => "expire-temp-after-7d",
+            LifecycleTemplate::ArchiveLogsAfter30d => "archive-logs-after-30d",
+            LifecycleTemplate::CleanupNoncurrentVersions => "cleanup-noncurrent-versions",
+        }
+    }
+
+    /// Human-readable summary of what the template does
+    pub fn description(&self) -> &'static str {
+        match self {
+            LifecycleTemplate::ExpireTempAfter7d => "Expire objects 7 days after creation",
+            LifecycleTemplate::ArchiveLogsAfter30d => {
+                "Transition objects to GLACIER storage 30 days after creation"
+            }
+            LifecycleTemplate::CleanupNoncurrentVersions => {
+                "Expire noncurrent versions after 30 days and abort multipart uploads after 7 days"
+            }
+        }
+    }
+
+    /// Prefix the rule applies to when none is given
+    pub fn default_prefix(&self) -> Option<&'static str> {
+        match self {
+            LifecycleTemplate::ExpireTempAfter7d => Some("tmp/"),
+            LifecycleTemplate::ArchiveLogsAfter30d => Some("logs/"),
+            LifecycleTemplate::CleanupNoncurrentVersions => None,
+        }
+    }
+
+    /// Build the rule described by this template
+    ///
+    /// `prefix` overrides the template's default prefix, and an empty prefix
+    /// applies the rule to the whole bucket. The rule ID defaults to the
+    /// template name.
+    pub fn rule(&self, prefix: Option<String>, rule_id: Option<String>) -> LifecycleRule {
+        let prefix = prefix
+            .or_else(|| self.default_prefix().map(str::to_string))
+            .filter(|prefix| !prefix.is_empty());
+
+        let mut rule = LifecycleRule {
+            id: rule_id.unwrap_or_else(|| self.name().to_string()),
+            status: RuleStatus::Enabled,
+            filter: Filter {
+                prefix,
+                ..Filter::default()
+            },
+            ..LifecycleRule::default()
+        };
+
+        match self {
+            LifecycleTemplate::ExpireTempAfter7d => {
+                rule.expiration_days = Some(7);
+            }
+            LifecycleTemplate::ArchiveLogsAfter30d => {
+                rule.transition_days = Some(30);
+                rule.transition_storage_class = Some(StorageClass::Glacier);
+            }
+            LifecycleTemplate::CleanupNoncurrentVersions => {
+                rule.noncurrent_version_expiration_noncurrent_days = Some(30);
+                rule.abort_incomplete_multipart_upload_days_after_initiation = Some(7);
+            }
+        }
+
+        rule
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_templates_are_addressable_by_name() {
+        for template in LifecycleTemplate::ALL {
+            assert_eq!(
+                LifecycleTemplate::from_name(template.name()),
+                Some(template)
+            );
+            assert!(template.rule(None, None).validate().is_ok());
+        }
+        assert_eq!(LifecycleTemplate::from_name("no-such-template"), None);
+    }
+
+    #[test]
+    fn test_template_rule_overrides() {
+        let template = LifecycleTemplate::ExpireTempAfter7d;
+
+        let rule = template.rule(None, None);
+        assert_eq!(rule.id, "expire-temp-after-7d");
+        assert_eq!(rule.status, RuleStatus::Enabled);
+        assert_eq!(rule.filter.prefix.as_deref(), Some("tmp/"));
+        assert_eq!(rule.expiration_days, Some(7));
+
+        let rule = template.rule(Some("scratch/".to_string()), Some("scratch".to_string()));
+        assert_eq!(rule.id, "scratch");
+        assert_eq!(rule.filter.prefix.as_deref(), Some("scratch/"));
+
+        let rule = template.rule(Some(String::new()), None);
+        assert_eq!(rule.filter.prefix, None);
+    }
+}
@@ -1,16 +1,53 @@
+pub mod audit;
+pub mod backup;
+pub mod dataset;
+pub mod delta;
 pub mod filter;
 pub mod lifecycle;
+pub mod lifecycle_event;
+pub mod lifecycle_templates;
+pub mod multipart;
 pub mod object;
+pub mod quota;
+pub mod transaction;
 pub mod version;
+pub mod worker_event;
 
+pub use audit::{
+    AUDIT_ACTION_INDEX_REPAIRED, AUDIT_ACTION_MALWARE_REJECTED, AUDIT_ACTION_OBJECT_REPAIRED,
+    AuditEvent,
+};
+pub use backup::{
+    BACKUP_PREFIX, BackupEntry, BackupJob, BackupMode, BackupRestore, BackupRun, BackupRunState,
+};
+pub use dataset::{
+    DATASET_MANIFEST_CONTENT_TYPE, DATASET_MANIFEST_PREFIX, DatasetEntry, DatasetManifest,
+};
+pub use delta::{
+    BlockSignature, DEFAULT_DELTA_BLOCK_SIZE, DeltaInstruction, MAX_DELTA_BLOCK_SIZE, ObjectDelta,
+    ObjectSignature,
+};
 pub use filter::*;
 pub use lifecycle::{
     ApplicableAction, EvaluateLifecycleRequest, LifecycleAction, LifecycleConfiguration,
-    LifecycleEvaluationResult, LifecycleRule, RuleStatus, StorageClass as LifecycleStorageClass,
+    LifecycleConfigurationDiff, LifecycleEvaluationResult, LifecycleRule, PrefixRewrite,
+    RuleChange, RuleStatus, StorageClass as LifecycleStorageClass,
     ValidationError as LifecycleValidationError,
 };
+pub use lifecycle_event::{LifecycleEvent, LifecycleEventType};
+pub use lifecycle_templates::LifecycleTemplate;
+pub use multipart::{
+    CHECKSUM_SHA256_METADATA_KEY, IntegrityManifest, MAX_PART_NUMBER, MultipartUploadInfo,
+    PART_CHECKSUMS_METADATA_KEY, PartChecksum, PresignedMultipartUpload, PresignedPart,
+    UploadedPart, multipart_etag, part_checksum, validate_part_number,
+};
 pub use object::*;
+pub use quota::{DEFAULT_SOFT_LIMIT_PERCENT, PrefixQuota, PrefixUsage, QuotaKind, QuotaWarning};
+pub use transaction::{ObjectTransaction, StagedObject, TRANSACTION_STAGING_PREFIX};
 pub use version::{
-    DeleteVersionRequest, DeleteVersionResult, RetentionMode, StorageClass as VersionStorageClass,
-    VersionMetadata, VersionRetentionPolicy, VersionTransition, VersioningConfiguration,
+    BulkDeleteVersionsRequest, BulkDeleteVersionsResult, DeleteVersionRequest, DeleteVersionResult,
+    LabelVersionRequest, RetentionMode, RollbackVersionResult, StorageClass as VersionStorageClass,
+    VERSION_STAGING_PREFIX, VersionDeletionOutcome, VersionMetadata, VersionRetentionPolicy,
+    VersionSelection, VersionTransition, VersioningConfiguration, version_key,
 };
+pub use worker_event::{WorkerEvent, WorkerEventType};
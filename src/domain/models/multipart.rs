@@ -0,0 +1,269 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::domain::{errors::ValidationError, value_objects::ObjectKey};
+
+/// Highest part number a multipart upload accepts, as in S3
+pub const MAX_PART_NUMBER: u32 = 10_000;
+
+/// Metadata key of the composite SHA-256 checksum of a multipart object
+pub const CHECKSUM_SHA256_METADATA_KEY: &str = "x-amz-checksum-sha256";
+
+/// Metadata key of the checksums of the parts of a multipart object
+pub const PART_CHECKSUMS_METADATA_KEY: &str = "x-part-checksums";
+
+/// A multipart upload that has been initiated but not yet completed or aborted
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultipartUploadInfo {
+    pub upload_id: String,
+    pub key: ObjectKey,
+    pub initiated: DateTime<Utc>,
+    /// Content type and metadata the completed object is stored with
+    pub content_type: Option<String>,
+    pub custom_metadata: HashMap<String, String>,
+}
+
+impl MultipartUploadInfo {
+    /// Whether the upload was initiated at least `days` days before `now`
+    pub fn is_older_than(&self, days: u32, now: DateTime<Utc>) -> bool {
+        now - self.initiated >= chrono::Duration::days(days as i64)
+    }
+}
+
+/// A part uploaded to an in-progress multipart upload
+#[derive(Debug, Clone, PartialEq)]
+pub struct UploadedPart {
+    pub part_number: u32,
+    pub etag: String,
+    pub size: u64,
+    pub last_modified: DateTime<Utc>,
+    /// Base64 SHA-256 of the part, unless it was copied without being read
+    pub checksum_sha256: Option<String>,
+}
+
+/// Base64 SHA-256 of the data of a part, as S3 reports it
+pub fn part_checksum(data: &[u8]) -> String {
+    BASE64.encode(Sha256::digest(data))
+}
+
+/// URLs letting a client upload parts of a multipart upload and complete it
+/// straight against the storage backend
+#[derive(Debug, Clone, PartialEq)]
+pub struct PresignedMultipartUpload {
+    pub upload_id: String,
+    pub parts: Vec<PresignedPart>,
+    pub complete_url: String,
+}
+
+/// URL uploading one part of a multipart upload
+#[derive(Debug, Clone, PartialEq)]
+pub struct PresignedPart {
+    pub part_number: u32,
+    pub url: String,
+}
+
+/// Check that a part number lies within 1 and [`MAX_PART_NUMBER`]
+pub fn validate_part_number(part_number: u32) -> Result<(), ValidationError> {
+    if (1..=MAX_PART_NUMBER).contains(&part_number) {
+        Ok(())
+    } else {
+        Err(ValidationError::InvalidField {
+            field: "partNumber".to_string(),
+            value: part_number.to_string(),
+            expected: format!("a number from 1 to {}", MAX_PART_NUMBER),
+        })
+    }
+}
+
+/// ETag of an object assembled from parts with the given ETags
+///
+/// Follows S3: the MD5 of the concatenated binary part digests, followed by
+/// `-` and the number of parts. Part ETags that are not MD5 digests are hashed
+/// as text.
+pub fn multipart_etag<'a>(part_etags: impl IntoIterator<Item = &'a str>) -> String {
+    let mut context = md5::Context::new();
+    let mut parts = 0;
+    for etag in part_etags {
+        let etag = etag.trim_matches('"');
+        match hex::decode(etag) {
+            Ok(digest) if digest.len() == 16 => context.consume(&digest),
+            _ => context.consume(etag.as_bytes()),
+        }
+        parts += 1;
+    }
+    format!("{:x}-{}", context.compute(), parts)
+}
+
+/// Checksums of the parts a multipart object was assembled from, and their
+/// composite, so a very large object can be verified one part at a time
+///
+/// It is kept in the object's metadata: the composite under
+/// [`CHECKSUM_SHA256_METADATA_KEY`], as S3 reports a composite checksum, and the
+/// parts under [`PART_CHECKSUMS_METADATA_KEY`] as comma-separated
+/// `<part number>:<size>:<checksum>` entries in object order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntegrityManifest {
+    pub parts: Vec<PartChecksum>,
+    /// Base64 SHA-256 of the concatenated part digests, followed by `-` and
+    /// the number of parts
+    pub composite: String,
+}
+
+/// Checksum of one part of a multipart object
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartChecksum {
+    pub part_number: u32,
+    pub size: u64,
+    pub checksum: String,
+}
+
+impl IntegrityManifest {
+    /// Manifest of an object assembled from `parts` in order, or `None` if
+    /// one of them has no checksum
+    pub fn from_parts<'a>(parts: impl IntoIterator<Item = &'a UploadedPart>) -> Option<Self> {
+        let parts = parts
+            .into_iter()
+            .map(|part| {
+                Some(PartChecksum {
+                    part_number: part.part_number,
+                    size: part.size,
+                    checksum: part.checksum_sha256.clone()?,
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let mut digests = Sha256::new();
+        for part in &parts {
+            digests.update(BASE64.decode(&part.checksum).ok()?);
+        }
+        Some(Self {
+            composite: format!("{}-{}", BASE64.encode(digests.finalize()), parts.len()),
+            parts,
+        })
+    }
+
+    /// Record the manifest in an object's metadata
+    pub fn write_metadata(&self, metadata: &mut HashMap<String, String>) {
+        let parts = self
+            .parts
+            .iter()
+            .map(|part| format!("{}:{}:{}", part.part_number, part.size, part.checksum))
+            .collect::<Vec<_>>()
+            .join(",");
+        metadata.insert(
+            CHECKSUM_SHA256_METADATA_KEY.to_string(),
+            self.composite.clone(),
+        );
+        metadata.insert(PART_CHECKSUMS_METADATA_KEY.to_string(), parts);
+    }
+
+    /// The manifest recorded in an object's metadata, if there is a valid one
+    pub fn from_metadata(metadata: &HashMap<String, String>) -> Option<Self> {
+        let composite = metadata.get(CHECKSUM_SHA256_METADATA_KEY)?.clone();
+        let parts = metadata
+            .get(PART_CHECKSUMS_METADATA_KEY)?
+            .split(',')
+            .map(|entry| {
+                let mut fields = entry.splitn(3, ':');
+                Some(PartChecksum {
+                    part_number: fields.next()?.parse().ok()?,
+                    size: fields.next()?.parse().ok()?,
+                    checksum: fields.next()?.to_string(),
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Self { parts, composite })
+    }
+
+    /// Byte range of each part within the object
+    pub fn part_ranges(&self) -> impl Iterator<Item = (&PartChecksum, Range<u64>)> {
+        self.parts.iter().scan(0, |offset, part| {
+            let start = *offset;
+            *offset += part.size;
+            Some((part, start..*offset))
+        })
+    }
+
+    /// Whether `data` is the part with `part_number`
+    pub fn verify_part(&self, part_number: u32, data: &[u8]) -> bool {
+        self.parts.iter().any(|part| {
+            part.part_number == part_number
+                && part.size == data.len() as u64
+                && part.checksum == part_checksum(data)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_integrity_manifest_round_trip() {
+        let part = |part_number: u32, data: &[u8]| UploadedPart {
+            part_number,
+            etag: format!("{:x}", md5::compute(data)),
+            size: data.len() as u64,
+            last_modified: Utc::now(),
+            checksum_sha256: Some(part_checksum(data)),
+        };
+        let parts = [part(1, b"hello "), part(3, b"world")];
+
+        let manifest = IntegrityManifest::from_parts(&parts).unwrap();
+        assert!(manifest.composite.ends_with("-2"));
+        let mut digests = Sha256::digest(b"hello ").to_vec();
+        digests.extend_from_slice(&Sha256::digest(b"world"));
+        assert_eq!(
+            manifest.composite,
+            format!("{}-2", BASE64.encode(Sha256::digest(&digests)))
+        );
+
+        let mut metadata = HashMap::new();
+        manifest.write_metadata(&mut metadata);
+        let recorded = IntegrityManifest::from_metadata(&metadata).unwrap();
+        assert_eq!(recorded, manifest);
+        let ranges: Vec<_> = recorded.part_ranges().map(|(_, range)| range).collect();
+        assert_eq!(ranges, [0..6, 6..11]);
+        assert!(recorded.verify_part(3, b"world"));
+        assert!(!recorded.verify_part(3, b"World"));
+
+        // A copied part without a checksum leaves the object without a manifest
+        let copied = UploadedPart {
+            checksum_sha256: None,
+            ..part(2, b"copied")
+        };
+        assert_eq!(
+            IntegrityManifest::from_parts(&[parts[0].clone(), copied]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_multipart_etag_matches_s3() {
+        let first = format!("{:x}", md5::compute(b"hello "));
+        let second = format!("{:x}", md5::compute(b"world"));
+
+        let mut digests = md5::compute(b"hello ").0.to_vec();
+        digests.extend_from_slice(&md5::compute(b"world").0);
+        let expected = format!("{:x}-2", md5::compute(&digests));
+
+        assert_eq!(multipart_etag([first.as_str(), second.as_str()]), expected);
+        assert_eq!(
+            multipart_etag([format!("\"{}\"", first).as_str(), second.as_str()]),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_part_numbers_are_bounded() {
+        assert!(validate_part_number(1).is_ok());
+        assert!(validate_part_number(MAX_PART_NUMBER).is_ok());
+        assert!(validate_part_number(0).is_err());
+        assert!(validate_part_number(MAX_PART_NUMBER + 1).is_err());
+    }
+}
@@ -1,7 +1,33 @@
-use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeSet, HashMap};
 
+use super::version::{RetentionMode, VersionRetentionPolicy};
 use crate::domain::value_objects::{ObjectKey, VersionId};
 
+/// Custom metadata key holding the time at which an object is scheduled for deletion
+pub const DELETE_AT_METADATA_KEY: &str = "x-delete-at";
+
+/// Request header giving an object a time-to-live in seconds from upload
+pub const EXPIRE_AFTER_SECONDS_HEADER: &str = "x-expire-after-seconds";
+
+/// Custom metadata key marking a version as pinned against deletion
+pub const PINNED_METADATA_KEY: &str = "x-version-pinned";
+
+/// Custom metadata key holding a version's retention mode
+pub const RETENTION_MODE_METADATA_KEY: &str = "x-amz-object-lock-mode";
+
+/// Custom metadata key holding the time until which a version is retained
+pub const RETAIN_UNTIL_METADATA_KEY: &str = "x-amz-object-lock-retain-until-date";
+
+/// Custom metadata key marking a version as under legal hold
+pub const LEGAL_HOLD_METADATA_KEY: &str = "x-amz-object-lock-legal-hold";
+
+/// Custom metadata key holding a version's comma-separated labels
+pub const LABELS_METADATA_KEY: &str = "x-version-labels";
+
+/// Prefix of the custom metadata keys holding a version's annotations
+pub const ANNOTATION_METADATA_PREFIX: &str = "x-version-annotation-";
+
 /// Represents metadata about an object in storage
 #[derive(Debug, Clone, PartialEq)]
 pub struct ObjectMetadata {
@@ -12,6 +38,243 @@ pub struct ObjectMetadata {
     pub custom_metadata: HashMap<String, String>,
 }
 
+impl ObjectMetadata {
+    /// Get the scheduled deletion time of this object, if any
+    pub fn delete_at(&self) -> Option<DateTime<Utc>> {
+        self.custom_metadata
+            .get(DELETE_AT_METADATA_KEY)
+            .and_then(|value| parse_delete_at(value))
+    }
+
+    /// Schedule this object for deletion at the given time, or clear the schedule
+    pub fn set_delete_at(&mut self, delete_at: Option<DateTime<Utc>>) {
+        match delete_at {
+            Some(delete_at) => {
+                self.custom_metadata
+                    .insert(DELETE_AT_METADATA_KEY.to_string(), delete_at.to_rfc3339());
+            }
+            None => {
+                self.custom_metadata.remove(DELETE_AT_METADATA_KEY);
+            }
+        }
+    }
+
+    /// Whether this version is pinned, protecting it from deletion
+    pub fn is_pinned(&self) -> bool {
+        self.custom_metadata
+            .get(PINNED_METADATA_KEY)
+            .is_some_and(|value| value == "true")
+    }
+
+    /// Pin or unpin this version
+    pub fn set_pinned(&mut self, pinned: bool) {
+        if pinned {
+            self.custom_metadata
+                .insert(PINNED_METADATA_KEY.to_string(), "true".to_string());
+        } else {
+            self.custom_metadata.remove(PINNED_METADATA_KEY);
+        }
+    }
+
+    /// Retention policy protecting this version, if any
+    ///
+    /// A retention date without a mode is treated as governance retention.
+    pub fn retention(&self) -> Option<VersionRetentionPolicy> {
+        let mode = self.custom_metadata.get(RETENTION_MODE_METADATA_KEY);
+        let retain_until = self
+            .custom_metadata
+            .get(RETAIN_UNTIL_METADATA_KEY)
+            .and_then(|value| parse_delete_at(value))
+            .map(std::time::SystemTime::from);
+        let legal_hold = self
+            .custom_metadata
+            .get(LEGAL_HOLD_METADATA_KEY)
+            .is_some_and(|value| value.eq_ignore_ascii_case("ON"));
+
+        if mode.is_none() && retain_until.is_none() && !legal_hold {
+            return None;
+        }
+
+        Some(VersionRetentionPolicy {
+            mode: mode
+                .and_then(|mode| RetentionMode::parse(mode))
+                .unwrap_or(RetentionMode::Governance),
+            retain_until,
+            legal_hold,
+        })
+    }
+
+    /// Replace the retention policy of this version, or clear it
+    pub fn set_retention(&mut self, retention: Option<&VersionRetentionPolicy>) {
+        for key in [
+            RETENTION_MODE_METADATA_KEY,
+            RETAIN_UNTIL_METADATA_KEY,
+            LEGAL_HOLD_METADATA_KEY,
+        ] {
+            self.custom_metadata.remove(key);
+        }
+
+        let Some(retention) = retention else {
+            return;
+        };
+        self.custom_metadata.insert(
+            RETENTION_MODE_METADATA_KEY.to_string(),
+            retention.mode.as_str().to_string(),
+        );
+        if let Some(retain_until) = retention.retain_until {
+            self.custom_metadata.insert(
+                RETAIN_UNTIL_METADATA_KEY.to_string(),
+                DateTime::<Utc>::from(retain_until).to_rfc3339(),
+            );
+        }
+        if retention.legal_hold {
+            self.custom_metadata
+                .insert(LEGAL_HOLD_METADATA_KEY.to_string(), "ON".to_string());
+        }
+    }
+
+    /// Why this version may not be deleted at `now`, None if it may
+    ///
+    /// Pinned versions and versions under retention are protected; see
+    /// [`VersionRetentionPolicy::deletion_blocked_reason`].
+    pub fn deletion_blocked_reason(
+        &self,
+        now: std::time::SystemTime,
+        bypass_governance: bool,
+    ) -> Option<String> {
+        if self.is_pinned() {
+            return Some("version is pinned".to_string());
+        }
+        self.retention()?
+            .deletion_blocked_reason(now, bypass_governance)
+    }
+
+    /// Labels attached to this version
+    pub fn labels(&self) -> BTreeSet<String> {
+        self.custom_metadata
+            .get(LABELS_METADATA_KEY)
+            .map(|labels| {
+                labels
+                    .split(',')
+                    .filter(|label| !label.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Replace the labels attached to this version
+    pub fn set_labels(&mut self, labels: &BTreeSet<String>) {
+        if labels.is_empty() {
+            self.custom_metadata.remove(LABELS_METADATA_KEY);
+        } else {
+            let joined = labels.iter().cloned().collect::<Vec<_>>().join(",");
+            self.custom_metadata
+                .insert(LABELS_METADATA_KEY.to_string(), joined);
+        }
+    }
+
+    /// Key/value annotations attached to this version
+    pub fn annotations(&self) -> HashMap<String, String> {
+        self.custom_metadata
+            .iter()
+            .filter_map(|(key, value)| {
+                key.strip_prefix(ANNOTATION_METADATA_PREFIX)
+                    .map(|name| (name.to_string(), value.clone()))
+            })
+            .collect()
+    }
+
+    /// Replace the annotations attached to this version
+    pub fn set_annotations(&mut self, annotations: &HashMap<String, String>) {
+        self.custom_metadata
+            .retain(|key, _| !key.starts_with(ANNOTATION_METADATA_PREFIX));
+        for (name, value) in annotations {
+            self.custom_metadata.insert(
+                format!("{}{}", ANNOTATION_METADATA_PREFIX, name),
+                value.clone(),
+            );
+        }
+    }
+}
+
+/// Parse a delete-at value given either as an RFC 3339 timestamp or as Unix seconds
+pub fn parse_delete_at(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<i64>() {
+        return DateTime::from_timestamp(seconds, 0);
+    }
+
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Metadata given to uploads under a key prefix that do not set it themselves
+///
+/// The prefix is matched against the full key, bucket included, so
+/// `assets/static/` covers the `static/` folder of the `assets` bucket.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DefaultMetadata {
+    pub prefix: String,
+    pub content_type: Option<String>,
+    pub custom_metadata: HashMap<String, String>,
+}
+
+impl DefaultMetadata {
+    /// Defaults for keys starting with `prefix`, initially setting nothing
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Give matching uploads without a content type this one
+    pub fn with_content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    /// Give matching uploads without the metadata entry `name` this value
+    pub fn with_metadata(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.custom_metadata.insert(name.into(), value.into());
+        self
+    }
+}
+
+/// Fill in the content type and metadata an upload to `key` leaves unset
+///
+/// Each value comes from the defaults with the longest prefix matching `key`
+/// that set it. Metadata names are compared case-insensitively, so a client's
+/// `Cache-Control` overrides a default `cache-control`.
+pub fn apply_default_metadata(
+    defaults: &[DefaultMetadata],
+    key: &ObjectKey,
+    content_type: &mut Option<String>,
+    custom_metadata: &mut HashMap<String, String>,
+) {
+    let mut matching: Vec<&DefaultMetadata> = defaults
+        .iter()
+        .filter(|defaults| key.as_str().starts_with(&defaults.prefix))
+        .collect();
+    matching.sort_by_key(|defaults| std::cmp::Reverse(defaults.prefix.len()));
+
+    for defaults in matching {
+        if content_type.is_none() {
+            content_type.clone_from(&defaults.content_type);
+        }
+        for (name, value) in &defaults.custom_metadata {
+            if !custom_metadata
+                .keys()
+                .any(|existing| existing.eq_ignore_ascii_case(name))
+            {
+                custom_metadata.insert(name.clone(), value.clone());
+            }
+        }
+    }
+}
+
 /// Represents an object in the storage system
 #[derive(Debug, Clone)]
 pub struct StorageObject {
@@ -56,6 +319,8 @@ pub struct ObjectVersionInfo {
     pub etag: Option<String>,
     pub is_latest: bool,
     pub deleted: bool,
+    pub labels: BTreeSet<String>,
+    pub annotations: HashMap<String, String>,
 }
 
 /// List of versions for an object
@@ -64,3 +329,377 @@ pub struct ObjectVersionList {
     pub key: ObjectKey,
     pub versions: Vec<ObjectVersionInfo>,
 }
+
+impl ObjectVersionList {
+    /// Keep only the versions carrying `label`
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.versions
+            .retain(|version| version.labels.contains(label));
+        self
+    }
+
+    /// One page of the versions, newest first, starting after `marker`
+    ///
+    /// The latest version always sorts first. Returns None when `marker` is not
+    /// one of the versions.
+    pub fn paginate(
+        mut self,
+        marker: Option<&VersionId>,
+        limit: usize,
+    ) -> Option<ObjectVersionPage> {
+        self.sort_newest_first();
+
+        let start = match marker {
+            Some(marker) => {
+                self.versions
+                    .iter()
+                    .position(|version| &version.version_id == marker)?
+                    + 1
+            }
+            None => 0,
+        };
+        Some(self.page(start, limit))
+    }
+
+    /// One page of versions with time-ordered IDs, newest first, starting after
+    /// `marker`
+    ///
+    /// Versions are ordered by ID alone. `marker` need not be one of the
+    /// versions, so a listing can go on past a version deleted since the
+    /// previous page.
+    pub fn paginate_by_version_id(
+        mut self,
+        marker: Option<&VersionId>,
+        limit: usize,
+    ) -> ObjectVersionPage {
+        self.versions
+            .sort_by(|a, b| b.version_id.as_str().cmp(a.version_id.as_str()));
+
+        let start = marker.map_or(0, |marker| {
+            self.versions
+                .partition_point(|version| version.version_id.as_str() >= marker.as_str())
+        });
+        self.page(start, limit)
+    }
+
+    /// One page of the versions in `order`, newest first, starting after
+    /// `marker`
+    ///
+    /// Returns None when `marker` is not one of the versions and `order`
+    /// needs it to be.
+    pub fn paginate_in(
+        self,
+        order: VersionOrder,
+        marker: Option<&VersionId>,
+        limit: usize,
+    ) -> Option<ObjectVersionPage> {
+        match order {
+            VersionOrder::WriteTime => self.paginate(marker, limit),
+            VersionOrder::VersionId => Some(self.paginate_by_version_id(marker, limit)),
+        }
+    }
+
+    /// The page of up to `limit` of the sorted versions from index `start`
+    fn page(self, start: usize, limit: usize) -> ObjectVersionPage {
+        let total_count = self.versions.len();
+        let versions = self.versions.into_iter().skip(start).take(limit).collect();
+        ObjectVersionPage::new(self.key, versions, start, total_count)
+    }
+
+    /// The version `steps` versions before the latest, ignoring deleted versions
+    ///
+    /// Zero steps gives the latest version. Returns None when the object has
+    /// fewer earlier versions.
+    pub fn previous_version(mut self, steps: usize) -> Option<ObjectVersionInfo> {
+        self.versions.retain(|version| !version.deleted);
+        self.sort_newest_first();
+        self.versions.into_iter().nth(steps)
+    }
+
+    fn sort_newest_first(&mut self) {
+        self.versions.sort_by(|a, b| {
+            b.is_latest
+                .cmp(&a.is_latest)
+                .then_with(|| b.last_modified.cmp(&a.last_modified))
+                .then_with(|| b.version_id.as_str().cmp(a.version_id.as_str()))
+        });
+    }
+}
+
+/// A page of an object's versions
+#[derive(Debug, Clone)]
+pub struct ObjectVersionPage {
+    pub key: ObjectKey,
+    pub versions: Vec<ObjectVersionInfo>,
+    /// Number of versions across all pages
+    pub total_count: usize,
+    /// Whether more versions follow this page
+    pub is_truncated: bool,
+    /// Marker to pass for the next page, set when the page is truncated
+    pub next_version_id_marker: Option<VersionId>,
+}
+
+impl ObjectVersionPage {
+    /// The page holding `versions`, which start at index `start` of a
+    /// listing of `total_count` versions
+    pub fn new(
+        key: ObjectKey,
+        versions: Vec<ObjectVersionInfo>,
+        start: usize,
+        total_count: usize,
+    ) -> Self {
+        let is_truncated = start + versions.len() < total_count;
+        let next_version_id_marker = is_truncated
+            .then(|| versions.last().map(|version| version.version_id.clone()))
+            .flatten();
+
+        Self {
+            key,
+            versions,
+            total_count,
+            is_truncated,
+            next_version_id_marker,
+        }
+    }
+}
+
+/// How a listing orders an object's versions, newest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VersionOrder {
+    /// By write time, with the latest version first
+    #[default]
+    WriteTime,
+    /// By version ID, for IDs that sort in the order they were generated
+    VersionId,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata() -> ObjectMetadata {
+        ObjectMetadata {
+            content_type: None,
+            content_length: 0,
+            etag: None,
+            last_modified: std::time::SystemTime::now(),
+            custom_metadata: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_delete_at() {
+        let expected = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        assert_eq!(parse_delete_at("1700000000"), Some(expected));
+        assert_eq!(parse_delete_at("2023-11-14T22:13:20Z"), Some(expected));
+        assert_eq!(parse_delete_at("tomorrow"), None);
+    }
+
+    #[test]
+    fn test_apply_default_metadata() {
+        let defaults = [
+            DefaultMetadata::new("assets/")
+                .with_content_type("application/octet-stream")
+                .with_metadata("cache-control", "no-cache")
+                .with_metadata("x-team", "web"),
+            DefaultMetadata::new("assets/static/")
+                .with_metadata("cache-control", "max-age=31536000"),
+        ];
+        let key = ObjectKey::new("assets/static/app.js".to_string()).unwrap();
+
+        let mut content_type = None;
+        let mut custom_metadata = HashMap::new();
+        apply_default_metadata(&defaults, &key, &mut content_type, &mut custom_metadata);
+        assert_eq!(content_type.as_deref(), Some("application/octet-stream"));
+        assert_eq!(custom_metadata["cache-control"], "max-age=31536000");
+        assert_eq!(custom_metadata["x-team"], "web");
+
+        // Values the client set are kept
+        let mut content_type = Some("text/javascript".to_string());
+        let mut custom_metadata =
+            HashMap::from([("Cache-Control".to_string(), "no-store".to_string())]);
+        apply_default_metadata(&defaults, &key, &mut content_type, &mut custom_metadata);
+        assert_eq!(content_type.as_deref(), Some("text/javascript"));
+        assert_eq!(custom_metadata.len(), 2);
+        assert_eq!(custom_metadata["Cache-Control"], "no-store");
+
+        let other = ObjectKey::new("uploads/app.js".to_string()).unwrap();
+        let mut content_type = None;
+        let mut custom_metadata = HashMap::new();
+        apply_default_metadata(&defaults, &other, &mut content_type, &mut custom_metadata);
+        assert!(content_type.is_none() && custom_metadata.is_empty());
+    }
+
+    #[test]
+    fn test_set_and_clear_delete_at() {
+        let mut metadata = metadata();
+        assert!(metadata.delete_at().is_none());
+
+        let delete_at = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        metadata.set_delete_at(Some(delete_at));
+        assert_eq!(metadata.delete_at(), Some(delete_at));
+
+        metadata.set_delete_at(None);
+        assert!(metadata.delete_at().is_none());
+    }
+
+    #[test]
+    fn test_labels_and_annotations() {
+        let mut metadata = metadata();
+        metadata
+            .custom_metadata
+            .insert("owner".to_string(), "ops".to_string());
+
+        let labels = BTreeSet::from(["approved".to_string(), "release-1.4".to_string()]);
+        let annotations = HashMap::from([("ticket".to_string(), "OPS-12".to_string())]);
+        metadata.set_labels(&labels);
+        metadata.set_annotations(&annotations);
+        assert_eq!(metadata.labels(), labels);
+        assert_eq!(metadata.annotations(), annotations);
+
+        metadata.set_labels(&BTreeSet::new());
+        metadata.set_annotations(&HashMap::new());
+        assert!(metadata.labels().is_empty());
+        assert!(metadata.annotations().is_empty());
+        assert_eq!(metadata.custom_metadata.len(), 1);
+    }
+
+    #[test]
+    fn test_retention_blocks_deletion() {
+        let now = std::time::SystemTime::now();
+        let later = now + std::time::Duration::from_secs(3600);
+        let mut metadata = metadata();
+        assert!(metadata.retention().is_none());
+
+        let governance = VersionRetentionPolicy {
+            mode: RetentionMode::Governance,
+            retain_until: Some(later),
+            legal_hold: false,
+        };
+        metadata.set_retention(Some(&governance));
+        assert!(metadata.deletion_blocked_reason(now, false).is_some());
+        assert!(metadata.deletion_blocked_reason(now, true).is_none());
+        assert!(metadata.deletion_blocked_reason(later, false).is_none());
+
+        let compliance = VersionRetentionPolicy {
+            mode: RetentionMode::Compliance,
+            ..governance.clone()
+        };
+        metadata.set_retention(Some(&compliance));
+        assert_eq!(
+            metadata.retention().unwrap().mode,
+            RetentionMode::Compliance
+        );
+        assert!(metadata.deletion_blocked_reason(now, true).is_some());
+        assert!(
+            compliance
+                .replacement_blocked_reason(Some(&governance), now, true)
+                .is_some()
+        );
+        assert!(
+            governance
+                .replacement_blocked_reason(None, now, false)
+                .is_some()
+        );
+        assert!(
+            governance
+                .replacement_blocked_reason(None, now, true)
+                .is_none()
+        );
+
+        metadata.set_retention(None);
+        assert!(metadata.custom_metadata.is_empty());
+    }
+
+    #[test]
+    fn test_paginate_versions() {
+        let key = ObjectKey::new("file.txt".to_string()).unwrap();
+        let start = std::time::SystemTime::UNIX_EPOCH;
+        let versions = (0..5u64)
+            .map(|i| ObjectVersionInfo {
+                version_id: VersionId::new(format!("v{}", i)).unwrap(),
+                last_modified: start + std::time::Duration::from_secs(i),
+                size: i,
+                etag: None,
+                is_latest: i == 4,
+                deleted: false,
+                labels: BTreeSet::new(),
+                annotations: HashMap::new(),
+            })
+            .collect();
+        let list = ObjectVersionList { key, versions };
+
+        let page = list.clone().paginate(None, 2).unwrap();
+        let ids: Vec<&str> = page
+            .versions
+            .iter()
+            .map(|v| v.version_id.as_str())
+            .collect();
+        assert_eq!(ids, ["v4", "v3"]);
+        assert_eq!(page.total_count, 5);
+        assert!(page.is_truncated);
+
+        let marker = page.next_version_id_marker.unwrap();
+        let page = list.clone().paginate(Some(&marker), 10).unwrap();
+        assert_eq!(page.versions.len(), 3);
+        assert!(!page.is_truncated);
+        assert_eq!(page.next_version_id_marker, None);
+
+        let unknown = VersionId::new("missing".to_string()).unwrap();
+        assert!(list.clone().paginate(Some(&unknown), 2).is_none());
+
+        assert_eq!(
+            list.clone()
+                .previous_version(2)
+                .unwrap()
+                .version_id
+                .as_str(),
+            "v2"
+        );
+        assert!(list.previous_version(5).is_none());
+    }
+
+    #[test]
+    fn test_paginate_versions_by_id() {
+        let key = ObjectKey::new("file.txt".to_string()).unwrap();
+        // Time-ordered IDs, written within the same second
+        let versions = ["01A", "01B", "01C", "01D"]
+            .into_iter()
+            .map(|id| ObjectVersionInfo {
+                version_id: VersionId::new(id.to_string()).unwrap(),
+                last_modified: std::time::SystemTime::UNIX_EPOCH,
+                size: 0,
+                etag: None,
+                is_latest: id == "01D",
+                deleted: false,
+                labels: BTreeSet::new(),
+                annotations: HashMap::new(),
+            })
+            .collect();
+        let list = ObjectVersionList { key, versions };
+
+        let page = list.clone().paginate_by_version_id(None, 2);
+        let ids: Vec<&str> = page
+            .versions
+            .iter()
+            .map(|v| v.version_id.as_str())
+            .collect();
+        assert_eq!(ids, ["01D", "01C"]);
+        assert!(page.is_truncated);
+        assert_eq!(page.next_version_id_marker.unwrap().as_str(), "01C");
+
+        // The marker's version was deleted after the first page
+        let mut list = list;
+        list.versions.retain(|v| v.version_id.as_str() != "01C");
+        let marker = VersionId::new("01C".to_string()).unwrap();
+        let page = list.paginate_by_version_id(Some(&marker), 10);
+        let ids: Vec<&str> = page
+            .versions
+            .iter()
+            .map(|v| v.version_id.as_str())
+            .collect();
+        assert_eq!(ids, ["01B", "01A"]);
+        assert_eq!(page.total_count, 3);
+        assert!(!page.is_truncated);
+    }
+}
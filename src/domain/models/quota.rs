@@ -0,0 +1,207 @@
+use crate::domain::value_objects::{BucketName, ObjectKey};
+
+/// Share of a limit, in percent, at which writes start to be warned about
+pub const DEFAULT_SOFT_LIMIT_PERCENT: u8 = 80;
+
+/// What a prefix quota limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaKind {
+    Objects,
+    Bytes,
+}
+
+impl QuotaKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuotaKind::Objects => "objects",
+            QuotaKind::Bytes => "bytes",
+        }
+    }
+}
+
+impl std::fmt::Display for QuotaKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Limits on the number and total size of the objects under a key prefix
+///
+/// The prefix is matched against the full key, bucket included, so several
+/// tenants sharing a bucket can each be given their own limits.
+///
+/// The limits are hard: writes beyond them are refused. Past the soft limit,
+/// a share of each hard limit, writes still succeed but are warned about.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PrefixQuota {
+    pub prefix: String,
+    pub max_objects: Option<u64>,
+    pub max_bytes: Option<u64>,
+    /// Percentage of each limit at which writes are warned about, if at all
+    pub soft_limit_percent: Option<u8>,
+}
+
+impl PrefixQuota {
+    /// A quota on `prefix`, initially without limits
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            soft_limit_percent: Some(DEFAULT_SOFT_LIMIT_PERCENT),
+            ..Default::default()
+        }
+    }
+
+    /// A quota on every object of `bucket`
+    pub fn for_bucket(bucket: &BucketName) -> Self {
+        Self::new(format!("{}/", bucket))
+    }
+
+    /// Allow at most `max_objects` objects under the prefix
+    pub fn with_max_objects(mut self, max_objects: u64) -> Self {
+        self.max_objects = Some(max_objects);
+        self
+    }
+
+    /// Allow at most `max_bytes` bytes of objects under the prefix
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Warn about writes once usage reaches `percent` of a limit, or never
+    /// with `None`
+    pub fn with_soft_limit_percent(mut self, percent: Option<u8>) -> Self {
+        self.soft_limit_percent = percent.map(|percent| percent.min(100));
+        self
+    }
+
+    /// Whether the quota counts `key`
+    pub fn covers(&self, key: &ObjectKey) -> bool {
+        key.as_str().starts_with(&self.prefix)
+    }
+
+    /// The first limit `usage` goes beyond, with the usage and the limit
+    pub fn exceeded_by(&self, usage: &PrefixUsage) -> Option<(QuotaKind, u64, u64)> {
+        let limits = [
+            (QuotaKind::Objects, usage.object_count, self.max_objects),
+            (QuotaKind::Bytes, usage.total_bytes, self.max_bytes),
+        ];
+        limits.into_iter().find_map(|(kind, used, limit)| {
+            let limit = limit?;
+            (used > limit).then_some((kind, used, limit))
+        })
+    }
+
+    /// The first soft limit `usage` has reached, with the usage and the limit
+    pub fn soft_limit_reached_by(&self, usage: &PrefixUsage) -> Option<QuotaWarning> {
+        let percent = u64::from(self.soft_limit_percent?);
+        let limits = [
+            (QuotaKind::Objects, usage.object_count, self.max_objects),
+            (QuotaKind::Bytes, usage.total_bytes, self.max_bytes),
+        ];
+        limits.into_iter().find_map(|(kind, used, limit)| {
+            let limit = limit?;
+            let soft_limit = limit.saturating_mul(percent) / 100;
+            (used >= soft_limit).then(|| QuotaWarning {
+                prefix: self.prefix.clone(),
+                kind,
+                used,
+                soft_limit,
+                limit,
+            })
+        })
+    }
+
+    /// Whether `usage` has reached one of the limits, leaving no room to write
+    pub fn limit_reached_by(&self, usage: &PrefixUsage) -> bool {
+        self.max_objects
+            .is_some_and(|limit| usage.object_count >= limit)
+            || self
+                .max_bytes
+                .is_some_and(|limit| usage.total_bytes >= limit)
+    }
+}
+
+/// Usage of a prefix past the soft limit of its quota
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuotaWarning {
+    pub prefix: String,
+    pub kind: QuotaKind,
+    pub used: u64,
+    pub soft_limit: u64,
+    pub limit: u64,
+}
+
+impl std::fmt::Display for QuotaWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Quota for prefix '{}' nearly used: {} {} of {} allowed",
+            self.prefix, self.used, self.kind, self.limit
+        )
+    }
+}
+
+/// Objects stored under a key prefix, with the quota configured for it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrefixUsage {
+    pub prefix: String,
+    pub object_count: u64,
+    pub total_bytes: u64,
+    pub quota: Option<PrefixQuota>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quota_limits() {
+        let quota = PrefixQuota::new("shared/tmp/")
+            .with_max_objects(2)
+            .with_max_bytes(100);
+        assert!(quota.covers(&ObjectKey::new("shared/tmp/a".to_string()).unwrap()));
+        assert!(!quota.covers(&ObjectKey::new("shared/data/a".to_string()).unwrap()));
+
+        let mut usage = PrefixUsage {
+            prefix: quota.prefix.clone(),
+            object_count: 2,
+            total_bytes: 100,
+            quota: None,
+        };
+        assert_eq!(quota.exceeded_by(&usage), None);
+
+        usage.total_bytes = 101;
+        assert_eq!(
+            quota.exceeded_by(&usage),
+            Some((QuotaKind::Bytes, 101, 100))
+        );
+
+        usage.object_count = 3;
+        assert_eq!(quota.exceeded_by(&usage), Some((QuotaKind::Objects, 3, 2)));
+        assert_eq!(PrefixQuota::new("shared/").exceeded_by(&usage), None);
+    }
+
+    #[test]
+    fn test_soft_limits() {
+        let quota = PrefixQuota::new("shared/").with_max_bytes(100);
+        let mut usage = PrefixUsage {
+            prefix: quota.prefix.clone(),
+            object_count: 1,
+            total_bytes: 79,
+            quota: None,
+        };
+        assert_eq!(quota.soft_limit_reached_by(&usage), None);
+        assert!(!quota.limit_reached_by(&usage));
+
+        usage.total_bytes = 80;
+        let warning = quota.soft_limit_reached_by(&usage).unwrap();
+        assert_eq!((warning.kind, warning.used), (QuotaKind::Bytes, 80));
+        assert_eq!((warning.soft_limit, warning.limit), (80, 100));
+
+        usage.total_bytes = 100;
+        assert!(quota.limit_reached_by(&usage));
+        let quota = quota.with_soft_limit_percent(None);
+        assert_eq!(quota.soft_limit_reached_by(&usage), None);
+    }
+}
@@ -0,0 +1,77 @@
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+use crate::domain::{
+    errors::ValidationError,
+    value_objects::{ObjectKey, VersionId},
+};
+
+/// Prefix under which the data of transactions is staged until they commit
+pub const TRANSACTION_STAGING_PREFIX: &str = ".transactions";
+
+/// An object uploaded into a transaction, stored under its staging key until
+/// the transaction commits
+#[derive(Debug, Clone, PartialEq)]
+pub struct StagedObject {
+    pub key: ObjectKey,
+    pub staged_key: ObjectKey,
+    /// Version the object becomes on commit, fixed when it is staged so that
+    /// committing again writes the same version
+    pub version_id: VersionId,
+    pub size: u64,
+    pub etag: String,
+    pub content_type: Option<String>,
+    pub custom_metadata: HashMap<String, String>,
+}
+
+/// A set of uploads that become visible together or not at all
+///
+/// The staged objects form the transaction's manifest. Marking the manifest
+/// committed is the single write that decides the outcome: before it an
+/// interrupted commit leaves no object visible, after it the commit is rolled
+/// forward until every object is in place.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectTransaction {
+    pub transaction_id: String,
+    pub started: DateTime<Utc>,
+    pub objects: Vec<StagedObject>,
+    /// When the manifest was marked committed, the time every object's new
+    /// version is given
+    pub committed_at: Option<DateTime<Utc>>,
+}
+
+impl ObjectTransaction {
+    /// Whether the manifest is marked committed
+    pub fn is_committed(&self) -> bool {
+        self.committed_at.is_some()
+    }
+
+    /// Key the data of `key` is staged under within the transaction
+    pub fn staged_key(&self, key: &ObjectKey) -> Result<ObjectKey, ValidationError> {
+        ObjectKey::new(format!(
+            "{}/{}/{}",
+            TRANSACTION_STAGING_PREFIX, self.transaction_id, key
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_staged_keys_are_namespaced_by_transaction() {
+        let transaction = ObjectTransaction {
+            transaction_id: "tx-1".to_string(),
+            started: Utc::now(),
+            objects: Vec::new(),
+            committed_at: None,
+        };
+        let key = ObjectKey::new("datasets/2025/part-0.parquet".to_string()).unwrap();
+
+        assert_eq!(
+            transaction.staged_key(&key).unwrap().as_str(),
+            ".transactions/tx-1/datasets/2025/part-0.parquet"
+        );
+    }
+}
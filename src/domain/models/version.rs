@@ -1,5 +1,22 @@
+use crate::domain::errors::ValidationError;
 use crate::domain::value_objects::{ObjectKey, VersionId};
-use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeSet, HashMap};
+
+/// Maximum length of a version label or annotation name
+pub const MAX_LABEL_LENGTH: usize = 64;
+
+/// Maximum length of a version annotation value
+pub const MAX_ANNOTATION_VALUE_LENGTH: usize = 1024;
+
+/// Prefix under which the data of every version is kept, while the latest
+/// version is also written to the object's own key
+pub const VERSION_STAGING_PREFIX: &str = ".versions";
+
+/// Key the data of version `version_id` of `key` is kept under
+pub fn version_key(key: &ObjectKey, version_id: &VersionId) -> Result<ObjectKey, ValidationError> {
+    ObjectKey::new(format!("{}/{}/{}", VERSION_STAGING_PREFIX, version_id, key))
+}
 
 /// Configuration for versioning behavior
 #[derive(Debug, Clone, PartialEq)]
@@ -24,6 +41,10 @@ pub struct VersionMetadata {
     pub created_by: Option<String>,
     pub delete_marker: bool,
     pub tags: HashMap<String, String>,
+    /// Human-readable labels such as `release-1.4` or `approved`
+    pub labels: BTreeSet<String>,
+    /// Free-form key/value annotations
+    pub annotations: HashMap<String, String>,
 }
 
 /// Represents a version transition (for lifecycle policies)
@@ -64,17 +85,100 @@ pub struct VersionRetentionPolicy {
     pub legal_hold: bool,
 }
 
+impl VersionRetentionPolicy {
+    /// Why this policy forbids deleting the version at `now`, None if it allows it
+    ///
+    /// Legal holds and compliance retention always apply. Governance retention
+    /// is lifted when `bypass_governance` is set.
+    pub fn deletion_blocked_reason(
+        &self,
+        now: std::time::SystemTime,
+        bypass_governance: bool,
+    ) -> Option<String> {
+        if self.legal_hold {
+            return Some("version is under legal hold".to_string());
+        }
+
+        let until = self.retain_until.filter(|until| now < *until)?;
+        match self.mode {
+            RetentionMode::Governance if bypass_governance => None,
+            _ => Some(format!(
+                "version is under {} retention until {}",
+                self.mode.as_str(),
+                DateTime::<Utc>::from(until).to_rfc3339()
+            )),
+        }
+    }
+
+    /// Why replacing this policy with `new` at `now` is forbidden, None if allowed
+    ///
+    /// Retention still in force cannot be shortened or removed, and compliance
+    /// retention cannot be downgraded to governance. Governance retention can
+    /// be weakened when `bypass_governance` is set. Legal holds may be placed
+    /// and lifted freely.
+    pub fn replacement_blocked_reason(
+        &self,
+        new: Option<&VersionRetentionPolicy>,
+        now: std::time::SystemTime,
+        bypass_governance: bool,
+    ) -> Option<String> {
+        let until = self.retain_until.filter(|until| now < *until)?;
+        let weakened = match new {
+            Some(new) => {
+                new.retain_until.is_none_or(|new_until| new_until < until)
+                    || (self.mode == RetentionMode::Compliance
+                        && new.mode != RetentionMode::Compliance)
+            }
+            None => true,
+        };
+
+        match self.mode {
+            _ if !weakened => None,
+            RetentionMode::Governance if bypass_governance => None,
+            _ => Some(format!(
+                "{} retention until {} cannot be shortened",
+                self.mode.as_str(),
+                DateTime::<Utc>::from(until).to_rfc3339()
+            )),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum RetentionMode {
+    /// Protects versions unless the deleter may bypass governance retention
     Governance,
+    /// Protects versions from everyone until the retention date
     Compliance,
 }
 
+impl RetentionMode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            RetentionMode::Governance => "GOVERNANCE",
+            RetentionMode::Compliance => "COMPLIANCE",
+        }
+    }
+
+    /// Parse a mode as S3 spells it, ignoring case
+    pub fn parse(value: &str) -> Option<Self> {
+        if value.eq_ignore_ascii_case("GOVERNANCE") {
+            Some(RetentionMode::Governance)
+        } else if value.eq_ignore_ascii_case("COMPLIANCE") {
+            Some(RetentionMode::Compliance)
+        } else {
+            None
+        }
+    }
+}
+
 /// Request to delete a specific version
 #[derive(Debug, Clone)]
 pub struct DeleteVersionRequest {
     pub key: ObjectKey,
     pub version_id: VersionId,
+    /// Delete despite governance retention, if the caller is allowed to
+    pub bypass_governance_retention: bool,
 }
 
 /// Result of a version deletion
@@ -84,3 +188,105 @@ pub struct DeleteVersionResult {
     pub version_id: VersionId,
     pub delete_marker_created: bool,
 }
+
+/// Result of rolling an object back to an earlier version
+#[derive(Debug, Clone)]
+pub struct RollbackVersionResult {
+    pub key: ObjectKey,
+    /// Version whose content was promoted
+    pub restored_version_id: VersionId,
+    /// New latest version holding the restored content
+    pub new_version_id: VersionId,
+}
+
+/// Request to replace the labels and annotations of a version
+#[derive(Debug, Clone)]
+pub struct LabelVersionRequest {
+    pub key: ObjectKey,
+    pub version_id: VersionId,
+    pub labels: BTreeSet<String>,
+    pub annotations: HashMap<String, String>,
+}
+
+impl LabelVersionRequest {
+    /// Check label and annotation names are 1 to 64 letters, digits, `.`, `_`
+    /// or `-`, and annotation values are at most 1024 bytes
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        for label in &self.labels {
+            validate_label_name("label", label)?;
+        }
+        for (name, value) in &self.annotations {
+            validate_label_name("annotation", name)?;
+            if value.len() > MAX_ANNOTATION_VALUE_LENGTH {
+                return Err(ValidationError::InvalidField {
+                    field: format!("annotation {}", name),
+                    value: format!("{} bytes", value.len()),
+                    expected: format!("at most {} bytes", MAX_ANNOTATION_VALUE_LENGTH),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+fn validate_label_name(field: &str, name: &str) -> Result<(), ValidationError> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_LABEL_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+    if valid {
+        Ok(())
+    } else {
+        Err(ValidationError::InvalidField {
+            field: field.to_string(),
+            value: name.to_string(),
+            expected: format!("1 to {} letters, digits, '.', '_' or '-'", MAX_LABEL_LENGTH),
+        })
+    }
+}
+
+/// Versions of an object selected for bulk deletion
+#[derive(Debug, Clone, PartialEq)]
+pub enum VersionSelection {
+    /// Noncurrent versions last modified before this time
+    OlderThan(DateTime<Utc>),
+    /// Every version except the latest
+    Noncurrent,
+    /// The listed versions, including the latest if it is listed
+    Explicit(Vec<VersionId>),
+}
+
+/// Request to delete many versions of an object at once
+#[derive(Debug, Clone)]
+pub struct BulkDeleteVersionsRequest {
+    pub key: ObjectKey,
+    pub selection: VersionSelection,
+    /// Delete despite governance retention, if the caller is allowed to
+    pub bypass_governance_retention: bool,
+}
+
+/// Outcome of deleting one version in a bulk deletion
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionDeletionOutcome {
+    pub version_id: VersionId,
+    /// Why the version was not deleted, None when it was
+    pub error: Option<String>,
+}
+
+/// Result of a bulk version deletion, with one outcome per selected version
+#[derive(Debug, Clone)]
+pub struct BulkDeleteVersionsResult {
+    pub key: ObjectKey,
+    pub outcomes: Vec<VersionDeletionOutcome>,
+}
+
+impl BulkDeleteVersionsResult {
+    /// Number of versions that were deleted
+    pub fn deleted_count(&self) -> usize {
+        self.outcomes
+            .iter()
+            .filter(|outcome| outcome.error.is_none())
+            .count()
+    }
+}
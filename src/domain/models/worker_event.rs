@@ -0,0 +1,50 @@
+use chrono::{DateTime, Utc};
+
+/// Change in whether a background worker runs its passes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkerEventType {
+    /// The worker stopped running passes because a backend is unavailable
+    Paused,
+    /// The worker runs passes again after its backends recovered
+    Resumed,
+}
+
+impl WorkerEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WorkerEventType::Paused => "WorkerPaused",
+            WorkerEventType::Resumed => "WorkerResumed",
+        }
+    }
+}
+
+/// A background worker pausing for an unavailable backend, or resuming
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkerEvent {
+    pub event_type: WorkerEventType,
+    pub time: DateTime<Utc>,
+    /// Name of the worker, e.g. `lifecycle`
+    pub worker: String,
+    /// Why the worker paused; `None` when it resumed
+    pub reason: Option<String>,
+}
+
+impl WorkerEvent {
+    pub fn paused(worker: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            event_type: WorkerEventType::Paused,
+            time: Utc::now(),
+            worker: worker.into(),
+            reason: Some(reason.into()),
+        }
+    }
+
+    pub fn resumed(worker: impl Into<String>) -> Self {
+        Self {
+            event_type: WorkerEventType::Resumed,
+            time: Utc::now(),
+            worker: worker.into(),
+            reason: None,
+        }
+    }
+}
@@ -26,7 +26,7 @@ impl BucketName {
         if !value
             .chars()
             .next()
-            .map_or(false, |c| c.is_ascii_lowercase() || c.is_ascii_digit())
+            .is_some_and(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
         {
             return Err(ValidationError::BucketNameInvalidStart);
         }
@@ -34,7 +34,7 @@ impl BucketName {
         if !value
             .chars()
             .last()
-            .map_or(false, |c| c.is_ascii_lowercase() || c.is_ascii_digit())
+            .is_some_and(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
         {
             return Err(ValidationError::BucketNameInvalidEnd);
         }
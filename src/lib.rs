@@ -1,8 +1,23 @@
+//! Object store server: an HTTP API with versioning and lifecycle management
+//! over `object_store` backends
+//!
+//! For `wasm32` targets only the [`client`], the [`domain`] types and the HTTP
+//! DTOs are built, so browser apps can use the client without the server.
+
 pub mod adapters;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod app;
+#[cfg(feature = "client")]
+pub mod client;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod config;
 pub mod domain;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod ports;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod services;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod testing;
 
 // Re-export key types for convenience
 
@@ -29,12 +44,14 @@ pub use domain::{
 };
 
 // Port types - interfaces for external systems
+#[cfg(not(target_arch = "wasm32"))]
 pub use ports::{
     // Repository ports
     LifecycleRepository,
     ObjectRepository,
     // Service ports
     LifecycleService,
+    ObjectService,
     VersioningService,
     // Storage ports
     storage::{
@@ -50,27 +67,34 @@ pub use ports::{
 };
 
 // Service implementations - business logic
+#[cfg(not(target_arch = "wasm32"))]
 pub use services::{
     LifecycleServiceImpl, ObjectServiceBuilder, ObjectServiceImpl, VersioningServiceImpl,
 };
 
 // Application factory and configuration
+#[cfg(not(target_arch = "wasm32"))]
 pub use app::{
     AppBuilder, AppConfig, AppDependencies, AppError, AppServices, RepositoryBackend,
     StorageBackend, create_app_from_env, create_in_memory_app, create_minio_app, create_s3_app,
 };
+#[cfg(not(target_arch = "wasm32"))]
+pub use config::{EnvConfig, Profile};
 
 // Adapter types - infrastructure implementations
+#[cfg(not(target_arch = "wasm32"))]
 pub use adapters::outbound::storage::{
     S3ObjectStoreAdapter, VersionedS3ObjectStoreAdapter,
 };
 
 // Public facade for easy construction
+#[cfg(not(target_arch = "wasm32"))]
 pub mod prelude {
     pub use crate::{
         AppBuilder, AppServices, BucketName, LifecycleRepository,
-        LifecycleService, LifecycleServiceImpl, ObjectKey, ObjectRepository, ObjectServiceImpl,
-        ObjectStore, VersionId, S3ObjectStoreAdapter, VersionedS3ObjectStoreAdapter, VersionedObjectStore,
+        LifecycleService, LifecycleServiceImpl, ObjectKey, ObjectRepository, ObjectService,
+        ObjectServiceImpl, ObjectStore, VersionId, S3ObjectStoreAdapter,
+        VersionedS3ObjectStoreAdapter, VersionedObjectStore,
         VersioningService, create_in_memory_app, create_minio_app, create_s3_app,
     };
 }
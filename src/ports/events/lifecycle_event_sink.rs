@@ -0,0 +1,12 @@
+use crate::domain::{errors::StorageResult, models::LifecycleEvent};
+use async_trait::async_trait;
+
+/// Receiver of the changes lifecycle processing makes, so downstream systems
+/// can react to retention-driven deletions as well as to client requests
+///
+/// Events are published after the change was made. A failure to publish is
+/// reported but does not undo or fail the change.
+#[async_trait]
+pub trait LifecycleEventSink: Send + Sync + 'static {
+    async fn publish(&self, event: &LifecycleEvent) -> StorageResult<()>;
+}
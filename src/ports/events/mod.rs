@@ -0,0 +1,5 @@
+mod lifecycle_event_sink;
+mod worker_event_sink;
+
+pub use lifecycle_event_sink::LifecycleEventSink;
+pub use worker_event_sink::WorkerEventSink;
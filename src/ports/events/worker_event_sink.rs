@@ -0,0 +1,11 @@
+use crate::domain::{errors::StorageResult, models::WorkerEvent};
+use async_trait::async_trait;
+
+/// Receiver of background workers pausing and resuming as their backends
+/// become unavailable and recover
+///
+/// A failure to publish is reported but does not hold up the worker.
+#[async_trait]
+pub trait WorkerEventSink: Send + Sync + 'static {
+    async fn publish(&self, event: &WorkerEvent) -> StorageResult<()>;
+}
@@ -0,0 +1,3 @@
+mod object_interceptor;
+
+pub use object_interceptor::{ObjectInterceptor, PutContext};
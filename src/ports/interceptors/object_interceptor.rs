@@ -0,0 +1,37 @@
+use crate::domain::{errors::StorageResult, models::ObjectMetadata, value_objects::ObjectKey};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use tokio::io::AsyncRead;
+
+/// An object upload on its way into the store
+///
+/// Interceptors may replace the body with a wrapping reader to inspect or
+/// transform the data as it streams through, and may adjust the metadata.
+pub struct PutContext {
+    pub content_type: Option<String>,
+    pub custom_metadata: HashMap<String, String>,
+    pub body: Box<dyn AsyncRead + Send + Unpin>,
+}
+
+/// Hook into object uploads and downloads without changing the server
+///
+/// Interceptors run in the order they were registered. Returning an error from
+/// any hook aborts the operation with that error. Every hook defaults to a no-op,
+/// so implementations only override the ones they need.
+#[async_trait]
+pub trait ObjectInterceptor: Send + Sync + 'static {
+    /// Called before an object is stored, with the chance to reject or rewrite it
+    async fn pre_put(&self, _key: &ObjectKey, put: PutContext) -> StorageResult<PutContext> {
+        Ok(put)
+    }
+
+    /// Called after an object and its metadata have been stored
+    async fn post_put(&self, _key: &ObjectKey, _metadata: &ObjectMetadata) -> StorageResult<()> {
+        Ok(())
+    }
+
+    /// Called before an object is returned to a client, with the chance to refuse it
+    async fn pre_get(&self, _key: &ObjectKey, _metadata: &ObjectMetadata) -> StorageResult<()> {
+        Ok(())
+    }
+}
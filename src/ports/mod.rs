@@ -1,12 +1,22 @@
+pub mod events;
+pub mod interceptors;
 pub mod repositories;
+pub mod runtime;
 pub mod services;
 pub mod storage;
 
 // Re-export all port traits for convenience
-pub use repositories::{LifecycleRepository, ObjectRepository};
+pub use events::LifecycleEventSink;
+pub use interceptors::{ObjectInterceptor, PutContext};
+pub use repositories::{
+    AuditLogRepository, BackupRepository, LeaseRepository, LifecycleRepository,
+    MultipartUploadRepository, ObjectRepository, TransactionRepository,
+};
+pub use runtime::{Clock, IdGenerator, SystemClock, UuidGenerator, VersionIdGenerator};
 pub use services::{
-    AppliedAction, BucketLifecycleResults, FailedAction, LifecycleActionResults, LifecycleService,
-    MetadataChange, ProcessingError, ProcessingStatus, ValidationError, ValidationResult,
-    ValidationWarning, VersionComparison, VersioningService,
+    AppliedAction, BackupService, BucketLifecycleResults, FailedAction, LifecycleActionResults,
+    LifecycleService, LifecycleSimulationResults, MetadataChange, ObjectService, ProcessingError,
+    ProcessingStatus, SimulatedObject, ValidationError, ValidationResult, ValidationWarning,
+    VersionComparison, VersioningService,
 };
 pub use storage::{CompletedPart, ObjectInfo, ObjectStore, VersionedObjectStore};
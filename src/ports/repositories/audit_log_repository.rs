@@ -0,0 +1,12 @@
+use crate::domain::{errors::StorageResult, models::AuditEvent, value_objects::ObjectKey};
+use async_trait::async_trait;
+
+/// Repository for the append-only audit log of actions taken on objects
+#[async_trait]
+pub trait AuditLogRepository: Send + Sync + 'static {
+    /// Append an event to the log
+    async fn record_event(&self, event: AuditEvent) -> StorageResult<()>;
+
+    /// List events in the order they were recorded, optionally only those for one object
+    async fn list_events(&self, key: Option<&ObjectKey>) -> StorageResult<Vec<AuditEvent>>;
+}
@@ -0,0 +1,27 @@
+use crate::domain::{
+    errors::StorageResult,
+    models::{BackupJob, BackupRun},
+};
+use async_trait::async_trait;
+
+/// Repository holding backup jobs and the history of their runs
+#[async_trait]
+pub trait BackupRepository: Send + Sync + 'static {
+    /// Create or replace a backup job
+    async fn save_job(&self, job: &BackupJob) -> StorageResult<()>;
+
+    /// Get a backup job by ID
+    async fn get_job(&self, job_id: &str) -> StorageResult<Option<BackupJob>>;
+
+    /// List every backup job
+    async fn list_jobs(&self) -> StorageResult<Vec<BackupJob>>;
+
+    /// Delete a backup job together with its run history
+    async fn delete_job(&self, job_id: &str) -> StorageResult<()>;
+
+    /// Create or replace a run of a backup job
+    async fn save_run(&self, run: &BackupRun) -> StorageResult<()>;
+
+    /// List the runs of a backup job, newest first
+    async fn list_runs(&self, job_id: &str) -> StorageResult<Vec<BackupRun>>;
+}
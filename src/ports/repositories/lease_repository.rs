@@ -0,0 +1,24 @@
+use crate::domain::errors::StorageResult;
+use async_trait::async_trait;
+use std::time::Duration;
+
+/// Repository of leases coordinating background work between server instances
+///
+/// A lease names a piece of work, such as one bucket's lifecycle pass, and is
+/// held by one instance at a time until it expires. Instances only coordinate
+/// with the others sharing their repository.
+#[async_trait]
+pub trait LeaseRepository: Send + Sync + 'static {
+    /// Take the lease `name` for `holder` for the next `ttl`
+    ///
+    /// The lease is taken when it is free or has expired, and renewed when
+    /// `holder` already holds it. Returns whether `holder` holds the lease
+    /// afterwards. Checking and taking the lease must be a single step for
+    /// every instance sharing the repository.
+    async fn try_acquire_lease(
+        &self,
+        name: &str,
+        holder: &str,
+        ttl: Duration,
+    ) -> StorageResult<bool>;
+}
@@ -1,5 +1,15 @@
+mod audit_log_repository;
+mod backup_repository;
+mod lease_repository;
 mod lifecycle_repository;
+mod multipart_upload_repository;
 mod object_repository;
+mod transaction_repository;
 
+pub use audit_log_repository::AuditLogRepository;
+pub use backup_repository::BackupRepository;
+pub use lease_repository::LeaseRepository;
 pub use lifecycle_repository::LifecycleRepository;
-pub use object_repository::ObjectRepository;
+pub use multipart_upload_repository::MultipartUploadRepository;
+pub use object_repository::{METADATA_UPDATE_ATTEMPTS, ObjectRepository};
+pub use transaction_repository::TransactionRepository;
@@ -0,0 +1,28 @@
+use crate::domain::{
+    errors::StorageResult,
+    models::{MultipartUploadInfo, UploadedPart},
+};
+use async_trait::async_trait;
+
+/// Repository tracking in-progress multipart uploads and the parts uploaded to them
+#[async_trait]
+pub trait MultipartUploadRepository: Send + Sync + 'static {
+    /// Record a newly initiated upload
+    async fn create_upload(&self, upload: &MultipartUploadInfo) -> StorageResult<()>;
+
+    /// Get an in-progress upload by ID
+    async fn get_upload(&self, upload_id: &str) -> StorageResult<Option<MultipartUploadInfo>>;
+
+    /// List in-progress uploads of keys starting with `prefix`, ordered by key
+    /// and then by initiation time
+    async fn list_uploads(&self, prefix: &str) -> StorageResult<Vec<MultipartUploadInfo>>;
+
+    /// Record a part, replacing any part previously uploaded with its number
+    async fn save_part(&self, upload_id: &str, part: &UploadedPart) -> StorageResult<()>;
+
+    /// List the parts of an upload in part number order
+    async fn list_parts(&self, upload_id: &str) -> StorageResult<Vec<UploadedPart>>;
+
+    /// Forget an upload and its parts once it is completed or aborted
+    async fn delete_upload(&self, upload_id: &str) -> StorageResult<()>;
+}
@@ -1,10 +1,17 @@
 use crate::domain::{
-    errors::StorageResult,
-    models::{ObjectMetadata, ObjectVersionInfo, ObjectVersionList},
+    errors::{StorageError, StorageResult},
+    models::{
+        Filter, ObjectMetadata, ObjectVersionInfo, ObjectVersionList, ObjectVersionPage,
+        PrefixUsage, TextQuery, VersionOrder,
+    },
     value_objects::{ObjectKey, VersionId},
 };
 use async_trait::async_trait;
 
+/// How many times [`ObjectRepository::modify_object_metadata`] applies a
+/// change before giving up on a version another writer keeps changing
+pub const METADATA_UPDATE_ATTEMPTS: usize = 5;
+
 /// Repository for managing object metadata and version information
 /// This trait handles metadata persistence, not the actual object data
 #[async_trait]
@@ -17,6 +24,24 @@ pub trait ObjectRepository: Send + Sync + 'static {
         metadata: &ObjectMetadata,
     ) -> StorageResult<()>;
 
+    /// Store new versions of several objects and make each the latest
+    /// version of its object in one operation
+    ///
+    /// Readers see either none of the versions or all of them. Storing a
+    /// version again with the same metadata changes nothing, so an
+    /// interrupted call can be repeated. The default implementation saves the
+    /// versions one at a time; repositories that can write them together
+    /// should override it.
+    async fn save_object_versions(
+        &self,
+        versions: &[(ObjectKey, VersionId, ObjectMetadata)],
+    ) -> StorageResult<()> {
+        for (key, version_id, metadata) in versions {
+            self.save_object_metadata(key, version_id, metadata).await?;
+        }
+        Ok(())
+    }
+
     /// Retrieve metadata for a specific version
     async fn get_object_metadata(
         &self,
@@ -27,6 +52,26 @@ pub trait ObjectRepository: Send + Sync + 'static {
     /// List all versions for an object
     async fn list_object_versions(&self, key: &ObjectKey) -> StorageResult<ObjectVersionList>;
 
+    /// List up to `limit` versions of an object in `order`, newest first,
+    /// starting after `marker`
+    ///
+    /// Returns None when `marker` is not a version of the object and `order`
+    /// needs it to be. The default implementation lists every version and
+    /// pages through them; repositories that can read a page of versions at
+    /// once should override it.
+    async fn list_object_versions_page(
+        &self,
+        key: &ObjectKey,
+        marker: Option<&VersionId>,
+        limit: usize,
+        order: VersionOrder,
+    ) -> StorageResult<Option<ObjectVersionPage>> {
+        Ok(self
+            .list_object_versions(key)
+            .await?
+            .paginate_in(order, marker, limit))
+    }
+
     /// Get information about a specific version
     async fn get_version_info(
         &self,
@@ -58,6 +103,117 @@ pub trait ObjectRepository: Send + Sync + 'static {
         max_results: Option<usize>,
     ) -> StorageResult<Vec<ObjectKey>>;
 
+    /// List the keys with a given prefix whose latest version is deleted, in
+    /// key order
+    ///
+    /// The deleted latest version is the key's delete marker; the key is not
+    /// listed by [`list_objects_by_prefix`](Self::list_objects_by_prefix).
+    async fn list_delete_markers(&self, prefix: &str) -> StorageResult<Vec<ObjectKey>>;
+
+    /// List the latest versions of the live objects with a given prefix, in
+    /// key order, starting after the key `start_after`
+    ///
+    /// This is the key index LIST is served from. The default implementation
+    /// looks up the latest version of every key under the prefix; repositories
+    /// that can read a page of the index at once should override it.
+    async fn list_latest_versions(
+        &self,
+        prefix: &str,
+        start_after: Option<&str>,
+        max_results: Option<usize>,
+    ) -> StorageResult<Vec<(ObjectKey, ObjectVersionInfo)>> {
+        let mut listing = Vec::new();
+        for key in self.list_objects_by_prefix(prefix, None).await? {
+            if max_results.is_some_and(|max| listing.len() >= max) {
+                break;
+            }
+            if start_after.is_some_and(|start_after| key.as_str() <= start_after) {
+                continue;
+            }
+            let Some(version_id) = self.get_latest_version_id(&key).await? else {
+                continue;
+            };
+            match self.get_version_info(&key, &version_id).await? {
+                Some(version) if !version.deleted => listing.push((key, version)),
+                _ => {}
+            }
+        }
+        Ok(listing)
+    }
+
+    /// Count the live objects with a given prefix and the bytes their latest
+    /// versions hold
+    ///
+    /// Prefix quotas are checked against this on every write. The default
+    /// implementation adds up the key index; repositories that can aggregate
+    /// it in place should override it.
+    async fn prefix_usage(&self, prefix: &str) -> StorageResult<PrefixUsage> {
+        let listing = self.list_latest_versions(prefix, None, None).await?;
+        Ok(PrefixUsage {
+            prefix: prefix.to_string(),
+            object_count: listing.len() as u64,
+            total_bytes: listing.iter().map(|(_, version)| version.size).sum(),
+            quota: None,
+        })
+    }
+
+    /// Find objects whose latest version matches `filter`, in key order
+    ///
+    /// Filter tags are matched against the custom metadata of the latest
+    /// version. The default implementation reads the metadata of every key
+    /// under the filter's prefix; repositories that index their metadata
+    /// should override it.
+    async fn find_objects(
+        &self,
+        filter: &Filter,
+        max_results: Option<usize>,
+    ) -> StorageResult<Vec<ObjectKey>> {
+        let prefix = filter.prefix.as_deref().unwrap_or("");
+        let mut matches = Vec::new();
+        for key in self.list_objects_by_prefix(prefix, None).await? {
+            if max_results.is_some_and(|max| matches.len() >= max) {
+                break;
+            }
+            let Some(metadata) = self.get_object_metadata(&key, None).await? else {
+                continue;
+            };
+            if filter.matches(
+                key.as_str(),
+                &metadata.custom_metadata,
+                metadata.content_length,
+            ) {
+                matches.push(key);
+            }
+        }
+        Ok(matches)
+    }
+
+    /// Find objects matching `filter` whose key or metadata values contain
+    /// every term of `query`, in key order
+    ///
+    /// The default implementation checks the text of every object matching
+    /// the filter; repositories with a text index should override it.
+    async fn search_objects(
+        &self,
+        query: &TextQuery,
+        filter: &Filter,
+        max_results: Option<usize>,
+    ) -> StorageResult<Vec<ObjectKey>> {
+        let mut matches = Vec::new();
+        for key in self.find_objects(filter, None).await? {
+            if max_results.is_some_and(|max| matches.len() >= max) {
+                break;
+            }
+            let Some(metadata) = self.get_object_metadata(&key, None).await? else {
+                continue;
+            };
+            if query.matches(key.as_str(), &metadata.custom_metadata) {
+                matches.push(key);
+            }
+        }
+        Ok(matches)
+    }
+
     /// Update metadata for an existing version
     async fn update_object_metadata(
         &self,
@@ -66,6 +222,76 @@ pub trait ObjectRepository: Send + Sync + 'static {
         metadata: &ObjectMetadata,
     ) -> StorageResult<()>;
 
-    /// Check if an object exists (any version)
+    /// Replace the metadata of a version with `metadata` if it is still
+    /// `expected`
+    ///
+    /// Fails with [`StorageError::VersionConflict`] when another writer has
+    /// changed the metadata since `expected` was read, so server instances
+    /// sharing the repository can't overwrite each other's updates. The
+    /// default implementation compares and writes in separate steps;
+    /// repositories shared between instances should override it to do both
+    /// atomically.
+    async fn compare_and_set_object_metadata(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+        expected: &ObjectMetadata,
+        metadata: &ObjectMetadata,
+    ) -> StorageResult<()> {
+        let current = self
+            .get_object_metadata(key, Some(version_id))
+            .await?
+            .ok_or_else(|| StorageError::VersionNotFound {
+                key: key.clone(),
+                version_id: version_id.clone(),
+            })?;
+        if current != *expected {
+            return Err(StorageError::VersionConflict {
+                key: key.clone(),
+                expected_version: Some(version_id.clone()),
+                actual_version: Some(version_id.clone()),
+            });
+        }
+        self.update_object_metadata(key, version_id, metadata).await
+    }
+
+    /// Apply `change` to the metadata of a version and return the result
+    ///
+    /// The change is written with
+    /// [`compare_and_set_object_metadata`](Self::compare_and_set_object_metadata)
+    /// and applied again to freshly read metadata whenever another writer got
+    /// there first, up to [`METADATA_UPDATE_ATTEMPTS`] times.
+    async fn modify_object_metadata(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+        change: &(dyn for<'m> Fn(&'m mut ObjectMetadata) -> StorageResult<()> + Send + Sync),
+    ) -> StorageResult<ObjectMetadata> {
+        let mut attempts = 1;
+        loop {
+            let expected = self
+                .get_object_metadata(key, Some(version_id))
+                .await?
+                .ok_or_else(|| StorageError::VersionNotFound {
+                    key: key.clone(),
+                    version_id: version_id.clone(),
+                })?;
+            let mut metadata = expected.clone();
+            change(&mut metadata)?;
+            match self
+                .compare_and_set_object_metadata(key, version_id, &expected, &metadata)
+                .await
+            {
+                Err(StorageError::VersionConflict { .. })
+                    if attempts < METADATA_UPDATE_ATTEMPTS =>
+                {
+                    attempts += 1
+                }
+                result => return result.map(|()| metadata),
+            }
+        }
+    }
+
+    /// Check if an object exists, that is its latest version isn't deleted
     async fn object_exists(&self, key: &ObjectKey) -> StorageResult<bool>;
 }
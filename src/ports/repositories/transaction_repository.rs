@@ -0,0 +1,39 @@
+use chrono::{DateTime, Utc};
+
+use crate::domain::{
+    errors::StorageResult,
+    models::{ObjectTransaction, StagedObject},
+};
+use async_trait::async_trait;
+
+/// Repository holding the manifests of multi-object transactions
+#[async_trait]
+pub trait TransactionRepository: Send + Sync + 'static {
+    /// Record a newly started transaction
+    async fn create_transaction(&self, transaction: &ObjectTransaction) -> StorageResult<()>;
+
+    /// Get a transaction that has not yet finished by ID
+    async fn get_transaction(
+        &self,
+        transaction_id: &str,
+    ) -> StorageResult<Option<ObjectTransaction>>;
+
+    /// Add an object to a transaction's manifest, replacing any object staged
+    /// under the same key
+    async fn save_staged_object(
+        &self,
+        transaction_id: &str,
+        object: &StagedObject,
+    ) -> StorageResult<()>;
+
+    /// Mark a transaction's manifest committed at `committed_at` in a single
+    /// write
+    async fn mark_committed(
+        &self,
+        transaction_id: &str,
+        committed_at: DateTime<Utc>,
+    ) -> StorageResult<()>;
+
+    /// Forget a transaction once it is committed or aborted
+    async fn delete_transaction(&self, transaction_id: &str) -> StorageResult<()>;
+}
@@ -0,0 +1,27 @@
+use chrono::{DateTime, Utc};
+use std::time::SystemTime;
+
+/// Source of the current time
+///
+/// Services read the time through this port instead of the system clock, so
+/// time-dependent behavior such as lifecycle expiration can be tested by
+/// supplying a clock that returns chosen times.
+pub trait Clock: std::fmt::Debug + Send + Sync + 'static {
+    /// Current time in UTC
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Current time as a [`SystemTime`]
+    fn system_time(&self) -> SystemTime {
+        self.now().into()
+    }
+}
+
+/// Clock reading the system time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
@@ -0,0 +1,17 @@
+use uuid::Uuid;
+
+/// Source of unique identifiers, such as version IDs
+pub trait IdGenerator: std::fmt::Debug + Send + Sync + 'static {
+    /// A new identifier, distinct from every one returned before
+    fn next_id(&self) -> String;
+}
+
+/// Generator of random (version 4) UUIDs
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UuidGenerator;
+
+impl IdGenerator for UuidGenerator {
+    fn next_id(&self) -> String {
+        Uuid::new_v4().to_string()
+    }
+}
@@ -0,0 +1,9 @@
+mod clock;
+mod id_generator;
+mod version_id_generator;
+
+pub use clock::{Clock, SystemClock};
+pub use id_generator::{IdGenerator, UuidGenerator};
+pub use version_id_generator::{
+    BackendVersionIds, UlidGenerator, UuidV7Generator, VersionIdGenerator, VersionIdScheme,
+};
@@ -0,0 +1,200 @@
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+use super::UuidGenerator;
+
+/// Source of the IDs of new object versions
+///
+/// IDs from a time-ordered generator sort in the order their versions were
+/// written, so version listings can be ordered by ID alone instead of by the
+/// write times kept in each version's metadata.
+pub trait VersionIdGenerator: std::fmt::Debug + Send + Sync + 'static {
+    /// ID for a new version, or `None` to use the ID the storage backend
+    /// assigned when it stored the version
+    fn next_version_id(&self) -> Option<String>;
+
+    /// Whether each ID sorts after every ID generated before it
+    fn is_time_ordered(&self) -> bool;
+}
+
+impl VersionIdGenerator for UuidGenerator {
+    fn next_version_id(&self) -> Option<String> {
+        Some(Uuid::new_v4().to_string())
+    }
+
+    fn is_time_ordered(&self) -> bool {
+        false
+    }
+}
+
+/// Generator of time-ordered (version 7) UUIDs
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UuidV7Generator;
+
+impl VersionIdGenerator for UuidV7Generator {
+    fn next_version_id(&self) -> Option<String> {
+        // Ordered even within a millisecond for UUIDs made by this process
+        Some(Uuid::now_v7().to_string())
+    }
+
+    fn is_time_ordered(&self) -> bool {
+        true
+    }
+}
+
+/// Crockford's base 32 alphabet ULIDs are written in
+const ULID_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// Generator of monotonic ULIDs
+///
+/// A ULID is a 48 bit millisecond timestamp followed by 80 random bits,
+/// written as 26 characters. IDs generated within the same millisecond
+/// increment the random part of the previous one, so they stay ordered.
+#[derive(Debug, Default)]
+pub struct UlidGenerator {
+    /// Timestamp and random part of the last ULID generated
+    last: Mutex<(u64, u128)>,
+}
+
+impl UlidGenerator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn encode(timestamp: u64, random: u128) -> String {
+        let value = (u128::from(timestamp) << 80) | random;
+        (0..26)
+            .rev()
+            .map(|digit| ULID_ALPHABET[((value >> (digit * 5)) & 0x1f) as usize] as char)
+            .collect()
+    }
+}
+
+impl VersionIdGenerator for UlidGenerator {
+    fn next_version_id(&self) -> Option<String> {
+        const RANDOM_MASK: u128 = (1 << 80) - 1;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let mut last = self.last.lock().unwrap();
+        let (timestamp, random) = match *last {
+            // An exhausted random part carries into the timestamp
+            (timestamp, random) if now <= timestamp => match random.checked_add(1) {
+                Some(random) if random <= RANDOM_MASK => (timestamp, random),
+                _ => (timestamp + 1, 0),
+            },
+            _ => (now, Uuid::new_v4().as_u128() & RANDOM_MASK),
+        };
+        *last = (timestamp, random);
+
+        Some(Self::encode(timestamp, random))
+    }
+
+    fn is_time_ordered(&self) -> bool {
+        true
+    }
+}
+
+/// Generator deferring to the version IDs the storage backend assigns
+///
+/// Meant for S3 and MinIO buckets with versioning enabled. Where the backend
+/// assigns no ID, such as on an unversioned bucket, versions get random UUIDs.
+/// Backend IDs are opaque, so they are not treated as time-ordered.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BackendVersionIds;
+
+impl VersionIdGenerator for BackendVersionIds {
+    fn next_version_id(&self) -> Option<String> {
+        None
+    }
+
+    fn is_time_ordered(&self) -> bool {
+        false
+    }
+}
+
+/// Scheme of the version IDs, as configured by name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VersionIdScheme {
+    /// Random (version 4) UUIDs
+    #[default]
+    Uuid,
+    /// Time-ordered (version 7) UUIDs
+    UuidV7,
+    /// Monotonic ULIDs
+    Ulid,
+    /// IDs assigned by the storage backend
+    Backend,
+}
+
+impl VersionIdScheme {
+    /// A generator of IDs in this scheme
+    pub fn generator(self) -> Arc<dyn VersionIdGenerator> {
+        match self {
+            VersionIdScheme::Uuid => Arc::new(UuidGenerator),
+            VersionIdScheme::UuidV7 => Arc::new(UuidV7Generator),
+            VersionIdScheme::Ulid => Arc::new(UlidGenerator::new()),
+            VersionIdScheme::Backend => Arc::new(BackendVersionIds),
+        }
+    }
+}
+
+impl FromStr for VersionIdScheme {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_lowercase().as_str() {
+            "uuid" => Ok(VersionIdScheme::Uuid),
+            "uuidv7" => Ok(VersionIdScheme::UuidV7),
+            "ulid" => Ok(VersionIdScheme::Ulid),
+            "backend" => Ok(VersionIdScheme::Backend),
+            _ => Err(format!(
+                "Unknown version ID scheme '{}', expected uuid, uuidv7, ulid or backend",
+                name
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::value_objects::VersionId;
+
+    #[test]
+    fn test_time_ordered_ids_sort_in_generation_order() {
+        for scheme in [VersionIdScheme::UuidV7, VersionIdScheme::Ulid] {
+            let generator = scheme.generator();
+            assert!(generator.is_time_ordered());
+
+            let ids: Vec<String> = (0..1000)
+                .map(|_| generator.next_version_id().unwrap())
+                .collect();
+            let mut sorted = ids.clone();
+            sorted.sort();
+            sorted.dedup();
+            assert_eq!(ids, sorted, "{:?} IDs are out of order", scheme);
+            assert!(VersionId::new(ids[0].clone()).is_ok());
+        }
+
+        assert_eq!(UlidGenerator::encode(0, 0), "0".repeat(26));
+        assert_eq!(
+            UlidGenerator::encode(u64::MAX >> 16, (1 << 80) - 1),
+            "7ZZZZZZZZZZZZZZZZZZZZZZZZZ"
+        );
+    }
+
+    #[test]
+    fn test_scheme_names() {
+        assert_eq!("UUIDv7".parse(), Ok(VersionIdScheme::UuidV7));
+        assert_eq!("ulid".parse(), Ok(VersionIdScheme::Ulid));
+        assert!("snowflake".parse::<VersionIdScheme>().is_err());
+
+        assert!(!VersionIdScheme::Uuid.generator().is_time_ordered());
+        assert_eq!(VersionIdScheme::Backend.generator().next_version_id(), None);
+    }
+}
@@ -0,0 +1,44 @@
+use crate::domain::{
+    errors::StorageResult,
+    models::{BackupJob, BackupMode, BackupRestore, BackupRun},
+    value_objects::BucketName,
+};
+use async_trait::async_trait;
+
+/// Port for recurring backups of buckets to a backup target
+#[async_trait]
+pub trait BackupService: Send + Sync + 'static {
+    /// Create a job backing up `bucket` on a cron `schedule`
+    async fn create_backup_job(
+        &self,
+        bucket: BucketName,
+        schedule: &str,
+        mode: BackupMode,
+    ) -> StorageResult<BackupJob>;
+
+    /// List every backup job
+    async fn list_backup_jobs(&self) -> StorageResult<Vec<BackupJob>>;
+
+    /// Delete a backup job and its run history; backups already written stay
+    /// in the target
+    async fn delete_backup_job(&self, job_id: &str) -> StorageResult<()>;
+
+    /// Run a backup job now, waiting for the run to finish
+    async fn run_backup(&self, job_id: &str) -> StorageResult<BackupRun>;
+
+    /// Run every job whose schedule has fired since its last run
+    async fn run_due_backups(&self) -> StorageResult<Vec<BackupRun>>;
+
+    /// List the runs of a backup job, newest first
+    async fn list_backup_runs(&self, job_id: &str) -> StorageResult<Vec<BackupRun>>;
+
+    /// Copy the objects of a successful run back into `bucket`, or the job's
+    /// own bucket, replacing existing objects only when `overwrite` is set
+    async fn restore_backup(
+        &self,
+        job_id: &str,
+        run_id: &str,
+        bucket: Option<BucketName>,
+        overwrite: bool,
+    ) -> StorageResult<BackupRestore>;
+}
@@ -2,11 +2,12 @@ use crate::domain::{
     errors::LifecycleResult,
     models::{
         ApplicableAction, EvaluateLifecycleRequest, LifecycleConfiguration,
-        LifecycleEvaluationResult, LifecycleRule,
+        LifecycleEvaluationResult, LifecycleRule, PrefixRewrite,
     },
     value_objects::{BucketName, ObjectKey},
 };
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 
 /// Service port for lifecycle management operations
 #[async_trait]
@@ -27,6 +28,17 @@ pub trait LifecycleService: Send + Sync + 'static {
     /// Delete lifecycle configuration for a bucket
     async fn delete_lifecycle_configuration(&self, bucket: &BucketName) -> LifecycleResult<()>;
 
+    /// Apply one bucket's lifecycle configuration to another bucket
+    ///
+    /// The target's existing configuration is replaced. Returns the configuration
+    /// applied to the target, after any prefix rewrite.
+    async fn copy_lifecycle_configuration(
+        &self,
+        source: &BucketName,
+        target: &BucketName,
+        prefix_rewrite: Option<&PrefixRewrite>,
+    ) -> LifecycleResult<LifecycleConfiguration>;
+
     /// Evaluate lifecycle rules for a specific object
     async fn evaluate_object_lifecycle(
         &self,
@@ -64,9 +76,44 @@ pub trait LifecycleService: Send + Sync + 'static {
         config: &LifecycleConfiguration,
     ) -> LifecycleResult<ValidationResult>;
 
+    /// Report which objects in a bucket the given rules would act on at `as_of`
+    ///
+    /// This is a dry run: nothing is expired or transitioned. Without a
+    /// configuration, the bucket's active configuration is simulated.
+    async fn simulate_bucket_lifecycle(
+        &self,
+        bucket: &BucketName,
+        config: Option<LifecycleConfiguration>,
+        as_of: DateTime<Utc>,
+    ) -> LifecycleResult<LifecycleSimulationResults>;
+
     /// Get lifecycle processing status
     async fn get_processing_status(&self, bucket: &BucketName)
     -> LifecycleResult<ProcessingStatus>;
+
+    /// Start processing the bucket's lifecycle in the background, returning
+    /// the status of the started run
+    ///
+    /// Fails with [`LifecycleError::ProcessingInProgress`] while a run of the
+    /// bucket is underway.
+    ///
+    /// [`LifecycleError::ProcessingInProgress`]: crate::domain::errors::LifecycleError::ProcessingInProgress
+    async fn start_bucket_lifecycle(
+        &self,
+        bucket: &BucketName,
+    ) -> LifecycleResult<ProcessingStatus>;
+
+    /// Stop the running lifecycle processing of the bucket once the object
+    /// it is working on is done
+    ///
+    /// Fails with [`LifecycleError::ProcessingNotRunning`] when no run of the
+    /// bucket is underway.
+    ///
+    /// [`LifecycleError::ProcessingNotRunning`]: crate::domain::errors::LifecycleError::ProcessingNotRunning
+    async fn cancel_bucket_lifecycle(
+        &self,
+        bucket: &BucketName,
+    ) -> LifecycleResult<ProcessingStatus>;
 }
 
 /// Results from applying lifecycle actions
@@ -109,6 +156,22 @@ pub struct ProcessingError {
     pub error: String,
 }
 
+/// Results from a lifecycle dry run over a bucket
+#[derive(Debug, Clone)]
+pub struct LifecycleSimulationResults {
+    pub bucket: BucketName,
+    pub as_of: DateTime<Utc>,
+    pub objects_evaluated: usize,
+    pub affected_objects: Vec<SimulatedObject>,
+}
+
+/// An object the simulated rules would act on
+#[derive(Debug, Clone)]
+pub struct SimulatedObject {
+    pub object_key: ObjectKey,
+    pub actions: Vec<ApplicableAction>,
+}
+
 /// Validation result for lifecycle configuration
 #[derive(Debug, Clone)]
 pub struct ValidationResult {
@@ -134,6 +197,8 @@ pub struct ValidationWarning {
 #[derive(Debug, Clone)]
 pub struct ProcessingStatus {
     pub is_running: bool,
+    /// Whether the running or last run was asked to stop early
+    pub cancelled: bool,
     pub last_run: Option<std::time::SystemTime>,
     pub next_scheduled_run: Option<std::time::SystemTime>,
     pub last_run_results: Option<BucketLifecycleResults>,
@@ -1,10 +1,13 @@
+mod backup_service;
 mod lifecycle_service;
 mod object_service;
 mod versioning_service;
 
+pub use backup_service::BackupService;
 pub use lifecycle_service::{
     AppliedAction, BucketLifecycleResults, FailedAction, LifecycleActionResults, LifecycleService,
-    ProcessingError, ProcessingStatus, ValidationError, ValidationResult, ValidationWarning,
+    LifecycleSimulationResults, ProcessingError, ProcessingStatus, SimulatedObject,
+    ValidationError, ValidationResult, ValidationWarning,
 };
 pub use object_service::ObjectService;
 pub use versioning_service::{MetadataChange, VersionComparison, VersioningService};
@@ -1,23 +1,56 @@
 use crate::{
     domain::{
         errors::StorageResult,
-        models::{CreateObjectRequest, GetObjectRequest, StorageObject},
+        models::{
+            CreateObjectRequest, Filter, GetObjectRequest, MultipartUploadInfo, ObjectMetadata,
+            ObjectTransaction, PrefixUsage, PresignedMultipartUpload, QuotaWarning, StagedObject,
+            StorageObject, TextQuery, UploadedPart,
+        },
         value_objects::ObjectKey,
     },
-    ports::storage::ObjectInfo,
+    ports::storage::{CompletedPart, ObjectInfo, PresignedUrlMethod},
 };
 use async_trait::async_trait;
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::ops::Range;
 
 /// Port for object storage service operations
 /// This trait defines the business logic for object management
 #[async_trait]
 pub trait ObjectService: Send + Sync + 'static {
     /// Create a new object
-    async fn create_object(&self, request: CreateObjectRequest) -> StorageResult<StorageObject>;
+    ///
+    /// Returns the object with the soft limits of the quotas covering it that
+    /// the write reached.
+    async fn create_object(
+        &self,
+        request: CreateObjectRequest,
+    ) -> StorageResult<(StorageObject, Vec<QuotaWarning>)>;
+
+    /// Create a new object from a stream of data without buffering it in memory
+    ///
+    /// Returns the object's metadata with the soft limits of the quotas
+    /// covering it that the write reached.
+    async fn create_object_stream(
+        &self,
+        key: ObjectKey,
+        reader: Box<dyn tokio::io::AsyncRead + Send + Unpin>,
+        content_type: Option<String>,
+        custom_metadata: HashMap<String, String>,
+    ) -> StorageResult<(ObjectMetadata, Vec<QuotaWarning>)>;
 
     /// Get an object
     async fn get_object(&self, request: GetObjectRequest) -> StorageResult<StorageObject>;
 
+    /// Get an object's metadata together with a stream of its data, or of
+    /// only the byte range `range` of it
+    async fn get_object_stream(
+        &self,
+        key: &ObjectKey,
+        range: Option<Range<u64>>,
+    ) -> StorageResult<(ObjectMetadata, Box<dyn tokio::io::AsyncRead + Send + Unpin>)>;
+
     /// Delete an object
     async fn delete_object(&self, key: &ObjectKey) -> StorageResult<()>;
 
@@ -28,12 +61,40 @@ pub trait ObjectService: Send + Sync + 'static {
         max_results: Option<usize>,
     ) -> StorageResult<Vec<ObjectInfo>>;
 
+    /// List objects with a prefix in key order, starting after the key
+    /// `start_after`
+    async fn list_objects_after(
+        &self,
+        prefix: Option<&str>,
+        start_after: Option<&str>,
+        max_results: Option<usize>,
+    ) -> StorageResult<Vec<ObjectInfo>>;
+
+    /// Find the keys of live objects matching a filter's prefix, tags and size bounds
+    async fn find_objects(
+        &self,
+        filter: &Filter,
+        max_results: Option<usize>,
+    ) -> StorageResult<Vec<ObjectKey>>;
+
+    /// Find live objects matching a filter whose key or metadata values
+    /// contain every term of a free-text query
+    async fn search_objects(
+        &self,
+        query: &TextQuery,
+        filter: &Filter,
+        max_results: Option<usize>,
+    ) -> StorageResult<Vec<ObjectKey>>;
+
     /// Copy an object
+    ///
+    /// Returns the copy with the soft limits of the quotas covering it that
+    /// the write reached.
     async fn copy_object(
         &self,
         source_key: &ObjectKey,
         destination_key: &ObjectKey,
-    ) -> StorageResult<StorageObject>;
+    ) -> StorageResult<(StorageObject, Vec<QuotaWarning>)>;
 
     /// Update object metadata
     async fn update_metadata(
@@ -42,9 +103,133 @@ pub trait ObjectService: Send + Sync + 'static {
         metadata: crate::domain::models::ObjectMetadata,
     ) -> StorageResult<()>;
 
+    /// Overwrite the bytes of an object starting at `offset` with `data`,
+    /// storing the result as a new version
+    ///
+    /// The object grows when the data runs past its end, but `offset` itself
+    /// may not lie beyond the end. The patch is refused with
+    /// [`StorageError::VersionConflict`](crate::domain::errors::StorageError::VersionConflict)
+    /// unless `if_match` is the object's current ETag, and with
+    /// `expected_size` unless the object has that size afterwards.
+    async fn patch_object(
+        &self,
+        key: &ObjectKey,
+        offset: u64,
+        data: Bytes,
+        if_match: &str,
+        expected_size: Option<u64>,
+    ) -> StorageResult<ObjectMetadata>;
+
+    /// Get an object's metadata without its data
+    async fn head_object(&self, key: &ObjectKey) -> StorageResult<ObjectMetadata>;
+
     /// Check if object exists
     async fn object_exists(&self, key: &ObjectKey) -> StorageResult<bool>;
 
     /// Get object size without retrieving data
     async fn get_object_size(&self, key: &ObjectKey) -> StorageResult<u64>;
+
+    /// Count the objects under `prefix` and their total size, along with the
+    /// quota configured for exactly that prefix if any
+    async fn prefix_usage(&self, prefix: &str) -> StorageResult<PrefixUsage>;
+
+    /// Usage of every prefix a quota is configured for
+    async fn quota_usage(&self) -> StorageResult<Vec<PrefixUsage>>;
+
+    /// Create a URL allowing `method` on an object for `expires_in_secs` seconds
+    /// without further authentication
+    async fn presign_url(
+        &self,
+        key: &ObjectKey,
+        method: PresignedUrlMethod,
+        expires_in_secs: u64,
+    ) -> StorageResult<String>;
+
+    /// Start a multipart upload of an object stored with the given content
+    /// type and metadata once completed
+    async fn initiate_multipart_upload(
+        &self,
+        key: ObjectKey,
+        content_type: Option<String>,
+        custom_metadata: HashMap<String, String>,
+    ) -> StorageResult<MultipartUploadInfo>;
+
+    /// Upload one part of an in-progress multipart upload
+    async fn upload_part(
+        &self,
+        key: &ObjectKey,
+        upload_id: &str,
+        part_number: u32,
+        data: Bytes,
+    ) -> StorageResult<UploadedPart>;
+
+    /// Copy a byte range of an existing object, or all of it, as one part of
+    /// an in-progress multipart upload
+    async fn upload_part_copy(
+        &self,
+        key: &ObjectKey,
+        upload_id: &str,
+        part_number: u32,
+        source_key: &ObjectKey,
+        range: Option<Range<u64>>,
+    ) -> StorageResult<UploadedPart>;
+
+    /// Assemble the object from the listed parts and end the upload
+    async fn complete_multipart_upload(
+        &self,
+        key: &ObjectKey,
+        upload_id: &str,
+        parts: Vec<CompletedPart>,
+    ) -> StorageResult<ObjectMetadata>;
+
+    /// Discard an in-progress multipart upload and its parts
+    async fn abort_multipart_upload(&self, key: &ObjectKey, upload_id: &str) -> StorageResult<()>;
+
+    /// List in-progress multipart uploads of keys with a prefix
+    async fn list_multipart_uploads(
+        &self,
+        prefix: Option<&str>,
+        max_uploads: Option<usize>,
+    ) -> StorageResult<Vec<MultipartUploadInfo>>;
+
+    /// List the parts uploaded so far to an in-progress multipart upload
+    async fn list_parts(
+        &self,
+        key: &ObjectKey,
+        upload_id: &str,
+    ) -> StorageResult<Vec<UploadedPart>>;
+
+    /// Create URLs uploading each of `part_numbers` of an in-progress multipart
+    /// upload, and one completing it, valid for `expires_in_secs` seconds
+    /// without further authentication
+    async fn presign_multipart_upload(
+        &self,
+        key: &ObjectKey,
+        upload_id: &str,
+        part_numbers: &[u32],
+        expires_in_secs: u64,
+    ) -> StorageResult<PresignedMultipartUpload>;
+
+    /// Start a transaction whose staged objects are committed together
+    async fn begin_transaction(&self) -> StorageResult<ObjectTransaction>;
+
+    /// Get a transaction that has not yet finished, with its staged objects
+    async fn get_transaction(&self, transaction_id: &str) -> StorageResult<ObjectTransaction>;
+
+    /// Upload an object into a transaction, where it stays invisible until the
+    /// transaction commits
+    async fn stage_object(
+        &self,
+        transaction_id: &str,
+        key: ObjectKey,
+        reader: Box<dyn tokio::io::AsyncRead + Send + Unpin>,
+        content_type: Option<String>,
+        custom_metadata: HashMap<String, String>,
+    ) -> StorageResult<StagedObject>;
+
+    /// Make every object staged in a transaction visible, or none of them
+    async fn commit_transaction(&self, transaction_id: &str) -> StorageResult<ObjectTransaction>;
+
+    /// Discard a transaction and its staged objects
+    async fn abort_transaction(&self, transaction_id: &str) -> StorageResult<()>;
 }
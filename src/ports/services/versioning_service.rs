@@ -1,12 +1,16 @@
 use crate::domain::{
     errors::StorageResult,
     models::{
-        CreateObjectRequest, DeleteVersionRequest, DeleteVersionResult, GetObjectRequest,
-        ObjectVersionInfo, ObjectVersionList, VersionedObject, VersioningConfiguration,
+        BulkDeleteVersionsRequest, BulkDeleteVersionsResult, CreateObjectRequest, DatasetManifest,
+        DeleteVersionRequest, DeleteVersionResult, GetObjectRequest, LabelVersionRequest,
+        ObjectDelta, ObjectMetadata, ObjectSignature, ObjectVersionInfo, ObjectVersionList,
+        ObjectVersionPage, RollbackVersionResult, VersionRetentionPolicy, VersionedObject,
+        VersioningConfiguration,
     },
     value_objects::{BucketName, ObjectKey, VersionId},
 };
 use async_trait::async_trait;
+use std::ops::Range;
 
 /// Service port for version management operations
 #[async_trait]
@@ -23,6 +27,10 @@ pub trait VersioningService: Send + Sync + 'static {
         bucket: &BucketName,
     ) -> StorageResult<VersioningConfiguration>;
 
+    /// Whether versioning was configured for a bucket, rather than the
+    /// bucket getting the default configuration
+    async fn has_versioning_configuration(&self, bucket: &BucketName) -> StorageResult<bool>;
+
     /// Create a new versioned object
     async fn create_versioned_object(
         &self,
@@ -32,9 +40,45 @@ pub trait VersioningService: Send + Sync + 'static {
     /// Get an object (optionally by version)
     async fn get_object(&self, request: GetObjectRequest) -> StorageResult<VersionedObject>;
 
+    /// Get the version ID and metadata of an object (optionally by version)
+    /// without its data
+    async fn head_object(
+        &self,
+        request: GetObjectRequest,
+    ) -> StorageResult<(VersionId, ObjectMetadata)>;
+
+    /// Get an object (optionally by version) as a stream of its data, or of
+    /// only the byte range `range` of it
+    async fn get_object_stream(
+        &self,
+        request: GetObjectRequest,
+        range: Option<Range<u64>>,
+    ) -> StorageResult<(
+        VersionId,
+        ObjectMetadata,
+        Box<dyn tokio::io::AsyncRead + Send + Unpin>,
+    )>;
+
     /// List all versions of an object
     async fn list_versions(&self, key: &ObjectKey) -> StorageResult<ObjectVersionList>;
 
+    /// List up to `limit` versions of an object, newest first, after `marker`
+    ///
+    /// Pass the page's `next_version_id_marker` as `marker` to fetch the next page.
+    async fn list_versions_paginated(
+        &self,
+        key: &ObjectKey,
+        marker: Option<&VersionId>,
+        limit: usize,
+    ) -> StorageResult<ObjectVersionPage>;
+
+    /// List the versions of an object carrying `label`
+    async fn list_labeled_versions(
+        &self,
+        key: &ObjectKey,
+        label: &str,
+    ) -> StorageResult<ObjectVersionList>;
+
     /// Get information about a specific version
     async fn get_version_info(
         &self,
@@ -48,6 +92,14 @@ pub trait VersioningService: Send + Sync + 'static {
         request: DeleteVersionRequest,
     ) -> StorageResult<DeleteVersionResult>;
 
+    /// Delete every selected version of an object, reporting the outcome of each
+    ///
+    /// A version that fails to delete does not stop the others from being deleted.
+    async fn delete_versions(
+        &self,
+        request: BulkDeleteVersionsRequest,
+    ) -> StorageResult<BulkDeleteVersionsResult>;
+
     /// Restore a previous version as the latest
     async fn restore_version(
         &self,
@@ -55,6 +107,15 @@ pub trait VersioningService: Send + Sync + 'static {
         version_id: &VersionId,
     ) -> StorageResult<VersionedObject>;
 
+    /// Promote the version `steps` versions before the latest as the new latest
+    ///
+    /// The content is copied into a new version, so no history is lost.
+    async fn rollback_version(
+        &self,
+        key: &ObjectKey,
+        steps: usize,
+    ) -> StorageResult<RollbackVersionResult>;
+
     /// Compare two versions
     async fn compare_versions(
         &self,
@@ -70,6 +131,33 @@ pub trait VersioningService: Send + Sync + 'static {
         keep_count: usize,
     ) -> StorageResult<Vec<VersionId>>; // Returns deleted version IDs
 
+    /// Replace the labels and annotations of a version
+    async fn label_version(&self, request: LabelVersionRequest) -> StorageResult<()>;
+
+    /// Pin or unpin a version
+    ///
+    /// Pinned versions cannot be deleted, whether directly, by pruning or by
+    /// lifecycle rules, until they are unpinned.
+    async fn set_version_pinned(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+        pinned: bool,
+    ) -> StorageResult<()>;
+
+    /// Replace the retention policy of a version, or clear it with None
+    ///
+    /// Versions under retention cannot be deleted until it expires. Retention
+    /// in force cannot be shortened, except governance retention when
+    /// `bypass_governance` is set and the caller is allowed to bypass it.
+    async fn set_version_retention(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+        retention: Option<VersionRetentionPolicy>,
+        bypass_governance: bool,
+    ) -> StorageResult<()>;
+
     /// Copy a specific version to a new location
     async fn copy_version(
         &self,
@@ -80,6 +168,46 @@ pub trait VersioningService: Send + Sync + 'static {
 
     /// Check if a specific version exists
     async fn version_exists(&self, key: &ObjectKey, version_id: &VersionId) -> StorageResult<bool>;
+
+    /// Block checksums of a version, for a client to compute a delta against
+    async fn version_signature(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+        block_size: u64,
+    ) -> StorageResult<ObjectSignature>;
+
+    /// Create a new version of an object by applying a delta to `base_version`
+    ///
+    /// Only the bytes missing from the base version travel with the delta, the
+    /// rest is copied from the stored base. The new version keeps the content
+    /// type and custom metadata of the base, but not the pin, retention,
+    /// labels or annotations given to the base version.
+    async fn create_version_from_delta(
+        &self,
+        key: &ObjectKey,
+        base_version: &VersionId,
+        delta: ObjectDelta,
+    ) -> StorageResult<VersionedObject>;
+
+    /// Publish a new manifest for dataset `name` listing the given object versions
+    ///
+    /// Objects given without a version ID are included at their latest version.
+    /// Readers of the dataset switch from the previous set of versions to this
+    /// one at once, when the manifest is stored.
+    async fn publish_dataset(
+        &self,
+        name: &str,
+        objects: Vec<(ObjectKey, Option<VersionId>)>,
+    ) -> StorageResult<DatasetManifest>;
+
+    /// Resolve the latest manifest of dataset `name`, or the one published as
+    /// `manifest_version`, with the size and ETag of every version it lists
+    async fn get_dataset(
+        &self,
+        name: &str,
+        manifest_version: Option<&VersionId>,
+    ) -> StorageResult<DatasetManifest>;
 }
 
 /// Result of comparing two versions
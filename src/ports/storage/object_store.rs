@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::ops::Range;
 use async_trait::async_trait;
 use bytes::Bytes;
 use chrono::{DateTime, Utc};
@@ -22,13 +23,23 @@ pub trait ObjectStore: Send + Sync + 'static {
         content_type: Option<&str>,
     ) -> StorageResult<ObjectInfo>;
 
+    /// Store object data read from a stream without buffering it in memory
+    async fn put_object_stream(
+        &self,
+        key: &ObjectKey,
+        reader: Box<dyn tokio::io::AsyncRead + Send + Unpin>,
+        content_type: Option<&str>,
+    ) -> StorageResult<ObjectInfo>;
+
     /// Retrieve object data
     async fn get_object(&self, key: &ObjectKey) -> StorageResult<Bytes>;
 
-    /// Retrieve object data as a stream for large objects
+    /// Retrieve object data, or only the byte range `range` of it, as a
+    /// stream for large objects
     async fn get_object_stream(
         &self,
         key: &ObjectKey,
+        range: Option<Range<u64>>,
     ) -> StorageResult<Box<dyn tokio::io::AsyncRead + Send + Unpin>>;
 
     /// Delete object data
@@ -58,6 +69,16 @@ pub trait ObjectStore: Send + Sync + 'static {
         method: PresignedUrlMethod,
     ) -> StorageResult<String>;
 
+    /// Get a pre-signed URL uploading part `part_number` of a multipart
+    /// upload, or completing the upload when `None`
+    async fn get_presigned_multipart_url(
+        &self,
+        key: &ObjectKey,
+        upload_id: &str,
+        part_number: Option<u32>,
+        expiration_seconds: u64,
+    ) -> StorageResult<String>;
+
     /// Initiate a multipart upload
     async fn initiate_multipart_upload(&self, key: &ObjectKey) -> StorageResult<String>; // Returns upload ID
 
@@ -70,6 +91,17 @@ pub trait ObjectStore: Send + Sync + 'static {
         data: Bytes,
     ) -> StorageResult<CompletedPart>;
 
+    /// Upload a byte range of an existing object, or all of it, as a part
+    /// in a multipart upload without passing the data through the caller
+    async fn upload_part_copy(
+        &self,
+        key: &ObjectKey,
+        upload_id: &str,
+        part_number: u32,
+        source_key: &ObjectKey,
+        range: Option<Range<u64>>,
+    ) -> StorageResult<CompletedPart>;
+
     /// Complete a multipart upload
     async fn complete_multipart_upload(
         &self,
@@ -100,9 +132,13 @@ pub trait ObjectStore: Send + Sync + 'static {
 #[async_trait]
 pub trait VersionedObjectStore: Send + Sync + 'static {
     /// Store object data and return version information
+    ///
+    /// `version_id` is the ID the new version is stored under; stores whose
+    /// backend assigns version IDs ignore it and report the assigned one.
     async fn put_object_version(
         &self,
         key: &ObjectKey,
+        version_id: Option<&VersionId>,
         data: Bytes,
         content_type: Option<&str>,
     ) -> StorageResult<ObjectInfo>;
@@ -114,11 +150,13 @@ pub trait VersionedObjectStore: Send + Sync + 'static {
         version_id: &VersionId,
     ) -> StorageResult<Bytes>;
 
-    /// Retrieve object data as a stream for a specific version
+    /// Retrieve object data, or only the byte range `range` of it, as a
+    /// stream for a specific version
     async fn get_object_version_stream(
         &self,
         key: &ObjectKey,
         version_id: &VersionId,
+        range: Option<Range<u64>>,
     ) -> StorageResult<Box<dyn tokio::io::AsyncRead + Send + Unpin>>;
 
     /// Delete a specific version
@@ -0,0 +1,108 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use super::{BackendReadiness, WorkerLeases};
+use crate::ports::services::BackupService;
+
+/// Default time between checks for backup jobs that are due
+pub const DEFAULT_BACKUP_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Handle to a running backup worker
+///
+/// The worker checks the schedules of all backup jobs every poll interval and
+/// runs the jobs that are due, one after another. Dropping the handle leaves
+/// the worker running; call [`stop`](Self::stop) to shut it down.
+///
+/// With a [`BackendReadiness`] the worker checks its backends before each
+/// poll, and pauses until they recover when a check fails.
+///
+/// With [`WorkerLeases`] shared by several server instances, each poll's due
+/// backups are run by the one instance that takes the lease for the poll.
+pub struct BackupWorkerHandle {
+    shutdown: CancellationToken,
+    paused: Arc<AtomicBool>,
+    task: JoinHandle<()>,
+}
+
+impl BackupWorkerHandle {
+    /// Start a worker running due backups every `poll_interval`
+    pub fn spawn(
+        backup_service: Arc<dyn BackupService>,
+        poll_interval: Duration,
+        readiness: Option<BackendReadiness>,
+        leases: Option<WorkerLeases>,
+    ) -> Self {
+        let shutdown = CancellationToken::new();
+        let paused = Arc::new(AtomicBool::new(false));
+        let task = tokio::spawn({
+            let shutdown = shutdown.clone();
+            let paused = paused.clone();
+            async move {
+                loop {
+                    tokio::select! {
+                        _ = shutdown.cancelled() => break,
+                        _ = tokio::time::sleep(poll_interval) => {}
+                    }
+
+                    if let Some(readiness) = &readiness {
+                        if !readiness
+                            .wait_until_ready("backup", &paused, &shutdown)
+                            .await
+                        {
+                            break;
+                        }
+                    }
+
+                    let due = backup_service.run_due_backups();
+                    let result = match &leases {
+                        Some(leases) => {
+                            match leases.run_exclusive("backup", poll_interval, due).await {
+                                Ok(result) => result,
+                                Err(_) => continue,
+                            }
+                        }
+                        None => due.await,
+                    };
+                    match result {
+                        Ok(runs) => {
+                            for run in runs.iter().filter(|run| run.error.is_some()) {
+                                tracing::error!(
+                                    "Backup run {} of job {} failed: {}",
+                                    run.run_id,
+                                    run.job_id,
+                                    run.error.as_deref().unwrap_or_default()
+                                );
+                            }
+                        }
+                        Err(e) => tracing::error!("Backup worker failed to run due backups: {}", e),
+                    }
+                }
+            }
+        });
+
+        Self {
+            shutdown,
+            paused,
+            task,
+        }
+    }
+
+    /// Whether the worker task is still running
+    pub fn is_running(&self) -> bool {
+        !self.task.is_finished()
+    }
+
+    /// Whether the worker is waiting for an unavailable backend to recover
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Stop the worker, waiting for a run in progress to finish
+    pub async fn stop(self) {
+        self.shutdown.cancel();
+        let _ = self.task.await;
+    }
+}
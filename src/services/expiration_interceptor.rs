@@ -0,0 +1,205 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::{
+    domain::{
+        errors::{LifecycleError, StorageError, StorageResult},
+        models::{EvaluateLifecycleRequest, LifecycleAction, ObjectMetadata},
+        value_objects::ObjectKey,
+    },
+    ports::{
+        interceptors::ObjectInterceptor,
+        runtime::{Clock, SystemClock},
+        services::LifecycleService,
+    },
+};
+
+use super::lifecycle_service_impl::SCHEDULED_DELETION_RULE_ID;
+
+/// Refuses reads of objects that are past their expiration but not yet deleted
+///
+/// The lifecycle worker only deletes expired objects when it runs, so without
+/// this an object stays readable until the next run. With it, an object whose
+/// own deletion schedule has passed, or that an enabled expiration rule of its
+/// bucket applies to, is reported as [`StorageError::ObjectExpired`].
+pub struct ExpirationInterceptor {
+    lifecycle_service: Arc<dyn LifecycleService>,
+    clock: Arc<dyn Clock>,
+}
+
+impl ExpirationInterceptor {
+    pub fn new(lifecycle_service: Arc<dyn LifecycleService>) -> Self {
+        Self {
+            lifecycle_service,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Use the given clock for deletion schedules instead of the system clock
+    ///
+    /// Rules are evaluated by the lifecycle service with its own clock, so the
+    /// two should agree.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+}
+
+#[async_trait]
+impl ObjectInterceptor for ExpirationInterceptor {
+    async fn pre_get(&self, key: &ObjectKey, metadata: &ObjectMetadata) -> StorageResult<()> {
+        if let Some(delete_at) = metadata.delete_at() {
+            if delete_at <= self.clock.now() {
+                return Err(StorageError::ObjectExpired {
+                    key: key.clone(),
+                    rule_id: SCHEDULED_DELETION_RULE_ID.to_string(),
+                    expired_at: delete_at,
+                });
+            }
+        }
+
+        // Tags are not considered, matching what the lifecycle worker applies
+        let request = EvaluateLifecycleRequest {
+            key: key.clone(),
+            object_created_at: metadata.last_modified,
+            object_tags: HashMap::new(),
+            is_delete_marker: false,
+            is_current_version: true,
+            delete_at: None,
+        };
+        let evaluation = match self
+            .lifecycle_service
+            .evaluate_object_lifecycle(request)
+            .await
+        {
+            Ok(evaluation) => evaluation,
+            // Keys outside a valid bucket have no rules to apply
+            Err(LifecycleError::ValidationFailed { .. }) => return Ok(()),
+            Err(e) => {
+                return Err(StorageError::InfrastructureError {
+                    message: "Failed to evaluate lifecycle rules".to_string(),
+                    source: Some(e.to_string()),
+                });
+            }
+        };
+
+        let created_at: DateTime<Utc> = metadata.last_modified.into();
+        let expiration = evaluation
+            .actions_to_apply
+            .into_iter()
+            .filter_map(|action| {
+                let expired_at = match action.action {
+                    LifecycleAction::Expiration {
+                        days: Some(days), ..
+                    } => created_at + Duration::days(days.into()),
+                    LifecycleAction::Expiration {
+                        date: Some(date), ..
+                    } => date,
+                    _ => return None,
+                };
+                Some((action.rule_id, expired_at))
+            })
+            .min_by_key(|(_, expired_at)| *expired_at);
+
+        match expiration {
+            Some((rule_id, expired_at)) => Err(StorageError::ObjectExpired {
+                key: key.clone(),
+                rule_id,
+                expired_at,
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        adapters::outbound::{
+            persistence::{InMemoryLifecycleRepository, InMemoryObjectRepository},
+            storage::{S3ObjectStoreAdapter, VersionedS3ObjectStoreAdapter},
+        },
+        domain::{
+            models::{LifecycleConfiguration, LifecycleRule, RuleStatus},
+            value_objects::BucketName,
+        },
+        services::LifecycleServiceImpl,
+        testing::FrozenClock,
+    };
+    use chrono::TimeZone;
+    use object_store::memory::InMemory;
+
+    fn metadata(last_modified: DateTime<Utc>) -> ObjectMetadata {
+        ObjectMetadata {
+            content_type: None,
+            content_length: 0,
+            etag: None,
+            last_modified: last_modified.into(),
+            custom_metadata: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_expired_objects_are_refused() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 10, 0, 0, 0).unwrap();
+        let clock = Arc::new(FrozenClock::new(now));
+        let bucket = BucketName::new("logs".to_string()).unwrap();
+        let memory_store = Arc::new(InMemory::new());
+        let object_store = Arc::new(S3ObjectStoreAdapter::new(
+            memory_store.clone(),
+            bucket.clone(),
+        ));
+        let lifecycle_service = LifecycleServiceImpl::new(
+            Arc::new(InMemoryLifecycleRepository::new()),
+            Arc::new(InMemoryObjectRepository::new()),
+            object_store.clone(),
+            Arc::new(VersionedS3ObjectStoreAdapter::new(
+                object_store,
+                memory_store,
+            )),
+        )
+        .with_clock(clock.clone());
+
+        let config = LifecycleConfiguration {
+            bucket: bucket.clone(),
+            rules: vec![LifecycleRule {
+                id: "expire-week".to_string(),
+                status: RuleStatus::Enabled,
+                expiration_days: Some(7),
+                ..Default::default()
+            }],
+        };
+        lifecycle_service
+            .set_lifecycle_configuration(&bucket, config)
+            .await
+            .unwrap();
+
+        let interceptor = ExpirationInterceptor::new(Arc::new(lifecycle_service)).with_clock(clock);
+        let key = ObjectKey::new("logs/app.log".to_string()).unwrap();
+
+        let fresh = metadata(now - Duration::days(1));
+        assert!(interceptor.pre_get(&key, &fresh).await.is_ok());
+
+        let old = metadata(now - Duration::days(8));
+        let result = interceptor.pre_get(&key, &old).await;
+        assert!(matches!(
+            result,
+            Err(StorageError::ObjectExpired { rule_id, expired_at, .. })
+                if rule_id == "expire-week" && expired_at == now - Duration::days(1)
+        ));
+
+        // Objects outside the bucket only expire by their own schedule
+        let other = ObjectKey::new("other/app.log".to_string()).unwrap();
+        assert!(interceptor.pre_get(&other, &old).await.is_ok());
+        let mut scheduled = metadata(now - Duration::days(1));
+        scheduled.set_delete_at(Some(now - Duration::hours(1)));
+        assert!(matches!(
+            interceptor.pre_get(&other, &scheduled).await,
+            Err(StorageError::ObjectExpired { rule_id, .. })
+                if rule_id == SCHEDULED_DELETION_RULE_ID
+        ));
+    }
+}
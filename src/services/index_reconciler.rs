@@ -0,0 +1,507 @@
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+
+use super::WorkerLeases;
+use crate::{
+    domain::{
+        errors::StorageResult,
+        models::{AUDIT_ACTION_INDEX_REPAIRED, AuditEvent, Filter, ObjectMetadata},
+        value_objects::{ObjectKey, VersionId},
+    },
+    ports::{
+        repositories::{AuditLogRepository, ObjectRepository},
+        storage::ObjectStore,
+    },
+};
+
+/// What a reconciliation pass compares and whether it repairs what it finds
+#[derive(Debug, Clone)]
+pub struct ReconcileConfig {
+    /// Only keys starting with this prefix are reconciled
+    pub prefix: String,
+
+    /// Keys changed this recently in either the index or the store are left
+    /// alone, as the write changing them may still be in flight and the
+    /// store's listing may not show it yet
+    pub grace_period: Duration,
+
+    /// Repair drift instead of only reporting it
+    pub repair: bool,
+}
+
+impl Default for ReconcileConfig {
+    fn default() -> Self {
+        Self {
+            prefix: String::new(),
+            grace_period: Duration::from_secs(5 * 60),
+            repair: true,
+        }
+    }
+}
+
+/// How the key index and the store disagree about a key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Discrepancy {
+    /// The store holds the object but the index has no live version of it
+    MissingFromIndex,
+
+    /// The index has a live version of the object but the store does not hold it
+    MissingFromStore,
+
+    /// The stored object's size differs from the indexed content length
+    SizeMismatch { indexed: u64, stored: u64 },
+}
+
+impl std::fmt::Display for Discrepancy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Discrepancy::MissingFromIndex => write!(f, "missing from the index"),
+            Discrepancy::MissingFromStore => write!(f, "missing from the store"),
+            Discrepancy::SizeMismatch { indexed, stored } => {
+                write!(
+                    f,
+                    "size mismatch: indexed {} bytes, stored {}",
+                    indexed, stored
+                )
+            }
+        }
+    }
+}
+
+/// A key on which the index and the store disagree
+#[derive(Debug, Clone)]
+pub struct IndexDrift {
+    pub key: ObjectKey,
+    pub discrepancy: Discrepancy,
+}
+
+/// Outcome of a reconciliation pass
+#[derive(Debug, Clone)]
+pub struct ReconcileReport {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// Live objects in the index
+    pub indexed_keys: u64,
+    /// Objects in the store's listing
+    pub stored_keys: u64,
+    /// Drift that was found and left as it is
+    pub drift: Vec<IndexDrift>,
+    /// Drift that was repaired
+    pub repaired: Vec<IndexDrift>,
+    pub errors: Vec<(ObjectKey, String)>,
+}
+
+/// Totals over every reconciliation pass since the reconciler was created
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReconcileMetrics {
+    pub passes: u64,
+    pub drift_found: u64,
+    pub drift_repaired: u64,
+    pub errors: u64,
+}
+
+#[derive(Default)]
+struct Counters {
+    passes: AtomicU64,
+    drift_found: AtomicU64,
+    drift_repaired: AtomicU64,
+    errors: AtomicU64,
+}
+
+/// Background job that diffs the repository's key index against the store's listing
+///
+/// Object listings are served from the index, so drift between it and the
+/// store, e.g. from a crash between writing an object and recording it, or
+/// from changes made to the store directly, would otherwise go unnoticed.
+/// Every disagreement is confirmed with a lookup of the key before it counts,
+/// since the store's listing may lag behind its writes.
+///
+/// Objects missing from the index are indexed from their stored size, ETag and
+/// content type, and index entries of objects missing from the store are
+/// marked deleted. Size mismatches are only reported; the
+/// [`IntegrityScrubber`](super::IntegrityScrubber) decides which side is
+/// right. Repairs are recorded in the audit log when one is configured.
+///
+/// Keys whose first segment starts with `.` hold the server's own records,
+/// such as `.config/lifecycle/`, and are never reconciled.
+///
+/// With [`WorkerLeases`] shared by several server instances, the passes
+/// started by [`spawn`](Self::spawn) run on one instance per interval.
+#[derive(Clone)]
+pub struct IndexReconciler {
+    repository: Arc<dyn ObjectRepository>,
+    store: Arc<dyn ObjectStore>,
+    audit_log: Option<Arc<dyn AuditLogRepository>>,
+    config: ReconcileConfig,
+    leases: Option<WorkerLeases>,
+    last_report: Arc<RwLock<Option<ReconcileReport>>>,
+    counters: Arc<Counters>,
+}
+
+impl IndexReconciler {
+    pub fn new(repository: Arc<dyn ObjectRepository>, store: Arc<dyn ObjectStore>) -> Self {
+        Self {
+            repository,
+            store,
+            audit_log: None,
+            config: ReconcileConfig::default(),
+            leases: None,
+            last_report: Arc::new(RwLock::new(None)),
+            counters: Arc::new(Counters::default()),
+        }
+    }
+
+    pub fn with_config(mut self, config: ReconcileConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Record repairs in the given audit log
+    pub fn with_audit_log(mut self, audit_log: Arc<dyn AuditLogRepository>) -> Self {
+        self.audit_log = Some(audit_log);
+        self
+    }
+
+    /// Share scheduled passes with the other instances taking `leases`
+    pub fn with_leases(mut self, leases: WorkerLeases) -> Self {
+        self.leases = Some(leases);
+        self
+    }
+
+    /// Report of the most recently completed reconciliation pass
+    pub async fn last_report(&self) -> Option<ReconcileReport> {
+        self.last_report.read().await.clone()
+    }
+
+    /// Drift found and repaired over every pass so far
+    pub fn metrics(&self) -> ReconcileMetrics {
+        ReconcileMetrics {
+            passes: self.counters.passes.load(Ordering::Relaxed),
+            drift_found: self.counters.drift_found.load(Ordering::Relaxed),
+            drift_repaired: self.counters.drift_repaired.load(Ordering::Relaxed),
+            errors: self.counters.errors.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Run reconciliation passes forever, waiting `interval` between the end of one pass and the next
+    pub fn spawn(self, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                let result = match &self.leases {
+                    Some(leases) => {
+                        let lease = format!("reconcile/{}", self.config.prefix);
+                        leases
+                            .run_exclusive(&lease, interval, self.reconcile())
+                            .await
+                    }
+                    None => Ok(self.reconcile().await),
+                };
+                if let Ok(Err(e)) = result {
+                    tracing::error!("Index reconciliation failed: {}", e);
+                }
+                tokio::time::sleep(interval).await;
+            }
+        })
+    }
+
+    /// Compare the index with the store under the configured prefix once
+    pub async fn reconcile(&self) -> StorageResult<ReconcileReport> {
+        let started_at = Utc::now();
+        let settled_before = chrono::Duration::from_std(self.config.grace_period)
+            .ok()
+            .and_then(|grace_period| started_at.checked_sub_signed(grace_period))
+            .unwrap_or(DateTime::<Utc>::MIN_UTC);
+
+        let indexed: BTreeMap<String, _> = self
+            .repository
+            .list_latest_versions(&self.config.prefix, None, None)
+            .await?
+            .into_iter()
+            .filter(|(key, _)| !is_internal(key))
+            .map(|(key, version)| (key.as_str().to_string(), (key, version)))
+            .collect();
+        let stored: BTreeMap<String, _> = self
+            .store
+            .list_objects(&Filter::new().with_prefix(self.config.prefix.clone()))
+            .await?
+            .into_iter()
+            .filter(|item| !is_internal(&item.key))
+            .map(|item| (item.key.as_str().to_string(), item))
+            .collect();
+
+        let mut report = ReconcileReport {
+            started_at,
+            finished_at: started_at,
+            indexed_keys: indexed.len() as u64,
+            stored_keys: stored.len() as u64,
+            drift: Vec::new(),
+            repaired: Vec::new(),
+            errors: Vec::new(),
+        };
+
+        for (key, item) in &stored {
+            match indexed.get(key) {
+                Some((_, version)) if version.size != item.size => {
+                    report.drift.push(IndexDrift {
+                        key: item.key.clone(),
+                        discrepancy: Discrepancy::SizeMismatch {
+                            indexed: version.size,
+                            stored: item.size,
+                        },
+                    });
+                }
+                Some(_) => {}
+                None if item.last_modified > settled_before => {}
+                None => {
+                    let metadata = ObjectMetadata {
+                        content_type: item.content_type.clone(),
+                        content_length: item.size,
+                        etag: item.etag.clone(),
+                        last_modified: item.last_modified.into(),
+                        custom_metadata: Default::default(),
+                    };
+                    self.settle(&mut report, &item.key, Discrepancy::MissingFromIndex, {
+                        let item_key = &item.key;
+                        async move {
+                            self.repository
+                                .save_object_metadata(item_key, &VersionId::generate(), &metadata)
+                                .await
+                        }
+                    })
+                    .await;
+                }
+            }
+        }
+
+        for (key, (object_key, version)) in &indexed {
+            let last_modified: DateTime<Utc> = version.last_modified.into();
+            if stored.contains_key(key) || last_modified > settled_before {
+                continue;
+            }
+            self.settle(&mut report, object_key, Discrepancy::MissingFromStore, {
+                let version_id = &version.version_id;
+                async move {
+                    self.repository
+                        .mark_version_deleted(object_key, version_id)
+                        .await
+                }
+            })
+            .await;
+        }
+
+        report.finished_at = Utc::now();
+        self.counters.passes.fetch_add(1, Ordering::Relaxed);
+        self.counters.drift_found.fetch_add(
+            (report.drift.len() + report.repaired.len()) as u64,
+            Ordering::Relaxed,
+        );
+        self.counters
+            .drift_repaired
+            .fetch_add(report.repaired.len() as u64, Ordering::Relaxed);
+        self.counters
+            .errors
+            .fetch_add(report.errors.len() as u64, Ordering::Relaxed);
+        *self.last_report.write().await = Some(report.clone());
+        Ok(report)
+    }
+
+    /// Confirm that `key` is missing from one side, then record the drift and
+    /// run `repair` if repairs are enabled
+    async fn settle(
+        &self,
+        report: &mut ReconcileReport,
+        key: &ObjectKey,
+        discrepancy: Discrepancy,
+        repair: impl std::future::Future<Output = StorageResult<()>>,
+    ) {
+        // The listings are a snapshot; the key may have been written since
+        let confirmed = match discrepancy {
+            Discrepancy::MissingFromIndex => self.repository.object_exists(key).await.map(|e| !e),
+            _ => self.store.object_exists(key).await.map(|e| !e),
+        };
+        match confirmed {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(e) => {
+                report.errors.push((key.clone(), e.to_string()));
+                return;
+            }
+        }
+
+        let drift = IndexDrift {
+            key: key.clone(),
+            discrepancy,
+        };
+        if !self.config.repair {
+            report.drift.push(drift);
+            return;
+        }
+        if let Err(e) = repair.await {
+            report.errors.push((key.clone(), e.to_string()));
+            report.drift.push(drift);
+            return;
+        }
+
+        if let Some(audit_log) = &self.audit_log {
+            let event = AuditEvent::new(AUDIT_ACTION_INDEX_REPAIRED, key.clone())
+                .with_detail("discrepancy", drift.discrepancy.to_string());
+            if let Err(e) = audit_log.record_event(event).await {
+                report.errors.push((key.clone(), e.to_string()));
+            }
+        }
+        report.repaired.push(drift);
+    }
+}
+
+/// Whether `key` lies under a dot-prefixed root holding the server's own records
+fn is_internal(key: &ObjectKey) -> bool {
+    key.as_str().starts_with('.')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::outbound::persistence::{
+        InMemoryAuditLogRepository, InMemoryObjectRepository,
+    };
+    use crate::adapters::outbound::storage::S3ObjectStoreAdapter;
+    use crate::domain::value_objects::BucketName;
+    use bytes::Bytes;
+    use object_store::memory::InMemory;
+    use std::collections::HashMap;
+
+    fn key(key: &str) -> ObjectKey {
+        ObjectKey::new(key.to_string()).unwrap()
+    }
+
+    async fn index(repository: &InMemoryObjectRepository, key: &ObjectKey, size: u64) {
+        let metadata = ObjectMetadata {
+            content_type: None,
+            content_length: size,
+            etag: None,
+            last_modified: std::time::SystemTime::now() - Duration::from_secs(3600),
+            custom_metadata: HashMap::new(),
+        };
+        repository
+            .save_object_metadata(key, &VersionId::generate(), &metadata)
+            .await
+            .unwrap();
+    }
+
+    fn reconciler(
+        repository: Arc<InMemoryObjectRepository>,
+        store: Arc<S3ObjectStoreAdapter>,
+        repair: bool,
+    ) -> IndexReconciler {
+        IndexReconciler::new(repository, store).with_config(ReconcileConfig {
+            prefix: "data/".to_string(),
+            grace_period: Duration::ZERO,
+            repair,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_reports_and_repairs_drift() {
+        let repository = Arc::new(InMemoryObjectRepository::new());
+        let bucket = BucketName::new("test-bucket".to_string()).unwrap();
+        let store = Arc::new(S3ObjectStoreAdapter::new(Arc::new(InMemory::new()), bucket));
+        let audit_log = Arc::new(InMemoryAuditLogRepository::new());
+
+        let (in_sync, unindexed, lost, resized) = (
+            key("data/in-sync.txt"),
+            key("data/unindexed.txt"),
+            key("data/lost.txt"),
+            key("data/resized.txt"),
+        );
+        for key in [&in_sync, &unindexed, &resized] {
+            store
+                .put_object(key, Bytes::from_static(b"hello"), None)
+                .await
+                .unwrap();
+        }
+        index(&repository, &in_sync, 5).await;
+        index(&repository, &lost, 5).await;
+        index(&repository, &resized, 3).await;
+
+        let report = reconciler(repository.clone(), store.clone(), false)
+            .reconcile()
+            .await
+            .unwrap();
+        assert_eq!((report.indexed_keys, report.stored_keys), (3, 3));
+        assert!(report.repaired.is_empty());
+        let drift: Vec<(&str, &Discrepancy)> = report
+            .drift
+            .iter()
+            .map(|drift| (drift.key.as_str(), &drift.discrepancy))
+            .collect();
+        assert_eq!(
+            drift,
+            [
+                (
+                    "data/resized.txt",
+                    &Discrepancy::SizeMismatch {
+                        indexed: 3,
+                        stored: 5
+                    }
+                ),
+                ("data/unindexed.txt", &Discrepancy::MissingFromIndex),
+                ("data/lost.txt", &Discrepancy::MissingFromStore),
+            ]
+        );
+
+        let reconciler =
+            reconciler(repository.clone(), store.clone(), true).with_audit_log(audit_log.clone());
+        let report = reconciler.reconcile().await.unwrap();
+        assert_eq!(report.repaired.len(), 2);
+        assert_eq!(report.drift.len(), 1);
+        assert!(repository.object_exists(&unindexed).await.unwrap());
+        assert!(!repository.object_exists(&lost).await.unwrap());
+        let events = audit_log.list_events(Some(&unindexed)).await.unwrap();
+        assert_eq!(events[0].action, AUDIT_ACTION_INDEX_REPAIRED);
+
+        // Only the size mismatch is left for the next pass
+        let report = reconciler.reconcile().await.unwrap();
+        assert!(report.repaired.is_empty());
+        assert_eq!(report.drift.len(), 1);
+        assert_eq!(
+            reconciler.metrics(),
+            ReconcileMetrics {
+                passes: 2,
+                drift_found: 4,
+                drift_repaired: 2,
+                errors: 0,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_recent_changes_are_left_alone() {
+        let repository = Arc::new(InMemoryObjectRepository::new());
+        let bucket = BucketName::new("test-bucket".to_string()).unwrap();
+        let store = Arc::new(S3ObjectStoreAdapter::new(Arc::new(InMemory::new()), bucket));
+
+        // An upload that has reached the store but not yet the index
+        store
+            .put_object(&key("data/uploading.txt"), Bytes::from_static(b"hi"), None)
+            .await
+            .unwrap();
+
+        let report = IndexReconciler::new(repository.clone(), store)
+            .reconcile()
+            .await
+            .unwrap();
+        assert!(report.drift.is_empty());
+        assert!(report.repaired.is_empty());
+        assert!(
+            !repository
+                .object_exists(&key("data/uploading.txt"))
+                .await
+                .unwrap()
+        );
+    }
+}
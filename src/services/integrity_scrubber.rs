@@ -0,0 +1,618 @@
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use super::WorkerLeases;
+use crate::{
+    domain::{
+        errors::{StorageError, StorageResult},
+        models::{AUDIT_ACTION_OBJECT_REPAIRED, AuditEvent, ObjectMetadata},
+        value_objects::ObjectKey,
+    },
+    ports::{
+        repositories::{AuditLogRepository, ObjectRepository},
+        storage::ObjectStore,
+    },
+};
+
+/// Custom metadata key set on objects whose data no longer matches their recorded digest
+pub const INTEGRITY_STATUS_METADATA_KEY: &str = "x-integrity-status";
+
+/// Custom metadata key recording when an object was found to be corrupted
+pub const INTEGRITY_CHECKED_AT_METADATA_KEY: &str = "x-integrity-checked-at";
+
+/// Value of [`INTEGRITY_STATUS_METADATA_KEY`] for corrupted objects
+pub const INTEGRITY_STATUS_CORRUPTED: &str = "corrupted";
+
+/// Size of the buffer used while hashing object data
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Limits keeping a scrub from competing with live traffic
+#[derive(Debug, Clone)]
+pub struct ScrubConfig {
+    /// Objects verified per second, unlimited when `None`
+    pub max_objects_per_second: Option<f64>,
+
+    /// Bytes read per second, unlimited when `None`
+    pub max_bytes_per_second: Option<u64>,
+
+    /// Only objects whose key starts with this prefix are scrubbed
+    pub prefix: String,
+}
+
+impl Default for ScrubConfig {
+    fn default() -> Self {
+        Self {
+            max_objects_per_second: Some(10.0),
+            max_bytes_per_second: Some(10 * 1024 * 1024),
+            prefix: String::new(),
+        }
+    }
+}
+
+/// Why an object failed verification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Corruption {
+    /// The data hashes to a different digest than the one recorded at upload
+    ChecksumMismatch { expected: String, actual: String },
+
+    /// The data size differs from the recorded content length
+    SizeMismatch { expected: u64, actual: u64 },
+
+    /// Metadata exists but the data is gone from the store
+    Missing,
+}
+
+impl std::fmt::Display for Corruption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Corruption::ChecksumMismatch { expected, actual } => {
+                write!(
+                    f,
+                    "checksum mismatch: expected {}, found {}",
+                    expected, actual
+                )
+            }
+            Corruption::SizeMismatch { expected, actual } => {
+                write!(
+                    f,
+                    "size mismatch: expected {} bytes, found {}",
+                    expected, actual
+                )
+            }
+            Corruption::Missing => write!(f, "object data missing"),
+        }
+    }
+}
+
+/// An object that failed verification
+#[derive(Debug, Clone)]
+pub struct CorruptObject {
+    pub key: ObjectKey,
+    pub corruption: Corruption,
+}
+
+/// Outcome of a scrub pass
+#[derive(Debug, Clone)]
+pub struct ScrubReport {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub objects_scanned: u64,
+    pub bytes_scanned: u64,
+    /// Objects without a digest that can be verified, e.g. multipart uploads
+    pub objects_skipped: u64,
+    pub corrupted: Vec<CorruptObject>,
+    /// Corrupted objects that were restored from a replica
+    pub repaired: Vec<CorruptObject>,
+    pub errors: Vec<(ObjectKey, String)>,
+}
+
+/// Background job that re-reads stored objects and verifies them against their recorded digests
+///
+/// Objects are visited one at a time and reads are paced according to the
+/// [`ScrubConfig`] limits. Corrupted objects are flagged in their metadata with
+/// [`INTEGRITY_STATUS_METADATA_KEY`] and listed in the [`ScrubReport`].
+///
+/// When replicas are configured, a corrupted object is repaired by copying it back
+/// from the first replica whose copy still matches the recorded digest, and the
+/// repair is recorded in the audit log.
+///
+/// With [`WorkerLeases`] shared by several server instances, the passes
+/// started by [`spawn`](Self::spawn) run on one instance per interval.
+#[derive(Clone)]
+pub struct IntegrityScrubber {
+    repository: Arc<dyn ObjectRepository>,
+    store: Arc<dyn ObjectStore>,
+    replicas: Vec<Arc<dyn ObjectStore>>,
+    audit_log: Option<Arc<dyn AuditLogRepository>>,
+    config: ScrubConfig,
+    leases: Option<WorkerLeases>,
+    last_report: Arc<RwLock<Option<ScrubReport>>>,
+}
+
+impl IntegrityScrubber {
+    pub fn new(repository: Arc<dyn ObjectRepository>, store: Arc<dyn ObjectStore>) -> Self {
+        Self {
+            repository,
+            store,
+            replicas: Vec::new(),
+            audit_log: None,
+            config: ScrubConfig::default(),
+            leases: None,
+            last_report: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    pub fn with_config(mut self, config: ScrubConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Add a replica of the primary store to repair corrupted objects from
+    pub fn with_replica(mut self, replica: Arc<dyn ObjectStore>) -> Self {
+        self.replicas.push(replica);
+        self
+    }
+
+    /// Record repairs in the given audit log
+    pub fn with_audit_log(mut self, audit_log: Arc<dyn AuditLogRepository>) -> Self {
+        self.audit_log = Some(audit_log);
+        self
+    }
+
+    /// Share scheduled passes with the other instances taking `leases`
+    pub fn with_leases(mut self, leases: WorkerLeases) -> Self {
+        self.leases = Some(leases);
+        self
+    }
+
+    /// Report of the most recently completed scrub pass
+    pub async fn last_report(&self) -> Option<ScrubReport> {
+        self.last_report.read().await.clone()
+    }
+
+    /// Run scrub passes forever, waiting `interval` between the end of one pass and the next
+    pub fn spawn(self, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                let result = match &self.leases {
+                    Some(leases) => {
+                        let lease = format!("scrub/{}", self.config.prefix);
+                        leases.run_exclusive(&lease, interval, self.scrub()).await
+                    }
+                    None => Ok(self.scrub().await),
+                };
+                if let Ok(Err(e)) = result {
+                    tracing::error!("Integrity scrub failed: {}", e);
+                }
+                tokio::time::sleep(interval).await;
+            }
+        })
+    }
+
+    /// Verify every object under the configured prefix once
+    pub async fn scrub(&self) -> StorageResult<ScrubReport> {
+        let started_at = Utc::now();
+        let keys = self
+            .repository
+            .list_objects_by_prefix(&self.config.prefix, None)
+            .await?;
+
+        let mut report = ScrubReport {
+            started_at,
+            finished_at: started_at,
+            objects_scanned: 0,
+            bytes_scanned: 0,
+            objects_skipped: 0,
+            corrupted: Vec::new(),
+            repaired: Vec::new(),
+            errors: Vec::new(),
+        };
+        let pacer = Pacer::new(&self.config);
+
+        for key in keys {
+            let metadata = match self.repository.get_object_metadata(&key, None).await {
+                Ok(Some(metadata)) => metadata,
+                Ok(None) => continue,
+                Err(e) => {
+                    report.errors.push((key, e.to_string()));
+                    continue;
+                }
+            };
+
+            let Some(expected) = verifiable_digest(&metadata) else {
+                report.objects_skipped += 1;
+                continue;
+            };
+
+            match self.verify(&key, &metadata, &expected).await {
+                Ok((bytes, corruption)) => {
+                    report.objects_scanned += 1;
+                    report.bytes_scanned += bytes;
+
+                    if let Some(corruption) = corruption {
+                        match self.repair(&key, &metadata, &expected, &corruption).await {
+                            Ok(true) => {
+                                report.repaired.push(CorruptObject { key, corruption });
+                            }
+                            result => {
+                                if let Err(e) = result {
+                                    report.errors.push((key.clone(), e.to_string()));
+                                }
+                                if let Err(e) = self.flag_corrupted(&key).await {
+                                    report.errors.push((key.clone(), e.to_string()));
+                                }
+                                report.corrupted.push(CorruptObject { key, corruption });
+                            }
+                        }
+                    }
+                }
+                Err(e) => report.errors.push((key, e.to_string())),
+            }
+
+            pacer
+                .wait(
+                    report.objects_scanned + report.objects_skipped,
+                    report.bytes_scanned,
+                )
+                .await;
+        }
+
+        report.finished_at = Utc::now();
+        *self.last_report.write().await = Some(report.clone());
+        Ok(report)
+    }
+
+    /// Hash an object's data from the store, returning the bytes read and any corruption found
+    async fn verify(
+        &self,
+        key: &ObjectKey,
+        metadata: &ObjectMetadata,
+        expected: &str,
+    ) -> StorageResult<(u64, Option<Corruption>)> {
+        let (size, actual) = match digest_object(self.store.as_ref(), key).await {
+            Ok(result) => result,
+            Err(StorageError::ObjectNotFound { .. }) => return Ok((0, Some(Corruption::Missing))),
+            Err(e) => return Err(e),
+        };
+
+        let corruption = if size != metadata.content_length {
+            Some(Corruption::SizeMismatch {
+                expected: metadata.content_length,
+                actual: size,
+            })
+        } else if actual != expected {
+            Some(Corruption::ChecksumMismatch {
+                expected: expected.to_string(),
+                actual,
+            })
+        } else {
+            None
+        };
+
+        Ok((size, corruption))
+    }
+
+    /// Restore a corrupted object from the first replica holding a verified copy
+    ///
+    /// Returns `Ok(false)` if no replica has a copy matching the recorded digest.
+    async fn repair(
+        &self,
+        key: &ObjectKey,
+        metadata: &ObjectMetadata,
+        expected: &str,
+        corruption: &Corruption,
+    ) -> StorageResult<bool> {
+        for (index, replica) in self.replicas.iter().enumerate() {
+            match digest_object(replica.as_ref(), key).await {
+                Ok((size, digest)) if size == metadata.content_length && digest == expected => {}
+                _ => continue,
+            }
+
+            let reader = replica.get_object_stream(key, None).await?;
+            self.store
+                .put_object_stream(key, reader, metadata.content_type.as_deref())
+                .await?;
+
+            // The replica could have changed between verifying and copying it
+            let (size, digest) = digest_object(self.store.as_ref(), key).await?;
+            if size != metadata.content_length || digest != expected {
+                return Err(StorageError::StorageBackendError {
+                    message: format!("Repaired copy of {} failed verification", key),
+                });
+            }
+
+            self.clear_corrupted_flag(key, metadata).await?;
+
+            if let Some(audit_log) = &self.audit_log {
+                audit_log
+                    .record_event(
+                        AuditEvent::new(AUDIT_ACTION_OBJECT_REPAIRED, key.clone())
+                            .with_detail("corruption", corruption.to_string())
+                            .with_detail("replica", index.to_string())
+                            .with_detail("etag", expected),
+                    )
+                    .await?;
+            }
+
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
+    /// Remove the corruption flag left on an object by an earlier scrub
+    async fn clear_corrupted_flag(
+        &self,
+        key: &ObjectKey,
+        metadata: &ObjectMetadata,
+    ) -> StorageResult<()> {
+        if !metadata
+            .custom_metadata
+            .contains_key(INTEGRITY_STATUS_METADATA_KEY)
+        {
+            return Ok(());
+        }
+
+        let version_id = self
+            .repository
+            .get_latest_version_id(key)
+            .await?
+            .ok_or_else(|| StorageError::ObjectNotFound { key: key.clone() })?;
+
+        self.repository
+            .modify_object_metadata(key, &version_id, &|metadata| {
+                metadata
+                    .custom_metadata
+                    .remove(INTEGRITY_STATUS_METADATA_KEY);
+                metadata
+                    .custom_metadata
+                    .remove(INTEGRITY_CHECKED_AT_METADATA_KEY);
+                Ok(())
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Mark the latest version of an object as corrupted in its metadata
+    async fn flag_corrupted(&self, key: &ObjectKey) -> StorageResult<()> {
+        let version_id = self
+            .repository
+            .get_latest_version_id(key)
+            .await?
+            .ok_or_else(|| StorageError::ObjectNotFound { key: key.clone() })?;
+
+        let checked_at = Utc::now().to_rfc3339();
+        self.repository
+            .modify_object_metadata(key, &version_id, &|metadata| {
+                metadata.custom_metadata.insert(
+                    INTEGRITY_STATUS_METADATA_KEY.to_string(),
+                    INTEGRITY_STATUS_CORRUPTED.to_string(),
+                );
+                metadata.custom_metadata.insert(
+                    INTEGRITY_CHECKED_AT_METADATA_KEY.to_string(),
+                    checked_at.clone(),
+                );
+                Ok(())
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+/// The MD5 digest recorded for an object, if its ETag is one
+///
+/// Multipart ETags (`<hash>-<parts>`) are not a digest of the data and cannot be verified.
+fn verifiable_digest(metadata: &ObjectMetadata) -> Option<String> {
+    let etag = metadata.etag.as_deref()?.trim_matches('"');
+    if etag.len() == 32 && etag.chars().all(|c| c.is_ascii_hexdigit()) {
+        Some(etag.to_ascii_lowercase())
+    } else {
+        None
+    }
+}
+
+/// Stream an object's data from the store, returning its size and MD5 digest
+pub(crate) async fn digest_object(
+    store: &dyn ObjectStore,
+    key: &ObjectKey,
+) -> StorageResult<(u64, String)> {
+    let mut reader = store.get_object_stream(key, None).await?;
+    let mut context = md5::Context::new();
+    let mut buffer = vec![0u8; READ_BUFFER_SIZE];
+    let mut size = 0u64;
+
+    loop {
+        let read =
+            reader
+                .read(&mut buffer)
+                .await
+                .map_err(|e| StorageError::StorageBackendError {
+                    message: format!("Failed to read {}: {}", key, e),
+                })?;
+        if read == 0 {
+            break;
+        }
+        context.consume(&buffer[..read]);
+        size += read as u64;
+    }
+
+    Ok((size, format!("{:x}", context.compute())))
+}
+
+/// Spaces out work so that a scrub stays within its rate limits
+struct Pacer {
+    started: Instant,
+    max_objects_per_second: Option<f64>,
+    max_bytes_per_second: Option<u64>,
+}
+
+impl Pacer {
+    fn new(config: &ScrubConfig) -> Self {
+        Self {
+            started: Instant::now(),
+            max_objects_per_second: config.max_objects_per_second.filter(|r| *r > 0.0),
+            max_bytes_per_second: config.max_bytes_per_second.filter(|r| *r > 0),
+        }
+    }
+
+    /// How long the work done so far should have taken at the configured rates
+    fn budgeted(&self, objects: u64, bytes: u64) -> Duration {
+        let by_objects = self
+            .max_objects_per_second
+            .map(|rate| Duration::from_secs_f64(objects as f64 / rate))
+            .unwrap_or_default();
+        let by_bytes = self
+            .max_bytes_per_second
+            .map(|rate| Duration::from_secs_f64(bytes as f64 / rate as f64))
+            .unwrap_or_default();
+        by_objects.max(by_bytes)
+    }
+
+    async fn wait(&self, objects: u64, bytes: u64) {
+        tokio::time::sleep_until(self.started + self.budgeted(objects, bytes)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::outbound::persistence::{
+        InMemoryAuditLogRepository, InMemoryObjectRepository,
+    };
+    use crate::adapters::outbound::storage::S3ObjectStoreAdapter;
+    use crate::domain::value_objects::{BucketName, VersionId};
+    use bytes::Bytes;
+    use object_store::memory::InMemory;
+    use std::collections::HashMap;
+
+    async fn store_object(
+        repository: &InMemoryObjectRepository,
+        store: &S3ObjectStoreAdapter,
+        key: &str,
+        data: &'static [u8],
+    ) -> ObjectKey {
+        let key = ObjectKey::new(key.to_string()).unwrap();
+        store
+            .put_object(&key, Bytes::from_static(data), None)
+            .await
+            .unwrap();
+
+        let metadata = ObjectMetadata {
+            content_type: None,
+            content_length: data.len() as u64,
+            etag: Some(format!("{:x}", md5::compute(data))),
+            last_modified: std::time::SystemTime::now(),
+            custom_metadata: HashMap::new(),
+        };
+        repository
+            .save_object_metadata(&key, &VersionId::generate(), &metadata)
+            .await
+            .unwrap();
+        key
+    }
+
+    #[test]
+    fn test_pacer_budget() {
+        let pacer = Pacer::new(&ScrubConfig {
+            max_objects_per_second: Some(10.0),
+            max_bytes_per_second: Some(1000),
+            prefix: String::new(),
+        });
+
+        assert_eq!(pacer.budgeted(5, 0), Duration::from_millis(500));
+        assert_eq!(pacer.budgeted(1, 2000), Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_scrub_detects_corruption() {
+        let repository = Arc::new(InMemoryObjectRepository::new());
+        let bucket = BucketName::new("test-bucket".to_string()).unwrap();
+        let store = Arc::new(S3ObjectStoreAdapter::new(Arc::new(InMemory::new()), bucket));
+
+        store_object(&repository, &store, "data/good.txt", b"hello").await;
+        let bad = store_object(&repository, &store, "data/bad.txt", b"hello").await;
+
+        // Simulate bit rot by replacing the stored data behind the repository's back
+        store
+            .put_object(&bad, Bytes::from_static(b"jello"), None)
+            .await
+            .unwrap();
+
+        let scrubber = IntegrityScrubber::new(repository.clone(), store).with_config(ScrubConfig {
+            max_objects_per_second: None,
+            max_bytes_per_second: None,
+            prefix: "data/".to_string(),
+        });
+        let report = scrubber.scrub().await.unwrap();
+
+        assert_eq!(report.objects_scanned, 2);
+        assert_eq!(report.corrupted.len(), 1);
+        assert_eq!(report.corrupted[0].key, bad);
+        assert!(matches!(
+            report.corrupted[0].corruption,
+            Corruption::ChecksumMismatch { .. }
+        ));
+
+        let metadata = repository
+            .get_object_metadata(&bad, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            metadata
+                .custom_metadata
+                .get(INTEGRITY_STATUS_METADATA_KEY)
+                .map(String::as_str),
+            Some(INTEGRITY_STATUS_CORRUPTED)
+        );
+        assert!(scrubber.last_report().await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_scrub_repairs_from_replica() {
+        let repository = Arc::new(InMemoryObjectRepository::new());
+        let bucket = BucketName::new("test-bucket".to_string()).unwrap();
+        let store = Arc::new(S3ObjectStoreAdapter::new(
+            Arc::new(InMemory::new()),
+            bucket.clone(),
+        ));
+        let replica = Arc::new(S3ObjectStoreAdapter::new(Arc::new(InMemory::new()), bucket));
+        let audit_log = Arc::new(InMemoryAuditLogRepository::new());
+
+        let key = store_object(&repository, &store, "data/object.txt", b"hello").await;
+        replica
+            .put_object(&key, Bytes::from_static(b"hello"), None)
+            .await
+            .unwrap();
+        store
+            .put_object(&key, Bytes::from_static(b"jello"), None)
+            .await
+            .unwrap();
+
+        let scrubber = IntegrityScrubber::new(repository.clone(), store.clone())
+            .with_replica(replica)
+            .with_audit_log(audit_log.clone())
+            .with_config(ScrubConfig {
+                max_objects_per_second: None,
+                max_bytes_per_second: None,
+                prefix: String::new(),
+            });
+        let report = scrubber.scrub().await.unwrap();
+
+        assert!(report.corrupted.is_empty());
+        assert_eq!(report.repaired.len(), 1);
+        assert_eq!(
+            store.get_object(&key).await.unwrap(),
+            Bytes::from_static(b"hello")
+        );
+
+        let events = audit_log.list_events(Some(&key)).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].action, AUDIT_ACTION_OBJECT_REPAIRED);
+    }
+}
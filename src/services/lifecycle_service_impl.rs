@@ -7,24 +7,30 @@ use tokio::sync::RwLock;
 
 use crate::{
     domain::{
-        errors::{LifecycleError, LifecycleResult},
+        errors::{LifecycleError, LifecycleResult, StorageError},
         models::{
-            ApplicableAction, EvaluateLifecycleRequest, LifecycleAction, LifecycleConfiguration,
-            LifecycleEvaluationResult, LifecycleRule, LifecycleStorageClass, RuleStatus,
+            ApplicableAction, EvaluateLifecycleRequest, Filter, LifecycleAction,
+            LifecycleConfiguration, LifecycleEvaluationResult, LifecycleEvent, LifecycleEventType,
+            LifecycleRule, LifecycleStorageClass, PrefixRewrite, RuleStatus,
         },
         value_objects::{BucketName, ObjectKey},
     },
     ports::{
-        repositories::{LifecycleRepository, ObjectRepository},
+        events::LifecycleEventSink,
+        repositories::{LifecycleRepository, MultipartUploadRepository, ObjectRepository},
+        runtime::{Clock, SystemClock},
         services::{
             AppliedAction, BucketLifecycleResults, FailedAction, LifecycleActionResults,
-            LifecycleService, ProcessingError, ProcessingStatus, ValidationError, ValidationResult,
-            ValidationWarning,
+            LifecycleService, LifecycleSimulationResults, ProcessingError, ProcessingStatus,
+            SimulatedObject, ValidationError, ValidationResult, ValidationWarning,
         },
         storage::{ObjectStore, VersionedObjectStore},
     },
 };
 
+/// Rule ID reported for actions triggered by an object's own delete-at schedule
+pub const SCHEDULED_DELETION_RULE_ID: &str = "scheduled-deletion";
+
 /// Implementation of the LifecycleService
 #[derive(Clone)]
 pub struct LifecycleServiceImpl {
@@ -32,7 +38,10 @@ pub struct LifecycleServiceImpl {
     object_repo: Arc<dyn ObjectRepository>,
     object_store: Arc<dyn ObjectStore>,
     versioned_store: Arc<dyn VersionedObjectStore>,
+    multipart_uploads: Option<Arc<dyn MultipartUploadRepository>>,
     processing_status: Arc<RwLock<HashMap<BucketName, ProcessingStatus>>>,
+    clock: Arc<dyn Clock>,
+    event_sinks: Vec<Arc<dyn LifecycleEventSink>>,
 }
 
 impl LifecycleServiceImpl {
@@ -47,7 +56,42 @@ impl LifecycleServiceImpl {
             object_repo,
             object_store,
             versioned_store,
+            multipart_uploads: None,
             processing_status: Arc::new(RwLock::new(HashMap::new())),
+            clock: Arc::new(SystemClock),
+            event_sinks: Vec::new(),
+        }
+    }
+
+    /// Read the current time from `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Abort the incomplete multipart uploads tracked in `repository` when
+    /// processing a bucket, as its rules direct
+    pub fn with_multipart_uploads(
+        mut self,
+        repository: Arc<dyn MultipartUploadRepository>,
+    ) -> Self {
+        self.multipart_uploads = Some(repository);
+        self
+    }
+
+    /// Publish the expirations, transitions and aborted uploads that
+    /// processing carries out to `sink`, after those of earlier sinks
+    pub fn with_event_sink(mut self, sink: Arc<dyn LifecycleEventSink>) -> Self {
+        self.event_sinks.push(sink);
+        self
+    }
+
+    /// Publish `event` to every sink, reporting the sinks that fail
+    async fn publish(&self, event: LifecycleEvent) {
+        for sink in &self.event_sinks {
+            if let Err(e) = sink.publish(&event).await {
+                tracing::warn!("Failed to publish lifecycle event: {}", e);
+            }
         }
     }
 }
@@ -101,146 +145,44 @@ impl LifecycleService for LifecycleServiceImpl {
         Ok(())
     }
 
+    async fn copy_lifecycle_configuration(
+        &self,
+        source: &BucketName,
+        target: &BucketName,
+        prefix_rewrite: Option<&PrefixRewrite>,
+    ) -> LifecycleResult<LifecycleConfiguration> {
+        let config = self
+            .get_lifecycle_configuration(source)
+            .await?
+            .ok_or_else(|| LifecycleError::ConfigurationNotFound {
+                bucket: source.clone(),
+            })?;
+
+        let copy = config.copy_to(target.clone(), prefix_rewrite);
+        self.set_lifecycle_configuration(target, copy.clone())
+            .await?;
+
+        Ok(copy)
+    }
+
     async fn evaluate_object_lifecycle(
         &self,
         request: EvaluateLifecycleRequest,
     ) -> LifecycleResult<LifecycleEvaluationResult> {
+        let current_time = self.clock.system_time();
+
         // Get the bucket from the object key (extract from path)
         let bucket_name = self.extract_bucket_from_key(&request.key)?;
 
         // Get lifecycle configuration for this bucket
-        let config = match self.get_lifecycle_configuration(&bucket_name).await? {
-            Some(config) => config,
-            None => {
-                return Ok(LifecycleEvaluationResult {
-                    actions_to_apply: Vec::new(),
-                });
-            }
+        let rules = match self.get_lifecycle_configuration(&bucket_name).await? {
+            Some(config) => config.rules,
+            None => Vec::new(),
         };
 
-        let mut actions_to_apply = Vec::new();
-        let current_time = SystemTime::now();
-
-        for rule in &config.rules {
-            if rule.status != RuleStatus::Enabled {
-                continue;
-            }
-
-            // Check if rule matches this object
-            if !rule.matches(&request.key, &request.object_tags, 0) {
-                continue;
-            }
-
-            // Evaluate expiration rules
-            if let Some(days) = rule.expiration_days {
-                if self.should_expire_by_days(&request, days, current_time) {
-                    actions_to_apply.push(ApplicableAction {
-                        rule_id: rule.id.clone(),
-                        action: LifecycleAction::Expiration {
-                            days: Some(days),
-                            date: None,
-                        },
-                        reason: format!("Object is {} days old", days),
-                    });
-                }
-            }
-
-            if let Some(date) = rule.expiration_date {
-                if self.should_expire_by_date(&request, date, current_time) {
-                    actions_to_apply.push(ApplicableAction {
-                        rule_id: rule.id.clone(),
-                        action: LifecycleAction::Expiration {
-                            days: None,
-                            date: Some(date),
-                        },
-                        reason: format!("Object scheduled to expire on {}", date),
-                    });
-                }
-            }
-
-            // Evaluate transition rules
-            if let Some(days) = rule.transition_days {
-                if let Some(storage_class) = &rule.transition_storage_class {
-                    if self.should_transition_by_days(&request, days, current_time) {
-                        actions_to_apply.push(ApplicableAction {
-                            rule_id: rule.id.clone(),
-                            action: LifecycleAction::Transition {
-                                days: Some(days),
-                                date: None,
-                                storage_class: storage_class.clone(),
-                            },
-                            reason: format!(
-                                "Object should transition to {} after {} days",
-                                storage_class.as_str(),
-                                days
-                            ),
-                        });
-                    }
-                }
-            }
-
-            // Evaluate delete marker expiration
-            if let Some(days) = rule.del_marker_expiration_days {
-                if request.is_delete_marker
-                    && self.should_expire_by_days(&request, days, current_time)
-                {
-                    actions_to_apply.push(ApplicableAction {
-                        rule_id: rule.id.clone(),
-                        action: LifecycleAction::ExpireDeleteMarker { days },
-                        reason: format!("Delete marker is {} days old", days),
-                    });
-                }
-            }
-
-            // Evaluate non-current version expiration
-            if let Some(days) = rule.noncurrent_version_expiration_noncurrent_days {
-                if !request.is_current_version
-                    && self.should_expire_by_days(&request, days, current_time)
-                {
-                    actions_to_apply.push(ApplicableAction {
-                        rule_id: rule.id.clone(),
-                        action: LifecycleAction::NonCurrentVersionExpiration { days },
-                        reason: format!("Non-current version is {} days old", days),
-                    });
-                }
-            }
-
-            // Evaluate non-current version transition
-            if let Some(days) = rule.noncurrent_version_transition_noncurrent_days {
-                if let Some(storage_class) = &rule.noncurrent_version_transition_storage_class {
-                    if !request.is_current_version
-                        && self.should_transition_by_days(&request, days, current_time)
-                    {
-                        actions_to_apply.push(ApplicableAction {
-                            rule_id: rule.id.clone(),
-                            action: LifecycleAction::NonCurrentVersionTransition {
-                                days,
-                                storage_class: storage_class.clone(),
-                            },
-                            reason: format!(
-                                "Non-current version should transition after {} days",
-                                days
-                            ),
-                        });
-                    }
-                }
-            }
-
-            // Evaluate incomplete multipart upload cleanup
-            if let Some(days) = rule.abort_incomplete_multipart_upload_days_after_initiation {
-                // This would require checking multipart upload status
-                // For now, we'll create the action but implementation depends on store capabilities
-                actions_to_apply.push(ApplicableAction {
-                    rule_id: rule.id.clone(),
-                    action: LifecycleAction::AbortIncompleteMultipartUpload {
-                        days_after_initiation: days,
-                    },
-                    reason: format!("Cleanup incomplete uploads after {} days", days),
-                });
-            }
-        }
-
-        Ok(LifecycleEvaluationResult { actions_to_apply })
+        Ok(LifecycleEvaluationResult {
+            actions_to_apply: self.evaluate_rules(&rules, &request, current_time),
+        })
     }
 
     async fn apply_lifecycle_actions(
@@ -252,7 +194,7 @@ impl LifecycleService for LifecycleServiceImpl {
         let mut failed_actions = Vec::new();
 
         for action in actions {
-            let start_time = SystemTime::now();
+            let start_time = self.clock.system_time();
 
             let result = match &action.action {
                 LifecycleAction::Expiration { .. } => {
@@ -275,8 +217,37 @@ impl LifecycleService for LifecycleServiceImpl {
                 LifecycleAction::AbortIncompleteMultipartUpload { .. } => {
                     self.apply_multipart_cleanup(key, &action).await
                 }
+                LifecycleAction::ScheduledDeletion { .. } => {
+                    self.apply_scheduled_deletion(key, &action).await
+                }
             };
 
+            // Noncurrent version expirations publish one event per version
+            let event = match (&action.action, &result) {
+                (
+                    LifecycleAction::Expiration { .. } | LifecycleAction::ScheduledDeletion { .. },
+                    Ok(_),
+                ) => Some(LifecycleEvent::new(
+                    LifecycleEventType::ObjectExpired,
+                    key.clone(),
+                    action.rule_id.clone(),
+                    self.clock.now(),
+                )),
+                (LifecycleAction::Transition { storage_class, .. }, Ok(_)) => Some(
+                    LifecycleEvent::new(
+                        LifecycleEventType::ObjectTransitioned,
+                        key.clone(),
+                        action.rule_id.clone(),
+                        self.clock.now(),
+                    )
+                    .with_storage_class(storage_class.clone()),
+                ),
+                _ => None,
+            };
+            if let Some(event) = event {
+                self.publish(event).await;
+            }
+
             match result {
                 Ok(action_type) => {
                     applied_actions.push(AppliedAction {
@@ -306,134 +277,9 @@ impl LifecycleService for LifecycleServiceImpl {
         &self,
         bucket: &BucketName,
     ) -> LifecycleResult<BucketLifecycleResults> {
-        let start_time = SystemTime::now();
-
-        // Update processing status to running
-        {
-            let mut status_map = self.processing_status.write().await;
-            status_map.insert(
-                bucket.clone(),
-                ProcessingStatus {
-                    is_running: true,
-                    last_run: Some(start_time),
-                    next_scheduled_run: Some(start_time + Duration::from_secs(86400)), // Next day
-                    last_run_results: None,
-                },
-            );
-        }
-
-        let mut objects_processed = 0;
-        let mut objects_affected = 0;
-        let mut actions_applied = 0;
-        let mut errors = Vec::new();
-
-        // Get all objects in the bucket (this is a simplified approach)
-        // In a real implementation, this would be paginated
-        let objects = match self
-            .object_store
-            .list_objects(Some(bucket.as_str()), None)
-            .await
-        {
-            Ok(objects) => objects,
-            Err(e) => {
-                errors.push(ProcessingError {
-                    object_key: ObjectKey::new("unknown".to_string()).unwrap(),
-                    rule_id: "system".to_string(),
-                    error: format!("Failed to list bucket objects: {}", e),
-                });
-
-                let duration = start_time.elapsed().unwrap_or(Duration::from_secs(0));
-                return Ok(BucketLifecycleResults {
-                    bucket: bucket.clone(),
-                    objects_processed: 0,
-                    objects_affected: 0,
-                    actions_applied: 0,
-                    errors,
-                    duration,
-                });
-            }
-        };
-
-        for object_info in objects {
-            objects_processed += 1;
-
-            // Create evaluation request for this object
-            let request = EvaluateLifecycleRequest {
-                key: object_info.key.clone(),
-                object_created_at: object_info.last_modified,
-                object_tags: HashMap::new(), // Would need to fetch actual tags
-                is_delete_marker: false,     // Would need to determine this
-                is_current_version: true,    // Would need to determine this
-            };
-
-            // Evaluate lifecycle rules for this object
-            match self.evaluate_object_lifecycle(request).await {
-                Ok(evaluation) => {
-                    if !evaluation.actions_to_apply.is_empty() {
-                        objects_affected += 1;
-
-                        // Apply actions
-                        match self
-                            .apply_lifecycle_actions(&object_info.key, evaluation.actions_to_apply)
-                            .await
-                        {
-                            Ok(results) => {
-                                actions_applied += results.applied_actions.len();
-
-                                // Add any failed actions as errors
-                                for failed in results.failed_actions {
-                                    errors.push(ProcessingError {
-                                        object_key: object_info.key.clone(),
-                                        rule_id: failed.rule_id,
-                                        error: failed.error,
-                                    });
-                                }
-                            }
-                            Err(e) => {
-                                errors.push(ProcessingError {
-                                    object_key: object_info.key.clone(),
-                                    rule_id: "apply_actions".to_string(),
-                                    error: e.to_string(),
-                                });
-                            }
-                        }
-                    }
-                }
-                Err(e) => {
-                    errors.push(ProcessingError {
-                        object_key: object_info.key.clone(),
-                        rule_id: "evaluation".to_string(),
-                        error: e.to_string(),
-                    });
-                }
-            }
-        }
-
-        let duration = start_time.elapsed().unwrap_or(Duration::from_secs(0));
-        let results = BucketLifecycleResults {
-            bucket: bucket.clone(),
-            objects_processed,
-            objects_affected,
-            actions_applied,
-            errors,
-            duration,
-        };
-
-        // Update processing status
-        {
-            let mut status_map = self.processing_status.write().await;
-            status_map.insert(
-                bucket.clone(),
-                ProcessingStatus {
-                    is_running: false,
-                    last_run: Some(start_time),
-                    next_scheduled_run: Some(SystemTime::now() + Duration::from_secs(86400)),
-                    last_run_results: Some(results.clone()),
-                },
-            );
-        }
-
-        Ok(results)
+        let status = self.begin_processing(bucket).await?;
+        let start_time = status.last_run.unwrap_or_else(|| self.clock.system_time());
+        Ok(self.run_processing(bucket, start_time).await)
     }
 
     async fn enable_rule(&self, bucket: &BucketName, rule_id: &str) -> LifecycleResult<()> {
@@ -551,21 +397,316 @@ impl LifecycleService for LifecycleServiceImpl {
         })
     }
 
-    async fn get_processing_status(
+    async fn simulate_bucket_lifecycle(
         &self,
         bucket: &BucketName,
-    ) -> LifecycleResult<ProcessingStatus> {
+        config: Option<LifecycleConfiguration>,
+        as_of: DateTime<Utc>,
+    ) -> LifecycleResult<LifecycleSimulationResults> {
+        let rules = match config {
+            Some(config) => {
+                let validation = self.validate_configuration(&config).await?;
+                if !validation.is_valid {
+                    return Err(LifecycleError::ValidationFailed {
+                        errors: validation.errors.into_iter().map(|e| e.message).collect(),
+                    });
+                }
+                config.rules
+            }
+            None => self
+                .get_lifecycle_configuration(bucket)
+                .await?
+                .map(|config| config.rules)
+                .unwrap_or_default(),
+        };
+
+        let bucket_prefix = format!("{}/", bucket.as_str());
+        let objects = self
+            .object_store
+            .list_objects(&Filter::new().with_prefix(bucket_prefix.clone()))
+            .await
+            .map_err(|e| LifecycleError::ProcessingError {
+                message: format!("Failed to list bucket objects: {}", e),
+            })?;
+
+        let current_time: SystemTime = as_of.into();
+        let objects_evaluated = objects.len();
+        let mut affected_objects = Vec::new();
+
+        for object in objects {
+            let delete_at = self
+                .object_repo
+                .get_object_metadata(&object.key, None)
+                .await
+                .map_err(|e| LifecycleError::RepositoryError {
+                    message: format!("Failed to read object metadata: {}", e),
+                })?
+                .and_then(|metadata| metadata.delete_at());
+
+            // Rule prefixes are relative to the bucket
+            let key = object
+                .key
+                .as_str()
+                .strip_prefix(&bucket_prefix)
+                .and_then(|key| ObjectKey::new(key.to_string()).ok())
+                .unwrap_or_else(|| object.key.clone());
+
+            let request = EvaluateLifecycleRequest {
+                key,
+                object_created_at: object.last_modified.into(),
+                object_tags: HashMap::new(),
+                is_delete_marker: false,
+                is_current_version: true,
+                delete_at,
+            };
+
+            let actions = self.evaluate_rules(&rules, &request, current_time);
+            if !actions.is_empty() {
+                affected_objects.push(SimulatedObject {
+                    object_key: object.key,
+                    actions,
+                });
+            }
+        }
+
+        Ok(LifecycleSimulationResults {
+            bucket: bucket.clone(),
+            as_of,
+            objects_evaluated,
+            affected_objects,
+        })
+    }
+
+    async fn get_processing_status(
+        &self,
+        bucket: &BucketName,
+    ) -> LifecycleResult<ProcessingStatus> {
         let status_map = self.processing_status.read().await;
         Ok(status_map.get(bucket).cloned().unwrap_or(ProcessingStatus {
             is_running: false,
+            cancelled: false,
             last_run: None,
             next_scheduled_run: None,
             last_run_results: None,
         }))
     }
+
+    async fn start_bucket_lifecycle(
+        &self,
+        bucket: &BucketName,
+    ) -> LifecycleResult<ProcessingStatus> {
+        let status = self.begin_processing(bucket).await?;
+        let start_time = status.last_run.unwrap_or_else(|| self.clock.system_time());
+
+        let service = self.clone();
+        let bucket = bucket.clone();
+        tokio::spawn(async move { service.run_processing(&bucket, start_time).await });
+
+        Ok(status)
+    }
+
+    async fn cancel_bucket_lifecycle(
+        &self,
+        bucket: &BucketName,
+    ) -> LifecycleResult<ProcessingStatus> {
+        let mut status_map = self.processing_status.write().await;
+        match status_map.get_mut(bucket) {
+            Some(status) if status.is_running => {
+                status.cancelled = true;
+                Ok(status.clone())
+            }
+            _ => Err(LifecycleError::ProcessingNotRunning {
+                bucket: bucket.clone(),
+            }),
+        }
+    }
 }
 
 impl LifecycleServiceImpl {
+    /// Record that processing of `bucket` is running, unless it already is
+    async fn begin_processing(&self, bucket: &BucketName) -> LifecycleResult<ProcessingStatus> {
+        let start_time = self.clock.system_time();
+        let mut status_map = self.processing_status.write().await;
+        if status_map
+            .get(bucket)
+            .is_some_and(|status| status.is_running)
+        {
+            return Err(LifecycleError::ProcessingInProgress {
+                bucket: bucket.clone(),
+            });
+        }
+
+        let status = ProcessingStatus {
+            is_running: true,
+            cancelled: false,
+            last_run: Some(start_time),
+            next_scheduled_run: Some(start_time + Duration::from_secs(86400)), // Next day
+            last_run_results: None,
+        };
+        status_map.insert(bucket.clone(), status.clone());
+        Ok(status)
+    }
+
+    /// Whether the running processing of `bucket` was asked to stop
+    async fn is_cancelled(&self, bucket: &BucketName) -> bool {
+        self.processing_status
+            .read()
+            .await
+            .get(bucket)
+            .is_some_and(|status| status.cancelled)
+    }
+
+    /// Process the bucket's lifecycle, begun with
+    /// [`begin_processing`](Self::begin_processing) at `start_time`, and record
+    /// the results in its status
+    async fn run_processing(
+        &self,
+        bucket: &BucketName,
+        start_time: SystemTime,
+    ) -> BucketLifecycleResults {
+        let mut objects_processed = 0;
+        let mut objects_affected = 0;
+        let mut actions_applied = 0;
+        let mut errors = Vec::new();
+
+        // Get all objects in the bucket (this is a simplified approach)
+        // In a real implementation, this would be paginated
+        let objects = match self
+            .object_store
+            .list_objects(&Filter::new().with_prefix(format!("{}/", bucket.as_str())))
+            .await
+        {
+            Ok(objects) => objects,
+            Err(e) => {
+                errors.push(ProcessingError {
+                    object_key: ObjectKey::new("unknown".to_string()).unwrap(),
+                    rule_id: "system".to_string(),
+                    error: format!("Failed to list bucket objects: {}", e),
+                });
+                Vec::new()
+            }
+        };
+
+        for object_info in objects {
+            if self.is_cancelled(bucket).await {
+                break;
+            }
+            objects_processed += 1;
+
+            // Pick up any per-object deletion schedule from the stored metadata
+            let delete_at = match self
+                .object_repo
+                .get_object_metadata(&object_info.key, None)
+                .await
+            {
+                Ok(metadata) => metadata.and_then(|m| m.delete_at()),
+                Err(e) => {
+                    errors.push(ProcessingError {
+                        object_key: object_info.key.clone(),
+                        rule_id: SCHEDULED_DELETION_RULE_ID.to_string(),
+                        error: format!("Failed to read object metadata: {}", e),
+                    });
+                    None
+                }
+            };
+
+            // Create evaluation request for this object
+            let request = EvaluateLifecycleRequest {
+                key: object_info.key.clone(),
+                object_created_at: object_info.last_modified.into(),
+                object_tags: HashMap::new(), // Would need to fetch actual tags
+                is_delete_marker: false,     // Would need to determine this
+                is_current_version: true,    // Would need to determine this
+                delete_at,
+            };
+
+            // Evaluate lifecycle rules for this object
+            match self.evaluate_object_lifecycle(request).await {
+                Ok(evaluation) => {
+                    if !evaluation.actions_to_apply.is_empty() {
+                        objects_affected += 1;
+
+                        // Apply actions
+                        match self
+                            .apply_lifecycle_actions(&object_info.key, evaluation.actions_to_apply)
+                            .await
+                        {
+                            Ok(results) => {
+                                actions_applied += results.applied_actions.len();
+
+                                // Add any failed actions as errors
+                                for failed in results.failed_actions {
+                                    errors.push(ProcessingError {
+                                        object_key: object_info.key.clone(),
+                                        rule_id: failed.rule_id,
+                                        error: failed.error,
+                                    });
+                                }
+                            }
+                            Err(e) => {
+                                errors.push(ProcessingError {
+                                    object_key: object_info.key.clone(),
+                                    rule_id: "apply_actions".to_string(),
+                                    error: e.to_string(),
+                                });
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    errors.push(ProcessingError {
+                        object_key: object_info.key.clone(),
+                        rule_id: "evaluation".to_string(),
+                        error: e.to_string(),
+                    });
+                }
+            }
+        }
+
+        if !self.is_cancelled(bucket).await {
+            actions_applied += self
+                .abort_incomplete_multipart_uploads(bucket, &mut errors)
+                .await;
+            actions_applied += self
+                .remove_expired_delete_markers(bucket, &mut errors)
+                .await;
+        }
+
+        let duration = self
+            .clock
+            .system_time()
+            .duration_since(start_time)
+            .unwrap_or(Duration::from_secs(0));
+        let results = BucketLifecycleResults {
+            bucket: bucket.clone(),
+            objects_processed,
+            objects_affected,
+            actions_applied,
+            errors,
+            duration,
+        };
+
+        // Update processing status
+        {
+            let mut status_map = self.processing_status.write().await;
+            let cancelled = status_map
+                .get(bucket)
+                .is_some_and(|status| status.cancelled);
+            status_map.insert(
+                bucket.clone(),
+                ProcessingStatus {
+                    is_running: false,
+                    cancelled,
+                    last_run: Some(start_time),
+                    next_scheduled_run: Some(self.clock.system_time() + Duration::from_secs(86400)),
+                    last_run_results: Some(results.clone()),
+                },
+            );
+        }
+
+        results
+    }
+
     /// Helper method to extract bucket name from object key
     fn extract_bucket_from_key(&self, key: &ObjectKey) -> LifecycleResult<BucketName> {
         // This is a simplified implementation
@@ -582,6 +723,150 @@ impl LifecycleServiceImpl {
         }
     }
 
+    /// Evaluate rules and the object's own deletion schedule at `current_time`
+    fn evaluate_rules(
+        &self,
+        rules: &[LifecycleRule],
+        request: &EvaluateLifecycleRequest,
+        current_time: SystemTime,
+    ) -> Vec<ApplicableAction> {
+        let mut actions_to_apply = Vec::new();
+
+        // Per-object scheduled deletion applies regardless of bucket rules
+        if let Some(delete_at) = request.delete_at {
+            if request.is_current_version
+                && self.should_expire_by_date(request, delete_at, current_time)
+            {
+                actions_to_apply.push(ApplicableAction {
+                    rule_id: SCHEDULED_DELETION_RULE_ID.to_string(),
+                    action: LifecycleAction::ScheduledDeletion { delete_at },
+                    reason: format!("Object scheduled for deletion at {}", delete_at),
+                });
+            }
+        }
+
+        for rule in rules {
+            if rule.status != RuleStatus::Enabled {
+                continue;
+            }
+
+            // Check if rule matches this object
+            if !rule.matches(&request.key, &request.object_tags, 0) {
+                continue;
+            }
+
+            // Evaluate expiration rules
+            if let Some(days) = rule.expiration_days {
+                if self.should_expire_by_days(request, days, current_time) {
+                    actions_to_apply.push(ApplicableAction {
+                        rule_id: rule.id.clone(),
+                        action: LifecycleAction::Expiration {
+                            days: Some(days),
+                            date: None,
+                        },
+                        reason: format!("Object is {} days old", days),
+                    });
+                }
+            }
+
+            if let Some(date) = rule.expiration_date {
+                if self.should_expire_by_date(request, date, current_time) {
+                    actions_to_apply.push(ApplicableAction {
+                        rule_id: rule.id.clone(),
+                        action: LifecycleAction::Expiration {
+                            days: None,
+                            date: Some(date),
+                        },
+                        reason: format!("Object scheduled to expire on {}", date),
+                    });
+                }
+            }
+
+            // Evaluate transition rules
+            if let Some(days) = rule.transition_days {
+                if let Some(storage_class) = &rule.transition_storage_class {
+                    if self.should_transition_by_days(request, days, current_time) {
+                        actions_to_apply.push(ApplicableAction {
+                            rule_id: rule.id.clone(),
+                            action: LifecycleAction::Transition {
+                                days: Some(days),
+                                date: None,
+                                storage_class: storage_class.clone(),
+                            },
+                            reason: format!(
+                                "Object should transition to {} after {} days",
+                                storage_class.as_str(),
+                                days
+                            ),
+                        });
+                    }
+                }
+            }
+
+            // Evaluate delete marker expiration
+            if let Some(days) = rule.del_marker_expiration_days {
+                if request.is_delete_marker
+                    && self.should_expire_by_days(request, days, current_time)
+                {
+                    actions_to_apply.push(ApplicableAction {
+                        rule_id: rule.id.clone(),
+                        action: LifecycleAction::ExpireDeleteMarker { days },
+                        reason: format!("Delete marker is {} days old", days),
+                    });
+                }
+            }
+
+            // Evaluate non-current version expiration
+            if let Some(days) = rule.noncurrent_version_expiration_noncurrent_days {
+                if !request.is_current_version
+                    && self.should_expire_by_days(request, days, current_time)
+                {
+                    actions_to_apply.push(ApplicableAction {
+                        rule_id: rule.id.clone(),
+                        action: LifecycleAction::NonCurrentVersionExpiration { days },
+                        reason: format!("Non-current version is {} days old", days),
+                    });
+                }
+            }
+
+            // Evaluate non-current version transition
+            if let Some(days) = rule.noncurrent_version_transition_noncurrent_days {
+                if let Some(storage_class) = &rule.noncurrent_version_transition_storage_class {
+                    if !request.is_current_version
+                        && self.should_transition_by_days(request, days, current_time)
+                    {
+                        actions_to_apply.push(ApplicableAction {
+                            rule_id: rule.id.clone(),
+                            action: LifecycleAction::NonCurrentVersionTransition {
+                                days,
+                                storage_class: storage_class.clone(),
+                            },
+                            reason: format!(
+                                "Non-current version should transition after {} days",
+                                days
+                            ),
+                        });
+                    }
+                }
+            }
+
+            // Evaluate incomplete multipart upload cleanup
+            if let Some(days) = rule.abort_incomplete_multipart_upload_days_after_initiation {
+                // This would require checking multipart upload status
+                // For now, we'll create the action but implementation depends on store capabilities
+                actions_to_apply.push(ApplicableAction {
+                    rule_id: rule.id.clone(),
+                    action: LifecycleAction::AbortIncompleteMultipartUpload {
+                        days_after_initiation: days,
+                    },
+                    reason: format!("Cleanup incomplete uploads after {} days", days),
+                });
+            }
+        }
+
+        actions_to_apply
+    }
+
     /// Check if object should expire based on days
     fn should_expire_by_days(
         &self,
@@ -659,6 +944,22 @@ impl LifecycleServiceImpl {
         Ok("expiration".to_string())
     }
 
+    /// Apply scheduled deletion
+    async fn apply_scheduled_deletion(
+        &self,
+        key: &ObjectKey,
+        _action: &ApplicableAction,
+    ) -> LifecycleResult<String> {
+        self.object_store.delete_object(key).await.map_err(|e| {
+            LifecycleError::ActionExecutionFailed {
+                action: "scheduled_deletion".to_string(),
+                reason: e.to_string(),
+            }
+        })?;
+
+        Ok("scheduled_deletion".to_string())
+    }
+
     /// Apply transition action
     async fn apply_transition_action(
         &self,
@@ -685,12 +986,70 @@ impl LifecycleServiceImpl {
     }
 
     /// Apply non-current version expiration
+    ///
+    /// Deletes the noncurrent versions of the object that are at least the
+    /// action's number of days old. Pinned versions are left in place.
     async fn apply_noncurrent_version_expiration(
         &self,
-        _key: &ObjectKey,
-        _action: &ApplicableAction,
+        key: &ObjectKey,
+        action: &ApplicableAction,
     ) -> LifecycleResult<String> {
-        // Non-current version cleanup would require versioning logic
+        let days = match action.action {
+            LifecycleAction::NonCurrentVersionExpiration { days } => days,
+            _ => return Ok("noncurrent_version_expiration".to_string()),
+        };
+        let failed = |e: StorageError| LifecycleError::ActionExecutionFailed {
+            action: "noncurrent_version_expiration".to_string(),
+            reason: e.to_string(),
+        };
+
+        let now = self.clock.system_time();
+        let max_age = Duration::from_secs(days as u64 * 86400);
+        let versions = self
+            .object_repo
+            .list_object_versions(key)
+            .await
+            .map_err(failed)?
+            .versions;
+
+        for version in versions {
+            let age = now
+                .duration_since(version.last_modified)
+                .unwrap_or(Duration::from_secs(0));
+            if version.is_latest || version.deleted || age < max_age {
+                continue;
+            }
+
+            // Pinned and retained versions outlive expiration; nothing bypasses retention here
+            let protected = self
+                .object_repo
+                .get_object_metadata(key, Some(&version.version_id))
+                .await
+                .map_err(failed)?
+                .is_some_and(|metadata| metadata.deletion_blocked_reason(now, false).is_some());
+            if protected {
+                continue;
+            }
+
+            self.versioned_store
+                .delete_object_version(key, &version.version_id)
+                .await
+                .map_err(failed)?;
+            self.object_repo
+                .mark_version_deleted(key, &version.version_id)
+                .await
+                .map_err(failed)?;
+
+            let event = LifecycleEvent::new(
+                LifecycleEventType::ObjectExpired,
+                key.clone(),
+                action.rule_id.clone(),
+                self.clock.now(),
+            )
+            .with_version_id(version.version_id);
+            self.publish(event).await;
+        }
+
         Ok("noncurrent_version_expiration".to_string())
     }
 
@@ -705,6 +1064,173 @@ impl LifecycleServiceImpl {
         Ok("noncurrent_version_transition".to_string())
     }
 
+    /// Abort the bucket's multipart uploads that were initiated longer ago
+    /// than a matching rule allows, returning how many were aborted
+    async fn abort_incomplete_multipart_uploads(
+        &self,
+        bucket: &BucketName,
+        errors: &mut Vec<ProcessingError>,
+    ) -> usize {
+        let Some(multipart_uploads) = &self.multipart_uploads else {
+            return 0;
+        };
+        let rules = match self.lifecycle_repo.get_configuration(bucket).await {
+            Ok(Some(config)) => config.rules,
+            Ok(None) => return 0,
+            Err(e) => {
+                errors.push(ProcessingError {
+                    object_key: ObjectKey::new("unknown".to_string()).unwrap(),
+                    rule_id: "system".to_string(),
+                    error: format!("Failed to read lifecycle configuration: {}", e),
+                });
+                return 0;
+            }
+        };
+        let uploads = match multipart_uploads
+            .list_uploads(&format!("{}/", bucket.as_str()))
+            .await
+        {
+            Ok(uploads) => uploads,
+            Err(e) => {
+                errors.push(ProcessingError {
+                    object_key: ObjectKey::new("unknown".to_string()).unwrap(),
+                    rule_id: "system".to_string(),
+                    error: format!("Failed to list multipart uploads: {}", e),
+                });
+                return 0;
+            }
+        };
+
+        let now = self.clock.now();
+        let mut aborted = 0;
+        for upload in uploads {
+            let Some(rule) = rules.iter().find(|rule| {
+                rule.matches(&upload.key, &HashMap::new(), 0)
+                    && rule
+                        .abort_incomplete_multipart_upload_days_after_initiation
+                        .is_some_and(|days| upload.is_older_than(days, now))
+            }) else {
+                continue;
+            };
+
+            let result = match self
+                .object_store
+                .abort_multipart_upload(&upload.key, &upload.upload_id)
+                .await
+            {
+                Ok(()) => multipart_uploads.delete_upload(&upload.upload_id).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(()) => {
+                    aborted += 1;
+                    let event = LifecycleEvent::new(
+                        LifecycleEventType::MultipartAborted,
+                        upload.key.clone(),
+                        rule.id.clone(),
+                        now,
+                    )
+                    .with_upload_id(upload.upload_id.clone());
+                    self.publish(event).await;
+                }
+                Err(e) => errors.push(ProcessingError {
+                    object_key: upload.key.clone(),
+                    rule_id: rule.id.clone(),
+                    error: format!("Failed to abort upload {}: {}", upload.upload_id, e),
+                }),
+            }
+        }
+
+        aborted
+    }
+
+    /// Remove the bucket's expired delete markers that a rule with
+    /// `ExpiredObjectDeleteMarker` matches, returning how many were removed
+    ///
+    /// A delete marker is expired once every noncurrent version behind it is
+    /// gone. Removing it drops the last of the key's version metadata, so the
+    /// key no longer shows up in version listings.
+    async fn remove_expired_delete_markers(
+        &self,
+        bucket: &BucketName,
+        errors: &mut Vec<ProcessingError>,
+    ) -> usize {
+        let rules: Vec<LifecycleRule> = match self.lifecycle_repo.get_configuration(bucket).await {
+            Ok(Some(config)) => config
+                .rules
+                .into_iter()
+                .filter(|rule| rule.expiration_expired_object_delete_marker == Some(true))
+                .collect(),
+            Ok(None) => return 0,
+            Err(e) => {
+                errors.push(ProcessingError {
+                    object_key: ObjectKey::new("unknown".to_string()).unwrap(),
+                    rule_id: "system".to_string(),
+                    error: format!("Failed to read lifecycle configuration: {}", e),
+                });
+                return 0;
+            }
+        };
+        if rules.is_empty() {
+            return 0;
+        }
+        let markers = match self
+            .object_repo
+            .list_delete_markers(&format!("{}/", bucket.as_str()))
+            .await
+        {
+            Ok(markers) => markers,
+            Err(e) => {
+                errors.push(ProcessingError {
+                    object_key: ObjectKey::new("unknown".to_string()).unwrap(),
+                    rule_id: "system".to_string(),
+                    error: format!("Failed to list delete markers: {}", e),
+                });
+                return 0;
+            }
+        };
+
+        let mut removed = 0;
+        for key in markers {
+            let Some(rule) = rules
+                .iter()
+                .find(|rule| rule.matches(&key, &HashMap::new(), 0))
+            else {
+                continue;
+            };
+
+            match self.remove_delete_marker_if_expired(&key).await {
+                Ok(true) => removed += 1,
+                Ok(false) => {}
+                Err(e) => errors.push(ProcessingError {
+                    object_key: key.clone(),
+                    rule_id: rule.id.clone(),
+                    error: format!("Failed to remove expired delete marker: {}", e),
+                }),
+            }
+        }
+
+        removed
+    }
+
+    /// Remove the metadata of `key` if its latest version is a delete marker
+    /// with only deleted versions behind it, returning whether it did
+    async fn remove_delete_marker_if_expired(&self, key: &ObjectKey) -> Result<bool, StorageError> {
+        let versions = self.object_repo.list_object_versions(key).await?.versions;
+        let expired = versions.iter().any(|version| version.is_latest)
+            && versions.iter().all(|version| version.deleted);
+        if !expired {
+            return Ok(false);
+        }
+
+        for version in versions {
+            self.object_repo
+                .delete_version_metadata(key, &version.version_id)
+                .await?;
+        }
+        Ok(true)
+    }
+
     /// Apply multipart upload cleanup
     async fn apply_multipart_cleanup(
         &self,
@@ -722,8 +1248,9 @@ mod tests {
     use crate::adapters::outbound::persistence::{
         InMemoryLifecycleRepository, InMemoryObjectRepository,
     };
-    use crate::adapters::outbound::storage::ApacheObjectStoreAdapter;
-    use crate::{Filter, VersionedApacheObjectStoreAdapter};
+    use crate::adapters::outbound::storage::{S3ObjectStoreAdapter, VersionedS3ObjectStoreAdapter};
+    use crate::domain::{models::ObjectMetadata, value_objects::VersionId};
+    use crate::Filter;
     use object_store::memory::InMemory;
     use std::collections::HashMap;
 
@@ -731,12 +1258,33 @@ mod tests {
         let lifecycle_repo = Arc::new(InMemoryLifecycleRepository::new());
         let object_repo = Arc::new(InMemoryObjectRepository::new());
         let memory_store = Arc::new(InMemory::new());
-        let object_store = Arc::new(ApacheObjectStoreAdapter::new(memory_store.clone()));
-        let versioned_store = Arc::new(VersionedApacheObjectStoreAdapter::new(memory_store));
+        let bucket = BucketName::new("test-bucket".to_string()).unwrap();
+        let object_store = Arc::new(S3ObjectStoreAdapter::new(memory_store.clone(), bucket));
+        let versioned_store = Arc::new(VersionedS3ObjectStoreAdapter::new(
+            object_store.clone(),
+            memory_store,
+        ));
 
         LifecycleServiceImpl::new(lifecycle_repo, object_repo, object_store, versioned_store)
     }
 
+    /// Sink keeping the events published to it
+    #[derive(Default)]
+    struct RecordingSink {
+        events: std::sync::Mutex<Vec<LifecycleEvent>>,
+    }
+
+    #[async_trait]
+    impl LifecycleEventSink for RecordingSink {
+        async fn publish(
+            &self,
+            event: &LifecycleEvent,
+        ) -> crate::domain::errors::StorageResult<()> {
+            self.events.lock().unwrap().push(event.clone());
+            Ok(())
+        }
+    }
+
     #[tokio::test]
     async fn test_set_and_get_lifecycle_configuration() {
         let service = create_test_service().await;
@@ -775,7 +1323,7 @@ mod tests {
             rules: vec![LifecycleRule {
                 id: "expire-old-logs".to_string(),
                 status: RuleStatus::Enabled,
-                filter: Filter::new().with_prefix("logs/".to_string()),
+                filter: Filter::new().with_prefix("test-bucket/logs/".to_string()),
                 expiration_days: Some(1), // 1 day for testing
                 ..Default::default()
             }],
@@ -789,11 +1337,12 @@ mod tests {
         // Test with an old object
         let old_time = SystemTime::now() - Duration::from_secs(2 * 86400); // 2 days ago
         let request = EvaluateLifecycleRequest {
-            key: ObjectKey::new("logs/old-file.log".to_string()).unwrap(),
+            key: ObjectKey::new("test-bucket/logs/old-file.log".to_string()).unwrap(),
             object_created_at: old_time,
             object_tags: HashMap::new(),
             is_delete_marker: false,
             is_current_version: true,
+            delete_at: None,
         };
 
         let result = service.evaluate_object_lifecycle(request).await.unwrap();
@@ -801,6 +1350,366 @@ mod tests {
         assert_eq!(result.actions_to_apply[0].rule_id, "expire-old-logs");
     }
 
+    #[derive(Debug)]
+    struct FixedClock(DateTime<Utc>);
+
+    impl Clock for FixedClock {
+        fn now(&self) -> DateTime<Utc> {
+            self.0
+        }
+    }
+
+    #[tokio::test]
+    async fn test_evaluation_uses_clock() {
+        // The bucket is taken from the first segment of the key
+        let bucket = BucketName::new("logs".to_string()).unwrap();
+        let config = LifecycleConfiguration {
+            bucket: bucket.clone(),
+            rules: vec![LifecycleRule {
+                id: "expire-logs".to_string(),
+                status: RuleStatus::Enabled,
+                filter: Filter::new().with_prefix("logs/".to_string()),
+                expiration_days: Some(1),
+                ..Default::default()
+            }],
+        };
+        let created_at: DateTime<Utc> = "2025-01-01T00:00:00Z".parse().unwrap();
+        let request = EvaluateLifecycleRequest {
+            key: ObjectKey::new("logs/app.log".to_string()).unwrap(),
+            object_created_at: created_at.into(),
+            object_tags: HashMap::new(),
+            is_delete_marker: false,
+            is_current_version: true,
+            delete_at: None,
+        };
+
+        for (hours, expired) in [(12, false), (36, true)] {
+            let now = created_at + chrono::Duration::hours(hours);
+            let service = create_test_service()
+                .await
+                .with_clock(Arc::new(FixedClock(now)));
+            service
+                .set_lifecycle_configuration(&bucket, config.clone())
+                .await
+                .unwrap();
+
+            let result = service
+                .evaluate_object_lifecycle(request.clone())
+                .await
+                .unwrap();
+            assert_eq!(!result.actions_to_apply.is_empty(), expired);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_deletion_evaluation() {
+        let service = create_test_service().await;
+
+        // No lifecycle configuration exists, the object's own schedule still applies
+        let request = EvaluateLifecycleRequest {
+            key: ObjectKey::new("tmp/upload.bin".to_string()).unwrap(),
+            object_created_at: SystemTime::now(),
+            object_tags: HashMap::new(),
+            is_delete_marker: false,
+            is_current_version: true,
+            delete_at: Some(Utc::now() - chrono::Duration::seconds(1)),
+        };
+
+        let result = service.evaluate_object_lifecycle(request).await.unwrap();
+        assert_eq!(result.actions_to_apply.len(), 1);
+        assert_eq!(
+            result.actions_to_apply[0].rule_id,
+            SCHEDULED_DELETION_RULE_ID
+        );
+
+        // A schedule in the future does not trigger anything yet
+        let request = EvaluateLifecycleRequest {
+            key: ObjectKey::new("tmp/upload.bin".to_string()).unwrap(),
+            object_created_at: SystemTime::now(),
+            object_tags: HashMap::new(),
+            is_delete_marker: false,
+            is_current_version: true,
+            delete_at: Some(Utc::now() + chrono::Duration::hours(1)),
+        };
+
+        let result = service.evaluate_object_lifecycle(request).await.unwrap();
+        assert!(result.actions_to_apply.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_simulate_bucket_lifecycle() {
+        let service = create_test_service().await;
+        let bucket = BucketName::new("test-bucket".to_string()).unwrap();
+
+        for key in ["test-bucket/tmp/scratch.bin", "test-bucket/logs/app.log"] {
+            service
+                .object_store
+                .put_object(
+                    &ObjectKey::new(key.to_string()).unwrap(),
+                    bytes::Bytes::from_static(b"data"),
+                    None,
+                )
+                .await
+                .unwrap();
+        }
+
+        let config = LifecycleConfiguration {
+            bucket: bucket.clone(),
+            rules: vec![LifecycleRule {
+                id: "expire-tmp".to_string(),
+                status: RuleStatus::Enabled,
+                filter: Filter::new().with_prefix("tmp/".to_string()),
+                expiration_days: Some(7),
+                ..Default::default()
+            }],
+        };
+
+        // Nothing is old enough yet
+        let results = service
+            .simulate_bucket_lifecycle(&bucket, Some(config.clone()), Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(results.objects_evaluated, 2);
+        assert!(results.affected_objects.is_empty());
+
+        // A week from now only the temporary object expires
+        let as_of = Utc::now() + chrono::Duration::days(8);
+        let results = service
+            .simulate_bucket_lifecycle(&bucket, Some(config), as_of)
+            .await
+            .unwrap();
+        assert_eq!(results.affected_objects.len(), 1);
+        assert_eq!(
+            results.affected_objects[0].object_key.as_str(),
+            "test-bucket/tmp/scratch.bin"
+        );
+        assert_eq!(results.affected_objects[0].actions[0].rule_id, "expire-tmp");
+
+        // The dry run never touches the objects or the stored configuration
+        assert!(
+            service
+                .object_store
+                .object_exists(&ObjectKey::new("test-bucket/tmp/scratch.bin".to_string()).unwrap())
+                .await
+                .unwrap()
+        );
+        assert!(
+            service
+                .get_lifecycle_configuration(&bucket)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_copy_lifecycle_configuration() {
+        let service = create_test_service().await;
+        let source = BucketName::new("source-bucket".to_string()).unwrap();
+        let target = BucketName::new("target-bucket".to_string()).unwrap();
+
+        let config = LifecycleConfiguration {
+            bucket: source.clone(),
+            rules: vec![
+                LifecycleRule {
+                    id: "expire-logs".to_string(),
+                    status: RuleStatus::Enabled,
+                    filter: Filter::new().with_prefix("logs/".to_string()),
+                    expiration_days: Some(30),
+                    ..Default::default()
+                },
+                LifecycleRule {
+                    id: "expire-tmp".to_string(),
+                    status: RuleStatus::Enabled,
+                    filter: Filter::new().with_prefix("tmp/".to_string()),
+                    expiration_days: Some(1),
+                    ..Default::default()
+                },
+            ],
+        };
+        service
+            .set_lifecycle_configuration(&source, config)
+            .await
+            .unwrap();
+
+        let rewrite = PrefixRewrite {
+            from: "logs/".to_string(),
+            to: "app/logs/".to_string(),
+        };
+        service
+            .copy_lifecycle_configuration(&source, &target, Some(&rewrite))
+            .await
+            .unwrap();
+
+        let copied = service
+            .get_lifecycle_configuration(&target)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(copied.bucket, target);
+        assert_eq!(copied.rules[0].filter.prefix.as_deref(), Some("app/logs/"));
+        assert_eq!(copied.rules[1].filter.prefix.as_deref(), Some("tmp/"));
+
+        // The source must have a configuration to copy
+        let missing = BucketName::new("missing-bucket".to_string()).unwrap();
+        assert!(matches!(
+            service
+                .copy_lifecycle_configuration(&missing, &target, None)
+                .await,
+            Err(LifecycleError::ConfigurationNotFound { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_expired_delete_markers_are_removed() {
+        let service = create_test_service().await;
+        let bucket = BucketName::new("test-bucket".to_string()).unwrap();
+        let version = |id: &str| VersionId::new(id.to_string()).unwrap();
+        let metadata = ObjectMetadata {
+            content_type: None,
+            content_length: 4,
+            etag: None,
+            last_modified: SystemTime::now(),
+            custom_metadata: HashMap::new(),
+        };
+
+        // One marker has only deleted versions behind it, the other a live one
+        let expired = ObjectKey::new("test-bucket/expired.txt".to_string()).unwrap();
+        let shadowing = ObjectKey::new("test-bucket/shadowing.txt".to_string()).unwrap();
+        for key in [&expired, &shadowing] {
+            for id in ["v1", "v2"] {
+                service
+                    .object_repo
+                    .save_object_metadata(key, &version(id), &metadata)
+                    .await
+                    .unwrap();
+            }
+            service
+                .object_repo
+                .mark_version_deleted(key, &version("v2"))
+                .await
+                .unwrap();
+        }
+        service
+            .object_repo
+            .mark_version_deleted(&expired, &version("v1"))
+            .await
+            .unwrap();
+
+        let config = LifecycleConfiguration {
+            bucket: bucket.clone(),
+            rules: vec![LifecycleRule {
+                id: "remove-markers".to_string(),
+                status: RuleStatus::Enabled,
+                filter: Filter::new(),
+                expiration_expired_object_delete_marker: Some(true),
+                ..Default::default()
+            }],
+        };
+        service
+            .set_lifecycle_configuration(&bucket, config)
+            .await
+            .unwrap();
+
+        let results = service.process_bucket_lifecycle(&bucket).await.unwrap();
+        assert!(results.errors.is_empty());
+        assert_eq!(results.actions_applied, 1);
+
+        let expired_versions = service.object_repo.list_object_versions(&expired).await;
+        assert!(expired_versions.unwrap().versions.is_empty());
+        let shadowing_versions = service.object_repo.list_object_versions(&shadowing).await;
+        assert_eq!(shadowing_versions.unwrap().versions.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_applied_actions_are_published() {
+        let sink = Arc::new(RecordingSink::default());
+        let service = create_test_service().await.with_event_sink(sink.clone());
+        let key = ObjectKey::new("test-bucket/tmp/scratch.bin".to_string()).unwrap();
+        service
+            .object_store
+            .put_object(&key, bytes::Bytes::from_static(b"data"), None)
+            .await
+            .unwrap();
+
+        let expiration = ApplicableAction {
+            rule_id: "expire-tmp".to_string(),
+            action: LifecycleAction::Expiration {
+                days: Some(7),
+                date: None,
+            },
+            reason: "Object is 7 days old".to_string(),
+        };
+        let transition = ApplicableAction {
+            rule_id: "archive-tmp".to_string(),
+            action: LifecycleAction::Transition {
+                days: Some(7),
+                date: None,
+                storage_class: LifecycleStorageClass::Glacier,
+            },
+            reason: "Object should transition".to_string(),
+        };
+        let results = service
+            .apply_lifecycle_actions(&key, vec![expiration, transition])
+            .await
+            .unwrap();
+        assert_eq!(results.applied_actions.len(), 1);
+
+        // Only the action that was carried out is published
+        let events = sink.events.lock().unwrap().clone();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_type, LifecycleEventType::ObjectExpired);
+        assert_eq!(events[0].key, key);
+        assert_eq!(events[0].rule_id, "expire-tmp");
+    }
+
+    #[tokio::test]
+    async fn test_background_run_can_be_cancelled() {
+        let service = create_test_service().await;
+        let bucket = BucketName::new("test-bucket".to_string()).unwrap();
+        for key in ["test-bucket/a.txt", "test-bucket/b.txt"] {
+            service
+                .object_store
+                .put_object(
+                    &ObjectKey::new(key.to_string()).unwrap(),
+                    bytes::Bytes::from_static(b"data"),
+                    None,
+                )
+                .await
+                .unwrap();
+        }
+
+        let status = service.start_bucket_lifecycle(&bucket).await.unwrap();
+        assert!(status.is_running);
+        assert!(matches!(
+            service.start_bucket_lifecycle(&bucket).await,
+            Err(LifecycleError::ProcessingInProgress { .. })
+        ));
+
+        // Cancelled before the run got to its first object
+        assert!(
+            service
+                .cancel_bucket_lifecycle(&bucket)
+                .await
+                .unwrap()
+                .cancelled
+        );
+        let status = loop {
+            let status = service.get_processing_status(&bucket).await.unwrap();
+            if !status.is_running {
+                break status;
+            }
+            tokio::task::yield_now().await;
+        };
+        assert!(status.cancelled);
+        assert_eq!(status.last_run_results.unwrap().objects_processed, 0);
+
+        assert!(matches!(
+            service.cancel_bucket_lifecycle(&bucket).await,
+            Err(LifecycleError::ProcessingNotRunning { .. })
+        ));
+    }
+
     #[tokio::test]
     async fn test_rule_management() {
         let service = create_test_service().await;
@@ -0,0 +1,214 @@
+use chrono::Utc;
+use cron::Schedule;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::{Notify, watch};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use super::{BackendReadiness, LeaseError, WorkerLeases};
+use crate::domain::value_objects::BucketName;
+use crate::ports::{repositories::LifecycleRepository, services::LifecycleService};
+
+/// Parse a cron expression for the lifecycle worker
+///
+/// Expressions have a leading seconds field, e.g. `0 0 3 * * *` for 03:00 UTC
+/// every day. Times are evaluated in UTC.
+pub fn parse_lifecycle_schedule(expression: &str) -> Result<Schedule, String> {
+    Schedule::from_str(expression)
+        .map_err(|e| format!("Invalid lifecycle schedule '{}': {}", expression, e))
+}
+
+/// Buckets a lifecycle pass went through
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LifecyclePass {
+    /// Buckets whose rules this instance processed, successfully or not
+    pub processed: Vec<BucketName>,
+
+    /// Buckets left alone because another instance holds their lease for
+    /// the cycle
+    pub held_by_another_instance: Vec<BucketName>,
+}
+
+/// Handle to a running lifecycle worker
+///
+/// The worker processes the lifecycle rules of every configured bucket each
+/// time the schedule fires, or when [`trigger`](Self::trigger) is called.
+/// Dropping the handle leaves the worker running; call [`stop`](Self::stop) to
+/// shut it down.
+///
+/// With a [`BackendReadiness`] the worker checks its backends before each
+/// pass, and pauses until they recover when a check fails.
+///
+/// With [`WorkerLeases`] shared by several server instances, each bucket is
+/// processed by the one instance that takes its lease, held until the
+/// schedule next fires. A triggered pass skips those buckets too, and lists
+/// them in its [`LifecyclePass`].
+pub struct LifecycleWorkerHandle {
+    trigger: Arc<Notify>,
+    shutdown: CancellationToken,
+    paused: Arc<AtomicBool>,
+    passes_started: Arc<AtomicU64>,
+    passes_finished: watch::Receiver<(u64, LifecyclePass)>,
+    task: JoinHandle<()>,
+}
+
+impl LifecycleWorkerHandle {
+    /// Start a worker running lifecycle passes on `schedule`
+    pub fn spawn(
+        lifecycle_service: Arc<dyn LifecycleService>,
+        lifecycle_repository: Arc<dyn LifecycleRepository>,
+        schedule: Schedule,
+        readiness: Option<BackendReadiness>,
+        leases: Option<WorkerLeases>,
+    ) -> Self {
+        let trigger = Arc::new(Notify::new());
+        let shutdown = CancellationToken::new();
+        let paused = Arc::new(AtomicBool::new(false));
+        let passes_started = Arc::new(AtomicU64::new(0));
+        let (finished_tx, passes_finished) = watch::channel((0, LifecyclePass::default()));
+
+        let task = tokio::spawn({
+            let trigger = trigger.clone();
+            let shutdown = shutdown.clone();
+            let paused = paused.clone();
+            let passes_started = passes_started.clone();
+            async move {
+                loop {
+                    // A schedule with no upcoming time only runs when triggered
+                    let until_next = schedule
+                        .upcoming(Utc)
+                        .next()
+                        .map(|next| (next - Utc::now()).to_std().unwrap_or_default());
+
+                    tokio::select! {
+                        _ = shutdown.cancelled() => break,
+                        _ = trigger.notified() => {}
+                        _ = async {
+                            match until_next {
+                                Some(delay) => tokio::time::sleep(delay).await,
+                                None => std::future::pending().await,
+                            }
+                        } => {}
+                    }
+
+                    if let Some(readiness) = &readiness {
+                        if !readiness
+                            .wait_until_ready("lifecycle", &paused, &shutdown)
+                            .await
+                        {
+                            break;
+                        }
+                    }
+
+                    // The cycle lasts until the schedule fires again
+                    let cycle = schedule
+                        .upcoming(Utc)
+                        .next()
+                        .and_then(|next| (next - Utc::now()).to_std().ok())
+                        .unwrap_or_default();
+
+                    let pass = passes_started.fetch_add(1, Ordering::SeqCst) + 1;
+                    let outcome = run_pass(
+                        lifecycle_service.as_ref(),
+                        lifecycle_repository.as_ref(),
+                        leases.as_ref().map(|leases| (leases, cycle)),
+                    )
+                    .await;
+                    finished_tx.send_replace((pass, outcome));
+                }
+            }
+        });
+
+        Self {
+            trigger,
+            shutdown,
+            paused,
+            passes_started,
+            passes_finished,
+            task,
+        }
+    }
+
+    /// Run a lifecycle pass now instead of waiting for the schedule
+    ///
+    /// Triggers made while a pass is running queue a single follow-up pass.
+    pub fn trigger(&self) {
+        self.trigger.notify_one();
+    }
+
+    /// Trigger a lifecycle pass and wait for it to finish
+    ///
+    /// Returns the buckets the pass went through, or None when the worker
+    /// stopped before finishing it.
+    pub async fn trigger_and_wait(&self) -> Option<LifecyclePass> {
+        let pass = self.passes_started.load(Ordering::SeqCst) + 1;
+        let mut finished = self.passes_finished.clone();
+        self.trigger();
+        let finished = finished
+            .wait_for(|(finished, _)| *finished >= pass)
+            .await
+            .ok()?;
+        Some(finished.1.clone())
+    }
+
+    /// Whether the worker task is still running
+    pub fn is_running(&self) -> bool {
+        !self.task.is_finished()
+    }
+
+    /// Whether the worker is waiting for an unavailable backend to recover
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Stop the worker, waiting for a pass in progress to finish
+    pub async fn stop(self) {
+        self.shutdown.cancel();
+        let _ = self.task.await;
+    }
+}
+
+/// Process the lifecycle rules of every configured bucket once
+///
+/// With leases, buckets whose lease another instance holds for the cycle
+/// are skipped.
+async fn run_pass(
+    service: &dyn LifecycleService,
+    repository: &dyn LifecycleRepository,
+    leases: Option<(&WorkerLeases, Duration)>,
+) -> LifecyclePass {
+    let mut outcome = LifecyclePass::default();
+    let buckets = match repository.list_configured_buckets().await {
+        Ok(buckets) => buckets,
+        Err(e) => {
+            tracing::error!("Lifecycle worker failed to list buckets: {}", e);
+            return outcome;
+        }
+    };
+
+    for bucket in buckets {
+        let processing = service.process_bucket_lifecycle(&bucket);
+        let result = match leases {
+            Some((leases, cycle)) => {
+                let lease = format!("lifecycle/{}", bucket);
+                match leases.run_exclusive(&lease, cycle, processing).await {
+                    Ok(result) => result,
+                    Err(LeaseError::HeldByAnotherInstance(_)) => {
+                        outcome.held_by_another_instance.push(bucket);
+                        continue;
+                    }
+                    Err(LeaseError::Repository { .. }) => continue,
+                }
+            }
+            None => processing.await,
+        };
+        if let Err(e) = result {
+            tracing::error!("Lifecycle processing failed for {}: {}", bucket, e);
+        }
+        outcome.processed.push(bucket);
+    }
+    outcome
+}
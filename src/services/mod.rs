@@ -1,7 +1,30 @@
+mod backup_service_impl;
+mod backup_worker;
+mod expiration_interceptor;
+mod index_reconciler;
+mod integrity_scrubber;
 mod lifecycle_service_impl;
+mod lifecycle_worker;
 mod object_service_impl;
+mod readiness;
 mod versioning_service_impl;
+mod worker_leases;
 
+pub use backup_service_impl::BackupServiceImpl;
+pub use backup_worker::{BackupWorkerHandle, DEFAULT_BACKUP_POLL_INTERVAL};
+pub use expiration_interceptor::ExpirationInterceptor;
+pub use index_reconciler::{
+    Discrepancy, IndexDrift, IndexReconciler, ReconcileConfig, ReconcileMetrics, ReconcileReport,
+};
+pub use integrity_scrubber::{
+    CorruptObject, Corruption, INTEGRITY_CHECKED_AT_METADATA_KEY, INTEGRITY_STATUS_CORRUPTED,
+    INTEGRITY_STATUS_METADATA_KEY, IntegrityScrubber, ScrubConfig, ScrubReport,
+};
 pub use lifecycle_service_impl::LifecycleServiceImpl;
+pub use lifecycle_worker::{LifecyclePass, LifecycleWorkerHandle, parse_lifecycle_schedule};
 pub use object_service_impl::{ObjectServiceBuilder, ObjectServiceImpl};
+pub use readiness::{
+    BackendReadiness, BackendUnavailable, DEFAULT_READINESS_RETRY_INTERVAL, DEFAULT_READINESS_TIMEOUT,
+};
 pub use versioning_service_impl::VersioningServiceImpl;
+pub use worker_leases::{LEASE_TTL, LeaseError, WorkerLeases};
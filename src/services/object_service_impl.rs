@@ -1,16 +1,30 @@
 use async_trait::async_trait;
-use std::sync::Arc;
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncReadExt, ReadBuf};
 
 use crate::{
     domain::{
         errors::{StorageError, StorageResult},
-        models::{CreateObjectRequest, GetObjectRequest, ObjectMetadata, StorageObject},
+        models::{
+            CreateObjectRequest, DefaultMetadata, Filter, GetObjectRequest, IntegrityManifest,
+            MultipartUploadInfo, ObjectMetadata, ObjectTransaction, PrefixQuota, PrefixUsage,
+            PresignedMultipartUpload, PresignedPart, QuotaWarning, StagedObject, StorageObject,
+            TextQuery, UploadedPart, apply_default_metadata, multipart_etag, part_checksum,
+            validate_part_number, version_key,
+        },
         value_objects::{ObjectKey, VersionId},
     },
     ports::{
-        repositories::ObjectRepository,
+        interceptors::{ObjectInterceptor, PutContext},
+        repositories::{MultipartUploadRepository, ObjectRepository, TransactionRepository},
+        runtime::{Clock, IdGenerator, SystemClock, UuidGenerator, VersionIdGenerator},
         services::ObjectService,
-        storage::{ObjectInfo, ObjectStore},
+        storage::{CompletedPart, ObjectInfo, ObjectStore, PresignedUrlMethod},
     },
 };
 
@@ -19,25 +33,293 @@ use crate::{
 pub struct ObjectServiceImpl {
     repository: Arc<dyn ObjectRepository>,
     store: Arc<dyn ObjectStore>,
+    interceptors: Vec<Arc<dyn ObjectInterceptor>>,
+    default_metadata: Vec<DefaultMetadata>,
+    prefix_quotas: Vec<PrefixQuota>,
+    multipart_uploads: Option<Arc<dyn MultipartUploadRepository>>,
+    transactions: Option<Arc<dyn TransactionRepository>>,
+    clock: Arc<dyn Clock>,
+    id_generator: Arc<dyn IdGenerator>,
+    version_ids: Arc<dyn VersionIdGenerator>,
+    patch_locks: Arc<Vec<tokio::sync::Mutex<()>>>,
 }
 
+/// Number of locks patches of different keys are spread over
+const PATCH_LOCK_STRIPES: usize = 64;
+
 impl ObjectServiceImpl {
     /// Create a new ObjectServiceImpl instance
     pub fn new(repository: Arc<dyn ObjectRepository>, store: Arc<dyn ObjectStore>) -> Self {
-        Self { repository, store }
+        Self {
+            repository,
+            store,
+            interceptors: Vec::new(),
+            default_metadata: Vec::new(),
+            prefix_quotas: Vec::new(),
+            multipart_uploads: None,
+            transactions: None,
+            clock: Arc::new(SystemClock),
+            id_generator: Arc::new(UuidGenerator),
+            version_ids: Arc::new(UuidGenerator),
+            patch_locks: Arc::new(
+                (0..PATCH_LOCK_STRIPES)
+                    .map(|_| tokio::sync::Mutex::new(()))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Register an interceptor, run after any registered before it
+    pub fn with_interceptor(mut self, interceptor: Arc<dyn ObjectInterceptor>) -> Self {
+        self.interceptors.push(interceptor);
+        self
+    }
+
+    /// Give uploads under the prefix of `defaults` the metadata they leave unset
+    ///
+    /// Defaults are applied before interceptors run, and where several
+    /// prefixes match a key the longest one wins.
+    pub fn with_default_metadata(mut self, defaults: DefaultMetadata) -> Self {
+        self.default_metadata.push(defaults);
+        self
+    }
+
+    /// Refuse writes that would take the prefix of `quota` beyond its limits
+    ///
+    /// Usage is counted from the repository's key index once per write, so
+    /// objects written concurrently may together overshoot a limit.
+    pub fn with_prefix_quota(mut self, quota: PrefixQuota) -> Self {
+        self.prefix_quotas.push(quota);
+        self
+    }
+
+    /// Track multipart uploads in `repository`, enabling the multipart operations
+    pub fn with_multipart_uploads(
+        mut self,
+        repository: Arc<dyn MultipartUploadRepository>,
+    ) -> Self {
+        self.multipart_uploads = Some(repository);
+        self
+    }
+
+    /// Keep transaction manifests in `repository`, enabling the transaction operations
+    pub fn with_transactions(mut self, repository: Arc<dyn TransactionRepository>) -> Self {
+        self.transactions = Some(repository);
+        self
+    }
+
+    /// Timestamp stored objects with `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Draw transaction IDs from `id_generator` instead of random UUIDs
+    pub fn with_id_generator(mut self, id_generator: Arc<dyn IdGenerator>) -> Self {
+        self.id_generator = id_generator;
+        self
+    }
+
+    /// Draw version IDs from `version_ids` instead of random UUIDs
+    pub fn with_version_id_generator(mut self, version_ids: Arc<dyn VersionIdGenerator>) -> Self {
+        self.version_ids = version_ids;
+        self
+    }
+
+    /// ID of a new version, given the ID the store `assigned` when writing it
+    fn next_version_id(&self, assigned: Option<String>) -> StorageResult<VersionId> {
+        let id = self
+            .version_ids
+            .next_version_id()
+            .or(assigned)
+            .unwrap_or_else(|| UuidGenerator.next_id());
+        VersionId::new(id).map_err(|e| StorageError::ValidationError {
+            message: e.to_string(),
+        })
+    }
+
+    fn multipart_uploads(&self) -> StorageResult<&dyn MultipartUploadRepository> {
+        self.multipart_uploads
+            .as_deref()
+            .ok_or_else(|| StorageError::UnsupportedOperation {
+                operation: "multipart upload".to_string(),
+                reason: "no multipart upload repository is configured".to_string(),
+            })
+    }
+
+    fn transactions(&self) -> StorageResult<&dyn TransactionRepository> {
+        self.transactions
+            .as_deref()
+            .ok_or_else(|| StorageError::UnsupportedOperation {
+                operation: "transaction".to_string(),
+                reason: "no transaction repository is configured".to_string(),
+            })
+    }
+
+    /// The in-progress upload `upload_id` of `key`
+    async fn in_progress_upload(
+        &self,
+        key: &ObjectKey,
+        upload_id: &str,
+    ) -> StorageResult<MultipartUploadInfo> {
+        self.multipart_uploads()?
+            .get_upload(upload_id)
+            .await?
+            .filter(|upload| &upload.key == key)
+            .ok_or_else(|| StorageError::UploadNotFound {
+                key: key.clone(),
+                upload_id: upload_id.to_string(),
+            })
+    }
+
+    /// Usage of every quota covering one of `keys`, read once for a write
+    async fn covering_quota_usage(
+        &self,
+        keys: &[&ObjectKey],
+    ) -> StorageResult<Vec<(&PrefixQuota, PrefixUsage)>> {
+        let mut usage = Vec::new();
+        for quota in &self.prefix_quotas {
+            if keys.iter().any(|key| quota.covers(key)) {
+                usage.push((quota, self.repository.prefix_usage(&quota.prefix).await?));
+            }
+        }
+        Ok(usage)
+    }
+
+    /// Check that every quota covering one of `objects` holds with them
+    /// stored, returning the soft limits reached
+    ///
+    /// Each object is given with its size and is not yet counted.
+    async fn check_prefix_quotas(
+        &self,
+        objects: &[(&ObjectKey, u64)],
+    ) -> StorageResult<Vec<QuotaWarning>> {
+        let keys: Vec<&ObjectKey> = objects.iter().map(|(key, _)| *key).collect();
+        let usage = self.covering_quota_usage(&keys).await?;
+        check_quota_usage(&usage, objects)
+    }
+
+    /// Run the pre-put hooks of every interceptor in order
+    async fn intercept_put(
+        &self,
+        key: &ObjectKey,
+        mut put: PutContext,
+    ) -> StorageResult<PutContext> {
+        for interceptor in &self.interceptors {
+            put = interceptor.pre_put(key, put).await?;
+        }
+        Ok(put)
+    }
+
+    /// Run the post-put hooks of every interceptor in order
+    async fn intercept_stored(
+        &self,
+        key: &ObjectKey,
+        metadata: &ObjectMetadata,
+    ) -> StorageResult<()> {
+        for interceptor in &self.interceptors {
+            interceptor.post_put(key, metadata).await?;
+        }
+        Ok(())
+    }
+
+    /// Run the pre-get hooks of every interceptor in order
+    async fn intercept_get(&self, key: &ObjectKey, metadata: &ObjectMetadata) -> StorageResult<()> {
+        for interceptor in &self.interceptors {
+            interceptor.pre_get(key, metadata).await?;
+        }
+        Ok(())
     }
 
     /// Calculate ETag for object data
+    /// The lock patches of `key` hold while they read and replace its data
+    fn patch_lock(&self, key: &ObjectKey) -> &tokio::sync::Mutex<()> {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        std::hash::Hash::hash(key.as_str(), &mut hasher);
+        let stripe = std::hash::Hasher::finish(&hasher) as usize % self.patch_locks.len();
+        &self.patch_locks[stripe]
+    }
+
     fn calculate_etag(&self, data: &[u8]) -> String {
         // Simple MD5 hash for ETag (in production, use proper hashing)
         format!("{:x}", md5::compute(data))
     }
 }
 
+/// Key the data of a staged object's version is kept under once it commits
+fn committed_version_key(object: &StagedObject) -> StorageResult<ObjectKey> {
+    version_key(&object.key, &object.version_id).map_err(|e| StorageError::ValidationError {
+        message: e.to_string(),
+    })
+}
+
+/// Check that every quota in `usage` holds with `objects` stored as well,
+/// returning the soft limits reached
+///
+/// Each object is given with its size and is not yet counted in `usage`.
+fn check_quota_usage(
+    usage: &[(&PrefixQuota, PrefixUsage)],
+    objects: &[(&ObjectKey, u64)],
+) -> StorageResult<Vec<QuotaWarning>> {
+    let mut warnings = Vec::new();
+    for (quota, usage) in usage {
+        let warned = quota.soft_limit_reached_by(usage).is_some();
+        let mut usage = usage.clone();
+        for (_, size) in objects.iter().filter(|(key, _)| quota.covers(key)) {
+            usage.object_count += 1;
+            usage.total_bytes += size;
+        }
+        if let Some((kind, used, limit)) = quota.exceeded_by(&usage) {
+            return Err(StorageError::PrefixQuotaExceeded {
+                prefix: quota.prefix.clone(),
+                kind,
+                used,
+                limit,
+            });
+        }
+
+        if let Some(warning) = quota.soft_limit_reached_by(&usage) {
+            // Only the write crossing the soft limit is logged
+            if !warned {
+                tracing::warn!(prefix = %quota.prefix, "{}", warning);
+            }
+            warnings.push(warning);
+        }
+    }
+    Ok(warnings)
+}
+
+/// Reader that computes the MD5 of everything read through it
+struct HashingReader {
+    inner: Box<dyn AsyncRead + Send + Unpin>,
+    context: Arc<Mutex<md5::Context>>,
+}
+
+impl AsyncRead for HashingReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let before = buf.filled().len();
+        let result = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = &result {
+            self.context
+                .lock()
+                .unwrap()
+                .consume(&buf.filled()[before..]);
+        }
+        result
+    }
+}
+
 #[async_trait]
 impl ObjectService for ObjectServiceImpl {
     /// Create a new object
-    async fn create_object(&self, request: CreateObjectRequest) -> StorageResult<StorageObject> {
+    async fn create_object(
+        &self,
+        mut request: CreateObjectRequest,
+    ) -> StorageResult<(StorageObject, Vec<QuotaWarning>)> {
         // Check if object already exists
         if self.repository.object_exists(&request.key).await? {
             return Err(StorageError::ObjectAlreadyExists {
@@ -45,11 +327,47 @@ impl ObjectService for ObjectServiceImpl {
             });
         }
 
+        apply_default_metadata(
+            &self.default_metadata,
+            &request.key,
+            &mut request.content_type,
+            &mut request.custom_metadata,
+        );
+
+        if !self.interceptors.is_empty() {
+            let put = self
+                .intercept_put(
+                    &request.key,
+                    PutContext {
+                        content_type: request.content_type.take(),
+                        custom_metadata: std::mem::take(&mut request.custom_metadata),
+                        body: Box::new(std::io::Cursor::new(request.data.clone())),
+                    },
+                )
+                .await?;
+
+            let mut data = Vec::with_capacity(request.data.len());
+            let mut body = put.body;
+            body.read_to_end(&mut data)
+                .await
+                .map_err(|e| StorageError::ValidationError {
+                    message: format!("Failed to read intercepted object data: {}", e),
+                })?;
+            request.data = data;
+            request.content_type = put.content_type;
+            request.custom_metadata = put.custom_metadata;
+        }
+
+        let quota_warnings = self
+            .check_prefix_quotas(&[(&request.key, request.data.len() as u64)])
+            .await?;
+
         // Store the object data
-        self.store
+        let info = self
+            .store
             .put_object(
                 &request.key,
-                request.data.clone(),
+                Bytes::from(request.data.clone()),
                 request.content_type.as_deref(),
             )
             .await?;
@@ -59,23 +377,107 @@ impl ObjectService for ObjectServiceImpl {
             content_type: request.content_type.clone(),
             content_length: request.data.len() as u64,
             etag: Some(self.calculate_etag(&request.data)),
-            last_modified: std::time::SystemTime::now(),
+            last_modified: self.clock.system_time(),
             custom_metadata: request.custom_metadata.clone(),
         };
 
         // Generate version ID for non-versioned object
-        let version_id = VersionId::generate();
+        let version_id = self.next_version_id(info.version_id)?;
 
         // Save metadata
         self.repository
             .save_object_metadata(&request.key, &version_id, &metadata)
             .await?;
 
-        Ok(StorageObject {
+        self.intercept_stored(&request.key, &metadata).await?;
+
+        let object = StorageObject {
             key: request.key,
             data: request.data,
             metadata,
-        })
+        };
+        Ok((object, quota_warnings))
+    }
+
+    /// Create a new object from a stream of data
+    async fn create_object_stream(
+        &self,
+        key: ObjectKey,
+        reader: Box<dyn AsyncRead + Send + Unpin>,
+        mut content_type: Option<String>,
+        mut custom_metadata: HashMap<String, String>,
+    ) -> StorageResult<(ObjectMetadata, Vec<QuotaWarning>)> {
+        // Check if object already exists
+        if self.repository.object_exists(&key).await? {
+            return Err(StorageError::ObjectAlreadyExists { key });
+        }
+
+        apply_default_metadata(
+            &self.default_metadata,
+            &key,
+            &mut content_type,
+            &mut custom_metadata,
+        );
+        // The size is only known once the data is stored, when the usage read
+        // here is checked again
+        let usage = self.covering_quota_usage(&[&key]).await?;
+        check_quota_usage(&usage, &[(&key, 0)])?;
+
+        let PutContext {
+            content_type,
+            custom_metadata,
+            body,
+        } = self
+            .intercept_put(
+                &key,
+                PutContext {
+                    content_type,
+                    custom_metadata,
+                    body: reader,
+                },
+            )
+            .await?;
+
+        // Stream the data to the store, hashing it on the way through
+        let context = Arc::new(Mutex::new(md5::Context::new()));
+        let reader = HashingReader {
+            inner: body,
+            context: context.clone(),
+        };
+        let info = self
+            .store
+            .put_object_stream(&key, Box::new(reader), content_type.as_deref())
+            .await?;
+        let quota_warnings = match check_quota_usage(&usage, &[(&key, info.size)]) {
+            Ok(warnings) => warnings,
+            Err(e) => {
+                self.store.delete_object(&key).await?;
+                return Err(e);
+            }
+        };
+
+        let digest = context.lock().unwrap().clone().compute();
+
+        // Create metadata
+        let metadata = ObjectMetadata {
+            content_type,
+            content_length: info.size,
+            etag: Some(format!("{:x}", digest)),
+            last_modified: self.clock.system_time(),
+            custom_metadata,
+        };
+
+        // Generate version ID for non-versioned object
+        let version_id = self.next_version_id(info.version_id)?;
+
+        // Save metadata
+        self.repository
+            .save_object_metadata(&key, &version_id, &metadata)
+            .await?;
+
+        self.intercept_stored(&key, &metadata).await?;
+
+        Ok((metadata, quota_warnings))
     }
 
     /// Get an object
@@ -89,16 +491,37 @@ impl ObjectService for ObjectServiceImpl {
                 key: request.key.clone(),
             })?;
 
+        self.intercept_get(&request.key, &metadata).await?;
+
         // Get object data from store
         let data = self.store.get_object(&request.key).await?;
 
         Ok(StorageObject {
             key: request.key,
-            data,
+            data: data.to_vec(),
             metadata,
         })
     }
 
+    /// Get an object's metadata and a stream of its data
+    async fn get_object_stream(
+        &self,
+        key: &ObjectKey,
+        range: Option<Range<u64>>,
+    ) -> StorageResult<(ObjectMetadata, Box<dyn AsyncRead + Send + Unpin>)> {
+        let metadata = self
+            .repository
+            .get_object_metadata(key, None)
+            .await?
+            .ok_or_else(|| StorageError::ObjectNotFound { key: key.clone() })?;
+
+        self.intercept_get(key, &metadata).await?;
+
+        let reader = self.store.get_object_stream(key, range).await?;
+
+        Ok((metadata, reader))
+    }
+
     /// Delete an object
     async fn delete_object(&self, key: &ObjectKey) -> StorageResult<()> {
         // Check if object exists
@@ -109,12 +532,22 @@ impl ObjectService for ObjectServiceImpl {
         // Delete from store
         self.store.delete_object(key).await?;
 
-        // Get latest version and mark as deleted
-        if let Some(version_id) = self.repository.get_latest_version_id(key).await? {
-            self.repository
-                .mark_version_deleted(key, &version_id)
-                .await?;
-        }
+        // A delete marker becomes the latest version, leaving the earlier
+        // versions readable by their IDs
+        let marker_id = self.next_version_id(None)?;
+        let marker = ObjectMetadata {
+            content_type: None,
+            content_length: 0,
+            etag: None,
+            last_modified: self.clock.system_time(),
+            custom_metadata: HashMap::new(),
+        };
+        self.repository
+            .save_object_metadata(key, &marker_id, &marker)
+            .await?;
+        self.repository
+            .mark_version_deleted(key, &marker_id)
+            .await?;
 
         Ok(())
     }
@@ -125,7 +558,56 @@ impl ObjectService for ObjectServiceImpl {
         prefix: Option<&str>,
         max_results: Option<usize>,
     ) -> StorageResult<Vec<ObjectInfo>> {
-        self.store.list_objects(prefix, max_results).await
+        self.list_objects_after(prefix, None, max_results).await
+    }
+
+    /// List objects from the repository's key index
+    ///
+    /// The index is kept up to date by every put and delete, so listings are
+    /// consistent with them and never wait on a backend listing, which is slow
+    /// and only eventually consistent on S3.
+    async fn list_objects_after(
+        &self,
+        prefix: Option<&str>,
+        start_after: Option<&str>,
+        max_results: Option<usize>,
+    ) -> StorageResult<Vec<ObjectInfo>> {
+        let listing = self
+            .repository
+            .list_latest_versions(prefix.unwrap_or(""), start_after, max_results)
+            .await?;
+        Ok(listing
+            .into_iter()
+            .map(|(key, version)| ObjectInfo {
+                key,
+                size: version.size,
+                etag: version.etag,
+                version_id: Some(version.version_id.as_str().to_string()),
+                last_modified: version.last_modified.into(),
+            })
+            .collect())
+    }
+
+    async fn find_objects(
+        &self,
+        filter: &Filter,
+        max_results: Option<usize>,
+    ) -> StorageResult<Vec<ObjectKey>> {
+        self.repository.find_objects(filter, max_results).await
+    }
+
+    async fn search_objects(
+        &self,
+        query: &TextQuery,
+        filter: &Filter,
+        max_results: Option<usize>,
+    ) -> StorageResult<Vec<ObjectKey>> {
+        if query.is_empty() {
+            return self.repository.find_objects(filter, max_results).await;
+        }
+        self.repository
+            .search_objects(query, filter, max_results)
+            .await
     }
 
     /// Copy an object
@@ -133,7 +615,7 @@ impl ObjectService for ObjectServiceImpl {
         &self,
         source_key: &ObjectKey,
         destination_key: &ObjectKey,
-    ) -> StorageResult<StorageObject> {
+    ) -> StorageResult<(StorageObject, Vec<QuotaWarning>)> {
         // Get source object
         let source = self
             .get_object(GetObjectRequest {
@@ -171,6 +653,162 @@ impl ObjectService for ObjectServiceImpl {
             .await
     }
 
+    /// Overwrite a region of an object by reading, modifying and rewriting it
+    ///
+    /// The patch bytes pass through the interceptors as an upload of their
+    /// own, with the object's content type and metadata.
+    async fn patch_object(
+        &self,
+        key: &ObjectKey,
+        offset: u64,
+        data: Bytes,
+        if_match: &str,
+        expected_size: Option<u64>,
+    ) -> StorageResult<ObjectMetadata> {
+        // Patches of a key apply one at a time, each to the data it was made
+        // against
+        let _patching = self.patch_lock(key).lock().await;
+        let metadata = self
+            .repository
+            .get_object_metadata(key, None)
+            .await?
+            .ok_or_else(|| StorageError::ObjectNotFound { key: key.clone() })?;
+        let etag = metadata.etag.as_deref().map(|etag| etag.trim_matches('"'));
+        if etag != Some(if_match.trim_matches('"')) {
+            return Err(StorageError::VersionConflict {
+                key: key.clone(),
+                expected_version: None,
+                actual_version: None,
+            });
+        }
+
+        let current_size = metadata.content_length;
+        if offset > current_size {
+            return Err(StorageError::ValidationError {
+                message: format!(
+                    "Cannot patch {} at offset {}, past its end at {} bytes",
+                    key, offset, current_size
+                ),
+            });
+        }
+
+        let put = self
+            .intercept_put(
+                key,
+                PutContext {
+                    content_type: metadata.content_type.clone(),
+                    custom_metadata: metadata.custom_metadata.clone(),
+                    body: Box::new(std::io::Cursor::new(data.clone())),
+                },
+            )
+            .await?;
+        let mut patch = Vec::with_capacity(data.len());
+        let mut body = put.body;
+        body.read_to_end(&mut patch)
+            .await
+            .map_err(|e| StorageError::ValidationError {
+                message: format!("Failed to read intercepted patch data: {}", e),
+            })?;
+        // The patch replaces exactly the bytes of its range
+        if patch.len() != data.len() {
+            return Err(StorageError::UnsupportedOperation {
+                operation: "patch".to_string(),
+                reason: format!(
+                    "an interceptor changes the length of the data written to {}",
+                    key
+                ),
+            });
+        }
+
+        let end = offset + patch.len() as u64;
+        let patched_size = end.max(current_size);
+        if let Some(expected_size) = expected_size.filter(|size| *size != patched_size) {
+            return Err(StorageError::ValidationError {
+                message: format!(
+                    "Patching {} would leave {} bytes, not the expected {}",
+                    key, patched_size, expected_size
+                ),
+            });
+        }
+
+        // The patched object takes the place of the current one in its quotas
+        let mut usage = self.covering_quota_usage(&[key]).await?;
+        for (_, usage) in &mut usage {
+            usage.object_count = usage.object_count.saturating_sub(1);
+            usage.total_bytes = usage.total_bytes.saturating_sub(current_size);
+        }
+        check_quota_usage(&usage, &[(key, patched_size)])?;
+
+        // The bytes around the patch are copied as ranges of the current
+        // data, which is only replaced when the upload completes
+        let upload_id = self.store.initiate_multipart_upload(key).await?;
+        let mut regions = Vec::new();
+        if offset > 0 {
+            regions.push((Some(0..offset), None));
+        }
+        regions.push((None, Some(Bytes::from(patch))));
+        if end < current_size {
+            regions.push((Some(end..current_size), None));
+        }
+        let mut parts = Vec::with_capacity(regions.len());
+        for (part_number, region) in (1..).zip(regions) {
+            let part = match region {
+                (_, Some(patch)) => {
+                    self.store
+                        .upload_part(key, &upload_id, part_number, patch)
+                        .await
+                }
+                (range, None) => {
+                    self.store
+                        .upload_part_copy(key, &upload_id, part_number, key, range)
+                        .await
+                }
+            };
+            match part {
+                Ok(part) => parts.push(part),
+                Err(e) => {
+                    let _ = self.store.abort_multipart_upload(key, &upload_id).await;
+                    return Err(e);
+                }
+            }
+        }
+        let etag = multipart_etag(parts.iter().map(|part| part.etag.as_str()));
+        let info = self
+            .store
+            .complete_multipart_upload(key, &upload_id, parts)
+            .await?;
+
+        // The patched data is a new version of the object
+        let metadata = ObjectMetadata {
+            content_type: put.content_type,
+            content_length: patched_size,
+            etag: Some(etag),
+            last_modified: self.clock.system_time(),
+            custom_metadata: put.custom_metadata,
+        };
+        let version_id = self.next_version_id(info.version_id)?;
+        self.repository
+            .save_object_metadata(key, &version_id, &metadata)
+            .await?;
+
+        self.intercept_stored(key, &metadata).await?;
+
+        Ok(metadata)
+    }
+
+    /// Get an object's metadata without its data
+    async fn head_object(&self, key: &ObjectKey) -> StorageResult<ObjectMetadata> {
+        let metadata = self
+            .repository
+            .get_object_metadata(key, None)
+            .await?
+            .ok_or_else(|| StorageError::ObjectNotFound { key: key.clone() })?;
+
+        self.intercept_get(key, &metadata).await?;
+
+        Ok(metadata)
+    }
+
     /// Check if object exists
     async fn object_exists(&self, key: &ObjectKey) -> StorageResult<bool> {
         self.repository.object_exists(key).await
@@ -178,14 +816,538 @@ impl ObjectService for ObjectServiceImpl {
 
     /// Get object size without retrieving data
     async fn get_object_size(&self, key: &ObjectKey) -> StorageResult<u64> {
-        self.store.get_object_size(key).await
+        Ok(self.store.head_object(key).await?.content_length)
+    }
+
+    async fn prefix_usage(&self, prefix: &str) -> StorageResult<PrefixUsage> {
+        Ok(PrefixUsage {
+            quota: self
+                .prefix_quotas
+                .iter()
+                .find(|quota| quota.prefix == prefix)
+                .cloned(),
+            ..self.repository.prefix_usage(prefix).await?
+        })
+    }
+
+    async fn quota_usage(&self) -> StorageResult<Vec<PrefixUsage>> {
+        let mut usage = Vec::with_capacity(self.prefix_quotas.len());
+        for quota in &self.prefix_quotas {
+            usage.push(self.prefix_usage(&quota.prefix).await?);
+        }
+        Ok(usage)
+    }
+
+    async fn presign_url(
+        &self,
+        key: &ObjectKey,
+        method: PresignedUrlMethod,
+        expires_in_secs: u64,
+    ) -> StorageResult<String> {
+        // Reading or deleting needs an object the interceptors let through
+        if !matches!(method, PresignedUrlMethod::Put) {
+            self.head_object(key).await?;
+        }
+
+        self.store
+            .get_presigned_url(key, expires_in_secs, method)
+            .await
+    }
+
+    async fn initiate_multipart_upload(
+        &self,
+        key: ObjectKey,
+        mut content_type: Option<String>,
+        mut custom_metadata: HashMap<String, String>,
+    ) -> StorageResult<MultipartUploadInfo> {
+        let repository = self.multipart_uploads()?;
+        if self.repository.object_exists(&key).await? {
+            return Err(StorageError::ObjectAlreadyExists { key });
+        }
+
+        apply_default_metadata(
+            &self.default_metadata,
+            &key,
+            &mut content_type,
+            &mut custom_metadata,
+        );
+        self.check_prefix_quotas(&[(&key, 0)]).await?;
+
+        let upload_id = self.store.initiate_multipart_upload(&key).await?;
+        let upload = MultipartUploadInfo {
+            upload_id,
+            key,
+            initiated: self.clock.now(),
+            content_type,
+            custom_metadata,
+        };
+        repository.create_upload(&upload).await?;
+
+        Ok(upload)
+    }
+
+    async fn upload_part(
+        &self,
+        key: &ObjectKey,
+        upload_id: &str,
+        part_number: u32,
+        data: Bytes,
+    ) -> StorageResult<UploadedPart> {
+        validate_part_number(part_number).map_err(|e| StorageError::ValidationError {
+            message: e.to_string(),
+        })?;
+        self.in_progress_upload(key, upload_id).await?;
+
+        let size = data.len() as u64;
+        let checksum = part_checksum(&data);
+        let stored = self
+            .store
+            .upload_part(key, upload_id, part_number, data)
+            .await?;
+        let part = UploadedPart {
+            part_number,
+            etag: stored.etag,
+            size,
+            last_modified: self.clock.now(),
+            checksum_sha256: Some(checksum),
+        };
+        self.multipart_uploads()?
+            .save_part(upload_id, &part)
+            .await?;
+
+        Ok(part)
+    }
+
+    /// Copy part of an existing object into a multipart upload
+    ///
+    /// The source is read like a download, so interceptors may refuse it.
+    async fn upload_part_copy(
+        &self,
+        key: &ObjectKey,
+        upload_id: &str,
+        part_number: u32,
+        source_key: &ObjectKey,
+        range: Option<Range<u64>>,
+    ) -> StorageResult<UploadedPart> {
+        validate_part_number(part_number).map_err(|e| StorageError::ValidationError {
+            message: e.to_string(),
+        })?;
+        self.in_progress_upload(key, upload_id).await?;
+
+        let source = self
+            .repository
+            .get_object_metadata(source_key, None)
+            .await?
+            .ok_or_else(|| StorageError::ObjectNotFound {
+                key: source_key.clone(),
+            })?;
+        self.intercept_get(source_key, &source).await?;
+
+        let size = match &range {
+            Some(range) if range.start < range.end && range.end <= source.content_length => {
+                range.end - range.start
+            }
+            Some(range) => {
+                return Err(StorageError::ValidationError {
+                    message: format!(
+                        "Range {}-{} is outside {} of {} bytes",
+                        range.start,
+                        range.end.saturating_sub(1),
+                        source_key,
+                        source.content_length
+                    ),
+                });
+            }
+            None => source.content_length,
+        };
+
+        let stored = self
+            .store
+            .upload_part_copy(key, upload_id, part_number, source_key, range)
+            .await?;
+        // The copied data never reaches the service, so it has no checksum
+        let part = UploadedPart {
+            part_number,
+            etag: stored.etag,
+            size,
+            last_modified: self.clock.now(),
+            checksum_sha256: None,
+        };
+        self.multipart_uploads()?
+            .save_part(upload_id, &part)
+            .await?;
+
+        Ok(part)
+    }
+
+    /// Assemble the object from the listed parts
+    ///
+    /// Parts must be listed in ascending order, each with the ETag returned
+    /// when it was uploaded. Interceptors only see the completed object, as
+    /// its data never passes through the service in one piece. When every
+    /// listed part has a checksum, the object's metadata records an
+    /// [`IntegrityManifest`] of them.
+    async fn complete_multipart_upload(
+        &self,
+        key: &ObjectKey,
+        upload_id: &str,
+        parts: Vec<CompletedPart>,
+    ) -> StorageResult<ObjectMetadata> {
+        let upload = self.in_progress_upload(key, upload_id).await?;
+        let repository = self.multipart_uploads()?;
+
+        if parts.is_empty() {
+            return Err(StorageError::ValidationError {
+                message: "A multipart upload needs at least one part to complete".to_string(),
+            });
+        }
+        if parts
+            .windows(2)
+            .any(|pair| pair[0].part_number >= pair[1].part_number)
+        {
+            return Err(StorageError::InvalidPartOrder {
+                upload_id: upload_id.to_string(),
+            });
+        }
+
+        let uploaded: HashMap<u32, UploadedPart> = repository
+            .list_parts(upload_id)
+            .await?
+            .into_iter()
+            .map(|part| (part.part_number, part))
+            .collect();
+        let mut content_length = 0;
+        for part in &parts {
+            match uploaded.get(&part.part_number) {
+                Some(stored) if stored.etag == part.etag.trim_matches('"') => {
+                    content_length += stored.size;
+                }
+                _ => {
+                    return Err(StorageError::InvalidPart {
+                        upload_id: upload_id.to_string(),
+                        part_number: part.part_number,
+                        etag: part.etag.clone(),
+                    });
+                }
+            }
+        }
+
+        if self.repository.object_exists(key).await? {
+            return Err(StorageError::ObjectAlreadyExists { key: key.clone() });
+        }
+        self.check_prefix_quotas(&[(key, content_length)]).await?;
+
+        let etag = multipart_etag(parts.iter().map(|part| part.etag.as_str()));
+        let manifest =
+            IntegrityManifest::from_parts(parts.iter().map(|part| &uploaded[&part.part_number]));
+        let info = self
+            .store
+            .complete_multipart_upload(key, upload_id, parts)
+            .await?;
+
+        let mut custom_metadata = upload.custom_metadata;
+        if let Some(manifest) = manifest {
+            manifest.write_metadata(&mut custom_metadata);
+        }
+        let metadata = ObjectMetadata {
+            content_type: upload.content_type,
+            content_length,
+            etag: Some(etag),
+            last_modified: self.clock.system_time(),
+            custom_metadata,
+        };
+        let version_id = self.next_version_id(info.version_id)?;
+        self.repository
+            .save_object_metadata(key, &version_id, &metadata)
+            .await?;
+        repository.delete_upload(upload_id).await?;
+
+        self.intercept_stored(key, &metadata).await?;
+
+        Ok(metadata)
+    }
+
+    async fn abort_multipart_upload(&self, key: &ObjectKey, upload_id: &str) -> StorageResult<()> {
+        self.in_progress_upload(key, upload_id).await?;
+        self.store.abort_multipart_upload(key, upload_id).await?;
+        self.multipart_uploads()?.delete_upload(upload_id).await
+    }
+
+    async fn list_multipart_uploads(
+        &self,
+        prefix: Option<&str>,
+        max_uploads: Option<usize>,
+    ) -> StorageResult<Vec<MultipartUploadInfo>> {
+        let mut uploads = self
+            .multipart_uploads()?
+            .list_uploads(prefix.unwrap_or_default())
+            .await?;
+        uploads.truncate(max_uploads.unwrap_or(usize::MAX));
+        Ok(uploads)
+    }
+
+    async fn list_parts(
+        &self,
+        key: &ObjectKey,
+        upload_id: &str,
+    ) -> StorageResult<Vec<UploadedPart>> {
+        self.in_progress_upload(key, upload_id).await?;
+        self.multipart_uploads()?.list_parts(upload_id).await
+    }
+
+    async fn presign_multipart_upload(
+        &self,
+        key: &ObjectKey,
+        upload_id: &str,
+        part_numbers: &[u32],
+        expires_in_secs: u64,
+    ) -> StorageResult<PresignedMultipartUpload> {
+        for &part_number in part_numbers {
+            validate_part_number(part_number).map_err(|e| StorageError::ValidationError {
+                message: e.to_string(),
+            })?;
+        }
+        self.in_progress_upload(key, upload_id).await?;
+
+        let mut parts = Vec::with_capacity(part_numbers.len());
+        for &part_number in part_numbers {
+            let url = self
+                .store
+                .get_presigned_multipart_url(key, upload_id, Some(part_number), expires_in_secs)
+                .await?;
+            parts.push(PresignedPart { part_number, url });
+        }
+        let complete_url = self
+            .store
+            .get_presigned_multipart_url(key, upload_id, None, expires_in_secs)
+            .await?;
+
+        Ok(PresignedMultipartUpload {
+            upload_id: upload_id.to_string(),
+            parts,
+            complete_url,
+        })
+    }
+
+    async fn begin_transaction(&self) -> StorageResult<ObjectTransaction> {
+        let transaction = ObjectTransaction {
+            transaction_id: self.id_generator.next_id(),
+            started: self.clock.now(),
+            objects: Vec::new(),
+            committed_at: None,
+        };
+        self.transactions()?
+            .create_transaction(&transaction)
+            .await?;
+
+        Ok(transaction)
+    }
+
+    async fn get_transaction(&self, transaction_id: &str) -> StorageResult<ObjectTransaction> {
+        self.transactions()?
+            .get_transaction(transaction_id)
+            .await?
+            .ok_or_else(|| StorageError::TransactionNotFound {
+                transaction_id: transaction_id.to_string(),
+            })
+    }
+
+    /// Stage an object under the transaction's staging prefix
+    ///
+    /// Default metadata and interceptors apply as for any upload, but quotas
+    /// are only checked once the transaction commits.
+    async fn stage_object(
+        &self,
+        transaction_id: &str,
+        key: ObjectKey,
+        reader: Box<dyn AsyncRead + Send + Unpin>,
+        mut content_type: Option<String>,
+        mut custom_metadata: HashMap<String, String>,
+    ) -> StorageResult<StagedObject> {
+        let transaction = self.get_transaction(transaction_id).await?;
+        if transaction.is_committed() {
+            return Err(StorageError::ValidationError {
+                message: format!("Transaction '{}' is already committed", transaction_id),
+            });
+        }
+        let staged_key =
+            transaction
+                .staged_key(&key)
+                .map_err(|e| StorageError::ValidationError {
+                    message: e.to_string(),
+                })?;
+
+        apply_default_metadata(
+            &self.default_metadata,
+            &key,
+            &mut content_type,
+            &mut custom_metadata,
+        );
+        let put = self
+            .intercept_put(
+                &key,
+                PutContext {
+                    content_type,
+                    custom_metadata,
+                    body: reader,
+                },
+            )
+            .await?;
+
+        let context = Arc::new(Mutex::new(md5::Context::new()));
+        let reader = HashingReader {
+            inner: put.body,
+            context: context.clone(),
+        };
+        let info = self
+            .store
+            .put_object_stream(&staged_key, Box::new(reader), put.content_type.as_deref())
+            .await?;
+        let digest = context.lock().unwrap().clone().compute();
+
+        let staged = StagedObject {
+            key,
+            staged_key,
+            version_id: self.next_version_id(None)?,
+            size: info.size,
+            etag: format!("{:x}", digest),
+            content_type: put.content_type,
+            custom_metadata: put.custom_metadata,
+        };
+        self.transactions()?
+            .save_staged_object(transaction_id, &staged)
+            .await?;
+
+        Ok(staged)
+    }
+
+    /// Copy the staged objects to the keys of their versions and then publish
+    /// all the versions at once
+    ///
+    /// Each copy goes to a key of its own version, so it never touches the
+    /// object's current data and stays invisible to reads until its version
+    /// is published. Objects that already exist get a new version. If copying
+    /// fails, the copies made so far are removed and the transaction stays
+    /// open. Once every copy is in place the manifest is marked committed,
+    /// and the version recorded in it for each object is published by a
+    /// single repository write, after which each object's key is brought up
+    /// to date with its published version. Committing a transaction
+    /// interrupted after that point publishes the same versions again rather
+    /// than adding new ones.
+    async fn commit_transaction(&self, transaction_id: &str) -> StorageResult<ObjectTransaction> {
+        let repository = self.transactions()?;
+        let mut transaction = self.get_transaction(transaction_id).await?;
+
+        if !transaction.is_committed() {
+            if transaction.objects.is_empty() {
+                return Err(StorageError::ValidationError {
+                    message: format!("Transaction '{}' has no staged objects", transaction_id),
+                });
+            }
+            let pending: Vec<(&ObjectKey, u64)> = transaction
+                .objects
+                .iter()
+                .map(|object| (&object.key, object.size))
+                .collect();
+            self.check_prefix_quotas(&pending).await?;
+
+            for (copied, object) in transaction.objects.iter().enumerate() {
+                let copy = match committed_version_key(object) {
+                    Ok(version_key) => self
+                        .store
+                        .copy_object(&object.staged_key, &version_key)
+                        .await
+                        .map(|_| ()),
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = copy {
+                    // Best effort: copies left behind hold no metadata and stay unreadable
+                    for object in &transaction.objects[..copied] {
+                        if let Ok(version_key) = committed_version_key(object) {
+                            let _ = self.store.delete_object(&version_key).await;
+                        }
+                    }
+                    return Err(e);
+                }
+            }
+
+            let committed_at = self.clock.now();
+            repository
+                .mark_committed(transaction_id, committed_at)
+                .await?;
+            transaction.committed_at = Some(committed_at);
+        }
+        let committed_at = transaction.committed_at.unwrap_or_else(|| self.clock.now());
+
+        let versions: Vec<(ObjectKey, VersionId, ObjectMetadata)> = transaction
+            .objects
+            .iter()
+            .map(|object| {
+                let metadata = ObjectMetadata {
+                    content_type: object.content_type.clone(),
+                    content_length: object.size,
+                    etag: Some(object.etag.clone()),
+                    last_modified: committed_at.into(),
+                    custom_metadata: object.custom_metadata.clone(),
+                };
+                (object.key.clone(), object.version_id.clone(), metadata)
+            })
+            .collect();
+        self.repository.save_object_versions(&versions).await?;
+
+        for object in &transaction.objects {
+            // A version published since by another write stays the one at the key
+            let latest = self.repository.get_latest_version_id(&object.key).await?;
+            if latest.as_ref() == Some(&object.version_id) {
+                self.store
+                    .copy_object(&committed_version_key(object)?, &object.key)
+                    .await?;
+            }
+        }
+
+        for object in &transaction.objects {
+            // An earlier attempt may have removed the staged copy already
+            match self.store.delete_object(&object.staged_key).await {
+                Ok(()) | Err(StorageError::ObjectNotFound { .. }) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        repository.delete_transaction(transaction_id).await?;
+
+        for (key, _, metadata) in &versions {
+            self.intercept_stored(key, metadata).await?;
+        }
+
+        Ok(transaction)
+    }
+
+    async fn abort_transaction(&self, transaction_id: &str) -> StorageResult<()> {
+        let transaction = self.get_transaction(transaction_id).await?;
+        if transaction.is_committed() {
+            return Err(StorageError::ValidationError {
+                message: format!(
+                    "Transaction '{}' is already committed, commit it again to finish it",
+                    transaction_id
+                ),
+            });
+        }
+
+        for object in &transaction.objects {
+            self.store.delete_object(&object.staged_key).await?;
+        }
+        self.transactions()?
+            .delete_transaction(transaction_id)
+            .await
     }
 }
 
 /// Builder for ObjectServiceImpl
+#[derive(Default)]
 pub struct ObjectServiceBuilder {
     repository: Option<Arc<dyn ObjectRepository>>,
     store: Option<Arc<dyn ObjectStore>>,
+    interceptors: Vec<Arc<dyn ObjectInterceptor>>,
+    multipart_uploads: Option<Arc<dyn MultipartUploadRepository>>,
 }
 
 impl ObjectServiceBuilder {
@@ -193,6 +1355,8 @@ impl ObjectServiceBuilder {
         Self {
             repository: None,
             store: None,
+            interceptors: Vec::new(),
+            multipart_uploads: None,
         }
     }
 
@@ -206,10 +1370,500 @@ impl ObjectServiceBuilder {
         self
     }
 
+    pub fn interceptor(mut self, interceptor: Arc<dyn ObjectInterceptor>) -> Self {
+        self.interceptors.push(interceptor);
+        self
+    }
+
+    pub fn multipart_uploads(mut self, repository: Arc<dyn MultipartUploadRepository>) -> Self {
+        self.multipart_uploads = Some(repository);
+        self
+    }
+
     pub fn build(self) -> Result<ObjectServiceImpl, &'static str> {
         let repository = self.repository.ok_or("Repository is required")?;
         let store = self.store.ok_or("Store is required")?;
 
-        Ok(ObjectServiceImpl::new(repository, store))
+        let mut service = self.interceptors.into_iter().fold(
+            ObjectServiceImpl::new(repository, store),
+            |service, interceptor| service.with_interceptor(interceptor),
+        );
+        if let Some(multipart_uploads) = self.multipart_uploads {
+            service = service.with_multipart_uploads(multipart_uploads);
+        }
+        Ok(service)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::outbound::persistence::{
+        InMemoryMultipartUploadRepository, InMemoryObjectRepository, InMemoryTransactionRepository,
+    };
+    use crate::adapters::outbound::storage::S3ObjectStoreAdapter;
+    use crate::domain::{models::QuotaKind, value_objects::BucketName};
+    use object_store::memory::InMemory;
+
+    /// Rejects empty uploads, tags everything else and hides objects marked secret
+    struct TestInterceptor;
+
+    #[async_trait]
+    impl ObjectInterceptor for TestInterceptor {
+        async fn pre_put(&self, key: &ObjectKey, mut put: PutContext) -> StorageResult<PutContext> {
+            let mut data = Vec::new();
+            put.body.read_to_end(&mut data).await.unwrap();
+            if data.is_empty() {
+                return Err(StorageError::ValidationError {
+                    message: format!("{} is empty", key),
+                });
+            }
+            put.custom_metadata
+                .insert("x-intercepted".to_string(), "true".to_string());
+            data.extend_from_slice(b" [watermarked]");
+            put.body = Box::new(std::io::Cursor::new(data));
+            Ok(put)
+        }
+
+        async fn pre_get(&self, key: &ObjectKey, metadata: &ObjectMetadata) -> StorageResult<()> {
+            if metadata.custom_metadata.contains_key("secret") {
+                return Err(StorageError::AccessDenied {
+                    key: key.clone(),
+                    operation: "get".to_string(),
+                });
+            }
+            Ok(())
+        }
+    }
+
+    fn service() -> ObjectServiceImpl {
+        let bucket = BucketName::new("test-bucket".to_string()).unwrap();
+        ObjectServiceImpl::new(
+            Arc::new(InMemoryObjectRepository::new()),
+            Arc::new(S3ObjectStoreAdapter::new(Arc::new(InMemory::new()), bucket)),
+        )
+        .with_interceptor(Arc::new(TestInterceptor))
+    }
+
+    #[tokio::test]
+    async fn test_interceptors_rewrite_and_reject() {
+        let service = service();
+        let key = ObjectKey::new("docs/a.txt".to_string()).unwrap();
+
+        let (metadata, _) = service
+            .create_object_stream(
+                key.clone(),
+                Box::new(std::io::Cursor::new(b"hello".to_vec())),
+                None,
+                HashMap::new(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(metadata.custom_metadata["x-intercepted"], "true");
+
+        let object = service
+            .get_object(GetObjectRequest {
+                key: key.clone(),
+                version_id: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(&object.data[..], b"hello [watermarked]");
+
+        let (_, mut range) = service.get_object_stream(&key, Some(6..11)).await.unwrap();
+        let mut data = Vec::new();
+        range.read_to_end(&mut data).await.unwrap();
+        assert_eq!(data, b"[wate");
+
+        let empty = ObjectKey::new("docs/empty.txt".to_string()).unwrap();
+        let rejected = service
+            .create_object_stream(
+                empty.clone(),
+                Box::new(std::io::Cursor::new(Vec::new())),
+                None,
+                HashMap::new(),
+            )
+            .await;
+        assert!(matches!(
+            rejected,
+            Err(StorageError::ValidationError { .. })
+        ));
+        assert!(!service.object_exists(&empty).await.unwrap());
+
+        let secret = ObjectKey::new("docs/secret.txt".to_string()).unwrap();
+        service
+            .create_object_stream(
+                secret.clone(),
+                Box::new(std::io::Cursor::new(b"classified".to_vec())),
+                None,
+                HashMap::from([("secret".to_string(), "yes".to_string())]),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(
+            service.get_object_stream(&secret, None).await,
+            Err(StorageError::AccessDenied { .. })
+        ));
+
+        // A patch covers a fixed range, so its length can't change on the way
+        let etag = metadata.etag.unwrap();
+        let patched = service
+            .patch_object(&key, 0, Bytes::from_static(b"j"), &etag, None)
+            .await;
+        assert!(matches!(
+            patched,
+            Err(StorageError::UnsupportedOperation { .. })
+        ));
+        let object = service
+            .get_object(GetObjectRequest {
+                key,
+                version_id: None,
+            })
+            .await;
+        assert_eq!(&object.unwrap().data[..], b"hello [watermarked]");
+    }
+
+    #[tokio::test]
+    async fn test_default_metadata_fills_unset_values() {
+        let service = service()
+            .with_default_metadata(
+                DefaultMetadata::new("docs/static/")
+                    .with_content_type("text/css")
+                    .with_metadata("cache-control", "max-age=31536000"),
+            )
+            .with_multipart_uploads(Arc::new(InMemoryMultipartUploadRepository::new()));
+        let key = ObjectKey::new("docs/static/site.css".to_string()).unwrap();
+
+        let (metadata, _) = service
+            .create_object_stream(
+                key,
+                Box::new(std::io::Cursor::new(b"body {}".to_vec())),
+                None,
+                HashMap::new(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(metadata.content_type.as_deref(), Some("text/css"));
+        assert_eq!(
+            metadata.custom_metadata["cache-control"],
+            "max-age=31536000"
+        );
+        assert_eq!(metadata.custom_metadata["x-intercepted"], "true");
+
+        let key = ObjectKey::new("docs/static/logo.svg".to_string()).unwrap();
+        let upload = service
+            .initiate_multipart_upload(
+                key,
+                Some("image/svg+xml".to_string()),
+                HashMap::from([("cache-control".to_string(), "no-cache".to_string())]),
+            )
+            .await
+            .unwrap();
+        assert_eq!(upload.content_type.as_deref(), Some("image/svg+xml"));
+        assert_eq!(upload.custom_metadata["cache-control"], "no-cache");
+    }
+
+    #[tokio::test]
+    async fn test_prefix_quotas_limit_writes() {
+        let service = service()
+            .with_prefix_quota(PrefixQuota::new("docs/tmp/").with_max_objects(2))
+            .with_prefix_quota(PrefixQuota::new("docs/").with_max_bytes(64));
+        let put = |name: &str, data: &[u8]| {
+            service.create_object_stream(
+                ObjectKey::new(format!("docs/{}", name)).unwrap(),
+                Box::new(std::io::Cursor::new(data.to_vec())),
+                None,
+                HashMap::new(),
+            )
+        };
+
+        put("tmp/a", b"one").await.unwrap();
+        // Writes past 80 percent of a limit are warned about
+        let (_, warnings) = put("tmp/b", b"two").await.unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            (warnings[0].prefix.as_str(), warnings[0].used),
+            ("docs/tmp/", 2)
+        );
+        assert!(matches!(
+            put("tmp/c", b"three").await,
+            Err(StorageError::PrefixQuotaExceeded {
+                kind: QuotaKind::Objects,
+                used: 3,
+                limit: 2,
+                ..
+            })
+        ));
+
+        // Each stored object is watermarked by the test interceptor, adding 14 bytes
+        let (_, warnings) = service
+            .create_object(CreateObjectRequest {
+                key: ObjectKey::new("docs/report".to_string()).unwrap(),
+                data: vec![0; 10],
+                content_type: None,
+                custom_metadata: HashMap::new(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!((warnings[0].used, warnings[0].soft_limit), (58, 51));
+        let oversized = ObjectKey::new("docs/large".to_string()).unwrap();
+        assert!(matches!(
+            put("large", &[0; 20]).await,
+            Err(StorageError::PrefixQuotaExceeded {
+                kind: QuotaKind::Bytes,
+                limit: 64,
+                ..
+            })
+        ));
+        assert!(service.get_object_size(&oversized).await.is_err());
+
+        let usage = service.quota_usage().await.unwrap();
+        assert_eq!(usage.len(), 2);
+        assert_eq!((usage[0].object_count, usage[0].total_bytes), (2, 34));
+        assert_eq!(usage[0].quota.as_ref().unwrap().max_objects, Some(2));
+        assert_eq!((usage[1].object_count, usage[1].total_bytes), (3, 58));
+
+        let unlimited = service.prefix_usage("docs/re").await.unwrap();
+        assert_eq!(unlimited.object_count, 1);
+        assert!(unlimited.quota.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_transactions_commit_all_objects_or_none() {
+        let service = service().with_transactions(Arc::new(InMemoryTransactionRepository::new()));
+        let stage = |transaction_id: String, name: &str| {
+            let key = ObjectKey::new(format!("docs/dataset/{}", name)).unwrap();
+            let service = &service;
+            async move {
+                service
+                    .stage_object(
+                        &transaction_id,
+                        key,
+                        Box::new(std::io::Cursor::new(b"rows".to_vec())),
+                        None,
+                        HashMap::new(),
+                    )
+                    .await
+            }
+        };
+        let part = |name: &str| ObjectKey::new(format!("docs/dataset/{}", name)).unwrap();
+
+        let transaction = service.begin_transaction().await.unwrap();
+        let id = transaction.transaction_id;
+        stage(id.clone(), "part-0").await.unwrap();
+        stage(id.clone(), "part-1").await.unwrap();
+
+        // Staged objects are neither readable nor listed
+        assert!(!service.object_exists(&part("part-0")).await.unwrap());
+        assert_eq!(service.prefix_usage("docs/").await.unwrap().object_count, 0);
+
+        let committed = service.commit_transaction(&id).await.unwrap();
+        assert!(committed.is_committed());
+        assert_eq!(committed.objects.len(), 2);
+        for name in ["part-0", "part-1"] {
+            let object = service
+                .get_object(GetObjectRequest {
+                    key: part(name),
+                    version_id: None,
+                })
+                .await
+                .unwrap();
+            assert_eq!(&object.data[..], b"rows [watermarked]");
+        }
+        assert!(matches!(
+            service.get_transaction(&id).await,
+            Err(StorageError::TransactionNotFound { .. })
+        ));
+
+        // Objects that already exist are republished as new versions
+        let transaction = service.begin_transaction().await.unwrap();
+        let id = transaction.transaction_id;
+        stage(id.clone(), "part-2").await.unwrap();
+        stage(id.clone(), "part-1").await.unwrap();
+        let republished = service.commit_transaction(&id).await.unwrap();
+        assert!(service.object_exists(&part("part-2")).await.unwrap());
+        let versions = service
+            .repository
+            .list_object_versions(&part("part-1"))
+            .await
+            .unwrap();
+        assert_eq!(versions.versions.len(), 2);
+        assert_eq!(
+            service
+                .repository
+                .get_latest_version_id(&part("part-1"))
+                .await
+                .unwrap(),
+            Some(republished.objects[1].version_id.clone())
+        );
+
+        // An aborted transaction publishes nothing
+        let transaction = service.begin_transaction().await.unwrap();
+        let id = transaction.transaction_id;
+        stage(id.clone(), "part-3").await.unwrap();
+        service.abort_transaction(&id).await.unwrap();
+        assert!(!service.object_exists(&part("part-3")).await.unwrap());
+        assert_eq!(service.prefix_usage("docs/").await.unwrap().object_count, 3);
+        assert!(matches!(
+            service.commit_transaction(&id).await,
+            Err(StorageError::TransactionNotFound { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_committing_again_republishes_the_same_versions() {
+        let transactions = InMemoryTransactionRepository::new();
+        let service = service().with_transactions(Arc::new(transactions.clone()));
+        let transaction = service.begin_transaction().await.unwrap();
+        let id = transaction.transaction_id;
+        for name in ["part-0", "part-1"] {
+            service
+                .stage_object(
+                    &id,
+                    ObjectKey::new(format!("docs/dataset/{}", name)).unwrap(),
+                    Box::new(std::io::Cursor::new(b"rows".to_vec())),
+                    None,
+                    HashMap::new(),
+                )
+                .await
+                .unwrap();
+        }
+        let committed = service.commit_transaction(&id).await.unwrap();
+
+        // As if the first commit stopped before forgetting the manifest
+        transactions.create_transaction(&committed).await.unwrap();
+        let again = service.commit_transaction(&id).await.unwrap();
+        assert_eq!(again.committed_at, committed.committed_at);
+
+        for object in &committed.objects {
+            let versions = service
+                .repository
+                .list_object_versions(&object.key)
+                .await
+                .unwrap();
+            assert_eq!(versions.versions.len(), 1);
+            assert_eq!(versions.versions[0].version_id, object.version_id);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_multipart_upload_tracking() {
+        let service =
+            service().with_multipart_uploads(Arc::new(InMemoryMultipartUploadRepository::new()));
+        let key = ObjectKey::new("docs/big.bin".to_string()).unwrap();
+
+        let upload = service
+            .initiate_multipart_upload(key.clone(), None, HashMap::new())
+            .await
+            .unwrap();
+        let first = service
+            .upload_part(&key, &upload.upload_id, 1, Bytes::from_static(b"hello "))
+            .await
+            .unwrap();
+        let second = service
+            .upload_part(&key, &upload.upload_id, 2, Bytes::from_static(b"world"))
+            .await
+            .unwrap();
+
+        let uploads = service
+            .list_multipart_uploads(Some("docs/"), None)
+            .await
+            .unwrap();
+        assert_eq!(uploads, std::slice::from_ref(&upload));
+        let parts = service.list_parts(&key, &upload.upload_id).await.unwrap();
+        assert_eq!(parts, [first.clone(), second.clone()]);
+
+        let completed = |parts: &[&UploadedPart]| -> Vec<CompletedPart> {
+            parts
+                .iter()
+                .map(|part| CompletedPart {
+                    part_number: part.part_number,
+                    etag: part.etag.clone(),
+                })
+                .collect()
+        };
+        let out_of_order = service
+            .complete_multipart_upload(&key, &upload.upload_id, completed(&[&second, &first]))
+            .await;
+        assert!(matches!(
+            out_of_order,
+            Err(StorageError::InvalidPartOrder { .. })
+        ));
+        let mut wrong_etag = completed(&[&first]);
+        wrong_etag[0].etag = second.etag.clone();
+        let wrong_etag = service
+            .complete_multipart_upload(&key, &upload.upload_id, wrong_etag)
+            .await;
+        assert!(matches!(
+            wrong_etag,
+            Err(StorageError::InvalidPart { part_number: 1, .. })
+        ));
+
+        let metadata = service
+            .complete_multipart_upload(&key, &upload.upload_id, completed(&[&first, &second]))
+            .await
+            .unwrap();
+        assert_eq!(metadata.content_length, 11);
+        assert!(metadata.etag.unwrap().ends_with("-2"));
+        let manifest = IntegrityManifest::from_metadata(&metadata.custom_metadata).unwrap();
+        assert!(manifest.composite.ends_with("-2"));
+        assert!(manifest.verify_part(2, b"world"));
+
+        let object = service
+            .get_object(GetObjectRequest {
+                key: key.clone(),
+                version_id: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(&object.data[..], b"hello world");
+
+        // Completed uploads are no longer in progress
+        assert!(
+            service
+                .list_multipart_uploads(None, None)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(matches!(
+            service.list_parts(&key, &upload.upload_id).await,
+            Err(StorageError::UploadNotFound { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_presigned_multipart_upload() {
+        let service =
+            service().with_multipart_uploads(Arc::new(InMemoryMultipartUploadRepository::new()));
+        let key = ObjectKey::new("docs/huge.bin".to_string()).unwrap();
+        let upload = service
+            .initiate_multipart_upload(key.clone(), None, HashMap::new())
+            .await
+            .unwrap();
+
+        let presigned = service
+            .presign_multipart_upload(&key, &upload.upload_id, &[1, 2], 600)
+            .await
+            .unwrap();
+        assert_eq!(presigned.parts.len(), 2);
+        assert!(presigned.parts[1].url.contains("partNumber=2"));
+        assert!(presigned.parts[1].url.contains("method=PUT"));
+        assert!(presigned.complete_url.contains("method=POST"));
+        assert!(!presigned.complete_url.contains("partNumber"));
+
+        assert!(matches!(
+            service
+                .presign_multipart_upload(&key, &upload.upload_id, &[0], 600)
+                .await,
+            Err(StorageError::ValidationError { .. })
+        ));
+        assert!(matches!(
+            service
+                .presign_multipart_upload(&key, "unknown", &[1], 600)
+                .await,
+            Err(StorageError::UploadNotFound { .. })
+        ));
     }
 }
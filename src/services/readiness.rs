@@ -0,0 +1,358 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    domain::{
+        models::WorkerEvent,
+        value_objects::{BucketName, ObjectKey},
+    },
+    ports::{
+        events::WorkerEventSink,
+        repositories::{LifecycleRepository, ObjectRepository},
+        storage::ObjectStore,
+    },
+};
+
+/// Default time a backend gets to answer a readiness check
+pub const DEFAULT_READINESS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Default time between readiness checks of a paused worker
+pub const DEFAULT_READINESS_RETRY_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A backend that failed its readiness check
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("{backend} is unavailable: {message}")]
+pub struct BackendUnavailable {
+    pub backend: &'static str,
+    pub message: String,
+}
+
+/// Readiness checks of the backends background workers depend on
+///
+/// A check looks up an object that does not exist in the storage backend and
+/// the object repository, and a bucket's lifecycle configuration, so it costs
+/// each backend one cheap request. Workers call
+/// [`wait_until_ready`](Self::wait_until_ready) before each pass: while a
+/// backend is down they pause instead of failing pass after pass, and
+/// publish a [`WorkerEvent`] when they pause and when they resume.
+#[derive(Clone)]
+pub struct BackendReadiness {
+    object_store: Arc<dyn ObjectStore>,
+    object_repository: Arc<dyn ObjectRepository>,
+    lifecycle_repository: Arc<dyn LifecycleRepository>,
+    timeout: Duration,
+    retry_interval: Duration,
+    event_sinks: Vec<Arc<dyn WorkerEventSink>>,
+}
+
+impl BackendReadiness {
+    pub fn new(
+        object_store: Arc<dyn ObjectStore>,
+        object_repository: Arc<dyn ObjectRepository>,
+        lifecycle_repository: Arc<dyn LifecycleRepository>,
+    ) -> Self {
+        Self {
+            object_store,
+            object_repository,
+            lifecycle_repository,
+            timeout: DEFAULT_READINESS_TIMEOUT,
+            retry_interval: DEFAULT_READINESS_RETRY_INTERVAL,
+            event_sinks: Vec::new(),
+        }
+    }
+
+    /// Give each backend `timeout` to answer a check
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Check the backends of a paused worker every `retry_interval`
+    pub fn with_retry_interval(mut self, retry_interval: Duration) -> Self {
+        self.retry_interval = retry_interval;
+        self
+    }
+
+    /// Publish workers pausing and resuming to `sink`
+    pub fn with_event_sink(mut self, sink: Arc<dyn WorkerEventSink>) -> Self {
+        self.event_sinks.push(sink);
+        self
+    }
+
+    /// Check every backend once, reporting the first that failed
+    pub async fn check(&self) -> Result<(), BackendUnavailable> {
+        let probe_key = ObjectKey::new(".connectivity-check".to_string())
+            .expect("the readiness probe key is valid");
+        let probe_bucket = BucketName::new("connectivity-check".to_string())
+            .expect("the readiness probe bucket is valid");
+
+        self.probe("object store", self.object_store.object_exists(&probe_key))
+            .await?;
+        self.probe(
+            "object repository",
+            self.object_repository.object_exists(&probe_key),
+        )
+        .await?;
+        self.probe(
+            "lifecycle repository",
+            self.lifecycle_repository
+                .configuration_exists(&probe_bucket),
+        )
+        .await
+    }
+
+    async fn probe<T, E: std::fmt::Display>(
+        &self,
+        backend: &'static str,
+        probe: impl std::future::Future<Output = Result<T, E>>,
+    ) -> Result<(), BackendUnavailable> {
+        match tokio::time::timeout(self.timeout, probe).await {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(e)) => Err(BackendUnavailable {
+                backend,
+                message: e.to_string(),
+            }),
+            Err(_) => Err(BackendUnavailable {
+                backend,
+                message: format!("no response within {:?}", self.timeout),
+            }),
+        }
+    }
+
+    /// Wait until every backend passes a check, or `shutdown` is cancelled
+    ///
+    /// `paused` is set while `worker` waits for a backend that failed a check.
+    /// Returns `false` if the wait ended because of `shutdown`.
+    pub async fn wait_until_ready(
+        &self,
+        worker: &str,
+        paused: &AtomicBool,
+        shutdown: &CancellationToken,
+    ) -> bool {
+        loop {
+            let result = tokio::select! {
+                _ = shutdown.cancelled() => return false,
+                result = self.check() => result,
+            };
+            match result {
+                Ok(()) => {
+                    if paused.swap(false, Ordering::SeqCst) {
+                        tracing::info!("Backends recovered, resuming the {} worker", worker);
+                        self.publish(WorkerEvent::resumed(worker)).await;
+                    }
+                    return true;
+                }
+                Err(e) => {
+                    if !paused.swap(true, Ordering::SeqCst) {
+                        tracing::warn!("Pausing the {} worker: {}", worker, e);
+                        self.publish(WorkerEvent::paused(worker, e.to_string()))
+                            .await;
+                    }
+                }
+            }
+
+            tokio::select! {
+                _ = shutdown.cancelled() => return false,
+                _ = tokio::time::sleep(self.retry_interval) => {}
+            }
+        }
+    }
+
+    async fn publish(&self, event: WorkerEvent) {
+        for sink in &self.event_sinks {
+            if let Err(e) = sink.publish(&event).await {
+                tracing::warn!(
+                    "Failed to publish {} event of the {} worker: {}",
+                    event.event_type.as_str(),
+                    event.worker,
+                    e
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::outbound::persistence::{
+        InMemoryLifecycleRepository, InMemoryObjectRepository,
+    };
+    use crate::adapters::outbound::storage::S3ObjectStoreAdapter;
+    use crate::domain::{
+        errors::{StorageError, StorageResult},
+        models::{ObjectMetadata, ObjectVersionInfo, ObjectVersionList, WorkerEventType},
+        value_objects::VersionId,
+    };
+    use async_trait::async_trait;
+    use object_store::memory::InMemory;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingSink {
+        events: Mutex<Vec<WorkerEvent>>,
+    }
+
+    #[async_trait]
+    impl WorkerEventSink for RecordingSink {
+        async fn publish(&self, event: &WorkerEvent) -> StorageResult<()> {
+            self.events.lock().unwrap().push(event.clone());
+            Ok(())
+        }
+    }
+
+    /// Object repository whose lookups fail while `down` is set
+    struct FlakyRepository {
+        inner: InMemoryObjectRepository,
+        down: AtomicBool,
+    }
+
+    impl FlakyRepository {
+        fn check(&self) -> StorageResult<()> {
+            match self.down.load(Ordering::SeqCst) {
+                true => Err(StorageError::StorageBackendError {
+                    message: "connection refused".to_string(),
+                }),
+                false => Ok(()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl ObjectRepository for FlakyRepository {
+        async fn save_object_metadata(
+            &self,
+            key: &ObjectKey,
+            version_id: &VersionId,
+            metadata: &ObjectMetadata,
+        ) -> StorageResult<()> {
+            self.inner
+                .save_object_metadata(key, version_id, metadata)
+                .await
+        }
+
+        async fn get_object_metadata(
+            &self,
+            key: &ObjectKey,
+            version_id: Option<&VersionId>,
+        ) -> StorageResult<Option<ObjectMetadata>> {
+            self.inner.get_object_metadata(key, version_id).await
+        }
+
+        async fn list_object_versions(&self, key: &ObjectKey) -> StorageResult<ObjectVersionList> {
+            self.inner.list_object_versions(key).await
+        }
+
+        async fn get_version_info(
+            &self,
+            key: &ObjectKey,
+            version_id: &VersionId,
+        ) -> StorageResult<Option<ObjectVersionInfo>> {
+            self.inner.get_version_info(key, version_id).await
+        }
+
+        async fn mark_version_deleted(
+            &self,
+            key: &ObjectKey,
+            version_id: &VersionId,
+        ) -> StorageResult<()> {
+            self.inner.mark_version_deleted(key, version_id).await
+        }
+
+        async fn delete_version_metadata(
+            &self,
+            key: &ObjectKey,
+            version_id: &VersionId,
+        ) -> StorageResult<()> {
+            self.inner.delete_version_metadata(key, version_id).await
+        }
+
+        async fn get_latest_version_id(&self, key: &ObjectKey) -> StorageResult<Option<VersionId>> {
+            self.inner.get_latest_version_id(key).await
+        }
+
+        async fn list_objects_by_prefix(
+            &self,
+            prefix: &str,
+            max_results: Option<usize>,
+        ) -> StorageResult<Vec<ObjectKey>> {
+            self.inner.list_objects_by_prefix(prefix, max_results).await
+        }
+
+        async fn list_delete_markers(&self, prefix: &str) -> StorageResult<Vec<ObjectKey>> {
+            self.inner.list_delete_markers(prefix).await
+        }
+
+        async fn update_object_metadata(
+            &self,
+            key: &ObjectKey,
+            version_id: &VersionId,
+            metadata: &ObjectMetadata,
+        ) -> StorageResult<()> {
+            self.inner
+                .update_object_metadata(key, version_id, metadata)
+                .await
+        }
+
+        async fn object_exists(&self, key: &ObjectKey) -> StorageResult<bool> {
+            self.check()?;
+            self.inner.object_exists(key).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_workers_pause_until_backends_recover() {
+        let repository = Arc::new(FlakyRepository {
+            inner: InMemoryObjectRepository::new(),
+            down: AtomicBool::new(true),
+        });
+        let sink = Arc::new(RecordingSink::default());
+        let bucket = BucketName::new("test-bucket".to_string()).unwrap();
+        let readiness = BackendReadiness::new(
+            Arc::new(S3ObjectStoreAdapter::new(Arc::new(InMemory::new()), bucket)),
+            repository.clone(),
+            Arc::new(InMemoryLifecycleRepository::new()),
+        )
+        .with_retry_interval(Duration::from_millis(10))
+        .with_event_sink(sink.clone());
+
+        let error = readiness.check().await.unwrap_err();
+        assert_eq!(error.backend, "object repository");
+
+        let paused = Arc::new(AtomicBool::new(false));
+        let shutdown = CancellationToken::new();
+        let waiting = tokio::spawn({
+            let readiness = readiness.clone();
+            let paused = paused.clone();
+            let shutdown = shutdown.clone();
+            async move { readiness.wait_until_ready("test", &paused, &shutdown).await }
+        });
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(paused.load(Ordering::SeqCst));
+        assert!(!waiting.is_finished());
+
+        repository.down.store(false, Ordering::SeqCst);
+        assert!(waiting.await.unwrap());
+        assert!(!paused.load(Ordering::SeqCst));
+
+        // Repeated failed checks while paused publish a single event
+        let events = sink.events.lock().unwrap().clone();
+        let types: Vec<WorkerEventType> = events.iter().map(|event| event.event_type).collect();
+        assert_eq!(types, [WorkerEventType::Paused, WorkerEventType::Resumed]);
+        assert!(
+            events[0]
+                .reason
+                .as_deref()
+                .unwrap()
+                .contains("object repository")
+        );
+
+        // A worker shut down while paused stops waiting
+        repository.down.store(true, Ordering::SeqCst);
+        shutdown.cancel();
+        assert!(!readiness.wait_until_ready("test", &paused, &shutdown).await);
+    }
+}
@@ -2,19 +2,25 @@ use crate::{
     domain::{
         errors::{StorageError, StorageResult},
         models::{
-            CreateObjectRequest, DeleteVersionRequest, DeleteVersionResult, GetObjectRequest,
-            ObjectMetadata, ObjectVersionInfo, ObjectVersionList, VersionedObject,
-            VersioningConfiguration,
+            BulkDeleteVersionsRequest, BulkDeleteVersionsResult, CreateObjectRequest,
+            DATASET_MANIFEST_CONTENT_TYPE, DatasetEntry, DatasetManifest, DeleteVersionRequest,
+            DeleteVersionResult, GetObjectRequest, LabelVersionRequest, ObjectDelta, ObjectMetadata,
+            ObjectSignature, ObjectVersionInfo, ObjectVersionList, ObjectVersionPage, RollbackVersionResult,
+            VersionDeletionOutcome, VersionOrder, VersionRetentionPolicy, VersionSelection,
+            VersionedObject, VersioningConfiguration,
         },
         value_objects::{BucketName, ObjectKey, VersionId},
     },
     ports::{
         repositories::ObjectRepository,
+        runtime::{Clock, IdGenerator, SystemClock, UuidGenerator, VersionIdGenerator},
         services::{MetadataChange, VersionComparison, VersioningService},
         storage::VersionedObjectStore,
     },
 };
 use async_trait::async_trait;
+use std::collections::HashMap;
+use std::ops::Range;
 use std::sync::Arc;
 
 /// Implementation of versioning service
@@ -24,6 +30,9 @@ pub struct VersioningServiceImpl {
     store: Arc<dyn VersionedObjectStore>,
     versioning_configs:
         Arc<tokio::sync::RwLock<std::collections::HashMap<BucketName, VersioningConfiguration>>>,
+    clock: Arc<dyn Clock>,
+    version_ids: Arc<dyn VersionIdGenerator>,
+    governance_bypass_allowed: bool,
 }
 
 impl VersioningServiceImpl {
@@ -37,8 +46,63 @@ impl VersioningServiceImpl {
             versioning_configs: Arc::new(
                 tokio::sync::RwLock::new(std::collections::HashMap::new()),
             ),
+            clock: Arc::new(SystemClock),
+            version_ids: Arc::new(UuidGenerator),
+            governance_bypass_allowed: false,
         }
     }
+
+    /// Timestamp new versions with `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Draw version IDs from `version_ids` instead of random UUIDs
+    ///
+    /// With a time-ordered generator, version listings are paginated by ID.
+    pub fn with_version_id_generator(mut self, version_ids: Arc<dyn VersionIdGenerator>) -> Self {
+        self.version_ids = version_ids;
+        self
+    }
+
+    /// Honour requests to bypass governance retention
+    ///
+    /// Off by default, so governance retention protects versions like
+    /// compliance retention until callers are trusted to override it.
+    pub fn with_governance_bypass(mut self, allowed: bool) -> Self {
+        self.governance_bypass_allowed = allowed;
+        self
+    }
+
+    /// The version a read asks for, the latest unless it names one, with
+    /// its metadata
+    async fn resolve_version(
+        &self,
+        request: &GetObjectRequest,
+    ) -> StorageResult<(VersionId, ObjectMetadata)> {
+        let version_id = match &request.version_id {
+            Some(v) => v.clone(),
+            None => self
+                .repository
+                .get_latest_version_id(&request.key)
+                .await?
+                .ok_or_else(|| StorageError::ObjectNotFound {
+                    key: request.key.clone(),
+                })?,
+        };
+
+        let metadata = self
+            .repository
+            .get_object_metadata(&request.key, Some(&version_id))
+            .await?
+            .ok_or_else(|| StorageError::VersionNotFound {
+                key: request.key.clone(),
+                version_id: version_id.clone(),
+            })?;
+
+        Ok((version_id, metadata))
+    }
 }
 
 #[async_trait]
@@ -56,10 +120,10 @@ impl VersioningService for VersioningServiceImpl {
     }
 
     async fn disable_versioning(&self, bucket: &BucketName) -> StorageResult<()> {
+        // Buckets without a configuration are versioned by default, so
+        // suspending one has to record it
         let mut configs = self.versioning_configs.write().await;
-        if let Some(config) = configs.get_mut(bucket) {
-            config.enabled = false;
-        }
+        configs.entry(bucket.clone()).or_default().enabled = false;
         Ok(())
     }
 
@@ -71,29 +135,52 @@ impl VersioningService for VersioningServiceImpl {
         Ok(configs.get(bucket).cloned().unwrap_or_default())
     }
 
+    async fn has_versioning_configuration(&self, bucket: &BucketName) -> StorageResult<bool> {
+        Ok(self.versioning_configs.read().await.contains_key(bucket))
+    }
+
     async fn create_versioned_object(
         &self,
         request: CreateObjectRequest,
     ) -> StorageResult<VersionedObject> {
-        // Generate new version ID
-        let version_id = VersionId::generate();
+        // Generate new version ID, unless deferring to the one the store assigns
+        let requested = self
+            .version_ids
+            .next_version_id()
+            .map(VersionId::new)
+            .transpose()
+            .map_err(|e| StorageError::ValidationError {
+                message: e.to_string(),
+            })?;
 
         // Store versioned object
-        self.store
-            .put_versioned_object(
+        let info = self
+            .store
+            .put_object_version(
                 &request.key,
-                &version_id,
-                request.data.clone(),
+                requested.as_ref(),
+                request.data.clone().into(),
                 request.content_type.as_deref(),
             )
             .await?;
 
+        let version_id = match requested {
+            Some(version_id) => version_id,
+            None => VersionId::new(
+                info.version_id
+                    .unwrap_or_else(|| UuidGenerator.next_id()),
+            )
+            .map_err(|e| StorageError::ValidationError {
+                message: e.to_string(),
+            })?,
+        };
+
         // Create metadata
         let metadata = ObjectMetadata {
             content_type: request.content_type.clone(),
             content_length: request.data.len() as u64,
             etag: Some(self.calculate_etag(&request.data)),
-            last_modified: std::time::SystemTime::now(),
+            last_modified: self.clock.system_time(),
             custom_metadata: request.custom_metadata.clone(),
         };
 
@@ -122,32 +209,14 @@ impl VersioningService for VersioningServiceImpl {
     }
 
     async fn get_object(&self, request: GetObjectRequest) -> StorageResult<VersionedObject> {
-        let version_id = match request.version_id {
-            Some(v) => v,
-            None => self
-                .repository
-                .get_latest_version_id(&request.key)
-                .await?
-                .ok_or_else(|| StorageError::ObjectNotFound {
-                    key: request.key.clone(),
-                })?,
-        };
-
-        // Get metadata
-        let metadata = self
-            .repository
-            .get_object_metadata(&request.key, Some(&version_id))
-            .await?
-            .ok_or_else(|| StorageError::VersionNotFound {
-                key: request.key.clone(),
-                version_id: version_id.clone(),
-            })?;
+        let (version_id, metadata) = self.resolve_version(&request).await?;
 
         // Get object data
         let data = self
             .store
-            .get_versioned_object(&request.key, &version_id)
-            .await?;
+            .get_object_version(&request.key, &version_id)
+            .await?
+            .to_vec();
 
         // Check if this is the latest version
         let latest_version = self.repository.get_latest_version_id(&request.key).await?;
@@ -163,10 +232,69 @@ impl VersioningService for VersioningServiceImpl {
         })
     }
 
+    async fn head_object(
+        &self,
+        request: GetObjectRequest,
+    ) -> StorageResult<(VersionId, ObjectMetadata)> {
+        self.resolve_version(&request).await
+    }
+
+    async fn get_object_stream(
+        &self,
+        request: GetObjectRequest,
+        range: Option<Range<u64>>,
+    ) -> StorageResult<(
+        VersionId,
+        ObjectMetadata,
+        Box<dyn tokio::io::AsyncRead + Send + Unpin>,
+    )> {
+        let (version_id, metadata) = self.resolve_version(&request).await?;
+        let reader = self
+            .store
+            .get_object_version_stream(&request.key, &version_id, range)
+            .await?;
+
+        Ok((version_id, metadata, reader))
+    }
+
     async fn list_versions(&self, key: &ObjectKey) -> StorageResult<ObjectVersionList> {
         self.repository.list_object_versions(key).await
     }
 
+    async fn list_versions_paginated(
+        &self,
+        key: &ObjectKey,
+        marker: Option<&VersionId>,
+        limit: usize,
+    ) -> StorageResult<ObjectVersionPage> {
+        let order = match self.version_ids.is_time_ordered() {
+            true => VersionOrder::VersionId,
+            false => VersionOrder::WriteTime,
+        };
+        self.repository
+            .list_object_versions_page(key, marker, limit, order)
+            .await?
+            .ok_or_else(|| StorageError::ValidationError {
+                message: format!(
+                    "Version ID marker {} is not a version of {}",
+                    marker.map(VersionId::as_str).unwrap_or_default(),
+                    key
+                ),
+            })
+    }
+
+    async fn list_labeled_versions(
+        &self,
+        key: &ObjectKey,
+        label: &str,
+    ) -> StorageResult<ObjectVersionList> {
+        Ok(self
+            .repository
+            .list_object_versions(key)
+            .await?
+            .with_label(label))
+    }
+
     async fn get_version_info(
         &self,
         key: &ObjectKey,
@@ -185,6 +313,20 @@ impl VersioningService for VersioningServiceImpl {
         &self,
         request: DeleteVersionRequest,
     ) -> StorageResult<DeleteVersionResult> {
+        if let Some(reason) = self
+            .deletion_blocked_reason(
+                &request.key,
+                &request.version_id,
+                request.bypass_governance_retention,
+            )
+            .await?
+        {
+            return Err(StorageError::AccessDenied {
+                key: request.key,
+                operation: format!("delete version {}: {}", request.version_id, reason),
+            });
+        }
+
         // Mark version as deleted
         self.repository
             .mark_version_deleted(&request.key, &request.version_id)
@@ -202,6 +344,60 @@ impl VersioningService for VersioningServiceImpl {
         })
     }
 
+    async fn delete_versions(
+        &self,
+        request: BulkDeleteVersionsRequest,
+    ) -> StorageResult<BulkDeleteVersionsResult> {
+        let versions = self.list_versions(&request.key).await?.versions;
+        let live = || versions.iter().filter(|version| !version.deleted);
+
+        let selected: Vec<(VersionId, Option<String>)> = match &request.selection {
+            VersionSelection::OlderThan(cutoff) => {
+                let cutoff: std::time::SystemTime = (*cutoff).into();
+                live()
+                    .filter(|version| !version.is_latest && version.last_modified < cutoff)
+                    .map(|version| (version.version_id.clone(), None))
+                    .collect()
+            }
+            VersionSelection::Noncurrent => live()
+                .filter(|version| !version.is_latest)
+                .map(|version| (version.version_id.clone(), None))
+                .collect(),
+            VersionSelection::Explicit(version_ids) => version_ids
+                .iter()
+                .map(|version_id| {
+                    let missing = !live().any(|version| &version.version_id == version_id);
+                    (
+                        version_id.clone(),
+                        missing.then(|| "Version not found".to_string()),
+                    )
+                })
+                .collect(),
+        };
+
+        let mut outcomes = Vec::with_capacity(selected.len());
+        for (version_id, error) in selected {
+            let error = match error {
+                Some(error) => Some(error),
+                None => self
+                    .delete_version(DeleteVersionRequest {
+                        key: request.key.clone(),
+                        version_id: version_id.clone(),
+                        bypass_governance_retention: request.bypass_governance_retention,
+                    })
+                    .await
+                    .err()
+                    .map(|e| e.to_string()),
+            };
+            outcomes.push(VersionDeletionOutcome { version_id, error });
+        }
+
+        Ok(BulkDeleteVersionsResult {
+            key: request.key,
+            outcomes,
+        })
+    }
+
     async fn restore_version(
         &self,
         key: &ObjectKey,
@@ -225,6 +421,34 @@ impl VersioningService for VersioningServiceImpl {
         .await
     }
 
+    async fn rollback_version(
+        &self,
+        key: &ObjectKey,
+        steps: usize,
+    ) -> StorageResult<RollbackVersionResult> {
+        if steps == 0 {
+            return Err(StorageError::ValidationError {
+                message: "Rollback must go back at least one version".to_string(),
+            });
+        }
+
+        let target = self
+            .repository
+            .list_object_versions(key)
+            .await?
+            .previous_version(steps)
+            .ok_or_else(|| StorageError::ValidationError {
+                message: format!("{} has fewer than {} earlier versions", key, steps),
+            })?;
+
+        let restored = self.restore_version(key, &target.version_id).await?;
+        Ok(RollbackVersionResult {
+            key: key.clone(),
+            restored_version_id: target.version_id,
+            new_version_id: restored.version_id,
+        })
+    }
+
     async fn compare_versions(
         &self,
         key: &ObjectKey,
@@ -268,17 +492,27 @@ impl VersioningService for VersioningServiceImpl {
         let mut versions = version_list.versions;
 
         // Sort by last modified, newest first
-        versions.sort_by(|a, b| b.last_modified.cmp(&a.last_modified));
+        versions.sort_by_key(|version| std::cmp::Reverse(version.last_modified));
 
         // Keep only the specified number of versions
         let mut deleted_versions = Vec::new();
 
         if versions.len() > keep_count {
             for version in versions.iter().skip(keep_count) {
+                // Pinned and retained versions are never pruned
+                if self
+                    .deletion_blocked_reason(key, &version.version_id, false)
+                    .await?
+                    .is_some()
+                {
+                    continue;
+                }
+
                 // Delete the version
                 self.delete_version(DeleteVersionRequest {
                     key: key.clone(),
                     version_id: version.version_id.clone(),
+                    bypass_governance_retention: false,
                 })
                 .await?;
 
@@ -289,6 +523,69 @@ impl VersioningService for VersioningServiceImpl {
         Ok(deleted_versions)
     }
 
+    async fn label_version(&self, request: LabelVersionRequest) -> StorageResult<()> {
+        request
+            .validate()
+            .map_err(|e| StorageError::ValidationError {
+                message: e.to_string(),
+            })?;
+
+        self.repository
+            .modify_object_metadata(&request.key, &request.version_id, &|metadata| {
+                metadata.set_labels(&request.labels);
+                metadata.set_annotations(&request.annotations);
+                Ok(())
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn set_version_pinned(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+        pinned: bool,
+    ) -> StorageResult<()> {
+        self.repository
+            .modify_object_metadata(key, version_id, &|metadata| {
+                metadata.set_pinned(pinned);
+                Ok(())
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn set_version_retention(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+        retention: Option<VersionRetentionPolicy>,
+        bypass_governance: bool,
+    ) -> StorageResult<()> {
+        let bypass_governance = bypass_governance && self.governance_bypass_allowed;
+        let now = self.clock.system_time();
+        // The lock is checked against the metadata being replaced, so a
+        // retention set concurrently by another instance is never bypassed
+        self.repository
+            .modify_object_metadata(key, version_id, &|metadata| {
+                if let Some(reason) = metadata.retention().and_then(|current| {
+                    current.replacement_blocked_reason(retention.as_ref(), now, bypass_governance)
+                }) {
+                    return Err(StorageError::AccessDenied {
+                        key: key.clone(),
+                        operation: format!(
+                            "change retention of version {}: {}",
+                            version_id, reason
+                        ),
+                    });
+                }
+                metadata.set_retention(retention.as_ref());
+                Ok(())
+            })
+            .await?;
+        Ok(())
+    }
+
     /// Copy a specific version to a new location
     async fn copy_version(
         &self,
@@ -297,18 +594,171 @@ impl VersioningService for VersioningServiceImpl {
         destination_key: &ObjectKey,
     ) -> StorageResult<VersionId> {
         // Use the versioned store's copy_object_version method
-        let object_info = self.store
+        let object_info = self
+            .store
             .copy_object_version(source_key, source_version, destination_key)
             .await?;
-        
+
         // Extract version ID from the result
-        Ok(VersionId::new(object_info.version_id.unwrap_or_else(|| "latest".to_string())).unwrap())
+        Ok(VersionId::new(
+            object_info
+                .version_id
+                .unwrap_or_else(|| "latest".to_string()),
+        )
+        .unwrap())
     }
 
     /// Check if a specific version exists
     async fn version_exists(&self, key: &ObjectKey, version_id: &VersionId) -> StorageResult<bool> {
         self.store.version_exists(key, version_id).await
     }
+
+    async fn version_signature(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+        block_size: u64,
+    ) -> StorageResult<ObjectSignature> {
+        let version = self
+            .get_object(GetObjectRequest {
+                key: key.clone(),
+                version_id: Some(version_id.clone()),
+            })
+            .await?;
+
+        ObjectSignature::compute(&version.data, block_size).map_err(|e| {
+            StorageError::ValidationError {
+                message: e.to_string(),
+            }
+        })
+    }
+
+    async fn create_version_from_delta(
+        &self,
+        key: &ObjectKey,
+        base_version: &VersionId,
+        delta: ObjectDelta,
+    ) -> StorageResult<VersionedObject> {
+        let base = self
+            .get_object(GetObjectRequest {
+                key: key.clone(),
+                version_id: Some(base_version.clone()),
+            })
+            .await?;
+        let data = delta
+            .apply(&base.data)
+            .map_err(|e| StorageError::ValidationError {
+                message: format!("Delta does not apply to version {}: {}", base_version, e),
+            })?;
+
+        // The pin, retention, labels and annotations belong to the base version alone
+        let mut metadata = base.metadata;
+        metadata.set_pinned(false);
+        metadata.set_retention(None);
+        metadata.set_labels(&Default::default());
+        metadata.set_annotations(&HashMap::new());
+
+        self.create_versioned_object(CreateObjectRequest {
+            key: key.clone(),
+            data,
+            content_type: metadata.content_type,
+            custom_metadata: metadata.custom_metadata,
+        })
+        .await
+    }
+
+    async fn publish_dataset(
+        &self,
+        name: &str,
+        objects: Vec<(ObjectKey, Option<VersionId>)>,
+    ) -> StorageResult<DatasetManifest> {
+        let manifest_key =
+            DatasetManifest::manifest_key(name).map_err(|e| StorageError::ValidationError {
+                message: e.to_string(),
+            })?;
+        if objects.is_empty() {
+            return Err(StorageError::ValidationError {
+                message: format!("Dataset {} must contain at least one object", name),
+            });
+        }
+
+        let mut entries: Vec<DatasetEntry> = Vec::with_capacity(objects.len());
+        for (key, version_id) in objects {
+            if entries.iter().any(|entry| entry.key == key) {
+                return Err(StorageError::ValidationError {
+                    message: format!("{} is listed more than once in dataset {}", key, name),
+                });
+            }
+            let version_id = match version_id {
+                Some(version_id) => version_id,
+                None => self
+                    .repository
+                    .get_latest_version_id(&key)
+                    .await?
+                    .ok_or_else(|| StorageError::ObjectNotFound { key: key.clone() })?,
+            };
+            entries.push(self.resolve_dataset_entry(key, version_id).await?);
+        }
+
+        let manifest = self
+            .create_versioned_object(CreateObjectRequest {
+                key: manifest_key,
+                data: DatasetManifest::encode_entries(&entries),
+                content_type: Some(DATASET_MANIFEST_CONTENT_TYPE.to_string()),
+                custom_metadata: HashMap::new(),
+            })
+            .await?;
+
+        Ok(DatasetManifest {
+            name: name.to_string(),
+            manifest_version: manifest.version_id,
+            published: manifest.metadata.last_modified.into(),
+            entries,
+        })
+    }
+
+    async fn get_dataset(
+        &self,
+        name: &str,
+        manifest_version: Option<&VersionId>,
+    ) -> StorageResult<DatasetManifest> {
+        let manifest_key =
+            DatasetManifest::manifest_key(name).map_err(|e| StorageError::ValidationError {
+                message: e.to_string(),
+            })?;
+        let manifest = self
+            .get_object(GetObjectRequest {
+                key: manifest_key,
+                version_id: manifest_version.cloned(),
+            })
+            .await
+            .map_err(|e| match e {
+                StorageError::ObjectNotFound { .. } => StorageError::DatasetNotFound {
+                    name: name.to_string(),
+                },
+                e => e,
+            })?;
+
+        let stored = DatasetManifest::decode_entries(&manifest.data).map_err(|e| {
+            StorageError::InternalError {
+                message: format!("Manifest of dataset {} is corrupt: {}", name, e),
+            }
+        })?;
+        let mut entries = Vec::with_capacity(stored.len());
+        for entry in stored {
+            entries.push(
+                self.resolve_dataset_entry(entry.key, entry.version_id)
+                    .await?,
+            );
+        }
+
+        Ok(DatasetManifest {
+            name: name.to_string(),
+            manifest_version: manifest.version_id,
+            published: manifest.metadata.last_modified.into(),
+            entries,
+        })
+    }
 }
 
 impl VersioningServiceImpl {
@@ -316,6 +766,43 @@ impl VersioningServiceImpl {
         format!("{:x}", md5::compute(data))
     }
 
+    /// Why a version may not be deleted now, None if it may
+    ///
+    /// Governance retention is only bypassed when this service allows it.
+    async fn deletion_blocked_reason(
+        &self,
+        key: &ObjectKey,
+        version_id: &VersionId,
+        bypass_governance: bool,
+    ) -> StorageResult<Option<String>> {
+        let bypass_governance = bypass_governance && self.governance_bypass_allowed;
+        Ok(self
+            .repository
+            .get_object_metadata(key, Some(version_id))
+            .await?
+            .and_then(|metadata| {
+                metadata.deletion_blocked_reason(self.clock.system_time(), bypass_governance)
+            }))
+    }
+
+    /// Entry for a version of a dataset, failing if the version is gone
+    async fn resolve_dataset_entry(
+        &self,
+        key: ObjectKey,
+        version_id: VersionId,
+    ) -> StorageResult<DatasetEntry> {
+        let info = self.get_version_info(&key, &version_id).await?;
+        if info.deleted {
+            return Err(StorageError::VersionNotFound { key, version_id });
+        }
+        Ok(DatasetEntry {
+            key,
+            version_id,
+            size: Some(info.size),
+            etag: info.etag,
+        })
+    }
+
     fn extract_bucket_from_key(&self, _key: &ObjectKey) -> Option<BucketName> {
         // In a real implementation, this would extract bucket from key
         // For now, return None
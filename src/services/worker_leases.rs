@@ -0,0 +1,186 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{Instant, interval_at};
+
+use crate::domain::errors::StorageError;
+use crate::ports::repositories::LeaseRepository;
+
+/// Time a lease is taken for while its work runs, renewed every third of it
+pub const LEASE_TTL: Duration = Duration::from_secs(30);
+
+/// Why work guarded by a lease did not run
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum LeaseError {
+    /// Another instance holds the lease for the current cycle
+    #[error("lease {0} is held by another instance")]
+    HeldByAnotherInstance(String),
+
+    /// The lease repository could not be reached
+    #[error("failed to take lease {name}: {source}")]
+    Repository { name: String, source: StorageError },
+}
+
+/// Leases sharing background work between server instances
+///
+/// Before a worker runs a piece of work, such as one bucket's lifecycle pass,
+/// it takes the work's lease for [`LEASE_TTL`], renewing it while the work
+/// runs, so the lease of an instance that stops half-way soon lapses. Once the
+/// work is done the lease is kept until the end of the cycle the work belongs
+/// to, but never past it, so an instance whose schedule fires moments after
+/// another's skips the work rather than repeating it.
+#[derive(Clone)]
+pub struct WorkerLeases {
+    repository: Arc<dyn LeaseRepository>,
+    holder: String,
+}
+
+impl WorkerLeases {
+    /// Take leases in `repository` as `holder`, which must differ between
+    /// the instances sharing the repository
+    pub fn new(repository: Arc<dyn LeaseRepository>, holder: impl Into<String>) -> Self {
+        Self {
+            repository,
+            holder: holder.into(),
+        }
+    }
+
+    /// Name the leases of this instance are taken under
+    pub fn holder(&self) -> &str {
+        &self.holder
+    }
+
+    /// Run `work` if this instance gets the lease `name` for the `cycle`
+    /// starting now
+    ///
+    /// `work` doesn't run when another instance holds the lease, or when the
+    /// repository could not be reached.
+    pub async fn run_exclusive<T>(
+        &self,
+        name: &str,
+        cycle: Duration,
+        work: impl Future<Output = T>,
+    ) -> Result<T, LeaseError> {
+        let cycle_end = Instant::now() + cycle;
+        match self
+            .repository
+            .try_acquire_lease(name, &self.holder, LEASE_TTL)
+            .await
+        {
+            Ok(true) => {}
+            Ok(false) => return Err(LeaseError::HeldByAnotherInstance(name.to_string())),
+            Err(source) => {
+                tracing::warn!(lease = name, error = %source, "Failed to take lease");
+                return Err(LeaseError::Repository {
+                    name: name.to_string(),
+                    source,
+                });
+            }
+        }
+
+        tokio::pin!(work);
+        // Renewed well before it expires, so a slow renewal doesn't let it lapse
+        let period = LEASE_TTL / 3;
+        let mut renewal = interval_at(Instant::now() + period, period);
+        let output = loop {
+            tokio::select! {
+                output = &mut work => break output,
+                _ = renewal.tick() => self.renew(name, LEASE_TTL).await,
+            }
+        };
+
+        // Keep the other instances off the work for the rest of the cycle
+        let rest_of_cycle = cycle_end.saturating_duration_since(Instant::now());
+        if !rest_of_cycle.is_zero() {
+            self.renew(name, rest_of_cycle).await;
+        }
+        Ok(output)
+    }
+
+    /// Extend a lease this instance holds by `ttl` from now
+    ///
+    /// The work goes on when the lease is lost: the passes of the workers
+    /// are safe to repeat, so another instance running one too only wastes
+    /// effort.
+    async fn renew(&self, name: &str, ttl: Duration) {
+        match self
+            .repository
+            .try_acquire_lease(name, &self.holder, ttl)
+            .await
+        {
+            Ok(true) => {}
+            Ok(false) => tracing::warn!(lease = name, "Lease was taken over by another instance"),
+            Err(e) => tracing::warn!(lease = name, error = %e, "Failed to renew lease"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::outbound::persistence::InMemoryLeaseRepository;
+
+    #[tokio::test]
+    async fn test_work_runs_on_one_instance_per_cycle() {
+        let repository = Arc::new(InMemoryLeaseRepository::new());
+        let first = WorkerLeases::new(repository.clone(), "first");
+        let second = WorkerLeases::new(repository, "second");
+        let cycle = Duration::from_secs(3600);
+
+        assert_eq!(
+            first
+                .run_exclusive("lifecycle/a", cycle, async { 1 })
+                .await
+                .unwrap(),
+            1
+        );
+        // The lease outlives the work, so the second instance skips it
+        assert!(matches!(
+            second.run_exclusive("lifecycle/a", cycle, async { 2 }).await,
+            Err(LeaseError::HeldByAnotherInstance(name)) if name == "lifecycle/a"
+        ));
+        assert_eq!(
+            second
+                .run_exclusive("lifecycle/b", cycle, async { 3 })
+                .await
+                .unwrap(),
+            3
+        );
+        // A triggered pass on the holding instance still runs
+        assert_eq!(
+            first
+                .run_exclusive("lifecycle/a", cycle, async { 4 })
+                .await
+                .unwrap(),
+            4
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lease_ends_with_the_cycle() {
+        let repository = Arc::new(InMemoryLeaseRepository::new());
+        let first = WorkerLeases::new(repository.clone(), "first");
+        let second = WorkerLeases::new(repository, "second");
+        let cycle = Duration::from_millis(100);
+
+        first
+            .run_exclusive("backup", cycle, async {})
+            .await
+            .unwrap();
+        assert!(
+            second
+                .run_exclusive("backup", cycle, async {})
+                .await
+                .is_err()
+        );
+
+        // The next cycle is free for whichever instance gets there first
+        tokio::time::sleep(cycle).await;
+        assert!(
+            second
+                .run_exclusive("backup", cycle, async {})
+                .await
+                .is_ok()
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use bytes::Bytes;
 use object_store_server::{
-    BucketName, ObjectKey, create_in_memory_app,
+    ObjectKey, create_in_memory_app,
     domain::models::{CreateObjectRequest, GetObjectRequest},
     ports::services::ObjectService,
 };
@@ -22,7 +22,7 @@ async fn basic_put_get() {
         custom_metadata: HashMap::new(),
     };
 
-    let put_result = services
+    let (put_result, _) = services
         .object_service
         .create_object(create_request)
         .await
@@ -43,6 +43,7 @@ async fn basic_put_get() {
     assert_eq!(result.data, data.to_vec());
     assert_eq!(result.key, key);
     assert_eq!(result.metadata.content_length, data.len() as u64);
+    assert_eq!(result.metadata.etag, put_result.metadata.etag);
 }
 
 #[tokio::test]
@@ -195,7 +196,7 @@ async fn test_copy_object() {
         .unwrap();
 
     // Copy the object
-    let copied = services
+    let (copied, _) = services
         .object_service
         .copy_object(&source_key, &dest_key)
         .await
@@ -1,18 +1,28 @@
+use axum::http::Method;
 use axum_test::TestServer;
 use bytes::Bytes;
 use chrono::{Duration, Utc};
 use object_store_server::{
-    BucketName, ObjectKey, VersionId,
-    adapters::inbound::http::router::{AppState, create_router},
+    AppBuilder, BucketName, ObjectKey, StorageError, VersionId,
+    adapters::inbound::http::{
+        dto::{ObjectDeltaDto, ObjectSignatureDto},
+        router::{AppState, create_router},
+    },
     create_in_memory_app,
     domain::models::{
-        CreateObjectRequest, Filter, GetObjectRequest, LifecycleConfiguration, LifecycleRule,
+        BulkDeleteVersionsRequest, CreateObjectRequest, DeleteVersionRequest, Filter,
+        GetObjectRequest, LabelVersionRequest, LifecycleConfiguration, LifecycleRule, ObjectDelta,
+        PrefixQuota, RetentionMode, TextQuery, VersionRetentionPolicy, VersionSelection,
         lifecycle::{RuleStatus, StorageClass},
     },
-    ports::services::{LifecycleService, ObjectService, VersioningService},
+    ports::{
+        runtime::Clock,
+        services::{LifecycleService, ObjectService, VersioningService},
+    },
+    testing::{TestMode, create_test_app},
 };
 use serde_json::json;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::sync::Arc;
 
 async fn setup_test_server() -> TestServer {
@@ -102,6 +112,7 @@ async fn test_versioning_integration() {
         .unwrap();
 
     assert_eq!(latest.data, v2_content.to_vec());
+    assert_eq!(latest.version_id, v2.version_id);
 }
 
 #[tokio::test]
@@ -163,22 +174,17 @@ async fn test_lifecycle_integration() {
 async fn test_http_object_operations() {
     let server = setup_test_server().await;
 
-    // Create a bucket
-    let create_bucket = server.put("/buckets/test-bucket").await;
-
-    assert_eq!(create_bucket.status_code(), 200);
-
-    // Upload an object
+    // Upload an object; buckets exist once they hold objects
     let upload = server
-        .put("/buckets/test-bucket/test-file.txt")
+        .put("/objects/test-bucket/test-file.txt")
         .content_type("text/plain")
         .text("Hello, World!")
         .await;
 
-    assert_eq!(upload.status_code(), 200);
+    assert_eq!(upload.status_code(), 201);
 
     // Get the object
-    let get = server.get("/buckets/test-bucket/test-file.txt").await;
+    let get = server.get("/objects/test-bucket/test-file.txt").await;
 
     assert_eq!(get.status_code(), 200);
     assert_eq!(get.text(), "Hello, World!");
@@ -187,16 +193,15 @@ async fn test_http_object_operations() {
     let list = server.get("/buckets/test-bucket").await;
 
     assert_eq!(list.status_code(), 200);
-    let list_json: serde_json::Value = list.json();
-    assert!(list_json["objects"].as_array().unwrap().len() > 0);
+    assert!(list.text().contains("<Key>test-file.txt</Key>"));
 
     // Delete the object
-    let delete = server.delete("/buckets/test-bucket/test-file.txt").await;
+    let delete = server.delete("/objects/test-bucket/test-file.txt").await;
 
-    assert_eq!(delete.status_code(), 204);
+    assert_eq!(delete.status_code(), 200);
 
     // Verify it's gone
-    let get_after_delete = server.get("/buckets/test-bucket/test-file.txt").await;
+    let get_after_delete = server.get("/objects/test-bucket/test-file.txt").await;
 
     assert_eq!(get_after_delete.status_code(), 404);
 }
@@ -205,9 +210,6 @@ async fn test_http_object_operations() {
 async fn test_http_versioning_operations() {
     let server = setup_test_server().await;
 
-    // Create a bucket
-    server.put("/buckets/versioned-bucket").await;
-
     // Enable versioning
     let enable_versioning = server
         .put("/buckets/versioned-bucket/versioning")
@@ -218,7 +220,7 @@ async fn test_http_versioning_operations() {
 
     // Upload first version
     let v1 = server
-        .put("/buckets/versioned-bucket/doc.txt")
+        .put("/versioned-objects/versioned-bucket/doc.txt")
         .text("version 1")
         .await;
 
@@ -228,7 +230,7 @@ async fn test_http_versioning_operations() {
 
     // Upload second version
     let v2 = server
-        .put("/buckets/versioned-bucket/doc.txt")
+        .put("/versioned-objects/versioned-bucket/doc.txt")
         .text("version 2")
         .await;
 
@@ -236,7 +238,7 @@ async fn test_http_versioning_operations() {
 
     // List versions
     let versions = server
-        .get("/buckets/versioned-bucket/doc.txt/versions")
+        .get("/versioned-objects/versioned-bucket/doc.txt/versions")
         .await;
 
     assert_eq!(versions.status_code(), 200);
@@ -246,24 +248,35 @@ async fn test_http_versioning_operations() {
     // Get specific version
     let get_v1 = server
         .get(&format!(
-            "/buckets/versioned-bucket/doc.txt?version_id={}",
+            "/versioned-objects/versioned-bucket/doc.txt/versions/{}",
             v1_id
         ))
         .await;
 
     assert_eq!(get_v1.status_code(), 200);
     assert_eq!(get_v1.text(), "version 1");
+
+    // Ranged reads of a version return only the requested bytes
+    let range_v1 = server
+        .get(&format!(
+            "/versioned-objects/versioned-bucket/doc.txt/versions/{}",
+            v1_id
+        ))
+        .add_header("range", "bytes=8-")
+        .await;
+
+    assert_eq!(range_v1.status_code(), 206);
+    assert_eq!(range_v1.header("content-range"), "bytes 8-8/9");
+    assert_eq!(range_v1.text(), "1");
 }
 
 #[tokio::test]
 async fn test_http_lifecycle_operations() {
     let server = setup_test_server().await;
 
-    // Create a bucket
-    server.put("/buckets/lifecycle-bucket").await;
-
     // Set lifecycle configuration
     let lifecycle_config = json!({
+        "bucket": "lifecycle-bucket",
         "rules": [
             {
                 "id": "expire-temp",
@@ -271,9 +284,7 @@ async fn test_http_lifecycle_operations() {
                 "filter": {
                     "prefix": "temp/"
                 },
-                "expiration": {
-                    "days": 7
-                }
+                "expiration_days": 7
             }
         ]
     });
@@ -295,7 +306,60 @@ async fn test_http_lifecycle_operations() {
     // Delete lifecycle configuration
     let delete_lifecycle = server.delete("/buckets/lifecycle-bucket/lifecycle").await;
 
-    assert_eq!(delete_lifecycle.status_code(), 204);
+    assert_eq!(delete_lifecycle.status_code(), 200);
+
+    let after_delete = server.get("/buckets/lifecycle-bucket/lifecycle").await;
+    assert_eq!(after_delete.status_code(), 404);
+}
+
+#[tokio::test]
+async fn test_http_lifecycle_validation() {
+    let server = setup_test_server().await;
+
+    let rule = |id: &str, days: u32| {
+        json!({
+            "id": id,
+            "status": "Enabled",
+            "filter": { "prefix": "temp/" },
+            "expiration_days": days
+        })
+    };
+
+    server
+        .put("/buckets/validate-bucket/lifecycle")
+        .json(&json!({
+            "bucket": "validate-bucket",
+            "rules": [rule("keep", 7), rule("change", 7), rule("drop", 7)]
+        }))
+        .await;
+
+    let validation = server
+        .post("/buckets/validate-bucket/lifecycle/validate")
+        .json(&json!({
+            "bucket": "validate-bucket",
+            "rules": [rule("keep", 7), rule("change", 30), rule("new", 1)]
+        }))
+        .await;
+
+    assert_eq!(validation.status_code(), 200);
+    let result: serde_json::Value = validation.json();
+    assert_eq!(result["is_valid"], true);
+    assert_eq!(result["diff"]["has_active_configuration"], true);
+    assert_eq!(result["diff"]["added"][0]["id"], "new");
+    assert_eq!(result["diff"]["removed"][0]["id"], "drop");
+    assert_eq!(result["diff"]["unchanged"], json!(["keep"]));
+    assert_eq!(result["diff"]["modified"][0]["rule_id"], "change");
+    assert_eq!(
+        result["diff"]["modified"][0]["changes"],
+        json!([{ "field": "expiration_days", "before": 7, "after": 30 }])
+    );
+
+    // Nothing was applied
+    let active: serde_json::Value = server
+        .get("/buckets/validate-bucket/lifecycle")
+        .await
+        .json();
+    assert_eq!(active["rules"][1]["expiration_days"], 7);
 }
 
 #[tokio::test]
@@ -366,7 +430,7 @@ async fn test_copy_operations() {
     // Copy to new location
     let dest_key = ObjectKey::new("destination.txt".to_string()).unwrap();
 
-    let copied = services
+    let (copied, _) = services
         .object_service
         .copy_object(&source_key, &dest_key)
         .await
@@ -409,7 +473,7 @@ async fn test_metadata_operations() {
         custom_metadata: custom_metadata.clone(),
     };
 
-    let created = services
+    let (created, _) = services
         .object_service
         .create_object(create_request)
         .await
@@ -577,8 +641,8 @@ async fn test_versioning_with_delete_markers() {
         .await
         .unwrap();
 
-    // Should have at least the original version
-    assert!(versions.versions.len() >= 1);
+    // The original version and the delete marker
+    assert_eq!(versions.versions.len(), 2);
 
     // Try to get latest (should fail due to delete marker)
     let get_latest = GetObjectRequest {
@@ -604,3 +668,1358 @@ async fn test_versioning_with_delete_markers() {
 
     assert_eq!(retrieved.data, b"original content");
 }
+
+#[tokio::test]
+async fn test_deterministic_test_mode() {
+    let key = ObjectKey::new("reproducible.txt".to_string()).unwrap();
+    let create = || CreateObjectRequest {
+        key: key.clone(),
+        data: b"content".to_vec(),
+        content_type: Some("text/plain".to_string()),
+        custom_metadata: HashMap::new(),
+    };
+
+    // Two runs with the same mode settings produce identical versions
+    let mut runs = Vec::new();
+    for _ in 0..2 {
+        let mode = TestMode::default();
+        let services = create_test_app(mode.clone()).await.unwrap();
+        let version = services
+            .versioning_service
+            .create_versioned_object(create())
+            .await
+            .unwrap();
+        assert_eq!(
+            chrono::DateTime::<Utc>::from(version.metadata.last_modified),
+            mode.clock.now()
+        );
+        runs.push(version.version_id);
+    }
+    assert_eq!(runs[0], runs[1]);
+}
+
+#[tokio::test]
+async fn test_paginated_version_listing() {
+    let services = create_in_memory_app().await.unwrap();
+    let key = ObjectKey::new("many-versions.txt".to_string()).unwrap();
+
+    for i in 0..5 {
+        services
+            .versioning_service
+            .create_versioned_object(CreateObjectRequest {
+                key: key.clone(),
+                data: format!("version {}", i).into_bytes(),
+                content_type: Some("text/plain".to_string()),
+                custom_metadata: HashMap::new(),
+            })
+            .await
+            .unwrap();
+    }
+
+    // Walk the pages until the listing is no longer truncated
+    let mut seen = Vec::new();
+    let mut marker: Option<VersionId> = None;
+    loop {
+        let page = services
+            .versioning_service
+            .list_versions_paginated(&key, marker.as_ref(), 2)
+            .await
+            .unwrap();
+        assert_eq!(page.total_count, 5);
+        assert!(page.versions.len() <= 2);
+        seen.extend(page.versions.into_iter().map(|v| v.version_id));
+
+        if !page.is_truncated {
+            break;
+        }
+        marker = page.next_version_id_marker;
+    }
+
+    assert_eq!(seen.len(), 5);
+    let unique: std::collections::HashSet<_> = seen.iter().collect();
+    assert_eq!(unique.len(), 5);
+}
+
+#[tokio::test]
+async fn test_bulk_version_deletion() {
+    let services = create_in_memory_app().await.unwrap();
+    let key = ObjectKey::new("bulk.txt".to_string()).unwrap();
+
+    let mut version_ids = Vec::new();
+    for i in 0..4 {
+        let version = services
+            .versioning_service
+            .create_versioned_object(CreateObjectRequest {
+                key: key.clone(),
+                data: format!("version {}", i).into_bytes(),
+                content_type: None,
+                custom_metadata: HashMap::new(),
+            })
+            .await
+            .unwrap();
+        version_ids.push(version.version_id);
+    }
+
+    // An unknown version is reported without stopping the others
+    let unknown = VersionId::new("no-such-version".to_string()).unwrap();
+    let result = services
+        .versioning_service
+        .delete_versions(BulkDeleteVersionsRequest {
+            key: key.clone(),
+            selection: VersionSelection::Explicit(vec![version_ids[0].clone(), unknown]),
+            bypass_governance_retention: false,
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.outcomes.len(), 2);
+    assert_eq!(result.deleted_count(), 1);
+
+    // Only the latest version survives deleting the noncurrent ones
+    let result = services
+        .versioning_service
+        .delete_versions(BulkDeleteVersionsRequest {
+            key: key.clone(),
+            selection: VersionSelection::Noncurrent,
+            bypass_governance_retention: false,
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.deleted_count(), 2);
+
+    let remaining: Vec<_> = services
+        .versioning_service
+        .list_versions(&key)
+        .await
+        .unwrap()
+        .versions
+        .into_iter()
+        .filter(|version| !version.deleted)
+        .collect();
+    assert_eq!(remaining.len(), 1);
+    assert!(remaining[0].is_latest);
+}
+
+#[tokio::test]
+async fn test_version_pinning() {
+    let services = create_in_memory_app().await.unwrap();
+    let key = ObjectKey::new("pinned.txt".to_string()).unwrap();
+
+    let mut version_ids = Vec::new();
+    for i in 0..2 {
+        let version = services
+            .versioning_service
+            .create_versioned_object(CreateObjectRequest {
+                key: key.clone(),
+                data: format!("version {}", i).into_bytes(),
+                content_type: None,
+                custom_metadata: HashMap::new(),
+            })
+            .await
+            .unwrap();
+        version_ids.push(version.version_id);
+    }
+
+    services
+        .versioning_service
+        .set_version_pinned(&key, &version_ids[0], true)
+        .await
+        .unwrap();
+
+    // Neither single nor bulk deletion removes a pinned version
+    let result = services
+        .versioning_service
+        .delete_version(DeleteVersionRequest {
+            key: key.clone(),
+            version_id: version_ids[0].clone(),
+            bypass_governance_retention: false,
+        })
+        .await;
+    assert!(matches!(result, Err(StorageError::AccessDenied { .. })));
+
+    let result = services
+        .versioning_service
+        .delete_versions(BulkDeleteVersionsRequest {
+            key: key.clone(),
+            selection: VersionSelection::Noncurrent,
+            bypass_governance_retention: false,
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.deleted_count(), 0);
+
+    services
+        .versioning_service
+        .set_version_pinned(&key, &version_ids[0], false)
+        .await
+        .unwrap();
+    services
+        .versioning_service
+        .delete_version(DeleteVersionRequest {
+            key: key.clone(),
+            version_id: version_ids[0].clone(),
+            bypass_governance_retention: false,
+        })
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_version_labels() {
+    let services = create_in_memory_app().await.unwrap();
+    let key = ObjectKey::new("labeled.txt".to_string()).unwrap();
+
+    let mut version_ids = Vec::new();
+    for i in 0..3 {
+        let version = services
+            .versioning_service
+            .create_versioned_object(CreateObjectRequest {
+                key: key.clone(),
+                data: format!("version {}", i).into_bytes(),
+                content_type: None,
+                custom_metadata: HashMap::new(),
+            })
+            .await
+            .unwrap();
+        version_ids.push(version.version_id);
+    }
+
+    services
+        .versioning_service
+        .label_version(LabelVersionRequest {
+            key: key.clone(),
+            version_id: version_ids[1].clone(),
+            labels: BTreeSet::from(["release-1.4".to_string(), "approved".to_string()]),
+            annotations: HashMap::from([("approved-by".to_string(), "alice".to_string())]),
+        })
+        .await
+        .unwrap();
+
+    let labeled = services
+        .versioning_service
+        .list_labeled_versions(&key, "release-1.4")
+        .await
+        .unwrap();
+    assert_eq!(labeled.versions.len(), 1);
+    assert_eq!(labeled.versions[0].version_id, version_ids[1]);
+    assert_eq!(labeled.versions[0].annotations["approved-by"], "alice");
+
+    let info = services
+        .versioning_service
+        .get_version_info(&key, &version_ids[1])
+        .await
+        .unwrap();
+    assert!(info.labels.contains("approved"));
+
+    // Labels may not contain separators
+    let result = services
+        .versioning_service
+        .label_version(LabelVersionRequest {
+            key: key.clone(),
+            version_id: version_ids[0].clone(),
+            labels: BTreeSet::from(["a,b".to_string()]),
+            annotations: HashMap::new(),
+        })
+        .await;
+    assert!(matches!(result, Err(StorageError::ValidationError { .. })));
+}
+
+#[tokio::test]
+async fn test_rollback_by_count() {
+    let server = setup_test_server().await;
+
+    for i in 0..3 {
+        let upload = server
+            .put("/versioned-objects/releases%2Fapp.bin")
+            .text(format!("build {}", i))
+            .await;
+        assert_eq!(upload.status_code(), 200);
+    }
+
+    let rollback = server
+        .post("/buckets/releases/objects/app.bin/rollback")
+        .add_query_param("steps", 2)
+        .await;
+    assert_eq!(rollback.status_code(), 200);
+    let body: serde_json::Value = rollback.json();
+    assert_eq!(body["key"], "releases/app.bin");
+
+    let latest = server
+        .get("/versioned-objects/releases%2Fapp.bin/latest")
+        .await;
+    assert_eq!(latest.status_code(), 200);
+    assert_eq!(latest.text(), "build 0");
+
+    // Four versions exist now, so going back four is too far
+    let too_far = server
+        .post("/buckets/releases/objects/app.bin/rollback")
+        .add_query_param("steps", 4)
+        .await;
+    assert_eq!(too_far.status_code(), 422);
+}
+
+#[tokio::test]
+async fn test_retention_enforcement() {
+    let mode = TestMode::default();
+    let services = AppBuilder::new()
+        .with_test_mode(mode.clone())
+        .with_governance_bypass(true)
+        .build()
+        .await
+        .unwrap();
+    let key = ObjectKey::new("retained.txt".to_string()).unwrap();
+
+    let mut version_ids = Vec::new();
+    for i in 0..3 {
+        let version = services
+            .versioning_service
+            .create_versioned_object(CreateObjectRequest {
+                key: key.clone(),
+                data: format!("version {}", i).into_bytes(),
+                content_type: None,
+                custom_metadata: HashMap::new(),
+            })
+            .await
+            .unwrap();
+        version_ids.push(version.version_id);
+    }
+
+    let retain_until = (mode.clock.now() + Duration::days(1)).into();
+    for (version_id, retention_mode) in [
+        (&version_ids[0], RetentionMode::Governance),
+        (&version_ids[1], RetentionMode::Compliance),
+    ] {
+        let retention = VersionRetentionPolicy {
+            mode: retention_mode,
+            retain_until: Some(retain_until),
+            legal_hold: false,
+        };
+        services
+            .versioning_service
+            .set_version_retention(&key, version_id, Some(retention), false)
+            .await
+            .unwrap();
+    }
+
+    let delete = |version_id: &VersionId, bypass_governance_retention| DeleteVersionRequest {
+        key: key.clone(),
+        version_id: version_id.clone(),
+        bypass_governance_retention,
+    };
+
+    // Governance retention yields to a bypass, compliance retention does not
+    let result = services
+        .versioning_service
+        .delete_version(delete(&version_ids[0], false))
+        .await;
+    assert!(matches!(result, Err(StorageError::AccessDenied { .. })));
+    let result = services
+        .versioning_service
+        .delete_version(delete(&version_ids[1], true))
+        .await;
+    assert!(matches!(result, Err(StorageError::AccessDenied { .. })));
+    services
+        .versioning_service
+        .delete_version(delete(&version_ids[0], true))
+        .await
+        .unwrap();
+
+    // Compliance retention cannot be removed early
+    let result = services
+        .versioning_service
+        .set_version_retention(&key, &version_ids[1], None, true)
+        .await;
+    assert!(matches!(result, Err(StorageError::AccessDenied { .. })));
+
+    let result = services
+        .versioning_service
+        .delete_versions(BulkDeleteVersionsRequest {
+            key: key.clone(),
+            selection: VersionSelection::Noncurrent,
+            bypass_governance_retention: true,
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.deleted_count(), 0);
+
+    // Once retention expires the version can be deleted
+    mode.clock.advance(Duration::days(2));
+    services
+        .versioning_service
+        .delete_version(delete(&version_ids[1], false))
+        .await
+        .unwrap();
+}
+
+
+#[tokio::test]
+async fn test_find_objects_by_prefix_tags_and_size() {
+    let services = create_in_memory_app().await.unwrap();
+
+    for (key, size, team) in [
+        ("reports/q1.csv", 10, "finance"),
+        ("reports/q2.csv", 500, "finance"),
+        ("reports/draft.txt", 20, "legal"),
+        ("images/logo.png", 300, "finance"),
+    ] {
+        services
+            .object_service
+            .create_object(CreateObjectRequest {
+                key: ObjectKey::new(key.to_string()).unwrap(),
+                data: vec![0; size],
+                content_type: None,
+                custom_metadata: HashMap::from([("team".to_string(), team.to_string())]),
+            })
+            .await
+            .unwrap();
+    }
+
+    let find = |filter: Filter| {
+        let object_service = &services.object_service;
+        async move {
+            let keys = object_service.find_objects(&filter, None).await.unwrap();
+            keys.iter()
+                .map(|key| key.as_str().to_string())
+                .collect::<Vec<_>>()
+        }
+    };
+
+    let mut filter = Filter::new().with_prefix("reports/".to_string());
+    filter.tags = HashMap::from([("team".to_string(), "finance".to_string())]);
+    assert_eq!(find(filter.clone()).await, ["reports/q1.csv", "reports/q2.csv"]);
+
+    filter.object_size_greater_than = Some(100);
+    assert_eq!(find(filter).await, ["reports/q2.csv"]);
+
+    let server = setup_test_server().await;
+    let search = server.get("/objects/search?tags=team").await;
+    assert_eq!(search.status_code(), 400);
+}
+
+#[tokio::test]
+async fn test_full_text_object_search() {
+    let services = create_in_memory_app().await.unwrap();
+
+    for (key, period) in [
+        ("billing/acme-invoice.pdf", "2024-03"),
+        ("billing/acme-invoice-2.pdf", "2024-04"),
+        ("billing/acme-receipt.pdf", "2024-03"),
+    ] {
+        services
+            .object_service
+            .create_object(CreateObjectRequest {
+                key: ObjectKey::new(key.to_string()).unwrap(),
+                data: b"pdf".to_vec(),
+                content_type: None,
+                custom_metadata: HashMap::from([("period".to_string(), period.to_string())]),
+            })
+            .await
+            .unwrap();
+    }
+
+    let found = services
+        .object_service
+        .search_objects(&TextQuery::new("Invoice 2024-03"), &Filter::new(), None)
+        .await
+        .unwrap();
+    assert_eq!(
+        found,
+        [ObjectKey::new("billing/acme-invoice.pdf".to_string()).unwrap()]
+    );
+
+    // Text terms combine with the structured filter
+    let found = services
+        .object_service
+        .search_objects(
+            &TextQuery::new("acme"),
+            &Filter::new().with_prefix("billing/acme-r".to_string()),
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(found.len(), 1);
+}
+
+#[tokio::test]
+async fn test_list_multipart_uploads_and_parts() {
+    let server = setup_test_server().await;
+
+    let initiated = server
+        .post("/buckets/media/objects/video.mp4")
+        .add_query_param("uploads", "")
+        .await;
+    assert_eq!(initiated.status_code(), 200);
+    let upload_id = initiated.json::<serde_json::Value>()["upload_id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let part = server
+        .put("/buckets/media/objects/video.mp4")
+        .add_query_param("uploadId", &upload_id)
+        .add_query_param("partNumber", 1)
+        .bytes(Bytes::from_static(b"frames"))
+        .await;
+    assert_eq!(part.status_code(), 200);
+    assert!(part.headers().contains_key("etag"));
+
+    let uploads = server
+        .get("/buckets/media")
+        .add_query_param("uploads", "")
+        .await;
+    assert_eq!(uploads.status_code(), 200);
+    let uploads: serde_json::Value = uploads.json();
+    assert_eq!(uploads["uploads"][0]["key"], "video.mp4");
+    assert_eq!(uploads["uploads"][0]["upload_id"], upload_id.as_str());
+
+    let parts = server
+        .get("/buckets/media/objects/video.mp4")
+        .add_query_param("uploadId", &upload_id)
+        .await;
+    assert_eq!(parts.status_code(), 200);
+    let parts: serde_json::Value = parts.json();
+    assert_eq!(parts["parts"][0]["part_number"], 1);
+    assert_eq!(parts["parts"][0]["size"], 6);
+
+    let aborted = server
+        .delete("/buckets/media/objects/video.mp4")
+        .add_query_param("uploadId", &upload_id)
+        .await;
+    assert_eq!(aborted.status_code(), 204);
+
+    let uploads: serde_json::Value = server
+        .get("/buckets/media")
+        .add_query_param("uploads", "")
+        .await
+        .json();
+    assert!(uploads["uploads"].as_array().unwrap().is_empty());
+    let parts = server
+        .get("/buckets/media/objects/video.mp4")
+        .add_query_param("uploadId", &upload_id)
+        .await;
+    assert_eq!(parts.status_code(), 404);
+}
+
+#[tokio::test]
+async fn test_upload_part_copy_from_byte_range() {
+    let server = setup_test_server().await;
+
+    let source = server
+        .put("/objects/media%2Fsource.txt")
+        .text("hello, world")
+        .await;
+    assert_eq!(source.status_code(), 201);
+
+    let initiated = server
+        .post("/buckets/media/objects/edited.txt")
+        .add_query_param("uploads", "")
+        .await;
+    let upload_id = initiated.json::<serde_json::Value>()["upload_id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let copied = server
+        .put("/buckets/media/objects/edited.txt")
+        .add_query_param("uploadId", &upload_id)
+        .add_query_param("partNumber", 1)
+        .add_header("x-amz-copy-source", "/media/source.txt")
+        .add_header("x-amz-copy-source-range", "bytes=0-4")
+        .await;
+    assert_eq!(copied.status_code(), 200);
+    let copied: serde_json::Value = copied.json();
+    assert_eq!(copied["size"], 5);
+
+    let uploaded = server
+        .put("/buckets/media/objects/edited.txt")
+        .add_query_param("uploadId", &upload_id)
+        .add_query_param("partNumber", 2)
+        .text(" there")
+        .await;
+    let uploaded: serde_json::Value = uploaded.json();
+
+    // Ranges past the end of the source are rejected
+    let out_of_range = server
+        .put("/buckets/media/objects/edited.txt")
+        .add_query_param("uploadId", &upload_id)
+        .add_query_param("partNumber", 3)
+        .add_header("x-amz-copy-source", "media/source.txt")
+        .add_header("x-amz-copy-source-range", "bytes=5-100")
+        .await;
+    assert_eq!(out_of_range.status_code(), 422);
+
+    let completed = server
+        .post("/buckets/media/objects/edited.txt")
+        .add_query_param("uploadId", &upload_id)
+        .json(&json!({
+            "parts": [
+                { "part_number": 1, "etag": copied["etag"] },
+                { "part_number": 2, "etag": uploaded["etag"] }
+            ]
+        }))
+        .await;
+    assert_eq!(completed.status_code(), 200);
+
+    let edited = server.get("/objects/media%2Fedited.txt").await;
+    assert_eq!(edited.status_code(), 200);
+    assert_eq!(edited.text(), "hello there");
+}
+
+#[tokio::test]
+async fn test_complete_multipart_upload_from_s3_xml() {
+    let server = setup_test_server().await;
+
+    let initiated = server
+        .post("/buckets/media/objects/clip.mp4")
+        .add_query_param("uploads", "")
+        .await;
+    let upload_id = initiated.json::<serde_json::Value>()["upload_id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let mut etags = Vec::new();
+    for (part_number, data) in [(1, "first "), (2, "second")] {
+        let part = server
+            .put("/buckets/media/objects/clip.mp4")
+            .add_query_param("uploadId", &upload_id)
+            .add_query_param("partNumber", part_number)
+            .text(data)
+            .await;
+        etags.push(part.header("etag").to_str().unwrap().to_string());
+    }
+    let complete = |parts: &[(u32, &str)]| {
+        let parts: String = parts
+            .iter()
+            .map(|(number, etag)| {
+                format!(
+                    "<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>",
+                    number, etag
+                )
+            })
+            .collect();
+        server
+            .post("/buckets/media/objects/clip.mp4")
+            .add_query_param("uploadId", &upload_id)
+            .bytes(
+                format!(
+                    "<CompleteMultipartUpload>{}</CompleteMultipartUpload>",
+                    parts
+                )
+                .into(),
+            )
+    };
+
+    let out_of_order = complete(&[(2, &etags[1]), (1, &etags[0])]).await;
+    assert_eq!(out_of_order.status_code(), 400);
+    assert!(out_of_order.text().contains("<Code>InvalidPartOrder</Code>"));
+
+    let wrong_etag = complete(&[(1, &etags[1])]).await;
+    assert_eq!(wrong_etag.status_code(), 400);
+    assert!(wrong_etag.text().contains("<Code>InvalidPart</Code>"));
+
+    let completed = complete(&[(1, &etags[0]), (2, &etags[1])]).await;
+    assert_eq!(completed.status_code(), 200);
+    assert_eq!(completed.header("content-type"), "application/xml");
+    let completed = completed.text();
+    assert!(completed.contains("<CompleteMultipartUploadResult"));
+    assert!(completed.contains("<Key>clip.mp4</Key>"));
+    assert!(completed.contains("-2&quot;</ETag>"));
+
+    let object = server.get("/objects/media%2Fclip.mp4").await;
+    assert_eq!(object.text(), "first second");
+
+    let unknown = server
+        .post("/buckets/media/objects/clip.mp4")
+        .add_query_param("uploadId", "missing")
+        .bytes("<CompleteMultipartUpload></CompleteMultipartUpload>".into())
+        .await;
+    assert_eq!(unknown.status_code(), 404);
+    assert!(unknown.text().contains("<Code>NoSuchUpload</Code>"));
+}
+
+#[tokio::test]
+async fn test_presign_object_urls() {
+    let server = setup_test_server().await;
+
+    let created = server
+        .put("/objects/shared.txt")
+        .content_type("text/plain")
+        .text("shared content")
+        .await;
+    assert_eq!(created.status_code(), 201);
+
+    let head = server.method(Method::HEAD, "/objects/shared.txt").await;
+    assert_eq!(head.status_code(), 200);
+    assert_eq!(head.header("content-type"), "text/plain");
+
+    let presigned = server
+        .post("/objects/shared.txt/presign")
+        .json(&json!({ "method": "get", "expires_in_secs": 600 }))
+        .await;
+    assert_eq!(presigned.status_code(), 200);
+    let presigned: serde_json::Value = presigned.json();
+    assert_eq!(presigned["method"], "GET");
+    assert!(presigned["url"].as_str().unwrap().contains("shared.txt"));
+
+    // Reading an object that does not exist cannot be presigned, writing it can
+    let missing = server
+        .post("/objects/missing.txt/presign")
+        .json(&json!({ "method": "GET" }))
+        .await;
+    assert_eq!(missing.status_code(), 404);
+    let upload = server
+        .post("/objects/missing.txt/presign")
+        .json(&json!({ "method": "PUT" }))
+        .await;
+    assert_eq!(upload.status_code(), 200);
+
+    let unsupported = server
+        .post("/objects/shared.txt/presign")
+        .json(&json!({ "method": "PATCH" }))
+        .await;
+    assert_eq!(unsupported.status_code(), 400);
+}
+
+#[tokio::test]
+async fn test_http_errors_map_to_consistent_statuses() {
+    let server = setup_test_server().await;
+
+    // Missing resources are 404 whichever service reports them
+    let missing_object = server.get("/objects/missing.txt").await;
+    assert_eq!(missing_object.status_code(), 404);
+    let missing_lifecycle = server.get("/buckets/no-rules/lifecycle").await;
+    assert_eq!(missing_lifecycle.status_code(), 404);
+    assert_eq!(
+        missing_lifecycle.json::<serde_json::Value>()["error"],
+        "LifecycleError"
+    );
+
+    // Well-formed requests with values the domain rejects are 422
+    let invalid_rule = server
+        .post("/buckets/no-rules/lifecycle/rules")
+        .json(&json!({
+            "id": "sometimes",
+            "status": "Sometimes",
+            "filter": { "prefix": "temp/" },
+            "expiration_days": 7
+        }))
+        .await;
+    assert_eq!(invalid_rule.status_code(), 422);
+    assert_eq!(
+        invalid_rule.json::<serde_json::Value>()["error"],
+        "ValidationError"
+    );
+    let invalid_bucket = server.get("/buckets/Not_A_Bucket/lifecycle").await;
+    assert_eq!(invalid_bucket.status_code(), 422);
+
+    // Requests that cannot be interpreted are 400
+    let bad_header = server
+        .put("/objects/scheduled.txt")
+        .add_header("x-expire-after-seconds", "soon")
+        .text("content")
+        .await;
+    assert_eq!(bad_header.status_code(), 400);
+}
+
+#[tokio::test]
+async fn test_expired_objects_are_hidden_before_the_lifecycle_worker_runs() {
+    let mode = TestMode::default();
+    let services = AppBuilder::new()
+        .with_test_mode(mode.clone())
+        .with_read_time_expiration(true)
+        .build()
+        .await
+        .unwrap();
+
+    let bucket = BucketName::new("logs".to_string()).unwrap();
+    let config = LifecycleConfiguration {
+        bucket: bucket.clone(),
+        rules: vec![LifecycleRule {
+            id: "expire-daily".to_string(),
+            status: RuleStatus::Enabled,
+            filter: Filter::new().with_prefix("logs/daily/".to_string()),
+            expiration_days: Some(1),
+            ..Default::default()
+        }],
+    };
+    services
+        .lifecycle_service
+        .set_lifecycle_configuration(&bucket, config)
+        .await
+        .unwrap();
+
+    let server = TestServer::new(create_router(AppState {
+        object_service: Arc::new(services.object_service),
+        lifecycle_service: Arc::new(services.lifecycle_service),
+        versioning_service: Arc::new(services.versioning_service),
+    }))
+    .unwrap();
+
+    for key in ["logs%2Fdaily%2Fapp.log", "logs%2Farchive%2Fapp.log"] {
+        let put = server.put(&format!("/objects/{}", key)).text("entry").await;
+        assert_eq!(put.status_code(), 201);
+    }
+    let scheduled = server
+        .put("/objects/logs%2Ftmp%2Fsession")
+        .add_header("x-delete-at", "2025-01-01T12:00:00Z")
+        .text("token")
+        .await;
+    assert_eq!(scheduled.status_code(), 201);
+    assert_eq!(
+        server.get("/objects/logs%2Ftmp%2Fsession").await.status_code(),
+        200
+    );
+
+    mode.clock.advance(Duration::days(2));
+
+    let expired = server.get("/objects/logs%2Fdaily%2Fapp.log").await;
+    assert_eq!(expired.status_code(), 404);
+    let body = expired.json::<serde_json::Value>();
+    assert_eq!(body["details"]["rule_id"], "expire-daily");
+    assert_eq!(body["details"]["expired_at"], "2025-01-02T00:00:00+00:00");
+    assert_eq!(
+        server
+            .method(Method::HEAD, "/objects/logs%2Fdaily%2Fapp.log")
+            .await
+            .status_code(),
+        404
+    );
+
+    let session = server.get("/objects/logs%2Ftmp%2Fsession").await;
+    assert_eq!(session.status_code(), 404);
+    assert_eq!(
+        session.json::<serde_json::Value>()["details"]["rule_id"],
+        "scheduled-deletion"
+    );
+
+    // Objects no rule applies to stay readable
+    let kept = server.get("/objects/logs%2Farchive%2Fapp.log").await;
+    assert_eq!(kept.status_code(), 200);
+    assert_eq!(kept.text(), "entry");
+}
+
+#[tokio::test]
+async fn test_prefix_quotas_are_enforced_and_reported() {
+    let services = AppBuilder::new()
+        .with_prefix_quota(
+            PrefixQuota::new("shared/tmp/")
+                .with_max_objects(2)
+                .with_max_bytes(10),
+        )
+        .build()
+        .await
+        .unwrap();
+    let server = TestServer::new(create_router(AppState {
+        object_service: Arc::new(services.object_service),
+        lifecycle_service: Arc::new(services.lifecycle_service),
+        versioning_service: Arc::new(services.versioning_service),
+    }))
+    .unwrap();
+
+    for (key, body) in [("a", "four"), ("b", "five!")] {
+        let put = server
+            .put(&format!("/objects/shared%2Ftmp%2F{}", key))
+            .text(body)
+            .await;
+        assert_eq!(put.status_code(), 201);
+    }
+    let rejected = server.put("/objects/shared%2Ftmp%2Fc").text("x").await;
+    assert_eq!(rejected.status_code(), 507);
+    let body = rejected.json::<serde_json::Value>();
+    assert_eq!(body["details"]["prefix"], "shared/tmp/");
+    assert_eq!(body["details"]["kind"], "objects");
+
+    // Other prefixes of the bucket are not limited
+    let other = server.put("/objects/shared%2Fdata%2Fc").text("x").await;
+    assert_eq!(other.status_code(), 201);
+
+    let usage = server.get("/usage").await;
+    assert_eq!(usage.status_code(), 200);
+    assert_eq!(
+        usage.json::<serde_json::Value>()["prefixes"],
+        json!([{
+            "prefix": "shared/tmp/",
+            "object_count": 2,
+            "total_bytes": 9,
+            "max_objects": 2,
+            "max_bytes": 10,
+            "soft_limit_percent": 80,
+            "soft_limit_reached": true,
+            "hard_limit_reached": true,
+        }])
+    );
+
+    let bucket = server.get("/usage").add_query_param("prefix", "shared/").await;
+    let bucket = &bucket.json::<serde_json::Value>()["prefixes"][0];
+    assert_eq!(bucket["object_count"], 3);
+    assert_eq!(bucket["max_bytes"], serde_json::Value::Null);
+}
+
+#[tokio::test]
+async fn test_transactions_publish_objects_together() {
+    let server = setup_test_server().await;
+
+    let begin = server.post("/transactions").await;
+    assert_eq!(begin.status_code(), 201);
+    let id = begin.json::<serde_json::Value>()["transaction_id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    for (name, body) in [("a.csv", "1,2"), ("b.csv", "3,4")] {
+        let staged = server
+            .put(&format!(
+                "/transactions/{}/objects/data%2Fset%2F{}",
+                id, name
+            ))
+            .text(body)
+            .await;
+        assert_eq!(staged.status_code(), 201);
+    }
+    let hidden = server.get("/objects/data%2Fset%2Fa.csv").await;
+    assert_eq!(hidden.status_code(), 404);
+
+    let commit = server.post(&format!("/transactions/{}/commit", id)).await;
+    assert_eq!(commit.status_code(), 200);
+    let commit = commit.json::<serde_json::Value>();
+    assert_eq!(commit["committed"], true);
+    assert_eq!(commit["objects"].as_array().unwrap().len(), 2);
+
+    for (name, body) in [("a.csv", "1,2"), ("b.csv", "3,4")] {
+        let object = server.get(&format!("/objects/data%2Fset%2F{}", name)).await;
+        assert_eq!(object.status_code(), 200);
+        assert_eq!(object.text(), body);
+    }
+    let finished = server.get(&format!("/transactions/{}", id)).await;
+    assert_eq!(finished.status_code(), 404);
+
+    // A transaction republishes objects that already exist
+    let begin = server.post("/transactions").await;
+    let id = begin.json::<serde_json::Value>()["transaction_id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    for name in ["c.csv", "a.csv"] {
+        server
+            .put(&format!(
+                "/transactions/{}/objects/data%2Fset%2F{}",
+                id, name
+            ))
+            .text("5,6")
+            .await;
+    }
+    let republish = server.post(&format!("/transactions/{}/commit", id)).await;
+    assert_eq!(republish.status_code(), 200);
+    for name in ["a.csv", "c.csv"] {
+        let object = server.get(&format!("/objects/data%2Fset%2F{}", name)).await;
+        assert_eq!(object.text(), "5,6");
+    }
+
+    // An aborted transaction publishes nothing
+    let begin = server.post("/transactions").await;
+    let id = begin.json::<serde_json::Value>()["transaction_id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    server
+        .put(&format!("/transactions/{}/objects/data%2Fset%2Fd.csv", id))
+        .text("7,8")
+        .await;
+    let abort = server.delete(&format!("/transactions/{}", id)).await;
+    assert_eq!(abort.status_code(), 204);
+    let missing = server.get("/objects/data%2Fset%2Fd.csv").await;
+    assert_eq!(missing.status_code(), 404);
+}
+
+#[tokio::test]
+async fn test_dataset_manifests_switch_versions_atomically() {
+    let server = setup_test_server().await;
+
+    let mut versions = Vec::new();
+    for body in ["train v1", "train v2"] {
+        let upload = server
+            .put("/versioned-objects/ml%2Ftrain.csv")
+            .text(body)
+            .await;
+        versions.push(upload.json::<serde_json::Value>()["version_id"].clone());
+    }
+    server
+        .put("/versioned-objects/ml%2Ftest.csv")
+        .text("test v1")
+        .await;
+
+    let missing = server.get("/datasets/churn").await;
+    assert_eq!(missing.status_code(), 404);
+    assert_eq!(
+        missing.json::<serde_json::Value>()["details"]["dataset"],
+        "churn"
+    );
+
+    let first = server
+        .put("/datasets/churn")
+        .json(&json!({"objects": [
+            {"key": "ml/train.csv", "version_id": versions[0]},
+            {"key": "ml/test.csv"},
+        ]}))
+        .await;
+    assert_eq!(first.status_code(), 200);
+    let first = first.json::<serde_json::Value>();
+    assert_eq!(first["objects"][0]["version_id"], versions[0]);
+    assert_eq!(first["objects"][0]["size"], 8);
+
+    let second = server
+        .put("/datasets/churn")
+        .json(&json!({"objects": [{"key": "ml/train.csv", "version_id": versions[1]}]}))
+        .await;
+    assert_eq!(second.status_code(), 200);
+
+    let latest = server
+        .get("/datasets/churn")
+        .await
+        .json::<serde_json::Value>();
+    assert_eq!(latest["objects"].as_array().unwrap().len(), 1);
+    assert_eq!(latest["objects"][0]["version_id"], versions[1]);
+
+    // Earlier manifests stay readable by their version
+    let previous = server
+        .get("/datasets/churn")
+        .add_query_param("version", first["manifest_version"].as_str().unwrap())
+        .await
+        .json::<serde_json::Value>();
+    assert_eq!(previous["objects"].as_array().unwrap().len(), 2);
+
+    let unknown = server
+        .put("/datasets/churn")
+        .json(&json!({"objects": [{"key": "ml/none.csv"}]}))
+        .await;
+    assert_eq!(unknown.status_code(), 404);
+}
+
+#[tokio::test]
+async fn test_delta_upload_sends_only_changed_blocks() {
+    let server = setup_test_server().await;
+
+    let base: String = (0..64).map(|i| format!("row {:04}\n", i)).collect();
+    let upload = server
+        .put("/versioned-objects/disks%2Fimage.raw")
+        .text(base.clone())
+        .await;
+    let base_version = upload.json::<serde_json::Value>()["version_id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let signature = server
+        .get(&format!(
+            "/versioned-objects/disks%2Fimage.raw/versions/{}/signature",
+            base_version
+        ))
+        .add_query_param("block_size", 64)
+        .await;
+    assert_eq!(signature.status_code(), 200);
+    let signature = signature.json::<ObjectSignatureDto>();
+    assert_eq!(signature.blocks.len(), 9);
+
+    let updated = base.replacen("row 0032", "ROW 0032", 1);
+    let delta = ObjectDelta::compute(&signature.into(), updated.as_bytes());
+    let response = server
+        .post(&format!(
+            "/versioned-objects/disks%2Fimage.raw/versions/{}/delta",
+            base_version
+        ))
+        .json(&ObjectDeltaDto::from(delta))
+        .await;
+    assert_eq!(response.status_code(), 200);
+    let response = response.json::<serde_json::Value>();
+    assert_eq!(response["size"], updated.len());
+    assert_eq!(response["literal_bytes"], 64);
+
+    let latest = server
+        .get("/versioned-objects/disks%2Fimage.raw/latest")
+        .await;
+    assert_eq!(latest.text(), updated);
+
+    // Deltas only apply to the version they were computed against
+    let stale = server
+        .post(&format!(
+            "/versioned-objects/disks%2Fimage.raw/versions/{}/delta",
+            response["version_id"].as_str().unwrap()
+        ))
+        .json(&json!({
+            "block_size": 64,
+            "instructions": [{"copy": {"block": 0, "checksum": "0"}}],
+        }))
+        .await;
+    assert_eq!(stale.status_code(), 422);
+}
+
+#[tokio::test]
+async fn test_delta_version_keeps_the_base_metadata() {
+    let services = create_in_memory_app().await.unwrap();
+    let key = ObjectKey::new("disks/meta.raw".to_string()).unwrap();
+    let base: String = (0..16).map(|i| format!("row {:04}\n", i)).collect();
+
+    let version = services
+        .versioning_service
+        .create_versioned_object(CreateObjectRequest {
+            key: key.clone(),
+            data: base.clone().into_bytes(),
+            content_type: Some("application/octet-stream".to_string()),
+            custom_metadata: HashMap::from([("owner".to_string(), "storage".to_string())]),
+        })
+        .await
+        .unwrap();
+    services
+        .versioning_service
+        .label_version(LabelVersionRequest {
+            key: key.clone(),
+            version_id: version.version_id.clone(),
+            labels: BTreeSet::from(["golden".to_string()]),
+            annotations: HashMap::new(),
+        })
+        .await
+        .unwrap();
+
+    let signature = services
+        .versioning_service
+        .version_signature(&key, &version.version_id, 64)
+        .await
+        .unwrap();
+    let updated = base.replacen("row 0008", "ROW 0008", 1);
+    let delta = ObjectDelta::compute(&signature, updated.as_bytes());
+    let created = services
+        .versioning_service
+        .create_version_from_delta(&key, &version.version_id, delta)
+        .await
+        .unwrap();
+
+    assert_eq!(created.metadata.custom_metadata["owner"], "storage");
+    let info = services
+        .versioning_service
+        .get_version_info(&key, &created.version_id)
+        .await
+        .unwrap();
+    assert!(info.labels.is_empty());
+}
+
+#[tokio::test]
+async fn test_patch_overwrites_a_byte_range() {
+    let server = setup_test_server().await;
+
+    let put = server
+        .put("/objects/vm%2Fdisk.img")
+        .text("0000000000")
+        .await;
+    assert_eq!(put.status_code(), 201);
+    let etag = |response: &axum_test::TestResponse| {
+        response.json::<serde_json::Value>()["data"]["etag"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    };
+    let original = server.get("/objects/vm%2Fdisk.img").await;
+    let original_etag = original.header("etag").to_str().unwrap().to_string();
+
+    // Patches must name the data they were made against
+    let unconditional = server
+        .method(Method::PATCH, "/objects/vm%2Fdisk.img")
+        .add_header("content-range", "bytes 2-4/10")
+        .bytes(Bytes::from_static(b"abc"))
+        .await;
+    assert_eq!(unconditional.status_code(), 428);
+
+    let patch = server
+        .method(Method::PATCH, "/objects/vm%2Fdisk.img")
+        .add_header("content-range", "bytes 2-4/10")
+        .add_header("if-match", &original_etag)
+        .bytes(Bytes::from_static(b"abc"))
+        .await;
+    assert_eq!(patch.status_code(), 200);
+    assert_eq!(patch.json::<serde_json::Value>()["data"]["size"], 10);
+
+    // A patch made against the data before the first one is refused
+    let stale = server
+        .method(Method::PATCH, "/objects/vm%2Fdisk.img")
+        .add_header("content-range", "bytes 0-1/10")
+        .add_header("if-match", &original_etag)
+        .bytes(Bytes::from_static(b"zz"))
+        .await;
+    assert_eq!(stale.status_code(), 412);
+
+    // A patch may extend the object past its end
+    let extend = server
+        .method(Method::PATCH, "/objects/vm%2Fdisk.img")
+        .add_header("content-range", "bytes 8-11/*")
+        .add_header("if-match", &etag(&patch))
+        .bytes(Bytes::from_static(b"wxyz"))
+        .await;
+    assert_eq!(extend.status_code(), 200);
+
+    let object = server.get("/objects/vm%2Fdisk.img").await;
+    assert_eq!(object.text(), "00abc000wxyz");
+
+    // Each patch is a new version of the object
+    let versions = server
+        .get("/versioned-objects/vm%2Fdisk.img/versions")
+        .await;
+    assert_eq!(versions.json::<serde_json::Value>()["total_count"], 3);
+
+    let gap = server
+        .method(Method::PATCH, "/objects/vm%2Fdisk.img")
+        .add_header("content-range", "bytes 20-21/*")
+        .add_header("if-match", &etag(&extend))
+        .bytes(Bytes::from_static(b"no"))
+        .await;
+    assert_eq!(gap.status_code(), 422);
+
+    let short_body = server
+        .method(Method::PATCH, "/objects/vm%2Fdisk.img")
+        .add_header("content-range", "bytes 0-9/*")
+        .add_header("if-match", &etag(&extend))
+        .bytes(Bytes::from_static(b"short"))
+        .await;
+    assert_eq!(short_body.status_code(), 400);
+}
+
+#[tokio::test]
+async fn test_object_reads_carry_cache_headers() {
+    let server = setup_test_server().await;
+
+    let put = server
+        .put("/objects/site%2Findex.html")
+        .add_header("cache-control", "public, max-age=300")
+        .text("<html></html>")
+        .await;
+    assert_eq!(put.status_code(), 201);
+
+    let get = server.get("/objects/site%2Findex.html").await;
+    assert_eq!(get.status_code(), 200);
+    let etag = get.header("etag").to_str().unwrap().to_string();
+    assert!(etag.starts_with('"'));
+    assert!(
+        get.header("last-modified")
+            .to_str()
+            .unwrap()
+            .ends_with("GMT")
+    );
+    assert_eq!(get.header("cache-control"), "public, max-age=300");
+
+    let head = server
+        .method(Method::HEAD, "/objects/site%2Findex.html")
+        .await;
+    assert_eq!(head.header("etag"), etag.as_str());
+
+    let cached = server
+        .get("/objects/site%2Findex.html")
+        .add_header("if-none-match", etag.as_str())
+        .await;
+    assert_eq!(cached.status_code(), 304);
+    assert!(cached.text().is_empty());
+
+    let stale = server
+        .get("/objects/site%2Findex.html")
+        .add_header("if-none-match", "\"stale\"")
+        .await;
+    assert_eq!(stale.status_code(), 200);
+
+    server
+        .put("/versioned-objects/site%2Fapp.js")
+        .text("app")
+        .await;
+    let latest = server.get("/versioned-objects/site%2Fapp.js/latest").await;
+    assert!(latest.maybe_header("etag").is_some());
+    assert!(latest.maybe_header("cache-control").is_none());
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn test_client_talks_to_a_running_server() {
+    use futures::{StreamExt, stream};
+    use object_store_server::client::{Client, ListVersionsOptions, PutObjectOptions};
+
+    let services = create_in_memory_app().await.unwrap();
+    let app = create_router(AppState {
+        object_service: Arc::new(services.object_service),
+        lifecycle_service: Arc::new(services.lifecycle_service),
+        versioning_service: Arc::new(services.versioning_service),
+    });
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let client = Client::new(format!("http://{}", address)).unwrap();
+
+    let options = PutObjectOptions {
+        content_type: Some("text/plain".to_string()),
+        ..Default::default()
+    };
+    client
+        .put_object("docs/readme.txt", "hello client", options)
+        .await
+        .unwrap();
+    let chunks = stream::iter(["streamed ", "upload"])
+        .map(|chunk| Ok::<_, std::io::Error>(Bytes::from_static(chunk.as_bytes())));
+    client
+        .put_object_stream("docs/streamed.txt", chunks, Default::default())
+        .await
+        .unwrap();
+
+    let download = client.get_object("docs/readme.txt").await.unwrap();
+    assert_eq!(download.head.content_type.as_deref(), Some("text/plain"));
+    assert_eq!(&download.bytes().await.unwrap()[..], b"hello client");
+    assert_eq!(
+        &client
+            .get_object_range("docs/streamed.txt", 9..15)
+            .await
+            .unwrap()[..],
+        b"upload"
+    );
+    let head = client.head_object("docs/readme.txt").await.unwrap();
+    assert_eq!(head.content_length, Some(12));
+    let etag = head.etag.unwrap();
+    assert_eq!(
+        &client
+            .get_object_range_if_match("docs/readme.txt", 0..5, &etag)
+            .await
+            .unwrap()[..],
+        b"hello"
+    );
+    let error = client
+        .get_object_range_if_match("docs/readme.txt", 0..5, "\"replaced\"")
+        .await
+        .unwrap_err();
+    assert_eq!(error.status(), Some(412));
+    let listing = client.list_objects(Some("docs/"), None).await.unwrap();
+    assert_eq!(listing.total_count, 2);
+
+    client.delete_object("docs/readme.txt").await.unwrap();
+    let error = client.get_object("docs/readme.txt").await.unwrap_err();
+    assert!(error.is_not_found());
+
+    let first = client
+        .put_versioned_object("notes.txt", "v1", None)
+        .await
+        .unwrap();
+    client
+        .put_versioned_object("notes.txt", "v2", None)
+        .await
+        .unwrap();
+    let versions = client
+        .list_versions("notes.txt", &ListVersionsOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(versions.versions.len(), 2);
+    let old = client
+        .get_version("notes.txt", &first.version_id)
+        .await
+        .unwrap();
+    assert_eq!(&old.bytes().await.unwrap()[..], b"v1");
+
+    let templates = client.list_lifecycle_templates().await.unwrap();
+    let rule = client
+        .apply_lifecycle_template("logs", &templates[0].name, &Default::default())
+        .await
+        .unwrap();
+    let config = client.get_lifecycle_configuration("logs").await.unwrap();
+    assert_eq!(config.rules[0].id, rule.id);
+}
@@ -241,9 +241,9 @@ async fn test_delete_lifecycle_configuration() {
         .await
         .unwrap();
 
-    match after_delete {
-        Some(config) => assert!(config.rules.is_empty()),
-        None => {} // Also acceptable
+    // No configuration at all is also acceptable
+    if let Some(config) = after_delete {
+        assert!(config.rules.is_empty());
     }
 }
 
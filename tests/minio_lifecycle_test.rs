@@ -1,6 +1,6 @@
 use chrono::{Duration, Utc};
 use object_store_server::{
-    BucketName, LifecycleConfiguration, LifecycleRule, ObjectKey, create_minio_app,
+    BucketName, LifecycleConfiguration, LifecycleRule, create_minio_app,
     domain::models::{
         Filter,
         lifecycle::{RuleStatus, StorageClass},
@@ -1,9 +1,6 @@
 use bytes::Bytes;
-use chrono::{Duration, Utc};
 use object_store_server::{
-    BucketName, ObjectKey,
-    adapters::inbound::http::router::{AppState, create_router},
-    create_minio_app,
+    BucketName, ObjectKey, create_minio_app,
     domain::models::{
         CreateObjectRequest, Filter, GetObjectRequest, LifecycleConfiguration, LifecycleRule,
         lifecycle::{RuleStatus, StorageClass},
@@ -11,7 +8,6 @@ use object_store_server::{
     ports::services::{LifecycleService, ObjectService, VersioningService},
 };
 use std::collections::HashMap;
-use std::sync::Arc;
 
 // Note: These tests require MinIO to be running and configured via environment variables:
 // - MINIO_ENDPOINT (default: http://localhost:9000)
@@ -52,7 +48,7 @@ async fn test_minio_object_operations() {
         custom_metadata: HashMap::new(),
     };
 
-    let created = services
+    let (created, _) = services
         .object_service
         .create_object(create_request)
         .await
@@ -352,7 +348,7 @@ async fn test_minio_multipart_upload() {
 
     println!("Uploading {}MB file...", size / 1024 / 1024);
 
-    let created = services
+    services
         .object_service
         .create_object(create_request)
         .await
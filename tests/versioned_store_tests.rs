@@ -1,6 +1,6 @@
 use bytes::Bytes;
 use object_store_server::{
-    ObjectKey, VersionId, create_in_memory_app,
+    ObjectKey, create_in_memory_app,
     domain::models::{CreateObjectRequest, GetObjectRequest},
     ports::services::{ObjectService, VersioningService},
 };
@@ -86,6 +86,13 @@ async fn test_versioned_store_basic() {
         .iter()
         .find(|v| !v.is_latest)
         .expect("Should find non-latest version");
+    assert_eq!(v1_version.version_id, v1_result.version_id);
+    assert!(
+        version_list
+            .versions
+            .iter()
+            .any(|v| v.is_latest && v.version_id == v2_result.version_id)
+    );
     let v1_id = &v1_version.version_id;
     let v1_request = GetObjectRequest {
         key: key.clone(),